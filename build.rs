@@ -1,8 +1,31 @@
 // Necessary because of this issue: https://github.com/rust-lang/cargo/issues/9641
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    if std::env::var("CARGO_CFG_TARGET_VENDOR") == Ok("espressif".to_string()) {
+    if std::env::var("CARGO_CFG_TARGET_VENDOR") == Ok("espressif".to_string())
+        && std::env::var("CARGO_CFG_TARGET_OS") != Ok("none".to_string())
+    {
         embuild::build::CfgArgs::output_propagated("ESP_IDF")?;
         embuild::build::LinkArgs::output_propagated("ESP_IDF")?
     }
+    mock_chip();
     Ok(())
 }
+
+/// Chips emulated by the mock, in the order of the priority of the `mock-*` features
+const MOCK_CHIPS: [&str; 6] = [
+    "esp32", "esp32s2", "esp32s3", "esp32c3", "esp32c6", "esp32h2",
+];
+
+/// Sets `mock_chip` cfg to the chip of the enabled `mock-*` feature.
+///
+/// The features are additive, e.g. by `--all-features`, so the first of [`MOCK_CHIPS`] wins.
+fn mock_chip() {
+    let values = MOCK_CHIPS.map(|chip| format!("\"{}\"", chip)).join(", ");
+    println!("cargo::rustc-check-cfg=cfg(mock_chip, values({}))", values);
+    let feature = |chip: &str| format!("CARGO_FEATURE_MOCK_{}", chip.to_uppercase());
+    if let Some(chip) = MOCK_CHIPS
+        .iter()
+        .find(|chip| std::env::var_os(feature(chip)).is_some())
+    {
+        println!("cargo:rustc-cfg=mock_chip=\"{}\"", chip);
+    }
+}
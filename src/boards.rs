@@ -0,0 +1,257 @@
+//! Presets of the popular boards with the LED pixels on board.
+//!
+//! Each board type implements [`Board`], describing the chip, the GPIO of the data line, the
+//! RMT channel and the LED pixels of the board. [`board_driver!`] and [`board_draw_target!`]
+//! create the driver of the board from the `Peripherals`, taking only the RMT channel and the
+//! GPIO pin of the board out of it.
+//!
+//! | Board                              | Type                  | Chip     | GPIO | LED pixels       |
+//! |------------------------------------|-----------------------|----------|------|------------------|
+//! | M5Stack ATOM Matrix                | [`M5AtomMatrix`]      | ESP32    | 27   | 5x5 WS2812C      |
+//! | M5Stack ATOM Lite                  | [`M5AtomLite`]        | ESP32    | 27   | 1 SK6812         |
+//! | ESP32-S3-DevKitC-1 (v1.0)          | [`Esp32S3DevKitC1`]   | ESP32-S3 | 48   | 1 WS2812         |
+//! | ESP32-S3-DevKitC-1 (v1.1)          | [`Esp32S3DevKitC1V11`]| ESP32-S3 | 38   | 1 WS2812         |
+//! | WLED-compatible ESP32 boards       | [`WledEsp32`]         | ESP32    | 16   | strip            |
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::board_driver;
+//! use ws2812_esp32_rmt_driver::boards::{Board, M5AtomLite};
+//! use ws2812_esp32_rmt_driver::LedStripWrite;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut driver = board_driver!(M5AtomLite, peripherals).unwrap();
+//! assert_eq!(M5AtomLite::PIXEL_LEN, Some(1));
+//! driver.write_blocking([0, 30, 0].into_iter()).unwrap();
+//!
+//! // The other pins are still available.
+//! let _pin = peripherals.pins.gpio26;
+//! ```
+
+use crate::driver::color::{LedPixelColor, LedPixelColorGrb24};
+#[cfg(feature = "embedded-graphics-core")]
+use crate::lib_embedded_graphics::LedPixelMatrix;
+
+/// Board with the LED pixels on board.
+pub trait Board {
+    /// Name of the board
+    const NAME: &'static str;
+    /// Chip of the board, e.g. `"esp32s3"`
+    const CHIP: &'static str;
+    /// GPIO number of the data line
+    const GPIO: i32;
+    /// RMT channel used by [`board_driver!`]
+    const CHANNEL: u32;
+    /// The number of the LED pixels on board, or `None` for the external strips
+    const PIXEL_LEN: Option<usize>;
+    /// Maximum brightness recommended for the board, e.g. for the heat of the dense matrices
+    const MAX_BRIGHTNESS: u8 = u8::MAX;
+    /// Color type of the LED pixels
+    type Color: LedPixelColor;
+}
+
+/// M5Stack ATOM Matrix: 5x5 WS2812C on GPIO27 of ESP32
+///
+/// The vendor recommends the brightness of 20 or less, as the matrix heats up the acrylic cover.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct M5AtomMatrix;
+
+impl Board for M5AtomMatrix {
+    const NAME: &'static str = "M5Stack ATOM Matrix";
+    const CHIP: &'static str = "esp32";
+    const GPIO: i32 = 27;
+    const CHANNEL: u32 = 0;
+    const PIXEL_LEN: Option<usize> = Some(25);
+    const MAX_BRIGHTNESS: u8 = 20;
+    type Color = LedPixelColorGrb24;
+}
+
+/// LED pixel shape of the [`M5AtomMatrix`]
+#[cfg(feature = "embedded-graphics-core")]
+pub type M5AtomMatrixShape = LedPixelMatrix<5, 5>;
+
+/// M5Stack ATOM Lite: an SK6812 on GPIO27 of ESP32
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct M5AtomLite;
+
+impl Board for M5AtomLite {
+    const NAME: &'static str = "M5Stack ATOM Lite";
+    const CHIP: &'static str = "esp32";
+    const GPIO: i32 = 27;
+    const CHANNEL: u32 = 0;
+    const PIXEL_LEN: Option<usize> = Some(1);
+    type Color = LedPixelColorGrb24;
+}
+
+/// ESP32-S3-DevKitC-1 v1.0: an RGB LED (WS2812) on GPIO48
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Esp32S3DevKitC1;
+
+impl Board for Esp32S3DevKitC1 {
+    const NAME: &'static str = "ESP32-S3-DevKitC-1 v1.0";
+    const CHIP: &'static str = "esp32s3";
+    const GPIO: i32 = 48;
+    const CHANNEL: u32 = 0;
+    const PIXEL_LEN: Option<usize> = Some(1);
+    type Color = LedPixelColorGrb24;
+}
+
+/// ESP32-S3-DevKitC-1 v1.1: an RGB LED (WS2812) moved to GPIO38
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Esp32S3DevKitC1V11;
+
+impl Board for Esp32S3DevKitC1V11 {
+    const NAME: &'static str = "ESP32-S3-DevKitC-1 v1.1";
+    const CHIP: &'static str = "esp32s3";
+    const GPIO: i32 = 38;
+    const CHANNEL: u32 = 0;
+    const PIXEL_LEN: Option<usize> = Some(1);
+    type Color = LedPixelColorGrb24;
+}
+
+/// WLED-compatible ESP32 boards: the strip on GPIO16, the default data pin of WLED on ESP32,
+/// e.g. QuinLED Dig-Uno
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WledEsp32;
+
+impl Board for WledEsp32 {
+    const NAME: &'static str = "WLED-compatible ESP32";
+    const CHIP: &'static str = "esp32";
+    const GPIO: i32 = 16;
+    const CHANNEL: u32 = 0;
+    const PIXEL_LEN: Option<usize> = None;
+    type Color = LedPixelColorGrb24;
+}
+
+/// Takes the RMT channel and the GPIO pin of the board out of the `Peripherals`.
+///
+/// It evaluates to the tuple of them. The other fields of the `Peripherals` are still available.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! board_peripherals {
+    (M5AtomMatrix, $peripherals:expr) => {
+        ($peripherals.rmt.channel0, $peripherals.pins.gpio27)
+    };
+    (M5AtomLite, $peripherals:expr) => {
+        ($peripherals.rmt.channel0, $peripherals.pins.gpio27)
+    };
+    (Esp32S3DevKitC1, $peripherals:expr) => {
+        ($peripherals.rmt.channel0, $peripherals.pins.gpio48)
+    };
+    (Esp32S3DevKitC1V11, $peripherals:expr) => {
+        ($peripherals.rmt.channel0, $peripherals.pins.gpio38)
+    };
+    (WledEsp32, $peripherals:expr) => {
+        ($peripherals.rmt.channel0, $peripherals.pins.gpio16)
+    };
+}
+
+/// Creates the [`Ws2812Esp32RmtDriver`] of the board from the `Peripherals`.
+///
+/// The first argument is one of the board types of [`crate::boards`], and the second is the
+/// `Peripherals`, out of which the RMT channel and the GPIO pin of the board are taken. It
+/// evaluates to `Result<Ws2812Esp32RmtDriver, Ws2812Esp32RmtDriverError>`.
+///
+/// [`Ws2812Esp32RmtDriver`]: crate::driver::Ws2812Esp32RmtDriver
+#[macro_export]
+macro_rules! board_driver {
+    ($board:ident, $peripherals:expr) => {{
+        let (channel, pin) = $crate::board_peripherals!($board, $peripherals);
+        $crate::driver::Ws2812Esp32RmtDriver::new(channel, pin)
+    }};
+}
+
+/// Creates the [`Ws2812DrawTarget`] of the LED matrix of the board from the `Peripherals`.
+///
+/// The boards with the LED matrix are: [`M5AtomMatrix`](crate::boards::M5AtomMatrix). The
+/// brightness is set to the [`Board::MAX_BRIGHTNESS`](crate::boards::Board::MAX_BRIGHTNESS).
+/// It evaluates to `Result<Ws2812DrawTarget, Ws2812Esp32RmtDriverError>`.
+///
+/// # Examples
+///
+/// ```
+/// #[cfg(not(target_vendor = "espressif"))]
+/// use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+///
+/// use embedded_graphics::pixelcolor::Rgb888;
+/// use embedded_graphics::prelude::*;
+/// use esp_idf_hal::peripherals::Peripherals;
+/// use ws2812_esp32_rmt_driver::board_draw_target;
+///
+/// let peripherals = Peripherals::take().unwrap();
+/// let mut draw = board_draw_target!(M5AtomMatrix, peripherals).unwrap();
+/// assert_eq!(draw.size(), Size::new(5, 5));
+/// Pixel(Point::new(2, 2), Rgb888::WHITE).draw(&mut draw).unwrap();
+/// draw.flush().unwrap();
+/// ```
+///
+/// [`Ws2812DrawTarget`]: crate::lib_embedded_graphics::Ws2812DrawTarget
+#[cfg(feature = "embedded-graphics-core")]
+#[macro_export]
+macro_rules! board_draw_target {
+    (M5AtomMatrix, $peripherals:expr) => {{
+        let (channel, pin) = $crate::board_peripherals!(M5AtomMatrix, $peripherals);
+        $crate::lib_embedded_graphics::Ws2812DrawTarget::<$crate::boards::M5AtomMatrixShape>::new(
+            channel, pin,
+        )
+        .map(|mut draw| {
+            draw.set_brightness(
+                <$crate::boards::M5AtomMatrix as $crate::boards::Board>::MAX_BRIGHTNESS,
+            );
+            draw
+        })
+    }};
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+
+    // GPIO48 is only on ESP32-S3
+    #[cfg(not(any(
+        mock_chip = "esp32",
+        mock_chip = "esp32s2",
+        mock_chip = "esp32c3",
+        mock_chip = "esp32c6",
+        mock_chip = "esp32h2"
+    )))]
+    #[test]
+    fn test_board_driver() {
+        let peripherals = Peripherals::take().unwrap();
+        let driver = board_driver!(Esp32S3DevKitC1, peripherals).unwrap();
+        let config = driver.config();
+        assert_eq!(config.gpio, Esp32S3DevKitC1::GPIO);
+        assert_eq!(config.channel, Esp32S3DevKitC1::CHANNEL);
+    }
+
+    // GPIO27 is not on ESP32-C3
+    #[cfg(not(mock_chip = "esp32c3"))]
+    #[test]
+    fn test_board_driver_wled() {
+        let peripherals = Peripherals::take().unwrap();
+        let driver = board_driver!(WledEsp32, peripherals).unwrap();
+        assert_eq!(driver.config().gpio, WledEsp32::GPIO);
+        assert_eq!(WledEsp32::PIXEL_LEN, None);
+        assert_eq!(WledEsp32::MAX_BRIGHTNESS, 255);
+    }
+
+    #[cfg(all(feature = "embedded-graphics-core", not(mock_chip = "esp32c3")))]
+    #[test]
+    fn test_board_draw_target() {
+        use crate::lib_embedded_graphics::LedPixelShape;
+
+        let peripherals = Peripherals::take().unwrap();
+        let draw = board_draw_target!(M5AtomMatrix, peripherals).unwrap();
+        assert_eq!(
+            Some(M5AtomMatrixShape::pixel_len()),
+            M5AtomMatrix::PIXEL_LEN
+        );
+        drop(draw);
+    }
+}
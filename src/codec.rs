@@ -0,0 +1,386 @@
+//! Target-independent WS2812 codec.
+//!
+//! The encoding of the firmware, pure and available on any target including the desktop: a
+//! pixel-byte sequence is encoded into the RMT symbols ending with the reset code ([`Encoder`]),
+//! the raw RMT memory words ([`Encoder::encode_words()`]) or the timed edges of the signal
+//! ([`edges()`]), and the captured symbols are decoded back into the bytes
+//! ([`Encoder::decode()`]). The RMT driver encodes the frames with the same [`Encoder`], thus the
+//! tools pre-encode the animations, generate the golden waveforms, and drive or check the
+//! hardware-in-the-loop rigs with the same code as the firmware.
+//!
+//! Without the `codec` feature, this module is internal to the drivers.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "codec")]
+//! # {
+//! use ws2812_esp32_rmt_driver::codec::{edges, Encoder, Symbol};
+//! use ws2812_esp32_rmt_driver::driver::timing::LedTiming;
+//! use core::time::Duration;
+//!
+//! // 10 MHz counter clock, as the RMT driver
+//! let encoder = Encoder::new(10_000_000, &LedTiming::WS2812).unwrap();
+//! let symbols = encoder.encode([0x80].into_iter()).collect::<Vec<_>>();
+//! assert_eq!(symbols.len(), 8 + 1);
+//! assert_eq!(symbols[0], Symbol::bit(8, 4));
+//! assert_eq!(symbols[1], Symbol::bit(4, 8));
+//! // 50 us of the reset code in two low pulses
+//! assert_eq!(symbols[8], Symbol::reset(500).unwrap());
+//!
+//! // Round trip of the captured symbols
+//! let decoded = encoder.decode(symbols.into_iter()).collect::<Vec<_>>();
+//! assert_eq!(decoded, [0x80]);
+//!
+//! let edges = edges([0x80].into_iter(), &LedTiming::WS2812).collect::<Vec<_>>();
+//! assert_eq!(edges[1].time, Duration::from_nanos(800));
+//! assert!(!edges[1].level);
+//! // The end of the reset code
+//! assert_eq!(edges[16].time, Duration::from_micros(10 + 50));
+//! # }
+//! ```
+
+use crate::driver::timing::{LedTiming, RmtBitTicks};
+use core::time::Duration;
+
+/// Maximum tick count of a pulse of an RMT symbol
+const MAX_TICKS: u16 = 0x7FFF;
+
+/// Expands a pixel-byte sequence into the bits to be sent, MSB first.
+#[inline]
+pub fn encode_bits<T>(pixel_sequence: T) -> impl Iterator<Item = bool>
+where
+    T: Iterator<Item = u8>,
+{
+    pixel_sequence.flat_map(|v| (0..u8::BITS).map(move |i| v & (0x80 >> i) != 0))
+}
+
+/// RMT symbol: two pulses of a level each, in the counter clock ticks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Symbol {
+    /// Level of the first pulse, `true` for high voltage
+    pub level0: bool,
+    /// Ticks of the first pulse
+    pub duration0: u16,
+    /// Level of the second pulse, `true` for high voltage
+    pub level1: bool,
+    /// Ticks of the second pulse
+    pub duration1: u16,
+}
+
+impl Symbol {
+    /// Returns the symbol of a bit: a high pulse followed by a low pulse.
+    pub const fn bit(high: u16, low: u16) -> Self {
+        Self {
+            level0: true,
+            duration0: high,
+            level1: false,
+            duration1: low,
+        }
+    }
+
+    /// Returns the symbol of the reset code of `ticks`, or `None` if `ticks` is 0.
+    ///
+    /// The reset code is split into the two low pulses, as a pulse of 0 ticks ends the
+    /// transmission. It saturates at the two pulses.
+    pub const fn reset(ticks: u32) -> Option<Self> {
+        if ticks == 0 {
+            return None;
+        }
+        let first = ticks / 2;
+        let first = if first == 0 { 1 } else { first };
+        let second = ticks - ticks / 2;
+        let (first, second) = (
+            if first > MAX_TICKS as u32 {
+                MAX_TICKS
+            } else {
+                first as u16
+            },
+            if second > MAX_TICKS as u32 {
+                MAX_TICKS
+            } else {
+                second as u16
+            },
+        );
+        Some(Self {
+            level0: false,
+            duration0: first,
+            level1: false,
+            duration1: second,
+        })
+    }
+
+    /// Returns the RMT memory word of the symbol, the layout of `rmt_item32_t` and
+    /// `rmt_symbol_word_t`: the 15-bit duration and the level of the first pulse in the lower
+    /// half-word, and of the second pulse in the upper half-word.
+    pub const fn to_word(self) -> u32 {
+        (self.duration0 & MAX_TICKS) as u32
+            | (self.level0 as u32) << 15
+            | ((self.duration1 & MAX_TICKS) as u32) << 16
+            | (self.level1 as u32) << 31
+    }
+
+    /// Returns the symbol of the RMT memory word.
+    pub const fn from_word(word: u32) -> Self {
+        Self {
+            level0: word & 1 << 15 != 0,
+            duration0: (word & MAX_TICKS as u32) as u16,
+            level1: word & 1 << 31 != 0,
+            duration1: ((word >> 16) & MAX_TICKS as u32) as u16,
+        }
+    }
+}
+
+/// Encoder of the pixel-byte sequences into the RMT symbols at a counter clock
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Encoder {
+    /// Symbol of a 0 code
+    pub bit0: Symbol,
+    /// Symbol of a 1 code
+    pub bit1: Symbol,
+    /// Symbol of the reset code ending a frame, if any
+    pub reset: Option<Symbol>,
+}
+
+impl Encoder {
+    /// Creates an encoder of `timing` at the counter clock `clock_hz`.
+    ///
+    /// Returns `None` if a pulse is too short or too long at `clock_hz`.
+    pub fn new(clock_hz: u32, timing: &LedTiming) -> Option<Self> {
+        RmtBitTicks::new(clock_hz, timing)
+            .map(|ticks| Self::from_ticks(&ticks, RmtBitTicks::reset_ticks(clock_hz, timing)))
+    }
+
+    /// Creates an encoder of the pulse ticks and the ticks of the reset code, 0 not to end the
+    /// frames with it.
+    pub const fn from_ticks(ticks: &RmtBitTicks, reset_ticks: u32) -> Self {
+        Self {
+            bit0: Symbol::bit(ticks.t0h, ticks.t0l),
+            bit1: Symbol::bit(ticks.t1h, ticks.t1l),
+            reset: Symbol::reset(reset_ticks),
+        }
+    }
+
+    /// Encodes a pixel-byte sequence into the symbols of the bits, 8 per byte, without the reset
+    /// code.
+    pub fn encode_bits<T>(&self, pixel_sequence: T) -> impl Iterator<Item = Symbol>
+    where
+        T: Iterator<Item = u8>,
+    {
+        let Self { bit0, bit1, .. } = *self;
+        encode_bits(pixel_sequence).map(move |bit| if bit { bit1 } else { bit0 })
+    }
+
+    /// Encodes a pixel-byte sequence into the symbols of a frame, 8 per byte followed by the reset
+    /// code.
+    pub fn encode<T>(&self, pixel_sequence: T) -> impl Iterator<Item = Symbol>
+    where
+        T: Iterator<Item = u8>,
+    {
+        self.encode_bits(pixel_sequence).chain(self.reset)
+    }
+
+    /// Encodes a pixel-byte sequence into the RMT memory words of a frame, 8 per byte followed by
+    /// the reset code, e.g. to be pre-encoded into the flash image.
+    pub fn encode_words<T>(&self, pixel_sequence: T) -> impl Iterator<Item = u32>
+    where
+        T: Iterator<Item = u8>,
+    {
+        self.encode(pixel_sequence).map(|symbol| symbol.to_word())
+    }
+
+    /// Decodes the symbols, e.g. captured by an RMT RX channel at the same counter clock, into
+    /// the pixel-byte sequence.
+    ///
+    /// A symbol is a 1 code if its high pulse is nearer to the one of [`Self::bit1`] than
+    /// [`Self::bit0`]. The symbols not starting with a high pulse, e.g. the reset code, are
+    /// skipped, and the trailing bits short of a byte are dropped.
+    pub fn decode<T>(&self, symbols: T) -> impl Iterator<Item = u8>
+    where
+        T: Iterator<Item = Symbol>,
+    {
+        let threshold = (u32::from(self.bit0.duration0) + u32::from(self.bit1.duration0)) / 2;
+        let one_is_longer = self.bit1.duration0 >= self.bit0.duration0;
+        let mut bits = symbols
+            .filter(|symbol| symbol.level0)
+            .map(move |symbol| (u32::from(symbol.duration0) > threshold) == one_is_longer);
+        core::iter::from_fn(move || {
+            let mut byte = 0u8;
+            for _ in 0..u8::BITS {
+                byte = byte << 1 | u8::from(bits.next()?);
+            }
+            Some(byte)
+        })
+    }
+}
+
+/// Level change of the signal, or the end of the frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Edge {
+    /// Time from the beginning of the frame
+    pub time: Duration,
+    /// Signal level after the edge, `true` for high voltage
+    pub level: bool,
+}
+
+/// Returns the edges of the signal of a pixel-byte sequence with `timing`, 2 per bit followed by
+/// the end of the frame.
+///
+/// Each bit is sent MSB first as a high pulse followed by a low pulse, and the frame ends with the
+/// low level of the reset code. The last edge is the low level at the end of the reset code.
+pub fn edges<T>(pixel_sequence: T, timing: &LedTiming) -> impl Iterator<Item = Edge>
+where
+    T: Iterator<Item = u8>,
+{
+    let timing = *timing;
+    let mut bits = encode_bits(pixel_sequence);
+    let mut time = Duration::ZERO;
+    let mut fall = None;
+    let mut end = false;
+    core::iter::from_fn(move || {
+        if let Some(fall) = fall.take() {
+            return Some(fall);
+        }
+        match bits.next() {
+            Some(bit) => {
+                let (high, low) = if bit {
+                    (timing.t1h, timing.t1l)
+                } else {
+                    (timing.t0h, timing.t0l)
+                };
+                let rise = Edge { time, level: true };
+                fall = Some(Edge {
+                    time: time + high,
+                    level: false,
+                });
+                time += high + low;
+                Some(rise)
+            }
+            None if !end => {
+                end = true;
+                Some(Edge {
+                    time: time + timing.reset,
+                    level: false,
+                })
+            }
+            None => None,
+        }
+    })
+}
+
+/// Returns the duration of the signal of a pixel-byte sequence with `timing`, excluding the
+/// reset code.
+pub fn signal_duration<T>(pixel_sequence: T, timing: &LedTiming) -> Duration
+where
+    T: Iterator<Item = u8>,
+{
+    let (ones, bits) = encode_bits(pixel_sequence).fold((0u32, 0u32), |(ones, bits), bit| {
+        (ones + u32::from(bit), bits + 1)
+    });
+    (timing.t1h + timing.t1l) * ones + (timing.t0h + timing.t0l) * (bits - ones)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encode_bits() {
+        let bits = encode_bits([0xA5, 0x01].into_iter()).collect::<Vec<_>>();
+        assert_eq!(
+            bits,
+            [
+                true, false, true, false, false, true, false, true, //
+                false, false, false, false, false, false, false, true,
+            ]
+        );
+        assert_eq!(encode_bits([].into_iter()).count(), 0);
+    }
+
+    #[test]
+    fn test_symbol_word() {
+        let symbol = Symbol::bit(8, 4);
+        assert_eq!(symbol.to_word(), 0x0004_8008);
+        assert_eq!(Symbol::from_word(symbol.to_word()), symbol);
+
+        let reset = Symbol::reset(501).unwrap();
+        assert_eq!((reset.duration0, reset.duration1), (250, 251));
+        assert_eq!(reset.to_word(), 0x00FB_00FA);
+        assert_eq!(Symbol::from_word(reset.to_word()), reset);
+        assert_eq!(Symbol::reset(1).unwrap().duration0, 1);
+        assert_eq!(Symbol::reset(0), None);
+        assert_eq!(Symbol::reset(u32::MAX).unwrap().duration1, 0x7FFF);
+    }
+
+    #[test]
+    fn test_encoder() {
+        let encoder = Encoder::new(80_000_000, &LedTiming::WS2812).unwrap();
+        assert_eq!(encoder.bit0, Symbol::bit(32, 68));
+        assert_eq!(encoder.bit1, Symbol::bit(64, 36));
+        assert_eq!(encoder.reset, Symbol::reset(4_000));
+        let words = encoder.encode_words([0xA5].into_iter()).collect::<Vec<_>>();
+        assert_eq!(words.len(), 8 + 1);
+        assert_eq!(words[0], encoder.bit1.to_word());
+        assert_eq!(words[1], encoder.bit0.to_word());
+        assert_eq!(words[8], encoder.reset.unwrap().to_word());
+        assert_eq!(encoder.encode_bits([0xA5].into_iter()).count(), 8);
+        assert!(Encoder::new(1_000_000, &LedTiming::WS2812).is_none());
+
+        let ticks = RmtBitTicks::new(80_000_000, &LedTiming::WS2812).unwrap();
+        let encoder = Encoder::from_ticks(&ticks, 0);
+        assert_eq!(encoder.encode([].into_iter()).count(), 0);
+    }
+
+    #[test]
+    fn test_decode() {
+        let encoder = Encoder::new(10_000_000, &LedTiming::WS2812).unwrap();
+        let data = [0x00, 0xFF, 0xA5, 0x3C];
+        // Captured with a jitter of a tick, and a trailing partial byte
+        let captured = encoder
+            .encode_bits(data.into_iter().chain([0xFF]))
+            .enumerate()
+            .map(|(i, symbol)| Symbol {
+                duration0: symbol.duration0 + (i % 2) as u16,
+                duration1: symbol.duration1.saturating_sub(1),
+                ..symbol
+            })
+            .take(data.len() * 8 + 3);
+        assert_eq!(encoder.decode(captured).collect::<Vec<_>>(), data);
+
+        // The reset code is skipped
+        let frame = encoder.encode(data.into_iter());
+        assert_eq!(encoder.decode(frame).collect::<Vec<_>>(), data);
+    }
+
+    #[test]
+    fn test_edges() {
+        let timing = LedTiming::WS2812;
+        let edges = edges([0x80, 0x00].into_iter(), &timing).collect::<Vec<_>>();
+        assert_eq!(edges.len(), 32 + 1);
+        assert_eq!(edges[2].time, timing.t1h + timing.t1l);
+        assert_eq!(
+            edges[31].time,
+            signal_duration([0x80, 0x00].into_iter(), &timing) - timing.t0l
+        );
+        assert_eq!(
+            edges[32],
+            Edge {
+                time: timing.frame_duration(2, 1),
+                level: false
+            }
+        );
+        assert_eq!(
+            signal_duration([0x80].into_iter(), &timing),
+            timing.t1h + timing.t1l + (timing.t0h + timing.t0l) * 7
+        );
+
+        let empty = super::edges([].into_iter(), &timing).collect::<Vec<_>>();
+        assert_eq!(
+            empty,
+            [Edge {
+                time: timing.reset,
+                level: false
+            }]
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! LED pixel shape of the LED cubes.
+//!
+//! [`LedPixelCube`] is the shape of a cube of `X`x`Y`x`Z` LED pixels chained layer by layer
+//! from the bottom (`z = 0`), each layer row by row as [`LedPixelMatrix`]. The draw target of
+//! the cube sets the voxels with [`set_voxel()`] and fills the planes with [`fill_plane()`] in
+//! the framebuffer, and [`flush()`] writes them as usual.
+//!
+//! As an [`LedPixelShape`], the layers are laid side by side from the left, so a layer is
+//! also drawn with the embedded-graphics primitives through a translated draw target.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use embedded_graphics::pixelcolor::Rgb888;
+//! use embedded_graphics::prelude::*;
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::cube::{LedPixelCube, Plane};
+//! use ws2812_esp32_rmt_driver::lib_embedded_graphics::Ws2812DrawTarget;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut draw = Ws2812DrawTarget::<LedPixelCube<4, 4, 4>>::new(
+//!     peripherals.rmt.channel0,
+//!     peripherals.pins.gpio0,
+//! )
+//! .unwrap();
+//! draw.fill_plane(Plane::Z(0), Rgb888::BLUE);
+//! draw.set_voxel(3, 3, 3, Rgb888::RED);
+//! draw.flush().unwrap();
+//! assert_eq!(draw.frame_buffer().as_bytes()[63 * 3..64 * 3], [0, 255, 0]);
+//! ```
+//!
+//! [`LedPixelMatrix`]: crate::lib_embedded_graphics::LedPixelMatrix
+//! [`set_voxel()`]: LedPixelDrawTarget::set_voxel
+//! [`fill_plane()`]: LedPixelDrawTarget::fill_plane
+//! [`flush()`]: LedPixelDrawTarget::flush
+
+use crate::driver::color::LedPixelColor;
+use crate::driver::LedStripDriver;
+use crate::lib_embedded_graphics::{LedPixelDrawTarget, LedPixelShape};
+use core::ops::DerefMut;
+use embedded_graphics_core::geometry::{Point, Size};
+use embedded_graphics_core::pixelcolor::RgbColor;
+
+/// LED pixel shape of `X`x`Y`x`Z` cube
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LedPixelCube<const X: usize, const Y: usize, const Z: usize> {}
+
+impl<const X: usize, const Y: usize, const Z: usize> LedPixelCube<X, Y, Z> {
+    /// The number of pixels.
+    pub const PIXEL_LEN: usize = X * Y * Z;
+
+    /// Converts the voxel at `(x, y, z)` to the index.
+    /// Returns `None` if it is out of the bounds.
+    pub const fn voxel_index(x: usize, y: usize, z: usize) -> Option<usize> {
+        if x < X && y < Y && z < Z {
+            Some(x + (y + z * Y) * X)
+        } else {
+            None
+        }
+    }
+}
+
+impl<const X: usize, const Y: usize, const Z: usize> LedPixelShape for LedPixelCube<X, Y, Z> {
+    #[inline]
+    fn size() -> Size {
+        Size::new((X * Z) as u32, Y as u32)
+    }
+    #[inline]
+    fn pixel_len() -> usize {
+        Self::PIXEL_LEN
+    }
+
+    fn pixel_index(point: Point) -> Option<usize> {
+        let x = usize::try_from(point.x).ok()?;
+        let y = usize::try_from(point.y).ok()?;
+        Self::voxel_index(x % X.max(1), y, x / X.max(1))
+    }
+}
+
+/// Plane of the voxels of a cube
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Plane {
+    /// Plane of the voxels at the `x`
+    X(usize),
+    /// Plane of the voxels at the `y`
+    Y(usize),
+    /// Plane of the voxels at the `z`, i.e. a layer
+    Z(usize),
+}
+
+impl<'d, CDraw, CDev, Data, D, const X: usize, const Y: usize, const Z: usize>
+    LedPixelDrawTarget<'d, CDraw, CDev, LedPixelCube<X, Y, Z>, Data, D>
+where
+    CDraw: RgbColor,
+    CDev: LedPixelColor + From<CDraw>,
+    Data: DerefMut<Target = [u8]> + FromIterator<u8> + IntoIterator<Item = u8>,
+    D: LedStripDriver,
+{
+    /// Sets the voxel at `(x, y, z)`.
+    /// Returns `false` if it is out of the bounds.
+    pub fn set_voxel(&mut self, x: usize, y: usize, z: usize, color: CDraw) -> bool {
+        match LedPixelCube::<X, Y, Z>::voxel_index(x, y, z) {
+            Some(index) => self.frame_buffer_mut().set_pixel(index, color),
+            None => false,
+        }
+    }
+
+    /// Fills the voxels of `plane`.
+    /// Nothing is filled if it is out of the bounds.
+    pub fn fill_plane(&mut self, plane: Plane, color: CDraw) {
+        let (xs, ys, zs) = match plane {
+            Plane::X(x) => (x..(x + 1).min(X), 0..Y, 0..Z),
+            Plane::Y(y) => (0..X, y..(y + 1).min(Y), 0..Z),
+            Plane::Z(z) => (0..X, 0..Y, z..(z + 1).min(Z)),
+        };
+        for z in zs {
+            for y in ys.clone() {
+                for x in xs.clone() {
+                    self.set_voxel(x, y, z, color);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lib_embedded_graphics::Ws2812DrawTarget;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+    use embedded_graphics_core::pixelcolor::Rgb888;
+
+    #[test]
+    fn test_led_pixel_cube() {
+        type Cube = LedPixelCube<3, 2, 4>;
+        assert_eq!(Cube::PIXEL_LEN, 24);
+        assert_eq!(Cube::pixel_len(), 24);
+        assert_eq!(Cube::size(), Size::new(12, 2));
+        assert_eq!(Cube::voxel_index(0, 0, 0), Some(0));
+        assert_eq!(Cube::voxel_index(2, 1, 0), Some(5));
+        assert_eq!(Cube::voxel_index(1, 0, 3), Some(19));
+        assert_eq!(Cube::voxel_index(3, 0, 0), None);
+        assert_eq!(Cube::voxel_index(0, 2, 0), None);
+        assert_eq!(Cube::voxel_index(0, 0, 4), None);
+        assert_eq!(Cube::pixel_index(Point::new(10, 0)), Some(19));
+        assert_eq!(Cube::pixel_index(Point::new(12, 0)), None);
+        assert_eq!(Cube::pixel_index(Point::new(-1, 0)), None);
+        assert_eq!(LedPixelCube::<0, 0, 0>::pixel_index(Point::zero()), None);
+    }
+
+    #[test]
+    fn test_cube_draw_target() {
+        let peripherals = Peripherals::take().unwrap();
+        let mut draw = Ws2812DrawTarget::<LedPixelCube<2, 2, 2>>::new(
+            peripherals.rmt.channel0,
+            peripherals.pins.gpio0,
+        )
+        .unwrap();
+        let color = Rgb888::new(0x01, 0x02, 0x03);
+        let lit = |draw: &Ws2812DrawTarget<LedPixelCube<2, 2, 2>>| {
+            (0..8)
+                .filter(|i| draw.frame_buffer().as_bytes()[i * 3..i * 3 + 3] == [0x02, 0x01, 0x03])
+                .collect::<Vec<_>>()
+        };
+
+        assert!(draw.set_voxel(1, 0, 1, color));
+        assert!(!draw.set_voxel(2, 0, 0, color));
+        assert_eq!(lit(&draw), [5]);
+
+        draw.clear_with_black().unwrap();
+        draw.fill_plane(Plane::X(1), color);
+        assert_eq!(lit(&draw), [1, 3, 5, 7]);
+        draw.clear_with_black().unwrap();
+        draw.fill_plane(Plane::Y(0), color);
+        assert_eq!(lit(&draw), [0, 1, 4, 5]);
+        draw.clear_with_black().unwrap();
+        draw.fill_plane(Plane::Z(1), color);
+        assert_eq!(lit(&draw), [4, 5, 6, 7]);
+        draw.clear_with_black().unwrap();
+        draw.fill_plane(Plane::Z(2), color);
+        assert!(lit(&draw).is_empty());
+    }
+}
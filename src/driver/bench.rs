@@ -0,0 +1,56 @@
+//! Encoding/transmission time measurement of frames.
+
+use core::time::Duration;
+
+/// Encoding and transmission time of a frame written by
+/// [`Ws2812Esp32RmtDriver::write_blocking()`](super::Ws2812Esp32RmtDriver::write_blocking).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameTiming {
+    /// Time to encode the pixel data into the RMT items.
+    pub encode: Duration,
+    /// Time to transmit the encoded RMT items.
+    ///
+    /// This is always zero in the mock.
+    pub transmit: Duration,
+}
+
+impl FrameTiming {
+    /// Returns the total time to write the frame.
+    #[inline]
+    pub fn total(&self) -> Duration {
+        self.encode + self.transmit
+    }
+}
+
+/// Stopwatch using `esp_timer` on target, or `Instant` on host.
+pub(crate) struct Stopwatch {
+    #[cfg(target_vendor = "espressif")]
+    start_us: i64,
+    #[cfg(not(target_vendor = "espressif"))]
+    start: std::time::Instant,
+}
+
+impl Stopwatch {
+    /// Starts the stopwatch.
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(target_vendor = "espressif")]
+            start_us: unsafe { esp_idf_sys::esp_timer_get_time() },
+            #[cfg(not(target_vendor = "espressif"))]
+            start: std::time::Instant::now(),
+        }
+    }
+
+    /// Returns the time elapsed since the stopwatch was started.
+    pub(crate) fn elapsed(&self) -> Duration {
+        #[cfg(target_vendor = "espressif")]
+        {
+            let now_us = unsafe { esp_idf_sys::esp_timer_get_time() };
+            Duration::from_micros(now_us.saturating_sub(self.start_us) as u64)
+        }
+        #[cfg(not(target_vendor = "espressif"))]
+        {
+            self.start.elapsed()
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Adaptive brightness sources.
+
+/// Source of the brightness polled before each frame is written.
+///
+/// It is typically fed by an ambient light sensor, so that the LED pixels dim at night.
+/// Closures returning `u8` are sources as well.
+pub trait BrightnessSource {
+    /// Returns the brightness of the next frame.
+    /// Each channel value shall be scaled down to `(brightness + 1) / 256`.
+    fn brightness(&mut self) -> u8;
+}
+
+impl<F: FnMut() -> u8> BrightnessSource for F {
+    #[inline]
+    fn brightness(&mut self) -> u8 {
+        self()
+    }
+}
+
+/// Brightness source smoothing the changes of another source.
+///
+/// The brightness follows the inner source by an exponential moving average of the polls,
+/// so that a sudden change of the ambient light fades over several frames.
+///
+/// # Examples
+///
+/// ```
+/// use ws2812_esp32_rmt_driver::driver::brightness::{BrightnessSource, SmoothedBrightness};
+///
+/// // e.g. readings of an ambient light sensor
+/// let mut readings = [255, 0, 0].into_iter();
+/// let mut source = SmoothedBrightness::new(move || readings.next().unwrap_or(0), 127);
+/// assert_eq!(source.brightness(), 255);
+/// assert_eq!(source.brightness(), 128);
+/// assert_eq!(source.brightness(), 64);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SmoothedBrightness<S: BrightnessSource> {
+    source: S,
+    weight: u8,
+    /// Brightness in 8.8 fixed point, `None` before the first poll
+    level: Option<u16>,
+}
+
+impl<S: BrightnessSource> SmoothedBrightness<S> {
+    /// Creates a smoothed brightness source.
+    ///
+    /// Each poll moves the brightness towards the inner source by `(weight + 1) / 256` of
+    /// the difference: 255 follows immediately and smaller values fade more slowly.
+    /// The first poll takes the brightness of the inner source as is.
+    pub fn new(source: S, weight: u8) -> Self {
+        Self {
+            source,
+            weight,
+            level: None,
+        }
+    }
+
+    /// Returns the inner source.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S: BrightnessSource> BrightnessSource for SmoothedBrightness<S> {
+    fn brightness(&mut self) -> u8 {
+        let target = (self.source.brightness() as i32) << 8;
+        let level = match self.level {
+            None => target,
+            Some(level) => {
+                let diff = target - level as i32;
+                let step = diff * (self.weight as i32 + 1) / 256;
+                // Make a step at least to reach the target eventually.
+                level as i32 + if step == 0 { diff.signum() } else { step }
+            }
+        };
+        self.level = Some(level as u16);
+        ((level + 0x80) >> 8).min(u8::MAX as i32) as u8
+    }
+}
+
+/// Scales the channel value down to `(brightness + 1) / 256`.
+#[inline]
+pub(crate) fn scale(value: u8, brightness: u8) -> u8 {
+    ((value as u16) * (brightness as u16 + 1) / 256) as u8
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_smoothed_brightness() {
+        let mut target = 200;
+        let mut source = SmoothedBrightness::new(
+            || {
+                let v = target;
+                target = 0;
+                v
+            },
+            127,
+        );
+        assert_eq!(source.brightness(), 200);
+        assert_eq!(source.brightness(), 100);
+        assert_eq!(source.brightness(), 50);
+        assert!((0..100)
+            .map(|_| source.brightness())
+            .is_sorted_by(|a, b| a >= b));
+        assert_eq!(source.brightness(), 0);
+
+        let mut source = SmoothedBrightness::new(|| 255, 0);
+        source.level = Some(0);
+        assert!((0..5000).map(|_| source.brightness()).is_sorted());
+        assert_eq!(source.brightness(), 255);
+
+        let mut source = SmoothedBrightness::new(|| 10, u8::MAX);
+        source.level = Some(200 << 8);
+        assert_eq!(source.brightness(), 10);
+    }
+
+    #[test]
+    fn test_scale() {
+        assert_eq!(scale(0xFF, 0xFF), 0xFF);
+        assert_eq!(scale(0xFF, 0x7F), 0x7F);
+        assert_eq!(scale(0xFF, 0x00), 0x00);
+        assert_eq!(scale(0x10, 0x7F), 0x08);
+    }
+}
@@ -0,0 +1,296 @@
+//! Storage of the encoded frame data.
+//!
+//! The SPI and the parallel drivers encode each frame into a buffer before the DMA transfer.
+//! [`BufferStorage`] chooses where the buffer is placed:
+//!
+//! * `Vec<u8>` - the global allocator (default)
+//! * [`InternalRamBuffer`] - the DMA-capable internal RAM allocated by `heap_caps_malloc()`,
+//!   since the buffers placed in PSRAM by the global allocator break the timing
+//! * [`SliceBuffer`] or `heapless::Vec<u8, N>` - the caller-supplied storage, e.g. a `static`,
+//!   for the projects without heap
+//!
+//! # Examples
+//!
+//! ```
+//! use ws2812_esp32_rmt_driver::driver::buffer::{BufferFull, BufferStorage, SliceBuffer};
+//!
+//! let mut storage = [0u8; 4];
+//! let mut buffer = SliceBuffer::new(&mut storage);
+//! buffer.extend_from_slice(&[1, 2, 3]).unwrap();
+//! assert_eq!(buffer.as_slice(), [1, 2, 3]);
+//! assert_eq!(buffer.extend_from_slice(&[4, 5]), Err(BufferFull));
+//! ```
+
+use core::error::Error;
+use core::fmt;
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(target_vendor = "espressif", not(target_os = "none")))]
+use core::ptr::NonNull;
+#[cfg(all(target_vendor = "espressif", not(target_os = "none")))]
+use esp_idf_sys::{
+    heap_caps_free, heap_caps_malloc, MALLOC_CAP_8BIT, MALLOC_CAP_DMA, MALLOC_CAP_INTERNAL,
+};
+
+/// Error of the buffer storage exhausted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BufferFull;
+
+impl Error for BufferFull {}
+
+impl fmt::Display for BufferFull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "buffer storage is exhausted")
+    }
+}
+
+/// Storage of the encoded frame data of a driver.
+pub trait BufferStorage {
+    /// Clears the content.
+    fn clear(&mut self);
+
+    /// Reserves the room for `additional` more bytes, if the storage grows.
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        let _ = additional;
+    }
+
+    /// Appends `bytes` to the content.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the storage cannot hold them.
+    fn extend_from_slice(&mut self, bytes: &[u8]) -> Result<(), BufferFull>;
+
+    /// Returns the content.
+    fn as_slice(&self) -> &[u8];
+}
+
+#[cfg(feature = "alloc")]
+impl BufferStorage for Vec<u8> {
+    #[inline]
+    fn clear(&mut self) {
+        Vec::clear(self);
+    }
+
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional);
+    }
+
+    #[inline]
+    fn extend_from_slice(&mut self, bytes: &[u8]) -> Result<(), BufferFull> {
+        Vec::extend_from_slice(self, bytes);
+        Ok(())
+    }
+
+    #[inline]
+    fn as_slice(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<const N: usize> BufferStorage for heapless::Vec<u8, N> {
+    #[inline]
+    fn clear(&mut self) {
+        heapless::Vec::clear(self);
+    }
+
+    #[inline]
+    fn extend_from_slice(&mut self, bytes: &[u8]) -> Result<(), BufferFull> {
+        heapless::Vec::extend_from_slice(self, bytes).map_err(|_| BufferFull)
+    }
+
+    #[inline]
+    fn as_slice(&self) -> &[u8] {
+        self
+    }
+}
+
+/// Buffer on the caller-supplied storage.
+#[derive(Debug)]
+pub struct SliceBuffer<'a> {
+    storage: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> SliceBuffer<'a> {
+    /// Creates an empty buffer on `storage`.
+    pub fn new(storage: &'a mut [u8]) -> Self {
+        Self { storage, len: 0 }
+    }
+
+    /// Returns the byte count the buffer can hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.storage.len()
+    }
+}
+
+impl BufferStorage for SliceBuffer<'_> {
+    #[inline]
+    fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    fn extend_from_slice(&mut self, bytes: &[u8]) -> Result<(), BufferFull> {
+        let end = self.len + bytes.len();
+        self.storage
+            .get_mut(self.len..end)
+            .ok_or(BufferFull)?
+            .copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+
+    #[inline]
+    fn as_slice(&self) -> &[u8] {
+        &self.storage[..self.len]
+    }
+}
+
+/// Buffer of a fixed capacity in the DMA-capable internal RAM.
+///
+/// In the mock, it is allocated by the global allocator.
+#[cfg(all(feature = "alloc", not(target_os = "none")))]
+#[derive(Debug)]
+pub struct InternalRamBuffer {
+    /// Internal RAM allocated by `heap_caps_malloc()`
+    #[cfg(target_vendor = "espressif")]
+    ptr: NonNull<u8>,
+    /// Content in the mock
+    #[cfg(not(target_vendor = "espressif"))]
+    data: Vec<u8>,
+    capacity: usize,
+    len: usize,
+}
+
+#[cfg(all(feature = "alloc", not(target_os = "none")))]
+impl InternalRamBuffer {
+    /// Allocates a buffer of `capacity` bytes in the DMA-capable internal RAM.
+    ///
+    /// Returns `None` if the internal RAM is exhausted.
+    pub fn with_capacity(capacity: usize) -> Option<Self> {
+        #[cfg(target_vendor = "espressif")]
+        {
+            let caps = MALLOC_CAP_INTERNAL | MALLOC_CAP_DMA | MALLOC_CAP_8BIT;
+            let ptr = unsafe { heap_caps_malloc(capacity.max(1), caps) };
+            Some(Self {
+                ptr: NonNull::new(ptr as *mut u8)?,
+                capacity,
+                len: 0,
+            })
+        }
+        #[cfg(not(target_vendor = "espressif"))] // Mock implement
+        {
+            Some(Self {
+                data: Vec::with_capacity(capacity),
+                capacity,
+                len: 0,
+            })
+        }
+    }
+
+    /// Returns the byte count the buffer can hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+#[cfg(all(feature = "alloc", not(target_os = "none")))]
+impl BufferStorage for InternalRamBuffer {
+    #[inline]
+    fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    fn extend_from_slice(&mut self, bytes: &[u8]) -> Result<(), BufferFull> {
+        let end = self.len + bytes.len();
+        if end > self.capacity {
+            return Err(BufferFull);
+        }
+        #[cfg(target_vendor = "espressif")]
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                self.ptr.as_ptr().add(self.len),
+                bytes.len(),
+            );
+        }
+        #[cfg(not(target_vendor = "espressif"))]
+        {
+            self.data.truncate(self.len);
+            self.data.extend_from_slice(bytes);
+        }
+        self.len = end;
+        Ok(())
+    }
+
+    #[inline]
+    fn as_slice(&self) -> &[u8] {
+        #[cfg(target_vendor = "espressif")]
+        {
+            unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+        }
+        #[cfg(not(target_vendor = "espressif"))]
+        {
+            &self.data[..self.len]
+        }
+    }
+}
+
+#[cfg(all(
+    feature = "alloc",
+    target_vendor = "espressif",
+    not(target_os = "none")
+))]
+impl Drop for InternalRamBuffer {
+    fn drop(&mut self) {
+        unsafe { heap_caps_free(self.ptr.as_ptr() as *mut _) };
+    }
+}
+
+// The buffer is owned exclusively like a `Vec`.
+#[cfg(all(
+    feature = "alloc",
+    target_vendor = "espressif",
+    not(target_os = "none")
+))]
+unsafe impl Send for InternalRamBuffer {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_buffer_storage() {
+        fn fill<B: BufferStorage>(buffer: &mut B) -> Result<(), BufferFull> {
+            buffer.clear();
+            buffer.reserve(3);
+            buffer.extend_from_slice(&[1, 2])?;
+            buffer.extend_from_slice(&[3])
+        }
+
+        let mut buffer = Vec::new();
+        fill(&mut buffer).unwrap();
+        assert_eq!(buffer.as_slice(), [1, 2, 3]);
+
+        let mut buffer = heapless::Vec::<u8, 2>::new();
+        assert_eq!(fill(&mut buffer), Err(BufferFull));
+        assert_eq!(buffer.as_slice(), [1, 2]);
+
+        let mut storage = [0u8; 3];
+        let mut buffer = SliceBuffer::new(&mut storage);
+        fill(&mut buffer).unwrap();
+        fill(&mut buffer).unwrap();
+        assert_eq!(buffer.as_slice(), [1, 2, 3]);
+
+        let mut buffer = InternalRamBuffer::with_capacity(3).unwrap();
+        fill(&mut buffer).unwrap();
+        fill(&mut buffer).unwrap();
+        assert_eq!(buffer.as_slice(), [1, 2, 3]);
+        assert_eq!(buffer.extend_from_slice(&[4]), Err(BufferFull));
+    }
+}
@@ -0,0 +1,215 @@
+//! Color calibration of the LED pixels.
+//!
+//! The red, green and blue LEDs of a strip are rarely balanced: the green of the typical 5050
+//! LEDs is noticeably stronger, and the white is tinted. [`ColorCalibration`] maps the RGB
+//! channels through a 3x3 matrix before the colors are written, either as the simple
+//! per-channel multipliers of [`ColorCalibration::with_scale()`] or as a full matrix compensating
+//! the crosstalk between the channels; the wrappers apply it with `set_calibration()`. The white
+//! channel is kept as is.
+//!
+//! [`ColorTemperature`] tints the white point to match the ambient lighting, e.g. the warm white
+//! of [`ColorTemperature::K2700`]; the wrappers apply it with `set_color_temperature()` on top of
+//! the calibration, so the color literals are kept as is.
+//!
+//! # Examples
+//!
+//! ```
+//! use ws2812_esp32_rmt_driver::driver::calibration::{ColorCalibration, ColorTemperature};
+//! use ws2812_esp32_rmt_driver::driver::color::{LedPixelColor, LedPixelColorGrb24};
+//!
+//! // Tone the green and the blue down
+//! let calibration = ColorCalibration::with_scale(255, 175, 239);
+//! let color = calibration.apply_color(&LedPixelColorGrb24::new_with_rgb(200, 200, 200));
+//! assert_eq!((color.r(), color.g(), color.b()), (200, 137, 187));
+//!
+//! // Bleed 1/4 of the red into the green, in 1/256 units
+//! let calibration = ColorCalibration::new([[256, 0, 0], [64, 256, 0], [0, 0, 256]]);
+//! let color = calibration.apply_color(&LedPixelColorGrb24::new_with_rgb(200, 100, 0));
+//! assert_eq!((color.r(), color.g(), color.b()), (200, 150, 0));
+//!
+//! // Warm white
+//! let calibration = ColorCalibration::from(ColorTemperature::K2700);
+//! let color = calibration.apply_color(&LedPixelColorGrb24::new_with_rgb(255, 255, 255));
+//! assert_eq!((color.r(), color.g(), color.b()), (255, 167, 87));
+//! ```
+
+use super::color::{LedPixelColor, LedPixelColor16};
+
+/// Calibration matrix of the RGB channels of the LED pixels
+///
+/// Each output channel is the sum of the input channels weighted by a row of the matrix, in
+/// 1/256 units, clamped into the channel range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ColorCalibration([[i16; 3]; 3]);
+
+impl ColorCalibration {
+    /// Identity matrix, i.e. no calibration
+    pub const IDENTITY: Self = Self([[256, 0, 0], [0, 256, 0], [0, 0, 256]]);
+
+    /// Typical SMD5050 LED strip, the green and the blue toned down (`0xFFB0F0` of FastLED)
+    pub const TYPICAL_LED_STRIP: Self = Self::with_scale(0xFF, 0xB0, 0xF0);
+
+    /// Typical 8 mm through-hole LED pixel (`0xFFE08C` of FastLED)
+    pub const TYPICAL_8MM_PIXEL: Self = Self::with_scale(0xFF, 0xE0, 0x8C);
+
+    /// Creates a calibration of the rows of the red, green and blue outputs, each weighting the
+    /// red, green and blue inputs in 1/256 units.
+    #[inline]
+    pub const fn new(matrix: [[i16; 3]; 3]) -> Self {
+        Self(matrix)
+    }
+
+    /// Creates a calibration of the per-channel multipliers, each scaling the channel down to
+    /// `(scale + 1) / 256` as the brightness.
+    #[inline]
+    pub const fn with_scale(r: u8, g: u8, b: u8) -> Self {
+        Self([
+            [r as i16 + 1, 0, 0],
+            [0, g as i16 + 1, 0],
+            [0, 0, b as i16 + 1],
+        ])
+    }
+
+    /// Returns the matrix in 1/256 units.
+    #[inline]
+    pub const fn matrix(&self) -> &[[i16; 3]; 3] {
+        &self.0
+    }
+
+    /// Returns the calibrated 16-bit color.
+    pub fn apply(&self, color: LedPixelColor16) -> LedPixelColor16 {
+        let [r, g, b] = self.0.map(|row| {
+            let sum = i64::from(row[0]) * i64::from(color.r)
+                + i64::from(row[1]) * i64::from(color.g)
+                + i64::from(row[2]) * i64::from(color.b);
+            (sum / 256).clamp(0, i64::from(u16::MAX)) as u16
+        });
+        LedPixelColor16::new_with_rgbw(r, g, b, color.w)
+    }
+
+    /// Returns the calibrated LED pixel color.
+    pub fn apply_color<CDev: LedPixelColor>(&self, color: &CDev) -> CDev {
+        let high = CDev::new_with_rgbw(color.r(), color.g(), color.b(), color.w());
+        let low = CDev::new_with_rgb(0, 0, 0);
+        let (high, _) = self.apply(LedPixelColor16::from_split(&high, &low)).split();
+        high
+    }
+}
+
+impl Default for ColorCalibration {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// White point of the LED pixels, the color of the full white
+///
+/// The channels of the colors are scaled to `(channel + 1) / 256` of the white point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ColorTemperature {
+    /// Red of the white point
+    pub r: u8,
+    /// Green of the white point
+    pub g: u8,
+    /// Blue of the white point
+    pub b: u8,
+}
+
+impl ColorTemperature {
+    /// 2700 K, the warm white of the incandescent lamps
+    pub const K2700: Self = Self::new(255, 167, 87);
+
+    /// 4000 K, the neutral white of the fluorescent lamps
+    pub const K4000: Self = Self::new(255, 206, 166);
+
+    /// 6500 K, the daylight white (D65)
+    pub const K6500: Self = Self::new(255, 254, 250);
+
+    /// Creates a white point of the RGB color.
+    #[inline]
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Creates a white point of the black body at `kelvin`, approximated for 1000 K to 40000 K.
+    ///
+    /// The white point is normalized at 6600 K, the full white.
+    #[cfg(feature = "std")]
+    pub fn from_kelvin(kelvin: u32) -> Self {
+        let t = kelvin.clamp(1000, 40000) as f32 / 100.0;
+        let (r, g) = if t <= 66.0 {
+            (255.0, 99.470_8 * t.ln() - 161.119_57)
+        } else {
+            (
+                329.698_73 * (t - 60.0).powf(-0.133_204_76),
+                288.122_17 * (t - 60.0).powf(-0.075_514_85),
+            )
+        };
+        let b = if t >= 66.0 {
+            255.0
+        } else if t <= 19.0 {
+            0.0
+        } else {
+            138.517_73 * (t - 10.0).ln() - 305.044_8
+        };
+        let channel = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+        Self::new(channel(r), channel(g), channel(b))
+    }
+}
+
+impl From<ColorTemperature> for ColorCalibration {
+    fn from(temperature: ColorTemperature) -> Self {
+        Self::with_scale(temperature.r, temperature.g, temperature.b)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::LedPixelColorGrbw32;
+
+    #[test]
+    fn test_color_calibration() {
+        let color = LedPixelColor16::new_with_rgbw(0x1234, 0xFFFF, 0x0000, 0x5678);
+        assert_eq!(ColorCalibration::IDENTITY.apply(color), color);
+        assert_eq!(ColorCalibration::default(), ColorCalibration::IDENTITY);
+        assert_eq!(
+            ColorCalibration::with_scale(255, 127, 0).apply(color),
+            LedPixelColor16::new_with_rgbw(0x1234, 0x7FFF, 0x0000, 0x5678)
+        );
+
+        // Clamped
+        let calibration = ColorCalibration::new([[512, 0, 0], [-256, 256, 0], [0, 0, 256]]);
+        assert_eq!(calibration.matrix()[1], [-256, 256, 0]);
+        let color = LedPixelColor16::new_with_rgb(0x9000, 0x1000, 0x0100);
+        assert_eq!(
+            calibration.apply(color),
+            LedPixelColor16::new_with_rgb(0xFFFF, 0x0000, 0x0100)
+        );
+
+        let color = LedPixelColorGrbw32::new_with_rgbw(0x20, 0x40, 0x80, 0xFF);
+        let color = ColorCalibration::TYPICAL_LED_STRIP.apply_color(&color);
+        assert_eq!(
+            (color.r(), color.g(), color.b(), color.w()),
+            (0x20, 0x2C, 0x78, 0xFF)
+        );
+    }
+
+    #[test]
+    fn test_color_temperature() {
+        assert_eq!(ColorTemperature::from_kelvin(2700), ColorTemperature::K2700);
+        assert_eq!(ColorTemperature::from_kelvin(4000), ColorTemperature::K4000);
+        assert_eq!(ColorTemperature::from_kelvin(6500), ColorTemperature::K6500);
+        assert_eq!(
+            ColorTemperature::from_kelvin(6600),
+            ColorTemperature::new(255, 255, 255)
+        );
+        assert_eq!(
+            ColorCalibration::from(ColorTemperature::new(255, 255, 255)),
+            ColorCalibration::IDENTITY
+        );
+
+        let from_kelvin = ColorTemperature::from_kelvin(10000);
+        assert!(from_kelvin.r < from_kelvin.g && from_kelvin.b == 255);
+    }
+}
@@ -0,0 +1,250 @@
+//! Chains of the different LED models on one data line.
+//!
+//! [`ChainLayout`] describes a chain where the LED models of the different byte layouts follow
+//! each other, e.g. 8 RGBW status LED pixels followed by 100 GRB strip pixels. It encodes the
+//! LED pixel colors into the pixel-byte sequence, switching the byte layout at the boundaries
+//! of the segments, so that the whole chain is written at once.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::driver::chain::{ChainLayout, ChainSegment};
+//! use ws2812_esp32_rmt_driver::driver::color::{
+//!     LedPixelColor, LedPixelColorGrb24, LedPixelColorGrbw32, LedPixelColorRgbw32,
+//! };
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+//!
+//! let chain = ChainLayout::new([
+//!     ChainSegment::new::<LedPixelColorGrbw32>(2),
+//!     ChainSegment::new::<LedPixelColorGrb24>(3),
+//! ]);
+//! assert_eq!(chain.pixel_len(), 5);
+//! assert_eq!(chain.byte_len(), 17);
+//!
+//! // The colors of the whole chain, in any color type holding the white channel
+//! let pixels = vec![LedPixelColorRgbw32::new_with_rgbw(1, 2, 3, 4); 5];
+//! chain.write(&mut driver, &pixels).unwrap();
+//! assert_eq!(
+//!     driver.pixel_data,
+//!     Some(vec![2, 1, 3, 4, 2, 1, 3, 4, 2, 1, 3, 2, 1, 3, 2, 1, 3]),
+//! );
+//! ```
+
+use crate::driver::color::LedPixelColor;
+use crate::driver::LedStripWrite;
+
+/// Byte layout of an LED model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PixelLayout {
+    /// Channel (0: R, 1: G, 2: B, 3: W) of each byte of a pixel
+    channels: [u8; 4],
+    bpp: u8,
+}
+
+impl PixelLayout {
+    /// Returns the byte layout of the LED pixel color type.
+    pub fn of<C: LedPixelColor>() -> Self {
+        let mut channels = [0; 4];
+        let probe = C::new_with_rgbw(0, 1, 2, 3);
+        channels[..C::BPP].copy_from_slice(probe.as_ref());
+        Self {
+            channels,
+            bpp: C::BPP as u8,
+        }
+    }
+
+    /// Returns the byte count per LED pixel.
+    #[inline]
+    pub fn bpp(&self) -> usize {
+        self.bpp as usize
+    }
+
+    /// Encodes the color into the bytes of this layout.
+    ///
+    /// The white channel is dropped in the layouts without it.
+    pub fn encode<C: LedPixelColor>(&self, color: &C) -> impl Iterator<Item = u8> {
+        self.encode_values(channel_values(color))
+    }
+
+    /// Encodes the R, G, B and W values into the bytes of this layout.
+    fn encode_values(&self, values: [u8; 4]) -> impl Iterator<Item = u8> {
+        self.channels
+            .into_iter()
+            .take(self.bpp())
+            .map(move |channel| values[channel as usize])
+    }
+}
+
+/// Returns the R, G, B and W values of the color.
+#[inline]
+fn channel_values<C: LedPixelColor>(color: &C) -> [u8; 4] {
+    [color.r(), color.g(), color.b(), color.w()]
+}
+
+/// Run of the LED pixels of the same model in a chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChainSegment {
+    layout: PixelLayout,
+    len: usize,
+}
+
+impl ChainSegment {
+    /// Creates a segment of `len` LED pixels of the color type `C`.
+    pub fn new<C: LedPixelColor>(len: usize) -> Self {
+        Self::with_layout(PixelLayout::of::<C>(), len)
+    }
+
+    /// Creates a segment of `len` LED pixels of the byte layout.
+    pub const fn with_layout(layout: PixelLayout, len: usize) -> Self {
+        Self { layout, len }
+    }
+
+    /// Returns the byte layout of the LED pixels.
+    #[inline]
+    pub const fn layout(&self) -> PixelLayout {
+        self.layout
+    }
+
+    /// Returns the number of the LED pixels.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the segment has no LED pixel.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Chain of `N` segments of the LED pixels on one data line, in the wiring order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChainLayout<const N: usize> {
+    segments: [ChainSegment; N],
+}
+
+impl<const N: usize> ChainLayout<N> {
+    /// Creates a chain of the segments in the wiring order.
+    pub const fn new(segments: [ChainSegment; N]) -> Self {
+        Self { segments }
+    }
+
+    /// Returns the segments.
+    #[inline]
+    pub fn segments(&self) -> &[ChainSegment; N] {
+        &self.segments
+    }
+
+    /// Returns the number of the LED pixels of the chain.
+    pub fn pixel_len(&self) -> usize {
+        self.segments.iter().map(ChainSegment::len).sum()
+    }
+
+    /// Returns the byte count of the pixel-byte sequence of the chain.
+    pub fn byte_len(&self) -> usize {
+        self.segments
+            .iter()
+            .map(|segment| segment.len * segment.layout.bpp())
+            .sum()
+    }
+
+    /// Returns the byte layout of the `index`-th LED pixel.
+    /// Returns `None` if it is beyond the chain.
+    pub fn layout_at(&self, index: usize) -> Option<PixelLayout> {
+        let mut start = 0;
+        self.segments.iter().find_map(|segment| {
+            start += segment.len;
+            (index < start).then_some(segment.layout)
+        })
+    }
+
+    /// Encodes the colors of the LED pixels of the chain into the pixel-byte sequence.
+    ///
+    /// The LED pixels beyond `pixels` are black, and the colors beyond the chain are ignored.
+    pub fn encode<'a, C, I>(&'a self, pixels: I) -> impl Iterator<Item = u8> + Send + 'a
+    where
+        C: LedPixelColor + 'a,
+        I: IntoIterator<Item = &'a C>,
+        I::IntoIter: Send + 'a,
+    {
+        let layouts = self
+            .segments
+            .iter()
+            .flat_map(|segment| core::iter::repeat_n(segment.layout, segment.len));
+        let pixels = pixels
+            .into_iter()
+            .map(channel_values)
+            .chain(core::iter::repeat([0; 4]));
+        layouts
+            .zip(pixels)
+            .flat_map(|(layout, values)| layout.encode_values(values))
+    }
+
+    /// Writes the colors of the LED pixels of the chain through the driver.
+    ///
+    /// The LED pixels beyond `pixels` are black, and the colors beyond the chain are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by the driver.
+    pub fn write<W, C>(&self, writer: &mut W, pixels: &[C]) -> Result<(), W::Error>
+    where
+        W: LedStripWrite,
+        C: LedPixelColor,
+    {
+        writer.write_blocking(self.encode(pixels))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::{LedPixelColorGrb24, LedPixelColorGrbw32, LedPixelColorRgbw32};
+
+    #[test]
+    fn test_pixel_layout() {
+        let color = LedPixelColorRgbw32::new_with_rgbw(1, 2, 3, 4);
+        let layout = PixelLayout::of::<LedPixelColorGrb24>();
+        assert_eq!(layout.bpp(), 3);
+        assert!(layout.encode(&color).eq([2, 1, 3]));
+        let layout = PixelLayout::of::<LedPixelColorGrbw32>();
+        assert_eq!(layout.bpp(), 4);
+        assert!(layout.encode(&color).eq([2, 1, 3, 4]));
+    }
+
+    #[test]
+    fn test_chain_layout() {
+        let chain = ChainLayout::new([
+            ChainSegment::new::<LedPixelColorRgbw32>(1),
+            ChainSegment::new::<LedPixelColorGrb24>(0),
+            ChainSegment::new::<LedPixelColorGrb24>(2),
+        ]);
+        assert_eq!(chain.pixel_len(), 3);
+        assert_eq!(chain.byte_len(), 10);
+        assert_eq!(
+            chain.layout_at(0),
+            Some(PixelLayout::of::<LedPixelColorRgbw32>())
+        );
+        assert_eq!(
+            chain.layout_at(2),
+            Some(PixelLayout::of::<LedPixelColorGrb24>())
+        );
+        assert_eq!(chain.layout_at(3), None);
+
+        let pixels = [
+            LedPixelColorGrbw32::new_with_rgbw(1, 2, 3, 4),
+            LedPixelColorGrbw32::new_with_rgbw(5, 6, 7, 8),
+        ];
+        assert!(chain.encode(&pixels).eq([1, 2, 3, 4, 6, 5, 7, 0, 0, 0]));
+        let pixels = vec![LedPixelColorGrbw32::new_with_rgbw(1, 1, 1, 1); 5];
+        assert_eq!(chain.encode(&pixels).count(), 10);
+    }
+}
@@ -0,0 +1,97 @@
+//! TX-capable RMT channels of the chip.
+//!
+//! Some chips have the RMT channels dedicated to RX: e.g. the channels 2 and 3 of ESP32-C3,
+//! ESP32-C6 and ESP32-H2, and the channels 4 to 7 of ESP32-S3. [`TxRmtChannel`] is implemented
+//! only for the TX-capable channels of the target chip, so that choosing an RX-only channel for
+//! the driver fails at compile time rather than with an `EspError` at runtime.
+//!
+//! In the mock, it is implemented for all the channels, which are restricted to the TX-capable
+//! ones by the `mock-esp32*` features.
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::rmt::CHANNEL1;
+//! use ws2812_esp32_rmt_driver::driver::channel::TxRmtChannel;
+//!
+//! assert_eq!(<CHANNEL1 as TxRmtChannel>::CHANNEL, 1);
+//! ```
+
+#[cfg(not(target_vendor = "espressif"))]
+use crate::mock::esp_idf_hal;
+use esp_idf_hal::rmt::{self, RmtChannel};
+
+/// RMT channel capable of TX on the target chip.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not a TX-capable RMT channel of the target chip",
+    note = "use one of the RMT channels capable of TX, e.g. `CHANNEL0`"
+)]
+pub trait TxRmtChannel: RmtChannel {
+    /// RMT channel number
+    const CHANNEL: u32;
+}
+
+macro_rules! impl_tx_rmt_channel {
+    ($($num:literal => $channel:ident),*) => {
+        $(
+            impl TxRmtChannel for rmt::$channel {
+                const CHANNEL: u32 = $num;
+            }
+        )*
+    };
+}
+
+#[cfg(not(target_vendor = "espressif"))]
+#[cfg(mock_chip = "esp32")]
+impl_tx_rmt_channel!(
+    0 => CHANNEL0, 1 => CHANNEL1, 2 => CHANNEL2, 3 => CHANNEL3,
+    4 => CHANNEL4, 5 => CHANNEL5, 6 => CHANNEL6, 7 => CHANNEL7
+);
+#[cfg(not(target_vendor = "espressif"))]
+#[cfg(any(mock_chip = "esp32s2", mock_chip = "esp32s3"))]
+impl_tx_rmt_channel!(0 => CHANNEL0, 1 => CHANNEL1, 2 => CHANNEL2, 3 => CHANNEL3);
+#[cfg(not(target_vendor = "espressif"))]
+#[cfg(any(mock_chip = "esp32c3", mock_chip = "esp32c6", mock_chip = "esp32h2"))]
+impl_tx_rmt_channel!(0 => CHANNEL0, 1 => CHANNEL1);
+#[cfg(not(target_vendor = "espressif"))]
+#[cfg(not(any(
+    mock_chip = "esp32",
+    mock_chip = "esp32s2",
+    mock_chip = "esp32s3",
+    mock_chip = "esp32c3",
+    mock_chip = "esp32c6",
+    mock_chip = "esp32h2"
+)))]
+impl_tx_rmt_channel!(
+    0 => CHANNEL0, 1 => CHANNEL1, 2 => CHANNEL2, 3 => CHANNEL3,
+    4 => CHANNEL4, 5 => CHANNEL5, 6 => CHANNEL6, 7 => CHANNEL7
+);
+
+#[cfg(target_vendor = "espressif")]
+#[cfg(esp32)]
+impl_tx_rmt_channel!(
+    0 => CHANNEL0, 1 => CHANNEL1, 2 => CHANNEL2, 3 => CHANNEL3,
+    4 => CHANNEL4, 5 => CHANNEL5, 6 => CHANNEL6, 7 => CHANNEL7
+);
+#[cfg(target_vendor = "espressif")]
+#[cfg(any(esp32s2, esp32s3, esp32p4))]
+impl_tx_rmt_channel!(0 => CHANNEL0, 1 => CHANNEL1, 2 => CHANNEL2, 3 => CHANNEL3);
+#[cfg(target_vendor = "espressif")]
+#[cfg(not(any(esp32, esp32s2, esp32s3, esp32p4)))]
+impl_tx_rmt_channel!(0 => CHANNEL0, 1 => CHANNEL1);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tx_rmt_channel() {
+        fn channel<C: TxRmtChannel>() -> u32 {
+            assert_eq!(C::CHANNEL, C::channel());
+            C::CHANNEL
+        }
+        assert_eq!(channel::<rmt::CHANNEL0>(), 0);
+        assert_eq!(channel::<rmt::CHANNEL1>(), 1);
+    }
+}
@@ -30,6 +30,27 @@ pub trait LedPixelColor:
             ((self.w() as u16) * (brightness as u16 + 1) / 256) as u8,
         )
     }
+
+    /// Returns the color with the common component of the red, green and blue channels,
+    /// `min(r, g, b)`, moved into the white channel.
+    /// The color is returned as is if it has no white channel.
+    #[inline]
+    fn extract_white(&self) -> Self {
+        let white = self.r().min(self.g()).min(self.b()).min(u8::MAX - self.w());
+        let w = self.w() + white;
+        let color = Self::new_with_rgbw(self.r() - white, self.g() - white, self.b() - white, w);
+        if color.w() == w {
+            color
+        } else {
+            self.clone()
+        }
+    }
+}
+
+/// Returns `true` if the LED pixel color type has the white channel.
+#[inline]
+pub(crate) fn has_white<CDev: LedPixelColor>() -> bool {
+    CDev::new_with_rgbw(0, 0, 0, 1).w() != 0
 }
 
 /// LED pixel color struct made with an `N`-length `u8` array.
@@ -49,7 +70,7 @@ pub trait LedPixelColor:
 /// assert_eq!(color.as_ref(), [2, 1, 3]);
 /// assert_eq!((color.r(), color.g(), color.b(), color.w()), (1, 2, 3, 0));
 /// ```
-#[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Hash)]
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone, Hash)]
 #[repr(transparent)]
 pub struct LedPixelColorImpl<
     const N: usize,
@@ -186,6 +207,26 @@ fn test_led_pixel_color_brightness() {
     );
 }
 
+#[test]
+fn test_led_pixel_color_extract_white() {
+    let color = LedPixelColorImpl::<4, 0, 1, 2, 3>::new_with_rgbw(255, 128, 64, 32);
+    assert_eq!(color.extract_white().0, [191, 64, 0, 96]);
+    let color = LedPixelColorImpl::<4, 0, 1, 2, 3>::new_with_rgbw(255, 128, 64, 250);
+    assert_eq!(color.extract_white().0, [250, 123, 59, 255]);
+    // No white channel
+    let color = LedPixelColorImpl::<3, 1, 0, 2, 255>::new_with_rgb(255, 128, 64);
+    assert_eq!(color.extract_white(), color);
+
+    assert!(has_white::<LedPixelColorImpl<4, 0, 1, 2, 3>>());
+    assert!(!has_white::<LedPixelColorImpl<3, 1, 0, 2, 255>>());
+
+    let color = LedPixelColor16::new_with_rgbw(0xFFFF, 0x8000, 0x4080, 0x0100);
+    assert_eq!(
+        color.extract_white(),
+        LedPixelColor16::new_with_rgbw(0xBF7F, 0x3F80, 0x0000, 0x4180)
+    );
+}
+
 /// 8-bit GRB LED pixel color (total 32-bit pixel), Typical RGB LED (WS2812B/SK6812) pixel color
 ///
 /// # Examples
@@ -221,3 +262,192 @@ pub type LedPixelColorRgbw32 = LedPixelColorImpl<4, 0, 1, 2, 3>;
 /// assert_eq!(color.as_ref(), [2, 1, 3, 4]);
 /// ```
 pub type LedPixelColorGrbw32 = LedPixelColorImpl<4, 1, 0, 2, 3>;
+
+/// 8-bit BGR LED pixel color (total 24-bit pixel), the channel order of the clocked LEDs
+/// (APA102/SK9822)
+///
+/// # Examples
+///
+/// ```
+/// use ws2812_esp32_rmt_driver::driver::color::{LedPixelColorBgr24, LedPixelColor};
+///
+/// let color = LedPixelColorBgr24::new_with_rgb(1, 2, 3);
+/// assert_eq!(color.as_ref(), [3, 2, 1]);
+/// ```
+pub type LedPixelColorBgr24 = LedPixelColorImpl<3, 2, 1, 0, 255>;
+
+/// 16-bit RGBW LED pixel color, the high dynamic range input of the framebuffer
+///
+/// The channels are quantized into the 8-bit channels of the LED pixel color type on writing;
+/// the framebuffer keeps the lower bytes as the fractions carried by the temporal dithering.
+///
+/// # Examples
+///
+/// ```
+/// use ws2812_esp32_rmt_driver::driver::color::{LedPixelColor, LedPixelColor16, LedPixelColorGrb24};
+///
+/// let color = LedPixelColor16::new_with_rgb(0x0180, 0x0200, 0xFFFF);
+/// let (high, low) = color.split::<LedPixelColorGrb24>();
+/// assert_eq!(high.as_ref(), [0x02, 0x01, 0xFF]);
+/// assert_eq!(low.as_ref(), [0x00, 0x80, 0xFF]);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct LedPixelColor16 {
+    /// Red
+    pub r: u16,
+    /// Green
+    pub g: u16,
+    /// Blue
+    pub b: u16,
+    /// White
+    pub w: u16,
+}
+
+impl LedPixelColor16 {
+    /// Creates with RGB (Red-Green-Blue) value.
+    #[inline]
+    pub const fn new_with_rgb(r: u16, g: u16, b: u16) -> Self {
+        Self { r, g, b, w: 0 }
+    }
+
+    /// Creates with RGBW (Red-Green-Blue, and White) value.
+    #[inline]
+    pub const fn new_with_rgbw(r: u16, g: u16, b: u16, w: u16) -> Self {
+        Self { r, g, b, w }
+    }
+
+    /// Returns brightness-adjusted color.
+    /// Each channel values of the returned shall be scaled down to `(brightness + 1) / 256`.
+    #[inline]
+    pub const fn brightness(&self, brightness: u8) -> Self {
+        const fn scale(value: u16, brightness: u8) -> u16 {
+            ((value as u32) * (brightness as u32 + 1) / 256) as u16
+        }
+        Self {
+            r: scale(self.r, brightness),
+            g: scale(self.g, brightness),
+            b: scale(self.b, brightness),
+            w: scale(self.w, brightness),
+        }
+    }
+
+    /// Returns the color with the common component of the red, green and blue channels,
+    /// `min(r, g, b)`, moved into the white channel.
+    #[inline]
+    pub fn extract_white(&self) -> Self {
+        let white = self.r.min(self.g).min(self.b).min(u16::MAX - self.w);
+        Self {
+            r: self.r - white,
+            g: self.g - white,
+            b: self.b - white,
+            w: self.w + white,
+        }
+    }
+
+    /// Joins the LED pixel colors of the upper bytes and the lower bytes of the channels, the
+    /// reverse of [`Self::split()`].
+    pub fn from_split<CDev: LedPixelColor>(high: &CDev, low: &CDev) -> Self {
+        Self {
+            r: u16::from_be_bytes([high.r(), low.r()]),
+            g: u16::from_be_bytes([high.g(), low.g()]),
+            b: u16::from_be_bytes([high.b(), low.b()]),
+            w: u16::from_be_bytes([high.w(), low.w()]),
+        }
+    }
+
+    /// Splits into the LED pixel colors of the upper bytes and the lower bytes of the channels.
+    pub fn split<CDev: LedPixelColor>(&self) -> (CDev, CDev) {
+        let [r, g, b, w] = [self.r, self.g, self.b, self.w].map(u16::to_be_bytes);
+        (
+            CDev::new_with_rgbw(r[0], g[0], b[0], w[0]),
+            CDev::new_with_rgbw(r[1], g[1], b[1], w[1]),
+        )
+    }
+}
+
+/// Channel order of the LED pixels on the wire, chosen at runtime
+///
+/// Unlike the channel order of [`LedPixelColorImpl`] fixed at the compile time, it is chosen at
+/// runtime, e.g. from a configuration file, and the wrappers reorder the bytes of the colors into
+/// it at write time.
+///
+/// # Examples
+///
+/// ```
+/// use ws2812_esp32_rmt_driver::driver::color::{ColorOrder, LedPixelColor, LedPixelColorGrb24};
+///
+/// let color = LedPixelColorGrb24::new_with_rgb(1, 2, 3);
+/// let bytes = ColorOrder::Brg.pixel_bytes(&color).collect::<Vec<_>>();
+/// assert_eq!(bytes, [3, 1, 2]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ColorOrder {
+    /// R, G, B
+    Rgb = 0,
+    /// R, B, G
+    Rbg = 1,
+    /// G, R, B, e.g. WS2812
+    Grb = 2,
+    /// G, B, R
+    Gbr = 3,
+    /// B, R, G
+    Brg = 4,
+    /// B, G, R
+    Bgr = 5,
+    /// R, G, B, W
+    Rgbw = 6,
+    /// G, R, B, W, e.g. SK6812 RGBW
+    Grbw = 7,
+}
+
+impl ColorOrder {
+    /// All the color orders
+    pub const ALL: [Self; 8] = [
+        Self::Rgb,
+        Self::Rbg,
+        Self::Grb,
+        Self::Gbr,
+        Self::Brg,
+        Self::Bgr,
+        Self::Rgbw,
+        Self::Grbw,
+    ];
+
+    /// Returns the byte count per LED pixel.
+    pub const fn bpp(&self) -> usize {
+        match self {
+            Self::Rgbw | Self::Grbw => 4,
+            _ => 3,
+        }
+    }
+
+    /// Returns the bytes of the RGBW in the order, [`Self::bpp()`] of them.
+    ///
+    /// The white channel is dropped by the 3-byte orders.
+    pub const fn bytes(&self, r: u8, g: u8, b: u8, w: u8) -> ([u8; 4], usize) {
+        let bytes = match self {
+            Self::Rgb => [r, g, b, 0],
+            Self::Rbg => [r, b, g, 0],
+            Self::Grb => [g, r, b, 0],
+            Self::Gbr => [g, b, r, 0],
+            Self::Brg => [b, r, g, 0],
+            Self::Bgr => [b, g, r, 0],
+            Self::Rgbw => [r, g, b, w],
+            Self::Grbw => [g, r, b, w],
+        };
+        (bytes, self.bpp())
+    }
+
+    /// Returns the bytes of the LED pixel color reordered into the order.
+    ///
+    /// The white channel is dropped by the 3-byte orders.
+    #[inline]
+    pub fn pixel_bytes<CDev: LedPixelColor>(
+        &self,
+        color: &CDev,
+    ) -> impl Iterator<Item = u8> + Send + Clone {
+        let (bytes, len) = self.bytes(color.r(), color.g(), color.b(), color.w());
+        bytes.into_iter().take(len)
+    }
+}
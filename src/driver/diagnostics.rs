@@ -0,0 +1,161 @@
+//! Diagnostics dump of the driver configuration.
+//!
+//! [`DriverConfig`] is a snapshot of the effective configuration of a driver, e.g. returned by
+//! `Ws2812Esp32RmtDriver::config()`, and its `Display` prints one `key: value` per line, so that
+//! it is pasted into a support request as is. The settings applied outside the driver, i.e. the
+//! strip length, the brightness and the gamma, are attached by the builder methods.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio27).unwrap();
+//!
+//! let config = driver.config().with_strip(100, 3).with_brightness(128);
+//! assert_eq!(config.gpio, 27);
+//! assert_eq!(config.counter_clock_hz, 10_000_000);
+//! println!("{config}");
+//! ```
+
+use super::timing::{LedTiming, RmtBitTicks};
+use core::fmt;
+
+/// Effective configuration of a driver.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DriverConfig {
+    /// RMT channel number
+    pub channel: u32,
+    /// GPIO number of the data line
+    pub gpio: i32,
+    /// Source clock frequency of the RMT channel
+    pub source_clock_hz: u32,
+    /// Clock divider of the RMT channel
+    pub clock_divider: u8,
+    /// Counter clock frequency of the RMT channel
+    pub counter_clock_hz: u32,
+    /// Signal timing of the LED pixels
+    pub timing: LedTiming,
+    /// Pulse ticks of the bits at the counter clock
+    pub ticks: Option<RmtBitTicks>,
+    /// Number of the memory blocks of the RMT channel
+    pub mem_block_num: u8,
+    /// Byte count of the last frame written by `write_blocking()`
+    pub frame_len: usize,
+    /// Whether the output is suspended for light sleep
+    pub suspended: bool,
+    /// The number of the LED pixels and the byte count per LED pixel of the strip, if known
+    pub strip: Option<(usize, usize)>,
+    /// Brightness applied to the frames, if any
+    pub brightness: Option<u8>,
+    /// Gamma applied to the frames, if any
+    pub gamma: Option<f32>,
+}
+
+impl DriverConfig {
+    /// Returns the configuration with the strip of `pixel_len` LED pixels of `bpp` bytes.
+    pub fn with_strip(mut self, pixel_len: usize, bpp: usize) -> Self {
+        self.strip = Some((pixel_len, bpp));
+        self
+    }
+
+    /// Returns the configuration with the brightness applied to the frames.
+    pub fn with_brightness(mut self, brightness: u8) -> Self {
+        self.brightness = Some(brightness);
+        self
+    }
+
+    /// Returns the configuration with the gamma applied to the frames.
+    pub fn with_gamma(mut self, gamma: f32) -> Self {
+        self.gamma = Some(gamma);
+        self
+    }
+}
+
+impl fmt::Display for DriverConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nanos = |d: core::time::Duration| d.as_nanos();
+        writeln!(f, "channel: {}", self.channel)?;
+        writeln!(f, "gpio: {}", self.gpio)?;
+        writeln!(
+            f,
+            "counter clock: {} Hz ({} Hz / {})",
+            self.counter_clock_hz, self.source_clock_hz, self.clock_divider
+        )?;
+        writeln!(
+            f,
+            "timing: T0H {} ns, T0L {} ns, T1H {} ns, T1L {} ns, reset {} ns",
+            nanos(self.timing.t0h),
+            nanos(self.timing.t0l),
+            nanos(self.timing.t1h),
+            nanos(self.timing.t1l),
+            nanos(self.timing.reset)
+        )?;
+        match &self.ticks {
+            Some(t) => writeln!(
+                f,
+                "ticks: T0H {}, T0L {}, T1H {}, T1L {}",
+                t.t0h, t.t0l, t.t1h, t.t1l
+            )?,
+            None => writeln!(f, "ticks: out of range")?,
+        }
+        writeln!(f, "mem blocks: {}", self.mem_block_num)?;
+        writeln!(f, "frame: {} bytes", self.frame_len)?;
+        writeln!(f, "suspended: {}", self.suspended)?;
+        match self.strip {
+            Some((pixel_len, bpp)) => writeln!(f, "strip: {} pixels x {} bytes", pixel_len, bpp)?,
+            None => writeln!(f, "strip: unknown")?,
+        }
+        match self.brightness {
+            Some(brightness) => writeln!(f, "brightness: {}", brightness)?,
+            None => writeln!(f, "brightness: none")?,
+        }
+        match self.gamma {
+            Some(gamma) => write!(f, "gamma: {}", gamma),
+            None => write!(f, "gamma: none"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+    use crate::Ws2812Esp32RmtDriver;
+
+    #[test]
+    fn test_driver_config() {
+        let peripherals = Peripherals::take().unwrap();
+        let mut driver =
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel1, peripherals.pins.gpio5).unwrap();
+        driver.write_blocking([0; 6].into_iter()).unwrap();
+
+        let config = driver.dump().with_strip(2, 3).with_gamma(2.2);
+        assert_eq!(config.channel, 1);
+        assert_eq!(config.clock_divider, 8);
+        assert_eq!(config.frame_len, 6);
+        assert_eq!(
+            config.ticks,
+            RmtBitTicks::new(10_000_000, &LedTiming::WS2812)
+        );
+        assert_eq!(
+            config.to_string(),
+            "channel: 1\n\
+             gpio: 5\n\
+             counter clock: 10000000 Hz (80000000 Hz / 8)\n\
+             timing: T0H 400 ns, T0L 850 ns, T1H 800 ns, T1L 450 ns, reset 50000 ns\n\
+             ticks: T0H 4, T0L 8, T1H 8, T1L 4\n\
+             mem blocks: 1\n\
+             frame: 6 bytes\n\
+             suspended: false\n\
+             strip: 2 pixels x 3 bytes\n\
+             brightness: none\n\
+             gamma: 2.2"
+        );
+    }
+}
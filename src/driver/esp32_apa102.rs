@@ -0,0 +1,435 @@
+#![cfg_attr(not(target_vendor = "espressif"), allow(dead_code))]
+
+use core::error::Error;
+use core::fmt;
+
+#[cfg(not(target_vendor = "espressif"))]
+use core::marker::PhantomData;
+
+use super::buffer::{BufferFull, BufferStorage};
+use super::trace::trace_event;
+#[cfg(target_vendor = "espressif")]
+use super::trace::TraceError;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(not(target_vendor = "espressif"))]
+use crate::mock::esp_idf_hal;
+#[cfg(target_vendor = "espressif")]
+use esp_idf_hal::{
+    gpio::AnyIOPin,
+    spi::{
+        config::{Config, DriverConfig},
+        Dma, SpiDeviceDriver, SpiDriver,
+    },
+    units::Hertz,
+};
+use esp_idf_hal::{gpio::OutputPin, peripheral::Peripheral, spi::SpiAnyPins};
+
+#[cfg(not(target_vendor = "espressif"))]
+use crate::mock::esp_idf_sys;
+use esp_idf_sys::EspError;
+
+/// Maximum byte count of a DMA transfer.
+#[cfg(target_vendor = "espressif")]
+const DMA_TRANSFER_SIZE: usize = 4096;
+
+/// Byte count of the pixel data of an APA102 LED pixel, excluding the header byte.
+const APA102_BPP: usize = 3;
+
+/// Maximum global brightness of the APA102 LED pixels.
+pub const APA102_MAX_GLOBAL_BRIGHTNESS: u8 = 0x1F;
+
+/// Returns the byte count of the SPI data of `pixel_len` APA102 LED pixels.
+pub const fn apa102_encoded_len(pixel_len: usize) -> usize {
+    4 + pixel_len * (APA102_BPP + 1) + 4 + pixel_len.div_ceil(16)
+}
+
+/// Encodes the pixel-byte sequence into the APA102 SPI data, replacing the content of `buffer`.
+///
+/// The pixel data is 3 bytes per LED pixel in the order on the wire, blue, green and red for
+/// [`LedPixelColorBgr24`]. The frame is the start frame of 4 zero bytes, the LED pixels
+/// each prefixed by the header byte of `global_brightness` (0 to 31), and the end frame of 4 zero
+/// bytes to latch the SK9822 followed by the zero bytes clocking the data through the APA102,
+/// one per 16 LED pixels. The trailing bytes short of a LED pixel are padded with zeros.
+///
+/// [`LedPixelColorBgr24`]: super::color::LedPixelColorBgr24
+///
+/// # Errors
+///
+/// Returns an error if `buffer` cannot hold the SPI data.
+pub fn encode_apa102<T, B>(
+    pixel_sequence: T,
+    global_brightness: u8,
+    buffer: &mut B,
+) -> Result<(), BufferFull>
+where
+    T: Iterator<Item = u8>,
+    B: BufferStorage + ?Sized,
+{
+    let header = 0xE0 | global_brightness.min(APA102_MAX_GLOBAL_BRIGHTNESS);
+    buffer.clear();
+    buffer.extend_from_slice(&[0; 4])?;
+    let mut pixel_sequence = pixel_sequence.peekable();
+    let mut pixel_len = 0usize;
+    while pixel_sequence.peek().is_some() {
+        let mut pixel = [header, 0, 0, 0];
+        for (dst, value) in pixel[1..].iter_mut().zip(&mut pixel_sequence) {
+            *dst = value;
+        }
+        buffer.extend_from_slice(&pixel)?;
+        pixel_len += 1;
+    }
+    buffer.extend_from_slice(&[0; 4])?;
+    for _ in 0..pixel_len.div_ceil(16) {
+        buffer.extend_from_slice(&[0])?;
+    }
+    Ok(())
+}
+
+/// APA102 ESP32 SPI Driver error.
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct Apa102Esp32SpiDriverError {
+    source: EspError,
+}
+
+#[cfg(not(feature = "std"))]
+impl Apa102Esp32SpiDriverError {
+    /// The `EspError` source of this error, if any.
+    ///
+    /// This is a workaround function until `core::error::Error` added to `esp_sys::EspError`.
+    pub fn source(&self) -> Option<&EspError> {
+        Some(&self.source)
+    }
+}
+
+impl Error for Apa102Esp32SpiDriverError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        #[cfg(feature = "std")]
+        {
+            Some(&self.source)
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Apa102Esp32SpiDriverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+impl From<EspError> for Apa102Esp32SpiDriverError {
+    fn from(source: EspError) -> Self {
+        Self { source }
+    }
+}
+
+impl From<BufferFull> for Apa102Esp32SpiDriverError {
+    fn from(_: BufferFull) -> Self {
+        #[cfg(target_vendor = "espressif")]
+        {
+            EspError::from_infallible::<{ esp_idf_sys::ESP_ERR_NO_MEM }>().into()
+        }
+        #[cfg(not(target_vendor = "espressif"))]
+        {
+            EspError().into()
+        }
+    }
+}
+
+/// APA102/SK9822 ESP32 SPI driver wrapper.
+///
+/// The clocked LED pixels are driven by an SPI host on the clock and data pins. As a
+/// [`LedStripWrite`], the pixel-byte sequence is the one of [`LedPixelColorBgr24`], so that the
+/// wrappers such as [`LedPixelFrameBuffer`], `lib_smart_leds` and `lib_embedded_graphics` drive
+/// the APA102 strips as the WS2812 ones. The 5-bit global brightness of the LED pixels is set by
+/// [`Self::set_global_brightness()`], on top of the brightness of the wrappers.
+///
+/// [`LedStripWrite`]: super::LedStripWrite
+/// [`LedPixelColorBgr24`]: super::color::LedPixelColorBgr24
+/// [`LedPixelFrameBuffer`]: crate::framebuffer::LedPixelFrameBuffer
+///
+/// # Examples
+///
+/// ```
+/// #[cfg(not(target_vendor = "espressif"))]
+/// use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+///
+/// use esp_idf_hal::peripherals::Peripherals;
+/// use ws2812_esp32_rmt_driver::driver::Apa102Esp32SpiDriver;
+/// use ws2812_esp32_rmt_driver::driver::color::{LedPixelColor, LedPixelColorBgr24};
+/// use ws2812_esp32_rmt_driver::framebuffer::LedPixelFrameBuffer;
+///
+/// let peripherals = Peripherals::take().unwrap();
+/// let pins = peripherals.pins;
+/// let mut driver = Apa102Esp32SpiDriver::new(peripherals.spi2, pins.gpio18, pins.gpio23, 8_000_000).unwrap();
+/// driver.set_global_brightness(16);
+///
+/// let mut frame = LedPixelFrameBuffer::<LedPixelColorBgr24, Vec<u8>, _>::from_driver(driver, 60);
+/// frame.fill(LedPixelColorBgr24::new_with_rgb(30, 0, 0));
+/// frame.show().unwrap();
+/// ```
+pub struct Apa102Esp32SpiDriver<'d, B = Vec<u8>>
+where
+    B: BufferStorage,
+{
+    /// SPI device driver.
+    #[cfg(target_vendor = "espressif")]
+    device: SpiDeviceDriver<'d, SpiDriver<'d>>,
+    /// Global brightness of the LED pixels, 0 to 31
+    global_brightness: u8,
+    /// SPI data of the last frame
+    buffer: B,
+
+    /// Pixel binary array to be written
+    ///
+    /// If the target vendor does not equals to "espressif", pixel data is written into this
+    /// instead of genuine SPI device.
+    #[cfg(not(target_vendor = "espressif"))]
+    pub pixel_data: Option<Vec<u8>>,
+    /// Recording state of the mock
+    #[cfg(not(target_vendor = "espressif"))]
+    recorder: crate::mock::recorder::FrameRecorder,
+    /// Dummy phantom to take care of lifetime for `pixel_data`.
+    #[cfg(not(target_vendor = "espressif"))]
+    phantom: PhantomData<&'d Option<Vec<u8>>>,
+}
+
+impl<'d> Apa102Esp32SpiDriver<'d> {
+    /// Creates an APA102 ESP32 SPI driver wrapper clocking at `clock_hz`.
+    ///
+    /// SPI driver of `spi` shall be initialized with the DMA for the clock `sclk_pin` and the
+    /// data `data_pin`. The APA102 takes up to around 20 MHz, less on the long strips.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SPI driver initialization failed.
+    pub fn new<SPI: SpiAnyPins>(
+        spi: impl Peripheral<P = SPI> + 'd,
+        sclk_pin: impl Peripheral<P = impl OutputPin> + 'd,
+        data_pin: impl Peripheral<P = impl OutputPin> + 'd,
+        clock_hz: u32,
+    ) -> Result<Self, Apa102Esp32SpiDriverError> {
+        Self::new_with_buffer(spi, sclk_pin, data_pin, clock_hz, Vec::new())
+    }
+}
+
+impl<'d, B: BufferStorage> Apa102Esp32SpiDriver<'d, B> {
+    /// Creates an APA102 ESP32 SPI driver wrapper encoding the frames into `buffer`.
+    ///
+    /// `buffer` shall hold [`apa102_encoded_len()`] bytes of the longest frame; use
+    /// [`InternalRamBuffer`] to keep it off PSRAM, or [`SliceBuffer`] not to use heap for it.
+    ///
+    /// [`InternalRamBuffer`]: super::buffer::InternalRamBuffer
+    /// [`SliceBuffer`]: super::buffer::SliceBuffer
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SPI driver initialization failed.
+    pub fn new_with_buffer<SPI: SpiAnyPins>(
+        spi: impl Peripheral<P = SPI> + 'd,
+        sclk_pin: impl Peripheral<P = impl OutputPin> + 'd,
+        data_pin: impl Peripheral<P = impl OutputPin> + 'd,
+        clock_hz: u32,
+        buffer: B,
+    ) -> Result<Self, Apa102Esp32SpiDriverError> {
+        #[cfg(target_vendor = "espressif")]
+        {
+            let trace_error =
+                |e: &EspError| trace_event!(error, "APA102 driver init failed: {}", TraceError(e));
+            let driver_config = DriverConfig::new().dma(Dma::Auto(DMA_TRANSFER_SIZE));
+            let driver = SpiDriver::new(
+                spi,
+                sclk_pin,
+                data_pin,
+                Option::<AnyIOPin>::None,
+                &driver_config,
+            )
+            .inspect_err(trace_error)?;
+            let config = Config::new().baudrate(Hertz(clock_hz));
+            let device = SpiDeviceDriver::new(driver, Option::<AnyIOPin>::None, &config)
+                .inspect_err(trace_error)?;
+            trace_event!(debug, "APA102 driver initialized at {} Hz", clock_hz);
+
+            Ok(Self {
+                device,
+                global_brightness: APA102_MAX_GLOBAL_BRIGHTNESS,
+                buffer,
+            })
+        }
+        #[cfg(not(target_vendor = "espressif"))] // Mock implement
+        {
+            let _ = (spi, sclk_pin, data_pin);
+            trace_event!(debug, "APA102 driver initialized at {} Hz", clock_hz);
+            Ok(Self {
+                global_brightness: APA102_MAX_GLOBAL_BRIGHTNESS,
+                buffer,
+                pixel_data: None,
+                recorder: Default::default(),
+                phantom: Default::default(),
+            })
+        }
+    }
+
+    /// Returns the global brightness of the LED pixels, 0 to 31.
+    #[inline]
+    pub fn global_brightness(&self) -> u8 {
+        self.global_brightness
+    }
+
+    /// Sets the global brightness of the LED pixels from the next frame, 0 to 31 (the default).
+    ///
+    /// The larger values are clamped to 31. Unlike the brightness of the wrappers, it dims the
+    /// LED pixels by the current of the LED drivers without losing the color resolution.
+    #[inline]
+    pub fn set_global_brightness(&mut self, global_brightness: u8) {
+        self.global_brightness = global_brightness.min(APA102_MAX_GLOBAL_BRIGHTNESS);
+    }
+
+    /// Writes pixel data from a pixel-byte sequence to the data pin.
+    ///
+    /// The pixel data sequence is 3 bytes per LED pixel in the order on the wire; see
+    /// [`encode_apa102()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer cannot hold the SPI data or if an SPI driver error occurred.
+    pub fn write_blocking<T>(&mut self, pixel_sequence: T) -> Result<(), Apa102Esp32SpiDriverError>
+    where
+        T: Iterator<Item = u8>,
+    {
+        trace_event!(trace, "APA102 frame start");
+        #[cfg(target_vendor = "espressif")]
+        {
+            encode_apa102(pixel_sequence, self.global_brightness, &mut self.buffer)?;
+            self.device.write(self.buffer.as_slice()).inspect_err(|e| {
+                trace_event!(error, "APA102 frame failed: {}", TraceError(e));
+            })?;
+        }
+        #[cfg(not(target_vendor = "espressif"))]
+        {
+            let pixel_data = pixel_sequence.collect::<Vec<_>>();
+            encode_apa102(
+                pixel_data.iter().copied(),
+                self.global_brightness,
+                &mut self.buffer,
+            )?;
+            self.pixel_data = Some(self.recorder.record(pixel_data.into_iter())?);
+        }
+        trace_event!(
+            trace,
+            "APA102 frame done: {} SPI bytes",
+            self.buffer.as_slice().len()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(not(target_vendor = "espressif"))]
+crate::mock::recorder::impl_frame_recording!({'d, B: BufferStorage} Apa102Esp32SpiDriver<'d, B>);
+
+#[cfg(not(target_vendor = "espressif"))]
+impl<B: BufferStorage> Apa102Esp32SpiDriver<'_, B> {
+    /// Returns the SPI data of the last frame written.
+    ///
+    /// This function is only available in the mock.
+    pub fn spi_data(&self) -> &[u8] {
+        self.buffer.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::buffer::SliceBuffer;
+    use crate::driver::color::{LedPixelColor, LedPixelColorBgr24};
+    use crate::framebuffer::LedPixelFrameBuffer;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+
+    #[test]
+    fn test_encode_apa102() {
+        let mut buffer = Vec::new();
+        encode_apa102([1, 2, 3, 4].into_iter(), 40, &mut buffer).unwrap();
+        assert_eq!(
+            buffer,
+            [
+                0, 0, 0, 0, //
+                0xFF, 1, 2, 3, //
+                0xFF, 4, 0, 0, //
+                0, 0, 0, 0, 0,
+            ]
+        );
+        assert_eq!(apa102_encoded_len(2), buffer.len());
+
+        encode_apa102([0; 17 * 3].into_iter(), 1, &mut buffer).unwrap();
+        assert_eq!(buffer.len(), apa102_encoded_len(17));
+        assert_eq!(buffer[4], 0xE1);
+        assert_eq!(buffer[buffer.len() - 6..], [0; 6]);
+
+        let mut buffer = heapless::Vec::<u8, 8>::new();
+        assert_eq!(
+            encode_apa102([1, 2, 3].into_iter(), 0, &mut buffer),
+            Err(BufferFull)
+        );
+    }
+
+    #[test]
+    fn test_apa102_esp32_spi_driver() {
+        let peripherals = Peripherals::take().unwrap();
+        let pins = peripherals.pins;
+        let mut driver =
+            Apa102Esp32SpiDriver::new(peripherals.spi2, pins.gpio0, pins.gpio1, 8_000_000).unwrap();
+        assert_eq!(driver.global_brightness(), APA102_MAX_GLOBAL_BRIGHTNESS);
+        driver.set_global_brightness(8);
+        assert_eq!(driver.global_brightness(), 8);
+
+        let mut frame =
+            LedPixelFrameBuffer::<LedPixelColorBgr24, Vec<u8>, _>::from_driver(driver, 1);
+        frame.fill(LedPixelColorBgr24::new_with_rgb(1, 2, 3));
+        frame.show().unwrap();
+        let driver = frame.driver();
+        assert_eq!(driver.pixel_data, Some(vec![3, 2, 1]));
+        assert_eq!(
+            driver.spi_data(),
+            [0, 0, 0, 0, 0xE8, 3, 2, 1, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "written frame shall be 2 pixels x 3 bytes")]
+    fn test_apa102_esp32_spi_driver_expect_strip_len() {
+        let peripherals = Peripherals::take().unwrap();
+        let pins = peripherals.pins;
+        let mut driver =
+            Apa102Esp32SpiDriver::new(peripherals.spi2, pins.gpio0, pins.gpio1, 8_000_000).unwrap();
+
+        driver.expect_strip_len(2, 3);
+        driver.write_blocking([0; 6].into_iter()).unwrap();
+        driver.write_blocking([0; 3].into_iter()).unwrap();
+    }
+
+    #[test]
+    fn test_apa102_esp32_spi_driver_with_buffer() {
+        let peripherals = Peripherals::take().unwrap();
+        let pins = peripherals.pins;
+        let mut storage = [0u8; 13];
+        let buffer = SliceBuffer::new(&mut storage);
+        let mut driver = Apa102Esp32SpiDriver::new_with_buffer(
+            peripherals.spi2,
+            pins.gpio0,
+            pins.gpio1,
+            8_000_000,
+            buffer,
+        )
+        .unwrap();
+
+        driver.write_blocking([1, 2, 3].into_iter()).unwrap();
+        assert_eq!(driver.spi_data().len(), apa102_encoded_len(1));
+        assert!(driver.write_blocking([0; 6].into_iter()).is_err());
+    }
+}
@@ -0,0 +1,629 @@
+#![cfg_attr(not(target_vendor = "espressif"), allow(dead_code))]
+
+use core::error::Error;
+use core::fmt;
+use core::marker::PhantomData;
+
+use super::buffer::{BufferFull, BufferStorage};
+use super::trace::trace_event;
+#[cfg(target_vendor = "espressif")]
+use super::trace::TraceError;
+use crate::codec::encode_bits;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(not(target_vendor = "espressif"))]
+use crate::mock::esp_idf_hal;
+#[cfg(target_vendor = "espressif")]
+use esp_idf_hal::{gpio::Pin, sys::*};
+use esp_idf_hal::{
+    gpio::{AnyOutputPin, OutputPin},
+    peripheral::Peripheral,
+};
+
+#[cfg(not(target_vendor = "espressif"))]
+use crate::mock::esp_idf_sys;
+use esp_idf_sys::EspError;
+
+#[cfg(all(target_vendor = "espressif", not(feature = "std")))]
+use alloc::boxed::Box;
+#[cfg(target_vendor = "espressif")]
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Pixel clock of the parallel bus in Hz, 3 clocks per WS2812 bit.
+pub const PARALLEL_CLOCK_HZ: u32 = 2_400_000;
+
+/// Returns the byte count of a bus word of the `lanes` lanes.
+#[inline]
+const fn word_len(lanes: usize) -> usize {
+    if lanes > 8 {
+        2
+    } else {
+        1
+    }
+}
+
+/// Returns the byte count of the bus data of the `lanes` lanes of `lane_len` bytes each.
+pub const fn parallel_encoded_len(lanes: usize, lane_len: usize) -> usize {
+    lane_len * 8 * 3 * word_len(lanes)
+}
+
+/// Encodes the pixel data of the lanes into the bus data, replacing the content of `buffer`.
+///
+/// Each WS2812 bit is sent as 3 bus words: all the lanes high, the bits of the lanes, and all the
+/// lanes low. The `n`-th lane is the `n`-th bit of the words, and the words are 8-bit for up to 8
+/// lanes and 16-bit little-endian for more. The lanes shorter than the longest one are padded
+/// with zeros, i.e. black.
+///
+/// # Errors
+///
+/// Returns an error if `buffer` cannot hold the bus data.
+pub fn encode_lanes<L, B>(lanes: &[L], buffer: &mut B) -> Result<(), BufferFull>
+where
+    L: AsRef<[u8]>,
+    B: BufferStorage + ?Sized,
+{
+    buffer.clear();
+    let len = lanes
+        .iter()
+        .map(|lane| lane.as_ref().len())
+        .max()
+        .unwrap_or(0);
+    let word_len = word_len(lanes.len());
+    let mask = (1u32 << lanes.len()) - 1;
+    buffer.reserve(parallel_encoded_len(lanes.len(), len));
+    for offset in 0..len {
+        let mut bits = [0u16; 8];
+        for (lane_index, lane) in lanes.iter().enumerate() {
+            let byte = lane.as_ref().get(offset).copied().unwrap_or(0);
+            for (bit, data) in bits.iter_mut().zip(encode_bits(core::iter::once(byte))) {
+                *bit |= u16::from(data) << lane_index;
+            }
+        }
+        for data in bits {
+            for word in [mask as u16, data, 0] {
+                buffer.extend_from_slice(&word.to_le_bytes()[..word_len])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// WS2812 ESP32 parallel Driver error.
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct Ws2812Esp32ParallelDriverError {
+    source: EspError,
+}
+
+#[cfg(not(feature = "std"))]
+impl Ws2812Esp32ParallelDriverError {
+    /// The `EspError` source of this error, if any.
+    ///
+    /// This is a workaround function until `core::error::Error` added to `esp_sys::EspError`.
+    pub fn source(&self) -> Option<&EspError> {
+        Some(&self.source)
+    }
+}
+
+impl Error for Ws2812Esp32ParallelDriverError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        #[cfg(feature = "std")]
+        {
+            Some(&self.source)
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Ws2812Esp32ParallelDriverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+impl From<EspError> for Ws2812Esp32ParallelDriverError {
+    fn from(source: EspError) -> Self {
+        Self { source }
+    }
+}
+
+impl From<BufferFull> for Ws2812Esp32ParallelDriverError {
+    fn from(_: BufferFull) -> Self {
+        #[cfg(target_vendor = "espressif")]
+        {
+            EspError::from_infallible::<ESP_ERR_NO_MEM>().into()
+        }
+        #[cfg(not(target_vendor = "espressif"))]
+        {
+            EspError().into()
+        }
+    }
+}
+
+/// Returns the error of a frame larger than the bus can transfer.
+fn too_large() -> EspError {
+    #[cfg(target_vendor = "espressif")]
+    {
+        EspError::from_infallible::<ESP_ERR_INVALID_SIZE>()
+    }
+    #[cfg(not(target_vendor = "espressif"))]
+    {
+        EspError()
+    }
+}
+
+/// Called by ESP-IDF when the bus data has been transferred.
+#[cfg(target_vendor = "espressif")]
+unsafe extern "C" fn on_trans_done(
+    _panel_io: esp_lcd_panel_io_handle_t,
+    _edata: *mut esp_lcd_panel_io_event_data_t,
+    user_ctx: *mut core::ffi::c_void,
+) -> bool {
+    (*(user_ctx as *const AtomicBool)).store(true, Ordering::Release);
+    false
+}
+
+/// WS2812 ESP32 parallel driver wrapper driving up to 16 LED strips at once.
+///
+/// The I2S (ESP32, ESP32-S2) or LCD (ESP32-S3) peripheral is driven as an Intel 8080 LCD bus of
+/// which each data pin drives a LED strip, so that all the strips are refreshed in the time of
+/// one. This is the way to refresh several thousand LED pixels at usable frame rates.
+///
+/// As a [`LedStripWrite`], the pixel-byte sequence is one framebuffer split evenly into the
+/// `LANES` strips from the first data pin, so that the wrappers such as
+/// [`LedPixelFrameBuffer`] drive all the strips as one strip of `LANES` times the pixels.
+/// The bus data is 24 times (8 lanes or less) or 48 times (more than 8 lanes) of the pixel data
+/// of a strip.
+///
+/// [`LedStripWrite`]: super::LedStripWrite
+/// [`LedPixelFrameBuffer`]: crate::framebuffer::LedPixelFrameBuffer
+///
+/// # Examples
+///
+/// ```
+/// #[cfg(not(target_vendor = "espressif"))]
+/// use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+///
+/// use esp_idf_hal::peripherals::Peripherals;
+/// use ws2812_esp32_rmt_driver::driver::Ws2812Esp32ParallelDriver;
+/// use ws2812_esp32_rmt_driver::driver::color::{LedPixelColor, LedPixelColorGrb24};
+/// use ws2812_esp32_rmt_driver::framebuffer::LedPixelFrameBuffer;
+///
+/// let peripherals = Peripherals::take().unwrap();
+/// let pins = peripherals.pins;
+/// let data_pins = [
+///     pins.gpio12.into(), pins.gpio13.into(), pins.gpio14.into(), pins.gpio15.into(),
+///     pins.gpio16.into(), pins.gpio17.into(), pins.gpio18.into(), pins.gpio19.into(),
+/// ];
+/// // 8 strips of 300 LED pixels
+/// let driver = Ws2812Esp32ParallelDriver::new(pins.gpio4, pins.gpio5, data_pins, 300 * 3).unwrap();
+/// let mut frame = LedPixelFrameBuffer::<LedPixelColorGrb24, Vec<u8>, _>::from_driver(driver, 8 * 300);
+///
+/// // The first LED pixel of the 2nd strip
+/// frame.set_pixel(300, LedPixelColorGrb24::new_with_rgb(30, 0, 0));
+/// frame.show().unwrap();
+/// ```
+pub struct Ws2812Esp32ParallelDriver<'d, const LANES: usize, B = Vec<u8>>
+where
+    B: BufferStorage,
+{
+    /// Intel 8080 LCD bus.
+    #[cfg(target_vendor = "espressif")]
+    bus: esp_lcd_i80_bus_handle_t,
+    /// Panel IO on the bus.
+    #[cfg(target_vendor = "espressif")]
+    io: esp_lcd_panel_io_handle_t,
+    /// Flag set when the bus data has been transferred.
+    #[cfg(target_vendor = "espressif")]
+    done: Box<AtomicBool>,
+    /// Data pins of the lanes
+    _pins: [AnyOutputPin; LANES],
+    /// Maximum byte count of the pixel data of a lane
+    max_lane_len: usize,
+    /// Bus data of the last frame
+    buffer: B,
+
+    /// Pixel binary arrays of the lanes to be written
+    ///
+    /// If the target vendor does not equals to "espressif", pixel data is written into this
+    /// instead of genuine bus.
+    #[cfg(not(target_vendor = "espressif"))]
+    pub pixel_data: Option<Vec<Vec<u8>>>,
+    /// Recording state of the mock, of the pixel data of the lanes in a row
+    #[cfg(not(target_vendor = "espressif"))]
+    recorder: crate::mock::recorder::FrameRecorder,
+    /// Dummy phantom to take care of lifetime for the clock pins.
+    phantom: PhantomData<&'d mut ()>,
+}
+
+impl<'d, const LANES: usize> Ws2812Esp32ParallelDriver<'d, LANES> {
+    /// Creates a WS2812 ESP32 parallel driver wrapper of the `LANES` strips, 1 to 16.
+    ///
+    /// The bus clock is output to `wr_pin` and the unused D/C signal to `dc_pin`; both are
+    /// not connected to the strips. `max_lane_len` is the maximum byte count of the pixel data
+    /// of a strip.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bus initialization failed.
+    pub fn new(
+        wr_pin: impl Peripheral<P = impl OutputPin> + 'd,
+        dc_pin: impl Peripheral<P = impl OutputPin> + 'd,
+        pins: [AnyOutputPin; LANES],
+        max_lane_len: usize,
+    ) -> Result<Self, Ws2812Esp32ParallelDriverError> {
+        Self::new_with_buffer(wr_pin, dc_pin, pins, max_lane_len, Vec::new())
+    }
+}
+
+impl<'d, const LANES: usize, B: BufferStorage> Ws2812Esp32ParallelDriver<'d, LANES, B> {
+    /// Creates a WS2812 ESP32 parallel driver wrapper of the `LANES` strips, encoding the frames
+    /// into `buffer`.
+    ///
+    /// `buffer` shall hold [`parallel_encoded_len()`] bytes of `LANES` lanes of
+    /// `max_lane_len` bytes; use [`InternalRamBuffer`] to keep it off PSRAM, or
+    /// [`SliceBuffer`] not to use heap for it.
+    ///
+    /// [`InternalRamBuffer`]: super::buffer::InternalRamBuffer
+    /// [`SliceBuffer`]: super::buffer::SliceBuffer
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bus initialization failed.
+    pub fn new_with_buffer(
+        wr_pin: impl Peripheral<P = impl OutputPin> + 'd,
+        dc_pin: impl Peripheral<P = impl OutputPin> + 'd,
+        pins: [AnyOutputPin; LANES],
+        max_lane_len: usize,
+        buffer: B,
+    ) -> Result<Self, Ws2812Esp32ParallelDriverError> {
+        const { assert!(LANES >= 1 && LANES <= 16, "LANES shall be 1 to 16") };
+
+        #[cfg(target_vendor = "espressif")]
+        {
+            let (mut wr_pin, mut dc_pin) = (wr_pin, dc_pin);
+            let mut bus_config = esp_lcd_i80_bus_config_t {
+                dc_gpio_num: unsafe { dc_pin.clone_unchecked() }.pin(),
+                wr_gpio_num: unsafe { wr_pin.clone_unchecked() }.pin(),
+                clk_src: soc_periph_lcd_clk_src_t_LCD_CLK_SRC_DEFAULT,
+                bus_width: word_len(LANES) * 8,
+                max_transfer_bytes: parallel_encoded_len(LANES, max_lane_len),
+                ..Default::default()
+            };
+            for (num, pin) in bus_config.data_gpio_nums.iter_mut().enumerate() {
+                *num = pins.get(num).map_or(-1, |pin| pin.pin());
+            }
+            let trace_error =
+                |e: &EspError| trace_event!(error, "parallel bus init failed: {}", TraceError(e));
+            let mut bus = core::ptr::null_mut();
+            esp!(unsafe { esp_lcd_new_i80_bus(&bus_config, &mut bus) }).inspect_err(trace_error)?;
+
+            let done = Box::new(AtomicBool::new(true));
+            let io_config = esp_lcd_panel_io_i80_config_t {
+                cs_gpio_num: -1,
+                pclk_hz: PARALLEL_CLOCK_HZ,
+                trans_queue_depth: 1,
+                on_color_trans_done: Some(on_trans_done),
+                user_ctx: &*done as *const AtomicBool as *mut _,
+                lcd_cmd_bits: 0,
+                lcd_param_bits: 0,
+                ..Default::default()
+            };
+            let mut io = core::ptr::null_mut();
+            if let Err(e) = esp!(unsafe { esp_lcd_new_panel_io_i80(bus, &io_config, &mut io) }) {
+                trace_error(&e);
+                unsafe { esp_lcd_del_i80_bus(bus) };
+                return Err(e.into());
+            }
+
+            trace_event!(debug, "parallel bus initialized with {} lanes", LANES);
+            Ok(Self {
+                bus,
+                io,
+                done,
+                _pins: pins,
+                max_lane_len,
+                buffer,
+                phantom: PhantomData,
+            })
+        }
+        #[cfg(not(target_vendor = "espressif"))] // Mock implement
+        {
+            let _ = (wr_pin, dc_pin);
+            trace_event!(debug, "parallel bus initialized with {} lanes", LANES);
+            Ok(Self {
+                _pins: pins,
+                max_lane_len,
+                buffer,
+                pixel_data: None,
+                recorder: Default::default(),
+                phantom: PhantomData,
+            })
+        }
+    }
+
+    /// Writes the pixel data of the lanes to the data pins at once.
+    ///
+    /// The lanes beyond `LANES` are ignored, and the missing ones are written black.
+    /// Byte count per LED pixel and channel order is not handled by this method.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a lane is longer than `max_lane_len` or if a bus error occurred.
+    pub fn write_lanes<L: AsRef<[u8]>>(
+        &mut self,
+        lanes: &[L],
+    ) -> Result<(), Ws2812Esp32ParallelDriverError> {
+        self.transmit(lanes)?;
+        #[cfg(target_vendor = "espressif")]
+        self.finish();
+        Ok(())
+    }
+
+    /// Writes the pixel data of the lanes to the data pins at once without waiting for the
+    /// transmission to complete.
+    ///
+    /// The bus data is encoded into the buffer owned by the driver and clocked out by DMA. The
+    /// next write waits for it before overwriting the buffer, and so does dropping the driver.
+    /// See [`Self::write_lanes()`] for the lanes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a lane is longer than `max_lane_len` or if a bus error occurred.
+    pub fn write_lanes_nonblocking<L: AsRef<[u8]>>(
+        &mut self,
+        lanes: &[L],
+    ) -> Result<(), Ws2812Esp32ParallelDriverError> {
+        self.transmit(lanes)
+    }
+
+    /// Encodes the lanes into the buffer and starts the transmission of the bus data.
+    fn transmit<L: AsRef<[u8]>>(
+        &mut self,
+        lanes: &[L],
+    ) -> Result<(), Ws2812Esp32ParallelDriverError> {
+        let lanes = &lanes[..lanes.len().min(LANES)];
+        let lane_len = lanes.iter().map(|lane| lane.as_ref().len()).max();
+        if let Some(lane_len) = lane_len.filter(|&len| len > self.max_lane_len) {
+            trace_event!(
+                warn,
+                "parallel frame overrun: lane of {} bytes exceeds {}",
+                lane_len,
+                self.max_lane_len
+            );
+            return Err(too_large().into());
+        }
+        trace_event!(trace, "parallel frame start");
+        let mut all = [&[][..]; LANES];
+        for (dst, lane) in all.iter_mut().zip(lanes) {
+            *dst = lane.as_ref();
+        }
+        #[cfg(target_vendor = "espressif")]
+        self.finish();
+        encode_lanes(&all, &mut self.buffer)?;
+
+        #[cfg(target_vendor = "espressif")]
+        {
+            self.done.store(false, Ordering::Release);
+            esp!(unsafe {
+                esp_lcd_panel_io_tx_color(
+                    self.io,
+                    -1,
+                    self.buffer.as_slice().as_ptr() as *const _,
+                    self.buffer.as_slice().len(),
+                )
+            })
+            .inspect_err(|e| {
+                self.done.store(true, Ordering::Release);
+                trace_event!(error, "parallel frame failed: {}", TraceError(e));
+            })?;
+        }
+        #[cfg(not(target_vendor = "espressif"))]
+        {
+            self.recorder
+                .record(all.iter().flat_map(|lane| lane.iter().copied()))?;
+            self.pixel_data = Some(all.iter().map(|lane| lane.to_vec()).collect());
+        }
+        trace_event!(
+            trace,
+            "parallel frame done: {} bus bytes",
+            self.buffer.as_slice().len()
+        );
+        Ok(())
+    }
+
+    /// Writes pixel data from a pixel-byte sequence split evenly into the lanes.
+    ///
+    /// The sequence is split into `LANES` lanes of the same byte count rounded up, so the last
+    /// lane is shorter if the byte count is not divisible by `LANES`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a lane is longer than `max_lane_len` or if a bus error occurred.
+    pub fn write_blocking<T>(
+        &mut self,
+        pixel_sequence: T,
+    ) -> Result<(), Ws2812Esp32ParallelDriverError>
+    where
+        T: Iterator<Item = u8>,
+    {
+        let data = pixel_sequence.collect::<Vec<_>>();
+        self.write_lanes(&split_lanes::<LANES>(&data))
+    }
+
+    /// Writes pixel data from a pixel-byte sequence split evenly into the lanes without waiting
+    /// for the transmission to complete.
+    ///
+    /// See [`Self::write_blocking()`] for the split, and [`Self::write_lanes_nonblocking()`] for
+    /// the transmission.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a lane is longer than `max_lane_len` or if a bus error occurred.
+    pub fn write<T>(&mut self, pixel_sequence: T) -> Result<(), Ws2812Esp32ParallelDriverError>
+    where
+        T: Iterator<Item = u8>,
+    {
+        let data = pixel_sequence.collect::<Vec<_>>();
+        self.write_lanes_nonblocking(&split_lanes::<LANES>(&data))
+    }
+
+    /// Waits until the frame in transmission, e.g. started by [`Self::write()`], has been sent.
+    ///
+    /// The data pins are left low after the frame, so the LED pixels latch it after the reset
+    /// time of the LED pixels.
+    pub fn latch(&mut self) {
+        #[cfg(target_vendor = "espressif")]
+        self.finish();
+    }
+
+    /// Waits for the bus data in transmission to be transferred.
+    #[cfg(target_vendor = "espressif")]
+    fn finish(&self) {
+        while !self.done.load(Ordering::Acquire) {
+            esp_idf_hal::task::do_yield();
+        }
+    }
+}
+
+/// Splits the pixel data into `LANES` lanes of the same byte count rounded up.
+fn split_lanes<const LANES: usize>(data: &[u8]) -> Vec<&[u8]> {
+    let lane_len = data.len().div_ceil(LANES).max(1);
+    data.chunks(lane_len).collect()
+}
+
+#[cfg(target_vendor = "espressif")]
+impl<const LANES: usize, B: BufferStorage> Drop for Ws2812Esp32ParallelDriver<'_, LANES, B> {
+    fn drop(&mut self) {
+        self.finish();
+        unsafe {
+            esp_lcd_panel_io_del(self.io);
+            esp_lcd_del_i80_bus(self.bus);
+        }
+    }
+}
+
+#[cfg(target_vendor = "espressif")]
+unsafe impl<const LANES: usize, B: BufferStorage + Send> Send
+    for Ws2812Esp32ParallelDriver<'_, LANES, B>
+{
+}
+
+#[cfg(not(target_vendor = "espressif"))]
+crate::mock::recorder::impl_frame_recording!(
+    {'d, const LANES: usize, B: BufferStorage} Ws2812Esp32ParallelDriver<'d, LANES, B>
+);
+
+#[cfg(not(target_vendor = "espressif"))]
+impl<const LANES: usize, B: BufferStorage> Ws2812Esp32ParallelDriver<'_, LANES, B> {
+    /// Returns the bus data of the last frame written.
+    ///
+    /// This function is only available in the mock.
+    pub fn bus_data(&self) -> &[u8] {
+        self.buffer.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::buffer::InternalRamBuffer;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+
+    #[test]
+    fn test_encode_lanes() {
+        let mut buffer = Vec::new();
+        encode_lanes(&[[0x80], [0x01]], &mut buffer).unwrap();
+        assert_eq!(
+            buffer,
+            [
+                3, 1, 0, 3, 0, 0, 3, 0, 0, 3, 0, 0, //
+                3, 0, 0, 3, 0, 0, 3, 0, 0, 3, 2, 0,
+            ]
+        );
+        assert_eq!(parallel_encoded_len(2, 1), buffer.len());
+
+        let lanes: [&[u8]; 9] = [&[0xFF], &[], &[], &[], &[], &[], &[], &[], &[0xFF, 0x00]];
+        encode_lanes(&lanes, &mut buffer).unwrap();
+        assert_eq!(buffer.len(), parallel_encoded_len(9, 2));
+        assert_eq!(buffer[..6], [0xFF, 0x01, 0x01, 0x01, 0x00, 0x00]);
+        assert_eq!(buffer[48..54], [0xFF, 0x01, 0x00, 0x00, 0x00, 0x00]);
+
+        let mut buffer = heapless::Vec::<u8, 24>::new();
+        assert_eq!(encode_lanes(&[[0x00; 2]], &mut buffer), Err(BufferFull));
+    }
+
+    #[test]
+    fn test_ws2812_esp32_parallel_driver() {
+        let peripherals = Peripherals::take().unwrap();
+        let pins = peripherals.pins;
+        let mut driver = Ws2812Esp32ParallelDriver::new(
+            pins.gpio0,
+            pins.gpio1,
+            [pins.gpio2.into(), pins.gpio3.into(), pins.gpio4.into()],
+            3,
+        )
+        .unwrap();
+
+        driver.write_blocking([1, 2, 3, 4, 5].into_iter()).unwrap();
+        assert_eq!(
+            driver.pixel_data,
+            Some(vec![vec![1, 2], vec![3, 4], vec![5]])
+        );
+        assert_eq!(driver.bus_data().len(), parallel_encoded_len(3, 2));
+
+        driver.write_lanes(&[[0xFF; 3]]).unwrap();
+        assert_eq!(driver.pixel_data, Some(vec![vec![0xFF; 3], vec![], vec![]]));
+        assert!(driver.write_lanes(&[[0xFF; 4]]).is_err());
+
+        driver.write([1, 2, 3, 4].into_iter()).unwrap();
+        assert_eq!(
+            driver.pixel_data,
+            Some(vec![vec![1, 2], vec![3, 4], vec![]])
+        );
+        driver.write_lanes_nonblocking(&[[0x01], [0x02]]).unwrap();
+        assert_eq!(
+            driver.pixel_data,
+            Some(vec![vec![0x01], vec![0x02], vec![]])
+        );
+    }
+
+    #[test]
+    fn test_ws2812_esp32_parallel_driver_recording() {
+        let peripherals = Peripherals::take().unwrap();
+        let pins = peripherals.pins;
+        let mut driver =
+            Ws2812Esp32ParallelDriver::new(pins.gpio0, pins.gpio1, [pins.gpio2.into()], 3).unwrap();
+
+        driver.record_history();
+        driver.write_lanes(&[[0x01, 0x02]]).unwrap();
+        driver.set_fail_after(1);
+        driver.write_lanes(&[[0x03]]).unwrap();
+        assert!(driver.write_lanes(&[[0x04]]).is_err());
+        assert_eq!(driver.pixel_data, Some(vec![vec![0x03]]));
+        assert_eq!(driver.frame_history(), [vec![0x01, 0x02], vec![0x03]]);
+    }
+
+    #[test]
+    fn test_ws2812_esp32_parallel_driver_with_buffer() {
+        let peripherals = Peripherals::take().unwrap();
+        let pins = peripherals.pins;
+        let buffer = InternalRamBuffer::with_capacity(parallel_encoded_len(2, 1)).unwrap();
+        let mut driver = Ws2812Esp32ParallelDriver::new_with_buffer(
+            pins.gpio0,
+            pins.gpio1,
+            [pins.gpio2.into(), pins.gpio3.into()],
+            2,
+            buffer,
+        )
+        .unwrap();
+
+        driver.write_lanes(&[[0x80], [0x01]]).unwrap();
+        assert_eq!(driver.bus_data().len(), parallel_encoded_len(2, 1));
+        assert!(driver.write_lanes(&[[0x80, 0x00], [0x01, 0x00]]).is_err());
+    }
+}
@@ -8,17 +8,28 @@ use core::time::Duration;
 #[cfg(not(target_vendor = "espressif"))]
 use core::marker::PhantomData;
 
+#[cfg(feature = "bench")]
+use super::bench::{FrameTiming, Stopwatch};
+use super::channel::TxRmtChannel;
+use super::diagnostics::DriverConfig;
+use super::power::CurrentModel;
+use super::timing::{
+    clock_divider, ChipTiming, IntoDuration, LedTiming, RmtBitTicks, DEFAULT_RESOLUTION_HZ,
+};
+use super::trace::{trace_event, TraceError};
+#[cfg(all(not(feature = "std"), feature = "alloc", target_vendor = "espressif"))]
+use alloc::vec::Vec;
+
+#[cfg(target_vendor = "espressif")]
+use crate::codec::{self, encode_bits};
 #[cfg(not(target_vendor = "espressif"))]
 use crate::mock::esp_idf_hal;
+#[cfg(target_vendor = "espressif")]
+use esp_idf_hal::rmt::{PinState, Pulse, PulseTicks, Symbol};
 use esp_idf_hal::{
     gpio::OutputPin,
     peripheral::Peripheral,
-    rmt::{config::TransmitConfig, RmtChannel, TxRmtDriver},
-};
-#[cfg(target_vendor = "espressif")]
-use esp_idf_hal::{
-    rmt::{PinState, Pulse, Symbol},
-    units::Hertz,
+    rmt::{config::TransmitConfig, TxRmtDriver},
 };
 
 #[cfg(not(target_vendor = "espressif"))]
@@ -26,48 +37,59 @@ use crate::mock::esp_idf_sys;
 use esp_idf_sys::EspError;
 
 /// T0H duration time (0 code, high voltage time)
-const WS2812_T0H_NS: Duration = Duration::from_nanos(400);
+pub(crate) const WS2812_T0H_NS: Duration = Duration::from_nanos(400);
 /// T0L duration time (0 code, low voltage time)
-const WS2812_T0L_NS: Duration = Duration::from_nanos(850);
+pub(crate) const WS2812_T0L_NS: Duration = Duration::from_nanos(850);
 /// T1H duration time (1 code, high voltage time)
-const WS2812_T1H_NS: Duration = Duration::from_nanos(800);
+pub(crate) const WS2812_T1H_NS: Duration = Duration::from_nanos(800);
 /// T1L duration time (1 code, low voltage time)
-const WS2812_T1L_NS: Duration = Duration::from_nanos(450);
+pub(crate) const WS2812_T1L_NS: Duration = Duration::from_nanos(450);
 
 /// Converter to a sequence of RMT items.
+///
+/// The items are the ones of [`codec::Encoder`], converted once into the RMT items of the driver.
 #[repr(C)]
 #[cfg(target_vendor = "espressif")]
-struct Ws2812Esp32RmtItemEncoder {
+pub(super) struct Ws2812Esp32RmtItemEncoder {
     /// The RMT item that represents a 0 code.
     bit0: Symbol,
     /// The RMT item that represents a 1 code.
     bit1: Symbol,
+    /// The RMT item of the reset code ending a frame, if any.
+    reset: Option<Symbol>,
 }
 
 #[cfg(target_vendor = "espressif")]
 impl Ws2812Esp32RmtItemEncoder {
-    /// Creates a new encoder with the given clock frequency.
+    /// Creates a new encoder with the given pulse ticks.
     ///
     /// # Arguments
     ///
-    /// * `clock_hz` - The clock frequency.
+    /// * `ticks` - The pulse ticks at the counter clock of the RMT channel.
+    /// * `reset_ticks` - The ticks of the reset code at the counter clock, up to two pulses, or 0
+    ///   not to end the frames with it.
     ///
     /// # Errors
     ///
-    /// Returns an error if the clock frequency is invalid or if the RMT item encoder cannot be created.
-    fn new(clock_hz: Hertz) -> Result<Self, EspError> {
-        let (bit0, bit1) = (
-            Symbol::new(
-                Pulse::new_with_duration(clock_hz, PinState::High, &WS2812_T0H_NS)?,
-                Pulse::new_with_duration(clock_hz, PinState::Low, &WS2812_T0L_NS)?,
-            ),
-            Symbol::new(
-                Pulse::new_with_duration(clock_hz, PinState::High, &WS2812_T1H_NS)?,
-                Pulse::new_with_duration(clock_hz, PinState::Low, &WS2812_T1L_NS)?,
-            ),
-        );
+    /// Returns an error if the ticks are invalid.
+    fn new(ticks: &RmtBitTicks, reset_ticks: u32) -> Result<Self, EspError> {
+        let encoder = codec::Encoder::from_ticks(ticks, reset_ticks);
+        let pulse = |level: bool, ticks: u16| -> Result<Pulse, EspError> {
+            let pin_state = if level { PinState::High } else { PinState::Low };
+            Ok(Pulse::new(pin_state, PulseTicks::new(ticks)?))
+        };
+        let symbol = |symbol: codec::Symbol| -> Result<Symbol, EspError> {
+            Ok(Symbol::new(
+                pulse(symbol.level0, symbol.duration0)?,
+                pulse(symbol.level1, symbol.duration1)?,
+            ))
+        };
 
-        Ok(Self { bit0, bit1 })
+        Ok(Self {
+            bit0: symbol(encoder.bit0)?,
+            bit1: symbol(encoder.bit1)?,
+            reset: encoder.reset.map(symbol).transpose()?,
+        })
     }
 
     /// Encodes a block of data as a sequence of RMT items.
@@ -80,28 +102,93 @@ impl Ws2812Esp32RmtItemEncoder {
     ///
     /// An iterator over the RMT items that represent the encoded data.
     #[inline]
-    fn encode_iter<'a, 'b, T>(&'a self, src: T) -> impl Iterator<Item = Symbol> + Send + 'a
+    pub(super) fn encode_iter<'a, 'b, T>(
+        &'a self,
+        src: T,
+    ) -> impl Iterator<Item = Symbol> + Send + 'a
     where
         'b: 'a,
         T: Iterator<Item = u8> + Send + 'b,
     {
-        src.flat_map(move |v| {
-            (0..(u8::BITS as usize)).map(move |i| {
-                if v & (1 << (7 - i)) != 0 {
-                    self.bit1
-                } else {
-                    self.bit0
-                }
-            })
-        })
+        encode_bits(src).map(move |bit| if bit { self.bit1 } else { self.bit0 })
+    }
+
+    /// Encodes a frame of data as a sequence of RMT items, ending with the reset code.
+    #[inline]
+    pub(super) fn encode_frame<'a, 'b, T>(
+        &'a self,
+        src: T,
+    ) -> impl Iterator<Item = Symbol> + Send + 'a
+    where
+        'b: 'a,
+        T: Iterator<Item = u8> + Send + 'b,
+    {
+        self.encode_iter(src).chain(self.reset)
     }
 }
 
 /// WS2812 ESP32 RMT Driver error.
+///
+/// Each variant tells the operation that failed, along with the underlying `EspError` and the
+/// context of the failure.
 #[derive(Debug)]
-#[repr(transparent)]
-pub struct Ws2812Esp32RmtDriverError {
-    source: EspError,
+#[non_exhaustive]
+pub enum Ws2812Esp32RmtDriverError {
+    /// The RMT driver initialization failed.
+    Init(EspError),
+    /// The transmission of a frame failed.
+    Transmit {
+        /// Byte count of the pixel data consumed before the failure.
+        ///
+        /// It is 0 if the transmission failed to start.
+        byte_offset: usize,
+        /// Underlying error
+        source: EspError,
+    },
+    /// The RMT RX of the loopback self-test failed.
+    Receive(EspError),
+    /// Suspending or resuming the output for light sleep failed, or the driver is suspended.
+    Suspend(EspError),
+    /// The frame exceeded the cap of the byte count per frame.
+    ///
+    /// The frame is rejected before the transmission if its length is known to exceed the cap,
+    /// e.g. of an infinite iterator; otherwise the transmission stops at the cap.
+    FrameTooLong {
+        /// Cap of the byte count per frame
+        max_len: usize,
+        /// Underlying error
+        source: EspError,
+    },
+    /// The RMT channel is busy with the transmission in progress, and the write would block.
+    WouldBlock(EspError),
+}
+
+impl Ws2812Esp32RmtDriverError {
+    /// Returns the underlying `EspError`.
+    pub fn esp_error(&self) -> &EspError {
+        match self {
+            Self::Init(source)
+            | Self::Transmit { source, .. }
+            | Self::Receive(source)
+            | Self::Suspend(source)
+            | Self::FrameTooLong { source, .. }
+            | Self::WouldBlock(source) => source,
+        }
+    }
+
+    /// Returns the byte offset of the pixel data where the transmission failed, if it failed.
+    pub fn byte_offset(&self) -> Option<usize> {
+        match self {
+            Self::Transmit { byte_offset, .. } => Some(*byte_offset),
+            _ => None,
+        }
+    }
+
+    /// Returns the index of the LED pixel of `bpp` bytes where the transmission failed, if it
+    /// failed.
+    pub fn pixel_index(&self, bpp: usize) -> Option<usize> {
+        self.byte_offset().map(|offset| offset / bpp)
+    }
 }
 
 #[cfg(not(feature = "std"))]
@@ -110,7 +197,7 @@ impl Ws2812Esp32RmtDriverError {
     ///
     /// This is a workaround function until `core::error::Error` added to `esp_sys::EspError`.
     pub fn source(&self) -> Option<&EspError> {
-        Some(&self.source)
+        Some(self.esp_error())
     }
 }
 
@@ -118,7 +205,7 @@ impl Error for Ws2812Esp32RmtDriverError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         #[cfg(feature = "std")]
         {
-            Some(&self.source)
+            Some(self.esp_error())
         }
         #[cfg(not(feature = "std"))]
         {
@@ -129,13 +216,217 @@ impl Error for Ws2812Esp32RmtDriverError {
 
 impl fmt::Display for Ws2812Esp32RmtDriverError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.source.fmt(f)
+        match self {
+            Self::Init(source) => write!(f, "RMT driver init failed: {}", source),
+            Self::Transmit {
+                byte_offset,
+                source,
+            } => write!(f, "RMT transmit failed at byte {}: {}", byte_offset, source),
+            Self::Receive(source) => write!(f, "RMT receive failed: {}", source),
+            Self::Suspend(source) => write!(f, "RMT suspend failed: {}", source),
+            Self::FrameTooLong { max_len, source } => write!(
+                f,
+                "RMT frame exceeds the cap of {} bytes: {}",
+                max_len, source
+            ),
+            Self::WouldBlock(source) => write!(f, "RMT channel is busy: {}", source),
+        }
+    }
+}
+
+/// Returns the transmit error at `byte_offset`, tracing it.
+fn transmit_error(byte_offset: usize, source: EspError) -> Ws2812Esp32RmtDriverError {
+    let e = Ws2812Esp32RmtDriverError::Transmit {
+        byte_offset,
+        source,
+    };
+    trace_event!(error, "RMT frame failed: {}", TraceError(&e));
+    e
+}
+
+/// Returns the initialization error, tracing it.
+fn init_error(source: EspError) -> Ws2812Esp32RmtDriverError {
+    let e = Ws2812Esp32RmtDriverError::Init(source);
+    trace_event!(error, "{}", TraceError(&e));
+    e
+}
+
+/// Returns the error of the frame exceeding `max_len` bytes, tracing it.
+pub(super) fn frame_too_long_error(max_len: usize) -> Ws2812Esp32RmtDriverError {
+    #[cfg(target_vendor = "espressif")]
+    let source = EspError::from_infallible::<{ esp_idf_sys::ESP_ERR_INVALID_SIZE }>();
+    #[cfg(not(target_vendor = "espressif"))]
+    let source = EspError();
+    let e = Ws2812Esp32RmtDriverError::FrameTooLong { max_len, source };
+    trace_event!(error, "{}", TraceError(&e));
+    e
+}
+
+/// Sets the clock divider of the RMT channel of `tx`.
+#[cfg(target_vendor = "espressif")]
+fn set_clock_divider(tx: &mut TxRmtDriver<'_>, divider: u8) -> Result<(), EspError> {
+    esp_idf_sys::esp!(unsafe { esp_idf_sys::rmt_set_clk_div(tx.channel(), divider) })
+}
+
+/// Sets the clock divider of the RMT channel of `tx`.
+#[cfg(not(target_vendor = "espressif"))]
+fn set_clock_divider(tx: &mut TxRmtDriver<'_>, divider: u8) -> Result<(), EspError> {
+    tx.set_clock_divider(divider)
+}
+
+/// Returns the error of the invalid argument.
+fn invalid_arg_error() -> EspError {
+    #[cfg(target_vendor = "espressif")]
+    {
+        EspError::from_infallible::<{ esp_idf_sys::ESP_ERR_INVALID_ARG }>()
+    }
+    #[cfg(not(target_vendor = "espressif"))]
+    {
+        EspError()
     }
 }
 
-impl From<EspError> for Ws2812Esp32RmtDriverError {
-    fn from(source: EspError) -> Self {
-        Self { source }
+/// Returns the error of the invalid state.
+pub(super) fn invalid_state_error() -> EspError {
+    #[cfg(target_vendor = "espressif")]
+    {
+        EspError::from_infallible::<{ esp_idf_sys::ESP_ERR_INVALID_STATE }>()
+    }
+    #[cfg(not(target_vendor = "espressif"))]
+    {
+        EspError()
+    }
+}
+
+/// Returns the error of the unsupported configuration.
+fn not_supported_error() -> EspError {
+    #[cfg(target_vendor = "espressif")]
+    {
+        EspError::from_infallible::<{ esp_idf_sys::ESP_ERR_NOT_SUPPORTED }>()
+    }
+    #[cfg(not(target_vendor = "espressif"))]
+    {
+        EspError()
+    }
+}
+
+/// Configuration of [`Ws2812Esp32RmtDriver`] built by the builder methods.
+///
+/// # Examples
+///
+/// ```
+/// #[cfg(not(target_vendor = "espressif"))]
+/// use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+///
+/// use core::time::Duration;
+/// use esp_idf_hal::peripherals::Peripherals;
+/// use ws2812_esp32_rmt_driver::driver::timing::LedTiming;
+/// use ws2812_esp32_rmt_driver::driver::{Ws2812Esp32RmtDriver, Ws2812Esp32RmtDriverConfig};
+///
+/// let config = Ws2812Esp32RmtDriverConfig::new()
+///     .with_timing(LedTiming::SK6812)
+///     .with_reset(Duration::from_micros(300))
+///     .with_mem_block_num(2);
+///
+/// let peripherals = Peripherals::take().unwrap();
+/// let driver = Ws2812Esp32RmtDriver::new_with_config(
+///     peripherals.rmt.channel0,
+///     peripherals.pins.gpio27,
+///     &config,
+/// )
+/// .unwrap();
+/// assert_eq!(driver.config().mem_block_num, 2);
+/// assert_eq!(driver.timing().reset, Duration::from_micros(300));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ws2812Esp32RmtDriverConfig {
+    /// Signal timing of the LED pixels, including the reset code ending the frames
+    pub timing: LedTiming,
+    /// Counter clock frequency requested, unless `clock_divider` is given
+    pub resolution_hz: u32,
+    /// Clock divider of the RMT channel, overriding `resolution_hz`
+    pub clock_divider: Option<u8>,
+    /// Number of the memory blocks of the RMT channel
+    ///
+    /// The channel takes the blocks of the following channels, which shall not be used then.
+    pub mem_block_num: u8,
+    /// Whether the RMT channel transmits by DMA
+    ///
+    /// The legacy RMT driver has no DMA; it is rejected by
+    /// [`Ws2812Esp32RmtDriver::new_with_config()`].
+    pub dma: bool,
+}
+
+impl Ws2812Esp32RmtDriverConfig {
+    /// Creates the default configuration: WS2812 timing at [`DEFAULT_RESOLUTION_HZ`] on a
+    /// memory block without DMA.
+    pub const fn new() -> Self {
+        Self {
+            timing: LedTiming::WS2812,
+            resolution_hz: DEFAULT_RESOLUTION_HZ,
+            clock_divider: None,
+            mem_block_num: 1,
+            dma: false,
+        }
+    }
+
+    /// Returns the configuration with the signal timing.
+    pub fn with_timing(mut self, timing: LedTiming) -> Self {
+        self.timing = timing;
+        self
+    }
+
+    /// Returns the configuration with the signal timing of the chip preset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ws2812_esp32_rmt_driver::driver::timing::ChipTiming;
+    /// use ws2812_esp32_rmt_driver::driver::Ws2812Esp32RmtDriverConfig;
+    ///
+    /// let config = Ws2812Esp32RmtDriverConfig::new().with_chip(ChipTiming::Ws2811LowSpeed);
+    /// assert_eq!(config.timing, ChipTiming::Ws2811LowSpeed.timing());
+    /// ```
+    pub fn with_chip(self, chip: ChipTiming) -> Self {
+        self.with_timing(chip.timing())
+    }
+
+    /// Returns the configuration with the reset code of `reset`.
+    pub fn with_reset(mut self, reset: impl IntoDuration) -> Self {
+        self.timing = self.timing.with_reset(reset);
+        self
+    }
+
+    /// Returns the configuration with the counter clock nearest to `resolution_hz`.
+    pub fn with_resolution(mut self, resolution_hz: u32) -> Self {
+        self.resolution_hz = resolution_hz;
+        self.clock_divider = None;
+        self
+    }
+
+    /// Returns the configuration with the clock divider of the RMT channel.
+    pub fn with_clock_divider(mut self, clock_divider: u8) -> Self {
+        self.clock_divider = Some(clock_divider);
+        self
+    }
+
+    /// Returns the configuration with the number of the memory blocks of the RMT channel.
+    pub fn with_mem_block_num(mut self, mem_block_num: u8) -> Self {
+        self.mem_block_num = mem_block_num;
+        self
+    }
+
+    /// Returns the configuration with DMA on or off.
+    pub fn with_dma(mut self, dma: bool) -> Self {
+        self.dma = dma;
+        self
+    }
+}
+
+impl Default for Ws2812Esp32RmtDriverConfig {
+    /// Returns [`Ws2812Esp32RmtDriverConfig::new()`]
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -165,10 +456,41 @@ impl From<EspError> for Ws2812Esp32RmtDriverError {
 /// ```
 pub struct Ws2812Esp32RmtDriver<'d> {
     /// TxRMT driver.
-    tx: TxRmtDriver<'d>,
+    pub(super) tx: TxRmtDriver<'d>,
+    /// RMT items of the frame in transmission by [`Self::write()`]
+    ///
+    /// It is declared after `tx`, so that the RMT driver is uninstalled, waiting for the
+    /// transmission, before this is dropped.
+    #[cfg(all(feature = "alloc", target_vendor = "espressif"))]
+    staging: Vec<Symbol>,
+    /// Source clock frequency of the RMT channel
+    pub(super) source_clock_hz: u32,
+    /// Clock divider of the RMT channel
+    clock_divider: u8,
+    /// Counter clock frequency of the RMT channel
+    pub(super) counter_clock_hz: u32,
+    /// Number of the memory blocks of the RMT channel
+    pub(super) mem_block_num: u8,
+    /// Signal timing of the LED pixels
+    timing: LedTiming,
+    /// GPIO number of the data line
+    gpio: i32,
+    /// Whether the output is suspended by [`Self::suspend()`]
+    pub(super) suspended: bool,
     /// `u8`-to-`rmt_item32_t` Encoder
     #[cfg(target_vendor = "espressif")]
-    encoder: Ws2812Esp32RmtItemEncoder,
+    pub(super) encoder: Ws2812Esp32RmtItemEncoder,
+    /// Timing of the last frame written by `write_blocking()`
+    #[cfg(feature = "bench")]
+    frame_timing: FrameTiming,
+    /// Current draw model for the telemetry
+    pub(super) current_model: CurrentModel,
+    /// Sum of the channel values of the last frame written by `write_blocking()`
+    pub(super) channel_sum: u64,
+    /// Byte count of the last frame written by `write_blocking()`
+    pub(super) frame_len: usize,
+    /// Cap of the byte count per frame set by [`Self::set_max_frame_len()`]
+    pub(super) max_frame_len: Option<usize>,
 
     /// Pixel binary array to be written
     ///
@@ -176,6 +498,9 @@ pub struct Ws2812Esp32RmtDriver<'d> {
     /// instead of genuine encoder.
     #[cfg(not(target_vendor = "espressif"))]
     pub pixel_data: Option<Vec<u8>>,
+    /// Recording state: error injection, strip length assertion and virtual timeline
+    #[cfg(not(target_vendor = "espressif"))]
+    pub(crate) recorder: crate::mock::recorder::FrameRecorder,
     /// Dummy phantom to take care of lifetime for `pixel_data`.
     #[cfg(not(target_vendor = "espressif"))]
     phantom: PhantomData<&'d Option<Vec<u8>>>,
@@ -185,37 +510,319 @@ impl<'d> Ws2812Esp32RmtDriver<'d> {
     /// Creates a WS2812 ESP32 RMT driver wrapper.
     ///
     /// RMT driver of `channel` shall be initialized and installed for `pin`.
-    /// `channel` shall be different between different `pin`.
+    /// `channel` shall be different between different `pin`, and shall be capable of TX on the
+    /// chip; see [`TxRmtChannel`].
     ///
     /// # Errors
     ///
     /// Returns an error if the RMT driver initialization failed.
-    pub fn new<C: RmtChannel>(
+    pub fn new<C: TxRmtChannel>(
+        channel: impl Peripheral<P = C> + 'd,
+        pin: impl Peripheral<P = impl OutputPin> + 'd,
+    ) -> Result<Self, Ws2812Esp32RmtDriverError> {
+        Self::new_with_resolution(channel, pin, DEFAULT_RESOLUTION_HZ)
+    }
+
+    /// Creates a WS2812 ESP32 RMT driver wrapper with the counter resolution nearest to
+    /// `resolution_hz`.
+    ///
+    /// The clock divider is derived from the source clock of the RMT channel, and the pulse
+    /// ticks from the resulting counter clock; see [`Self::counter_clock_hz()`].
+    /// [`Self::new()`] requests [`DEFAULT_RESOLUTION_HZ`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the RMT driver initialization failed, or the counter clock is too
+    /// coarse or too fine for the pulses.
+    pub fn new_with_resolution<C: TxRmtChannel>(
         channel: impl Peripheral<P = C> + 'd,
         pin: impl Peripheral<P = impl OutputPin> + 'd,
+        resolution_hz: u32,
     ) -> Result<Self, Ws2812Esp32RmtDriverError> {
+        let config = Ws2812Esp32RmtDriverConfig::new().with_resolution(resolution_hz);
+        Self::new_with_config(channel, pin, &config)
+    }
+
+    /// Creates a WS2812 ESP32 RMT driver wrapper with `config`.
+    ///
+    /// The clock divider is `config.clock_divider` if given, or derived from the source clock
+    /// of the RMT channel to the counter clock nearest to `config.resolution_hz`. The frames end
+    /// with the reset code of `config.timing`, so that the frames written back to back latch.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the RMT driver initialization failed, `config.mem_block_num` is 0,
+    /// `config.dma` is set, or the counter clock is too coarse or too fine for the pulses.
+    pub fn new_with_config<C: TxRmtChannel>(
+        channel: impl Peripheral<P = C> + 'd,
+        mut pin: impl Peripheral<P = impl OutputPin> + 'd,
+        config: &Ws2812Esp32RmtDriverConfig,
+    ) -> Result<Self, Ws2812Esp32RmtDriverError> {
+        if config.dma {
+            return Err(init_error(not_supported_error()));
+        }
+        if config.mem_block_num == 0 {
+            return Err(init_error(invalid_arg_error()));
+        }
+        // SAFETY: the clone is used only to read the GPIO number.
+        let gpio = unsafe { pin.clone_unchecked() }.pin();
+        let tx_config = TransmitConfig::new()
+            .clock_divider(1)
+            .mem_block_num(config.mem_block_num);
+        let mut tx = TxRmtDriver::new(channel, pin, &tx_config).map_err(init_error)?;
+        trace_event!(debug, "RMT driver initialized on channel {}", C::CHANNEL);
+
+        let source_clock_hz = tx.counter_clock().map_err(init_error)?.0;
+        let divider = config
+            .clock_divider
+            .unwrap_or_else(|| clock_divider(source_clock_hz, config.resolution_hz));
+        if divider != 1 {
+            set_clock_divider(&mut tx, divider).map_err(init_error)?;
+        }
+        let counter_clock_hz = tx.counter_clock().map_err(init_error)?.0;
+        trace_event!(
+            debug,
+            "RMT counter clock {} Hz (source {} Hz / {})",
+            counter_clock_hz,
+            source_clock_hz,
+            divider
+        );
+        let timing = config.timing;
+        let ticks = RmtBitTicks::new(counter_clock_hz, &timing)
+            .ok_or_else(|| init_error(invalid_arg_error()))?;
+
         #[cfg(target_vendor = "espressif")]
         {
-            let config = TransmitConfig::new().clock_divider(1);
-            let tx = TxRmtDriver::new(channel, pin, &config)?;
-
-            let clock_hz = tx.counter_clock()?;
-            let encoder = Ws2812Esp32RmtItemEncoder::new(clock_hz)?;
+            let reset_ticks = RmtBitTicks::reset_ticks(counter_clock_hz, &timing);
+            let encoder =
+                Ws2812Esp32RmtItemEncoder::new(&ticks, reset_ticks).map_err(init_error)?;
 
-            Ok(Self { tx, encoder })
+            Ok(Self {
+                tx,
+                #[cfg(feature = "alloc")]
+                staging: Vec::new(),
+                source_clock_hz,
+                clock_divider: divider,
+                counter_clock_hz,
+                mem_block_num: config.mem_block_num,
+                timing,
+                gpio,
+                suspended: false,
+                encoder,
+                #[cfg(feature = "bench")]
+                frame_timing: Default::default(),
+                current_model: CurrentModel::WS2812,
+                channel_sum: 0,
+                frame_len: 0,
+                max_frame_len: None,
+            })
         }
         #[cfg(not(target_vendor = "espressif"))] // Mock implement
         {
-            let config = TransmitConfig::new();
-            let tx = TxRmtDriver::new(channel, pin, &config)?;
+            let _ = ticks;
             Ok(Self {
                 tx,
+                source_clock_hz,
+                clock_divider: divider,
+                counter_clock_hz,
+                mem_block_num: config.mem_block_num,
+                timing,
+                gpio,
+                suspended: false,
                 pixel_data: None,
+                recorder: Default::default(),
+                #[cfg(feature = "bench")]
+                frame_timing: Default::default(),
+                current_model: CurrentModel::WS2812,
+                channel_sum: 0,
+                frame_len: 0,
+                max_frame_len: None,
                 phantom: Default::default(),
             })
         }
     }
 
+    /// Returns the counter clock frequency of the RMT channel, i.e. the resolution of the
+    /// pulses.
+    #[inline]
+    pub fn counter_clock_hz(&self) -> u32 {
+        self.counter_clock_hz
+    }
+
+    /// Suspends the output before light sleep.
+    ///
+    /// It waits for the transmission in progress, stops the RMT channel and holds the data line
+    /// low, so that the LED pixels keep their colors and do not latch the noise while the chip
+    /// sleeps. The writes fail until [`Self::resume()`]. It does nothing if already suspended.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the RMT driver or the GPIO failed to be suspended.
+    pub fn suspend(&mut self) -> Result<(), Ws2812Esp32RmtDriverError> {
+        if self.suspended {
+            return Ok(());
+        }
+        #[cfg(target_vendor = "espressif")]
+        {
+            use esp_idf_sys::{esp, gpio_hold_en, portMAX_DELAY, rmt_wait_tx_done};
+
+            let suspend_error = Ws2812Esp32RmtDriverError::Suspend;
+            esp!(unsafe { rmt_wait_tx_done(self.tx.channel(), portMAX_DELAY) })
+                .map_err(suspend_error)?;
+            self.tx.stop().map_err(suspend_error)?;
+            esp!(unsafe { gpio_hold_en(self.gpio) }).map_err(suspend_error)?;
+        }
+        self.suspended = true;
+        trace_event!(debug, "RMT output suspended on GPIO {}", self.gpio);
+        Ok(())
+    }
+
+    /// Resumes the output suspended by [`Self::suspend()`] after light sleep.
+    ///
+    /// It releases the data line to the RMT channel. It does nothing if not suspended.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the GPIO failed to be released.
+    pub fn resume(&mut self) -> Result<(), Ws2812Esp32RmtDriverError> {
+        if !self.suspended {
+            return Ok(());
+        }
+        #[cfg(target_vendor = "espressif")]
+        {
+            use esp_idf_sys::{esp, gpio_hold_dis};
+
+            esp!(unsafe { gpio_hold_dis(self.gpio) })
+                .map_err(Ws2812Esp32RmtDriverError::Suspend)?;
+        }
+        self.suspended = false;
+        trace_event!(debug, "RMT output resumed on GPIO {}", self.gpio);
+        Ok(())
+    }
+
+    /// Returns the effective configuration of this driver.
+    ///
+    /// See [`DriverConfig`] to attach the strip length, the brightness and the gamma.
+    pub fn config(&self) -> DriverConfig {
+        DriverConfig {
+            channel: self.tx.channel(),
+            gpio: self.gpio,
+            source_clock_hz: self.source_clock_hz,
+            clock_divider: self.clock_divider,
+            counter_clock_hz: self.counter_clock_hz,
+            timing: self.timing(),
+            ticks: RmtBitTicks::new(self.counter_clock_hz, &self.timing()),
+            mem_block_num: self.mem_block_num,
+            frame_len: self.frame_len,
+            suspended: self.suspended,
+            strip: None,
+            brightness: None,
+            gamma: None,
+        }
+    }
+
+    /// Returns the effective configuration of this driver, tracing it at `info` level.
+    ///
+    /// See [`Self::config()`].
+    pub fn dump(&self) -> DriverConfig {
+        let config = self.config();
+        trace_event!(info, "RMT driver configuration:\n{}", TraceError(&config));
+        config
+    }
+
+    /// Sets the cap of the byte count per frame, or removes it by `None`.
+    ///
+    /// The writes of the frames longer than `max_len` fail with
+    /// [`Ws2812Esp32RmtDriverError::FrameTooLong`], so that an unbounded pixel sequence, e.g.
+    /// a forgotten `take()` of a `cycle()`, does not hang the device in the interrupt handler
+    /// feeding the RMT. The frames over the cap are rejected before the transmission: the
+    /// frames whose length is not known to fit by `size_hint()` are staged up to the cap first.
+    /// Without `alloc` feature, such frames are transmitted up to the cap and then rejected
+    /// instead. There is no cap by default.
+    #[inline]
+    pub fn set_max_frame_len(&mut self, max_len: Option<usize>) {
+        self.max_frame_len = max_len;
+    }
+
+    /// Returns the cap of the byte count per frame set by [`Self::set_max_frame_len()`].
+    #[inline]
+    pub fn max_frame_len(&self) -> Option<usize> {
+        self.max_frame_len
+    }
+
+    /// Returns an error if `pixel_sequence` is known to exceed the cap of the byte count per
+    /// frame, and the cap otherwise.
+    fn check_frame_len<T: Iterator>(
+        &self,
+        pixel_sequence: &T,
+    ) -> Result<usize, Ws2812Esp32RmtDriverError> {
+        let max_len = self.max_frame_len.unwrap_or(usize::MAX);
+        if pixel_sequence.size_hint().0 > max_len {
+            return Err(frame_too_long_error(max_len));
+        }
+        Ok(max_len)
+    }
+
+    /// Stages `pixel_sequence` up to the cap of the byte count per frame, unless `size_hint()`
+    /// tells that it fits, so that the frame over the cap is rejected before the transmission.
+    ///
+    /// Returns `None` if the frame needs no staging.
+    #[cfg(any(feature = "alloc", not(target_vendor = "espressif")))]
+    fn stage_frame<T: Iterator<Item = u8>>(
+        &self,
+        pixel_sequence: &mut T,
+    ) -> Result<Option<Vec<u8>>, Ws2812Esp32RmtDriverError> {
+        let Some(max_len) = self.max_frame_len else {
+            return Ok(None);
+        };
+        if pixel_sequence
+            .size_hint()
+            .1
+            .is_some_and(|len| len <= max_len)
+        {
+            return Ok(None);
+        }
+        let staged = pixel_sequence
+            .take(max_len.saturating_add(1))
+            .collect::<Vec<_>>();
+        if staged.len() > max_len {
+            return Err(frame_too_long_error(max_len));
+        }
+        Ok(Some(staged))
+    }
+
+    /// Returns whether the output is suspended by [`Self::suspend()`].
+    #[inline]
+    pub fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+
+    /// Returns an error if the output is suspended.
+    fn ensure_resumed(&self) -> Result<(), Ws2812Esp32RmtDriverError> {
+        if self.suspended {
+            let e = Ws2812Esp32RmtDriverError::Suspend(invalid_state_error());
+            trace_event!(error, "{}", TraceError(&e));
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Sets the current draw model used by [`Self::last_frame_current_ma()`].
+    ///
+    /// The default is [`CurrentModel::WS2812`].
+    pub fn set_current_model(&mut self, model: CurrentModel) {
+        self.current_model = model;
+    }
+
+    /// Returns the estimated current draw of the last frame written by
+    /// [`Self::write_blocking()`] in milliamperes.
+    ///
+    /// See [`CurrentModel::estimate_ma()`] for details.
+    #[inline]
+    pub fn last_frame_current_ma(&self) -> u32 {
+        self.current_model.channel_sum_ma(self.channel_sum)
+    }
+
     /// Writes pixel data from a pixel-byte sequence to the IO pin.
     ///
     /// Byte count per LED pixel and channel order is not handled by this method.
@@ -223,7 +830,8 @@ impl<'d> Ws2812Esp32RmtDriver<'d> {
     ///
     /// # Errors
     ///
-    /// Returns an error if an RMT driver error occurred.
+    /// Returns an error if an RMT driver error occurred, or `pixel_sequence` exceeded the cap of
+    /// the byte count per frame.
     ///
     /// # Warning
     ///
@@ -238,51 +846,720 @@ impl<'d> Ws2812Esp32RmtDriver<'d> {
         'b: 'a,
         T: Iterator<Item = u8> + Send + 'b,
     {
+        self.ensure_resumed()?;
+        let max_len = self.check_frame_len(&pixel_sequence)?;
+        #[cfg(any(feature = "alloc", not(target_vendor = "espressif")))]
+        let mut pixel_sequence = pixel_sequence;
+        #[cfg(any(feature = "alloc", not(target_vendor = "espressif")))]
+        if let Some(staged) = self.stage_frame(&mut pixel_sequence)? {
+            return self.write_blocking(staged.into_iter());
+        }
+        trace_event!(trace, "RMT frame start");
+        let (mut channel_sum, mut len, mut overflow) = (0u64, 0usize, false);
+        let pixel_sequence = pixel_sequence.take_while(|&v| {
+            if len == max_len {
+                overflow = true;
+                return false;
+            }
+            channel_sum += u64::from(v);
+            len += 1;
+            true
+        });
+        #[cfg(all(target_vendor = "espressif", not(feature = "bench")))]
+        {
+            let signal = self.encoder.encode_frame(pixel_sequence);
+            self.tx
+                .start_iter_blocking(signal)
+                .map_err(|source| transmit_error(len, source))?;
+        }
+        #[cfg(all(target_vendor = "espressif", feature = "bench"))]
+        {
+            let stopwatch = Stopwatch::start();
+            let signal = self
+                .encoder
+                .encode_frame(pixel_sequence)
+                .collect::<Vec<_>>();
+            let encode = stopwatch.elapsed();
+            self.tx
+                .start_iter_blocking(signal.into_iter())
+                .map_err(|source| transmit_error(len, source))?;
+            self.frame_timing = FrameTiming {
+                encode,
+                transmit: stopwatch.elapsed().saturating_sub(encode),
+            };
+        }
+        #[cfg(not(target_vendor = "espressif"))]
+        {
+            #[cfg(feature = "bench")]
+            let stopwatch = Stopwatch::start();
+            self.write_mock(pixel_sequence)?;
+            #[cfg(feature = "bench")]
+            {
+                self.frame_timing = FrameTiming {
+                    encode: stopwatch.elapsed(),
+                    transmit: Duration::ZERO,
+                };
+            }
+        }
+        self.channel_sum = channel_sum;
+        self.frame_len = len;
+        if overflow {
+            return Err(frame_too_long_error(max_len));
+        }
+        trace_event!(trace, "RMT frame done: {} bytes", len);
+        Ok(())
+    }
+
+    /// Writes the pixel data in place from `data`, e.g. a `static` scene in the flash, and
+    /// blocks until the transmission completes.
+    ///
+    /// The pixel data is read by the encoder a chunk of the RMT memory at a time during the
+    /// transmission, and is never copied into RAM, even with `bench` feature, where
+    /// [`Self::write_blocking()`] buffers the encoded frame; thus a scene of thousands of LED
+    /// pixels takes no heap. `data` is laid out as for [`Self::write_blocking()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an RMT driver error occurred, or `data` exceeds the cap of the byte
+    /// count per frame.
+    ///
+    /// # Warning
+    ///
+    /// `data` is read inside the interrupt handler feeding the RMT. If the handler is placed in
+    /// IRAM (`CONFIG_RMT_ISR_IRAM_SAFE`), it runs while the flash cache is disabled, and `data`
+    /// in the flash is unreadable then; pause the writes during the flash operations by
+    /// `flash_safe` instead, or keep `data` in RAM.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[cfg(not(target_vendor = "espressif"))]
+    /// use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+    ///
+    /// use esp_idf_hal::peripherals::Peripherals;
+    /// use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+    ///
+    /// // 2000 LED pixels of GRB, placed in the flash
+    /// static SCENE: [u8; 6000] = [0x10; 6000];
+    ///
+    /// let peripherals = Peripherals::take().unwrap();
+    /// let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+    /// driver.write_static(&SCENE).unwrap();
+    /// assert_eq!(driver.pixel_data.as_deref(), Some(&SCENE[..]));
+    /// ```
+    pub fn write_static(&mut self, data: &'static [u8]) -> Result<(), Ws2812Esp32RmtDriverError> {
+        self.ensure_resumed()?;
+        let max_len = self.max_frame_len.unwrap_or(usize::MAX);
+        if data.len() > max_len {
+            return Err(frame_too_long_error(max_len));
+        }
+        trace_event!(trace, "RMT static frame start");
         #[cfg(target_vendor = "espressif")]
         {
-            let signal = self.encoder.encode_iter(pixel_sequence);
-            self.tx.start_iter_blocking(signal)?;
+            let mut len = 0;
+            let signal = self
+                .encoder
+                .encode_frame(data.iter().copied().inspect(|_| len += 1));
+            self.tx
+                .start_iter_blocking(signal)
+                .map_err(|source| transmit_error(len, source))?;
         }
         #[cfg(not(target_vendor = "espressif"))]
         {
-            self.pixel_data = Some(pixel_sequence.collect());
+            self.write_mock(data.iter().copied())?;
         }
+        self.channel_sum = data.iter().map(|&v| u64::from(v)).sum();
+        self.frame_len = data.len();
+        trace_event!(trace, "RMT static frame done: {} bytes", data.len());
         Ok(())
     }
 
-    /// Writes pixel data from a pixel-byte sequence to the IO pin.
+    /// Writes pixel data from a pixel-byte sequence, and returns the future completing when the
+    /// transmission completes.
+    ///
+    /// The frame is encoded into the RMT items before the transmission starts, and the future
+    /// is woken by the TX end interrupt of the RMT channel, so the task awaiting it does not
+    /// block the others meanwhile. The TX end callback of the legacy RMT driver is shared by all
+    /// the channels and is replaced at the first call; do not register another one by
+    /// `rmt_register_tx_end_callback()`. Dropping the future before the completion blocks until
+    /// the end of the transmission.
+    ///
+    /// In the mock, the returned future completes immediately unless the virtual clock is
+    /// attached. If attached, it completes when the virtual clock reaches the end of the
+    /// transmission on the virtual timeline. See `mock::time` for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an RMT driver error occurred, or `pixel_sequence` exceeded the cap of
+    /// the byte count per frame.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[cfg(not(target_vendor = "espressif"))]
+    /// use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+    ///
+    /// use esp_idf_hal::peripherals::Peripherals;
+    /// use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+    ///
+    /// async fn blink(driver: &mut Ws2812Esp32RmtDriver<'_>) {
+    ///     driver.write_async([0, 30, 0].into_iter()).await.unwrap();
+    ///     // e.g. `Timer::after_millis(500).await` of embassy-time
+    ///     driver.write_async([0, 0, 0].into_iter()).await.unwrap();
+    /// }
+    ///
+    /// let peripherals = Peripherals::take().unwrap();
+    /// let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+    /// # #[cfg(not(target_vendor = "espressif"))]
+    /// # {
+    /// # use core::future::Future;
+    /// # use core::task::{Context, RawWaker, RawWakerVTable, Waker};
+    /// # const VTABLE: RawWakerVTable = RawWakerVTable::new(
+    /// #     |_| RawWaker::new(core::ptr::null(), &VTABLE),
+    /// #     |_| {},
+    /// #     |_| {},
+    /// #     |_| {},
+    /// # );
+    /// # let waker = unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &VTABLE)) };
+    /// # let mut cx = Context::from_waker(&waker);
+    /// # let mut future = core::pin::pin!(blink(&mut driver));
+    /// # assert!(future.as_mut().poll(&mut cx).is_ready());
+    /// # }
+    /// ```
+    #[cfg(any(feature = "alloc", not(target_vendor = "espressif")))]
+    pub async fn write_async<T>(
+        &mut self,
+        pixel_sequence: T,
+    ) -> Result<(), Ws2812Esp32RmtDriverError>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        self.ensure_resumed()?;
+        let max_len = self.check_frame_len(&pixel_sequence)?;
+        trace_event!(trace, "RMT async frame start");
+        let (mut channel_sum, mut len, mut overflow) = (0u64, 0usize, false);
+        let pixel_sequence = pixel_sequence.take_while(|&v| {
+            if len == max_len {
+                overflow = true;
+                return false;
+            }
+            channel_sum += u64::from(v);
+            len += 1;
+            true
+        });
+        #[cfg(target_vendor = "espressif")]
+        let symbols = self
+            .encoder
+            .encode_frame(pixel_sequence)
+            .collect::<Vec<_>>();
+        #[cfg(not(target_vendor = "espressif"))]
+        let pixel_data = pixel_sequence.collect::<Vec<_>>();
+        self.channel_sum = channel_sum;
+        self.frame_len = len;
+        if overflow {
+            return Err(frame_too_long_error(max_len));
+        }
+        #[cfg(not(target_vendor = "espressif"))]
+        self.store_mock(pixel_data.into_iter())?;
+
+        #[cfg(target_vendor = "espressif")]
+        {
+            super::tx_done::Transmission::start(self.tx.channel(), symbols)
+                .map_err(|source| transmit_error(0, source))?
+                .await;
+        }
+        #[cfg(not(target_vendor = "espressif"))]
+        if let Some((clock, end)) = self.recorder.timeline.schedule(len) {
+            crate::mock::time::VirtualTimer::new(clock, end).await;
+        }
+        trace_event!(trace, "RMT async frame done: {} bytes", len);
+        Ok(())
+    }
+
+    /// Returns the signal timing of the LED pixels driven by this driver.
+    ///
+    /// Use it to estimate the frame duration and the maximum frame rate, e.g.
+    /// `driver.timing().max_fps(pixel_len, bpp)`.
+    #[inline]
+    pub const fn timing(&self) -> LedTiming {
+        self.timing
+    }
+
+    /// Returns the encoding and transmission time of the last frame written by
+    /// [`Self::write_blocking()`].
+    ///
+    /// To measure them separately, `write_blocking()` encodes the whole frame into a buffer before
+    /// the transmission while `bench` feature is enabled.
+    #[cfg(feature = "bench")]
+    #[inline]
+    pub fn last_frame_timing(&self) -> FrameTiming {
+        self.frame_timing
+    }
+
+    /// Writes pixel data from a pixel-byte sequence to the IO pin without waiting for the
+    /// transmission to complete.
     ///
     /// Byte count per LED pixel and channel order is not handled by this method.
     /// The pixel data sequence has to be correctly laid out depending on the LED strip model.
     ///
-    /// Note that this requires `pixel_sequence` to be [`Box`]ed for an allocation free version see [`Self::write_blocking`].
+    /// The frame is encoded into the staging buffer owned by the driver before the transmission
+    /// starts, so `pixel_sequence` may borrow the data of the caller. The buffer is reused by
+    /// the next frame, and the next write waits for the transmission in progress before
+    /// overwriting it. Dropping the driver waits for the transmission as well.
+    ///
+    /// The frame over the cap of [`Self::set_max_frame_len()`] is not transmitted at all, as it
+    /// is staged as a whole before the transmission starts.
     ///
     /// # Errors
     ///
-    /// Returns an error if an RMT driver error occurred.
+    /// Returns an error if an RMT driver error occurred, or `pixel_sequence` exceeded the cap of
+    /// the byte count per frame.
     ///
-    /// # Warning
+    /// # Examples
     ///
-    /// Iteration of `pixel_sequence` happens inside an interrupt handler so beware of side-effects
-    /// that don't work in interrupt handlers.
-    /// See [esp_idf_hal::rmt::TxRmtDriver#start_iter()] for details.
+    /// ```
+    /// #[cfg(not(target_vendor = "espressif"))]
+    /// use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+    ///
+    /// use esp_idf_hal::peripherals::Peripherals;
+    /// use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+    ///
+    /// struct Lamp<'d> {
+    ///     driver: Ws2812Esp32RmtDriver<'d>,
+    ///     pixels: [u8; 6],
+    /// }
+    ///
+    /// let peripherals = Peripherals::take().unwrap();
+    /// let driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+    /// let mut lamp = Lamp { driver, pixels: [0x20; 6] };
+    ///
+    /// lamp.driver.write(lamp.pixels.iter().copied()).unwrap();
+    /// // Render the next frame meanwhile.
+    /// lamp.pixels = [0x40; 6];
+    /// # assert_eq!(lamp.driver.pixel_data, Some(vec![0x20; 6]));
+    /// ```
     #[cfg(feature = "alloc")]
-    pub fn write<'b, T>(
-        &'static mut self,
-        pixel_sequence: T,
-    ) -> Result<(), Ws2812Esp32RmtDriverError>
+    pub fn write<T>(&mut self, pixel_sequence: T) -> Result<(), Ws2812Esp32RmtDriverError>
     where
-        T: Iterator<Item = u8> + Send + 'static,
+        T: Iterator<Item = u8> + Send,
     {
+        self.ensure_resumed()?;
+        let max_len = self.check_frame_len(&pixel_sequence)?;
+        let (mut len, mut overflow) = (0usize, false);
+        let pixel_sequence = pixel_sequence.take_while(|_| {
+            if len == max_len {
+                overflow = true;
+                return false;
+            }
+            len += 1;
+            true
+        });
         #[cfg(target_vendor = "espressif")]
         {
-            let signal = self.encoder.encode_iter(pixel_sequence);
-            self.tx.start_iter(signal)?;
+            use esp_idf_sys::{esp, portMAX_DELAY, rmt_wait_tx_done, rmt_write_items};
+
+            let channel = self.tx.channel();
+            esp!(unsafe { rmt_wait_tx_done(channel, portMAX_DELAY) })
+                .map_err(|source| transmit_error(0, source))?;
+            self.staging.clear();
+            self.staging
+                .extend(self.encoder.encode_frame(pixel_sequence));
+            if overflow {
+                self.staging.clear();
+                return Err(frame_too_long_error(max_len));
+            }
+            // SAFETY: `Symbol` is a transparent wrapper of `rmt_item32_t`, and the staging buffer
+            // is untouched until the end of the transmission as described above.
+            esp!(unsafe {
+                rmt_write_items(
+                    channel,
+                    self.staging.as_ptr().cast(),
+                    self.staging.len() as i32,
+                    false,
+                )
+            })
+            .map_err(|source| transmit_error(0, source))?;
         }
         #[cfg(not(target_vendor = "espressif"))]
         {
-            self.pixel_data = Some(pixel_sequence.collect());
+            let staging = pixel_sequence.collect::<Vec<_>>();
+            if overflow {
+                return Err(frame_too_long_error(max_len));
+            }
+            self.write_mock(staging.into_iter())?;
         }
         Ok(())
     }
+
+    /// Waits until the frame in transmission, e.g. started by [`Self::write()`], has been
+    /// latched by the LED pixels.
+    ///
+    /// The reset code is transmitted as a part of the frame, so the LED pixels show the frame
+    /// when this returns.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an RMT driver error occurred.
+    pub fn latch(&mut self) -> Result<(), Ws2812Esp32RmtDriverError> {
+        #[cfg(target_vendor = "espressif")]
+        {
+            use esp_idf_sys::{esp, portMAX_DELAY, rmt_wait_tx_done};
+
+            esp!(unsafe { rmt_wait_tx_done(self.tx.channel(), portMAX_DELAY) })
+                .map_err(|source| transmit_error(0, source))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_vendor = "espressif"))]
+crate::mock::recorder::impl_frame_recording!({'d} Ws2812Esp32RmtDriver<'d>);
+
+#[cfg(not(target_vendor = "espressif"))]
+impl Ws2812Esp32RmtDriver<'_> {
+    /// Writes the pixel data to the mock.
+    fn write_mock<T>(&mut self, pixel_sequence: T) -> Result<(), Ws2812Esp32RmtDriverError>
+    where
+        T: Iterator<Item = u8>,
+    {
+        let len = self.store_mock(pixel_sequence)?;
+        self.recorder.timeline.transmit(len);
+        Ok(())
+    }
+
+    /// Stores the pixel data into [`Self::pixel_data`] and returns its byte count.
+    fn store_mock<T>(&mut self, pixel_sequence: T) -> Result<usize, Ws2812Esp32RmtDriverError>
+    where
+        T: Iterator<Item = u8>,
+    {
+        let mut byte_offset = 0;
+        let pixel_sequence = pixel_sequence.inspect(|_| byte_offset += 1);
+        let pixel_data = self
+            .recorder
+            .record(pixel_sequence)
+            .map_err(|source| transmit_error(byte_offset, source))?;
+        let len = pixel_data.len();
+        self.pixel_data = Some(pixel_data);
+        Ok(len)
+    }
+
+    /// Returns the mock of the TxRMT driver, which records the channel, the pin and the
+    /// configuration used at construction.
+    ///
+    /// This function is only available in the mock.
+    pub fn tx_driver(&self) -> &TxRmtDriver<'_> {
+        &self.tx
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+
+    #[test]
+    fn test_ws2812_esp32_rmt_driver_resolution() {
+        let peripherals = Peripherals::take().unwrap();
+        let driver =
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+        assert_eq!(driver.counter_clock_hz(), 10_000_000);
+        assert_eq!(driver.tx.config().clock_divider, 8);
+
+        let driver = Ws2812Esp32RmtDriver::new_with_resolution(
+            peripherals.rmt.channel1,
+            peripherals.pins.gpio1,
+            40_000_000,
+        )
+        .unwrap();
+        assert_eq!(driver.counter_clock_hz(), 40_000_000);
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_driver_resolution_too_coarse() {
+        let peripherals = Peripherals::take().unwrap();
+        assert!(Ws2812Esp32RmtDriver::new_with_resolution(
+            peripherals.rmt.channel0,
+            peripherals.pins.gpio0,
+            1_000_000,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_driver_with_config() {
+        let peripherals = Peripherals::take().unwrap();
+        let config = Ws2812Esp32RmtDriverConfig::new()
+            .with_clock_divider(2)
+            .with_mem_block_num(4)
+            .with_timing(LedTiming::SK6812);
+        let driver = Ws2812Esp32RmtDriver::new_with_config(
+            peripherals.rmt.channel0,
+            peripherals.pins.gpio0,
+            &config,
+        )
+        .unwrap();
+        assert_eq!(driver.counter_clock_hz(), 40_000_000);
+        assert_eq!(driver.tx.config().mem_block_num, 4);
+        assert_eq!(driver.config().timing, LedTiming::SK6812);
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_driver_with_invalid_config() {
+        let peripherals = Peripherals::take().unwrap();
+        let config = Ws2812Esp32RmtDriverConfig::new();
+
+        // No DMA on the legacy RMT driver
+        assert!(Ws2812Esp32RmtDriver::new_with_config(
+            peripherals.rmt.channel0,
+            peripherals.pins.gpio0,
+            &config.with_dma(true),
+        )
+        .is_err());
+        assert!(Ws2812Esp32RmtDriver::new_with_config(
+            peripherals.rmt.channel1,
+            peripherals.pins.gpio1,
+            &config.with_mem_block_num(0),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_driver_suspend() {
+        let peripherals = Peripherals::take().unwrap();
+        let mut driver =
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+        driver.write_blocking([1, 2, 3].into_iter()).unwrap();
+
+        driver.suspend().unwrap();
+        driver.suspend().unwrap();
+        assert!(driver.is_suspended());
+        let e = driver.write_blocking([4, 5, 6].into_iter()).unwrap_err();
+        assert!(matches!(e, Ws2812Esp32RmtDriverError::Suspend(_)));
+        assert_eq!(driver.pixel_data, Some(vec![1, 2, 3]));
+
+        driver.resume().unwrap();
+        assert!(!driver.is_suspended());
+        driver.write_blocking([4, 5, 6].into_iter()).unwrap();
+        assert_eq!(driver.pixel_data, Some(vec![4, 5, 6]));
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_driver_error_injection() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        driver.inject_error(EspError());
+        let e = driver.write_blocking([0x01].into_iter()).unwrap_err();
+        assert!(matches!(
+            e,
+            Ws2812Esp32RmtDriverError::Transmit { byte_offset: 0, .. }
+        ));
+        assert_eq!(driver.pixel_data, None);
+        driver.write_blocking([0x02].into_iter()).unwrap();
+        assert_eq!(driver.pixel_data, Some(vec![0x02]));
+
+        driver.set_fail_after(2);
+        driver.write_blocking([0x03].into_iter()).unwrap();
+        driver.write_blocking([0x04].into_iter()).unwrap();
+        assert!(driver.write_blocking([0x05].into_iter()).is_err());
+        assert_eq!(driver.pixel_data, Some(vec![0x04]));
+        driver.write_blocking([0x06].into_iter()).unwrap();
+        assert_eq!(driver.pixel_data, Some(vec![0x06]));
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_driver_error_context() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        driver.inject_error_at(742 * 3 + 1, EspError());
+        let e = driver
+            .write_blocking(core::iter::repeat_n(0x00, 900 * 3))
+            .unwrap_err();
+        assert_eq!(e.byte_offset(), Some(2227));
+        assert_eq!(e.pixel_index(3), Some(742));
+        assert_eq!(e.esp_error(), &EspError());
+        assert_eq!(e.to_string(), "RMT transmit failed at byte 2227: EspError");
+
+        let e = Ws2812Esp32RmtDriverError::Init(EspError());
+        assert_eq!(e.byte_offset(), None);
+        assert_eq!(e.to_string(), "RMT driver init failed: EspError");
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_driver_max_frame_len() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+        driver.set_max_frame_len(Some(6));
+
+        driver.write_blocking([0x01; 6].into_iter()).unwrap();
+        assert_eq!(driver.pixel_data, Some(vec![0x01; 6]));
+
+        // Known to exceed: rejected before the transmission
+        let e = driver.write_blocking(core::iter::repeat(0x02)).unwrap_err();
+        assert!(matches!(
+            e,
+            Ws2812Esp32RmtDriverError::FrameTooLong { max_len: 6, .. }
+        ));
+        assert_eq!(
+            e.to_string(),
+            "RMT frame exceeds the cap of 6 bytes: EspError"
+        );
+        assert_eq!(driver.pixel_data, Some(vec![0x01; 6]));
+
+        // Unknown length: staged and rejected before the transmission
+        let e = driver
+            .write_blocking((0..).map(|_| 0x03).filter(|_| true))
+            .unwrap_err();
+        assert!(matches!(e, Ws2812Esp32RmtDriverError::FrameTooLong { .. }));
+        assert_eq!(driver.pixel_data, Some(vec![0x01; 6]));
+        driver
+            .write_blocking([0x03; 8].into_iter().filter(|&v| v != 0))
+            .unwrap_err();
+        assert_eq!(driver.pixel_data, Some(vec![0x01; 6]));
+        driver
+            .write_blocking([0x03; 6].into_iter().filter(|&v| v != 0))
+            .unwrap();
+        assert_eq!(driver.pixel_data, Some(vec![0x03; 6]));
+
+        driver.set_max_frame_len(None);
+        driver.write_blocking([0x04; 9].into_iter()).unwrap();
+        assert_eq!(driver.max_frame_len(), None);
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_driver_write_static() {
+        static SCENE: [u8; 9] = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        driver.write_static(&SCENE).unwrap();
+        assert_eq!(driver.pixel_data, Some(SCENE.to_vec()));
+        assert_eq!(driver.config().frame_len, 9);
+        assert_eq!(driver.last_frame_current_ma(), 4);
+
+        driver.set_max_frame_len(Some(6));
+        assert!(matches!(
+            driver.write_static(&SCENE),
+            Err(Ws2812Esp32RmtDriverError::FrameTooLong { max_len: 6, .. })
+        ));
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_driver_write() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        let mut pixels = [1, 2, 3];
+        driver.write(pixels.iter().copied()).unwrap();
+        pixels.fill(0);
+        driver.latch().unwrap();
+        assert_eq!(driver.pixel_data, Some(vec![1, 2, 3]));
+
+        driver.set_max_frame_len(Some(3));
+        assert!(matches!(
+            driver.write([0; 6].into_iter()),
+            Err(Ws2812Esp32RmtDriverError::FrameTooLong { max_len: 3, .. })
+        ));
+
+        // Unbounded: not truncated but rejected before the transmission
+        assert!(matches!(
+            driver.write([4, 5].into_iter().cycle()),
+            Err(Ws2812Esp32RmtDriverError::FrameTooLong { max_len: 3, .. })
+        ));
+        assert!(matches!(
+            driver.write([4, 5].into_iter().cycle().filter(|_| true)),
+            Err(Ws2812Esp32RmtDriverError::FrameTooLong { max_len: 3, .. })
+        ));
+        assert_eq!(driver.pixel_data, Some(vec![1, 2, 3]));
+        driver.write([4, 5, 6].into_iter()).unwrap();
+        assert_eq!(driver.pixel_data, Some(vec![4, 5, 6]));
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_driver_expect_strip_len() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        driver.expect_strip_len(2, 3);
+        driver.write_blocking([0x00; 6].into_iter()).unwrap();
+        assert_eq!(driver.pixel_data, Some(vec![0x00; 6]));
+    }
+
+    #[test]
+    #[should_panic(expected = "written frame shall be 2 pixels x 3 bytes")]
+    fn test_ws2812_esp32_rmt_driver_expect_strip_len_mismatch() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        driver.expect_strip_len(2, 3);
+        driver.write_blocking([0x00; 8].into_iter()).unwrap();
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_driver_frame_history() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        driver.write_blocking([0x01].into_iter()).unwrap();
+        assert!(driver.frame_history().is_empty());
+
+        driver.record_history();
+        driver.write_blocking([0x02].into_iter()).unwrap();
+        driver.inject_error(EspError());
+        assert!(driver.write_blocking([0x03].into_iter()).is_err());
+        driver.write_blocking([0x04, 0x05].into_iter()).unwrap();
+        assert_eq!(driver.frame_history(), [vec![0x02], vec![0x04, 0x05]]);
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn test_ws2812_esp32_rmt_driver_frame_timing() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        assert_eq!(driver.last_frame_timing(), FrameTiming::default());
+        driver.write_blocking([0x00; 300].into_iter()).unwrap();
+        let timing = driver.last_frame_timing();
+        assert_eq!(timing.transmit, Duration::ZERO);
+        assert_eq!(timing.total(), timing.encode);
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_driver_current() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        assert_eq!(driver.last_frame_current_ma(), 0);
+        driver.write_blocking([0xFF; 30].into_iter()).unwrap();
+        assert_eq!(driver.last_frame_current_ma(), 600);
+
+        driver.set_current_model(CurrentModel { channel_ma: 10 });
+        assert_eq!(driver.last_frame_current_ma(), 300);
+
+        driver.set_fail_after(0);
+        assert!(driver.write_blocking([0x00; 30].into_iter()).is_err());
+        assert_eq!(driver.last_frame_current_ma(), 300);
+    }
 }
@@ -0,0 +1,465 @@
+#![cfg_attr(not(target_vendor = "espressif"), allow(dead_code))]
+
+use core::error::Error;
+use core::fmt;
+use core::marker::PhantomData;
+
+use super::buffer::{BufferFull, BufferStorage};
+use super::memory::RMT_MEM_BLOCK_SYMBOLS;
+use super::timing::{LedTiming, RmtBitTicks, DEFAULT_RESOLUTION_HZ};
+use super::trace::{trace_event, TraceError};
+use super::Ws2812Esp32RmtDriverConfig;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(not(target_vendor = "espressif"))]
+use crate::mock::esp_idf_hal;
+#[cfg(target_vendor = "espressif")]
+use esp_idf_hal::sys::*;
+use esp_idf_hal::{gpio::OutputPin, peripheral::Peripheral};
+
+#[cfg(not(target_vendor = "espressif"))]
+use crate::mock::esp_idf_sys;
+use esp_idf_sys::EspError;
+
+/// WS2812 ESP32 RMT TX Driver error.
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct Ws2812Esp32RmtTxDriverError {
+    source: EspError,
+}
+
+#[cfg(not(feature = "std"))]
+impl Ws2812Esp32RmtTxDriverError {
+    /// The `EspError` source of this error, if any.
+    ///
+    /// This is a workaround function until `core::error::Error` added to `esp_sys::EspError`.
+    pub fn source(&self) -> Option<&EspError> {
+        Some(&self.source)
+    }
+}
+
+impl Error for Ws2812Esp32RmtTxDriverError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        #[cfg(feature = "std")]
+        {
+            Some(&self.source)
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Ws2812Esp32RmtTxDriverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+impl From<EspError> for Ws2812Esp32RmtTxDriverError {
+    fn from(source: EspError) -> Self {
+        Self { source }
+    }
+}
+
+impl From<BufferFull> for Ws2812Esp32RmtTxDriverError {
+    fn from(_: BufferFull) -> Self {
+        #[cfg(target_vendor = "espressif")]
+        {
+            EspError::from_infallible::<{ ESP_ERR_NO_MEM }>().into()
+        }
+        #[cfg(not(target_vendor = "espressif"))]
+        {
+            EspError().into()
+        }
+    }
+}
+
+/// Returns the `EspError` of the configuration not applicable to the `rmt_tx` API.
+fn not_supported_error() -> EspError {
+    #[cfg(target_vendor = "espressif")]
+    {
+        EspError::from_infallible::<ESP_ERR_NOT_SUPPORTED>()
+    }
+    #[cfg(not(target_vendor = "espressif"))]
+    {
+        EspError()
+    }
+}
+
+/// Returns the `EspError` of the pulses not representable at the resolution.
+fn invalid_arg_error() -> EspError {
+    #[cfg(target_vendor = "espressif")]
+    {
+        EspError::from_infallible::<ESP_ERR_INVALID_ARG>()
+    }
+    #[cfg(not(target_vendor = "espressif"))]
+    {
+        EspError()
+    }
+}
+
+/// Returns the RMT symbol of a high pulse of `high` ticks followed by a low pulse of `low` ticks.
+#[cfg(target_vendor = "espressif")]
+fn symbol(high: u16, low: u16) -> rmt_symbol_word_t {
+    rmt_symbol_word_t {
+        val: u32::from(high & 0x7FFF) | 1 << 15 | u32::from(low & 0x7FFF) << 16,
+    }
+}
+
+/// WS2812 ESP32 RMT driver wrapper on the `rmt_tx` API of ESP-IDF v5.
+///
+/// It drives the LED strip by an RMT TX channel allocated by `rmt_new_tx_channel()` and the
+/// bytes encoder of `rmt_new_bytes_encoder()`, instead of the legacy RMT driver deprecated in
+/// ESP-IDF v5, which [`Ws2812Esp32RmtDriver`] is built on. The channel is chosen by ESP-IDF, so
+/// only the pin is given.
+///
+/// This crate enables the `rmt-legacy` feature of `esp-idf-hal` for [`Ws2812Esp32RmtDriver`],
+/// and ESP-IDF aborts at the boot if a firmware links both the legacy and the new RMT drivers
+/// unless `CONFIG_RMT_SKIP_LEGACY_CONFLICT_CHECK` is set. A firmware using this wrapper shall
+/// not create [`Ws2812Esp32RmtDriver`] nor the RMT driver of `esp-idf-hal`, e.g. by the `new()`
+/// of the APIs on the driver; create the APIs by `from_driver()` of this wrapper instead.
+///
+/// The frame is copied into a buffer before the transmission, as the bytes encoder reads it
+/// during the transmission. The buffer is allocated by the global allocator unless another
+/// [`BufferStorage`] is given by [`Self::new_with_buffer()`].
+///
+/// [`Ws2812Esp32RmtDriver`]: super::Ws2812Esp32RmtDriver
+///
+/// # Examples
+///
+/// ```
+/// #[cfg(not(target_vendor = "espressif"))]
+/// use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+///
+/// use esp_idf_hal::peripherals::Peripherals;
+/// use ws2812_esp32_rmt_driver::driver::Ws2812Esp32RmtTxDriver;
+/// use ws2812_esp32_rmt_driver::driver::color::{LedPixelColor, LedPixelColorGrb24};
+/// use ws2812_esp32_rmt_driver::framebuffer::LedPixelFrameBuffer;
+///
+/// let peripherals = Peripherals::take().unwrap();
+/// let driver = Ws2812Esp32RmtTxDriver::new(peripherals.pins.gpio27).unwrap();
+///
+/// let mut frame = LedPixelFrameBuffer::<LedPixelColorGrb24, Vec<u8>, _>::from_driver(driver, 25);
+/// frame.fill(LedPixelColorGrb24::new_with_rgb(30, 0, 0));
+/// frame.show().unwrap();
+/// ```
+pub struct Ws2812Esp32RmtTxDriver<'d, B = Vec<u8>>
+where
+    B: BufferStorage,
+{
+    /// RMT TX channel
+    #[cfg(target_vendor = "espressif")]
+    channel: rmt_channel_handle_t,
+    /// Bytes encoder of the WS2812 bits
+    #[cfg(target_vendor = "espressif")]
+    encoder: rmt_encoder_handle_t,
+    /// Counter clock frequency of the RMT channel
+    resolution_hz: u32,
+    /// Signal timing of the LED pixels
+    timing: LedTiming,
+    /// GPIO number of the data line
+    gpio: i32,
+    /// Pixel data of the frame in transmission
+    buffer: B,
+
+    /// Pixel binary array to be written
+    ///
+    /// If the target vendor does not equals to "espressif", pixel data is written into this
+    /// instead of genuine RMT channel.
+    #[cfg(not(target_vendor = "espressif"))]
+    pub pixel_data: Option<Vec<u8>>,
+    /// Dummy phantom to take care of lifetime for the pin.
+    phantom: PhantomData<&'d mut ()>,
+}
+
+impl<'d> Ws2812Esp32RmtTxDriver<'d> {
+    /// Creates a WS2812 ESP32 RMT TX driver wrapper of `pin` at [`DEFAULT_RESOLUTION_HZ`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no RMT TX channel is available or the RMT initialization failed.
+    pub fn new(
+        pin: impl Peripheral<P = impl OutputPin> + 'd,
+    ) -> Result<Self, Ws2812Esp32RmtTxDriverError> {
+        Self::new_with_buffer(pin, DEFAULT_RESOLUTION_HZ, Vec::new())
+    }
+}
+
+impl<'d, B: BufferStorage> Ws2812Esp32RmtTxDriver<'d, B> {
+    /// Creates a WS2812 ESP32 RMT TX driver wrapper of `pin` at `resolution_hz`, copying the
+    /// frames into `buffer`.
+    ///
+    /// `buffer` shall hold the pixel data of the longest frame; use [`InternalRamBuffer`] to keep
+    /// it off PSRAM, or [`SliceBuffer`] not to use heap for it.
+    ///
+    /// [`InternalRamBuffer`]: super::buffer::InternalRamBuffer
+    /// [`SliceBuffer`]: super::buffer::SliceBuffer
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no RMT TX channel is available, the RMT initialization failed, or
+    /// `resolution_hz` is too coarse or too fine for the pulses.
+    pub fn new_with_buffer(
+        pin: impl Peripheral<P = impl OutputPin> + 'd,
+        resolution_hz: u32,
+        buffer: B,
+    ) -> Result<Self, Ws2812Esp32RmtTxDriverError> {
+        let config = Ws2812Esp32RmtDriverConfig::new().with_resolution(resolution_hz);
+        Self::new_with_config(pin, &config, buffer)
+    }
+
+    /// Creates a WS2812 ESP32 RMT TX driver wrapper of `pin` with `config`, copying the frames
+    /// into `buffer`.
+    ///
+    /// The counter clock is `config.resolution_hz`, as the `rmt_tx` API derives the clock
+    /// divider itself. `config.dma` is available on the chips whose RMT has DMA, e.g. ESP32-S3,
+    /// and `config.mem_block_num` is the size of the DMA buffer then. The frames are followed by
+    /// the reset code of `config.timing` before [`Self::write_blocking()`] returns.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no RMT TX channel is available, the RMT initialization failed,
+    /// `config.clock_divider` is given, `config.mem_block_num` is 0, or `config.resolution_hz`
+    /// is too coarse or too fine for the pulses.
+    pub fn new_with_config(
+        mut pin: impl Peripheral<P = impl OutputPin> + 'd,
+        config: &Ws2812Esp32RmtDriverConfig,
+        buffer: B,
+    ) -> Result<Self, Ws2812Esp32RmtTxDriverError> {
+        // SAFETY: the clone is used only to read the GPIO number.
+        let gpio = unsafe { pin.clone_unchecked() }.pin();
+        let trace_error =
+            |e: &EspError| trace_event!(error, "RMT TX driver init failed: {}", TraceError(e));
+        if config.clock_divider.is_some() {
+            let e = not_supported_error();
+            trace_error(&e);
+            return Err(e.into());
+        }
+        let (resolution_hz, timing) = (config.resolution_hz, config.timing);
+        let ticks = RmtBitTicks::new(resolution_hz, &timing)
+            .filter(|_| config.mem_block_num > 0)
+            .ok_or_else(invalid_arg_error)
+            .inspect_err(trace_error)?;
+
+        #[cfg(target_vendor = "espressif")]
+        {
+            let mut channel_config = rmt_tx_channel_config_t {
+                gpio_num: gpio,
+                clk_src: soc_periph_rmt_clk_src_t_RMT_CLK_SRC_DEFAULT,
+                resolution_hz,
+                mem_block_symbols: RMT_MEM_BLOCK_SYMBOLS * usize::from(config.mem_block_num),
+                trans_queue_depth: 1,
+                ..Default::default()
+            };
+            channel_config.flags.set_with_dma(u32::from(config.dma));
+            let mut channel = core::ptr::null_mut();
+            esp!(unsafe { rmt_new_tx_channel(&channel_config, &mut channel) })
+                .inspect_err(trace_error)?;
+
+            let mut encoder_config = rmt_bytes_encoder_config_t {
+                bit0: symbol(ticks.t0h, ticks.t0l),
+                bit1: symbol(ticks.t1h, ticks.t1l),
+                ..Default::default()
+            };
+            encoder_config.flags.set_msb_first(1);
+            let mut encoder = core::ptr::null_mut();
+            if let Err(e) = esp!(unsafe { rmt_new_bytes_encoder(&encoder_config, &mut encoder) })
+                .and_then(|()| esp!(unsafe { rmt_enable(channel) }))
+            {
+                trace_error(&e);
+                unsafe {
+                    if !encoder.is_null() {
+                        rmt_del_encoder(encoder);
+                    }
+                    rmt_del_channel(channel);
+                }
+                return Err(e.into());
+            }
+
+            trace_event!(
+                debug,
+                "RMT TX driver initialized on GPIO{} at {} Hz",
+                gpio,
+                resolution_hz
+            );
+            Ok(Self {
+                channel,
+                encoder,
+                resolution_hz,
+                timing,
+                gpio,
+                buffer,
+                phantom: PhantomData,
+            })
+        }
+        #[cfg(not(target_vendor = "espressif"))] // Mock implement
+        {
+            let _ = (pin, ticks, RMT_MEM_BLOCK_SYMBOLS);
+            trace_event!(
+                debug,
+                "RMT TX driver initialized on GPIO{} at {} Hz",
+                gpio,
+                resolution_hz
+            );
+            Ok(Self {
+                resolution_hz,
+                timing,
+                gpio,
+                buffer,
+                pixel_data: None,
+                phantom: PhantomData,
+            })
+        }
+    }
+
+    /// Returns the counter clock frequency of the RMT channel, i.e. the resolution of the
+    /// pulses.
+    #[inline]
+    pub fn counter_clock_hz(&self) -> u32 {
+        self.resolution_hz
+    }
+
+    /// Returns the GPIO number of the data line.
+    #[inline]
+    pub fn gpio(&self) -> i32 {
+        self.gpio
+    }
+
+    /// Writes pixel data from a pixel-byte sequence to the IO pin.
+    ///
+    /// Byte count per LED pixel and channel order is not handled by this method.
+    /// The pixel data sequence has to be correctly laid out depending on the LED strip model.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer cannot hold the pixel data or if an RMT error occurred.
+    pub fn write_blocking<T>(
+        &mut self,
+        pixel_sequence: T,
+    ) -> Result<(), Ws2812Esp32RmtTxDriverError>
+    where
+        T: Iterator<Item = u8>,
+    {
+        trace_event!(trace, "RMT TX frame start");
+        self.buffer.clear();
+        self.buffer.reserve(pixel_sequence.size_hint().0);
+        for v in pixel_sequence {
+            self.buffer.extend_from_slice(&[v]).inspect_err(|_| {
+                trace_event!(warn, "RMT TX frame overrun: buffer is full");
+            })?;
+        }
+
+        #[cfg(target_vendor = "espressif")]
+        {
+            let data = self.buffer.as_slice();
+            let config = rmt_transmit_config_t {
+                loop_count: 0,
+                ..Default::default()
+            };
+            esp!(unsafe {
+                rmt_transmit(
+                    self.channel,
+                    self.encoder,
+                    data.as_ptr().cast(),
+                    data.len(),
+                    &config,
+                )
+            })
+            .and_then(|()| esp!(unsafe { rmt_tx_wait_all_done(self.channel, -1) }))
+            .inspect_err(|e| trace_event!(error, "RMT TX frame failed: {}", TraceError(e)))?;
+            unsafe { esp_rom_delay_us(self.timing.reset.as_micros() as u32) };
+        }
+        #[cfg(not(target_vendor = "espressif"))]
+        {
+            self.pixel_data = Some(self.buffer.as_slice().to_vec());
+        }
+        trace_event!(
+            trace,
+            "RMT TX frame done: {} bytes",
+            self.buffer.as_slice().len()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(target_vendor = "espressif")]
+impl<B: BufferStorage> Drop for Ws2812Esp32RmtTxDriver<'_, B> {
+    fn drop(&mut self) {
+        unsafe {
+            rmt_disable(self.channel);
+            rmt_del_encoder(self.encoder);
+            rmt_del_channel(self.channel);
+        }
+    }
+}
+
+#[cfg(target_vendor = "espressif")]
+unsafe impl<B: BufferStorage + Send> Send for Ws2812Esp32RmtTxDriver<'_, B> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::buffer::SliceBuffer;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+
+    #[test]
+    fn test_ws2812_esp32_rmt_tx_driver() {
+        let peripherals = Peripherals::take().unwrap();
+        let mut driver = Ws2812Esp32RmtTxDriver::new(peripherals.pins.gpio5).unwrap();
+        assert_eq!(driver.counter_clock_hz(), DEFAULT_RESOLUTION_HZ);
+        assert_eq!(driver.gpio(), 5);
+
+        driver
+            .write_blocking([0x80, 0x01, 0xFF].into_iter())
+            .unwrap();
+        assert_eq!(driver.pixel_data, Some(vec![0x80, 0x01, 0xFF]));
+
+        // Too coarse for the pulses
+        assert!(Ws2812Esp32RmtTxDriver::new_with_buffer(
+            peripherals.pins.gpio6,
+            1_000_000,
+            Vec::new()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_tx_driver_with_config() {
+        let peripherals = Peripherals::take().unwrap();
+        let config = Ws2812Esp32RmtDriverConfig::new()
+            .with_timing(LedTiming::SK6812)
+            .with_mem_block_num(2)
+            .with_dma(true);
+        let driver =
+            Ws2812Esp32RmtTxDriver::new_with_config(peripherals.pins.gpio1, &config, Vec::new())
+                .unwrap();
+        assert_eq!(driver.counter_clock_hz(), DEFAULT_RESOLUTION_HZ);
+
+        let config = config.with_clock_divider(8);
+        assert!(Ws2812Esp32RmtTxDriver::new_with_config(
+            peripherals.pins.gpio2,
+            &config,
+            Vec::new()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_tx_driver_with_buffer() {
+        let peripherals = Peripherals::take().unwrap();
+        let mut storage = [0u8; 3];
+        let mut driver = Ws2812Esp32RmtTxDriver::new_with_buffer(
+            peripherals.pins.gpio0,
+            DEFAULT_RESOLUTION_HZ,
+            SliceBuffer::new(&mut storage),
+        )
+        .unwrap();
+
+        driver.write_blocking([1, 2, 3].into_iter()).unwrap();
+        assert_eq!(driver.pixel_data, Some(vec![1, 2, 3]));
+        assert!(driver.write_blocking([0; 4].into_iter()).is_err());
+    }
+}
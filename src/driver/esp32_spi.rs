@@ -0,0 +1,562 @@
+#![cfg_attr(not(target_vendor = "espressif"), allow(dead_code))]
+
+use core::error::Error;
+use core::fmt;
+
+#[cfg(not(target_vendor = "espressif"))]
+use core::marker::PhantomData;
+
+use super::buffer::{BufferFull, BufferStorage};
+use super::trace::trace_event;
+#[cfg(target_vendor = "espressif")]
+use super::trace::TraceError;
+use crate::codec::encode_bits;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(not(target_vendor = "espressif"))]
+use crate::mock::esp_idf_hal;
+#[cfg(target_vendor = "espressif")]
+use esp_idf_hal::{
+    gpio::AnyIOPin,
+    spi::{
+        config::{Config, DriverConfig},
+        Dma, SpiDeviceDriver, SpiDriver,
+    },
+    units::Hertz,
+};
+use esp_idf_hal::{gpio::OutputPin, peripheral::Peripheral, spi::SpiAnyPins};
+
+#[cfg(not(target_vendor = "espressif"))]
+use crate::mock::esp_idf_sys;
+use esp_idf_sys::EspError;
+#[cfg(target_vendor = "espressif")]
+use esp_idf_sys::{
+    esp, portMAX_DELAY, spi_device_get_trans_result, spi_device_queue_trans, spi_transaction_t,
+    spi_transaction_t__bindgen_ty_1,
+};
+
+/// Maximum byte count of a DMA transfer.
+///
+/// Longer frames are sent by the consecutive transfers.
+#[cfg(target_vendor = "espressif")]
+const DMA_TRANSFER_SIZE: usize = 4096;
+
+/// Encoding of a WS2812 bit into the SPI MOSI bits.
+///
+/// Each WS2812 bit is sent as 3 or 4 SPI bits, the first of which is always high and the last of
+/// which is always low, so that the MOSI line is left low after a frame to latch it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SpiBitEncoding {
+    /// 3 SPI bits per WS2812 bit at 2.5 MHz: `100` for 0 and `110` for 1.
+    ///
+    /// The SPI buffer is 3 times of the pixel data.
+    #[default]
+    ThreeBits,
+    /// 4 SPI bits per WS2812 bit at 3.2 MHz: `1000` for 0 and `1110` for 1.
+    ///
+    /// The SPI buffer is 4 times of the pixel data, but the timing is closer to the nominal one.
+    FourBits,
+}
+
+impl SpiBitEncoding {
+    /// Returns the SPI clock frequency in Hz.
+    pub const fn clock_hz(&self) -> u32 {
+        match self {
+            Self::ThreeBits => 2_500_000,
+            Self::FourBits => 3_200_000,
+        }
+    }
+
+    /// Returns the number of the SPI bits per WS2812 bit.
+    pub const fn bits(&self) -> usize {
+        match self {
+            Self::ThreeBits => 3,
+            Self::FourBits => 4,
+        }
+    }
+
+    /// Returns the SPI bit pattern of a WS2812 bit, MSB first.
+    const fn pattern(&self, bit: bool) -> u8 {
+        match (self, bit) {
+            (Self::ThreeBits, false) => 0b100,
+            (Self::ThreeBits, true) => 0b110,
+            (Self::FourBits, false) => 0b1000,
+            (Self::FourBits, true) => 0b1110,
+        }
+    }
+
+    /// Returns the byte count of the SPI data of `len` bytes of the pixel data.
+    pub const fn encoded_len(&self, len: usize) -> usize {
+        (len * 8 * self.bits()).div_ceil(8)
+    }
+
+    /// Encodes the pixel-byte sequence into the SPI data, replacing the content of `buffer`.
+    ///
+    /// The last byte is padded with low bits.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `buffer` cannot hold the SPI data.
+    pub fn encode<T, B>(&self, pixel_sequence: T, buffer: &mut B) -> Result<(), BufferFull>
+    where
+        T: Iterator<Item = u8>,
+        B: BufferStorage + ?Sized,
+    {
+        buffer.clear();
+        let bits = self.bits();
+        let (mut acc, mut acc_bits) = (0u16, 0);
+        for bit in encode_bits(pixel_sequence) {
+            acc = (acc << bits) | u16::from(self.pattern(bit));
+            acc_bits += bits;
+            if acc_bits >= 8 {
+                acc_bits -= 8;
+                buffer.extend_from_slice(&[(acc >> acc_bits) as u8])?;
+            }
+        }
+        if acc_bits > 0 {
+            buffer.extend_from_slice(&[(acc << (8 - acc_bits)) as u8])?;
+        }
+        Ok(())
+    }
+}
+
+/// WS2812 ESP32 SPI Driver error.
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct Ws2812Esp32SpiDriverError {
+    source: EspError,
+}
+
+#[cfg(not(feature = "std"))]
+impl Ws2812Esp32SpiDriverError {
+    /// The `EspError` source of this error, if any.
+    ///
+    /// This is a workaround function until `core::error::Error` added to `esp_sys::EspError`.
+    pub fn source(&self) -> Option<&EspError> {
+        Some(&self.source)
+    }
+}
+
+impl Error for Ws2812Esp32SpiDriverError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        #[cfg(feature = "std")]
+        {
+            Some(&self.source)
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Ws2812Esp32SpiDriverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+impl From<EspError> for Ws2812Esp32SpiDriverError {
+    fn from(source: EspError) -> Self {
+        Self { source }
+    }
+}
+
+impl From<BufferFull> for Ws2812Esp32SpiDriverError {
+    fn from(_: BufferFull) -> Self {
+        #[cfg(target_vendor = "espressif")]
+        {
+            EspError::from_infallible::<{ esp_idf_sys::ESP_ERR_NO_MEM }>().into()
+        }
+        #[cfg(not(target_vendor = "espressif"))]
+        {
+            EspError().into()
+        }
+    }
+}
+
+/// DMA transactions queued by [`Ws2812Esp32SpiDriver::write()`]
+#[cfg(target_vendor = "espressif")]
+#[derive(Default)]
+struct Transactions(Vec<spi_transaction_t>);
+
+// SAFETY: the transactions point to the buffer owned by the same driver, and are accessed only
+// through the driver.
+#[cfg(target_vendor = "espressif")]
+unsafe impl Send for Transactions {}
+
+/// WS2812 ESP32 SPI driver wrapper.
+///
+/// The WS2812 bits are encoded into the SPI MOSI bits and sent by DMA, so that the LED strip is
+/// driven by an SPI host instead of an RMT channel, e.g. when the RMT channels are exhausted.
+/// Only the MOSI pin is used; no clock and chip select pin is assigned.
+///
+/// The frame is encoded into a buffer of [`SpiBitEncoding::encoded_len()`] bytes before the
+/// transmission. The buffer is allocated by the global allocator unless another
+/// [`BufferStorage`] is given by [`Self::new_with_buffer()`].
+///
+/// # Examples
+///
+/// ```
+/// #[cfg(not(target_vendor = "espressif"))]
+/// use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+///
+/// use esp_idf_hal::peripherals::Peripherals;
+/// use ws2812_esp32_rmt_driver::driver::{SpiBitEncoding, Ws2812Esp32SpiDriver};
+/// use ws2812_esp32_rmt_driver::driver::color::{LedPixelColor, LedPixelColorGrb24};
+/// use ws2812_esp32_rmt_driver::framebuffer::LedPixelFrameBuffer;
+///
+/// let peripherals = Peripherals::take().unwrap();
+/// let led_pin = peripherals.pins.gpio27;
+/// let driver = Ws2812Esp32SpiDriver::new(peripherals.spi2, led_pin, SpiBitEncoding::ThreeBits).unwrap();
+///
+/// // The wrappers work on the SPI driver as well.
+/// let mut frame = LedPixelFrameBuffer::<LedPixelColorGrb24, Vec<u8>, _>::from_driver(driver, 25);
+/// frame.fill(LedPixelColorGrb24::new_with_rgb(30, 0, 0));
+/// frame.show().unwrap();
+/// ```
+pub struct Ws2812Esp32SpiDriver<'d, B = Vec<u8>>
+where
+    B: BufferStorage,
+{
+    /// SPI device driver.
+    #[cfg(target_vendor = "espressif")]
+    device: SpiDeviceDriver<'d, SpiDriver<'d>>,
+    /// Encoding of the WS2812 bits
+    encoding: SpiBitEncoding,
+    /// SPI data of the last frame
+    buffer: B,
+    /// DMA transactions of the frame in transmission by [`Self::write()`]
+    #[cfg(target_vendor = "espressif")]
+    transactions: Transactions,
+
+    /// Pixel binary array to be written
+    ///
+    /// If the target vendor does not equals to "espressif", pixel data is written into this
+    /// instead of genuine SPI device.
+    #[cfg(not(target_vendor = "espressif"))]
+    pub pixel_data: Option<Vec<u8>>,
+    /// Recording state of the mock
+    #[cfg(not(target_vendor = "espressif"))]
+    recorder: crate::mock::recorder::FrameRecorder,
+    /// Dummy phantom to take care of lifetime for `pixel_data`.
+    #[cfg(not(target_vendor = "espressif"))]
+    phantom: PhantomData<&'d Option<Vec<u8>>>,
+}
+
+impl<'d> Ws2812Esp32SpiDriver<'d> {
+    /// Creates a WS2812 ESP32 SPI driver wrapper.
+    ///
+    /// SPI driver of `spi` shall be initialized with the DMA for the MOSI `pin`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SPI driver initialization failed.
+    pub fn new<SPI: SpiAnyPins>(
+        spi: impl Peripheral<P = SPI> + 'd,
+        pin: impl Peripheral<P = impl OutputPin> + 'd,
+        encoding: SpiBitEncoding,
+    ) -> Result<Self, Ws2812Esp32SpiDriverError> {
+        Self::new_with_buffer(spi, pin, encoding, Vec::new())
+    }
+}
+
+impl<'d, B: BufferStorage> Ws2812Esp32SpiDriver<'d, B> {
+    /// Creates a WS2812 ESP32 SPI driver wrapper encoding the frames into `buffer`.
+    ///
+    /// `buffer` shall hold [`SpiBitEncoding::encoded_len()`] bytes of the longest frame; use
+    /// [`InternalRamBuffer`] to keep it off PSRAM, or [`SliceBuffer`] not to use heap for it.
+    ///
+    /// [`InternalRamBuffer`]: super::buffer::InternalRamBuffer
+    /// [`SliceBuffer`]: super::buffer::SliceBuffer
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SPI driver initialization failed.
+    pub fn new_with_buffer<SPI: SpiAnyPins>(
+        spi: impl Peripheral<P = SPI> + 'd,
+        pin: impl Peripheral<P = impl OutputPin> + 'd,
+        encoding: SpiBitEncoding,
+        buffer: B,
+    ) -> Result<Self, Ws2812Esp32SpiDriverError> {
+        #[cfg(target_vendor = "espressif")]
+        {
+            let trace_error =
+                |e: &EspError| trace_event!(error, "SPI driver init failed: {}", TraceError(e));
+            let driver_config = DriverConfig::new().dma(Dma::Auto(DMA_TRANSFER_SIZE));
+            let driver =
+                SpiDriver::new_without_sclk(spi, pin, Option::<AnyIOPin>::None, &driver_config)
+                    .inspect_err(trace_error)?;
+            let config = Config::new().baudrate(Hertz(encoding.clock_hz()));
+            let device = SpiDeviceDriver::new(driver, Option::<AnyIOPin>::None, &config)
+                .inspect_err(trace_error)?;
+            trace_event!(
+                debug,
+                "SPI driver initialized at {} Hz",
+                encoding.clock_hz()
+            );
+
+            Ok(Self {
+                device,
+                encoding,
+                buffer,
+                transactions: Transactions::default(),
+            })
+        }
+        #[cfg(not(target_vendor = "espressif"))] // Mock implement
+        {
+            let _ = (spi, pin);
+            trace_event!(
+                debug,
+                "SPI driver initialized at {} Hz",
+                encoding.clock_hz()
+            );
+            Ok(Self {
+                encoding,
+                buffer,
+                pixel_data: None,
+                recorder: Default::default(),
+                phantom: Default::default(),
+            })
+        }
+    }
+
+    /// Returns the encoding of the WS2812 bits.
+    #[inline]
+    pub fn encoding(&self) -> SpiBitEncoding {
+        self.encoding
+    }
+
+    /// Writes pixel data from a pixel-byte sequence to the MOSI pin.
+    ///
+    /// Byte count per LED pixel and channel order is not handled by this method.
+    /// The pixel data sequence has to be correctly laid out depending on the LED strip model.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer cannot hold the SPI data or if an SPI driver error occurred.
+    pub fn write_blocking<T>(&mut self, pixel_sequence: T) -> Result<(), Ws2812Esp32SpiDriverError>
+    where
+        T: Iterator<Item = u8>,
+    {
+        trace_event!(trace, "SPI frame start");
+        #[cfg(target_vendor = "espressif")]
+        {
+            self.finish()?;
+            self.encoding.encode(pixel_sequence, &mut self.buffer)?;
+            self.device.write(self.buffer.as_slice()).inspect_err(|e| {
+                trace_event!(error, "SPI frame failed: {}", TraceError(e));
+            })?;
+        }
+        #[cfg(not(target_vendor = "espressif"))]
+        {
+            let pixel_data = pixel_sequence.collect::<Vec<_>>();
+            self.encoding
+                .encode(pixel_data.iter().copied(), &mut self.buffer)?;
+            self.pixel_data = Some(self.recorder.record(pixel_data.into_iter())?);
+        }
+        trace_event!(
+            trace,
+            "SPI frame done: {} SPI bytes",
+            self.buffer.as_slice().len()
+        );
+        Ok(())
+    }
+
+    /// Writes pixel data from a pixel-byte sequence to the MOSI pin without waiting for the
+    /// transmission to complete.
+    ///
+    /// Byte count per LED pixel and channel order is not handled by this method.
+    /// The pixel data sequence has to be correctly laid out depending on the LED strip model.
+    ///
+    /// The frame is encoded into the buffer owned by the driver, and queued as the DMA
+    /// transactions. The next write waits for them before overwriting the buffer, and so does
+    /// dropping the driver.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer cannot hold the SPI data or if an SPI driver error occurred.
+    pub fn write<T>(&mut self, pixel_sequence: T) -> Result<(), Ws2812Esp32SpiDriverError>
+    where
+        T: Iterator<Item = u8>,
+    {
+        #[cfg(target_vendor = "espressif")]
+        {
+            trace_event!(trace, "SPI queued frame start");
+            self.finish()?;
+            self.encoding.encode(pixel_sequence, &mut self.buffer)?;
+            self.transactions
+                .0
+                .extend(
+                    self.buffer
+                        .as_slice()
+                        .chunks(DMA_TRANSFER_SIZE)
+                        .map(|chunk| spi_transaction_t {
+                            length: chunk.len() * 8,
+                            __bindgen_anon_1: spi_transaction_t__bindgen_ty_1 {
+                                tx_buffer: chunk.as_ptr().cast(),
+                            },
+                            ..Default::default()
+                        }),
+                );
+            let handle = self.device.device();
+            for (i, transaction) in self.transactions.0.iter_mut().enumerate() {
+                // SAFETY: the transaction and the buffer are kept until its result is taken by
+                // `finish()`.
+                if let Err(e) =
+                    esp!(unsafe { spi_device_queue_trans(handle, transaction, portMAX_DELAY) })
+                {
+                    trace_event!(error, "SPI frame failed: {}", TraceError(&e));
+                    self.transactions.0.truncate(i);
+                    return Err(e.into());
+                }
+            }
+            Ok(())
+        }
+        #[cfg(not(target_vendor = "espressif"))]
+        {
+            self.write_blocking(pixel_sequence)
+        }
+    }
+
+    /// Waits until the frame in transmission, e.g. started by [`Self::write()`], has been sent.
+    ///
+    /// The MOSI line is left low after the frame, so the LED pixels latch it after the reset
+    /// time of the LED pixels.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an SPI driver error occurred.
+    pub fn latch(&mut self) -> Result<(), Ws2812Esp32SpiDriverError> {
+        #[cfg(target_vendor = "espressif")]
+        self.finish()?;
+        Ok(())
+    }
+
+    /// Waits for the DMA transactions queued by [`Self::write()`].
+    #[cfg(target_vendor = "espressif")]
+    fn finish(&mut self) -> Result<(), EspError> {
+        let handle = self.device.device();
+        while !self.transactions.0.is_empty() {
+            let mut done = core::ptr::null_mut();
+            esp!(unsafe { spi_device_get_trans_result(handle, &mut done, portMAX_DELAY) })
+                .inspect_err(|e| trace_event!(error, "SPI frame failed: {}", TraceError(e)))?;
+            self.transactions.0.pop();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_vendor = "espressif")]
+impl<B: BufferStorage> Drop for Ws2812Esp32SpiDriver<'_, B> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+#[cfg(not(target_vendor = "espressif"))]
+crate::mock::recorder::impl_frame_recording!({'d, B: BufferStorage} Ws2812Esp32SpiDriver<'d, B>);
+
+#[cfg(not(target_vendor = "espressif"))]
+impl<B: BufferStorage> Ws2812Esp32SpiDriver<'_, B> {
+    /// Returns the SPI data of the last frame written.
+    ///
+    /// This function is only available in the mock.
+    pub fn spi_data(&self) -> &[u8] {
+        self.buffer.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::buffer::SliceBuffer;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+
+    #[test]
+    fn test_spi_bit_encoding() {
+        let mut buffer = Vec::new();
+
+        let encoding = SpiBitEncoding::ThreeBits;
+        encoding.encode([0xA5].into_iter(), &mut buffer).unwrap();
+        // 110 100 110 100 100 110 100 110
+        assert_eq!(buffer, [0b1101_0011, 0b0100_1001, 0b1010_0110]);
+        encoding
+            .encode([0x00, 0xFF].into_iter(), &mut buffer)
+            .unwrap();
+        assert_eq!(buffer, [0x92, 0x49, 0x24, 0xDB, 0x6D, 0xB6]);
+        assert_eq!(encoding.encoded_len(2), buffer.len());
+
+        let encoding = SpiBitEncoding::FourBits;
+        encoding.encode([0xA5].into_iter(), &mut buffer).unwrap();
+        assert_eq!(buffer, [0xE8, 0xE8, 0x8E, 0x8E]);
+        assert_eq!(encoding.encoded_len(1), buffer.len());
+
+        encoding.encode([].into_iter(), &mut buffer).unwrap();
+        assert!(buffer.is_empty());
+
+        let mut buffer = heapless::Vec::<u8, 3>::new();
+        assert_eq!(
+            encoding.encode([0xA5].into_iter(), &mut buffer),
+            Err(BufferFull)
+        );
+    }
+
+    #[test]
+    fn test_ws2812_esp32_spi_driver() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let mut driver =
+            Ws2812Esp32SpiDriver::new(peripherals.spi2, led_pin, SpiBitEncoding::FourBits).unwrap();
+        assert_eq!(driver.encoding(), SpiBitEncoding::FourBits);
+
+        driver.write_blocking([0x80, 0x01].into_iter()).unwrap();
+        assert_eq!(driver.pixel_data, Some(vec![0x80, 0x01]));
+        assert_eq!(
+            driver.spi_data(),
+            [0xE8, 0x88, 0x88, 0x88, 0x88, 0x88, 0x88, 0x8E]
+        );
+
+        let pixels = [0x01, 0x80];
+        driver.write(pixels.iter().copied()).unwrap();
+        driver.latch().unwrap();
+        assert_eq!(driver.pixel_data, Some(vec![0x01, 0x80]));
+    }
+
+    #[test]
+    fn test_ws2812_esp32_spi_driver_recording() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let mut driver =
+            Ws2812Esp32SpiDriver::new(peripherals.spi2, led_pin, SpiBitEncoding::ThreeBits)
+                .unwrap();
+
+        driver.record_history();
+        driver.write_blocking([0x01].into_iter()).unwrap();
+        driver.inject_error(EspError());
+        assert!(driver.write_blocking([0x02].into_iter()).is_err());
+        assert_eq!(driver.pixel_data, Some(vec![0x01]));
+        driver.write([0x03].into_iter()).unwrap();
+        assert_eq!(driver.frame_history(), [vec![0x01], vec![0x03]]);
+    }
+
+    #[test]
+    fn test_ws2812_esp32_spi_driver_with_buffer() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let mut storage = [0u8; 6];
+        let buffer = SliceBuffer::new(&mut storage);
+        let mut driver = Ws2812Esp32SpiDriver::new_with_buffer(
+            peripherals.spi2,
+            led_pin,
+            SpiBitEncoding::ThreeBits,
+            buffer,
+        )
+        .unwrap();
+
+        driver.write_blocking([0x00, 0xFF].into_iter()).unwrap();
+        assert_eq!(driver.spi_data(), [0x92, 0x49, 0x24, 0xDB, 0x6D, 0xB6]);
+        assert!(driver.write_blocking([0x00; 3].into_iter()).is_err());
+    }
+}
@@ -0,0 +1,226 @@
+//! Bare-metal RMT driver on `esp-hal`.
+//!
+//! On the bare-metal targets (`target_os = "none"`), [`Ws2812EspHalRmtDriver`] drives the LED
+//! strip by an RMT TX channel of [esp-hal](https://crates.io/crates/esp-hal) instead of ESP-IDF.
+//! The color types and the wrappers work on it as on the ESP-IDF RMT driver, via
+//! `from_driver()`.
+//!
+//! The pulse encoding is independent of `esp-hal` and is also available on the other targets.
+
+#[cfg(target_os = "none")]
+use super::timing::{LedTiming, RmtBitTicks};
+#[cfg(target_os = "none")]
+use super::trace::{trace_event, TraceError};
+
+#[cfg(target_os = "none")]
+use core::fmt;
+#[cfg(target_os = "none")]
+use esp_hal::{
+    gpio::Level,
+    rmt::{Channel, Error as RmtError, PulseCode, Tx},
+    Blocking,
+};
+
+/// Encodes a pixel-byte sequence into `buffer` as the RMT items `bit0` and `bit1` followed by
+/// `end`, MSB first.
+///
+/// Returns the item count including `end`, or `None` if `buffer` is too small.
+pub fn encode_pulses<T, I>(
+    pixel_sequence: I,
+    bit0: T,
+    bit1: T,
+    end: T,
+    buffer: &mut [T],
+) -> Option<usize>
+where
+    T: Copy,
+    I: Iterator<Item = u8>,
+{
+    let mut len = 0;
+    for byte in pixel_sequence {
+        let items = buffer.get_mut(len..len + 8)?;
+        for (i, item) in items.iter_mut().enumerate() {
+            *item = if byte & (0x80 >> i) != 0 { bit1 } else { bit0 };
+        }
+        len += 8;
+    }
+    *buffer.get_mut(len)? = end;
+    Some(len + 1)
+}
+
+/// WS2812 esp-hal RMT Driver error.
+#[cfg(target_os = "none")]
+#[derive(Debug)]
+pub enum Ws2812EspHalRmtDriverError {
+    /// The timing cannot be represented at the RMT clock.
+    InvalidTiming,
+    /// The frame does not fit in the item buffer.
+    BufferTooSmall,
+    /// The RMT channel is lost by a previous error.
+    ChannelLost,
+    /// RMT error
+    Rmt(RmtError),
+}
+
+#[cfg(target_os = "none")]
+impl core::error::Error for Ws2812EspHalRmtDriverError {}
+
+#[cfg(target_os = "none")]
+impl fmt::Display for Ws2812EspHalRmtDriverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidTiming => write!(f, "timing cannot be represented at the RMT clock"),
+            Self::BufferTooSmall => write!(f, "RMT item buffer is too small for the frame"),
+            Self::ChannelLost => write!(f, "RMT channel is lost"),
+            Self::Rmt(e) => write!(f, "RMT error: {:?}", e),
+        }
+    }
+}
+
+#[cfg(target_os = "none")]
+impl From<RmtError> for Ws2812EspHalRmtDriverError {
+    fn from(e: RmtError) -> Self {
+        Self::Rmt(e)
+    }
+}
+
+/// WS2812 esp-hal RMT driver wrapper.
+///
+/// The frame is encoded into the caller-supplied RMT item buffer, which shall hold 8 items per
+/// byte of the pixel data plus 1, so that no heap is required.
+///
+/// # Examples
+///
+/// ```ignore
+/// use esp_hal::rmt::{PulseCode, Rmt, TxChannelConfig, TxChannelCreator};
+/// use esp_hal::time::Rate;
+/// use ws2812_esp32_rmt_driver::driver::color::{LedPixelColor, LedPixelColorGrb24};
+/// use ws2812_esp32_rmt_driver::driver::Ws2812EspHalRmtDriver;
+/// use ws2812_esp32_rmt_driver::framebuffer::LedPixelFrameBuffer;
+///
+/// let peripherals = esp_hal::init(esp_hal::Config::default());
+/// let rmt = Rmt::new(peripherals.RMT, Rate::from_mhz(80)).unwrap();
+/// let config = TxChannelConfig::default()
+///     .with_clk_divider(1)
+///     .with_idle_output(true);
+/// let channel = rmt.channel0.configure_tx(peripherals.GPIO8, config).unwrap();
+///
+/// // 25 LED pixels of 3 bytes
+/// let mut buffer = [PulseCode::end_marker(); 25 * 3 * 8 + 1];
+/// let driver = Ws2812EspHalRmtDriver::new(channel, 80_000_000, &mut buffer).unwrap();
+/// let mut frame =
+///     LedPixelFrameBuffer::<LedPixelColorGrb24, heapless::Vec<u8, 75>, _>::from_driver(driver, 25);
+/// frame.fill(LedPixelColorGrb24::new_with_rgb(30, 0, 0));
+/// frame.show().unwrap();
+/// ```
+#[cfg(target_os = "none")]
+pub struct Ws2812EspHalRmtDriver<'d> {
+    /// RMT TX channel, taken while transmitting
+    channel: Option<Channel<'d, Blocking, Tx>>,
+    /// RMT item of a 0 code
+    bit0: PulseCode,
+    /// RMT item of a 1 code
+    bit1: PulseCode,
+    /// RMT item buffer
+    buffer: &'d mut [PulseCode],
+}
+
+#[cfg(target_os = "none")]
+impl<'d> Ws2812EspHalRmtDriver<'d> {
+    /// Creates a WS2812 esp-hal RMT driver wrapper.
+    ///
+    /// `channel` shall be configured for the LED pin, and `clock_hz` is its counter clock, i.e.
+    /// the RMT source clock divided by the clock divider.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the WS2812 timing cannot be represented at `clock_hz`.
+    pub fn new(
+        channel: Channel<'d, Blocking, Tx>,
+        clock_hz: u32,
+        buffer: &'d mut [PulseCode],
+    ) -> Result<Self, Ws2812EspHalRmtDriverError> {
+        let Some(ticks) = RmtBitTicks::new(clock_hz, &LedTiming::WS2812) else {
+            let e = Ws2812EspHalRmtDriverError::InvalidTiming;
+            trace_event!(error, "esp-hal RMT driver init failed: {}", TraceError(&e));
+            return Err(e);
+        };
+        trace_event!(debug, "esp-hal RMT driver initialized at {} Hz", clock_hz);
+        Ok(Self {
+            channel: Some(channel),
+            bit0: PulseCode::new(Level::High, ticks.t0h, Level::Low, ticks.t0l),
+            bit1: PulseCode::new(Level::High, ticks.t1h, Level::Low, ticks.t1l),
+            buffer,
+        })
+    }
+
+    /// Writes pixel data from a pixel-byte sequence to the IO pin.
+    ///
+    /// Byte count per LED pixel and channel order is not handled by this method.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the frame does not fit in the buffer or if an RMT error occurred.
+    pub fn write_blocking<T>(&mut self, pixel_sequence: T) -> Result<(), Ws2812EspHalRmtDriverError>
+    where
+        T: Iterator<Item = u8>,
+    {
+        trace_event!(trace, "esp-hal RMT frame start");
+        let Some(len) = encode_pulses(
+            pixel_sequence,
+            self.bit0,
+            self.bit1,
+            PulseCode::end_marker(),
+            self.buffer,
+        ) else {
+            trace_event!(
+                warn,
+                "esp-hal RMT frame overrun: buffer of {} items is too small",
+                self.buffer.len()
+            );
+            return Err(Ws2812EspHalRmtDriverError::BufferTooSmall);
+        };
+        self.transmit(len)
+            .inspect(|()| trace_event!(trace, "esp-hal RMT frame done: {} items", len))
+            .inspect_err(|e| trace_event!(error, "esp-hal RMT frame failed: {}", TraceError(e)))
+    }
+
+    /// Transmits the first `len` items of the buffer.
+    fn transmit(&mut self, len: usize) -> Result<(), Ws2812EspHalRmtDriverError> {
+        let channel = self
+            .channel
+            .take()
+            .ok_or(Ws2812EspHalRmtDriverError::ChannelLost)?;
+        let transaction = channel.transmit(&self.buffer[..len])?;
+        match transaction.wait() {
+            Ok(channel) => {
+                self.channel = Some(channel);
+                Ok(())
+            }
+            Err((e, channel)) => {
+                self.channel = Some(channel);
+                Err(e.into())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encode_pulses() {
+        let mut buffer = [0u8; 17];
+        assert_eq!(
+            encode_pulses([0xA5, 0x01].into_iter(), 0, 1, 9, &mut buffer),
+            Some(17)
+        );
+        assert_eq!(buffer, [1, 0, 1, 0, 0, 1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 9]);
+        assert_eq!(
+            encode_pulses([0xA5, 0x01].into_iter(), 0, 1, 9, &mut buffer[..16]),
+            None
+        );
+        assert_eq!(encode_pulses([].into_iter(), 0, 1, 9, &mut buffer), Some(1));
+    }
+}
@@ -0,0 +1,159 @@
+//! Gamma correction of the LED pixel channels.
+//!
+//! The LED pixels are driven by PWM, so the channel values are linear in the light output
+//! rather than in the perceived brightness, and the fades look abrupt at the low end.
+//! [`GammaLut`] maps the channel values through a 256-entry table before they are written; the
+//! wrappers apply it with `set_gamma()`. Besides the power curve of [`GammaLut::GAMMA_2_2`],
+//! [`GammaLut::CIE1931`] follows the CIE 1931 lightness, which dims more evenly at the low end.
+//!
+//! # Examples
+//!
+//! ```
+//! use ws2812_esp32_rmt_driver::driver::gamma::GammaLut;
+//!
+//! let gamma = GammaLut::GAMMA_2_2;
+//! assert_eq!(gamma.apply(0), 0);
+//! assert_eq!(gamma.apply(128), 56);
+//! assert_eq!(gamma.apply(255), 255);
+//!
+//! let cie = GammaLut::CIE1931;
+//! assert_eq!(cie.apply(128), 47);
+//!
+//! // User table, e.g. measured on the LED pixels
+//! let custom = GammaLut::from_fn(|v| v / 2);
+//! assert_eq!(custom.apply(200), 100);
+//! ```
+
+/// Lookup table mapping the channel values of the LED pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GammaLut([u8; 256]);
+
+impl GammaLut {
+    /// Gamma of 2.2, the perceptual curve of the typical displays
+    pub const GAMMA_2_2: Self = Self([
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2,
+        2, 2, 3, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 6, 6, 6, 6, 7, 7, 7, 8, 8, 8, 9, 9, 9, 10, 10,
+        11, 11, 11, 12, 12, 13, 13, 13, 14, 14, 15, 15, 16, 16, 17, 17, 18, 18, 19, 19, 20, 20, 21,
+        22, 22, 23, 23, 24, 25, 25, 26, 26, 27, 28, 28, 29, 30, 30, 31, 32, 33, 33, 34, 35, 35, 36,
+        37, 38, 39, 39, 40, 41, 42, 43, 43, 44, 45, 46, 47, 48, 49, 49, 50, 51, 52, 53, 54, 55, 56,
+        57, 58, 59, 60, 61, 62, 63, 64, 65, 66, 67, 68, 69, 70, 71, 73, 74, 75, 76, 77, 78, 79, 81,
+        82, 83, 84, 85, 87, 88, 89, 90, 91, 93, 94, 95, 97, 98, 99, 100, 102, 103, 105, 106, 107,
+        109, 110, 111, 113, 114, 116, 117, 119, 120, 121, 123, 124, 126, 127, 129, 130, 132, 133,
+        135, 137, 138, 140, 141, 143, 145, 146, 148, 149, 151, 153, 154, 156, 158, 159, 161, 163,
+        165, 166, 168, 170, 172, 173, 175, 177, 179, 181, 182, 184, 186, 188, 190, 192, 194, 196,
+        197, 199, 201, 203, 205, 207, 209, 211, 213, 215, 217, 219, 221, 223, 225, 227, 229, 231,
+        234, 236, 238, 240, 242, 244, 246, 248, 251, 253, 255,
+    ]);
+
+    /// CIE 1931 lightness curve, the channel value taken as the perceived lightness L*
+    ///
+    /// It is linear at the low end instead of the power curve, so the dim levels step evenly.
+    pub const CIE1931: Self = Self([
+        0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3, 3,
+        3, 4, 4, 4, 4, 4, 4, 5, 5, 5, 5, 5, 6, 6, 6, 6, 6, 7, 7, 7, 7, 8, 8, 8, 8, 9, 9, 9, 10, 10,
+        10, 10, 11, 11, 11, 12, 12, 12, 13, 13, 13, 14, 14, 15, 15, 15, 16, 16, 17, 17, 17, 18, 18,
+        19, 19, 20, 20, 21, 21, 22, 22, 23, 23, 24, 24, 25, 25, 26, 26, 27, 28, 28, 29, 29, 30, 31,
+        31, 32, 32, 33, 34, 34, 35, 36, 37, 37, 38, 39, 39, 40, 41, 42, 43, 43, 44, 45, 46, 47, 47,
+        48, 49, 50, 51, 52, 53, 54, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 64, 65, 66, 67, 68, 70,
+        71, 72, 73, 74, 75, 76, 77, 79, 80, 81, 82, 83, 85, 86, 87, 88, 90, 91, 92, 94, 95, 96, 98,
+        99, 100, 102, 103, 105, 106, 108, 109, 110, 112, 113, 115, 116, 118, 120, 121, 123, 124,
+        126, 128, 129, 131, 132, 134, 136, 138, 139, 141, 143, 145, 146, 148, 150, 152, 154, 155,
+        157, 159, 161, 163, 165, 167, 169, 171, 173, 175, 177, 179, 181, 183, 185, 187, 189, 191,
+        193, 196, 198, 200, 202, 204, 207, 209, 211, 214, 216, 218, 220, 223, 225, 228, 230, 232,
+        235, 237, 240, 242, 245, 247, 250, 252, 255,
+    ]);
+
+    /// Identity table, i.e. no correction
+    pub const LINEAR: Self = {
+        let mut table = [0; 256];
+        let mut i = 0;
+        while i < table.len() {
+            table[i] = i as u8;
+            i += 1;
+        }
+        Self(table)
+    };
+
+    /// Creates a lookup table of `table`, indexed by the channel value.
+    #[inline]
+    pub const fn new(table: [u8; 256]) -> Self {
+        Self(table)
+    }
+
+    /// Creates a lookup table mapping each channel value by `f`.
+    pub fn from_fn(mut f: impl FnMut(u8) -> u8) -> Self {
+        Self(core::array::from_fn(|i| f(i as u8)))
+    }
+
+    /// Creates a lookup table of the power curve of `gamma`, e.g. 2.8 for the LED pixels
+    /// looking too bright at the low end with the default one.
+    #[cfg(feature = "std")]
+    pub fn with_gamma(gamma: f32) -> Self {
+        Self::from_fn(|v| ((f32::from(v) / 255.0).powf(gamma) * 255.0).round() as u8)
+    }
+
+    /// Returns the table, indexed by the channel value.
+    #[inline]
+    pub const fn table(&self) -> &[u8; 256] {
+        &self.0
+    }
+
+    /// Returns the corrected channel value.
+    #[inline]
+    pub const fn apply(&self, value: u8) -> u8 {
+        self.0[value as usize]
+    }
+
+    /// Returns the corrected channel value of the 8.8 fixed-point `value`, in 8.8 fixed point
+    /// interpolated between the entries of the table, e.g. for the dithering.
+    pub const fn apply_fixed(&self, value: u16) -> u16 {
+        let index = (value >> 8) as usize;
+        let low = self.0[index] as u16;
+        let high = self.0[if index < 255 { index + 1 } else { 255 }] as u16;
+        let frac = value & 0xFF;
+        if high >= low {
+            (low << 8) + (high - low) * frac
+        } else {
+            (low << 8) - (low - high) * frac
+        }
+    }
+}
+
+impl Default for GammaLut {
+    /// Returns [`GammaLut::GAMMA_2_2`].
+    fn default() -> Self {
+        Self::GAMMA_2_2
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_gamma_lut() {
+        let gamma = GammaLut::default();
+        assert_eq!(gamma, GammaLut::GAMMA_2_2);
+        assert!(gamma.table().windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!((gamma.apply(0), gamma.apply(255)), (0, 255));
+
+        let computed = GammaLut::with_gamma(2.2);
+        assert!(computed
+            .table()
+            .iter()
+            .zip(gamma.table())
+            .all(|(&a, &b)| a.abs_diff(b) <= 1));
+        assert_eq!(GammaLut::with_gamma(1.0), GammaLut::LINEAR);
+
+        let cie = GammaLut::CIE1931;
+        assert!(cie.table().windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!((cie.apply(0), cie.apply(255)), (0, 255));
+        // Linear segment at the low end
+        assert!(cie.table()[..20].windows(2).all(|w| w[1] - w[0] <= 1));
+        assert_eq!(GammaLut::LINEAR.apply(77), 77);
+        assert_eq!(GammaLut::LINEAR.apply_fixed(0x4D80), 0x4D80);
+        assert_eq!(gamma.apply_fixed(0x8000), 56 << 8);
+        assert_eq!(gamma.apply_fixed(0xFF80), 0xFF00);
+        assert_eq!(gamma.apply_fixed(0x0E80), 0x0080);
+    }
+}
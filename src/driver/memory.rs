@@ -0,0 +1,192 @@
+//! Memory requirements estimation.
+//!
+//! [`estimate_memory()`] returns the memory a strip takes on a backend before anything is
+//! allocated, so that the feasibility on a constrained chip is checked at the startup, or at the
+//! build time by a `const` assertion.
+//!
+//! # Examples
+//!
+//! ```
+//! use ws2812_esp32_rmt_driver::driver::memory::{estimate_memory, Backend};
+//!
+//! // 300 LED pixels of GRB on the SPI driver
+//! const MEMORY: usize = estimate_memory(300, 3, Backend::Spi { bits: 3 }).total();
+//! const _: () = assert!(MEMORY <= 8 * 1024, "the strip does not fit in the budget");
+//!
+//! let estimate = estimate_memory(300, 3, Backend::Rmt { mem_block_num: 1 });
+//! assert_eq!(estimate.ram, 900);
+//! assert_eq!(estimate.dma, 0);
+//! ```
+
+/// Byte count of an RMT item (`rmt_item32_t`)
+pub const RMT_SYMBOL_SIZE: usize = 4;
+
+/// Number of the RMT items of a memory block of an RMT channel
+#[cfg(target_vendor = "espressif")]
+#[cfg(any(esp32, esp32s2))]
+pub const RMT_MEM_BLOCK_SYMBOLS: usize = 64;
+/// Number of the RMT items of a memory block of an RMT channel
+#[cfg(target_vendor = "espressif")]
+#[cfg(not(any(esp32, esp32s2)))]
+pub const RMT_MEM_BLOCK_SYMBOLS: usize = 48;
+/// Number of the RMT items of a memory block of an RMT channel
+#[cfg(not(target_vendor = "espressif"))]
+#[cfg(any(
+    mock_chip = "esp32s3",
+    mock_chip = "esp32c3",
+    mock_chip = "esp32c6",
+    mock_chip = "esp32h2"
+))]
+pub const RMT_MEM_BLOCK_SYMBOLS: usize = 48;
+/// Number of the RMT items of a memory block of an RMT channel
+#[cfg(not(target_vendor = "espressif"))]
+#[cfg(not(any(
+    mock_chip = "esp32s3",
+    mock_chip = "esp32c3",
+    mock_chip = "esp32c6",
+    mock_chip = "esp32h2"
+)))]
+pub const RMT_MEM_BLOCK_SYMBOLS: usize = 64;
+
+/// Backend configuration of [`estimate_memory()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// RMT driver encoding the frame on the fly into the RMT memory, e.g.
+    /// `Ws2812Esp32RmtDriver::write_blocking()`
+    Rmt {
+        /// Number of the memory blocks of the RMT channel
+        mem_block_num: u8,
+    },
+    /// RMT driver transmitting the frame encoded in advance, e.g. the `EncodedFrame` of the
+    /// split driver, or `write_blocking()` with `bench` feature
+    RmtPreEncoded {
+        /// Number of the memory blocks of the RMT channel
+        mem_block_num: u8,
+    },
+    /// SPI driver
+    Spi {
+        /// Number of the SPI bits per WS2812 bit, e.g. 3 of `SpiBitEncoding::ThreeBits`
+        bits: usize,
+    },
+    /// Parallel driver, the LED pixels evenly distributed to the lanes
+    Parallel {
+        /// Number of the lanes
+        lanes: usize,
+    },
+}
+
+/// Memory requirements returned by [`estimate_memory()`], in bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct MemoryEstimate {
+    /// RAM of the pixel data and the encoded frame
+    pub ram: usize,
+    /// DMA-capable internal RAM of the transfer buffer
+    pub dma: usize,
+    /// RMT memory of the channel
+    pub rmt: usize,
+}
+
+impl MemoryEstimate {
+    /// Returns the total of the RAM and the DMA buffer, i.e. the heap taken from the chip.
+    ///
+    /// The RMT memory is dedicated to the peripheral, so it is not included.
+    #[inline]
+    pub const fn total(&self) -> usize {
+        self.ram + self.dma
+    }
+}
+
+/// Returns the byte count of the RMT memory of `mem_block_num` memory blocks.
+const fn rmt_memory(mem_block_num: u8) -> usize {
+    mem_block_num as usize * RMT_MEM_BLOCK_SYMBOLS * RMT_SYMBOL_SIZE
+}
+
+/// Estimates the memory requirements of `pixel_len` LED pixels of `bpp` bytes on `backend`.
+///
+/// The RAM includes the pixel data of a frame buffer, e.g. `LedPixelFrameBuffer`, in addition to
+/// the buffers of the backend. The bookkeeping of the drivers and the allocator is not included.
+pub const fn estimate_memory(pixel_len: usize, bpp: usize, backend: Backend) -> MemoryEstimate {
+    let len = pixel_len * bpp;
+    match backend {
+        Backend::Rmt { mem_block_num } => MemoryEstimate {
+            ram: len,
+            dma: 0,
+            rmt: rmt_memory(mem_block_num),
+        },
+        Backend::RmtPreEncoded { mem_block_num } => MemoryEstimate {
+            ram: len + len * 8 * RMT_SYMBOL_SIZE,
+            dma: 0,
+            rmt: rmt_memory(mem_block_num),
+        },
+        Backend::Spi { bits } => MemoryEstimate {
+            ram: len,
+            dma: (len * 8 * bits).div_ceil(8),
+            rmt: 0,
+        },
+        Backend::Parallel { lanes } => {
+            let lanes = if lanes == 0 { 1 } else { lanes };
+            let word_len = if lanes > 8 { 2 } else { 1 };
+            let lane_len = pixel_len.div_ceil(lanes) * bpp;
+            MemoryEstimate {
+                ram: len,
+                dma: lane_len * 8 * 3 * word_len,
+                rmt: 0,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_estimate_memory() {
+        let rmt = estimate_memory(100, 3, Backend::Rmt { mem_block_num: 2 });
+        assert_eq!(rmt.ram, 300);
+        assert_eq!(rmt.rmt, 2 * RMT_MEM_BLOCK_SYMBOLS * 4);
+        assert_eq!(rmt.total(), 300);
+
+        let pre_encoded = estimate_memory(100, 3, Backend::RmtPreEncoded { mem_block_num: 1 });
+        assert_eq!(pre_encoded.ram, 300 + 300 * 8 * 4);
+
+        let spi = estimate_memory(100, 4, Backend::Spi { bits: 3 });
+        assert_eq!(
+            spi,
+            MemoryEstimate {
+                ram: 400,
+                dma: 1200,
+                rmt: 0
+            }
+        );
+
+        let parallel = estimate_memory(100, 3, Backend::Parallel { lanes: 16 });
+        assert_eq!(parallel.dma, 7 * 3 * 8 * 3 * 2);
+    }
+
+    #[cfg(feature = "spi")]
+    #[test]
+    fn test_estimate_memory_spi_encoding() {
+        use crate::driver::SpiBitEncoding;
+
+        for encoding in [SpiBitEncoding::ThreeBits, SpiBitEncoding::FourBits] {
+            let spi = estimate_memory(
+                25,
+                3,
+                Backend::Spi {
+                    bits: encoding.bits(),
+                },
+            );
+            assert_eq!(spi.dma, encoding.encoded_len(75));
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_estimate_memory_parallel_encoding() {
+        use crate::driver::parallel_encoded_len;
+
+        let parallel = estimate_memory(8 * 30, 3, Backend::Parallel { lanes: 8 });
+        assert_eq!(parallel.dma, parallel_encoded_len(8, 90));
+    }
+}
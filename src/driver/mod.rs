@@ -1,7 +1,82 @@
 //! Low-level LED pixel driver API.
 
+#[cfg(all(feature = "bench", not(target_os = "none")))]
+pub mod bench;
+pub mod brightness;
+pub mod buffer;
+pub mod calibration;
+pub mod chain;
+#[cfg(not(target_os = "none"))]
+pub mod channel;
 pub mod color;
+#[cfg(not(target_os = "none"))]
+pub mod diagnostics;
+#[cfg(all(feature = "apa102", not(target_os = "none")))]
+mod esp32_apa102;
+#[cfg(all(feature = "parallel", not(target_os = "none")))]
+mod esp32_parallel;
+#[cfg(not(target_os = "none"))]
 mod esp32_rmt;
+#[cfg(all(feature = "rmt-tx", not(target_os = "none")))]
+mod esp32_rmt_tx;
+#[cfg(all(feature = "spi", not(target_os = "none")))]
+mod esp32_spi;
+#[cfg(feature = "esp-hal")]
+mod esp_hal_rmt;
+pub mod gamma;
+pub mod memory;
+pub mod power;
+#[cfg(all(feature = "self-test", not(target_os = "none")))]
+pub mod self_test;
+#[cfg(all(feature = "alloc", not(target_os = "none")))]
+pub mod split;
+pub mod timing;
+pub(crate) mod trace;
+#[cfg(all(
+    target_vendor = "espressif",
+    not(target_os = "none"),
+    feature = "alloc"
+))]
+mod tx_done;
+mod write;
 
+#[cfg(all(feature = "apa102", not(target_os = "none")))]
+pub use esp32_apa102::{
+    apa102_encoded_len, encode_apa102, Apa102Esp32SpiDriver, Apa102Esp32SpiDriverError,
+    APA102_MAX_GLOBAL_BRIGHTNESS,
+};
+#[cfg(all(feature = "parallel", not(target_os = "none")))]
+pub use esp32_parallel::{
+    encode_lanes, parallel_encoded_len, Ws2812Esp32ParallelDriver, Ws2812Esp32ParallelDriverError,
+    PARALLEL_CLOCK_HZ,
+};
+#[cfg(not(target_os = "none"))]
 pub use esp32_rmt::Ws2812Esp32RmtDriver;
-pub use esp32_rmt::Ws2812Esp32RmtDriverError;
+#[cfg(not(target_os = "none"))]
+pub use esp32_rmt::{Ws2812Esp32RmtDriverConfig, Ws2812Esp32RmtDriverError};
+#[cfg(all(feature = "rmt-tx", not(target_os = "none")))]
+pub use esp32_rmt_tx::{Ws2812Esp32RmtTxDriver, Ws2812Esp32RmtTxDriverError};
+#[cfg(all(feature = "spi", not(target_os = "none")))]
+pub use esp32_spi::{SpiBitEncoding, Ws2812Esp32SpiDriver, Ws2812Esp32SpiDriverError};
+#[cfg(feature = "esp-hal")]
+pub use esp_hal_rmt::encode_pulses;
+#[cfg(all(feature = "esp-hal", target_os = "none"))]
+pub use esp_hal_rmt::{Ws2812EspHalRmtDriver, Ws2812EspHalRmtDriverError};
+pub use timing::ChipTiming;
+#[cfg(feature = "esp-hal")]
+pub use timing::RmtBitTicks;
+pub use write::{LedStripDriver, LedStripWrite};
+
+/// LED driver backend of the wrappers unless specified.
+///
+/// This is [`Ws2812Esp32RmtDriver`] on ESP-IDF (and in the mock), and `Ws2812EspHalRmtDriver`
+/// on the bare-metal targets.
+#[cfg(not(target_os = "none"))]
+pub type DefaultLedDriver<'d> = Ws2812Esp32RmtDriver<'d>;
+
+/// LED driver backend of the wrappers unless specified.
+///
+/// This is `Ws2812Esp32RmtDriver` on ESP-IDF (and in the mock), and [`Ws2812EspHalRmtDriver`]
+/// on the bare-metal targets.
+#[cfg(all(feature = "esp-hal", target_os = "none"))]
+pub type DefaultLedDriver<'d> = Ws2812EspHalRmtDriver<'d>;
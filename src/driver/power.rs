@@ -0,0 +1,118 @@
+//! Current draw estimation.
+
+/// Current draw model of the LED pixels.
+///
+/// The current draw of a channel is assumed to be proportional to its value,
+/// which is a good approximation for the constant current drivers of the LED pixels.
+///
+/// # Examples
+///
+/// ```
+/// use ws2812_esp32_rmt_driver::driver::power::CurrentModel;
+///
+/// // 10 LEDs of 8-bit GRB, full white
+/// let model = CurrentModel::WS2812;
+/// assert_eq!(model.estimate_ma(&[0xFF; 30]), 600);
+/// // half brightness red
+/// assert_eq!(model.estimate_ma(&[0x00, 0x80, 0x00].repeat(10)), 101);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CurrentModel {
+    /// Current draw of a channel at the value 255 in milliamperes
+    pub channel_ma: u32,
+}
+
+impl CurrentModel {
+    /// WS2812 (5050 package) current draw: 20 mA per channel
+    pub const WS2812: Self = Self { channel_ma: 20 };
+
+    /// Estimates the total current draw of the pixel data in milliamperes, rounded up.
+    ///
+    /// The pixel data is a pixel-byte sequence as written by the driver,
+    /// so that byte count per LED pixel and channel order do not matter.
+    pub fn estimate_ma<'a, T>(&self, pixel_data: T) -> u32
+    where
+        T: IntoIterator<Item = &'a u8>,
+    {
+        let channel_sum = pixel_data.into_iter().map(|&v| u64::from(v)).sum();
+        self.channel_sum_ma(channel_sum)
+    }
+
+    /// Returns the brightness, at most `brightness`, that keeps the total current draw of the
+    /// pixel data within `limit_ma` milliamperes when the channel values are scaled to
+    /// `(brightness + 1) / 256`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ws2812_esp32_rmt_driver::driver::power::CurrentModel;
+    ///
+    /// // 10 LEDs of full white draw 600 mA.
+    /// let model = CurrentModel::WS2812;
+    /// assert_eq!(model.limit_brightness(&[0xFF; 30], 255, 300), 127);
+    /// assert_eq!(model.limit_brightness(&[0xFF; 30], 63, 300), 63);
+    /// ```
+    pub fn limit_brightness<'a, T>(&self, pixel_data: T, brightness: u8, limit_ma: u32) -> u8
+    where
+        T: IntoIterator<Item = &'a u8>,
+    {
+        let channel_sum: u64 = pixel_data.into_iter().map(|&v| u64::from(v)).sum();
+        let full_ma = channel_sum * u64::from(self.channel_ma);
+        if full_ma == 0 {
+            return brightness;
+        }
+        // The maximum `brightness + 1` within the limit, flooring all the way
+        let max_scale = u64::from(limit_ma) * u64::from(u8::MAX) * 256 / full_ma;
+        if max_scale > u64::from(brightness) {
+            brightness
+        } else {
+            max_scale.saturating_sub(1) as u8
+        }
+    }
+
+    /// Returns the current draw of the sum of the channel values in milliamperes, rounded up.
+    pub(crate) fn channel_sum_ma(&self, channel_sum: u64) -> u32 {
+        let ma = (channel_sum * u64::from(self.channel_ma)).div_ceil(u8::MAX as u64);
+        ma.min(u32::MAX as u64) as u32
+    }
+}
+
+impl Default for CurrentModel {
+    fn default() -> Self {
+        Self::WS2812
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_current_model() {
+        let model = CurrentModel::WS2812;
+        assert_eq!(model.estimate_ma(&[]), 0);
+        assert_eq!(model.estimate_ma(&[0x00; 30]), 0);
+        assert_eq!(model.estimate_ma(&[0x01]), 1);
+        assert_eq!(model.estimate_ma(&[0xFF, 0xFF, 0xFF]), 60);
+
+        let model = CurrentModel { channel_ma: 12 };
+        assert_eq!(model.estimate_ma(&[0xFF; 4]), 48);
+        assert_eq!(model.estimate_ma([0xFF; 4].iter()), 48);
+    }
+
+    #[test]
+    fn test_current_model_limit_brightness() {
+        let model = CurrentModel::WS2812;
+        assert_eq!(model.limit_brightness(&[], 255, 0), 255);
+        assert_eq!(model.limit_brightness(&[0x00; 30], 255, 0), 255);
+        assert_eq!(model.limit_brightness(&[0xFF; 30], 255, 600), 255);
+        assert_eq!(model.limit_brightness(&[0xFF; 30], 255, 0), 0);
+
+        let data = [0xFF; 30];
+        let brightness = model.limit_brightness(&data, 255, 300);
+        let scaled = data.map(|v| crate::driver::brightness::scale(v, brightness));
+        assert!(model.estimate_ma(&scaled) <= 300);
+        let scaled = data.map(|v| crate::driver::brightness::scale(v, brightness + 1));
+        assert!(model.estimate_ma(&scaled) > 300);
+    }
+}
@@ -0,0 +1,209 @@
+//! RMT RX loopback self-test.
+//!
+//! The output pin of the driver is wired to another GPIO that is received by an RMT RX channel.
+//! A known pattern is transmitted, and the received pulses are decoded and checked whether the
+//! timings are within the WS2812 specification.
+
+use super::esp32_rmt::{WS2812_T0H_NS, WS2812_T0L_NS, WS2812_T1H_NS, WS2812_T1L_NS};
+use core::time::Duration;
+
+#[cfg(target_vendor = "espressif")]
+use super::esp32_rmt::{Ws2812Esp32RmtDriver, Ws2812Esp32RmtDriverError};
+#[cfg(all(not(feature = "std"), feature = "alloc", target_vendor = "espressif"))]
+use alloc::vec;
+#[cfg(target_vendor = "espressif")]
+use esp_idf_hal::{
+    delay::TickType,
+    gpio::InputPin,
+    peripheral::Peripheral,
+    rmt::{config::ReceiveConfig, PinState, Pulse, Receive, RmtChannel, RxRmtDriver},
+};
+
+/// Acceptable deviation of each pulse duration (WS2812 datasheet: ±150ns)
+pub const WS2812_TOLERANCE_NS: Duration = Duration::from_nanos(150);
+
+/// RX idle threshold in ticks (10us at 80MHz) that terminates the reception.
+#[cfg(target_vendor = "espressif")]
+const RX_IDLE_THRESHOLD_TICKS: u16 = 800;
+/// RX filter threshold in ticks (125ns at 80MHz) to ignore glitches.
+#[cfg(target_vendor = "espressif")]
+const RX_FILTER_TICKS: u8 = 10;
+/// Timeout to wait for the received pulses.
+#[cfg(target_vendor = "espressif")]
+const RX_TIMEOUT_MS: u64 = 100;
+
+/// Result of the loopback self-test.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelfTestReport {
+    /// The number of bits transmitted.
+    pub expected_bits: usize,
+    /// The number of bits received.
+    pub received_bits: usize,
+    /// The number of received bits that do not match with the transmitted ones.
+    pub bit_errors: usize,
+    /// The number of received bits whose pulse durations are out of the tolerance.
+    pub timing_violations: usize,
+    /// The largest deviation of the pulse durations from the specification.
+    pub max_deviation: Duration,
+}
+
+impl SelfTestReport {
+    /// Returns `true` if all the bits are received correctly within the timing specification.
+    pub fn passed(&self) -> bool {
+        self.expected_bits == self.received_bits
+            && self.bit_errors == 0
+            && self.timing_violations == 0
+    }
+}
+
+/// Analyzes the received pulses against the transmitted `pattern`.
+///
+/// * `pulses` - pairs of the high and low durations in ticks, one per bit.
+/// * `clock_hz` - the counter clock frequency of the RX channel.
+///
+/// The low duration of the last bit is not checked if it is zero
+/// because the receiver terminates the last pulse on the idle (reset) state.
+pub fn analyze_pulses<I>(pattern: &[u8], pulses: I, clock_hz: u32) -> SelfTestReport
+where
+    I: IntoIterator<Item = (u16, u16)>,
+{
+    let to_duration =
+        |ticks: u16| Duration::from_nanos(ticks as u64 * 1_000_000_000 / clock_hz as u64);
+    let expected_bits = pattern.len() * u8::BITS as usize;
+
+    let mut report = SelfTestReport {
+        expected_bits,
+        ..Default::default()
+    };
+    for (i, (high, low)) in pulses.into_iter().enumerate() {
+        report.received_bits += 1;
+        let (high, low) = (to_duration(high), to_duration(low));
+
+        let bit = high.abs_diff(WS2812_T1H_NS) < high.abs_diff(WS2812_T0H_NS);
+        let expected = pattern
+            .get(i / 8)
+            .map(|v| v & (1 << (7 - i % 8)) != 0)
+            .unwrap_or(false);
+        if i >= expected_bits || bit != expected {
+            report.bit_errors += 1;
+        }
+
+        let (spec_high, spec_low) = if bit {
+            (WS2812_T1H_NS, WS2812_T1L_NS)
+        } else {
+            (WS2812_T0H_NS, WS2812_T0L_NS)
+        };
+        let mut deviation = high.abs_diff(spec_high);
+        if !(low.is_zero() && i + 1 == expected_bits) {
+            deviation = deviation.max(low.abs_diff(spec_low));
+        }
+        if deviation > WS2812_TOLERANCE_NS {
+            report.timing_violations += 1;
+        }
+        report.max_deviation = report.max_deviation.max(deviation);
+    }
+    report
+}
+
+#[cfg(target_vendor = "espressif")]
+impl Ws2812Esp32RmtDriver<'_> {
+    /// Runs the loopback self-test.
+    ///
+    /// `rx_pin` shall be physically connected to the output pin of this driver,
+    /// and `rx_channel` shall be an RX-capable RMT channel different from the one of this driver.
+    /// `pattern` is transmitted as pixel data, so the connected LEDs also show it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an RMT driver error occurred.
+    pub fn self_test<C: RmtChannel>(
+        &mut self,
+        rx_channel: impl Peripheral<P = C>,
+        rx_pin: impl Peripheral<P = impl InputPin>,
+        pattern: &[u8],
+    ) -> Result<SelfTestReport, Ws2812Esp32RmtDriverError> {
+        let rx_error = Ws2812Esp32RmtDriverError::Receive;
+        // The RX channel counts at the source clock of the TX channel.
+        let clock_hz = self.source_clock_hz;
+        let bits = pattern.len() * u8::BITS as usize;
+
+        let config = ReceiveConfig::new()
+            .clock_divider(1)
+            .idle_threshold(RX_IDLE_THRESHOLD_TICKS)
+            .filter_ticks_thresh(RX_FILTER_TICKS);
+        // Each RMT item takes 4 bytes.
+        let mut rx =
+            RxRmtDriver::new(rx_channel, rx_pin, &config, (bits + 1) * 4).map_err(rx_error)?;
+        rx.start().map_err(rx_error)?;
+
+        self.write_blocking(pattern.iter().copied())?;
+
+        let mut buf = vec![(Pulse::zero(), Pulse::zero()); bits + 1];
+        let len = match rx
+            .receive(&mut buf, TickType::new_millis(RX_TIMEOUT_MS).ticks())
+            .map_err(rx_error)?
+        {
+            Receive::Read(len) | Receive::Overflow(len) => len,
+            Receive::Timeout => 0,
+        };
+        rx.stop().map_err(rx_error)?;
+
+        let pulses = buf[..len].iter().map(|(p0, p1)| {
+            if p0.pin_state == PinState::High {
+                (p0.ticks.ticks(), p1.ticks.ticks())
+            } else {
+                (p1.ticks.ticks(), 0)
+            }
+        });
+        Ok(analyze_pulses(pattern, pulses, clock_hz))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Counter clock frequency at 80MHz APB clock with clock divider 1
+    const CLOCK_HZ: u32 = 80_000_000;
+
+    /// Returns the pulse of the bit in ticks at [`CLOCK_HZ`].
+    fn pulse(bit: bool) -> (u16, u16) {
+        if bit {
+            (64, 36)
+        } else {
+            (32, 68)
+        }
+    }
+
+    #[test]
+    fn test_analyze_pulses() {
+        let pattern = [0b1010_0000];
+        let pulses = (0..8).map(|i| pulse(pattern[0] & (1 << (7 - i)) != 0));
+        let report = analyze_pulses(&pattern, pulses, CLOCK_HZ);
+        assert_eq!(report.expected_bits, 8);
+        assert_eq!(report.received_bits, 8);
+        assert_eq!(report.bit_errors, 0);
+        assert_eq!(report.timing_violations, 0);
+        assert_eq!(report.max_deviation, Duration::ZERO);
+        assert!(report.passed());
+    }
+
+    #[test]
+    fn test_analyze_pulses_last_bit_low_is_idle() {
+        let pattern = [0xFF];
+        let pulses = (0..8).map(|i| if i == 7 { (64, 0) } else { pulse(true) });
+        assert!(analyze_pulses(&pattern, pulses, CLOCK_HZ).passed());
+    }
+
+    #[test]
+    fn test_analyze_pulses_errors() {
+        let pattern = [0x00];
+        let pulses = [pulse(false), pulse(true), (32, 100), pulse(false)];
+        let report = analyze_pulses(&pattern, pulses, CLOCK_HZ);
+        assert_eq!(report.received_bits, 4);
+        assert_eq!(report.bit_errors, 1);
+        assert_eq!(report.timing_violations, 1);
+        assert_eq!(report.max_deviation, Duration::from_nanos(400));
+        assert!(!report.passed());
+    }
+}
@@ -0,0 +1,492 @@
+//! Split of the driver into the control and the writer handles.
+//!
+//! [`Ws2812Esp32RmtDriver::split()`] splits the driver into two handles: a [`Control`] that
+//! borrows the driver, encodes the frames into [`EncodedFrame`]s and holds the configuration and
+//! the statistics, and a small owned [`FrameWriter`] that only starts the transmission of an
+//! encoded frame. The writer neither blocks, encodes, allocates nor traces, so the real-time
+//! code, e.g. an interrupt handler or a timer callback, triggers the frames prepared in the task
+//! context.
+//!
+//! The writer copies the frame into the RMT memory of the channel and starts the transmission
+//! by itself, so the frame shall fit in the RMT memory; see [`Control::frame_capacity()`].
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::driver::{Ws2812Esp32RmtDriver, Ws2812Esp32RmtDriverConfig};
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let config = Ws2812Esp32RmtDriverConfig::new().with_mem_block_num(2);
+//! let mut driver = Ws2812Esp32RmtDriver::new_with_config(
+//!     peripherals.rmt.channel0,
+//!     peripherals.pins.gpio0,
+//!     &config,
+//! )
+//! .unwrap();
+//!
+//! let (mut writer, mut control) = driver.split();
+//! // Task context: encode the frame once.
+//! let frame = control.encode([0, 30, 0].into_iter()).unwrap();
+//! // Interrupt context: start the transmission, or skip it if the channel is busy.
+//! let _ = writer.start(&frame);
+//! # #[cfg(not(target_vendor = "espressif"))]
+//! # assert_eq!(writer.pixel_data, Some(vec![0, 30, 0]));
+//! ```
+
+use super::esp32_rmt::{frame_too_long_error, Ws2812Esp32RmtDriver, Ws2812Esp32RmtDriverError};
+use super::memory::RMT_MEM_BLOCK_SYMBOLS;
+use super::power::CurrentModel;
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use esp_idf_sys::EspError;
+
+#[cfg(target_vendor = "espressif")]
+use super::esp32_rmt::Ws2812Esp32RmtItemEncoder;
+#[cfg(target_vendor = "espressif")]
+use esp_idf_hal::rmt::Symbol;
+#[cfg(target_vendor = "espressif")]
+use esp_idf_sys::rmt_item32_t;
+
+#[cfg(not(target_vendor = "espressif"))]
+use crate::mock::esp_idf_sys;
+
+/// Busy flag of the channel, set by [`FrameWriter::start()`] and cleared at the end of the
+/// transmission
+#[cfg(target_vendor = "espressif")]
+type BusyFlag = &'static AtomicBool;
+/// Busy flag of the channel, set by [`FrameWriter::start()`] and cleared at the end of the
+/// transmission
+#[cfg(not(target_vendor = "espressif"))]
+type BusyFlag = std::sync::Arc<AtomicBool>;
+
+/// End marker of the RMT items in the RMT memory
+#[cfg(target_vendor = "espressif")]
+static END_MARKER: rmt_item32_t = unsafe { core::mem::zeroed() };
+
+/// Returns the number of the RMT items of `mem_block_num` memory blocks.
+const fn mem_symbols(mem_block_num: u8) -> usize {
+    mem_block_num as usize * RMT_MEM_BLOCK_SYMBOLS
+}
+
+/// Returns the error of the busy channel.
+///
+/// It is not traced, as the writer may run in an interrupt handler.
+fn would_block_error() -> Ws2812Esp32RmtDriverError {
+    #[cfg(target_vendor = "espressif")]
+    let source = EspError::from_infallible::<{ esp_idf_sys::ESP_ERR_TIMEOUT }>();
+    #[cfg(not(target_vendor = "espressif"))]
+    let source = EspError();
+    Ws2812Esp32RmtDriverError::WouldBlock(source)
+}
+
+/// Frame encoded by [`Control::encode()`], ready to be transmitted by [`FrameWriter::start()`].
+#[derive(Debug, Clone)]
+pub struct EncodedFrame {
+    /// RMT items of the frame
+    #[cfg(target_vendor = "espressif")]
+    symbols: Vec<Symbol>,
+    /// Pixel data of the frame
+    #[cfg(not(target_vendor = "espressif"))]
+    pixel_data: Vec<u8>,
+    /// Byte count of the pixel data
+    len: usize,
+}
+
+impl EncodedFrame {
+    /// Returns the byte count of the pixel data.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the frame has no pixel data.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Writer handle of a split driver, starting the transmissions of the encoded frames.
+///
+/// It owns no borrow of the driver, only the channel number and the busy flag of the channel,
+/// so it is moved into a `'static` context, e.g. an interrupt handler. Do not start a
+/// transmission after the driver is dropped, nor write with the driver while
+/// [`Self::is_busy()`].
+pub struct FrameWriter {
+    channel: u32,
+    busy: BusyFlag,
+    mem_block_num: u8,
+    suspended: bool,
+    /// Pixel data of the frame last started
+    ///
+    /// If the target vendor does not equals to "espressif", the frame is written into this
+    /// instead of the RMT memory.
+    #[cfg(not(target_vendor = "espressif"))]
+    pub pixel_data: Option<Vec<u8>>,
+}
+
+impl FrameWriter {
+    /// Starts the transmission of `frame` without waiting for its completion.
+    ///
+    /// `frame` is copied into the RMT memory of the channel before this returns, so it may be
+    /// dropped or reused meanwhile. This never blocks: if the transmission of the previous frame
+    /// is in progress, `frame` is rejected, and the caller skips or retries it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Ws2812Esp32RmtDriverError::WouldBlock`] if the channel is busy, or an error if
+    /// the driver is suspended, `frame` does not fit in the RMT memory of the channel, or an
+    /// RMT driver error occurred.
+    pub fn start(&mut self, frame: &EncodedFrame) -> Result<(), Ws2812Esp32RmtDriverError> {
+        if self.suspended {
+            return Err(Ws2812Esp32RmtDriverError::Suspend(
+                super::esp32_rmt::invalid_state_error(),
+            ));
+        }
+        if frame.len > frame_capacity(self.mem_block_num) {
+            #[cfg(target_vendor = "espressif")]
+            let source = EspError::from_infallible::<{ esp_idf_sys::ESP_ERR_INVALID_SIZE }>();
+            #[cfg(not(target_vendor = "espressif"))]
+            let source = EspError();
+            return Err(Ws2812Esp32RmtDriverError::FrameTooLong {
+                max_len: frame_capacity(self.mem_block_num),
+                source,
+            });
+        }
+        if self.busy.swap(true, Ordering::Acquire) {
+            return Err(would_block_error());
+        }
+        #[cfg(target_vendor = "espressif")]
+        {
+            use esp_idf_sys::{esp, rmt_fill_tx_items, rmt_tx_start};
+
+            let symbols = frame.symbols.as_slice();
+            // SAFETY: `Symbol` is a transparent wrapper of `rmt_item32_t`, and the items with
+            // the end marker fit in the RMT memory as checked above.
+            let result = esp!(unsafe {
+                rmt_fill_tx_items(
+                    self.channel,
+                    symbols.as_ptr().cast(),
+                    symbols.len() as u16,
+                    0,
+                )
+            })
+            .and_then(|()| {
+                esp!(unsafe {
+                    rmt_fill_tx_items(self.channel, &END_MARKER, 1, symbols.len() as u16)
+                })
+            })
+            .and_then(|()| esp!(unsafe { rmt_tx_start(self.channel, true) }));
+            result.map_err(|source| {
+                self.busy.store(false, Ordering::Release);
+                Ws2812Esp32RmtDriverError::Transmit {
+                    byte_offset: 0,
+                    source,
+                }
+            })
+        }
+        #[cfg(not(target_vendor = "espressif"))]
+        {
+            let _ = self.channel;
+            self.pixel_data = Some(frame.pixel_data.clone());
+            Ok(())
+        }
+    }
+
+    /// Returns whether the transmission started by [`Self::start()`] is in progress.
+    #[inline]
+    pub fn is_busy(&self) -> bool {
+        self.busy.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(not(target_vendor = "espressif"))]
+impl FrameWriter {
+    /// Completes the transmission in progress, as the TX end interrupt does.
+    ///
+    /// The transmission started by [`Self::start()`] is in progress until this is called.
+    ///
+    /// This function is only available in the mock.
+    pub fn end_transmission(&mut self) {
+        self.busy.store(false, Ordering::Release);
+    }
+}
+
+/// Returns the byte count of the pixel data fitting in `mem_block_num` memory blocks with the
+/// reset code and the end marker.
+const fn frame_capacity(mem_block_num: u8) -> usize {
+    mem_symbols(mem_block_num).saturating_sub(2) / 8
+}
+
+/// Control handle of a split driver, encoding the frames and holding the configuration and the
+/// statistics.
+pub struct Control<'a> {
+    #[cfg(target_vendor = "espressif")]
+    encoder: &'a Ws2812Esp32RmtItemEncoder,
+    counter_clock_hz: u32,
+    mem_block_num: u8,
+    current_model: &'a mut CurrentModel,
+    channel_sum: &'a mut u64,
+    frame_len: &'a mut usize,
+    max_frame_len: &'a mut Option<usize>,
+}
+
+impl Control<'_> {
+    /// Encodes a pixel-byte sequence into a frame to be transmitted by [`FrameWriter::start()`].
+    ///
+    /// The statistics, e.g. [`Self::last_frame_current_ma()`], are of the last encoded frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `pixel_sequence` exceeds the cap of the byte count per frame, or
+    /// [`Self::frame_capacity()`].
+    pub fn encode<T>(
+        &mut self,
+        pixel_sequence: T,
+    ) -> Result<EncodedFrame, Ws2812Esp32RmtDriverError>
+    where
+        T: Iterator<Item = u8>,
+    {
+        let max_len = self
+            .max_frame_len
+            .unwrap_or(usize::MAX)
+            .min(self.frame_capacity());
+        if pixel_sequence.size_hint().0 > max_len {
+            return Err(frame_too_long_error(max_len));
+        }
+        let (mut channel_sum, mut len, mut overflow) = (0u64, 0usize, false);
+        let pixel_sequence = pixel_sequence.take_while(|&v| {
+            if len == max_len {
+                overflow = true;
+                return false;
+            }
+            channel_sum += u64::from(v);
+            len += 1;
+            true
+        });
+        #[cfg(target_vendor = "espressif")]
+        let symbols = self
+            .encoder
+            .encode_frame(pixel_sequence)
+            .collect::<Vec<_>>();
+        #[cfg(not(target_vendor = "espressif"))]
+        let pixel_data = pixel_sequence.collect::<Vec<_>>();
+        if overflow {
+            return Err(frame_too_long_error(max_len));
+        }
+        *self.channel_sum = channel_sum;
+        *self.frame_len = len;
+        Ok(EncodedFrame {
+            #[cfg(target_vendor = "espressif")]
+            symbols,
+            #[cfg(not(target_vendor = "espressif"))]
+            pixel_data,
+            len,
+        })
+    }
+
+    /// Returns the byte count of the pixel data of a frame fitting in the RMT memory of the
+    /// channel, i.e. the longest frame transmitted by [`FrameWriter::start()`].
+    ///
+    /// Increase the memory blocks of the channel by
+    /// [`Ws2812Esp32RmtDriverConfig::with_mem_block_num()`] for the longer frames.
+    ///
+    /// [`Ws2812Esp32RmtDriverConfig::with_mem_block_num()`]: super::Ws2812Esp32RmtDriverConfig::with_mem_block_num
+    #[inline]
+    pub fn frame_capacity(&self) -> usize {
+        frame_capacity(self.mem_block_num)
+    }
+
+    /// Returns the counter clock frequency of the RMT channel.
+    #[inline]
+    pub fn counter_clock_hz(&self) -> u32 {
+        self.counter_clock_hz
+    }
+
+    /// Sets the cap of the byte count per frame, or removes it by `None`.
+    ///
+    /// See [`Ws2812Esp32RmtDriver::set_max_frame_len()`].
+    #[inline]
+    pub fn set_max_frame_len(&mut self, max_len: Option<usize>) {
+        *self.max_frame_len = max_len;
+    }
+
+    /// Returns the cap of the byte count per frame.
+    #[inline]
+    pub fn max_frame_len(&self) -> Option<usize> {
+        *self.max_frame_len
+    }
+
+    /// Sets the current draw model used by [`Self::last_frame_current_ma()`].
+    #[inline]
+    pub fn set_current_model(&mut self, model: CurrentModel) {
+        *self.current_model = model;
+    }
+
+    /// Returns the estimated current draw of the last encoded frame in mA.
+    pub fn last_frame_current_ma(&self) -> u32 {
+        self.current_model.channel_sum_ma(*self.channel_sum)
+    }
+
+    /// Returns the byte count of the last encoded frame.
+    #[inline]
+    pub fn last_frame_len(&self) -> usize {
+        *self.frame_len
+    }
+}
+
+impl Ws2812Esp32RmtDriver<'_> {
+    /// Splits this driver into the writer handle and the control handle.
+    ///
+    /// The writer only starts the transmissions of the frames encoded by the control, so it is
+    /// handed over to the real-time context while the control lives in the task context. The
+    /// driver is available again when the control is dropped, with the statistics of the last
+    /// encoded frame.
+    ///
+    /// The busy flag of the writer is cleared by the TX end callback of the legacy RMT driver,
+    /// which is registered at the first call; do not register another one by
+    /// `rmt_register_tx_end_callback()`.
+    pub fn split(&mut self) -> (FrameWriter, Control<'_>) {
+        let channel = self.tx.channel();
+        #[cfg(target_vendor = "espressif")]
+        let busy = {
+            super::tx_done::register();
+            super::tx_done::busy_flag(channel)
+        };
+        #[cfg(not(target_vendor = "espressif"))]
+        let busy = BusyFlag::default();
+        let writer = FrameWriter {
+            channel,
+            busy,
+            mem_block_num: self.mem_block_num,
+            suspended: self.suspended,
+            #[cfg(not(target_vendor = "espressif"))]
+            pixel_data: None,
+        };
+        let control = Control {
+            #[cfg(target_vendor = "espressif")]
+            encoder: &self.encoder,
+            counter_clock_hz: self.counter_clock_hz,
+            mem_block_num: self.mem_block_num,
+            current_model: &mut self.current_model,
+            channel_sum: &mut self.channel_sum,
+            frame_len: &mut self.frame_len,
+            max_frame_len: &mut self.max_frame_len,
+        };
+        (writer, control)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::Ws2812Esp32RmtDriverConfig;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+
+    #[test]
+    fn test_split() {
+        let peripherals = Peripherals::take().unwrap();
+        let mut driver = Ws2812Esp32RmtDriver::new_with_config(
+            peripherals.rmt.channel0,
+            peripherals.pins.gpio0,
+            &Ws2812Esp32RmtDriverConfig::new().with_mem_block_num(2),
+        )
+        .unwrap();
+
+        let (mut writer, mut control) = driver.split();
+        assert_eq!(
+            control.frame_capacity(),
+            (2 * RMT_MEM_BLOCK_SYMBOLS - 2) / 8
+        );
+        control.set_max_frame_len(Some(6));
+        let frame = control.encode([255, 0, 0].into_iter()).unwrap();
+        assert_eq!(frame.len(), 3);
+        assert!(control.encode([0; 7].into_iter()).is_err());
+        assert_eq!(control.last_frame_len(), 3);
+        assert!(control.last_frame_current_ma() > 0);
+
+        let writer = std::thread::spawn(move || {
+            writer.start(&frame).unwrap();
+            writer
+        })
+        .join()
+        .unwrap();
+        assert_eq!(writer.pixel_data, Some(vec![255, 0, 0]));
+        assert!(writer.is_busy());
+
+        assert_eq!(driver.max_frame_len(), Some(6));
+        driver.write_blocking([0; 6].into_iter()).unwrap();
+    }
+
+    #[test]
+    fn test_split_busy() {
+        let peripherals = Peripherals::take().unwrap();
+        let mut driver =
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+
+        let (mut writer, mut control) = driver.split();
+        let frame1 = control.encode([1; 3].into_iter()).unwrap();
+        let frame2 = control.encode([2; 3].into_iter()).unwrap();
+
+        writer.start(&frame1).unwrap();
+        // Rejected, not waited for
+        assert!(matches!(
+            writer.start(&frame2),
+            Err(Ws2812Esp32RmtDriverError::WouldBlock(_))
+        ));
+        assert_eq!(writer.pixel_data, Some(vec![1; 3]));
+
+        writer.end_transmission();
+        assert!(!writer.is_busy());
+        writer.start(&frame2).unwrap();
+        assert_eq!(writer.pixel_data, Some(vec![2; 3]));
+    }
+
+    #[test]
+    fn test_split_frame_capacity() {
+        let peripherals = Peripherals::take().unwrap();
+        let mut driver =
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+
+        let (mut writer, mut control) = driver.split();
+        let capacity = control.frame_capacity();
+        assert!(matches!(
+            control.encode(core::iter::repeat_n(0, capacity + 1)),
+            Err(Ws2812Esp32RmtDriverError::FrameTooLong { max_len, .. }) if max_len == capacity
+        ));
+        let frame = control.encode(core::iter::repeat_n(0, capacity)).unwrap();
+        writer.start(&frame).unwrap();
+
+        // Encoded by a driver of more memory blocks
+        let frame = EncodedFrame {
+            pixel_data: vec![0; capacity + 1],
+            len: capacity + 1,
+        };
+        writer.end_transmission();
+        assert!(matches!(
+            writer.start(&frame),
+            Err(Ws2812Esp32RmtDriverError::FrameTooLong { .. })
+        ));
+        assert!(!writer.is_busy());
+    }
+
+    #[test]
+    fn test_split_suspended() {
+        let peripherals = Peripherals::take().unwrap();
+        let mut driver =
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+        driver.suspend().unwrap();
+
+        let (mut writer, mut control) = driver.split();
+        let frame = control.encode([0; 3].into_iter()).unwrap();
+        assert!(matches!(
+            writer.start(&frame),
+            Err(Ws2812Esp32RmtDriverError::Suspend(_))
+        ));
+    }
+}
@@ -0,0 +1,470 @@
+//! LED signal timing and frame rate estimation.
+//!
+//! The RMT drivers request a counter resolution ([`DEFAULT_RESOLUTION_HZ`]) rather than a clock
+//! divider: the divider is derived from the source clock of the chip by [`clock_divider()`], and
+//! the pulse ticks ([`RmtBitTicks`]) from the actual counter clock, so that the waveforms are in
+//! spec regardless of the APB frequency of the chip.
+//!
+//! The timings and the intervals are accepted as any [`IntoDuration`], i.e. [`Duration`] or, with
+//! the `fugit` feature, the `fugit` durations, so that they share the units with the rest of an
+//! embedded project.
+
+use super::esp32_rmt::{WS2812_T0H_NS, WS2812_T0L_NS, WS2812_T1H_NS, WS2812_T1L_NS};
+use core::time::Duration;
+
+/// Conversion of the duration types into [`Duration`].
+///
+/// It is implemented for [`Duration`] and, with the `fugit` feature, for the `fugit` durations
+/// of `u32` and `u64` ticks.
+///
+/// # Examples
+///
+/// ```
+/// use ws2812_esp32_rmt_driver::driver::timing::IntoDuration;
+/// use core::time::Duration;
+///
+/// assert_eq!(Duration::from_micros(50).into_duration(), Duration::from_micros(50));
+/// # #[cfg(feature = "fugit")]
+/// # {
+/// use fugit::MicrosDurationU32;
+///
+/// assert_eq!(
+///     MicrosDurationU32::from_ticks(50).into_duration(),
+///     Duration::from_micros(50)
+/// );
+/// # }
+/// ```
+pub trait IntoDuration {
+    /// Converts into [`Duration`].
+    fn into_duration(self) -> Duration;
+}
+
+impl IntoDuration for Duration {
+    #[inline]
+    fn into_duration(self) -> Duration {
+        self
+    }
+}
+
+#[cfg(feature = "fugit")]
+impl<const NOM: u32, const DENOM: u32> IntoDuration for fugit::Duration<u32, NOM, DENOM> {
+    #[inline]
+    fn into_duration(self) -> Duration {
+        ticks_to_duration(u64::from(self.ticks()), NOM, DENOM)
+    }
+}
+
+#[cfg(feature = "fugit")]
+impl<const NOM: u32, const DENOM: u32> IntoDuration for fugit::Duration<u64, NOM, DENOM> {
+    #[inline]
+    fn into_duration(self) -> Duration {
+        ticks_to_duration(self.ticks(), NOM, DENOM)
+    }
+}
+
+/// Returns the duration of `ticks` of `nom / denom` seconds, saturating.
+#[cfg(feature = "fugit")]
+fn ticks_to_duration(ticks: u64, nom: u32, denom: u32) -> Duration {
+    let nanos = u128::from(ticks) * u128::from(nom) * 1_000_000_000 / u128::from(denom);
+    Duration::from_nanos(nanos.min(u128::from(u64::MAX)) as u64)
+}
+
+/// Returns `duration` as a `fugit` duration in nanoseconds, saturating.
+///
+/// Use it for the durations returned by this crate, e.g. [`LedTiming::frame_duration()`].
+#[cfg(feature = "fugit")]
+pub fn to_fugit(duration: Duration) -> fugit::NanosDurationU64 {
+    let nanos = duration.as_nanos().min(u128::from(u64::MAX)) as u64;
+    fugit::NanosDurationU64::from_ticks(nanos)
+}
+
+/// Signal timing of the LED pixels.
+///
+/// # Examples
+///
+/// ```
+/// use ws2812_esp32_rmt_driver::driver::timing::LedTiming;
+/// use core::time::Duration;
+///
+/// // 100 LEDs of 8-bit GRB
+/// let timing = LedTiming::WS2812;
+/// assert_eq!(timing.frame_duration(100, 3), Duration::from_micros(3050));
+/// assert_eq!(timing.max_fps(100, 3) as u32, 327);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LedTiming {
+    /// T0H duration time (0 code, high voltage time)
+    pub t0h: Duration,
+    /// T0L duration time (0 code, low voltage time)
+    pub t0l: Duration,
+    /// T1H duration time (1 code, high voltage time)
+    pub t1h: Duration,
+    /// T1L duration time (1 code, low voltage time)
+    pub t1l: Duration,
+    /// Low voltage time required to latch the data (reset code)
+    pub reset: Duration,
+}
+
+impl LedTiming {
+    /// WS2812 timing
+    pub const WS2812: Self = Self {
+        t0h: WS2812_T0H_NS,
+        t0l: WS2812_T0L_NS,
+        t1h: WS2812_T1H_NS,
+        t1l: WS2812_T1L_NS,
+        reset: Duration::from_micros(50),
+    };
+
+    /// SK6812 timing
+    pub const SK6812: Self = Self {
+        t0h: Duration::from_nanos(300),
+        t0l: Duration::from_nanos(900),
+        t1h: Duration::from_nanos(600),
+        t1l: Duration::from_nanos(600),
+        reset: Duration::from_micros(80),
+    };
+
+    /// Creates a timing of the pulse durations and the reset code.
+    pub fn new(
+        t0h: impl IntoDuration,
+        t0l: impl IntoDuration,
+        t1h: impl IntoDuration,
+        t1l: impl IntoDuration,
+        reset: impl IntoDuration,
+    ) -> Self {
+        Self {
+            t0h: t0h.into_duration(),
+            t0l: t0l.into_duration(),
+            t1h: t1h.into_duration(),
+            t1l: t1l.into_duration(),
+            reset: reset.into_duration(),
+        }
+    }
+
+    /// Returns the timing with the reset code of `reset`.
+    ///
+    /// Some LED pixels clones require a longer reset code than the datasheet.
+    pub fn with_reset(mut self, reset: impl IntoDuration) -> Self {
+        self.reset = reset.into_duration();
+        self
+    }
+
+    /// Returns the duration to transmit a bit.
+    pub const fn bit_duration(&self) -> Duration {
+        let bit0 = self.t0h.as_nanos() + self.t0l.as_nanos();
+        let bit1 = self.t1h.as_nanos() + self.t1l.as_nanos();
+        Duration::from_nanos(if bit0 > bit1 { bit0 } else { bit1 } as u64)
+    }
+
+    /// Returns the duration to transmit `len` bytes, excluding the reset code.
+    pub const fn data_duration(&self, len: usize) -> Duration {
+        let nanos = self.bit_duration().as_nanos() as u64 * (len as u64 * u8::BITS as u64);
+        Duration::from_nanos(nanos)
+    }
+
+    /// Returns the duration of a frame of `pixel_len` LED pixels of `bpp` bytes,
+    /// including the reset code.
+    pub const fn frame_duration(&self, pixel_len: usize, bpp: usize) -> Duration {
+        let data = self.data_duration(pixel_len * bpp);
+        Duration::from_nanos((data.as_nanos() + self.reset.as_nanos()) as u64)
+    }
+
+    /// Returns the theoretical maximum frame rate (frame per second) of `pixel_len` LED pixels of
+    /// `bpp` bytes.
+    pub fn max_fps(&self, pixel_len: usize, bpp: usize) -> f32 {
+        1.0 / self.frame_duration(pixel_len, bpp).as_secs_f32()
+    }
+}
+
+impl Default for LedTiming {
+    /// Returns [`LedTiming::WS2812`]
+    fn default() -> Self {
+        Self::WS2812
+    }
+}
+
+/// Timing presets of the LED chips
+///
+/// The pulse durations are the typical ones of the datasheets, and the reset codes are the
+/// minimum ones, e.g. 280 us of the recent revisions of WS2812B.
+///
+/// # Examples
+///
+/// ```
+/// use ws2812_esp32_rmt_driver::driver::timing::{ChipTiming, LedTiming};
+/// use core::time::Duration;
+///
+/// let timing = LedTiming::from(ChipTiming::Ws2815);
+/// assert_eq!(timing.reset, Duration::from_micros(280));
+/// assert_eq!(ChipTiming::Ws2812.timing(), LedTiming::WS2812);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ChipTiming {
+    /// WS2811 in the 800 kHz mode
+    Ws2811,
+    /// WS2811 in the 400 kHz mode, and the other 400 kHz LED pixels
+    Ws2811LowSpeed,
+    /// WS2812, same as [`LedTiming::WS2812`]
+    Ws2812,
+    /// WS2812B (V5)
+    Ws2812b,
+    /// WS2812B-2020
+    Ws2812b2020,
+    /// WS2813
+    Ws2813,
+    /// WS2815
+    Ws2815,
+    /// SK6812, same as [`LedTiming::SK6812`]
+    Sk6812,
+    /// TM1814
+    ///
+    /// Note that the TM1814 modules expect the data line inverted against WS2812; invert the
+    /// output, e.g. by a transistor stage.
+    Tm1814,
+}
+
+impl ChipTiming {
+    /// Returns the signal timing of the chip.
+    pub const fn timing(&self) -> LedTiming {
+        const fn timing(t0h: u64, t0l: u64, t1h: u64, t1l: u64, reset_us: u64) -> LedTiming {
+            LedTiming {
+                t0h: Duration::from_nanos(t0h),
+                t0l: Duration::from_nanos(t0l),
+                t1h: Duration::from_nanos(t1h),
+                t1l: Duration::from_nanos(t1l),
+                reset: Duration::from_micros(reset_us),
+            }
+        }
+        match self {
+            Self::Ws2811 => timing(250, 1000, 600, 650, 50),
+            Self::Ws2811LowSpeed => timing(500, 2000, 1200, 1300, 50),
+            Self::Ws2812 => LedTiming::WS2812,
+            Self::Ws2812b => timing(400, 850, 800, 450, 280),
+            Self::Ws2812b2020 => timing(300, 900, 800, 400, 280),
+            Self::Ws2813 | Self::Ws2815 => timing(300, 900, 800, 450, 280),
+            Self::Sk6812 => LedTiming::SK6812,
+            Self::Tm1814 => timing(360, 890, 720, 530, 200),
+        }
+    }
+}
+
+impl From<ChipTiming> for LedTiming {
+    fn from(chip: ChipTiming) -> Self {
+        chip.timing()
+    }
+}
+
+/// Default RMT counter resolution requested by the drivers (100ns per tick)
+pub const DEFAULT_RESOLUTION_HZ: u32 = 10_000_000;
+
+/// Returns the RMT clock divider of the counter clock nearest to `resolution_hz` from the source
+/// clock `source_hz`.
+///
+/// The divider is clamped to `1..=255`.
+///
+/// # Examples
+///
+/// ```
+/// use ws2812_esp32_rmt_driver::driver::timing::{clock_divider, DEFAULT_RESOLUTION_HZ};
+///
+/// assert_eq!(clock_divider(80_000_000, DEFAULT_RESOLUTION_HZ), 8);
+/// assert_eq!(clock_divider(40_000_000, DEFAULT_RESOLUTION_HZ), 4);
+/// ```
+pub const fn clock_divider(source_hz: u32, resolution_hz: u32) -> u8 {
+    if resolution_hz == 0 {
+        return u8::MAX;
+    }
+    let divider = (source_hz as u64 + resolution_hz as u64 / 2) / resolution_hz as u64;
+    if divider < 1 {
+        1
+    } else if divider > u8::MAX as u64 {
+        u8::MAX
+    } else {
+        divider as u8
+    }
+}
+
+/// Maximum tick count of a pulse of an RMT item
+const MAX_TICKS: u32 = 0x7FFF;
+
+/// Tick counts of the high and low pulses of the WS2812 bits at an RMT clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RmtBitTicks {
+    /// High ticks of a 0 code
+    pub t0h: u16,
+    /// Low ticks of a 0 code
+    pub t0l: u16,
+    /// High ticks of a 1 code
+    pub t1h: u16,
+    /// Low ticks of a 1 code
+    pub t1l: u16,
+}
+
+impl RmtBitTicks {
+    /// Returns the tick counts of `timing` at the RMT counter clock `clock_hz`.
+    ///
+    /// Returns `None` if a pulse is shorter than a tick or longer than an RMT item holds.
+    pub fn new(clock_hz: u32, timing: &LedTiming) -> Option<Self> {
+        let ticks = |duration: Duration| {
+            let ticks = duration.as_nanos() * u128::from(clock_hz) / 1_000_000_000;
+            (1..=u128::from(MAX_TICKS))
+                .contains(&ticks)
+                .then_some(ticks as u16)
+        };
+        Some(Self {
+            t0h: ticks(timing.t0h)?,
+            t0l: ticks(timing.t0l)?,
+            t1h: ticks(timing.t1h)?,
+            t1l: ticks(timing.t1l)?,
+        })
+    }
+
+    /// Returns the tick count of the reset code of `timing` at the RMT counter clock `clock_hz`.
+    ///
+    /// It saturates at the two low pulses an RMT item holds.
+    pub fn reset_ticks(clock_hz: u32, timing: &LedTiming) -> u32 {
+        let ticks = timing.reset.as_nanos() * u128::from(clock_hz) / 1_000_000_000;
+        ticks.min(u128::from(2 * MAX_TICKS)) as u32
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_chip_timing() {
+        let chips = [
+            ChipTiming::Ws2811,
+            ChipTiming::Ws2811LowSpeed,
+            ChipTiming::Ws2812,
+            ChipTiming::Ws2812b,
+            ChipTiming::Ws2812b2020,
+            ChipTiming::Ws2813,
+            ChipTiming::Ws2815,
+            ChipTiming::Sk6812,
+            ChipTiming::Tm1814,
+        ];
+        for chip in chips {
+            let timing = chip.timing();
+            assert!(timing.t0h < timing.t1h, "{chip:?}");
+            assert!(RmtBitTicks::new(DEFAULT_RESOLUTION_HZ, &timing).is_some());
+        }
+        assert_eq!(
+            ChipTiming::Ws2811LowSpeed.timing().bit_duration(),
+            Duration::from_micros(5) / 2
+        );
+    }
+
+    #[test]
+    fn test_led_timing_ws2812() {
+        let timing = LedTiming::WS2812;
+        assert_eq!(timing.bit_duration(), Duration::from_nanos(1250));
+        assert_eq!(timing.data_duration(3), Duration::from_micros(30));
+        assert_eq!(timing.frame_duration(100, 3), Duration::from_micros(3050));
+        assert_eq!(timing.frame_duration(100, 4), Duration::from_micros(4050));
+        assert_eq!(RmtBitTicks::reset_ticks(10_000_000, &timing), 500);
+        assert_eq!(
+            RmtBitTicks::reset_ticks(80_000_000, &timing.with_reset(Duration::from_millis(1))),
+            0xFFFE
+        );
+        assert_eq!(timing.frame_duration(0, 3), Duration::from_micros(50));
+        assert!((timing.max_fps(100, 3) - 327.87).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_led_timing_sk6812() {
+        let timing = LedTiming::SK6812;
+        assert_eq!(timing.bit_duration(), Duration::from_nanos(1200));
+        assert_eq!(timing.frame_duration(100, 4), Duration::from_micros(3920));
+    }
+
+    #[test]
+    fn test_led_timing_bit_duration_asymmetric() {
+        let timing = LedTiming {
+            t0h: Duration::from_nanos(300),
+            t0l: Duration::from_nanos(900),
+            t1h: Duration::from_nanos(600),
+            t1l: Duration::from_nanos(700),
+            reset: Duration::from_micros(280),
+        };
+        assert_eq!(timing.bit_duration(), Duration::from_nanos(1300));
+    }
+
+    #[test]
+    fn test_led_timing_new() {
+        let timing = LedTiming::new(
+            Duration::from_nanos(400),
+            Duration::from_nanos(850),
+            Duration::from_nanos(800),
+            Duration::from_nanos(450),
+            Duration::from_micros(50),
+        );
+        assert_eq!(timing, LedTiming::WS2812);
+        let timing = timing.with_reset(Duration::from_micros(280));
+        assert_eq!(timing.reset, Duration::from_micros(280));
+    }
+
+    #[cfg(feature = "fugit")]
+    #[test]
+    fn test_fugit_durations() {
+        use fugit::{MicrosDurationU32, MicrosDurationU64, MillisDurationU64, NanosDurationU32};
+
+        let timing = LedTiming::new(
+            NanosDurationU32::from_ticks(400),
+            NanosDurationU32::from_ticks(850),
+            NanosDurationU32::from_ticks(800),
+            NanosDurationU32::from_ticks(450),
+            MicrosDurationU32::from_ticks(50),
+        );
+        assert_eq!(timing, LedTiming::WS2812);
+        assert_eq!(
+            MillisDurationU64::from_ticks(3).into_duration(),
+            Duration::from_millis(3)
+        );
+        // 3 ticks of a 32768 Hz timer
+        assert_eq!(
+            fugit::Duration::<u32, 1, 32768>::from_ticks(3).into_duration(),
+            Duration::from_nanos(91552)
+        );
+        assert_eq!(
+            to_fugit(timing.frame_duration(100, 3)),
+            MicrosDurationU64::from_ticks(3050)
+        );
+    }
+
+    #[test]
+    fn test_rmt_bit_ticks() {
+        assert_eq!(
+            RmtBitTicks::new(80_000_000, &LedTiming::WS2812),
+            Some(RmtBitTicks {
+                t0h: 32,
+                t0l: 68,
+                t1h: 64,
+                t1l: 36,
+            })
+        );
+        assert_eq!(RmtBitTicks::new(1_000_000, &LedTiming::WS2812), None);
+    }
+
+    #[test]
+    fn test_clock_divider() {
+        assert_eq!(clock_divider(80_000_000, 80_000_000), 1);
+        assert_eq!(clock_divider(80_000_000, 10_000_000), 8);
+        assert_eq!(clock_divider(40_000_000, 10_000_000), 4);
+        assert_eq!(clock_divider(80_000_000, 12_000_000), 7);
+        assert_eq!(clock_divider(80_000_000, 100_000_000), 1);
+        assert_eq!(clock_divider(80_000_000, 1), 255);
+        assert_eq!(clock_divider(80_000_000, 0), 255);
+        assert_eq!(
+            RmtBitTicks::new(10_000_000, &LedTiming::WS2812),
+            Some(RmtBitTicks {
+                t0h: 4,
+                t0l: 8,
+                t1h: 8,
+                t1l: 4,
+            })
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! Tracing of the driver activity.
+//!
+//! With `log` or `defmt` feature, the drivers emit the events of the initialization, the start
+//! and the completion of the frames, the overruns and the errors through the enabled logging
+//! facade, so that field issues like flicker can be correlated with the other activities, e.g.
+//! Wi-Fi, in the logs. Without them, the events are compiled out.
+//!
+//! `defmt` is only used on the targets, as the host has no global logger of it to link the
+//! mock and the tests with; there the events go to `log` only.
+//!
+//! | Event            | Level   |
+//! |------------------|---------|
+//! | initialization   | `debug` |
+//! | frame start/done | `trace` |
+//! | overrun          | `warn`  |
+//! | error            | `error` |
+
+use core::fmt;
+
+/// Emits a tracing event to `log` and/or `defmt`.
+///
+/// The format string shall be compatible with both, i.e. only `{}` placeholders of the
+/// primitive types and [`TraceError`].
+macro_rules! trace_event {
+    ($level:ident, $fmt:literal $(, $arg:expr)* $(,)?) => {{
+        #[cfg(feature = "log")]
+        ::log::$level!($fmt $(, $arg)*);
+        #[cfg(all(
+            feature = "defmt",
+            any(target_vendor = "espressif", target_os = "none")
+        ))]
+        ::defmt::$level!($fmt $(, $arg)*);
+        #[cfg(not(any(
+            feature = "log",
+            all(
+                feature = "defmt",
+                any(target_vendor = "espressif", target_os = "none")
+            )
+        )))]
+        {
+            $(let _ = &$arg;)*
+        }
+    }};
+}
+pub(crate) use trace_event;
+
+/// Error formatted by `Display` for both `log` and `defmt`.
+pub(crate) struct TraceError<'a, E>(pub &'a E);
+
+impl<E: fmt::Display> fmt::Display for TraceError<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(all(
+    feature = "defmt",
+    any(target_vendor = "espressif", target_os = "none")
+))]
+impl<E: fmt::Display> defmt::Format for TraceError<'_, E> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "{}", defmt::Display2Format(self.0))
+    }
+}
+
+#[cfg(all(test, feature = "log"))]
+mod test {
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+    use crate::Ws2812Esp32RmtDriver;
+    use std::sync::Mutex;
+
+    static MESSAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    struct Recorder;
+
+    impl log::Log for Recorder {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            MESSAGES.lock().unwrap().push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn test_trace_event_log() {
+        log::set_logger(&Recorder).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let peripherals = Peripherals::take().unwrap();
+        let mut driver =
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel1, peripherals.pins.gpio2).unwrap();
+        driver.write_blocking([1, 2, 3].into_iter()).unwrap();
+
+        let messages = MESSAGES.lock().unwrap();
+        assert!(messages.contains(&"RMT driver initialized on channel 1".to_string()));
+        assert!(messages.contains(&"RMT frame done: 3 bytes".to_string()));
+    }
+}
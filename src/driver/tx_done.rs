@@ -0,0 +1,141 @@
+//! Completion of the RMT transmissions woken by the TX end interrupt.
+//!
+//! The legacy RMT driver calls a single TX end callback for all the channels; it is registered
+//! at the first asynchronous write or split, wakes the task awaiting the [`Transmission`] of the
+//! channel, and clears the busy flag of the channel returned by [`busy_flag()`].
+
+use core::cell::UnsafeCell;
+use core::ffi::c_void;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll, Waker};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use esp_idf_hal::delay::BLOCK;
+use esp_idf_hal::interrupt::IsrCriticalSection;
+use esp_idf_hal::rmt::Symbol;
+use esp_idf_sys::{
+    esp, rmt_channel_t, rmt_register_tx_end_callback, rmt_wait_tx_done, rmt_write_items, EspError,
+};
+
+/// Maximum number of the RMT channels of the chips
+const CHANNELS: usize = 8;
+
+/// Completion state of a channel
+struct Slot {
+    done: bool,
+    waker: Option<Waker>,
+}
+
+/// Completion states of the channels, guarded by [`CRITICAL_SECTION`]
+struct Slots(UnsafeCell<[Slot; CHANNELS]>);
+
+// SAFETY: the slots are accessed only in the critical section.
+unsafe impl Sync for Slots {}
+
+static SLOTS: Slots = Slots(UnsafeCell::new(
+    [const {
+        Slot {
+            done: true,
+            waker: None,
+        }
+    }; CHANNELS],
+));
+static CRITICAL_SECTION: IsrCriticalSection = IsrCriticalSection::new();
+static REGISTERED: AtomicBool = AtomicBool::new(false);
+/// Busy flags of the channels transmitting by `FrameWriter::start()`
+static BUSY: [AtomicBool; CHANNELS] = [const { AtomicBool::new(false) }; CHANNELS];
+
+/// Registers the TX end callback unless registered.
+pub(super) fn register() {
+    if !REGISTERED.swap(true, Ordering::AcqRel) {
+        unsafe { rmt_register_tx_end_callback(Some(on_tx_end), core::ptr::null_mut()) };
+    }
+}
+
+/// Returns the busy flag of `channel` cleared by the TX end interrupt.
+pub(super) fn busy_flag(channel: rmt_channel_t) -> &'static AtomicBool {
+    &BUSY[channel as usize]
+}
+
+/// Calls `f` with the slot of `channel` in the critical section.
+fn with_slot<R>(channel: rmt_channel_t, f: impl FnOnce(&mut Slot) -> R) -> Option<R> {
+    let _guard = CRITICAL_SECTION.enter();
+    // SAFETY: the slots are accessed only in the critical section.
+    let slots = unsafe { &mut *SLOTS.0.get() };
+    slots.get_mut(channel as usize).map(f)
+}
+
+/// Called by the RMT driver in the interrupt context when a transmission completes.
+unsafe extern "C" fn on_tx_end(channel: rmt_channel_t, _arg: *mut c_void) {
+    if let Some(busy) = BUSY.get(channel as usize) {
+        busy.store(false, Ordering::Release);
+    }
+    let waker = with_slot(channel, |slot| {
+        slot.done = true;
+        slot.waker.take()
+    });
+    if let Some(Some(waker)) = waker {
+        waker.wake();
+    }
+}
+
+/// Transmission of the RMT items started without waiting for its completion
+///
+/// It completes when the TX end interrupt of the channel fires. Dropping it before the
+/// completion blocks until the end of the transmission, as the RMT reads the items until then.
+pub(super) struct Transmission {
+    channel: rmt_channel_t,
+    _symbols: Vec<Symbol>,
+}
+
+impl Transmission {
+    /// Starts the transmission of `symbols` on `channel`.
+    pub(super) fn start(channel: rmt_channel_t, symbols: Vec<Symbol>) -> Result<Self, EspError> {
+        register();
+        with_slot(channel, |slot| {
+            slot.done = false;
+            slot.waker = None;
+        });
+        // SAFETY: `Symbol` is a transparent wrapper of `rmt_item32_t`, and `symbols` is kept
+        // until the end of the transmission.
+        esp!(unsafe {
+            rmt_write_items(
+                channel,
+                symbols.as_ptr().cast(),
+                symbols.len() as i32,
+                false,
+            )
+        })?;
+        Ok(Self {
+            channel,
+            _symbols: symbols,
+        })
+    }
+}
+
+impl Future for Transmission {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let done = with_slot(self.channel, |slot| {
+            if !slot.done && !slot.waker.as_ref().is_some_and(|w| w.will_wake(cx.waker())) {
+                slot.waker = Some(cx.waker().clone());
+            }
+            slot.done
+        });
+        match done {
+            Some(false) => Poll::Pending,
+            // The channels beyond the slots are not woken; wait for them in `drop()`.
+            _ => Poll::Ready(()),
+        }
+    }
+}
+
+impl Drop for Transmission {
+    fn drop(&mut self) {
+        unsafe { rmt_wait_tx_done(self.channel, BLOCK) };
+    }
+}
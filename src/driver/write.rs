@@ -0,0 +1,311 @@
+//! Backend-agnostic LED strip write trait.
+
+#[cfg(all(
+    any(
+        feature = "spi",
+        feature = "parallel",
+        feature = "rmt-tx",
+        feature = "apa102"
+    ),
+    not(target_os = "none")
+))]
+use super::buffer::BufferStorage;
+#[cfg(all(feature = "apa102", not(target_os = "none")))]
+use super::{Apa102Esp32SpiDriver, Apa102Esp32SpiDriverError};
+#[cfg(all(feature = "parallel", not(target_os = "none")))]
+use super::{Ws2812Esp32ParallelDriver, Ws2812Esp32ParallelDriverError};
+#[cfg(not(target_os = "none"))]
+use super::{Ws2812Esp32RmtDriver, Ws2812Esp32RmtDriverError};
+#[cfg(all(feature = "rmt-tx", not(target_os = "none")))]
+use super::{Ws2812Esp32RmtTxDriver, Ws2812Esp32RmtTxDriverError};
+#[cfg(all(feature = "spi", not(target_os = "none")))]
+use super::{Ws2812Esp32SpiDriver, Ws2812Esp32SpiDriverError};
+#[cfg(all(feature = "esp-hal", target_os = "none"))]
+use super::{Ws2812EspHalRmtDriver, Ws2812EspHalRmtDriverError};
+
+/// Writer of pixel-byte sequences to the LED strip.
+///
+/// This is implemented by [`Ws2812Esp32RmtDriver`] both on the target and in the mock,
+/// so that libraries accepting this trait can be tested on the host against the mock
+/// without `target_vendor` conditions.
+///
+/// # Examples
+///
+/// ```
+/// use ws2812_esp32_rmt_driver::driver::LedStripWrite;
+///
+/// fn fill<W: LedStripWrite>(strip: &mut W, pixel_len: usize) -> Result<(), W::Error> {
+///     strip.write_blocking(core::iter::repeat(0x80).take(pixel_len * 3))
+/// }
+/// # #[cfg(not(target_vendor = "espressif"))]
+/// # {
+/// # use ws2812_esp32_rmt_driver::mock::esp_idf_hal::peripherals::Peripherals;
+/// # use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+/// # let peripherals = Peripherals::take().unwrap();
+/// # let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+/// fill(&mut driver, 2).unwrap();
+/// assert_eq!(driver.pixel_data, Some(vec![0x80; 6]));
+/// # }
+/// ```
+pub trait LedStripWrite {
+    /// Error type returned by the writer.
+    type Error;
+
+    /// Writes pixel data from a pixel-byte sequence and blocks until the transmission completes.
+    ///
+    /// Byte count per LED pixel and channel order is not handled by this method.
+    fn write_blocking<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send;
+}
+
+/// LED driver backend writing pixel-byte sequences.
+///
+/// The wrappers [`LedPixelEsp32Rmt`], [`LedPixelDrawTarget`] and [`LedPixelFrameBuffer`] are
+/// generic over this trait, so that they work on any backend implementing it, e.g. the RMT
+/// driver [`Ws2812Esp32RmtDriver`] (and its mock), the SPI driver `Ws2812Esp32SpiDriver`, the parallel
+/// driver `Ws2812Esp32ParallelDriver`, the APA102 driver `Apa102Esp32SpiDriver`, the bare-metal
+/// driver `Ws2812EspHalRmtDriver` or a backend of a downstream crate.
+///
+/// [`LedPixelEsp32Rmt`]: crate::lib_smart_leds::LedPixelEsp32Rmt
+/// [`LedPixelDrawTarget`]: crate::lib_embedded_graphics::LedPixelDrawTarget
+/// [`LedPixelFrameBuffer`]: crate::framebuffer::LedPixelFrameBuffer
+///
+/// # Examples
+///
+/// ```
+/// use ws2812_esp32_rmt_driver::driver::{LedStripDriver, LedStripWrite};
+/// use ws2812_esp32_rmt_driver::framebuffer::LedPixelFrameBuffer;
+/// use ws2812_esp32_rmt_driver::driver::color::{LedPixelColor, LedPixelColorGrb24};
+///
+/// /// Backend recording the pixel data
+/// #[derive(Default)]
+/// struct Recorder(Vec<u8>);
+///
+/// impl LedStripWrite for Recorder {
+///     type Error = core::convert::Infallible;
+///
+///     fn write_blocking<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+///     where
+///         T: Iterator<Item = u8> + Send,
+///     {
+///         self.0 = pixel_sequence.collect();
+///         Ok(())
+///     }
+/// }
+///
+/// impl LedStripDriver for Recorder {}
+///
+/// let mut frame = LedPixelFrameBuffer::<LedPixelColorGrb24, Vec<u8>, _>::from_driver(Recorder::default(), 2);
+/// frame.set_pixel(1, LedPixelColorGrb24::new_with_rgb(1, 2, 3));
+/// frame.show().unwrap();
+/// frame.driver_mut().latch().unwrap();
+/// assert_eq!(frame.driver().0, [0, 0, 0, 2, 1, 3]);
+///
+/// let pixels = [4, 5, 6];
+/// frame.driver_mut().write(pixels.iter().copied()).unwrap();
+/// assert_eq!(frame.driver().0, pixels);
+/// ```
+pub trait LedStripDriver: LedStripWrite {
+    /// Starts writing pixel data from a pixel-byte sequence without waiting for the transmission
+    /// to complete, if the backend supports it.
+    ///
+    /// The backend stages `pixel_sequence` into its own buffer before returning, so it may borrow
+    /// the data of the caller.
+    ///
+    /// The default implementation writes by [`LedStripWrite::write_blocking()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend failed to start the transmission.
+    fn write<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        self.write_blocking(pixel_sequence)
+    }
+
+    /// Waits until the frame written last has been transmitted, so that the LED pixels latch it.
+    ///
+    /// This returns once the LED pixels show the frame or the line is left idle for them to
+    /// latch it after their reset time, depending on the backend. A frame written by
+    /// [`LedStripWrite::write_blocking()`] is latched already.
+    ///
+    /// The default implementation does nothing, as the default [`Self::write()`]
+    /// blocks.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transmission failed.
+    fn latch(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "none"))]
+impl LedStripWrite for Ws2812Esp32RmtDriver<'_> {
+    type Error = Ws2812Esp32RmtDriverError;
+
+    #[inline]
+    fn write_blocking<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        Ws2812Esp32RmtDriver::write_blocking(self, pixel_sequence)
+    }
+}
+
+#[cfg(not(target_os = "none"))]
+impl LedStripDriver for Ws2812Esp32RmtDriver<'_> {
+    #[cfg(feature = "alloc")]
+    #[inline]
+    fn write<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        Ws2812Esp32RmtDriver::write(self, pixel_sequence)
+    }
+
+    #[inline]
+    fn latch(&mut self) -> Result<(), Self::Error> {
+        Ws2812Esp32RmtDriver::latch(self)
+    }
+}
+
+#[cfg(all(feature = "rmt-tx", not(target_os = "none")))]
+impl<B: BufferStorage> LedStripWrite for Ws2812Esp32RmtTxDriver<'_, B> {
+    type Error = Ws2812Esp32RmtTxDriverError;
+
+    #[inline]
+    fn write_blocking<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        Ws2812Esp32RmtTxDriver::write_blocking(self, pixel_sequence)
+    }
+}
+
+#[cfg(all(feature = "rmt-tx", not(target_os = "none")))]
+impl<B: BufferStorage> LedStripDriver for Ws2812Esp32RmtTxDriver<'_, B> {}
+
+#[cfg(all(feature = "spi", not(target_os = "none")))]
+impl<B: BufferStorage> LedStripWrite for Ws2812Esp32SpiDriver<'_, B> {
+    type Error = Ws2812Esp32SpiDriverError;
+
+    #[inline]
+    fn write_blocking<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        Ws2812Esp32SpiDriver::write_blocking(self, pixel_sequence)
+    }
+}
+
+#[cfg(all(feature = "spi", not(target_os = "none")))]
+impl<B: BufferStorage> LedStripDriver for Ws2812Esp32SpiDriver<'_, B> {
+    #[inline]
+    fn write<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        Ws2812Esp32SpiDriver::write(self, pixel_sequence)
+    }
+
+    #[inline]
+    fn latch(&mut self) -> Result<(), Self::Error> {
+        Ws2812Esp32SpiDriver::latch(self)
+    }
+}
+
+#[cfg(all(feature = "parallel", not(target_os = "none")))]
+impl<const LANES: usize, B: BufferStorage> LedStripWrite
+    for Ws2812Esp32ParallelDriver<'_, LANES, B>
+{
+    type Error = Ws2812Esp32ParallelDriverError;
+
+    #[inline]
+    fn write_blocking<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        Ws2812Esp32ParallelDriver::write_blocking(self, pixel_sequence)
+    }
+}
+
+#[cfg(all(feature = "parallel", not(target_os = "none")))]
+impl<const LANES: usize, B: BufferStorage> LedStripDriver
+    for Ws2812Esp32ParallelDriver<'_, LANES, B>
+{
+    #[inline]
+    fn write<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        Ws2812Esp32ParallelDriver::write(self, pixel_sequence)
+    }
+
+    #[inline]
+    fn latch(&mut self) -> Result<(), Self::Error> {
+        Ws2812Esp32ParallelDriver::latch(self);
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "apa102", not(target_os = "none")))]
+impl<B: BufferStorage> LedStripWrite for Apa102Esp32SpiDriver<'_, B> {
+    type Error = Apa102Esp32SpiDriverError;
+
+    #[inline]
+    fn write_blocking<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        Apa102Esp32SpiDriver::write_blocking(self, pixel_sequence)
+    }
+}
+
+#[cfg(all(feature = "apa102", not(target_os = "none")))]
+impl<B: BufferStorage> LedStripDriver for Apa102Esp32SpiDriver<'_, B> {}
+
+#[cfg(all(feature = "esp-hal", target_os = "none"))]
+impl LedStripWrite for Ws2812EspHalRmtDriver<'_> {
+    type Error = Ws2812EspHalRmtDriverError;
+
+    #[inline]
+    fn write_blocking<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        Ws2812EspHalRmtDriver::write_blocking(self, pixel_sequence)
+    }
+}
+
+#[cfg(all(feature = "esp-hal", target_os = "none"))]
+impl LedStripDriver for Ws2812EspHalRmtDriver<'_> {}
+
+impl<W: LedStripWrite + ?Sized> LedStripWrite for &mut W {
+    type Error = W::Error;
+
+    #[inline]
+    fn write_blocking<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        (**self).write_blocking(pixel_sequence)
+    }
+}
+
+impl<D: LedStripDriver + ?Sized> LedStripDriver for &mut D {
+    #[inline]
+    fn write<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        (**self).write(pixel_sequence)
+    }
+
+    #[inline]
+    fn latch(&mut self) -> Result<(), Self::Error> {
+        (**self).latch()
+    }
+}
@@ -0,0 +1,679 @@
+//! Stored animation player.
+//!
+//! An animation is a byte sequence of a header followed by the frames, raw or run-length encoded.
+//! It is read in place from a const byte array or a memory-mapped flash partition,
+//! and [`AnimationPlayer`] streams the frames directly into the driver without a frame buffer.
+//! Animations are authored offline with [`encode()`].
+//!
+//! # Format
+//!
+//! The header is 14 bytes, and the multi-byte values are little-endian:
+//!
+//! |Offset|Size|Description                                      |
+//! |------|----|-------------------------------------------------|
+//! |0     |4   |magic `WSAN`                                     |
+//! |4     |1   |version, `1`                                     |
+//! |5     |1   |encoding, `0` raw, `1` run-length, `2` PackBits  |
+//! |6     |1   |byte per pixel                                   |
+//! |7     |1   |reserved, `0`                                    |
+//! |8     |2   |the number of the LED pixels of a frame          |
+//! |10    |2   |the number of the frames                         |
+//! |12    |2   |frame interval in milliseconds                   |
+//!
+//! A raw frame is the pixel data as is. A run-length encoded frame is a sequence of runs,
+//! each of which is a repeat count (1 to 255) followed by the pixel data of a LED pixel.
+//! A PackBits encoded frame is a sequence of runs, each of which is a control byte `n` followed
+//! by the pixel data of a LED pixel repeated `n + 1` times if `n` is 0 to 127, or by the pixel
+//! data of `n - 127` LED pixels as is if `n` is 128 to 255; unlike the run-length encoding, the
+//! frames without the repeats, e.g. gradients, do not grow beyond a byte per 128 LED pixels.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::effects::animation::{Animation, AnimationPlayer};
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//!
+//! // 2 frames of 4 GRB pixels: all red, and then all green.
+//! static BOOT_ANIMATION: &[u8] = &[
+//!     b'W', b'S', b'A', b'N', 1, 1, 3, 0, 4, 0, 2, 0, 100, 0,
+//!     4, 0x00, 0xFF, 0x00,
+//!     4, 0xFF, 0x00, 0x00,
+//! ];
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio27).unwrap();
+//!
+//! let animation = Animation::parse(BOOT_ANIMATION).unwrap();
+//! let mut player = AnimationPlayer::new(animation);
+//! player
+//!     .play(&mut driver, |_interval| {
+//!         // e.g. `esp_idf_hal::delay::FreeRtos::delay_ms(interval.as_millis() as u32)`
+//!     })
+//!     .unwrap();
+//! assert_eq!(driver.pixel_data, Some([0xFF, 0x00, 0x00].repeat(4)));
+//! ```
+
+use crate::driver::LedStripWrite;
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use core::time::Duration;
+
+/// Magic number at the beginning of an animation
+pub const MAGIC: [u8; 4] = *b"WSAN";
+/// Version of the animation format
+pub const VERSION: u8 = 1;
+/// Byte count of the header
+pub const HEADER_LEN: usize = 14;
+
+/// Encoding of the frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// Pixel data as is
+    Raw,
+    /// Runs of a repeat count and the pixel data of a LED pixel
+    RunLength,
+    /// Runs of a repeated LED pixel and spans of the LED pixels as is
+    PackBits,
+}
+
+/// Header of an animation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AnimationHeader {
+    /// Encoding of the frames
+    pub encoding: Encoding,
+    /// Byte per pixel
+    pub bpp: u8,
+    /// The number of the LED pixels of a frame
+    pub pixel_len: u16,
+    /// The number of the frames
+    pub frame_count: u16,
+    /// Interval between the frames
+    pub frame_interval: Duration,
+}
+
+impl AnimationHeader {
+    /// Returns the header as bytes.
+    pub fn to_bytes(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0; HEADER_LEN];
+        bytes[0..4].copy_from_slice(&MAGIC);
+        bytes[4] = VERSION;
+        bytes[5] = match self.encoding {
+            Encoding::Raw => 0,
+            Encoding::RunLength => 1,
+            Encoding::PackBits => 2,
+        };
+        bytes[6] = self.bpp;
+        bytes[8..10].copy_from_slice(&self.pixel_len.to_le_bytes());
+        bytes[10..12].copy_from_slice(&self.frame_count.to_le_bytes());
+        let interval = self.frame_interval.as_millis().min(u16::MAX as u128) as u16;
+        bytes[12..14].copy_from_slice(&interval.to_le_bytes());
+        bytes
+    }
+
+    /// Parses the header.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header is malformed.
+    pub fn parse(data: &[u8]) -> Result<Self, AnimationError> {
+        let header = data
+            .first_chunk::<HEADER_LEN>()
+            .ok_or(AnimationError::Truncated)?;
+        let u16_at = |i: usize| u16::from_le_bytes([header[i], header[i + 1]]);
+        if header[0..4] != MAGIC {
+            return Err(AnimationError::BadMagic);
+        }
+        if header[4] != VERSION {
+            return Err(AnimationError::UnsupportedVersion(header[4]));
+        }
+        let encoding = match header[5] {
+            0 => Encoding::Raw,
+            1 => Encoding::RunLength,
+            2 => Encoding::PackBits,
+            v => return Err(AnimationError::UnknownEncoding(v)),
+        };
+        if header[6] == 0 {
+            return Err(AnimationError::InvalidFrame(0));
+        }
+        Ok(Self {
+            encoding,
+            bpp: header[6],
+            pixel_len: u16_at(8),
+            frame_count: u16_at(10),
+            frame_interval: Duration::from_millis(u16_at(12).into()),
+        })
+    }
+
+    /// Returns the byte count of the encoded frame at the beginning of `data`,
+    /// or `None` if the frame is truncated or malformed.
+    fn encoded_len(&self, data: &[u8]) -> Option<usize> {
+        let bpp = self.bpp as usize;
+        let pixel_len = self.pixel_len as usize;
+        match self.encoding {
+            Encoding::Raw => Some(pixel_len * bpp).filter(|&len| len <= data.len()),
+            Encoding::RunLength => {
+                let mut pixels = 0;
+                let mut len = 0;
+                while pixels < pixel_len {
+                    let count = *data.get(len)? as usize;
+                    len += 1 + bpp;
+                    pixels += count;
+                    if count == 0 || pixels > pixel_len || len > data.len() {
+                        return None;
+                    }
+                }
+                Some(len)
+            }
+            Encoding::PackBits => {
+                let mut pixels = 0;
+                let mut len = 0;
+                while pixels < pixel_len {
+                    let (count, run_len) = packbits_run(*data.get(len)?, bpp);
+                    len += 1 + run_len;
+                    pixels += count;
+                    if pixels > pixel_len || len > data.len() {
+                        return None;
+                    }
+                }
+                Some(len)
+            }
+        }
+    }
+}
+
+/// Returns the number of the LED pixels and the byte count of the pixel data of a PackBits run
+/// of the control byte `n`.
+#[inline]
+const fn packbits_run(n: u8, bpp: usize) -> (usize, usize) {
+    if n < 128 {
+        (n as usize + 1, bpp)
+    } else {
+        let count = n as usize - 127;
+        (count, count * bpp)
+    }
+}
+
+/// Animation parse error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnimationError {
+    /// The header is truncated.
+    Truncated,
+    /// The magic number does not match.
+    BadMagic,
+    /// The version is not supported.
+    UnsupportedVersion(u8),
+    /// The encoding is unknown.
+    UnknownEncoding(u8),
+    /// The frame of the index is truncated or malformed.
+    InvalidFrame(u16),
+}
+
+impl Error for AnimationError {}
+
+impl fmt::Display for AnimationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "animation header is truncated"),
+            Self::BadMagic => write!(f, "animation magic number does not match"),
+            Self::UnsupportedVersion(v) => write!(f, "animation version {} is not supported", v),
+            Self::UnknownEncoding(v) => write!(f, "animation encoding {} is unknown", v),
+            Self::InvalidFrame(index) => write!(f, "animation frame #{} is invalid", index),
+        }
+    }
+}
+
+/// Animation stored in a byte sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Animation<'a> {
+    header: AnimationHeader,
+    frames: &'a [u8],
+}
+
+impl<'a> Animation<'a> {
+    /// Parses the animation and validates all the frames.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header or a frame is malformed.
+    pub fn parse(data: &'a [u8]) -> Result<Self, AnimationError> {
+        let header = AnimationHeader::parse(data)?;
+        let frames = &data[HEADER_LEN..];
+        let mut offset = 0;
+        for index in 0..header.frame_count {
+            let len = header
+                .encoded_len(&frames[offset..])
+                .ok_or(AnimationError::InvalidFrame(index))?;
+            offset += len;
+        }
+        Ok(Self { header, frames })
+    }
+
+    /// Returns the header.
+    #[inline]
+    pub fn header(&self) -> &AnimationHeader {
+        &self.header
+    }
+
+    /// Returns the iterator of the frames.
+    pub fn frames(&self) -> Frames<'a> {
+        Frames {
+            header: self.header,
+            data: self.frames,
+            remaining: self.header.frame_count,
+        }
+    }
+}
+
+/// Encoded frame of an animation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame<'a> {
+    encoding: Encoding,
+    bpp: usize,
+    data: &'a [u8],
+}
+
+impl<'a> Frame<'a> {
+    /// Returns the decoded pixel data.
+    pub fn bytes(&self) -> impl Iterator<Item = u8> + Send + 'a {
+        let (raw, runs, packbits): (&[u8], &[u8], &[u8]) = match self.encoding {
+            Encoding::Raw => (self.data, &[], &[]),
+            Encoding::RunLength => (&[], self.data, &[]),
+            Encoding::PackBits => (&[], &[], self.data),
+        };
+        let decoded = runs.chunks_exact(1 + self.bpp).flat_map(|run| {
+            core::iter::repeat_n(&run[1..], run[0] as usize).flat_map(|pixel| pixel.iter().copied())
+        });
+        let packbits = PackBitsBytes {
+            data: packbits,
+            bpp: self.bpp,
+            pixel: &[],
+            repeated: &[],
+            repeat: 0,
+        };
+        raw.iter().copied().chain(decoded).chain(packbits)
+    }
+}
+
+/// Decoder of a PackBits encoded frame
+#[derive(Debug, Clone)]
+struct PackBitsBytes<'a> {
+    /// Runs not decoded yet
+    data: &'a [u8],
+    bpp: usize,
+    /// Remaining bytes of the pixel data being output
+    pixel: &'a [u8],
+    /// Pixel data of the repeat run
+    repeated: &'a [u8],
+    /// Number of the times the pixel data of the repeat run is output again
+    repeat: usize,
+}
+
+impl Iterator for PackBitsBytes<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((&v, rest)) = self.pixel.split_first() {
+                self.pixel = rest;
+                return Some(v);
+            }
+            if self.repeat > 0 {
+                self.repeat -= 1;
+                self.pixel = self.repeated;
+                continue;
+            }
+            // The frames are validated by `Animation::parse()`.
+            let (&n, rest) = self.data.split_first()?;
+            let (count, run_len) = packbits_run(n, self.bpp);
+            let (pixel, rest) = rest.split_at_checked(run_len)?;
+            self.data = rest;
+            self.pixel = pixel;
+            self.repeated = pixel;
+            self.repeat = if n < 128 { count - 1 } else { 0 };
+        }
+    }
+}
+
+/// Iterator of the frames of an animation
+#[derive(Debug, Clone)]
+pub struct Frames<'a> {
+    header: AnimationHeader,
+    data: &'a [u8],
+    remaining: u16,
+}
+
+impl<'a> Iterator for Frames<'a> {
+    type Item = Frame<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        // The frames are validated by `Animation::parse()`.
+        let len = self.header.encoded_len(self.data)?;
+        let (data, rest) = self.data.split_at(len);
+        self.data = rest;
+        self.remaining -= 1;
+        Some(Frame {
+            encoding: self.header.encoding,
+            bpp: self.header.bpp as usize,
+            data,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining as usize, Some(self.remaining as usize))
+    }
+}
+
+/// Player streaming the frames of an animation into the driver
+#[derive(Debug, Clone)]
+pub struct AnimationPlayer<'a> {
+    animation: Animation<'a>,
+    frames: Frames<'a>,
+    looping: bool,
+}
+
+impl<'a> AnimationPlayer<'a> {
+    /// Creates a player of the animation, played once.
+    pub fn new(animation: Animation<'a>) -> Self {
+        Self {
+            animation,
+            frames: animation.frames(),
+            looping: false,
+        }
+    }
+
+    /// Sets whether the animation is played repeatedly.
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// Restarts the animation from the first frame.
+    pub fn rewind(&mut self) {
+        self.frames = self.animation.frames();
+    }
+
+    /// Returns `true` if all the frames have been played and the animation is not looped.
+    pub fn is_finished(&self) -> bool {
+        self.frames.remaining == 0 && (!self.looping || self.animation.header.frame_count == 0)
+    }
+
+    /// Writes the next frame through the driver.
+    ///
+    /// Call it every [`AnimationHeader::frame_interval`] to play the animation in a main loop.
+    /// Returns `false` without writing if the animation is finished.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by `writer`.
+    pub fn play_next<W: LedStripWrite>(&mut self, writer: &mut W) -> Result<bool, W::Error> {
+        if self.frames.remaining == 0 && self.looping {
+            self.rewind();
+        }
+        match self.frames.next() {
+            Some(frame) => {
+                writer.write_blocking(frame.bytes())?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Plays the remaining frames through the driver, calling `delay` with the frame interval
+    /// between the frames.
+    ///
+    /// This does not return for a looped animation unless an error occurs.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error returned by `writer`.
+    pub fn play<W, D>(&mut self, writer: &mut W, mut delay: D) -> Result<(), W::Error>
+    where
+        W: LedStripWrite,
+        D: FnMut(Duration),
+    {
+        let interval = self.animation.header.frame_interval;
+        if self.play_next(writer)? {
+            while !self.is_finished() {
+                delay(interval);
+                self.play_next(writer)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Encodes the frames of the pixel data into an animation.
+///
+/// `header.frame_count` is overwritten with the number of the frames.
+///
+/// # Panics
+///
+/// Panics if a frame is not `header.pixel_len` LED pixels of `header.bpp` bytes,
+/// or if there are more than `u16::MAX` frames.
+#[cfg(feature = "alloc")]
+pub fn encode<F: AsRef<[u8]>>(header: &AnimationHeader, frames: &[F]) -> Vec<u8> {
+    let header = AnimationHeader {
+        frame_count: u16::try_from(frames.len()).expect("too many frames"),
+        ..*header
+    };
+    let bpp = header.bpp as usize;
+    let mut data = Vec::from(header.to_bytes());
+    for frame in frames {
+        let frame = frame.as_ref();
+        assert_eq!(
+            frame.len(),
+            header.pixel_len as usize * bpp,
+            "frame shall be {} pixels x {} bytes",
+            header.pixel_len,
+            bpp
+        );
+        match header.encoding {
+            Encoding::Raw => data.extend_from_slice(frame),
+            Encoding::RunLength => {
+                let mut pixels = frame.chunks_exact(bpp).peekable();
+                while let Some(pixel) = pixels.next() {
+                    let mut count = 1u8;
+                    while count < u8::MAX && pixels.next_if_eq(&pixel).is_some() {
+                        count += 1;
+                    }
+                    data.push(count);
+                    data.extend_from_slice(pixel);
+                }
+            }
+            Encoding::PackBits => {
+                let pixels = frame.chunks_exact(bpp).collect::<Vec<_>>();
+                let mut i = 0;
+                while i < pixels.len() {
+                    let repeat = pixels[i..]
+                        .iter()
+                        .take(128)
+                        .take_while(|&&pixel| pixel == pixels[i])
+                        .count();
+                    if repeat >= 2 {
+                        data.push(repeat as u8 - 1);
+                        data.extend_from_slice(pixels[i]);
+                        i += repeat;
+                    } else {
+                        // Up to 128 LED pixels until a repeat begins
+                        let start = i;
+                        i += 1;
+                        while i < pixels.len()
+                            && i - start < 128
+                            && pixels.get(i + 1) != Some(&pixels[i])
+                        {
+                            i += 1;
+                        }
+                        data.push((127 + i - start) as u8);
+                        data.extend_from_slice(&frame[start * bpp..i * bpp]);
+                    }
+                }
+            }
+        }
+    }
+    data
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::Ws2812Esp32RmtDriver;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+
+    const HEADER: AnimationHeader = AnimationHeader {
+        encoding: Encoding::RunLength,
+        bpp: 3,
+        pixel_len: 300,
+        frame_count: 0,
+        frame_interval: Duration::from_millis(20),
+    };
+
+    #[test]
+    fn test_animation_encode_parse() {
+        let frames = [[0x10; 900], [0x20; 900]];
+        let frame2 = (0..300).flat_map(|i| [i as u8, 0, 0]).collect::<Vec<_>>();
+
+        let rle = encode(&HEADER, &[&frames[0][..], &frames[1][..], &frame2]);
+        // 300 = 255 + 45 pixels: 2 runs of 4 bytes for the solid frames
+        assert_eq!(rle.len(), HEADER_LEN + 8 + 8 + 300 * 4);
+        let animation = Animation::parse(&rle).unwrap();
+        assert_eq!(animation.header().frame_count, 3);
+        assert_eq!(animation.header().frame_interval, Duration::from_millis(20));
+        let decoded = animation
+            .frames()
+            .map(|frame| frame.bytes().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            decoded,
+            [frames[0].to_vec(), frames[1].to_vec(), frame2.clone()]
+        );
+
+        let header = AnimationHeader {
+            encoding: Encoding::Raw,
+            ..HEADER
+        };
+        let raw = encode(&header, &[&frame2]);
+        assert_eq!(raw.len(), HEADER_LEN + 900);
+        let animation = Animation::parse(&raw).unwrap();
+        assert_eq!(
+            animation
+                .frames()
+                .next()
+                .unwrap()
+                .bytes()
+                .collect::<Vec<_>>(),
+            frame2
+        );
+    }
+
+    #[test]
+    fn test_animation_packbits() {
+        let header = AnimationHeader {
+            encoding: Encoding::PackBits,
+            ..HEADER
+        };
+        let solid = [0x10; 900];
+        let gradient = (0..300).flat_map(|i| [i as u8, 0, 0]).collect::<Vec<_>>();
+        let mixed = (0..300)
+            .flat_map(|i| {
+                if i % 10 < 7 {
+                    [0, 0, 9]
+                } else {
+                    [i as u8, 1, 2]
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let data = encode(&header, &[&solid[..], &gradient, &mixed]);
+        let animation = Animation::parse(&data).unwrap();
+        let lens = animation
+            .frames()
+            .map(|frame| frame.data.len())
+            .collect::<Vec<_>>();
+        // 128 + 128 + 44 pixels in 3 runs each
+        assert_eq!(lens[0], 3 * 4);
+        assert_eq!(lens[1], 3 + 900);
+        // A repeat run of 7 pixels and a span of 3 pixels per 10 pixels
+        assert_eq!(lens[2], 30 * (4 + 1 + 9));
+        let decoded = animation
+            .frames()
+            .map(|frame| frame.bytes().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(decoded, [solid.to_vec(), gradient, mixed]);
+
+        let mut bad = data.clone();
+        bad[HEADER_LEN] = 0xFF;
+        assert_eq!(Animation::parse(&bad), Err(AnimationError::InvalidFrame(0)));
+    }
+
+    #[test]
+    fn test_animation_parse_error() {
+        let data = encode(&HEADER, &[[0; 900]]);
+        assert_eq!(
+            Animation::parse(&data[..10]),
+            Err(AnimationError::Truncated)
+        );
+        assert_eq!(
+            Animation::parse(&data[..data.len() - 1]),
+            Err(AnimationError::InvalidFrame(0))
+        );
+
+        let mut bad = data.clone();
+        bad[0] = b'X';
+        assert_eq!(Animation::parse(&bad), Err(AnimationError::BadMagic));
+        let mut bad = data.clone();
+        bad[4] = 2;
+        assert_eq!(
+            Animation::parse(&bad),
+            Err(AnimationError::UnsupportedVersion(2))
+        );
+        let mut bad = data.clone();
+        bad[5] = 9;
+        assert_eq!(
+            Animation::parse(&bad),
+            Err(AnimationError::UnknownEncoding(9))
+        );
+        let mut bad = data.clone();
+        bad[HEADER_LEN] = 0;
+        assert_eq!(Animation::parse(&bad), Err(AnimationError::InvalidFrame(0)));
+    }
+
+    #[test]
+    fn test_animation_player() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+        driver.record_history();
+
+        let header = AnimationHeader {
+            pixel_len: 2,
+            ..HEADER
+        };
+        let data = encode(&header, &[[1; 6], [2; 6]]);
+        let mut player = AnimationPlayer::new(Animation::parse(&data).unwrap());
+
+        let mut delays = Vec::new();
+        player
+            .play(&mut driver, |interval| delays.push(interval))
+            .unwrap();
+        assert_eq!(delays, [Duration::from_millis(20)]);
+        assert!(player.is_finished());
+        assert!(!player.play_next(&mut driver).unwrap());
+        assert_eq!(driver.frame_history(), [vec![1; 6], vec![2; 6]]);
+
+        player.set_looping(true);
+        assert!(!player.is_finished());
+        for _ in 0..3 {
+            assert!(player.play_next(&mut driver).unwrap());
+        }
+        assert_eq!(driver.pixel_data, Some(vec![1; 6]));
+    }
+}
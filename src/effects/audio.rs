@@ -0,0 +1,308 @@
+//! Audio-reactive effects.
+//!
+//! The effects map the audio levels computed outside, e.g. by the ADC sampling and the FFT of the
+//! application, onto the LED pixels. The levels are supplied before rendering each frame:
+//! [`VuMeter`] shows a level as a bar on a strip, and [`Spectrum`] shows the frequency bins as
+//! the bars on a matrix. The levels fall gradually rather than dropping, which is easier to
+//! follow by eyes.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::driver::color::{LedPixelColor, LedPixelColorGrb24};
+//! use ws2812_esp32_rmt_driver::effects::audio::Spectrum;
+//! use ws2812_esp32_rmt_driver::effects::{write_pixels, Effect};
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio27).unwrap();
+//!
+//! // 8x8 matrix in the row-major order
+//! let mut pixels = vec![LedPixelColorGrb24::default(); 64];
+//! let mut spectrum = Spectrum::<_, 8>::new(
+//!     LedPixelColorGrb24::new_with_rgb(0, 255, 0),
+//!     LedPixelColorGrb24::new_with_rgb(255, 0, 0),
+//! );
+//! for tick in 0..10 {
+//!     // magnitudes of the FFT bins, scaled to 0 to 255
+//!     let bins = [200u8; 128];
+//!     spectrum.set_bins(&bins);
+//!     spectrum.render(tick, &mut pixels);
+//!     write_pixels(&mut driver, &pixels).unwrap();
+//! }
+//! ```
+
+use super::transition::blend;
+use super::{black, Effect};
+use crate::driver::color::LedPixelColor;
+
+#[cfg(feature = "embedded-graphics-core")]
+use crate::lib_embedded_graphics::LedPixelShape;
+#[cfg(feature = "embedded-graphics-core")]
+use embedded_graphics_core::geometry::Point;
+
+/// Level fallen by `decay` from `current`, or `level` if it is higher.
+#[inline]
+fn fall(current: u8, level: u8, decay: u8) -> u8 {
+    level.max(current.saturating_sub(decay))
+}
+
+/// Returns the number of the LED pixels lit of `len` at `level`, rounded.
+#[inline]
+fn lit_len(level: u8, len: usize) -> usize {
+    (level as usize * len + u8::MAX as usize / 2) / u8::MAX as usize
+}
+
+/// Returns the color of the `index`-th LED pixel of `len` in the gradient.
+fn gradient<C: LedPixelColor>(low: &C, high: &C, index: usize, len: usize) -> C {
+    let amount = index * u8::MAX as usize / len.saturating_sub(1).max(1);
+    blend(low, high, amount as u8)
+}
+
+/// VU meter: a bar of the level with the peak hold.
+///
+/// The bar grows from the first pixel in the gradient from `low` to `high`, and the peak is shown
+/// in `high`.
+#[derive(Debug, Clone)]
+pub struct VuMeter<C> {
+    /// Color of the bottom of the bar
+    pub low: C,
+    /// Color of the top of the bar and the peak
+    pub high: C,
+    /// Fall of the level per level supplied
+    pub decay: u8,
+    /// Fall of the peak per level supplied
+    pub peak_decay: u8,
+    level: u8,
+    peak: u8,
+}
+
+impl<C> VuMeter<C> {
+    /// Creates a VU meter in the gradient from `low` to `high`.
+    pub fn new(low: C, high: C) -> Self {
+        Self {
+            low,
+            high,
+            decay: 16,
+            peak_decay: 2,
+            level: 0,
+            peak: 0,
+        }
+    }
+
+    /// Supplies the level, 0 to 255.
+    pub fn set_level(&mut self, level: u8) {
+        self.level = fall(self.level, level, self.decay);
+        self.peak = fall(self.peak, self.level, self.peak_decay);
+    }
+
+    /// Returns the level shown.
+    #[inline]
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// Returns the peak shown.
+    #[inline]
+    pub fn peak(&self) -> u8 {
+        self.peak
+    }
+}
+
+impl<C: LedPixelColor> Effect<C> for VuMeter<C> {
+    fn render(&mut self, _tick: u32, pixels: &mut [C]) {
+        let len = pixels.len();
+        let lit = lit_len(self.level, len);
+        for (index, pixel) in pixels.iter_mut().enumerate() {
+            *pixel = if index < lit {
+                gradient(&self.low, &self.high, index, len)
+            } else {
+                black()
+            };
+        }
+        let peak = lit_len(self.peak, len);
+        if peak > 0 {
+            pixels[peak - 1] = self.high.clone();
+        }
+    }
+}
+
+/// Spectrum: a bar per band of the frequency bins.
+///
+/// The bars of the `N` bands grow from the bottom row in the gradient from `low` to `high`.
+/// As an [`Effect`], the LED pixels are a matrix of `N` columns in the row-major order.
+///
+/// * `N` - the number of the bands
+#[derive(Debug, Clone)]
+pub struct Spectrum<C, const N: usize> {
+    /// Color of the bottom of the bars
+    pub low: C,
+    /// Color of the top of the bars
+    pub high: C,
+    /// Fall of the levels per bins supplied
+    pub decay: u8,
+    levels: [u8; N],
+}
+
+impl<C, const N: usize> Spectrum<C, N> {
+    /// Creates a spectrum in the gradient from `low` to `high`.
+    pub fn new(low: C, high: C) -> Self {
+        Self {
+            low,
+            high,
+            decay: 16,
+            levels: [0; N],
+        }
+    }
+
+    /// Supplies the levels of the frequency bins, 0 to 255 each, from the lowest frequency.
+    ///
+    /// The bins are grouped into `N` bands of the same count, and the level of a band is the
+    /// maximum of its bins. A bin is shared by the bands if the bins are fewer than the bands.
+    pub fn set_bins(&mut self, bins: &[u8]) {
+        if bins.is_empty() {
+            return self.levels.fill(0);
+        }
+        for (band, level) in self.levels.iter_mut().enumerate() {
+            let start = band * bins.len() / N;
+            let end = ((band + 1) * bins.len() / N).max(start + 1);
+            let max = bins[start..end].iter().copied().max().unwrap_or(0);
+            *level = fall(*level, max, self.decay);
+        }
+    }
+
+    /// Returns the levels of the bands shown.
+    #[inline]
+    pub fn levels(&self) -> &[u8; N] {
+        &self.levels
+    }
+}
+
+impl<C: LedPixelColor, const N: usize> Spectrum<C, N> {
+    /// Returns the color of the point of the matrix of `height` rows.
+    fn color_at(&self, x: usize, y: usize, width: usize, height: usize) -> C {
+        let level = self.levels[x * N / width.max(1)];
+        let row = height - 1 - y;
+        if row < lit_len(level, height) {
+            gradient(&self.low, &self.high, row, height)
+        } else {
+            black()
+        }
+    }
+
+    /// Renders the bars into `pixels` of the LED pixel shape `S`.
+    ///
+    /// The bands are stretched or shrunk to the width of the shape.
+    #[cfg(feature = "embedded-graphics-core")]
+    pub fn render_shape<S: LedPixelShape>(&self, pixels: &mut [C]) {
+        let size = S::size();
+        let (width, height) = (size.width as usize, size.height as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let Some(index) = S::pixel_index(Point::new(x as i32, y as i32)) else {
+                    continue;
+                };
+                if let Some(pixel) = pixels.get_mut(index) {
+                    *pixel = self.color_at(x, y, width, height);
+                }
+            }
+        }
+    }
+}
+
+impl<C: LedPixelColor, const N: usize> Effect<C> for Spectrum<C, N> {
+    fn render(&mut self, _tick: u32, pixels: &mut [C]) {
+        if N == 0 {
+            return pixels.fill(black());
+        }
+        let height = pixels.len() / N;
+        for (index, pixel) in pixels.iter_mut().enumerate() {
+            let (x, y) = (index % N, index / N);
+            *pixel = if y < height {
+                self.color_at(x, y, N, height)
+            } else {
+                black()
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::LedPixelColorGrb24;
+
+    type Color = LedPixelColorGrb24;
+
+    fn lit(pixels: &[Color]) -> Vec<bool> {
+        pixels.iter().map(|c| *c != Color::default()).collect()
+    }
+
+    #[test]
+    fn test_vu_meter() {
+        let green = Color::new_with_rgb(0, 255, 0);
+        let red = Color::new_with_rgb(255, 0, 0);
+        let mut pixels: [Color; 5] = Default::default();
+        let mut meter = VuMeter::new(green.clone(), red.clone());
+
+        meter.set_level(153);
+        meter.render(0, &mut pixels);
+        assert_eq!(lit(&pixels), [true, true, true, false, false]);
+        assert_eq!(pixels[0], green);
+        assert_eq!(pixels[2], red);
+        assert_eq!(
+            (pixels[1].r(), pixels[1].g(), pixels[1].b()),
+            (0x3F, 0xC0, 0x00)
+        );
+
+        meter.set_level(0);
+        assert_eq!((meter.level(), meter.peak()), (137, 151));
+        meter.render(1, &mut pixels);
+        assert_eq!(lit(&pixels), [true, true, true, false, false]);
+        for _ in 0..10 {
+            meter.set_level(0);
+        }
+        meter.render(2, &mut pixels);
+        assert_eq!(lit(&pixels), [false, false, true, false, false]);
+    }
+
+    #[test]
+    fn test_spectrum() {
+        let green = Color::new_with_rgb(0, 255, 0);
+        let red = Color::new_with_rgb(255, 0, 0);
+        let mut pixels: [Color; 6] = Default::default();
+        let mut spectrum = Spectrum::<_, 2>::new(green.clone(), red.clone());
+
+        spectrum.set_bins(&[0, 255, 0, 128, 64, 0]);
+        assert_eq!(spectrum.levels(), &[255, 128]);
+        spectrum.render(0, &mut pixels);
+        assert_eq!(lit(&pixels), [true, false, true, true, true, true]);
+        assert_eq!(pixels[0], red);
+        assert_eq!(pixels[4], green);
+
+        spectrum.set_bins(&[32]);
+        assert_eq!(spectrum.levels(), &[239, 112]);
+        spectrum.set_bins(&[]);
+        assert_eq!(spectrum.levels(), &[0, 0]);
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-graphics-core")]
+    fn test_spectrum_render_shape() {
+        use crate::lib_embedded_graphics::LedPixelMatrix;
+
+        let green = Color::new_with_rgb(0, 255, 0);
+        let mut pixels: [Color; 8] = Default::default();
+        let mut spectrum = Spectrum::<_, 2>::new(green.clone(), green);
+        spectrum.set_bins(&[255, 0]);
+        spectrum.render_shape::<LedPixelMatrix<4, 2>>(&mut pixels);
+        assert_eq!(
+            lit(&pixels),
+            [true, true, false, false, true, true, false, false]
+        );
+    }
+}
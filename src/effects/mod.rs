@@ -0,0 +1,487 @@
+//! LED effects engine.
+//!
+//! Tick-driven implementations of the classic effects. Each [`Effect`] renders a frame into a
+//! caller-owned LED pixel color buffer without allocation, and the frame is written with
+//! [`write_pixels()`] through the driver, [`write_smart_leds()`] through the smart-leds wrapper,
+//! or [`draw_pixels()`] through the embedded-graphics draw target.
+//!
+//! [`transition`] crossfades between frames, e.g. on scene changes, and [`animation`] plays
+//! animations stored in flash, e.g. boot animations. [`segment`] runs independent effects on
+//! the ranges of a strip (requires `alloc` feature). [`audio`] maps the audio levels onto the
+//! LED pixels, e.g. VU meters and spectrum bars.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::driver::color::LedPixelColorGrb24;
+//! use ws2812_esp32_rmt_driver::effects::{write_pixels, Effect, RainbowCycle};
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio27).unwrap();
+//!
+//! let mut pixels: [LedPixelColorGrb24; 25] = Default::default();
+//! let mut effect = RainbowCycle::new(4);
+//! for tick in 0..10 {
+//!     effect.render(tick, &mut pixels);
+//!     write_pixels(&mut driver, &pixels).unwrap();
+//! }
+//! ```
+
+pub mod animation;
+pub mod audio;
+#[cfg(feature = "alloc")]
+pub mod segment;
+pub mod transition;
+
+use crate::driver::color::LedPixelColor;
+use crate::driver::LedStripWrite;
+use crate::rng::Rng;
+
+#[cfg(feature = "embedded-graphics-core")]
+use embedded_graphics_core::{draw_target::DrawTarget, geometry::Point, pixelcolor::Rgb888, Pixel};
+#[cfg(feature = "smart-leds-trait")]
+use smart_leds_trait::{SmartLedsWrite, RGB8};
+
+/// Tick-driven LED effect
+///
+/// * `C` - the LED pixel color type
+pub trait Effect<C: LedPixelColor> {
+    /// Renders the frame of `tick` into `pixels`.
+    ///
+    /// `tick` is the frame count, which is typically incremented by one on every frame, or
+    /// [`FramePacer::frame()`] to follow the time rather than the frames written.
+    ///
+    /// [`FramePacer::frame()`]: crate::time::FramePacer::frame
+    fn render(&mut self, tick: u32, pixels: &mut [C]);
+}
+
+/// Writes the LED pixel colors through the driver.
+///
+/// # Errors
+///
+/// Returns the error returned by `writer`.
+pub fn write_pixels<W, C>(writer: &mut W, pixels: &[C]) -> Result<(), W::Error>
+where
+    W: LedStripWrite,
+    C: LedPixelColor,
+{
+    writer.write_blocking(
+        pixels
+            .iter()
+            .flat_map(|color| color.as_ref().iter().copied()),
+    )
+}
+
+/// Writes the LED pixel colors through the smart-leds API.
+///
+/// The white channel is dropped.
+///
+/// # Errors
+///
+/// Returns the error returned by `writer`.
+#[cfg(feature = "smart-leds-trait")]
+pub fn write_smart_leds<W, C>(writer: &mut W, pixels: &[C]) -> Result<(), W::Error>
+where
+    W: SmartLedsWrite<Color = RGB8>,
+    C: LedPixelColor,
+{
+    writer.write(
+        pixels
+            .iter()
+            .map(|color| RGB8::new(color.r(), color.g(), color.b())),
+    )
+}
+
+/// Draws the LED pixel colors to the draw target in the row-major order of its bounding box.
+///
+/// The white channel is dropped.
+/// Note that [`flush()`] is required for the [`LedPixelDrawTarget`] to write the frame.
+///
+/// # Errors
+///
+/// Returns the error returned by `target`.
+///
+/// [`flush()`]: crate::lib_embedded_graphics::LedPixelDrawTarget::flush
+/// [`LedPixelDrawTarget`]: crate::lib_embedded_graphics::LedPixelDrawTarget
+#[cfg(feature = "embedded-graphics-core")]
+pub fn draw_pixels<D, C>(target: &mut D, pixels: &[C]) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = Rgb888>,
+    C: LedPixelColor,
+{
+    let bounding_box = target.bounding_box();
+    let width = bounding_box.size.width.max(1) as usize;
+    target.draw_iter(pixels.iter().enumerate().map(|(index, color)| {
+        let point = Point::new((index % width) as i32, (index / width) as i32);
+        Pixel(
+            bounding_box.top_left + point,
+            Rgb888::new(color.r(), color.g(), color.b()),
+        )
+    }))
+}
+
+/// Returns the color on the color wheel at `pos`: red, green, blue and back to red.
+pub fn wheel<C: LedPixelColor>(pos: u8) -> C {
+    let pos = u8::MAX - pos;
+    match pos {
+        0..85 => C::new_with_rgb(255 - pos * 3, 0, pos * 3),
+        85..170 => {
+            let pos = pos - 85;
+            C::new_with_rgb(0, pos * 3, 255 - pos * 3)
+        }
+        _ => {
+            let pos = pos - 170;
+            C::new_with_rgb(pos * 3, 255 - pos * 3, 0)
+        }
+    }
+}
+
+/// Returns the black color (All LED OFF)
+#[inline]
+fn black<C: LedPixelColor>() -> C {
+    C::new_with_rgb(0, 0, 0)
+}
+
+/// Solid: the whole strip filled with the color.
+#[derive(Debug, Clone)]
+pub struct Solid<C> {
+    /// Color to fill
+    pub color: C,
+}
+
+impl<C> Solid<C> {
+    /// Creates a solid of `color`.
+    pub fn new(color: C) -> Self {
+        Self { color }
+    }
+}
+
+impl<C: LedPixelColor> Effect<C> for Solid<C> {
+    fn render(&mut self, _tick: u32, pixels: &mut [C]) {
+        pixels.fill(self.color.clone());
+    }
+}
+
+/// Rainbow cycle: the color wheel spread over the strip and rotated on every tick.
+#[derive(Debug, Clone)]
+pub struct RainbowCycle {
+    /// Rotation of the color wheel per tick
+    pub speed: u8,
+}
+
+impl RainbowCycle {
+    /// Creates a rainbow cycle rotating by `speed` per tick.
+    pub fn new(speed: u8) -> Self {
+        Self { speed }
+    }
+}
+
+impl<C: LedPixelColor> Effect<C> for RainbowCycle {
+    fn render(&mut self, tick: u32, pixels: &mut [C]) {
+        let len = pixels.len().max(1);
+        let offset = tick.wrapping_mul(self.speed as u32) as usize;
+        for (index, pixel) in pixels.iter_mut().enumerate() {
+            *pixel = wheel((index * 256 / len + offset) as u8);
+        }
+    }
+}
+
+/// Theater chase: every `spacing`-th pixel lit and marching on every tick.
+#[derive(Debug, Clone)]
+pub struct TheaterChase<C> {
+    /// Color of the lit pixels
+    pub color: C,
+    /// Distance between the lit pixels
+    pub spacing: usize,
+}
+
+impl<C> TheaterChase<C> {
+    /// Creates a theater chase of `color` lighting every `spacing`-th pixel.
+    pub fn new(color: C, spacing: usize) -> Self {
+        Self { color, spacing }
+    }
+}
+
+impl<C: LedPixelColor> Effect<C> for TheaterChase<C> {
+    fn render(&mut self, tick: u32, pixels: &mut [C]) {
+        let spacing = self.spacing.max(1);
+        let phase = tick as usize % spacing;
+        for (index, pixel) in pixels.iter_mut().enumerate() {
+            *pixel = if index % spacing == phase {
+                self.color.clone()
+            } else {
+                black()
+            };
+        }
+    }
+}
+
+/// Breathe: the whole strip fading in and out with an eased brightness.
+#[derive(Debug, Clone)]
+pub struct Breathe<C> {
+    /// Color at the full brightness
+    pub color: C,
+    /// Ticks of a breath
+    pub period: u32,
+}
+
+impl<C> Breathe<C> {
+    /// Creates a breathe of `color` taking `period` ticks per breath.
+    pub fn new(color: C, period: u32) -> Self {
+        Self { color, period }
+    }
+}
+
+impl<C: LedPixelColor> Effect<C> for Breathe<C> {
+    fn render(&mut self, tick: u32, pixels: &mut [C]) {
+        let period = self.period.max(2) as u64;
+        let phase = tick as u64 % period;
+        let half = period / 2;
+        let triangle = if phase < half { phase } else { period - phase };
+        let linear = (triangle * 255 / half.max(1)).min(255);
+        let level = (linear * linear / 255) as u8;
+        let color = self.color.brightness(level);
+        pixels.fill(color);
+    }
+}
+
+/// Sparkle: random pixels flashing on every tick.
+#[derive(Debug, Clone)]
+pub struct Sparkle<C> {
+    /// Color of the sparkles
+    pub color: C,
+    /// Probability of each pixel to sparkle on a tick, out of 256
+    pub density: u8,
+    rng: Rng,
+}
+
+impl<C> Sparkle<C> {
+    /// Creates a sparkle of `color` with `density`.
+    /// The sparkles are determined by `seed`.
+    pub fn new(color: C, density: u8, seed: u32) -> Self {
+        Self {
+            color,
+            density,
+            rng: Rng::new(u64::from(seed)),
+        }
+    }
+}
+
+impl<C: LedPixelColor> Effect<C> for Sparkle<C> {
+    fn render(&mut self, _tick: u32, pixels: &mut [C]) {
+        for pixel in pixels.iter_mut() {
+            *pixel = if self.rng.next_u8() < self.density {
+                self.color.clone()
+            } else {
+                black()
+            };
+        }
+    }
+}
+
+/// Color wipe: the strip filled with the color one pixel per tick, and then cleared.
+#[derive(Debug, Clone)]
+pub struct ColorWipe<C> {
+    /// Color to fill
+    pub color: C,
+}
+
+impl<C> ColorWipe<C> {
+    /// Creates a color wipe of `color`.
+    pub fn new(color: C) -> Self {
+        Self { color }
+    }
+}
+
+impl<C: LedPixelColor> Effect<C> for ColorWipe<C> {
+    fn render(&mut self, tick: u32, pixels: &mut [C]) {
+        let filled = tick as usize % (pixels.len() + 1);
+        for (index, pixel) in pixels.iter_mut().enumerate() {
+            *pixel = if index < filled {
+                self.color.clone()
+            } else {
+                black()
+            };
+        }
+    }
+}
+
+/// Fire: flickering flames rising from the first pixel.
+///
+/// * `N` - the number of the pixels simulated. The pixels beyond it are black.
+#[derive(Debug, Clone)]
+pub struct Fire<const N: usize> {
+    /// Cooling of the flames rising up
+    pub cooling: u8,
+    /// Probability of a new spark on a tick, out of 256
+    pub sparking: u8,
+    heat: [u8; N],
+    rng: Rng,
+}
+
+impl<const N: usize> Fire<N> {
+    /// Creates a fire with `cooling` and `sparking`, e.g. 55 and 120.
+    /// The flames are determined by `seed`.
+    pub fn new(cooling: u8, sparking: u8, seed: u32) -> Self {
+        Self {
+            cooling,
+            sparking,
+            heat: [0; N],
+            rng: Rng::new(u64::from(seed)),
+        }
+    }
+
+    /// Returns the color of the black body at `heat`.
+    fn heat_color<C: LedPixelColor>(heat: u8) -> C {
+        let t192 = (heat as u16 * 191 / 255) as u8;
+        let ramp = (t192 & 0x3F) << 2;
+        if t192 & 0x80 != 0 {
+            C::new_with_rgb(255, 255, ramp)
+        } else if t192 & 0x40 != 0 {
+            C::new_with_rgb(255, ramp, 0)
+        } else {
+            C::new_with_rgb(ramp, 0, 0)
+        }
+    }
+}
+
+impl<C: LedPixelColor, const N: usize> Effect<C> for Fire<N> {
+    fn render(&mut self, _tick: u32, pixels: &mut [C]) {
+        let cool_max = (self.cooling as usize * 10 / N.max(1)) + 2;
+        for heat in self.heat.iter_mut() {
+            *heat = heat.saturating_sub(self.rng.below(cool_max + 1) as u8);
+        }
+        for k in (2..N).rev() {
+            self.heat[k] = ((self.heat[k - 1] as u16 + 2 * self.heat[k - 2] as u16) / 3) as u8;
+        }
+        if N > 0 && self.rng.next_u8() < self.sparking {
+            let y = self.rng.below(N.min(7));
+            let spark = 160 + self.rng.below(96) as u8;
+            self.heat[y] = self.heat[y].saturating_add(spark);
+        }
+
+        for (index, pixel) in pixels.iter_mut().enumerate() {
+            *pixel = self
+                .heat
+                .get(index)
+                .map_or_else(black, |&heat| Self::heat_color(heat));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::{LedPixelColorGrb24, LedPixelColorGrbw32};
+    use crate::driver::Ws2812Esp32RmtDriver;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+
+    type Color = LedPixelColorGrb24;
+
+    fn rgb(color: &Color) -> (u8, u8, u8) {
+        (color.r(), color.g(), color.b())
+    }
+
+    #[test]
+    fn test_wheel() {
+        assert_eq!(rgb(&wheel::<Color>(0)), (255, 0, 0));
+        assert_eq!(rgb(&wheel::<Color>(85)), (0, 255, 0));
+        assert_eq!(rgb(&wheel::<Color>(170)), (0, 0, 255));
+        assert_eq!(rgb(&wheel::<Color>(255)), (255, 0, 0));
+    }
+
+    #[test]
+    fn test_rainbow_cycle() {
+        let mut pixels: [Color; 3] = Default::default();
+        let mut effect = RainbowCycle::new(85);
+        effect.render(0, &mut pixels);
+        assert_eq!(
+            pixels.each_ref().map(rgb),
+            [(255, 0, 0), (0, 255, 0), (0, 0, 255)]
+        );
+        effect.render(1, &mut pixels);
+        assert_eq!(
+            pixels.each_ref().map(rgb),
+            [(0, 255, 0), (0, 0, 255), (255, 0, 0)]
+        );
+    }
+
+    #[test]
+    fn test_theater_chase_and_color_wipe() {
+        let red = Color::new_with_rgb(255, 0, 0);
+        let mut pixels: [Color; 5] = Default::default();
+
+        let mut chase = TheaterChase::new(red.clone(), 3);
+        chase.render(1, &mut pixels);
+        assert_eq!(
+            pixels.each_ref().map(|c| *c == red),
+            [false, true, false, false, true]
+        );
+
+        let mut wipe = ColorWipe::new(red.clone());
+        wipe.render(2, &mut pixels);
+        assert_eq!(
+            pixels.each_ref().map(|c| *c == red),
+            [true, true, false, false, false]
+        );
+        wipe.render(5, &mut pixels);
+        assert!(pixels.iter().all(|c| *c == red));
+        wipe.render(6, &mut pixels);
+        assert!(pixels.iter().all(|c| *c == Color::default()));
+    }
+
+    #[test]
+    fn test_breathe() {
+        let white = LedPixelColorGrbw32::new_with_rgbw(0, 0, 0, 255);
+        let mut pixels: [LedPixelColorGrbw32; 2] = Default::default();
+        let mut effect = Breathe::new(white.clone(), 100);
+
+        effect.render(0, &mut pixels);
+        assert_eq!(pixels[0].w(), 0);
+        effect.render(25, &mut pixels);
+        assert!((60..=70).contains(&pixels[0].w()));
+        effect.render(50, &mut pixels);
+        assert_eq!(pixels, [white.clone(), white]);
+        effect.render(75, &mut pixels);
+        assert!((60..=70).contains(&pixels[1].w()));
+    }
+
+    #[test]
+    fn test_sparkle_and_fire_deterministic() {
+        let white = Color::new_with_rgb(255, 255, 255);
+        let mut pixels1: [Color; 30] = Default::default();
+        let mut pixels2: [Color; 30] = Default::default();
+
+        let mut sparkle1 = Sparkle::new(white.clone(), 64, 7);
+        let mut sparkle2 = Sparkle::new(white, 64, 7);
+        let mut fire1 = Fire::<20>::new(55, 120, 7);
+        let mut fire2 = Fire::<20>::new(55, 120, 7);
+        for tick in 0..50 {
+            sparkle1.render(tick, &mut pixels1);
+            sparkle2.render(tick, &mut pixels2);
+            assert_eq!(pixels1, pixels2);
+            fire1.render(tick, &mut pixels1);
+            fire2.render(tick, &mut pixels2);
+            assert_eq!(pixels1, pixels2);
+        }
+        assert!(pixels1[..20].iter().any(|c| c.r() > 0));
+        assert!(pixels1[20..].iter().all(|c| *c == Color::default()));
+    }
+
+    #[test]
+    fn test_write_pixels() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        let pixels = [Color::new_with_rgb(1, 2, 3), Color::new_with_rgb(4, 5, 6)];
+        write_pixels(&mut driver, &pixels).unwrap();
+        assert_eq!(driver.pixel_data, Some(vec![2, 1, 3, 5, 4, 6]));
+    }
+}
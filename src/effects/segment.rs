@@ -0,0 +1,329 @@
+//! Segments of a LED strip.
+//!
+//! [`Segments`] splits one physical strip into named ranges of the LED pixels, each of which has
+//! its own [`Effect`] and brightness. The segments are rendered into the combined frame, which is
+//! written through the driver at once.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::driver::color::{LedPixelColor, LedPixelColorGrb24};
+//! use ws2812_esp32_rmt_driver::effects::segment::Segments;
+//! use ws2812_esp32_rmt_driver::effects::{ColorWipe, RainbowCycle};
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio27).unwrap();
+//!
+//! let mut segments = Segments::<LedPixelColorGrb24>::new(60);
+//! segments.add("shelf", 0..40, RainbowCycle::new(4)).unwrap();
+//! segments
+//!     .add("accent", 40..60, ColorWipe::new(LedPixelColorGrb24::new_with_rgb(255, 0, 0)))
+//!     .unwrap()
+//!     .brightness = 64;
+//!
+//! for tick in 0..10 {
+//!     segments.update(tick, &mut driver).unwrap();
+//! }
+//! ```
+
+use super::{black, write_pixels, Effect};
+use crate::driver::color::LedPixelColor;
+use crate::driver::LedStripWrite;
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::error::Error;
+use core::fmt;
+use core::ops::Range;
+
+/// Named range of the LED pixels with its own effect and brightness
+pub struct Segment<C: LedPixelColor> {
+    name: String,
+    range: Range<usize>,
+    /// Brightness applied to the rendered colors, 255 for as is
+    pub brightness: u8,
+    effect: Box<dyn Effect<C> + Send>,
+}
+
+impl<C: LedPixelColor> Segment<C> {
+    /// Returns the name of the segment.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the range of the LED pixel indices of the segment.
+    #[inline]
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Replaces the effect of the segment.
+    pub fn set_effect<E: Effect<C> + Send + 'static>(&mut self, effect: E) {
+        self.effect = Box::new(effect);
+    }
+
+    /// Replaces the effect of the segment by the boxed one, e.g. created at runtime.
+    pub fn set_boxed_effect(&mut self, effect: Box<dyn Effect<C> + Send>) {
+        self.effect = effect;
+    }
+}
+
+impl<C: LedPixelColor> fmt::Debug for Segment<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Segment")
+            .field("name", &self.name)
+            .field("range", &self.range)
+            .field("brightness", &self.brightness)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Segment definition error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SegmentError {
+    /// The range is empty or exceeds the strip.
+    OutOfRange,
+    /// The range overlaps another segment.
+    Overlapped,
+    /// The name is used by another segment.
+    DuplicateName,
+}
+
+impl Error for SegmentError {}
+
+impl fmt::Display for SegmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfRange => write!(f, "segment range is empty or exceeds the strip"),
+            Self::Overlapped => write!(f, "segment range overlaps another segment"),
+            Self::DuplicateName => write!(f, "segment name is already used"),
+        }
+    }
+}
+
+/// Segments of a LED strip composing the frame of the strip
+///
+/// The LED pixels not covered by any segment are black.
+#[derive(Debug)]
+pub struct Segments<C: LedPixelColor> {
+    pixels: Vec<C>,
+    segments: Vec<Segment<C>>,
+}
+
+impl<C: LedPixelColor> Segments<C> {
+    /// Creates segments of a strip of `len` LED pixels, without any segment.
+    pub fn new(len: usize) -> Self {
+        Self {
+            pixels: core::iter::repeat_with(black).take(len).collect(),
+            segments: Vec::new(),
+        }
+    }
+
+    /// Returns the number of the LED pixels of the strip.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.pixels.len()
+    }
+
+    /// Returns `true` if the strip has no LED pixel.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pixels.is_empty()
+    }
+
+    /// Adds a segment of the LED pixels in `range` rendered by `effect` at full brightness.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `range` is empty, exceeds the strip or overlaps another segment,
+    /// or if `name` is already used.
+    pub fn add<E>(
+        &mut self,
+        name: impl Into<String>,
+        range: Range<usize>,
+        effect: E,
+    ) -> Result<&mut Segment<C>, SegmentError>
+    where
+        E: Effect<C> + Send + 'static,
+    {
+        let name = name.into();
+        if range.is_empty() || range.end > self.pixels.len() {
+            return Err(SegmentError::OutOfRange);
+        }
+        if self
+            .segments
+            .iter()
+            .any(|s| s.range.start < range.end && range.start < s.range.end)
+        {
+            return Err(SegmentError::Overlapped);
+        }
+        if self.get(&name).is_some() {
+            return Err(SegmentError::DuplicateName);
+        }
+        self.segments.push(Segment {
+            name,
+            range,
+            brightness: u8::MAX,
+            effect: Box::new(effect),
+        });
+        Ok(self.segments.last_mut().unwrap())
+    }
+
+    /// Removes the segment of the name and turns its LED pixels black.
+    pub fn remove(&mut self, name: &str) -> Option<Segment<C>> {
+        let index = self.segments.iter().position(|s| s.name == name)?;
+        let segment = self.segments.remove(index);
+        self.pixels[segment.range()].fill(black());
+        Some(segment)
+    }
+
+    /// Returns the segment of the name.
+    pub fn get(&self, name: &str) -> Option<&Segment<C>> {
+        self.segments.iter().find(|s| s.name == name)
+    }
+
+    /// Returns the mutable segment of the name.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Segment<C>> {
+        self.segments.iter_mut().find(|s| s.name == name)
+    }
+
+    /// Returns the segments in the order of addition.
+    pub fn iter(&self) -> impl Iterator<Item = &Segment<C>> {
+        self.segments.iter()
+    }
+
+    /// Returns the combined frame.
+    #[inline]
+    pub fn pixels(&self) -> &[C] {
+        &self.pixels
+    }
+
+    /// Renders the frame of `tick` of all the segments into the combined frame.
+    pub fn render(&mut self, tick: u32) {
+        for segment in &mut self.segments {
+            let pixels = &mut self.pixels[segment.range.clone()];
+            segment.effect.render(tick, pixels);
+            if segment.brightness < u8::MAX {
+                for pixel in pixels {
+                    *pixel = pixel.brightness(segment.brightness);
+                }
+            }
+        }
+    }
+
+    /// Writes the combined frame through the driver.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by `writer`.
+    pub fn flush<W: LedStripWrite>(&self, writer: &mut W) -> Result<(), W::Error> {
+        write_pixels(writer, &self.pixels)
+    }
+
+    /// Renders the frame of `tick` and writes it through the driver.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by `writer`.
+    pub fn update<W: LedStripWrite>(&mut self, tick: u32, writer: &mut W) -> Result<(), W::Error> {
+        self.render(tick);
+        self.flush(writer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::LedPixelColorGrb24;
+    use crate::driver::Ws2812Esp32RmtDriver;
+    use crate::effects::ColorWipe;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+
+    #[test]
+    fn test_segments_add() {
+        let red = LedPixelColorGrb24::new_with_rgb(255, 0, 0);
+        let mut segments = Segments::<LedPixelColorGrb24>::new(10);
+        segments
+            .add("a", 0..4, ColorWipe::new(red.clone()))
+            .unwrap();
+        segments
+            .add("b", 6..10, ColorWipe::new(red.clone()))
+            .unwrap();
+
+        assert_eq!(
+            segments
+                .add("c", 4..4, ColorWipe::new(red.clone()))
+                .unwrap_err(),
+            SegmentError::OutOfRange
+        );
+        assert_eq!(
+            segments
+                .add("c", 8..11, ColorWipe::new(red.clone()))
+                .unwrap_err(),
+            SegmentError::OutOfRange
+        );
+        assert_eq!(
+            segments
+                .add("c", 3..6, ColorWipe::new(red.clone()))
+                .unwrap_err(),
+            SegmentError::Overlapped
+        );
+        assert_eq!(
+            segments
+                .add("a", 4..6, ColorWipe::new(red.clone()))
+                .unwrap_err(),
+            SegmentError::DuplicateName
+        );
+        segments
+            .add("c", 4..6, ColorWipe::new(red.clone()))
+            .unwrap();
+
+        assert_eq!(
+            segments.iter().map(|s| s.name()).collect::<Vec<_>>(),
+            ["a", "b", "c"]
+        );
+        assert_eq!(segments.get("c").unwrap().range(), 4..6);
+        assert!(segments.get("d").is_none());
+    }
+
+    #[test]
+    fn test_segments_update() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        let mut segments = Segments::<LedPixelColorGrb24>::new(4);
+        let red = LedPixelColorGrb24::new_with_rgb(255, 0, 0);
+        let blue = LedPixelColorGrb24::new_with_rgb(0, 0, 255);
+        segments
+            .add("red", 0..1, ColorWipe::new(red.clone()))
+            .unwrap();
+        segments
+            .add("blue", 2..4, ColorWipe::new(blue.clone()))
+            .unwrap()
+            .brightness = 127;
+
+        segments.update(5, &mut driver).unwrap();
+        assert_eq!(
+            driver.pixel_data,
+            Some(vec![0, 255, 0, 0, 0, 0, 0, 0, 127, 0, 0, 127])
+        );
+
+        segments
+            .get_mut("red")
+            .unwrap()
+            .set_effect(ColorWipe::new(blue.clone()));
+        segments.remove("blue").unwrap();
+        segments.update(5, &mut driver).unwrap();
+        assert_eq!(
+            driver.pixel_data,
+            Some(vec![0, 0, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0])
+        );
+    }
+}
@@ -0,0 +1,288 @@
+//! Crossfade between frames.
+//!
+//! A [`Transition`] interpolates from the current frame to a target frame over a duration,
+//! writing the intermediate frames at a frame rate.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use core::time::Duration;
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::driver::color::{LedPixelColor, LedPixelColorGrb24};
+//! use ws2812_esp32_rmt_driver::effects::transition::{Easing, Transition};
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio27).unwrap();
+//!
+//! let red: [LedPixelColorGrb24; 3] = core::array::from_fn(|_| LedPixelColorGrb24::new_with_rgb(255, 0, 0));
+//! let blue: [LedPixelColorGrb24; 3] = core::array::from_fn(|_| LedPixelColorGrb24::new_with_rgb(0, 0, 255));
+//! let mut buffer: [LedPixelColorGrb24; 3] = Default::default();
+//!
+//! // 0.5 s crossfade at 60 fps
+//! let transition = Transition::new(Duration::from_millis(500), 60).with_easing(Easing::EaseInOut);
+//! transition
+//!     .run(&mut driver, &red, &blue, &mut buffer, |_interval| {
+//!         // e.g. `esp_idf_hal::delay::FreeRtos::delay_ms(interval.as_millis() as u32)`
+//!     })
+//!     .unwrap();
+//! assert_eq!(driver.pixel_data, Some(vec![0, 0, 255, 0, 0, 255, 0, 0, 255]));
+//! ```
+
+use super::write_pixels;
+use crate::driver::color::LedPixelColor;
+use crate::driver::timing::IntoDuration;
+use crate::driver::LedStripWrite;
+use core::time::Duration;
+
+/// Easing of the transition progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Easing {
+    /// Constant speed
+    #[default]
+    Linear,
+    /// Slow start
+    EaseIn,
+    /// Slow end
+    EaseOut,
+    /// Slow start and end (smoothstep)
+    EaseInOut,
+}
+
+impl Easing {
+    /// Returns the eased progress of the linear progress `x`, both out of 255.
+    pub fn apply(&self, x: u8) -> u8 {
+        let x = x as u32;
+        let eased = match self {
+            Self::Linear => x,
+            Self::EaseIn => x * x / 255,
+            Self::EaseOut => 255 - (255 - x) * (255 - x) / 255,
+            Self::EaseInOut => x * x * (3 * 255 - 2 * x) / (255 * 255),
+        };
+        eased as u8
+    }
+}
+
+/// Returns the color between `from` and `to` at `amount` out of 255, channel by channel.
+pub fn blend<C: LedPixelColor>(from: &C, to: &C, amount: u8) -> C {
+    let mut color = from.clone();
+    for (v, &target) in color.as_mut().iter_mut().zip(to.as_ref()) {
+        let (a, b) = (*v as i32, target as i32);
+        *v = (a + (b - a) * amount as i32 / 255) as u8;
+    }
+    color
+}
+
+/// Crossfade from a frame to another frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    /// Duration of the transition
+    pub duration: Duration,
+    /// Frame rate (frame per second) of the transition
+    pub fps: u32,
+    /// Easing of the transition
+    pub easing: Easing,
+}
+
+impl Transition {
+    /// Creates a linear transition taking `duration` at `fps` frames per second.
+    pub fn new(duration: impl IntoDuration, fps: u32) -> Self {
+        Self {
+            duration: duration.into_duration(),
+            fps,
+            easing: Easing::Linear,
+        }
+    }
+
+    /// Returns the transition with the easing.
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Returns the number of frames of the transition, at least one.
+    pub fn frame_count(&self) -> u32 {
+        let nanos = self.duration.as_nanos() * self.fps as u128;
+        nanos.div_ceil(1_000_000_000).clamp(1, u32::MAX as u128) as u32
+    }
+
+    /// Returns the interval between the frames.
+    pub fn frame_interval(&self) -> Duration {
+        self.duration / self.frame_count()
+    }
+
+    /// Returns the eased progress of the `index`-th frame out of 255.
+    ///
+    /// The last frame is the target frame.
+    pub fn progress(&self, index: u32) -> u8 {
+        let count = self.frame_count() as u64;
+        let linear = ((index as u64 + 1).min(count) * 255 / count) as u8;
+        self.easing.apply(linear)
+    }
+
+    /// Returns the index of the frame at `elapsed` since the start of the transition, e.g.
+    /// [`TimeSource::elapsed_since()`] of the start time.
+    ///
+    /// It is the last frame once `elapsed` reaches it.
+    ///
+    /// [`TimeSource::elapsed_since()`]: crate::time::TimeSource::elapsed_since
+    pub fn index_at(&self, elapsed: Duration) -> u32 {
+        let last = self.frame_count() - 1;
+        match self.frame_interval().as_nanos() {
+            0 => last,
+            interval => (elapsed.as_nanos() / interval).min(last as u128) as u32,
+        }
+    }
+
+    /// Returns `true` if the last frame is due at `elapsed` since the start of the transition.
+    pub fn is_finished_at(&self, elapsed: Duration) -> bool {
+        self.index_at(elapsed) == self.frame_count() - 1
+    }
+
+    /// Renders the `index`-th frame of the transition from `from` to `to` into `pixels`.
+    ///
+    /// Only the pixels present in all of `from`, `to` and `pixels` are rendered.
+    pub fn render<C: LedPixelColor>(&self, index: u32, from: &[C], to: &[C], pixels: &mut [C]) {
+        let amount = self.progress(index);
+        for ((pixel, from), to) in pixels.iter_mut().zip(from).zip(to) {
+            *pixel = blend(from, to, amount);
+        }
+    }
+
+    /// Writes the frames of the transition from `from` to `to` through the driver,
+    /// using `pixels` as the frame buffer.
+    ///
+    /// `delay` is called with [`Self::frame_interval()`] between the frames to pace the writes,
+    /// e.g. with `FreeRtos::delay_ms()` on the device or with [`VirtualClock::advance()`] in the mock.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error returned by `writer`.
+    ///
+    /// [`VirtualClock::advance()`]: crate::mock::time::VirtualClock::advance
+    pub fn run<W, C, D>(
+        &self,
+        writer: &mut W,
+        from: &[C],
+        to: &[C],
+        pixels: &mut [C],
+        mut delay: D,
+    ) -> Result<(), W::Error>
+    where
+        W: LedStripWrite,
+        C: LedPixelColor,
+        D: FnMut(Duration),
+    {
+        let interval = self.frame_interval();
+        for index in 0..self.frame_count() {
+            if index > 0 {
+                delay(interval);
+            }
+            self.render(index, from, to, pixels);
+            write_pixels(writer, pixels)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::{LedPixelColorGrb24, LedPixelColorGrbw32};
+    use crate::driver::Ws2812Esp32RmtDriver;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+    use crate::mock::time::VirtualClock;
+    use crate::time::TimeSource;
+
+    #[test]
+    fn test_easing() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0), 0);
+            assert_eq!(easing.apply(255), 255);
+            assert!((0..255).all(|x| easing.apply(x) <= easing.apply(x + 1)));
+        }
+        assert_eq!(Easing::Linear.apply(128), 128);
+        assert!(Easing::EaseIn.apply(64) < 64);
+        assert!(Easing::EaseOut.apply(64) > 64);
+        assert!(Easing::EaseInOut.apply(64) < 64);
+        assert!(Easing::EaseInOut.apply(192) > 192);
+    }
+
+    #[test]
+    fn test_blend() {
+        let from = LedPixelColorGrbw32::new_with_rgbw(0, 100, 255, 10);
+        let to = LedPixelColorGrbw32::new_with_rgbw(255, 0, 255, 20);
+        assert_eq!(blend(&from, &to, 0), from);
+        assert_eq!(blend(&from, &to, 255), to);
+        let half = blend(&from, &to, 128);
+        assert_eq!((half.r(), half.g(), half.b(), half.w()), (128, 50, 255, 15));
+    }
+
+    #[test]
+    fn test_transition_frames() {
+        let transition = Transition::new(Duration::from_millis(100), 30);
+        assert_eq!(transition.frame_count(), 3);
+        assert_eq!(
+            transition.frame_interval(),
+            Duration::from_nanos(33_333_333)
+        );
+        assert_eq!(
+            [0, 1, 2, 3].map(|index| transition.progress(index)),
+            [85, 170, 255, 255]
+        );
+        assert_eq!(Transition::new(Duration::ZERO, 60).frame_count(), 1);
+        assert_eq!(Transition::new(Duration::ZERO, 60).progress(0), 255);
+    }
+
+    #[test]
+    fn test_transition_index_at() {
+        let transition = Transition::new(Duration::from_millis(100), 50);
+        let clock = VirtualClock::new();
+        let start = clock.now();
+        assert_eq!(transition.index_at(clock.elapsed_since(start)), 0);
+        clock.advance(Duration::from_millis(45));
+        assert_eq!(transition.index_at(clock.elapsed_since(start)), 2);
+        assert!(!transition.is_finished_at(clock.elapsed_since(start)));
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(transition.index_at(clock.elapsed_since(start)), 4);
+        assert!(transition.is_finished_at(clock.elapsed_since(start)));
+        assert_eq!(
+            Transition::new(Duration::ZERO, 60).index_at(Duration::ZERO),
+            0
+        );
+    }
+
+    #[test]
+    fn test_transition_run() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+        let clock = VirtualClock::new();
+        driver.set_virtual_clock(clock.clone());
+
+        let from = [LedPixelColorGrb24::new_with_rgb(0, 0, 0)];
+        let to = [LedPixelColorGrb24::new_with_rgb(255, 0, 0)];
+        let mut pixels: [LedPixelColorGrb24; 1] = Default::default();
+        let transition = Transition::new(Duration::from_millis(100), 50);
+        transition
+            .run(&mut driver, &from, &to, &mut pixels, |interval| {
+                clock.advance(interval)
+            })
+            .unwrap();
+
+        let records = driver.frame_records();
+        assert_eq!(records.len(), 5);
+        assert_eq!(records[1].start - records[0].end, Duration::from_millis(20));
+        assert_eq!(pixels, to);
+        assert_eq!(driver.pixel_data, Some(vec![0, 255, 0]));
+    }
+}
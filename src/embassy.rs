@@ -0,0 +1,479 @@
+//! Embassy task integration.
+//!
+//! [`RefreshTask`] owns the LED driver and writes the frames received over a [`FrameChannel`],
+//! at most once per frame interval. When the frames are sent faster than the interval, only
+//! the latest one is written and the others are dropped. The application tasks send the frames
+//! with a [`FrameSender`], a lightweight `Copy` handle of the channel.
+//!
+//! A frame is any owned byte container, e.g. `[u8; LEN]` or `heapless::Vec<u8, LEN>`, holding
+//! the pixel data in the device byte order.
+//!
+//! Where every frame matters, [`FramePipeline`] passes the frames without dropping: a
+//! [`FrameProducer`] renders into a free buffer of the pool and submits it, and the
+//! [`PipelineTask`] writes it and returns the buffer to the pool. With two buffers, the next
+//! frame is rendered while the current one is written, and the producer waits while all the
+//! buffers are in flight.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+//! use embassy_time::Duration;
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::embassy::{FrameChannel, RefreshTask};
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//!
+//! // Typically a `static` guarded by `CriticalSectionRawMutex` shared among the tasks
+//! let frames = FrameChannel::<NoopRawMutex, [u8; 6], 2>::new();
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+//! // Up to 50 frames per second
+//! let mut task = RefreshTask::new(driver, &frames, Duration::from_millis(20));
+//!
+//! // In an application task
+//! let sender = frames.sender();
+//! sender.try_send([0, 30, 0, 30, 0, 0]).unwrap();
+//!
+//! // In the refresh task, typically `task.run().await` in an `#[embassy_executor::task]`
+//! embassy_futures::block_on(task.refresh_once()).unwrap();
+//! assert_eq!(task.driver().pixel_data, Some(vec![0, 30, 0, 30, 0, 0]));
+//! ```
+
+use crate::driver::trace::trace_event;
+use crate::driver::LedStripDriver;
+use core::convert::Infallible;
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::channel::{Channel, Receiver, Sender};
+use embassy_time::{Duration, Instant, Timer};
+
+/// Channel of the frames from the application tasks to the [`RefreshTask`].
+///
+/// * `M` - the raw mutex type guarding the channel, e.g. `CriticalSectionRawMutex`
+/// * `F` - the frame type
+/// * `N` - the number of the frames the channel holds
+pub struct FrameChannel<M: RawMutex, F, const N: usize> {
+    channel: Channel<M, F, N>,
+}
+
+impl<M: RawMutex, F, const N: usize> FrameChannel<M, F, N> {
+    /// Creates an empty frame channel.
+    ///
+    /// It is `const` so that the channel can be placed in a `static`.
+    pub const fn new() -> Self {
+        Self {
+            channel: Channel::new(),
+        }
+    }
+
+    /// Returns a handle to send the frames to the channel.
+    pub fn sender(&self) -> FrameSender<'_, M, F, N> {
+        FrameSender {
+            sender: self.channel.sender(),
+        }
+    }
+}
+
+impl<M: RawMutex, F, const N: usize> Default for FrameChannel<M, F, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Handle to send the frames to a [`FrameChannel`].
+///
+/// It is `Copy`, so that each application task can hold its own.
+pub struct FrameSender<'a, M: RawMutex, F, const N: usize> {
+    sender: Sender<'a, M, F, N>,
+}
+
+impl<M: RawMutex, F, const N: usize> Clone for FrameSender<'_, M, F, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M: RawMutex, F, const N: usize> Copy for FrameSender<'_, M, F, N> {}
+
+impl<M: RawMutex, F, const N: usize> FrameSender<'_, M, F, N> {
+    /// Sends a frame, waiting until the channel has room for it.
+    pub async fn send(&self, frame: F) {
+        self.sender.send(frame).await;
+    }
+
+    /// Sends a frame without waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns the frame back if the channel is full.
+    pub fn try_send(&self, frame: F) -> Result<(), F> {
+        self.sender.try_send(frame).map_err(|e| match e {
+            embassy_sync::channel::TrySendError::Full(frame) => frame,
+        })
+    }
+}
+
+/// Pipeline of the frame buffers between a [`FrameProducer`] and a [`PipelineTask`].
+///
+/// * `M` - the raw mutex type guarding the channels, e.g. `CriticalSectionRawMutex`
+/// * `F` - the frame type
+/// * `N` - the number of the frame buffers, e.g. 2 for the double buffering
+///
+/// # Examples
+///
+/// ```
+/// #[cfg(not(target_vendor = "espressif"))]
+/// use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+///
+/// use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+/// use esp_idf_hal::peripherals::Peripherals;
+/// use ws2812_esp32_rmt_driver::embassy::FramePipeline;
+/// use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+///
+/// // Typically a `static` guarded by `CriticalSectionRawMutex` shared among the tasks
+/// let pipeline = FramePipeline::<NoopRawMutex, [u8; 3], 2>::new();
+///
+/// let peripherals = Peripherals::take().unwrap();
+/// let driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+/// let producer = pipeline.producer([[0; 3]; 2]);
+/// let mut task = pipeline.task(driver);
+///
+/// embassy_futures::block_on(async {
+///     // In an application task
+///     producer.render(|frame| *frame = [0, 30, 0]).await;
+///     // In the output task, typically `task.run().await` in an `#[embassy_executor::task]`
+///     task.write_once().await.unwrap();
+/// });
+/// assert_eq!(task.driver().pixel_data, Some(vec![0, 30, 0]));
+/// ```
+pub struct FramePipeline<M: RawMutex, F, const N: usize> {
+    /// Frames submitted and waiting to be written
+    filled: Channel<M, F, N>,
+    /// Frame buffers free to be rendered
+    free: Channel<M, F, N>,
+}
+
+impl<M: RawMutex, F, const N: usize> FramePipeline<M, F, N> {
+    /// Creates an empty pipeline.
+    ///
+    /// It is `const` so that the pipeline can be placed in a `static`.
+    pub const fn new() -> Self {
+        Self {
+            filled: Channel::new(),
+            free: Channel::new(),
+        }
+    }
+
+    /// Puts the frame buffers into the pool and returns the handle to render the frames.
+    ///
+    /// Call it once; the buffers beyond `N` in the pipeline are dropped.
+    pub fn producer(&self, buffers: [F; N]) -> FrameProducer<'_, M, F, N> {
+        for buffer in buffers {
+            let _ = self.free.try_send(buffer);
+        }
+        FrameProducer { pipeline: self }
+    }
+
+    /// Creates the output task writing the frames of this pipeline through `driver`.
+    pub fn task<D: LedStripDriver>(&self, driver: D) -> PipelineTask<'_, D, M, F, N>
+    where
+        F: AsRef<[u8]>,
+    {
+        PipelineTask {
+            driver,
+            pipeline: self,
+            written_frames: 0,
+        }
+    }
+}
+
+impl<M: RawMutex, F, const N: usize> Default for FramePipeline<M, F, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Handle to render the frames into the buffers of a [`FramePipeline`].
+///
+/// It is `Copy`, so that each application task can hold its own.
+pub struct FrameProducer<'a, M: RawMutex, F, const N: usize> {
+    pipeline: &'a FramePipeline<M, F, N>,
+}
+
+impl<M: RawMutex, F, const N: usize> Clone for FrameProducer<'_, M, F, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M: RawMutex, F, const N: usize> Copy for FrameProducer<'_, M, F, N> {}
+
+impl<M: RawMutex, F, const N: usize> FrameProducer<'_, M, F, N> {
+    /// Takes a free frame buffer, waiting until one is returned by the output task.
+    ///
+    /// The buffer holds the frame rendered into it before; submit it by [`Self::submit()`].
+    pub async fn frame(&self) -> F {
+        self.pipeline.free.receive().await
+    }
+
+    /// Takes a free frame buffer without waiting.
+    ///
+    /// Returns `None` if all the buffers are in flight.
+    pub fn try_frame(&self) -> Option<F> {
+        self.pipeline.free.try_receive().ok()
+    }
+
+    /// Submits a frame to be written by the output task.
+    ///
+    /// It does not wait, as a taken buffer always has room in the pipeline.
+    pub fn submit(&self, frame: F) {
+        let _ = self.pipeline.filled.try_send(frame);
+    }
+
+    /// Renders a frame into a free buffer by `f` and submits it, waiting for a free buffer.
+    pub async fn render(&self, f: impl FnOnce(&mut F)) {
+        let mut frame = self.frame().await;
+        f(&mut frame);
+        self.submit(frame);
+    }
+}
+
+/// Output task writing the frames of a [`FramePipeline`] through the LED driver.
+///
+/// * `D` - the LED driver backend
+/// * `M`, `F`, `N` - the parameters of the [`FramePipeline`]
+pub struct PipelineTask<'a, D, M, F, const N: usize>
+where
+    D: LedStripDriver,
+    M: RawMutex,
+    F: AsRef<[u8]>,
+{
+    driver: D,
+    pipeline: &'a FramePipeline<M, F, N>,
+    written_frames: u32,
+}
+
+impl<D, M, F, const N: usize> PipelineTask<'_, D, M, F, N>
+where
+    D: LedStripDriver,
+    M: RawMutex,
+    F: AsRef<[u8]>,
+{
+    /// Returns the driver.
+    #[inline]
+    pub fn driver(&self) -> &D {
+        &self.driver
+    }
+
+    /// Returns the driver mutably.
+    #[inline]
+    pub fn driver_mut(&mut self) -> &mut D {
+        &mut self.driver
+    }
+
+    /// Returns the number of the frames written.
+    #[inline]
+    pub fn written_frames(&self) -> u32 {
+        self.written_frames
+    }
+
+    /// Waits for a submitted frame, writes it and returns the buffer to the pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the driver failed to write the frame. The buffer is returned to the
+    /// pool even then.
+    pub async fn write_once(&mut self) -> Result<(), D::Error> {
+        let frame = self.pipeline.filled.receive().await;
+        let result = self.driver.write_blocking(frame.as_ref().iter().copied());
+        if result.is_err() {
+            trace_event!(error, "pipeline task failed to write the frame");
+        } else {
+            self.written_frames = self.written_frames.wrapping_add(1);
+        }
+        let _ = self.pipeline.free.try_send(frame);
+        result
+    }
+
+    /// Keeps writing the frames submitted.
+    ///
+    /// This is intended to be awaited as the body of an embassy task.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error the driver failed with.
+    pub async fn run(&mut self) -> Result<Infallible, D::Error> {
+        loop {
+            self.write_once().await?;
+        }
+    }
+}
+
+/// Refresh task writing the frames received over a [`FrameChannel`] through the LED driver.
+///
+/// * `D` - the LED driver backend
+/// * `M`, `F`, `N` - the parameters of the [`FrameChannel`]
+pub struct RefreshTask<'a, D, M, F, const N: usize>
+where
+    D: LedStripDriver,
+    M: RawMutex,
+    F: AsRef<[u8]>,
+{
+    driver: D,
+    receiver: Receiver<'a, M, F, N>,
+    frame_interval: Duration,
+    next_write: Option<Instant>,
+    dropped_frames: u32,
+}
+
+impl<'a, D, M, F, const N: usize> RefreshTask<'a, D, M, F, N>
+where
+    D: LedStripDriver,
+    M: RawMutex,
+    F: AsRef<[u8]>,
+{
+    /// Creates a refresh task writing the frames of `channel` through `driver` at most once per
+    /// `frame_interval`.
+    pub fn new(driver: D, channel: &'a FrameChannel<M, F, N>, frame_interval: Duration) -> Self {
+        Self {
+            driver,
+            receiver: channel.channel.receiver(),
+            frame_interval,
+            next_write: None,
+            dropped_frames: 0,
+        }
+    }
+
+    /// Returns the driver.
+    #[inline]
+    pub fn driver(&self) -> &D {
+        &self.driver
+    }
+
+    /// Returns the driver mutably.
+    #[inline]
+    pub fn driver_mut(&mut self) -> &mut D {
+        &mut self.driver
+    }
+
+    /// Returns the minimum interval between the frames written.
+    #[inline]
+    pub fn frame_interval(&self) -> Duration {
+        self.frame_interval
+    }
+
+    /// Returns the number of the frames dropped in favor of a newer one.
+    #[inline]
+    pub fn dropped_frames(&self) -> u32 {
+        self.dropped_frames
+    }
+
+    /// Waits for a frame and writes the latest one once the frame interval has elapsed since the
+    /// last write.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the driver failed to write the frame.
+    pub async fn refresh_once(&mut self) -> Result<(), D::Error> {
+        let mut frame = self.receiver.receive().await;
+        if let Some(next_write) = self.next_write {
+            Timer::at(next_write).await;
+        }
+        let mut dropped = 0u32;
+        while let Ok(newer) = self.receiver.try_receive() {
+            frame = newer;
+            dropped += 1;
+        }
+        if dropped > 0 {
+            trace_event!(warn, "refresh task overrun: {} frames dropped", dropped);
+            self.dropped_frames = self.dropped_frames.saturating_add(dropped);
+        }
+
+        let result = self.driver.write_blocking(frame.as_ref().iter().copied());
+        if result.is_err() {
+            trace_event!(error, "refresh task failed to write the frame");
+        }
+        self.next_write = Some(Instant::now() + self.frame_interval);
+        result
+    }
+
+    /// Keeps writing the frames received.
+    ///
+    /// This is intended to be awaited as the body of an embassy task.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error the driver failed with.
+    pub async fn run(&mut self) -> Result<Infallible, D::Error> {
+        loop {
+            self.refresh_once().await?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+    use crate::Ws2812Esp32RmtDriver;
+    use embassy_futures::block_on;
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+
+    #[test]
+    fn test_refresh_task() {
+        let peripherals = Peripherals::take().unwrap();
+        let driver =
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+        let channel = FrameChannel::<NoopRawMutex, [u8; 3], 2>::new();
+        let mut task = RefreshTask::new(driver, &channel, Duration::from_millis(10));
+        let sender = channel.sender();
+
+        sender.try_send([1, 2, 3]).unwrap();
+        block_on(task.refresh_once()).unwrap();
+        assert_eq!(task.driver().pixel_data, Some(vec![1, 2, 3]));
+        assert_eq!(task.dropped_frames(), 0);
+
+        // Only the latest frame is written after the frame interval.
+        let start = Instant::now();
+        sender.try_send([4, 5, 6]).unwrap();
+        sender.try_send([7, 8, 9]).unwrap();
+        assert_eq!(sender.try_send([0, 0, 0]), Err([0, 0, 0]));
+        block_on(task.refresh_once()).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(5));
+        assert_eq!(task.driver().pixel_data, Some(vec![7, 8, 9]));
+        assert_eq!(task.dropped_frames(), 1);
+    }
+
+    #[test]
+    fn test_frame_pipeline() {
+        let peripherals = Peripherals::take().unwrap();
+        let mut driver =
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+        driver.record_history();
+        let pipeline = FramePipeline::<NoopRawMutex, [u8; 3], 2>::new();
+        let producer = pipeline.producer([[0; 3]; 2]);
+        let mut task = pipeline.task(driver);
+
+        // Both buffers in flight: the producer is back-pressured.
+        block_on(producer.render(|frame| *frame = [1, 1, 1]));
+        block_on(producer.render(|frame| *frame = [2, 2, 2]));
+        assert!(producer.try_frame().is_none());
+
+        // Every frame is written in order, returning its buffer.
+        block_on(task.write_once()).unwrap();
+        let mut frame = producer.try_frame().unwrap();
+        assert_eq!(frame, [1, 1, 1]);
+        frame[0] = 3;
+        producer.submit(frame);
+        block_on(task.write_once()).unwrap();
+        block_on(task.write_once()).unwrap();
+        assert_eq!(
+            task.driver().frame_history(),
+            [vec![1, 1, 1], vec![2, 2, 2], vec![3, 1, 1]]
+        );
+        assert_eq!(task.written_frames(), 3);
+    }
+}
@@ -0,0 +1,212 @@
+//! Safe mode of the LED transmissions during the flash operations.
+//!
+//! The flash writes and erases, e.g. of OTA updates or NVS commits, disable the cache, which
+//! stalls the interrupt handler feeding the RMT items and corrupts the bit timing; the strip
+//! then flashes with garbage. [`FlashSafeMode::pause()`] waits for the transmissions in
+//! progress of the registered drivers and returns a guard ([`FlashPause`]); while it is held,
+//! the writes through the [`FlashSafeDriver`]s are skipped and the LED pixels keep the last
+//! frame.
+//!
+//! A [`FlashSafeDriver`] is itself an LED driver, so the wrappers can be built on it by
+//! `from_driver()`.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::flash_safe::FlashSafeMode;
+//! use ws2812_esp32_rmt_driver::{LedStripWrite, Ws2812Esp32RmtDriver};
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let safe_mode = FlashSafeMode::new();
+//! let mut driver = safe_mode.register(
+//!     Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap(),
+//! );
+//! driver.write_blocking([0, 30, 0].into_iter()).unwrap();
+//!
+//! safe_mode.while_paused(|| {
+//!     // Write the OTA image chunk here. The frames in the meantime are skipped.
+//! });
+//! assert_eq!(driver.driver().pixel_data, Some(vec![0, 30, 0]));
+//! ```
+
+use crate::driver::{LedStripDriver, LedStripWrite};
+use std::sync::{Arc, RwLock, RwLockWriteGuard, TryLockError};
+
+/// Safe mode of the registered drivers during the flash operations.
+///
+/// The clones share the state.
+#[derive(Debug, Clone, Default)]
+pub struct FlashSafeMode {
+    /// Read-locked during a transmission and write-locked while paused
+    lock: Arc<RwLock<()>>,
+}
+
+impl FlashSafeMode {
+    /// Creates a safe mode without registered drivers.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers `driver` with this safe mode.
+    pub fn register<D: LedStripWrite>(&self, driver: D) -> FlashSafeDriver<D> {
+        FlashSafeDriver {
+            driver,
+            safe_mode: self.clone(),
+            skipped_frames: 0,
+        }
+    }
+
+    /// Pauses the transmissions of the registered drivers until the returned guard is dropped.
+    ///
+    /// It blocks until the transmissions in progress complete.
+    pub fn pause(&self) -> FlashPause<'_> {
+        let guard = self.lock.write().unwrap_or_else(|e| e.into_inner());
+        FlashPause { _guard: guard }
+    }
+
+    /// Calls `f` while the transmissions of the registered drivers are paused.
+    pub fn while_paused<R>(&self, f: impl FnOnce() -> R) -> R {
+        let _pause = self.pause();
+        f()
+    }
+
+    /// Returns whether the transmissions are paused.
+    pub fn is_paused(&self) -> bool {
+        matches!(self.lock.try_read(), Err(TryLockError::WouldBlock))
+    }
+}
+
+/// Guard pausing the transmissions of a [`FlashSafeMode`], returned by
+/// [`FlashSafeMode::pause()`].
+#[must_use = "the transmissions resume when the guard is dropped"]
+pub struct FlashPause<'a> {
+    _guard: RwLockWriteGuard<'a, ()>,
+}
+
+/// LED driver registered with a [`FlashSafeMode`].
+pub struct FlashSafeDriver<D> {
+    driver: D,
+    safe_mode: FlashSafeMode,
+    skipped_frames: usize,
+}
+
+impl<D> FlashSafeDriver<D> {
+    /// Returns the driver.
+    #[inline]
+    pub fn driver(&self) -> &D {
+        &self.driver
+    }
+
+    /// Returns the driver mutably.
+    ///
+    /// The writes through it bypass the safe mode.
+    #[inline]
+    pub fn driver_mut(&mut self) -> &mut D {
+        &mut self.driver
+    }
+
+    /// Returns the number of the frames skipped while paused.
+    #[inline]
+    pub fn skipped_frames(&self) -> usize {
+        self.skipped_frames
+    }
+
+    /// Unregisters the driver and returns it.
+    pub fn into_inner(self) -> D {
+        self.driver
+    }
+
+    /// Calls `write` unless paused, holding off the pause during the call.
+    fn write_unless_paused<E>(
+        &mut self,
+        write: impl FnOnce(&mut D) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let _transmission = match self.safe_mode.lock.try_read() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+            Err(TryLockError::WouldBlock) => {
+                self.skipped_frames += 1;
+                return Ok(());
+            }
+        };
+        write(&mut self.driver)
+    }
+}
+
+impl<D: LedStripWrite> LedStripWrite for FlashSafeDriver<D> {
+    type Error = D::Error;
+
+    /// Writes the pixel-byte sequence, or skips it while paused.
+    fn write_blocking<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        self.write_unless_paused(|driver| driver.write_blocking(pixel_sequence))
+    }
+}
+
+impl<D: LedStripDriver> LedStripDriver for FlashSafeDriver<D> {
+    /// Starts writing the pixel-byte sequence, or skips it while paused.
+    ///
+    /// The pause does not wait for the transmission started by this method; the wrapped
+    /// driver waits for it at its next write.
+    fn write<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        self.write_unless_paused(|driver| driver.write(pixel_sequence))
+    }
+
+    #[inline]
+    fn latch(&mut self) -> Result<(), Self::Error> {
+        self.driver.latch()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+    use crate::Ws2812Esp32RmtDriver;
+
+    #[test]
+    fn test_flash_safe_mode() {
+        let peripherals = Peripherals::take().unwrap();
+        let safe_mode = FlashSafeMode::new();
+        let mut driver = safe_mode.register(
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap(),
+        );
+        driver.write_blocking([1, 2, 3].into_iter()).unwrap();
+        assert!(!safe_mode.is_paused());
+
+        let pause = safe_mode.pause();
+        assert!(safe_mode.is_paused());
+        driver.write_blocking([4, 5, 6].into_iter()).unwrap();
+        driver.write_blocking([7, 8, 9].into_iter()).unwrap();
+        assert_eq!(driver.driver().pixel_data, Some(vec![1, 2, 3]));
+        assert_eq!(driver.skipped_frames(), 2);
+        drop(pause);
+
+        assert!(!safe_mode.is_paused());
+        driver.write_blocking([4, 5, 6].into_iter()).unwrap();
+        assert_eq!(driver.driver().pixel_data, Some(vec![4, 5, 6]));
+    }
+
+    #[test]
+    fn test_flash_safe_mode_waits_transmission() {
+        let safe_mode = FlashSafeMode::new();
+        let transmission = safe_mode.lock.read().unwrap();
+        let paused = std::thread::scope(|scope| {
+            let pausing = scope.spawn(|| safe_mode.while_paused(|| true));
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            assert!(!pausing.is_finished());
+            drop(transmission);
+            pausing.join().unwrap()
+        });
+        assert!(paused);
+    }
+}
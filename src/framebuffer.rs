@@ -0,0 +1,1283 @@
+//! Persistent framebuffer of the LED pixels.
+//!
+//! [`LedPixelFrameBuffer`] keeps the frame of the LED pixels in the device byte order, so that
+//! the individual pixels are set and the frame is written by [`show()`] only when changed.
+//! The embedded-graphics draw target is built on it, and the smart-leds users set the `RGB8`
+//! colors on it as is, instead of rebuilding the frame from an iterator each time.
+//!
+//! [`show()`]: LedPixelFrameBuffer::show
+
+use crate::driver::brightness::scale;
+#[cfg(feature = "alloc")]
+use crate::driver::brightness::BrightnessSource;
+use crate::driver::calibration::{ColorCalibration, ColorTemperature};
+use crate::driver::color::{
+    has_white, ColorOrder, LedPixelColor, LedPixelColor16, LedPixelColorGrb24,
+};
+use crate::driver::gamma::GammaLut;
+use crate::driver::power::CurrentModel;
+#[cfg(not(target_os = "none"))]
+use crate::driver::{channel::TxRmtChannel, Ws2812Esp32RmtDriver, Ws2812Esp32RmtDriverError};
+use crate::driver::{DefaultLedDriver, LedStripDriver};
+#[cfg(feature = "portable-atomic")]
+use crate::shared::ChangedFlag;
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::marker::PhantomData;
+use core::ops::{DerefMut, Range};
+
+#[cfg(not(any(target_vendor = "espressif", target_os = "none")))]
+use crate::mock::esp_idf_hal;
+#[cfg(not(target_os = "none"))]
+use esp_idf_hal::{gpio::OutputPin, peripheral::Peripheral};
+
+/// Default data storage type for `LedPixelFrameBuffer`.
+#[cfg(feature = "std")]
+pub(crate) type FrameBufferData = Vec<u8>;
+
+/// Default data storage type for `LedPixelFrameBuffer`.
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+pub(crate) type FrameBufferData = alloc::vec::Vec<u8>;
+
+/// Default data storage type for `LedPixelFrameBuffer`.
+/// In case of heapless, allocate 256-byte capacity vector.
+#[cfg(all(not(feature = "std"), not(feature = "alloc")))]
+pub(crate) type FrameBufferData = heapless::Vec<u8, 256>;
+
+/// Framebuffer of the LED pixels written through the driver.
+///
+/// * `CDev` - the LED pixel color type (device dependant)
+/// * `Data` - (optional) data storage type. It shall be `Vec`-like struct.
+/// * `D` - (optional) the LED driver backend
+///
+/// For non-`alloc` no_std environment, `Data` should be explicitly set to some `Vec`-like struct:
+/// e.g., `heapless::Vec<u8, LEN>` where `LEN` equals to the pixel count * `CDev::BPP`.
+///
+/// # Examples
+///
+/// ```
+/// #[cfg(not(target_vendor = "espressif"))]
+/// use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+///
+/// use esp_idf_hal::peripherals::Peripherals;
+/// use ws2812_esp32_rmt_driver::driver::color::{LedPixelColor, LedPixelColorGrb24};
+/// use ws2812_esp32_rmt_driver::framebuffer::Ws2812FrameBuffer;
+///
+/// let peripherals = Peripherals::take().unwrap();
+/// let led_pin = peripherals.pins.gpio27;
+/// let channel = peripherals.rmt.channel0;
+/// let mut frame: Ws2812FrameBuffer = Ws2812FrameBuffer::new(channel, led_pin, 25).unwrap();
+///
+/// frame.set_brightness(40);
+/// frame.set_pixel(0, LedPixelColorGrb24::new_with_rgb(255, 0, 0));
+/// frame.set_pixel(24, LedPixelColorGrb24::new_with_rgb(0, 0, 255));
+/// frame.show().unwrap();
+/// ```
+pub struct LedPixelFrameBuffer<'d, CDev, Data = FrameBufferData, D = DefaultLedDriver<'d>>
+where
+    CDev: LedPixelColor,
+    Data: DerefMut<Target = [u8]> + FromIterator<u8> + IntoIterator<Item = u8>,
+    D: LedStripDriver,
+{
+    pub(crate) driver: D,
+    pub(crate) data: Data,
+    brightness: u8,
+    #[cfg(feature = "alloc")]
+    pub(crate) brightness_source: Option<Box<dyn BrightnessSource + Send>>,
+    ambient_brightness: u8,
+    pub(crate) color_order: Option<ColorOrder>,
+    pub(crate) gamma: Option<GammaLut>,
+    pub(crate) calibration: Option<ColorCalibration>,
+    pub(crate) color_temperature: Option<ColorTemperature>,
+    pixel_calibration: Option<&'d [[u8; 3]]>,
+    dead_pixels: Option<DeadPixelMap<'d>>,
+    pub(crate) white_extraction: bool,
+    pub(crate) max_power_ma: Option<u32>,
+    pub(crate) current_model: CurrentModel,
+    /// Sum of the channel values of the last written frame
+    channel_sum: u64,
+    #[cfg(feature = "alloc")]
+    dither: Option<Vec<u8>>,
+    /// Lower bytes of the channels set by `set_pixel16()`, in the order of `CDev`
+    #[cfg(feature = "alloc")]
+    fractions: Option<Vec<u8>>,
+    pub(crate) changed: bool,
+    /// Range of the LED pixels changed since the last write
+    dirty: Option<Range<usize>>,
+    /// Range of the LED pixels changed in the last written frame
+    last_dirty: Option<Range<usize>>,
+    #[cfg(feature = "portable-atomic")]
+    changed_flag: Option<&'d ChangedFlag>,
+    _phantom: PhantomData<(&'d (), CDev)>,
+}
+
+#[cfg(not(target_os = "none"))]
+impl<'d, CDev, Data> LedPixelFrameBuffer<'d, CDev, Data>
+where
+    CDev: LedPixelColor,
+    Data: DerefMut<Target = [u8]> + FromIterator<u8> + IntoIterator<Item = u8>,
+{
+    /// Create a new black framebuffer of `pixel_len` LED pixels.
+    ///
+    /// `channel` shall be different between different `pin`.
+    pub fn new<C: TxRmtChannel>(
+        channel: impl Peripheral<P = C> + 'd,
+        pin: impl Peripheral<P = impl OutputPin> + 'd,
+        pixel_len: usize,
+    ) -> Result<Self, Ws2812Esp32RmtDriverError> {
+        let driver = Ws2812Esp32RmtDriver::<'d>::new(channel, pin)?;
+        Ok(Self::from_driver(driver, pixel_len))
+    }
+}
+
+impl<'d, CDev, Data, D> LedPixelFrameBuffer<'d, CDev, Data, D>
+where
+    CDev: LedPixelColor,
+    Data: DerefMut<Target = [u8]> + FromIterator<u8> + IntoIterator<Item = u8>,
+    D: LedStripDriver,
+{
+    /// Create a new black framebuffer of `pixel_len` LED pixels written through `driver`.
+    pub fn from_driver(driver: D, pixel_len: usize) -> Self {
+        let data = core::iter::repeat_n(0, pixel_len * CDev::BPP).collect::<Data>();
+        Self {
+            driver,
+            data,
+            brightness: u8::MAX,
+            #[cfg(feature = "alloc")]
+            brightness_source: None,
+            ambient_brightness: u8::MAX,
+            color_order: None,
+            gamma: None,
+            calibration: None,
+            color_temperature: None,
+            pixel_calibration: None,
+            dead_pixels: None,
+            white_extraction: false,
+            max_power_ma: None,
+            current_model: CurrentModel::WS2812,
+            channel_sum: 0,
+            #[cfg(feature = "alloc")]
+            dither: None,
+            #[cfg(feature = "alloc")]
+            fractions: None,
+            changed: true,
+            dirty: Some(0..pixel_len),
+            last_dirty: None,
+            #[cfg(feature = "portable-atomic")]
+            changed_flag: None,
+            _phantom: Default::default(),
+        }
+    }
+
+    /// Returns the driver.
+    #[inline]
+    pub fn driver(&self) -> &D {
+        &self.driver
+    }
+
+    /// Returns the driver mutably.
+    #[inline]
+    pub fn driver_mut(&mut self) -> &mut D {
+        &mut self.driver
+    }
+
+    /// Returns the number of the LED pixels.
+    #[inline]
+    pub fn pixel_len(&self) -> usize {
+        self.data.len() / CDev::BPP
+    }
+
+    /// Returns the pixel data in the device byte order.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the range of the LED pixels changed since the last write by [`show()`], or
+    /// `None` if unchanged.
+    ///
+    /// [`set_pixel()`] extends the range by the LED pixel, and the other changes, e.g.
+    /// [`fill()`] or [`set_brightness()`], mark all the LED pixels. The whole frame is written
+    /// anyway, as the LED pixels are chained.
+    ///
+    /// [`show()`]: #method.show
+    /// [`set_pixel()`]: #method.set_pixel
+    /// [`fill()`]: #method.fill
+    /// [`set_brightness()`]: #method.set_brightness
+    #[inline]
+    pub fn dirty_range(&self) -> Option<Range<usize>> {
+        self.dirty.clone()
+    }
+
+    /// Returns the range of the LED pixels changed in the last frame written by [`show()`],
+    /// e.g. for the diagnostics of the frames, or `None` if nothing has been written or the
+    /// frame was rewritten unchanged for the dithering.
+    ///
+    /// [`show()`]: #method.show
+    #[inline]
+    pub fn last_dirty_range(&self) -> Option<Range<usize>> {
+        self.last_dirty.clone()
+    }
+
+    /// Marks all the LED pixels as changed.
+    fn mark_changed(&mut self) {
+        self.changed = true;
+        self.dirty = Some(0..self.pixel_len());
+    }
+
+    /// Marks the LED pixels of `range` as changed in addition to the marked ones.
+    fn mark_dirty(&mut self, range: Range<usize>) {
+        self.changed = true;
+        let range = range.start..range.end.min(self.pixel_len());
+        if range.is_empty() {
+            return;
+        }
+        self.dirty = Some(match self.dirty.take() {
+            Some(dirty) => dirty.start.min(range.start)..dirty.end.max(range.end),
+            None => range,
+        });
+    }
+
+    /// Set maximum brightness.
+    /// Each channel values of the colors set afterwards shall be scaled down to
+    /// `(brightness + 1) / 256`.
+    #[inline]
+    pub fn set_brightness(&mut self, brightness: u8) {
+        self.brightness = brightness;
+        self.mark_changed();
+    }
+
+    /// Returns maximum brightness.
+    #[inline]
+    pub fn brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    /// Set the source of the ambient brightness polled on each [`show()`].
+    ///
+    /// The ambient brightness is applied on top of [`brightness()`] when the framebuffer is
+    /// written, and the framebuffer is written again whenever the ambient brightness changes.
+    ///
+    /// [`show()`]: #method.show
+    /// [`brightness()`]: #method.brightness
+    #[cfg(feature = "alloc")]
+    pub fn set_brightness_source<B: BrightnessSource + Send + 'static>(&mut self, source: B) {
+        self.brightness_source = Some(Box::new(source));
+    }
+
+    /// Remove the source of the ambient brightness and restore the full ambient brightness.
+    #[cfg(feature = "alloc")]
+    pub fn clear_brightness_source(&mut self) {
+        self.brightness_source = None;
+        self.ambient_brightness = u8::MAX;
+        self.mark_changed();
+    }
+
+    /// Set the channel order of the LED pixels on the wire chosen at runtime, or `None` to write
+    /// the bytes in the order of `CDev`.
+    ///
+    /// The framebuffer keeps the colors in the order of `CDev`, and [`show()`] reorders the bytes
+    /// into `color_order`.
+    ///
+    /// [`show()`]: #method.show
+    pub fn set_color_order(&mut self, color_order: Option<ColorOrder>) {
+        self.color_order = color_order;
+        self.mark_changed();
+    }
+
+    /// Returns the channel order of the LED pixels on the wire set by [`set_color_order()`].
+    ///
+    /// [`set_color_order()`]: #method.set_color_order
+    #[inline]
+    pub fn color_order(&self) -> Option<ColorOrder> {
+        self.color_order
+    }
+
+    /// Set the gamma correction of the channel values, or `None` to write them as is.
+    ///
+    /// The framebuffer keeps the colors as set, and [`show()`] maps the channel values through
+    /// `gamma` after the brightness is applied.
+    ///
+    /// [`show()`]: #method.show
+    pub fn set_gamma(&mut self, gamma: Option<GammaLut>) {
+        self.gamma = gamma;
+        self.mark_changed();
+    }
+
+    /// Returns the gamma correction set by [`set_gamma()`].
+    ///
+    /// [`set_gamma()`]: #method.set_gamma
+    #[inline]
+    pub fn gamma(&self) -> Option<&GammaLut> {
+        self.gamma.as_ref()
+    }
+
+    /// Set the color calibration of the LED pixels, e.g. [`ColorCalibration::TYPICAL_LED_STRIP`]
+    /// for the stronger green of the typical strips, or `None` to write the colors as is.
+    ///
+    /// The colors are calibrated on each [`show()`], before the brightness and the gamma are
+    /// applied.
+    ///
+    /// [`show()`]: #method.show
+    pub fn set_calibration(&mut self, calibration: Option<ColorCalibration>) {
+        self.calibration = calibration;
+        self.mark_changed();
+    }
+
+    /// Returns the color calibration set by [`set_calibration()`].
+    ///
+    /// [`set_calibration()`]: #method.set_calibration
+    #[inline]
+    pub fn calibration(&self) -> Option<&ColorCalibration> {
+        self.calibration.as_ref()
+    }
+
+    /// Set the white point of the LED pixels, e.g. [`ColorTemperature::K2700`] to match the warm
+    /// ambient lighting, or `None` to write the colors as is.
+    ///
+    /// The colors are tinted on each [`show()`], after the color calibration.
+    ///
+    /// [`show()`]: #method.show
+    pub fn set_color_temperature(&mut self, color_temperature: Option<ColorTemperature>) {
+        self.color_temperature = color_temperature;
+        self.mark_changed();
+    }
+
+    /// Returns the white point set by [`set_color_temperature()`].
+    ///
+    /// [`set_color_temperature()`]: #method.set_color_temperature
+    #[inline]
+    pub fn color_temperature(&self) -> Option<ColorTemperature> {
+        self.color_temperature
+    }
+
+    /// Set the per-pixel calibration table, the red, green and blue scales of each LED pixel by
+    /// index, or `None` not to calibrate the LED pixels individually.
+    ///
+    /// Each channel of the `index`-th LED pixel is scaled down to `(scale + 1) / 256` of
+    /// `table[index]` on each [`show()`], after the color calibration and the white point, to
+    /// compensate the color drift along the long strips. The LED pixels beyond the table are
+    /// not scaled.
+    ///
+    /// [`show()`]: #method.show
+    pub fn set_pixel_calibration(&mut self, table: Option<&'d [[u8; 3]]>) {
+        self.pixel_calibration = table;
+        self.mark_changed();
+    }
+
+    /// Returns the per-pixel calibration table set by [`set_pixel_calibration()`].
+    ///
+    /// [`set_pixel_calibration()`]: #method.set_pixel_calibration
+    #[inline]
+    pub fn pixel_calibration(&self) -> Option<&'d [[u8; 3]]> {
+        self.pixel_calibration
+    }
+
+    /// Set the map of the dead or absent LED pixels, or `None` to write all the LED pixels.
+    ///
+    /// The LED pixels keep their indices, so the drawing coordinates stay as is, but on each
+    /// [`show()`] the dead ones are written black and the absent ones are skipped, i.e. the
+    /// following LED pixels are shifted on the wire.
+    ///
+    /// [`show()`]: #method.show
+    pub fn set_dead_pixels(&mut self, map: Option<DeadPixelMap<'d>>) {
+        self.dead_pixels = map;
+        self.mark_changed();
+    }
+
+    /// Returns the map of the dead or absent LED pixels set by [`set_dead_pixels()`].
+    ///
+    /// [`set_dead_pixels()`]: #method.set_dead_pixels
+    #[inline]
+    pub fn dead_pixels(&self) -> Option<DeadPixelMap<'d>> {
+        self.dead_pixels
+    }
+
+    /// Enables or disables the white channel extraction of the RGBW LED pixels.
+    ///
+    /// While enabled, the common component of the red, green and blue channels, `min(r, g, b)`,
+    /// is moved into the white channel on each [`show()`], so that the RGB colors light the
+    /// white LED. It has no effect on the LED pixels without the white channel.
+    ///
+    /// [`show()`]: #method.show
+    pub fn set_white_extraction(&mut self, white_extraction: bool) {
+        self.white_extraction = white_extraction;
+        self.mark_changed();
+    }
+
+    /// Returns `true` if the white channel extraction is enabled by [`set_white_extraction()`].
+    ///
+    /// [`set_white_extraction()`]: #method.set_white_extraction
+    #[inline]
+    pub fn white_extraction(&self) -> bool {
+        self.white_extraction
+    }
+
+    /// Set the power budget of the LED pixels in milliamperes, or `None` not to limit.
+    ///
+    /// The current draw of the frame is estimated with [`current_model()`] on each [`show()`],
+    /// and the brightness is scaled down so that the estimation stays within `limit_ma`. The
+    /// estimation is of the channel values before the gamma correction, so it is an upper bound
+    /// with the gamma of above 1.
+    ///
+    /// [`current_model()`]: #method.current_model
+    /// [`show()`]: #method.show
+    pub fn set_max_power_ma(&mut self, limit_ma: Option<u32>) {
+        self.max_power_ma = limit_ma;
+        self.mark_changed();
+    }
+
+    /// Returns the power budget set by [`set_max_power_ma()`].
+    ///
+    /// [`set_max_power_ma()`]: #method.set_max_power_ma
+    #[inline]
+    pub fn max_power_ma(&self) -> Option<u32> {
+        self.max_power_ma
+    }
+
+    /// Set the current draw model of the LED pixels, [`CurrentModel::WS2812`] by default.
+    pub fn set_current_model(&mut self, model: CurrentModel) {
+        self.current_model = model;
+        self.mark_changed();
+    }
+
+    /// Returns the current draw model set by [`set_current_model()`].
+    ///
+    /// [`set_current_model()`]: #method.set_current_model
+    #[inline]
+    pub fn current_model(&self) -> CurrentModel {
+        self.current_model
+    }
+
+    /// Returns the estimated current draw of the last frame written by [`show()`] in
+    /// milliamperes, with the brightness, the power budget and the gamma applied.
+    ///
+    /// See [`CurrentModel::estimate_ma()`] for details.
+    ///
+    /// [`show()`]: #method.show
+    #[inline]
+    pub fn estimated_current_ma(&self) -> u32 {
+        self.current_model.channel_sum_ma(self.channel_sum)
+    }
+
+    /// Enables or disables the temporal dithering of the channel values.
+    ///
+    /// The brightness and the gamma are applied in a higher precision, and the fractions of the
+    /// channel values left by the 8-bit quantization are accumulated per channel and carried
+    /// into the following frames, so that the low-brightness gradients and fades stay smooth.
+    /// The lower bytes of the 16-bit colors set by [`set_pixel16()`] are carried likewise.
+    /// While enabled, [`show()`] writes the framebuffer on each call even if unchanged; call it
+    /// at a steady frame rate, e.g. 100 Hz or more, not to flicker.
+    ///
+    /// [`set_pixel16()`]: #method.set_pixel16
+    /// [`show()`]: #method.show
+    #[cfg(feature = "alloc")]
+    pub fn set_dithering(&mut self, dithering: bool) {
+        match (dithering, &self.dither) {
+            (true, None) => self.dither = Some(Vec::new()),
+            (false, Some(_)) => {
+                self.dither = None;
+                self.mark_changed();
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns `true` if the temporal dithering is enabled by [`set_dithering()`].
+    ///
+    /// [`set_dithering()`]: #method.set_dithering
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub fn dithering(&self) -> bool {
+        self.dither.is_some()
+    }
+
+    /// Set the flag polled on each [`show()`] to mark the framebuffer as changed, e.g. from an
+    /// interrupt handler.
+    ///
+    /// The flag is cleared when the framebuffer is written by [`show()`].
+    ///
+    /// [`show()`]: #method.show
+    #[cfg(feature = "portable-atomic")]
+    pub fn set_changed_flag(&mut self, flag: &'d ChangedFlag) {
+        self.changed_flag = Some(flag);
+    }
+
+    /// Remove the flag set by [`set_changed_flag()`].
+    ///
+    /// [`set_changed_flag()`]: #method.set_changed_flag
+    #[cfg(feature = "portable-atomic")]
+    pub fn clear_changed_flag(&mut self) {
+        self.changed_flag = None;
+    }
+
+    /// Sets the color of the `index`-th LED pixel.
+    ///
+    /// Returns `false` if the LED pixel is beyond the framebuffer.
+    pub fn set_pixel<T: Into<CDev>>(&mut self, index: usize, color: T) -> bool {
+        let offset = index * CDev::BPP;
+        let Some(pixel) = self.data.get_mut(offset..offset + CDev::BPP) else {
+            return false;
+        };
+        pixel.copy_from_slice(color.into().brightness(self.brightness).as_ref());
+        #[cfg(feature = "alloc")]
+        if let Some(fractions) = self.fractions.as_mut() {
+            fractions[offset..offset + CDev::BPP].fill(0);
+        }
+        self.mark_dirty(index..index + 1);
+        true
+    }
+
+    /// Sets the 16-bit color of the `index`-th LED pixel.
+    ///
+    /// The upper bytes of the channels are kept as the color of the LED pixel, and the lower
+    /// bytes as the fractions carried into the frames by the temporal dithering; enable
+    /// [`set_dithering()`] to keep the precision, or the channels are truncated to 8 bits on
+    /// [`show()`].
+    ///
+    /// Returns `false` if the LED pixel is beyond the framebuffer.
+    ///
+    /// [`set_dithering()`]: #method.set_dithering
+    /// [`show()`]: #method.show
+    #[cfg(feature = "alloc")]
+    pub fn set_pixel16<T: Into<LedPixelColor16>>(&mut self, index: usize, color: T) -> bool {
+        let offset = index * CDev::BPP;
+        let Some(pixel) = self.data.get_mut(offset..offset + CDev::BPP) else {
+            return false;
+        };
+        let (high, low) = color.into().brightness(self.brightness).split::<CDev>();
+        pixel.copy_from_slice(high.as_ref());
+        let data_len = self.data.len();
+        let fractions = self.fractions.get_or_insert_with(|| vec![0; data_len]);
+        fractions[offset..offset + CDev::BPP].copy_from_slice(low.as_ref());
+        self.mark_dirty(index..index + 1);
+        true
+    }
+
+    /// Fills all the LED pixels with the 16-bit color.
+    ///
+    /// See [`set_pixel16()`] for the precision.
+    ///
+    /// [`set_pixel16()`]: #method.set_pixel16
+    #[cfg(feature = "alloc")]
+    pub fn fill16<T: Into<LedPixelColor16>>(&mut self, color: T) {
+        let (high, low) = color.into().brightness(self.brightness).split::<CDev>();
+        for pixel in self.data.chunks_exact_mut(CDev::BPP) {
+            pixel.copy_from_slice(high.as_ref());
+        }
+        let fractions = low.as_ref().iter().copied().cycle().take(self.data.len());
+        self.fractions = Some(fractions.collect());
+        self.mark_changed();
+    }
+
+    /// Returns the color of the `index`-th LED pixel with the maximum brightness applied.
+    ///
+    /// Returns `None` if the LED pixel is beyond the framebuffer.
+    pub fn pixel(&self, index: usize) -> Option<CDev> {
+        let offset = index * CDev::BPP;
+        let pixel = self.data.get(offset..offset + CDev::BPP)?;
+        let mut color = CDev::new_with_rgb(0, 0, 0);
+        color.as_mut().copy_from_slice(pixel);
+        Some(color)
+    }
+
+    /// Fills all the LED pixels with the color.
+    pub fn fill<T: Into<CDev>>(&mut self, color: T) {
+        let color = color.into().brightness(self.brightness);
+        for pixel in self.data.chunks_exact_mut(CDev::BPP) {
+            pixel.copy_from_slice(color.as_ref());
+        }
+        #[cfg(feature = "alloc")]
+        {
+            self.fractions = None;
+        }
+        self.mark_changed();
+    }
+
+    /// Clear with black.
+    pub fn clear_with_black(&mut self) {
+        self.data.fill(0);
+        #[cfg(feature = "alloc")]
+        {
+            self.fractions = None;
+        }
+        self.mark_changed();
+    }
+
+    /// Write changes from the framebuffer to the LED pixels
+    pub fn show(&mut self) -> Result<(), D::Error> {
+        self.show_with(false)
+    }
+
+    /// Starts writing changes from the framebuffer to the LED pixels without waiting for the
+    /// transmission to complete, if the driver supports it.
+    ///
+    /// The framebuffer is free to be updated for the next frame meanwhile. Call [`latch()`] to
+    /// wait until the LED pixels show the frame. See [`LedStripDriver::write()`] for details.
+    ///
+    /// [`latch()`]: #method.latch
+    pub fn show_nonblocking(&mut self) -> Result<(), D::Error> {
+        self.show_with(true)
+    }
+
+    /// Waits until the frame written by [`show_nonblocking()`] has been latched by the LED
+    /// pixels.
+    ///
+    /// [`show_nonblocking()`]: #method.show_nonblocking
+    pub fn latch(&mut self) -> Result<(), D::Error> {
+        self.driver.latch()
+    }
+
+    /// Writes changes from the framebuffer to the LED pixels by [`LedStripDriver::write()`] if
+    /// `nonblocking`, and by [`LedStripWrite::write_blocking()`] otherwise.
+    ///
+    /// [`LedStripWrite::write_blocking()`]: crate::driver::LedStripWrite::write_blocking
+    fn show_with(&mut self, nonblocking: bool) -> Result<(), D::Error> {
+        #[cfg(feature = "alloc")]
+        if let Some(source) = self.brightness_source.as_mut() {
+            let ambient = source.brightness();
+            if ambient != self.ambient_brightness {
+                self.ambient_brightness = ambient;
+                self.mark_changed();
+            }
+        }
+        #[cfg(feature = "portable-atomic")]
+        if let Some(range) = self.changed_flag.and_then(ChangedFlag::take_range) {
+            self.mark_dirty(range);
+        }
+        #[cfg(feature = "alloc")]
+        let dither = self.dither.as_mut().map(|errors| {
+            let len = match self.color_order {
+                Some(order) => self.data.len() / CDev::BPP * order.bpp(),
+                None => self.data.len(),
+            };
+            errors.resize(len, 0);
+            &mut errors[..]
+        });
+        #[cfg(not(feature = "alloc"))]
+        let dither = None;
+        if self.changed || dither.is_some() {
+            let ambient = match self.max_power_ma {
+                Some(limit_ma) => self.current_model.limit_brightness(
+                    self.data.iter(),
+                    self.ambient_brightness,
+                    limit_ma,
+                ),
+                None => self.ambient_brightness,
+            };
+            let correction = Correction {
+                ambient,
+                gamma: self.gamma.as_ref(),
+            };
+            #[cfg(feature = "alloc")]
+            let fractions = self.fractions.as_deref();
+            #[cfg(not(feature = "alloc"))]
+            let fractions: Option<&[u8]> = None;
+            let fraction = move |offset: usize| fractions.map_or(0, |fractions| fractions[offset]);
+            let filter = PixelFilter {
+                calibration: self.calibration,
+                temperature: self.color_temperature.map(ColorCalibration::from),
+                pixel_scales: self.pixel_calibration,
+                dead_pixels: self.dead_pixels,
+                white: self.white_extraction && has_white::<CDev>(),
+            };
+            if self.color_order.is_some() || !filter.is_identity() {
+                let order = self.color_order;
+                let len = order.map_or(CDev::BPP, |order| order.bpp());
+                let pixels = self.data.chunks_exact(CDev::BPP).enumerate();
+                let values = pixels.flat_map(move |(index, pixel)| {
+                    let offset = index * CDev::BPP;
+                    let (mut high, mut low) =
+                        (CDev::new_with_rgb(0, 0, 0), CDev::new_with_rgb(0, 0, 0));
+                    high.as_mut().copy_from_slice(pixel);
+                    for (i, v) in low.as_mut().iter_mut().enumerate() {
+                        *v = fraction(offset + i);
+                    }
+                    let color = filter.apply(index, LedPixelColor16::from_split(&high, &low));
+                    let len = if color.is_some() { len } else { 0 };
+                    let color = color.unwrap_or_default();
+                    (0..len).map(move |i| wire_value::<CDev>(&color, order, i))
+                });
+                self.channel_sum =
+                    correction.write(&mut self.driver, values, dither, nonblocking)?;
+            } else {
+                let values = self.data.iter().enumerate();
+                let values =
+                    values.map(move |(offset, &high)| u16::from_be_bytes([high, fraction(offset)]));
+                self.channel_sum =
+                    correction.write(&mut self.driver, values, dither, nonblocking)?;
+            }
+            self.last_dirty = self.dirty.take();
+            self.changed = false;
+        }
+        Ok(())
+    }
+}
+
+/// Dead or absent LED pixels of the framebuffer, by index
+///
+/// # Examples
+///
+/// ```
+/// #[cfg(not(target_vendor = "espressif"))]
+/// use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+///
+/// use esp_idf_hal::peripherals::Peripherals;
+/// use ws2812_esp32_rmt_driver::driver::color::{LedPixelColor, LedPixelColorGrb24};
+/// use ws2812_esp32_rmt_driver::framebuffer::{DeadPixelMap, Ws2812FrameBuffer};
+///
+/// let peripherals = Peripherals::take().unwrap();
+/// let mut frame: Ws2812FrameBuffer =
+///     Ws2812FrameBuffer::new(peripherals.rmt.channel0, peripherals.pins.gpio0, 4).unwrap();
+/// // No LED pixel at the index 1, e.g. a gap of the installation
+/// frame.set_dead_pixels(Some(DeadPixelMap::absent(&[1])));
+/// frame.fill(LedPixelColorGrb24::new_with_rgb(0, 1, 0));
+/// frame.show().unwrap();
+/// assert_eq!(frame.driver().pixel_data, Some(vec![1, 0, 0, 1, 0, 0, 1, 0, 0]));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeadPixelMap<'a> {
+    /// Indices of the LED pixels, in the ascending order
+    pub indices: &'a [usize],
+    /// Skips the LED pixels on the wire instead of writing black
+    pub absent: bool,
+}
+
+impl<'a> DeadPixelMap<'a> {
+    /// Creates a map of the dead LED pixels, still passing the data through, written black.
+    ///
+    /// `indices` shall be in the ascending order.
+    #[inline]
+    pub const fn black(indices: &'a [usize]) -> Self {
+        Self {
+            indices,
+            absent: false,
+        }
+    }
+
+    /// Creates a map of the physically absent LED pixels, skipped on the wire.
+    ///
+    /// `indices` shall be in the ascending order.
+    #[inline]
+    pub const fn absent(indices: &'a [usize]) -> Self {
+        Self {
+            indices,
+            absent: true,
+        }
+    }
+
+    /// Returns `true` if the `index`-th LED pixel is in the map.
+    #[inline]
+    pub fn contains(&self, index: usize) -> bool {
+        self.indices.binary_search(&index).is_ok()
+    }
+}
+
+/// Filter of the LED pixel colors applied on writing the framebuffer
+#[derive(Clone, Copy)]
+struct PixelFilter<'a> {
+    /// Calibrates the colors.
+    calibration: Option<ColorCalibration>,
+    /// Tints the white point of the colors.
+    temperature: Option<ColorCalibration>,
+    /// Scales the channels of each LED pixel.
+    pixel_scales: Option<&'a [[u8; 3]]>,
+    /// Blanks or skips the LED pixels.
+    dead_pixels: Option<DeadPixelMap<'a>>,
+    /// Moves the common component of the RGB channels into the white channel.
+    white: bool,
+}
+
+impl PixelFilter<'_> {
+    /// Returns `true` if the filter passes the colors as is.
+    fn is_identity(&self) -> bool {
+        self.calibration.is_none()
+            && self.temperature.is_none()
+            && self.pixel_scales.is_none()
+            && self.dead_pixels.is_none()
+            && !self.white
+    }
+
+    /// Returns the filtered color of the `index`-th LED pixel, or `None` to skip it.
+    fn apply(&self, index: usize, color: LedPixelColor16) -> Option<LedPixelColor16> {
+        if let Some(map) = self.dead_pixels.filter(|map| map.contains(index)) {
+            return (!map.absent).then(LedPixelColor16::default);
+        }
+        let pixel_scale = self
+            .pixel_scales
+            .and_then(|scales| scales.get(index))
+            .map(|&[r, g, b]| ColorCalibration::with_scale(r, g, b));
+        let color = [self.calibration, self.temperature, pixel_scale]
+            .into_iter()
+            .flatten()
+            .fold(color, |color, calibration| calibration.apply(color));
+        if self.white {
+            Some(color.extract_white())
+        } else {
+            Some(color)
+        }
+    }
+}
+
+/// Returns the `i`-th channel value of `color` on the wire in 8.8 fixed point, in `order` or
+/// the order of `CDev`.
+fn wire_value<CDev: LedPixelColor>(
+    color: &LedPixelColor16,
+    order: Option<ColorOrder>,
+    i: usize,
+) -> u16 {
+    let (high, low) = color.split::<CDev>();
+    match order {
+        Some(order) => {
+            let (high, _) = order.bytes(high.r(), high.g(), high.b(), high.w());
+            let (low, _) = order.bytes(low.r(), low.g(), low.b(), low.w());
+            u16::from_be_bytes([high[i], low[i]])
+        }
+        None => u16::from_be_bytes([high.as_ref()[i], low.as_ref()[i]]),
+    }
+}
+
+/// Correction of the channel values applied on writing the framebuffer
+#[derive(Clone, Copy)]
+struct Correction<'a> {
+    ambient: u8,
+    gamma: Option<&'a GammaLut>,
+}
+
+impl Correction<'_> {
+    /// Returns the corrected channel value of the 8.8 fixed-point `value`, in 8.8 fixed point.
+    fn apply_fixed(&self, value: u16) -> u16 {
+        let value = (u32::from(value) * (u32::from(self.ambient) + 1) / 256) as u16;
+        self.gamma.map_or(value, |gamma| gamma.apply_fixed(value))
+    }
+
+    /// Returns the corrected channel value of the 8.8 fixed-point `value`, truncated to 8 bits.
+    fn apply(&self, value: u16) -> u8 {
+        let value = scale((value >> 8) as u8, self.ambient);
+        self.gamma.map_or(value, |gamma| gamma.apply(value))
+    }
+
+    /// Writes the corrected 8.8 fixed-point channel values through `driver`, dithered with the
+    /// errors of the previous frames in `dither`, if any, and returns the sum of the written
+    /// channel values.
+    ///
+    /// The values are written without waiting for the transmission if `nonblocking`.
+    fn write<D, T>(
+        self,
+        driver: &mut D,
+        values: T,
+        dither: Option<&mut [u8]>,
+        nonblocking: bool,
+    ) -> Result<u64, D::Error>
+    where
+        D: LedStripDriver,
+        T: Iterator<Item = u16> + Send,
+    {
+        let mut channel_sum = 0u64;
+        let sum = |&v: &u8| channel_sum += u64::from(v);
+        match dither {
+            Some(errors) => {
+                let values = values.zip(errors.iter_mut()).map(move |(value, error)| {
+                    let value = u32::from(self.apply_fixed(value)) + u32::from(*error);
+                    *error = value as u8;
+                    (value >> 8).min(u32::from(u8::MAX)) as u8
+                });
+                write_with(driver, values.inspect(sum), nonblocking)?;
+            }
+            None => {
+                let values = values.map(move |value| self.apply(value));
+                write_with(driver, values.inspect(sum), nonblocking)?;
+            }
+        }
+        Ok(channel_sum)
+    }
+}
+
+/// Writes `pixel_sequence` through `driver` by [`LedStripDriver::write()`] if `nonblocking`, and
+/// by [`LedStripWrite::write_blocking()`] otherwise.
+///
+/// [`LedStripWrite::write_blocking()`]: crate::driver::LedStripWrite::write_blocking
+pub(crate) fn write_with<D, T>(
+    driver: &mut D,
+    pixel_sequence: T,
+    nonblocking: bool,
+) -> Result<(), D::Error>
+where
+    D: LedStripDriver,
+    T: Iterator<Item = u8> + Send,
+{
+    if nonblocking {
+        driver.write(pixel_sequence)
+    } else {
+        driver.write_blocking(pixel_sequence)
+    }
+}
+
+/// 8-bit GRB (total 24-bit pixel) LED framebuffer, Typical RGB LED (WS2812B/SK6812) framebuffer
+pub type Ws2812FrameBuffer<'d, Data = FrameBufferData> =
+    LedPixelFrameBuffer<'d, LedPixelColorGrb24, Data>;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::LedPixelColorGrbw32;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+
+    #[test]
+    fn test_frame_buffer_pixels() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+
+        let mut frame: Ws2812FrameBuffer = Ws2812FrameBuffer::new(channel, led_pin, 3).unwrap();
+        assert_eq!(frame.pixel_len(), 3);
+        assert_eq!(frame.as_bytes(), [0x00; 9]);
+
+        assert!(frame.set_pixel(1, LedPixelColorGrb24::new_with_rgb(0x01, 0x02, 0x03)));
+        assert!(!frame.set_pixel(3, LedPixelColorGrb24::new_with_rgb(0xFF, 0xFF, 0xFF)));
+        assert_eq!(
+            frame.pixel(1),
+            Some(LedPixelColorGrb24::new_with_rgb(0x01, 0x02, 0x03))
+        );
+        assert_eq!(frame.pixel(3), None);
+
+        frame.set_brightness(0x7F);
+        frame.fill(LedPixelColorGrb24::new_with_rgb(0xFF, 0x00, 0x80));
+        assert_eq!(frame.as_bytes(), [0x00, 0x7F, 0x40].repeat(3));
+        frame.clear_with_black();
+        assert_eq!(frame.as_bytes(), [0x00; 9]);
+    }
+
+    #[test]
+    fn test_frame_buffer_show() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio1;
+        let channel = peripherals.rmt.channel1;
+
+        let mut frame: Ws2812FrameBuffer = Ws2812FrameBuffer::new(channel, led_pin, 2).unwrap();
+        frame.set_pixel(0, LedPixelColorGrb24::new_with_rgb(0x01, 0x02, 0x03));
+        frame.show().unwrap();
+        assert_eq!(
+            frame.driver.pixel_data,
+            Some(vec![0x02, 0x01, 0x03, 0x00, 0x00, 0x00])
+        );
+        assert!(!frame.changed);
+
+        frame.driver.pixel_data = None;
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, None);
+    }
+
+    #[test]
+    fn test_frame_buffer_show_nonblocking() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio1;
+        let channel = peripherals.rmt.channel1;
+
+        let mut frame: Ws2812FrameBuffer = Ws2812FrameBuffer::new(channel, led_pin, 2).unwrap();
+        frame.set_pixel(1, LedPixelColorGrb24::new_with_rgb(0x01, 0x02, 0x03));
+        frame.show_nonblocking().unwrap();
+        frame.set_pixel(0, LedPixelColorGrb24::new_with_rgb(0x04, 0x05, 0x06));
+        frame.latch().unwrap();
+        assert_eq!(
+            frame.driver().pixel_data,
+            Some(vec![0x00, 0x00, 0x00, 0x02, 0x01, 0x03])
+        );
+        assert_eq!(frame.dirty_range(), Some(0..1));
+    }
+
+    #[test]
+    fn test_frame_buffer_dirty_range() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio1;
+        let channel = peripherals.rmt.channel1;
+
+        let mut frame: Ws2812FrameBuffer = Ws2812FrameBuffer::new(channel, led_pin, 8).unwrap();
+        assert_eq!(frame.dirty_range(), Some(0..8));
+        assert_eq!(frame.last_dirty_range(), None);
+        frame.show().unwrap();
+        assert_eq!(frame.dirty_range(), None);
+        assert_eq!(frame.last_dirty_range(), Some(0..8));
+
+        let color = || LedPixelColorGrb24::new_with_rgb(0x01, 0x02, 0x03);
+        frame.set_pixel(5, color());
+        frame.set_pixel(2, color());
+        assert!(!frame.set_pixel(8, color()));
+        assert_eq!(frame.dirty_range(), Some(2..6));
+        frame.show().unwrap();
+        assert_eq!(frame.last_dirty_range(), Some(2..6));
+
+        frame.set_pixel(3, color());
+        frame.set_brightness(0x7F);
+        assert_eq!(frame.dirty_range(), Some(0..8));
+        frame.show().unwrap();
+
+        #[cfg(feature = "portable-atomic")]
+        {
+            static FLAG: ChangedFlag = ChangedFlag::new();
+            frame.set_changed_flag(&FLAG);
+            FLAG.set_range(6..100);
+            frame.show().unwrap();
+            assert_eq!(frame.last_dirty_range(), Some(6..8));
+            frame.clear_changed_flag();
+        }
+    }
+
+    #[test]
+    fn test_frame_buffer_color_order() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio2;
+        let channel = peripherals.rmt.channel1;
+
+        let mut frame: Ws2812FrameBuffer = Ws2812FrameBuffer::new(channel, led_pin, 2).unwrap();
+        frame.set_pixel(0, LedPixelColorGrb24::new_with_rgb(0x01, 0x02, 0x03));
+        frame.set_color_order(Some(ColorOrder::Rgb));
+        assert_eq!(frame.color_order(), Some(ColorOrder::Rgb));
+        frame.show().unwrap();
+        assert_eq!(frame.as_bytes(), [0x02, 0x01, 0x03, 0x00, 0x00, 0x00]);
+        assert_eq!(
+            frame.driver.pixel_data,
+            Some(vec![0x01, 0x02, 0x03, 0x00, 0x00, 0x00])
+        );
+
+        // 4 bytes per LED pixel on the wire
+        frame.set_color_order(Some(ColorOrder::Grbw));
+        frame.show().unwrap();
+        assert_eq!(
+            frame.driver.pixel_data,
+            Some(vec![0x02, 0x01, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00])
+        );
+    }
+
+    #[test]
+    fn test_frame_buffer_gamma() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio3;
+        let channel = peripherals.rmt.channel1;
+
+        let mut frame: Ws2812FrameBuffer = Ws2812FrameBuffer::new(channel, led_pin, 1).unwrap();
+        frame.set_pixel(0, LedPixelColorGrb24::new_with_rgb(0x80, 0xFF, 0x10));
+        frame.set_gamma(Some(GammaLut::GAMMA_2_2));
+        assert_eq!(frame.gamma(), Some(&GammaLut::GAMMA_2_2));
+        frame.show().unwrap();
+        assert_eq!(frame.as_bytes(), [0xFF, 0x80, 0x10]);
+        assert_eq!(frame.driver.pixel_data, Some(vec![0xFF, 56, 1]));
+
+        frame.set_gamma(None);
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, Some(vec![0xFF, 0x80, 0x10]));
+    }
+
+    #[test]
+    fn test_frame_buffer_calibration() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio8;
+        let channel = peripherals.rmt.channel0;
+
+        let mut frame: Ws2812FrameBuffer = Ws2812FrameBuffer::new(channel, led_pin, 1).unwrap();
+        frame.set_pixel(0, LedPixelColorGrb24::new_with_rgb(0x80, 0x80, 0x80));
+        frame.set_calibration(Some(ColorCalibration::with_scale(0xFF, 0x7F, 0x3F)));
+        assert_eq!(
+            frame.calibration(),
+            Some(&ColorCalibration::with_scale(0xFF, 0x7F, 0x3F))
+        );
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, Some(vec![0x40, 0x80, 0x20]));
+        assert_eq!(frame.as_bytes(), [0x80, 0x80, 0x80]);
+
+        frame.set_calibration(None);
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, Some(vec![0x80, 0x80, 0x80]));
+
+        frame.set_color_temperature(Some(ColorTemperature::new(0xFF, 0x7F, 0x3F)));
+        assert_eq!(
+            frame.color_temperature(),
+            Some(ColorTemperature::new(0xFF, 0x7F, 0x3F))
+        );
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, Some(vec![0x40, 0x80, 0x20]));
+
+        // Calibrated, then tinted
+        frame.set_calibration(Some(ColorCalibration::with_scale(0x7F, 0xFF, 0xFF)));
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, Some(vec![0x40, 0x40, 0x20]));
+    }
+
+    #[test]
+    fn test_frame_buffer_pixel_calibration() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio9;
+        let channel = peripherals.rmt.channel1;
+
+        static TABLE: [[u8; 3]; 2] = [[0xFF, 0xFF, 0xFF], [0x7F, 0xFF, 0x3F]];
+        let mut frame: Ws2812FrameBuffer = Ws2812FrameBuffer::new(channel, led_pin, 3).unwrap();
+        frame.fill(LedPixelColorGrb24::new_with_rgb(0x80, 0x80, 0x80));
+        frame.set_pixel_calibration(Some(&TABLE));
+        assert_eq!(frame.pixel_calibration(), Some(&TABLE[..]));
+        frame.show().unwrap();
+        assert_eq!(
+            frame.driver.pixel_data,
+            Some(vec![0x80, 0x80, 0x80, 0x80, 0x40, 0x20, 0x80, 0x80, 0x80])
+        );
+
+        frame.set_pixel_calibration(None);
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, Some(vec![0x80; 9]));
+    }
+
+    #[test]
+    fn test_frame_buffer_dead_pixels() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio10;
+        let channel = peripherals.rmt.channel1;
+
+        let mut frame: Ws2812FrameBuffer = Ws2812FrameBuffer::new(channel, led_pin, 4).unwrap();
+        for i in 0..4 {
+            frame.set_pixel(i, LedPixelColorGrb24::new_with_rgb(i as u8 + 1, 0, 0));
+        }
+        frame.set_dead_pixels(Some(DeadPixelMap::black(&[0, 2])));
+        assert_eq!(frame.dead_pixels(), Some(DeadPixelMap::black(&[0, 2])));
+        frame.show().unwrap();
+        assert_eq!(
+            frame.driver.pixel_data,
+            Some(vec![0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 4, 0])
+        );
+        assert_eq!(
+            frame.pixel(2),
+            Some(LedPixelColorGrb24::new_with_rgb(3, 0, 0))
+        );
+
+        frame.set_dead_pixels(Some(DeadPixelMap::absent(&[0, 2])));
+        frame.set_color_order(Some(ColorOrder::Rgb));
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, Some(vec![2, 0, 0, 4, 0, 0]));
+
+        frame.set_dead_pixels(None);
+        frame.show().unwrap();
+        assert_eq!(
+            frame.driver.pixel_data,
+            Some(vec![1, 0, 0, 2, 0, 0, 3, 0, 0, 4, 0, 0])
+        );
+    }
+
+    #[test]
+    fn test_frame_buffer_white_extraction() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio7;
+        let channel = peripherals.rmt.channel1;
+
+        let mut frame =
+            LedPixelFrameBuffer::<LedPixelColorGrbw32>::new(channel, led_pin, 1).unwrap();
+        frame.set_pixel(0, LedPixelColorGrbw32::new_with_rgb(0xFF, 0x80, 0x40));
+        frame.set_white_extraction(true);
+        assert!(frame.white_extraction());
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, Some(vec![0x40, 0xBF, 0x00, 0x40]));
+        assert_eq!(frame.as_bytes(), [0x80, 0xFF, 0x40, 0x00]);
+
+        frame.set_color_order(Some(ColorOrder::Rgbw));
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, Some(vec![0xBF, 0x40, 0x00, 0x40]));
+
+        frame.set_white_extraction(false);
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, Some(vec![0xFF, 0x80, 0x40, 0x00]));
+    }
+
+    #[test]
+    fn test_frame_buffer_max_power() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio6;
+        let channel = peripherals.rmt.channel1;
+
+        let mut frame: Ws2812FrameBuffer = Ws2812FrameBuffer::new(channel, led_pin, 10).unwrap();
+        frame.fill(LedPixelColorGrb24::new_with_rgb(0xFF, 0xFF, 0xFF));
+        frame.set_max_power_ma(Some(300));
+        assert_eq!(frame.max_power_ma(), Some(300));
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, Some(vec![127; 30]));
+        assert_eq!(frame.as_bytes(), [0xFF; 30]);
+        assert_eq!(frame.estimated_current_ma(), 299);
+
+        // Within the budget
+        frame.set_current_model(CurrentModel { channel_ma: 10 });
+        assert_eq!(frame.current_model(), CurrentModel { channel_ma: 10 });
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, Some(vec![0xFF; 30]));
+        assert_eq!(frame.estimated_current_ma(), 300);
+
+        frame.set_current_model(CurrentModel::WS2812);
+        frame.set_max_power_ma(None);
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, Some(vec![0xFF; 30]));
+    }
+
+    #[test]
+    fn test_frame_buffer_dithering() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio4;
+        let channel = peripherals.rmt.channel1;
+
+        let mut frame: Ws2812FrameBuffer = Ws2812FrameBuffer::new(channel, led_pin, 1).unwrap();
+        frame.set_brightness_source(|| 0x3F);
+        frame.set_pixel(0, LedPixelColorGrb24::new_with_rgb(6, 4, 0));
+        frame.set_dithering(true);
+        assert!(frame.dithering());
+
+        // 1.5, 1.0 and 0.0 on average, written on each call
+        let mut frames = Vec::new();
+        for _ in 0..4 {
+            frame.driver.pixel_data = None;
+            frame.show().unwrap();
+            frames.push(frame.driver.pixel_data.clone().unwrap());
+        }
+        assert_eq!(frames, [[1, 1, 0], [1, 2, 0], [1, 1, 0], [1, 2, 0]]);
+
+        frame.set_dithering(false);
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, Some(vec![1, 1, 0]));
+        frame.driver.pixel_data = None;
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, None);
+    }
+
+    #[test]
+    fn test_frame_buffer_pixel16() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio5;
+        let channel = peripherals.rmt.channel1;
+
+        let mut frame: Ws2812FrameBuffer = Ws2812FrameBuffer::new(channel, led_pin, 2).unwrap();
+        frame.set_dithering(true);
+        assert!(frame.set_pixel16(0, LedPixelColor16::new_with_rgb(0x0140, 0x0080, 0)));
+        assert!(!frame.set_pixel16(2, LedPixelColor16::default()));
+        frame.set_pixel(1, LedPixelColorGrb24::new_with_rgb(1, 0, 0));
+        assert_eq!(frame.as_bytes(), [0, 1, 0, 0, 1, 0]);
+
+        // 0.5, 1.25 and 1.0 on average
+        let mut sums = [0; 6];
+        for _ in 0..4 {
+            frame.show().unwrap();
+            let pixel_data = frame.driver.pixel_data.take().unwrap();
+            sums.iter_mut()
+                .zip(pixel_data)
+                .for_each(|(sum, v)| *sum += v);
+        }
+        assert_eq!(sums, [2, 5, 0, 0, 4, 0]);
+
+        // Reordered with the fractions
+        frame.set_color_order(Some(ColorOrder::Rgb));
+        frame.fill16(LedPixelColor16::new_with_rgb(0x0080, 0, 0));
+        frame.show().unwrap();
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, Some(vec![1, 0, 0, 1, 0, 0]));
+
+        frame.set_dithering(false);
+        frame.fill(LedPixelColorGrb24::new_with_rgb(2, 0, 0));
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, Some(vec![2, 0, 0, 2, 0, 0]));
+        frame.driver.pixel_data = None;
+        frame.show().unwrap();
+        assert_eq!(frame.driver.pixel_data, None);
+    }
+}
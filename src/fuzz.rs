@@ -0,0 +1,101 @@
+//! Fuzzing entry points of the encoding logic.
+//!
+//! This module is available with `--cfg fuzzing`, which is set by `cargo fuzz`.
+//! Each entry point accepts an arbitrary input, runs the encoder and panics if any of its
+//! invariants is violated.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! // fuzz/fuzz_targets/encode.rs
+//! #![no_main]
+//!
+//! libfuzzer_sys::fuzz_target!(|data: &[u8]| {
+//!     ws2812_esp32_rmt_driver::fuzz::encode(data);
+//! });
+//! ```
+
+use crate::codec::encode_bits;
+use crate::driver::timing::LedTiming;
+use core::time::Duration;
+
+/// Byte count of the timing config at the beginning of the input of [`encode()`].
+pub const TIMING_LEN: usize = 5 * 2;
+
+/// Splits the input into the timing config and the pixel data.
+///
+/// The timing config is T0H, T0L, T1H, T1L and the reset code duration in nanoseconds,
+/// each as a little-endian `u16`. [`LedTiming::WS2812`] is used if the input is too short.
+pub fn split_timing(data: &[u8]) -> (LedTiming, &[u8]) {
+    let Some((config, pixel_data)) = data.split_first_chunk::<TIMING_LEN>() else {
+        return (LedTiming::WS2812, data);
+    };
+    let nanos = |i: usize| {
+        let value = u16::from_le_bytes([config[i], config[i + 1]]);
+        Duration::from_nanos(value.into())
+    };
+    let timing = LedTiming {
+        t0h: nanos(0),
+        t0l: nanos(2),
+        t1h: nanos(4),
+        t1l: nanos(6),
+        reset: nanos(8),
+    };
+    (timing, pixel_data)
+}
+
+/// Runs the encoder over the input of a timing config followed by the pixel data.
+///
+/// See [`split_timing()`] for the layout of the input.
+///
+/// # Panics
+///
+/// Panics if an invariant of the encoder is violated.
+pub fn encode(data: &[u8]) {
+    let (timing, pixel_data) = split_timing(data);
+    encode_with_timing(&timing, pixel_data);
+}
+
+/// Runs the encoder over the pixel data with the timing config.
+///
+/// # Panics
+///
+/// Panics if an invariant of the encoder is violated.
+pub fn encode_with_timing(timing: &LedTiming, pixel_data: &[u8]) {
+    let bit0 = timing.t0h + timing.t0l;
+    let bit1 = timing.t1h + timing.t1l;
+
+    let mut bits = encode_bits(pixel_data.iter().copied());
+    let mut duration = Duration::ZERO;
+    for &v in pixel_data {
+        let mut decoded = 0u8;
+        for _ in 0..u8::BITS {
+            let bit = bits.next().expect("encoder shall emit 8 bits per byte");
+            decoded = (decoded << 1) | u8::from(bit);
+            duration += if bit { bit1 } else { bit0 };
+        }
+        assert_eq!(decoded, v, "encoded bits shall be MSB first");
+    }
+    assert!(bits.next().is_none(), "encoder shall not emit extra bits");
+
+    let bit_len = pixel_data.len() as u32 * u8::BITS;
+    assert!(duration >= bit0.min(bit1) * bit_len);
+    assert!(duration <= timing.data_duration(pixel_data.len()));
+    assert_eq!(
+        timing.frame_duration(pixel_data.len(), 1),
+        timing.data_duration(pixel_data.len()) + timing.reset
+    );
+
+    #[cfg(all(feature = "waveform", not(target_vendor = "espressif")))]
+    {
+        let waveform = crate::mock::waveform::Waveform::encode(pixel_data, timing);
+        let (end, bit_edges) = waveform.edges.split_last().expect("end of the frame");
+        assert_eq!(bit_edges.len(), bit_len as usize * 2);
+        for edges in bit_edges.windows(2) {
+            assert_ne!(edges[0].level, edges[1].level);
+            assert!(edges[0].time <= edges[1].time);
+        }
+        assert!(!end.level);
+        assert_eq!(waveform.duration, duration + timing.reset);
+    }
+}
@@ -0,0 +1,235 @@
+//! Global frame-rate governor across the LED strips.
+//!
+//! [`FrameGovernor`] enforces a maximum aggregate frame rate over all the drivers registered
+//! with it. Each write through a [`GovernedDriver`] takes its own time slot, one slot interval
+//! apart from the others, and waits for it; thus the transmissions of several strips on one
+//! ESP32 are staggered instead of contending for the CPU and the interrupts at the same
+//! instant.
+//!
+//! A [`GovernedDriver`] is itself an LED driver, so the wrappers can be built on it by
+//! `from_driver()`.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::governor::FrameGovernor;
+//! use ws2812_esp32_rmt_driver::{LedStripWrite, Ws2812Esp32RmtDriver};
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! // Up to 1000 frames per second in total
+//! let governor = FrameGovernor::new(1000);
+//! let mut strip0 = governor.register(
+//!     Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap(),
+//! );
+//! let mut strip1 = governor.register(
+//!     Ws2812Esp32RmtDriver::new(peripherals.rmt.channel1, peripherals.pins.gpio1).unwrap(),
+//! );
+//!
+//! // The second write waits for 1 ms after the first.
+//! strip0.write_blocking([0, 30, 0].into_iter()).unwrap();
+//! strip1.write_blocking([30, 0, 0].into_iter()).unwrap();
+//! ```
+
+use crate::driver::{LedStripDriver, LedStripWrite};
+use crate::time::{StdTimeSource, TimeSource};
+use core::time::Duration;
+use std::sync::{Arc, Mutex};
+
+/// Clock of a governor: the current time and the sleep.
+struct GovernorClock {
+    now: Box<dyn TimeSource + Send + Sync>,
+    sleep: Box<dyn Fn(Duration) + Send + Sync>,
+}
+
+struct GovernorInner {
+    slot_interval: Duration,
+    next_slot: Mutex<Option<Duration>>,
+    clock: GovernorClock,
+}
+
+/// Governor of the aggregate frame rate of the registered drivers.
+///
+/// The clones share the time slots.
+#[derive(Clone)]
+pub struct FrameGovernor {
+    inner: Arc<GovernorInner>,
+}
+
+impl FrameGovernor {
+    /// Creates a governor allowing up to `max_frame_rate` frames per second in total.
+    ///
+    /// It sleeps the calling thread with [`std::thread::sleep()`] until the time slot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_frame_rate` is zero.
+    pub fn new(max_frame_rate: u32) -> Self {
+        Self::with_clock(max_frame_rate, StdTimeSource::new(), std::thread::sleep)
+    }
+
+    /// Creates a governor allowing up to `max_frame_rate` frames per second in total, with a
+    /// custom clock.
+    ///
+    /// `now` is the time source, e.g. a [`VirtualClock`] or a closure, and `sleep` blocks for
+    /// the duration, e.g. [`VirtualClock::advance()`] in the mock.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_frame_rate` is zero.
+    ///
+    /// [`VirtualClock`]: crate::mock::time::VirtualClock
+    /// [`VirtualClock::advance()`]: crate::mock::time::VirtualClock::advance
+    pub fn with_clock<N, S>(max_frame_rate: u32, now: N, sleep: S) -> Self
+    where
+        N: TimeSource + Send + Sync + 'static,
+        S: Fn(Duration) + Send + Sync + 'static,
+    {
+        assert!(max_frame_rate > 0, "max_frame_rate shall not be zero");
+        Self {
+            inner: Arc::new(GovernorInner {
+                slot_interval: Duration::from_secs(1) / max_frame_rate,
+                next_slot: Mutex::new(None),
+                clock: GovernorClock {
+                    now: Box::new(now),
+                    sleep: Box::new(sleep),
+                },
+            }),
+        }
+    }
+
+    /// Returns the minimum interval between the writes of the registered drivers.
+    #[inline]
+    pub fn slot_interval(&self) -> Duration {
+        self.inner.slot_interval
+    }
+
+    /// Registers `driver` with this governor.
+    pub fn register<D: LedStripWrite>(&self, driver: D) -> GovernedDriver<D> {
+        GovernedDriver {
+            driver,
+            governor: self.clone(),
+        }
+    }
+
+    /// Takes the next time slot and waits for it.
+    fn wait_slot(&self) {
+        let clock = &self.inner.clock;
+        let wait = {
+            let mut next_slot = self.inner.next_slot.lock().unwrap();
+            let now = clock.now.now();
+            let slot = next_slot.map_or(now, |next_slot| next_slot.max(now));
+            *next_slot = Some(slot + self.inner.slot_interval);
+            slot - now
+        };
+        if !wait.is_zero() {
+            (clock.sleep)(wait);
+        }
+    }
+}
+
+/// LED driver registered with a [`FrameGovernor`].
+pub struct GovernedDriver<D> {
+    driver: D,
+    governor: FrameGovernor,
+}
+
+impl<D> GovernedDriver<D> {
+    /// Returns the driver.
+    #[inline]
+    pub fn driver(&self) -> &D {
+        &self.driver
+    }
+
+    /// Returns the driver mutably.
+    ///
+    /// The writes through it bypass the governor.
+    #[inline]
+    pub fn driver_mut(&mut self) -> &mut D {
+        &mut self.driver
+    }
+
+    /// Unregisters the driver and returns it.
+    pub fn into_inner(self) -> D {
+        self.driver
+    }
+}
+
+impl<D: LedStripWrite> LedStripWrite for GovernedDriver<D> {
+    type Error = D::Error;
+
+    fn write_blocking<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        self.governor.wait_slot();
+        self.driver.write_blocking(pixel_sequence)
+    }
+}
+
+impl<D: LedStripDriver> LedStripDriver for GovernedDriver<D> {
+    fn write<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        self.governor.wait_slot();
+        self.driver.write(pixel_sequence)
+    }
+
+    #[inline]
+    fn latch(&mut self) -> Result<(), Self::Error> {
+        self.driver.latch()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+    use crate::mock::time::VirtualClock;
+    use crate::Ws2812Esp32RmtDriver;
+
+    #[test]
+    fn test_frame_governor() {
+        let clock = VirtualClock::new();
+        let governor = {
+            let sleep = clock.clone();
+            FrameGovernor::with_clock(100, clock.clone(), move |d| sleep.advance(d))
+        };
+        assert_eq!(governor.slot_interval(), Duration::from_millis(10));
+
+        let peripherals = Peripherals::take().unwrap();
+        let mut drivers = [
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap(),
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel1, peripherals.pins.gpio1).unwrap(),
+        ]
+        .map(|mut driver| {
+            driver.set_virtual_clock(clock.clone());
+            governor.register(driver)
+        });
+
+        // The writes are staggered by the slot interval.
+        drivers[0].write_blocking([1, 2, 3].into_iter()).unwrap();
+        drivers[1].write_blocking([4, 5, 6].into_iter()).unwrap();
+        drivers[0].write_blocking([7, 8, 9].into_iter()).unwrap();
+        let starts = |driver: &GovernedDriver<Ws2812Esp32RmtDriver>| {
+            let records = driver.driver().frame_records();
+            records.iter().map(|r| r.start).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            starts(&drivers[0]),
+            [Duration::ZERO, Duration::from_millis(20)]
+        );
+        assert_eq!(starts(&drivers[1]), [Duration::from_millis(10)]);
+
+        // No wait once the slot has passed.
+        clock.advance(Duration::from_millis(50));
+        let now = clock.now();
+        drivers[1].write_blocking([0, 0, 0].into_iter()).unwrap();
+        assert_eq!(starts(&drivers[1])[1], now);
+        assert_eq!(drivers[1].driver().pixel_data, Some(vec![0, 0, 0]));
+    }
+}
@@ -0,0 +1,400 @@
+//! Group controller of the LED strips.
+//!
+//! [`GroupController`] applies the brightness, the gamma and the color correction uniformly
+//! to all the strips (or panels) registered with it, so that a fixture of several outputs
+//! behaves as one logical light. Each registered driver is a [`GroupMember`] that maps the
+//! pixel data through the lookup tables of the current [`GroupSettings`].
+//!
+//! A [`GroupMember`] is itself an LED driver, so the wrappers can be built on it by
+//! `from_driver()`. It keeps the last frame written, and [`GroupMember::refresh()`] writes it
+//! again when the settings have changed since, without redrawing the frame.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::driver::color::{LedPixelColorGrb24, LedPixelColorGrbw32};
+//! use ws2812_esp32_rmt_driver::group::{ColorCorrection, GroupController, GroupSettings};
+//! use ws2812_esp32_rmt_driver::{LedStripWrite, Ws2812Esp32RmtDriver};
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let controller = GroupController::new(GroupSettings::default());
+//! let mut strip0 = controller.register::<LedPixelColorGrb24, _>(
+//!     Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap(),
+//! );
+//! let mut strip1 = controller.register::<LedPixelColorGrbw32, _>(
+//!     Ws2812Esp32RmtDriver::new(peripherals.rmt.channel1, peripherals.pins.gpio1).unwrap(),
+//! );
+//!
+//! // G, R, B and G, R, B, W
+//! strip0.write_blocking([255, 255, 255].into_iter()).unwrap();
+//! strip1.write_blocking([255, 255, 255, 255].into_iter()).unwrap();
+//!
+//! // Halve the red of the both strips.
+//! controller.set_correction(ColorCorrection::new(127, 255, 255, 255));
+//! strip0.refresh().unwrap();
+//! strip1.refresh().unwrap();
+//! assert_eq!(strip0.driver().pixel_data, Some(vec![255, 127, 255]));
+//! assert_eq!(strip1.driver().pixel_data, Some(vec![255, 127, 255, 255]));
+//! ```
+
+use crate::driver::brightness::scale;
+use crate::driver::color::LedPixelColor;
+use crate::driver::{LedStripDriver, LedStripWrite};
+use std::sync::{Arc, Mutex};
+
+/// Lookup tables of the R, G, B and W channel values
+type Luts = [[u8; 256]; 4];
+
+/// Scaling of each channel to correct the color of the LED pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ColorCorrection {
+    /// Scale of R, 255 for as is
+    pub r: u8,
+    /// Scale of G, 255 for as is
+    pub g: u8,
+    /// Scale of B, 255 for as is
+    pub b: u8,
+    /// Scale of W, 255 for as is
+    pub w: u8,
+}
+
+impl ColorCorrection {
+    /// No correction
+    pub const NONE: Self = Self::new(255, 255, 255, 255);
+
+    /// Creates a color correction scaling each channel down to `(scale + 1) / 256`.
+    pub const fn new(r: u8, g: u8, b: u8, w: u8) -> Self {
+        Self { r, g, b, w }
+    }
+}
+
+impl Default for ColorCorrection {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// Settings applied to all the strips of a group
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GroupSettings {
+    /// Brightness, 255 for as is
+    pub brightness: u8,
+    /// Gamma of the color correction, 1.0 for none
+    pub gamma: f32,
+    /// Scaling of each channel
+    pub correction: ColorCorrection,
+}
+
+impl GroupSettings {
+    /// Creates the settings without brightness limit and color correction.
+    pub const fn new() -> Self {
+        Self {
+            brightness: u8::MAX,
+            gamma: 1.0,
+            correction: ColorCorrection::NONE,
+        }
+    }
+
+    /// Returns the lookup tables of the settings.
+    fn luts(&self) -> Luts {
+        let correction = self.correction;
+        let mut luts = [[0; 256]; 4];
+        for (lut, channel_scale) in
+            luts.iter_mut()
+                .zip([correction.r, correction.g, correction.b, correction.w])
+        {
+            for (value, out) in lut.iter_mut().enumerate() {
+                let corrected = (value as f32 / 255.0).powf(self.gamma) * 255.0;
+                let corrected = corrected.round().clamp(0.0, 255.0) as u8;
+                *out = scale(scale(corrected, channel_scale), self.brightness);
+            }
+        }
+        luts
+    }
+}
+
+impl Default for GroupSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct ControllerState {
+    settings: GroupSettings,
+    luts: Arc<Luts>,
+    /// Incremented on each change of the settings
+    generation: u32,
+}
+
+/// Controller of the settings shared by the registered strips.
+///
+/// The clones share the settings.
+#[derive(Clone)]
+pub struct GroupController {
+    state: Arc<Mutex<ControllerState>>,
+}
+
+impl GroupController {
+    /// Creates a controller of the settings.
+    pub fn new(settings: GroupSettings) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ControllerState {
+                settings,
+                luts: Arc::new(settings.luts()),
+                generation: 0,
+            })),
+        }
+    }
+
+    /// Returns the current settings.
+    pub fn settings(&self) -> GroupSettings {
+        self.state.lock().unwrap().settings
+    }
+
+    /// Changes the settings of all the strips.
+    ///
+    /// The strips apply them on the next write or [`GroupMember::refresh()`].
+    pub fn set_settings(&self, settings: GroupSettings) {
+        let mut state = self.state.lock().unwrap();
+        if state.settings != settings {
+            state.settings = settings;
+            state.luts = Arc::new(settings.luts());
+            state.generation = state.generation.wrapping_add(1);
+        }
+    }
+
+    /// Changes the brightness of all the strips.
+    pub fn set_brightness(&self, brightness: u8) {
+        self.set_settings(GroupSettings {
+            brightness,
+            ..self.settings()
+        });
+    }
+
+    /// Changes the gamma of all the strips.
+    pub fn set_gamma(&self, gamma: f32) {
+        self.set_settings(GroupSettings {
+            gamma,
+            ..self.settings()
+        });
+    }
+
+    /// Changes the color correction of all the strips.
+    pub fn set_correction(&self, correction: ColorCorrection) {
+        self.set_settings(GroupSettings {
+            correction,
+            ..self.settings()
+        });
+    }
+
+    /// Registers `driver` of the LED pixels of `CDev` with this controller.
+    pub fn register<CDev, D>(&self, driver: D) -> GroupMember<D>
+    where
+        CDev: LedPixelColor,
+        D: LedStripWrite,
+    {
+        // Channel index (R, G, B, W) of each byte of a pixel
+        let channels = CDev::new_with_rgbw(0, 1, 2, 3).as_ref().to_vec();
+        let (luts, generation) = self.snapshot();
+        GroupMember {
+            driver,
+            controller: self.clone(),
+            channels,
+            luts,
+            generation,
+            frame: None,
+        }
+    }
+
+    /// Returns the current lookup tables and generation.
+    fn snapshot(&self) -> (Arc<Luts>, u32) {
+        let state = self.state.lock().unwrap();
+        (state.luts.clone(), state.generation)
+    }
+
+    /// Returns the current generation.
+    fn generation(&self) -> u32 {
+        self.state.lock().unwrap().generation
+    }
+}
+
+/// LED driver registered with a [`GroupController`].
+pub struct GroupMember<D> {
+    driver: D,
+    controller: GroupController,
+    channels: Vec<u8>,
+    luts: Arc<Luts>,
+    generation: u32,
+    /// Last frame written before the settings applied
+    frame: Option<Vec<u8>>,
+}
+
+impl<D> GroupMember<D> {
+    /// Returns the driver.
+    #[inline]
+    pub fn driver(&self) -> &D {
+        &self.driver
+    }
+
+    /// Returns the driver mutably.
+    ///
+    /// The writes through it bypass the settings.
+    #[inline]
+    pub fn driver_mut(&mut self) -> &mut D {
+        &mut self.driver
+    }
+
+    /// Unregisters the driver and returns it.
+    pub fn into_inner(self) -> D {
+        self.driver
+    }
+
+    /// Returns whether the settings have changed since the last frame was written.
+    pub fn is_stale(&self) -> bool {
+        self.frame.is_some() && self.generation != self.controller.generation()
+    }
+
+    /// Takes the current settings of the controller.
+    fn sync(&mut self) {
+        (self.luts, self.generation) = self.controller.snapshot();
+    }
+}
+
+impl<D: LedStripWrite> GroupMember<D> {
+    /// Writes the last frame again if the settings have changed since it was written.
+    ///
+    /// Returns whether the frame was written.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by the driver.
+    pub fn refresh(&mut self) -> Result<bool, D::Error> {
+        if !self.is_stale() {
+            return Ok(false);
+        }
+        self.sync();
+        self.write_frame()?;
+        Ok(true)
+    }
+
+    /// Writes the last frame through the lookup tables.
+    fn write_frame(&mut self) -> Result<(), D::Error> {
+        let (luts, channels) = (&*self.luts, &self.channels);
+        let frame = self.frame.as_deref().unwrap_or_default();
+        self.driver.write_blocking(
+            frame
+                .iter()
+                .zip(channels.iter().cycle())
+                .map(|(&v, &channel)| luts[channel as usize][v as usize]),
+        )
+    }
+}
+
+impl<D: LedStripWrite> LedStripWrite for GroupMember<D> {
+    type Error = D::Error;
+
+    fn write_blocking<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        self.sync();
+        let frame = self.frame.get_or_insert_with(Vec::new);
+        frame.clear();
+        frame.extend(pixel_sequence);
+        self.write_frame()
+    }
+}
+
+impl<D: LedStripDriver> LedStripDriver for GroupMember<D> {
+    fn write<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        self.sync();
+        let frame = pixel_sequence.collect::<Vec<_>>();
+        self.frame = Some(frame.clone());
+        let (luts, channels) = (self.luts.clone(), self.channels.clone());
+        self.driver.write(
+            frame
+                .into_iter()
+                .zip(channels.into_iter().cycle())
+                .map(move |(v, channel)| luts[channel as usize][v as usize]),
+        )
+    }
+
+    #[inline]
+    fn latch(&mut self) -> Result<(), Self::Error> {
+        self.driver.latch()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::{LedPixelColorGrb24, LedPixelColorGrbw32};
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+    use crate::Ws2812Esp32RmtDriver;
+
+    #[test]
+    fn test_group_settings_luts() {
+        let luts = GroupSettings::new().luts();
+        assert!(luts
+            .iter()
+            .all(|lut| lut.iter().enumerate().all(|(i, &v)| i == v as usize)));
+
+        let settings = GroupSettings {
+            brightness: 127,
+            gamma: 2.0,
+            correction: ColorCorrection::new(255, 255, 127, 255),
+        };
+        let luts = settings.luts();
+        assert_eq!(luts[0][255], 127);
+        assert_eq!(luts[0][128], 32);
+        assert_eq!(luts[2][255], 63);
+        assert_eq!(luts[3][0], 0);
+    }
+
+    #[test]
+    fn test_group_controller() {
+        let peripherals = Peripherals::take().unwrap();
+        let controller = GroupController::new(GroupSettings::new());
+        let mut strip0 = controller.register::<LedPixelColorGrb24, _>(
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap(),
+        );
+        let mut strip1 = controller.register::<LedPixelColorGrbw32, _>(
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel1, peripherals.pins.gpio1).unwrap(),
+        );
+        assert!(!strip0.is_stale());
+        assert!(!strip0.refresh().unwrap());
+
+        strip0
+            .write_blocking([10, 20, 30, 40, 50, 60].into_iter())
+            .unwrap();
+        strip1.write([255; 4].into_iter()).unwrap();
+        assert_eq!(
+            strip0.driver().pixel_data,
+            Some(vec![10, 20, 30, 40, 50, 60])
+        );
+        assert_eq!(strip1.driver().pixel_data, Some(vec![255; 4]));
+
+        controller.set_correction(ColorCorrection::new(255, 127, 255, 63));
+        assert!(strip0.is_stale() && strip1.is_stale());
+        assert!(strip0.refresh().unwrap());
+        assert!(strip1.refresh().unwrap());
+        assert!(!strip1.refresh().unwrap());
+        assert_eq!(
+            strip0.driver().pixel_data,
+            Some(vec![5, 20, 30, 20, 50, 60])
+        );
+        assert_eq!(strip1.driver().pixel_data, Some(vec![127, 255, 255, 63]));
+
+        controller.set_brightness(0);
+        strip0.write_blocking([255; 3].into_iter()).unwrap();
+        assert_eq!(strip0.driver().pixel_data, Some(vec![0; 3]));
+        assert_eq!(controller.settings().brightness, 0);
+    }
+}
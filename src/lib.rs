@@ -7,17 +7,96 @@ extern crate alloc;
 
 pub mod driver;
 
+pub use driver::{LedStripDriver, LedStripWrite};
+#[cfg(not(target_os = "none"))]
 pub use driver::{Ws2812Esp32RmtDriver, Ws2812Esp32RmtDriverError};
 
+#[cfg(all(target_os = "none", not(feature = "esp-hal")))]
+compile_error!("`esp-hal` feature is required on the bare-metal targets");
+
+#[cfg(all(feature = "boards", not(target_os = "none")))]
+pub mod boards;
+
+#[cfg(feature = "codec")]
+pub mod codec;
+#[cfg(not(feature = "codec"))]
+#[allow(dead_code)]
+pub(crate) mod codec;
+
+#[cfg(feature = "embedded-graphics-core")]
+pub mod cube;
+
+#[cfg(feature = "effects")]
+pub mod effects;
+
+#[cfg(feature = "embassy")]
+pub mod embassy;
+
+#[cfg(feature = "flash-safe")]
+pub mod flash_safe;
+
+pub mod framebuffer;
+
+#[cfg(feature = "governor")]
+pub mod governor;
+
+#[cfg(feature = "group")]
+pub mod group;
+
 #[cfg(feature = "embedded-graphics-core")]
 pub mod lib_embedded_graphics;
 
 #[cfg(feature = "smart-leds-trait")]
 pub mod lib_smart_leds;
 
-#[cfg(not(target_vendor = "espressif"))]
+#[cfg(all(feature = "smart-leds-matrix", not(target_os = "none")))]
+pub mod lib_smart_leds_matrix;
+
+#[cfg(not(any(target_vendor = "espressif", target_os = "none")))]
 pub mod mock;
 
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+
+#[cfg(feature = "net")]
+pub mod net;
+
+#[cfg(feature = "patterns")]
+pub mod patterns;
+
+#[cfg(feature = "embedded-graphics-core")]
+pub mod pixel_map;
+
+#[cfg(feature = "power-guard")]
+pub mod power_guard;
+
+#[cfg(feature = "embedded-graphics-core")]
+pub mod ring;
+
+#[cfg(any(
+    feature = "effects",
+    feature = "test-support",
+    not(any(target_vendor = "espressif", target_os = "none"))
+))]
+mod rng;
+
+#[cfg(feature = "nvs")]
+pub mod settings;
+
+#[cfg(feature = "portable-atomic")]
+pub mod shared;
+
+#[cfg(feature = "test-support")]
+pub mod test_support;
+
+pub mod time;
+
+#[cfg(feature = "embedded-graphics-core")]
+pub mod topology;
+
+#[cfg(fuzzing)]
+pub mod fuzz;
+
 #[cfg(feature = "smart-leds-trait")]
 pub use lib_smart_leds::{LedPixelEsp32Rmt, Ws2812Esp32Rmt, RGBW8};
 #[cfg(feature = "smart-leds-trait")]
@@ -1,17 +1,29 @@
 //! embedded-graphics draw target API.
 
-use crate::driver::color::{LedPixelColor, LedPixelColorGrb24, LedPixelColorImpl};
-use crate::driver::{Ws2812Esp32RmtDriver, Ws2812Esp32RmtDriverError};
+#[cfg(feature = "alloc")]
+use crate::driver::brightness::BrightnessSource;
+use crate::driver::calibration::{ColorCalibration, ColorTemperature};
+use crate::driver::color::{ColorOrder, LedPixelColor, LedPixelColorGrb24, LedPixelColorImpl};
+use crate::driver::gamma::GammaLut;
+use crate::driver::power::CurrentModel;
+#[cfg(not(target_os = "none"))]
+use crate::driver::{channel::TxRmtChannel, Ws2812Esp32RmtDriverError};
+use crate::driver::{DefaultLedDriver, LedStripDriver};
+use crate::framebuffer::{DeadPixelMap, FrameBufferData, LedPixelFrameBuffer};
+#[cfg(feature = "portable-atomic")]
+use crate::shared::ChangedFlag;
+use crate::topology::Tile;
 use core::marker::PhantomData;
-use core::ops::DerefMut;
+use core::ops::{DerefMut, Range};
 use embedded_graphics_core::draw_target::DrawTarget;
 use embedded_graphics_core::geometry::{OriginDimensions, Point, Size};
 use embedded_graphics_core::pixelcolor::{Rgb888, RgbColor};
 use embedded_graphics_core::Pixel;
 
-#[cfg(not(target_vendor = "espressif"))]
+#[cfg(not(any(target_vendor = "espressif", target_os = "none")))]
 use crate::mock::esp_idf_hal;
-use esp_idf_hal::{gpio::OutputPin, peripheral::Peripheral, rmt::RmtChannel};
+#[cfg(not(target_os = "none"))]
+use esp_idf_hal::{gpio::OutputPin, peripheral::Peripheral};
 
 /// LED pixel shape
 pub trait LedPixelShape {
@@ -27,7 +39,36 @@ pub trait LedPixelShape {
     fn pixel_index(point: Point) -> Option<usize>;
 }
 
+/// LED pixel shape of the instance, e.g. of the size read from the configuration at runtime
+///
+/// It is implemented for every [`LedPixelShape`], and for [`DynLedPixelShape`].
+pub trait LedPixelShapeInstance {
+    /// Returns the number of pixels
+    fn shape_pixel_len(&self) -> usize;
+    /// Physical size of the LED pixel equipment.
+    fn shape_size(&self) -> Size;
+    /// Convert from `point` to the index.
+    /// Returns `None` if it is out of the bounds.
+    fn shape_pixel_index(&self, point: Point) -> Option<usize>;
+}
+
+impl<S: LedPixelShape> LedPixelShapeInstance for S {
+    #[inline]
+    fn shape_pixel_len(&self) -> usize {
+        S::pixel_len()
+    }
+    #[inline]
+    fn shape_size(&self) -> Size {
+        S::size()
+    }
+    #[inline]
+    fn shape_pixel_index(&self, point: Point) -> Option<usize> {
+        S::pixel_index(point)
+    }
+}
+
 /// LED pixel shape of `W`x`H` matrix
+#[derive(Debug, Clone, Copy, Default)]
 pub struct LedPixelMatrix<const W: usize, const H: usize> {}
 
 impl<const W: usize, const H: usize> LedPixelMatrix<W, H> {
@@ -35,6 +76,14 @@ impl<const W: usize, const H: usize> LedPixelMatrix<W, H> {
     pub const SIZE: Size = Size::new(W as u32, H as u32);
     /// The number of pixels.
     pub const PIXEL_LEN: usize = W * H;
+
+    /// Returns the tile of the matrix placed at `origin`, e.g. a panel of
+    /// [`LedPixelTiledMatrix`].
+    ///
+    /// [`LedPixelTiledMatrix`]: crate::topology::LedPixelTiledMatrix
+    pub const fn tile(origin: Point) -> Tile {
+        Tile::new(0, origin, Self::SIZE)
+    }
 }
 
 impl<const W: usize, const H: usize> LedPixelShape for LedPixelMatrix<W, H> {
@@ -56,18 +105,84 @@ impl<const W: usize, const H: usize> LedPixelShape for LedPixelMatrix<W, H> {
     }
 }
 
-/// Default data storage type for `LedPixelDrawTarget`.
-#[cfg(feature = "std")]
-type LedPixelDrawTargetData = Vec<u8>;
+/// LED pixel shape of the matrix sized at runtime, the counterpart of [`LedPixelMatrix`]
+///
+/// # Examples
+///
+/// ```
+/// #[cfg(not(target_vendor = "espressif"))]
+/// use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+///
+/// use embedded_graphics::pixelcolor::Rgb888;
+/// use embedded_graphics::prelude::*;
+/// use esp_idf_hal::peripherals::Peripherals;
+/// use ws2812_esp32_rmt_driver::lib_embedded_graphics::{DynLedPixelShape, Ws2812DrawTarget};
+///
+/// // e.g. read from the NVS
+/// let (width, height) = (8, 4);
+///
+/// let peripherals = Peripherals::take().unwrap();
+/// let led_pin = peripherals.pins.gpio27;
+/// let channel = peripherals.rmt.channel0;
+/// let shape = DynLedPixelShape::new(width, height);
+/// let mut draw = Ws2812DrawTarget::<DynLedPixelShape>::new_with_shape(shape, channel, led_pin).unwrap();
+/// assert_eq!(draw.size(), Size::new(8, 4));
+/// Pixel(Point::new(7, 3), Rgb888::WHITE).draw(&mut draw).unwrap();
+/// draw.flush().unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DynLedPixelShape {
+    width: usize,
+    height: usize,
+}
 
-/// Default data storage type for `LedPixelDrawTarget`.
-#[cfg(all(not(feature = "std"), feature = "alloc"))]
-type LedPixelDrawTargetData = alloc::vec::Vec<u8>;
+impl DynLedPixelShape {
+    /// Creates a shape of `width`x`height` matrix.
+    #[inline]
+    pub const fn new(width: usize, height: usize) -> Self {
+        Self { width, height }
+    }
+
+    /// Creates a shape of `len`-led strip.
+    #[inline]
+    pub const fn strip(len: usize) -> Self {
+        Self::new(len, 1)
+    }
+
+    /// Returns the width.
+    #[inline]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height.
+    #[inline]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+}
+
+impl LedPixelShapeInstance for DynLedPixelShape {
+    #[inline]
+    fn shape_pixel_len(&self) -> usize {
+        self.width * self.height
+    }
+    #[inline]
+    fn shape_size(&self) -> Size {
+        Size::new(self.width as u32, self.height as u32)
+    }
+
+    fn shape_pixel_index(&self, point: Point) -> Option<usize> {
+        let (x, y) = (
+            usize::try_from(point.x).ok()?,
+            usize::try_from(point.y).ok()?,
+        );
+        (x < self.width && y < self.height).then_some(x + y * self.width)
+    }
+}
 
 /// Default data storage type for `LedPixelDrawTarget`.
-/// In case of heapless, allocate 256-byte capacity vector.
-#[cfg(all(not(feature = "std"), not(feature = "alloc")))]
-type LedPixelDrawTargetData = heapless::Vec<u8, 256>;
+type LedPixelDrawTargetData = FrameBufferData;
 
 /// Target for embedded-graphics drawing operations of the LED pixels.
 ///
@@ -76,8 +191,9 @@ type LedPixelDrawTargetData = heapless::Vec<u8, 256>;
 ///
 /// * `CDraw` - color type for embedded-graphics drawing operations
 /// * `CDev` - the LED pixel color type (device dependant). It shall be convertible from `CDraw`.
-/// * `S` - the LED pixel shape
+/// * `S` - the LED pixel shape, static [`LedPixelShape`] or of the instance, e.g. [`DynLedPixelShape`]
 /// * `Data` - (optional) data storage type. It shall be `Vec`-like struct.
+/// * `D` - (optional) the LED driver backend
 ///
 /// [`flush()`] operation shall be required to write changes from a framebuffer to the display.
 ///
@@ -85,125 +201,448 @@ type LedPixelDrawTargetData = heapless::Vec<u8, 256>;
 /// e.g., `heapless::Vec<u8, PIXEL_LEN>` where `PIXEL_LEN` equals to `S::size() * CDev::BPP`.
 ///
 /// [`flush()`]: #method.flush
-pub struct LedPixelDrawTarget<'d, CDraw, CDev, S, Data = LedPixelDrawTargetData>
-where
+pub struct LedPixelDrawTarget<
+    'd,
+    CDraw,
+    CDev,
+    S,
+    Data = LedPixelDrawTargetData,
+    D = DefaultLedDriver<'d>,
+> where
     CDraw: RgbColor,
     CDev: LedPixelColor + From<CDraw>,
-    S: LedPixelShape,
+    S: LedPixelShapeInstance,
     Data: DerefMut<Target = [u8]> + FromIterator<u8> + IntoIterator<Item = u8>,
+    D: LedStripDriver,
 {
-    driver: Ws2812Esp32RmtDriver<'d>,
-    data: Data,
-    brightness: u8,
-    changed: bool,
-    _phantom: PhantomData<(CDraw, CDev, S, Data)>,
+    frame: LedPixelFrameBuffer<'d, CDev, Data, D>,
+    shape: S,
+    _phantom: PhantomData<CDraw>,
 }
 
+#[cfg(not(target_os = "none"))]
 impl<'d, CDraw, CDev, S, Data> LedPixelDrawTarget<'d, CDraw, CDev, S, Data>
 where
     CDraw: RgbColor,
     CDev: LedPixelColor + From<CDraw>,
-    S: LedPixelShape,
+    S: LedPixelShapeInstance,
     Data: DerefMut<Target = [u8]> + FromIterator<u8> + IntoIterator<Item = u8>,
 {
     /// Create a new draw target.
     ///
     /// `channel` shall be different between different `pin`.
-    pub fn new<C: RmtChannel>(
+    pub fn new<C: TxRmtChannel>(
+        channel: impl Peripheral<P = C> + 'd,
+        pin: impl Peripheral<P = impl OutputPin> + 'd,
+    ) -> Result<Self, Ws2812Esp32RmtDriverError>
+    where
+        S: LedPixelShape + Default,
+    {
+        Self::new_with_shape(S::default(), channel, pin)
+    }
+
+    /// Create a new draw target of the `shape` instance, e.g. [`DynLedPixelShape`].
+    ///
+    /// `channel` shall be different between different `pin`.
+    pub fn new_with_shape<C: TxRmtChannel>(
+        shape: S,
         channel: impl Peripheral<P = C> + 'd,
         pin: impl Peripheral<P = impl OutputPin> + 'd,
     ) -> Result<Self, Ws2812Esp32RmtDriverError> {
-        let driver = Ws2812Esp32RmtDriver::<'d>::new(channel, pin)?;
-        let data = core::iter::repeat(0)
-            .take(S::pixel_len() * CDev::BPP)
-            .collect::<Data>();
         Ok(Self {
-            driver,
-            data,
-            brightness: u8::MAX,
-            changed: true,
+            frame: LedPixelFrameBuffer::new(channel, pin, shape.shape_pixel_len())?,
+            shape,
             _phantom: Default::default(),
         })
     }
+}
+
+impl<'d, CDraw, CDev, S, Data, D> LedPixelDrawTarget<'d, CDraw, CDev, S, Data, D>
+where
+    CDraw: RgbColor,
+    CDev: LedPixelColor + From<CDraw>,
+    S: LedPixelShapeInstance,
+    Data: DerefMut<Target = [u8]> + FromIterator<u8> + IntoIterator<Item = u8>,
+    D: LedStripDriver,
+{
+    /// Create a new draw target written through `driver`.
+    pub fn from_driver(driver: D) -> Self
+    where
+        S: LedPixelShape + Default,
+    {
+        Self::from_driver_with_shape(driver, S::default())
+    }
+
+    /// Create a new draw target of the `shape` instance written through `driver`.
+    pub fn from_driver_with_shape(driver: D, shape: S) -> Self {
+        Self {
+            frame: LedPixelFrameBuffer::from_driver(driver, shape.shape_pixel_len()),
+            shape,
+            _phantom: Default::default(),
+        }
+    }
+
+    /// Returns the LED pixel shape.
+    #[inline]
+    pub fn shape(&self) -> &S {
+        &self.shape
+    }
+
+    /// Returns the framebuffer.
+    #[inline]
+    pub fn frame_buffer(&self) -> &LedPixelFrameBuffer<'d, CDev, Data, D> {
+        &self.frame
+    }
+
+    /// Returns the framebuffer mutably, e.g. to set the LED pixels by index.
+    #[inline]
+    pub fn frame_buffer_mut(&mut self) -> &mut LedPixelFrameBuffer<'d, CDev, Data, D> {
+        &mut self.frame
+    }
 
     /// Set maximum brightness.
     /// Each channel values of the returned shall be scaled down to `(brightness + 1) / 256`.
     #[inline]
     pub fn set_brightness(&mut self, brightness: u8) {
-        self.brightness = brightness;
-        self.changed = true;
+        self.frame.set_brightness(brightness);
     }
 
     /// Returns maximum brightness.
     #[inline]
     pub fn brightness(&self) -> u8 {
-        self.brightness
+        self.frame.brightness()
+    }
+
+    /// Set the source of the ambient brightness polled on each [`flush()`].
+    ///
+    /// The ambient brightness is applied on top of [`brightness()`] when the framebuffer is
+    /// written, and the framebuffer is written again whenever the ambient brightness changes.
+    ///
+    /// [`flush()`]: #method.flush
+    /// [`brightness()`]: #method.brightness
+    #[cfg(feature = "alloc")]
+    pub fn set_brightness_source<B: BrightnessSource + Send + 'static>(&mut self, source: B) {
+        self.frame.set_brightness_source(source);
+    }
+
+    /// Remove the source of the ambient brightness and restore the full ambient brightness.
+    #[cfg(feature = "alloc")]
+    pub fn clear_brightness_source(&mut self) {
+        self.frame.clear_brightness_source();
+    }
+
+    /// Set the channel order of the LED pixels on the wire chosen at runtime, or `None` to write
+    /// the bytes in the order of `CDev`.
+    ///
+    /// The bytes are reordered on each [`flush()`].
+    ///
+    /// [`flush()`]: #method.flush
+    #[inline]
+    pub fn set_color_order(&mut self, color_order: Option<ColorOrder>) {
+        self.frame.set_color_order(color_order);
+    }
+
+    /// Returns the channel order of the LED pixels on the wire set by [`set_color_order()`].
+    ///
+    /// [`set_color_order()`]: #method.set_color_order
+    #[inline]
+    pub fn color_order(&self) -> Option<ColorOrder> {
+        self.frame.color_order()
+    }
+
+    /// Set the gamma correction of the channel values, e.g. [`GammaLut::CIE1931`] for the even
+    /// dimming at the low end, or `None` to write them as is.
+    ///
+    /// The channel values are corrected on each [`flush()`].
+    ///
+    /// [`flush()`]: #method.flush
+    #[inline]
+    pub fn set_gamma(&mut self, gamma: Option<GammaLut>) {
+        self.frame.set_gamma(gamma);
+    }
+
+    /// Returns the gamma correction set by [`set_gamma()`].
+    ///
+    /// [`set_gamma()`]: #method.set_gamma
+    #[inline]
+    pub fn gamma(&self) -> Option<&GammaLut> {
+        self.frame.gamma()
+    }
+
+    /// Set the color calibration of the LED pixels, or `None` to write the drawn colors as is.
+    ///
+    /// The colors are calibrated on each [`flush()`].
+    ///
+    /// [`flush()`]: #method.flush
+    #[inline]
+    pub fn set_calibration(&mut self, calibration: Option<ColorCalibration>) {
+        self.frame.set_calibration(calibration);
+    }
+
+    /// Returns the color calibration set by [`set_calibration()`].
+    ///
+    /// [`set_calibration()`]: #method.set_calibration
+    #[inline]
+    pub fn calibration(&self) -> Option<&ColorCalibration> {
+        self.frame.calibration()
+    }
+
+    /// Set the white point of the LED pixels, e.g. [`ColorTemperature::K2700`] to match the warm
+    /// ambient lighting, or `None` to write the drawn colors as is.
+    ///
+    /// The colors are tinted on each [`flush()`], after the color calibration.
+    ///
+    /// [`flush()`]: #method.flush
+    #[inline]
+    pub fn set_color_temperature(&mut self, color_temperature: Option<ColorTemperature>) {
+        self.frame.set_color_temperature(color_temperature);
+    }
+
+    /// Returns the white point set by [`set_color_temperature()`].
+    ///
+    /// [`set_color_temperature()`]: #method.set_color_temperature
+    #[inline]
+    pub fn color_temperature(&self) -> Option<ColorTemperature> {
+        self.frame.color_temperature()
+    }
+
+    /// Set the per-pixel calibration table, the red, green and blue scales of each LED pixel by
+    /// index, or `None` not to calibrate the LED pixels individually.
+    ///
+    /// Each channel of the `index`-th LED pixel is scaled down to `(scale + 1) / 256` of
+    /// `table[index]` on each [`flush()`]. The index is of the LED pixel on the strip, as mapped
+    /// by the shape `S`; the LED pixels beyond the table are not scaled.
+    ///
+    /// [`flush()`]: #method.flush
+    #[inline]
+    pub fn set_pixel_calibration(&mut self, table: Option<&'d [[u8; 3]]>) {
+        self.frame.set_pixel_calibration(table);
+    }
+
+    /// Returns the per-pixel calibration table set by [`set_pixel_calibration()`].
+    ///
+    /// [`set_pixel_calibration()`]: #method.set_pixel_calibration
+    #[inline]
+    pub fn pixel_calibration(&self) -> Option<&'d [[u8; 3]]> {
+        self.frame.pixel_calibration()
+    }
+
+    /// Set the map of the dead or absent LED pixels, or `None` to write all the LED pixels.
+    ///
+    /// The drawing coordinates stay as is, but on each [`flush()`] the dead LED pixels are
+    /// written black and the absent ones are skipped on the wire. The indices are of the LED
+    /// pixels on the strip, as mapped by the shape `S`.
+    ///
+    /// [`flush()`]: #method.flush
+    #[inline]
+    pub fn set_dead_pixels(&mut self, map: Option<DeadPixelMap<'d>>) {
+        self.frame.set_dead_pixels(map);
+    }
+
+    /// Returns the map of the dead or absent LED pixels set by [`set_dead_pixels()`].
+    ///
+    /// [`set_dead_pixels()`]: #method.set_dead_pixels
+    #[inline]
+    pub fn dead_pixels(&self) -> Option<DeadPixelMap<'d>> {
+        self.frame.dead_pixels()
+    }
+
+    /// Enables or disables the white channel extraction of the RGBW LED pixels.
+    ///
+    /// While enabled, the common component of the red, green and blue channels, `min(r, g, b)`,
+    /// of the drawn colors is moved into the white channel on each [`flush()`].
+    ///
+    /// [`flush()`]: #method.flush
+    #[inline]
+    pub fn set_white_extraction(&mut self, white_extraction: bool) {
+        self.frame.set_white_extraction(white_extraction);
+    }
+
+    /// Returns `true` if the white channel extraction is enabled by [`set_white_extraction()`].
+    ///
+    /// [`set_white_extraction()`]: #method.set_white_extraction
+    #[inline]
+    pub fn white_extraction(&self) -> bool {
+        self.frame.white_extraction()
+    }
+
+    /// Set the power budget of the LED pixels in milliamperes, or `None` not to limit.
+    ///
+    /// The brightness is scaled down on each [`flush()`] so that the current draw estimated with
+    /// [`current_model()`] stays within `limit_ma`.
+    ///
+    /// [`current_model()`]: #method.current_model
+    /// [`flush()`]: #method.flush
+    #[inline]
+    pub fn set_max_power_ma(&mut self, limit_ma: Option<u32>) {
+        self.frame.set_max_power_ma(limit_ma);
+    }
+
+    /// Returns the power budget set by [`set_max_power_ma()`].
+    ///
+    /// [`set_max_power_ma()`]: #method.set_max_power_ma
+    #[inline]
+    pub fn max_power_ma(&self) -> Option<u32> {
+        self.frame.max_power_ma()
+    }
+
+    /// Set the current draw model of the LED pixels, [`CurrentModel::WS2812`] by default.
+    #[inline]
+    pub fn set_current_model(&mut self, model: CurrentModel) {
+        self.frame.set_current_model(model);
+    }
+
+    /// Returns the current draw model set by [`set_current_model()`].
+    ///
+    /// [`set_current_model()`]: #method.set_current_model
+    #[inline]
+    pub fn current_model(&self) -> CurrentModel {
+        self.frame.current_model()
+    }
+
+    /// Returns the estimated current draw of the last frame written by [`flush()`] in
+    /// milliamperes.
+    ///
+    /// [`flush()`]: #method.flush
+    #[inline]
+    pub fn estimated_current_ma(&self) -> u32 {
+        self.frame.estimated_current_ma()
+    }
+
+    /// Returns the range of the LED pixels changed since the last write by [`flush()`], or
+    /// `None` if unchanged.
+    ///
+    /// Drawing extends the range by the LED pixels drawn, and the other changes, e.g.
+    /// [`clear()`] or [`set_brightness()`], mark all the LED pixels.
+    ///
+    /// [`flush()`]: #method.flush
+    /// [`clear()`]: #method.clear
+    /// [`set_brightness()`]: #method.set_brightness
+    #[inline]
+    pub fn dirty_range(&self) -> Option<Range<usize>> {
+        self.frame.dirty_range()
+    }
+
+    /// Returns the range of the LED pixels changed in the last frame written by [`flush()`],
+    /// e.g. for the diagnostics of the frames.
+    ///
+    /// [`flush()`]: #method.flush
+    #[inline]
+    pub fn last_dirty_range(&self) -> Option<Range<usize>> {
+        self.frame.last_dirty_range()
+    }
+
+    /// Enables or disables the temporal dithering of the channel values.
+    ///
+    /// The fractions of the channel values left by the 8-bit quantization of the brightness and
+    /// the gamma are carried into the following frames, so that the low-brightness gradients stay
+    /// smooth. While enabled, [`flush()`] writes on each call even if unchanged.
+    ///
+    /// [`flush()`]: #method.flush
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub fn set_dithering(&mut self, dithering: bool) {
+        self.frame.set_dithering(dithering);
+    }
+
+    /// Returns `true` if the temporal dithering is enabled by [`set_dithering()`].
+    ///
+    /// [`set_dithering()`]: #method.set_dithering
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub fn dithering(&self) -> bool {
+        self.frame.dithering()
+    }
+
+    /// Set the flag polled on each [`flush()`] to mark the draw target as changed, e.g. from an
+    /// interrupt handler.
+    ///
+    /// [`flush()`]: #method.flush
+    #[cfg(feature = "portable-atomic")]
+    pub fn set_changed_flag(&mut self, flag: &'d ChangedFlag) {
+        self.frame.set_changed_flag(flag);
+    }
+
+    /// Remove the flag set by [`set_changed_flag()`].
+    ///
+    /// [`set_changed_flag()`]: #method.set_changed_flag
+    #[cfg(feature = "portable-atomic")]
+    pub fn clear_changed_flag(&mut self) {
+        self.frame.clear_changed_flag();
     }
 
     /// Clear with black.
     /// Same operation as `clear(black_color)`.
-    pub fn clear_with_black(&mut self) -> Result<(), Ws2812Esp32RmtDriverError> {
-        self.data.fill(0);
-        self.changed = true;
+    pub fn clear_with_black(&mut self) -> Result<(), D::Error> {
+        self.frame.clear_with_black();
         Ok(())
     }
 
     /// Write changes from a framebuffer to the LED pixels
-    pub fn flush(&mut self) -> Result<(), Ws2812Esp32RmtDriverError> {
-        if self.changed {
-            self.driver.write_blocking(self.data.iter().copied())?;
-            self.changed = false;
-        }
-        Ok(())
+    pub fn flush(&mut self) -> Result<(), D::Error> {
+        self.frame.show()
+    }
+
+    /// Starts writing changes from a framebuffer to the LED pixels without waiting for the
+    /// transmission to complete, if the driver supports it.
+    ///
+    /// The next frame is drawn meanwhile. Call [`latch()`] to wait until the LED pixels show the
+    /// frame. See [`LedPixelFrameBuffer::show_nonblocking()`] for details.
+    ///
+    /// [`latch()`]: #method.latch
+    pub fn flush_nonblocking(&mut self) -> Result<(), D::Error> {
+        self.frame.show_nonblocking()
+    }
+
+    /// Waits until the frame written by [`flush_nonblocking()`] has been latched by the LED
+    /// pixels.
+    ///
+    /// [`flush_nonblocking()`]: #method.flush_nonblocking
+    pub fn latch(&mut self) -> Result<(), D::Error> {
+        self.frame.latch()
     }
 }
 
-impl<'d, CDraw, CDev, S, Data> OriginDimensions for LedPixelDrawTarget<'d, CDraw, CDev, S, Data>
+impl<'d, CDraw, CDev, S, Data, D> OriginDimensions
+    for LedPixelDrawTarget<'d, CDraw, CDev, S, Data, D>
 where
     CDraw: RgbColor,
     CDev: LedPixelColor + From<CDraw>,
-    S: LedPixelShape,
+    S: LedPixelShapeInstance,
     Data: DerefMut<Target = [u8]> + FromIterator<u8> + IntoIterator<Item = u8>,
+    D: LedStripDriver,
 {
     #[inline]
     fn size(&self) -> Size {
-        S::size()
+        self.shape.shape_size()
     }
 }
 
-impl<'d, CDraw, CDev, S, Data> DrawTarget for LedPixelDrawTarget<'d, CDraw, CDev, S, Data>
+impl<'d, CDraw, CDev, S, Data, D> DrawTarget for LedPixelDrawTarget<'d, CDraw, CDev, S, Data, D>
 where
     CDraw: RgbColor,
     CDev: LedPixelColor + From<CDraw>,
-    S: LedPixelShape,
+    S: LedPixelShapeInstance,
     Data: DerefMut<Target = [u8]> + FromIterator<u8> + IntoIterator<Item = u8>,
+    D: LedStripDriver,
 {
     type Color = CDraw;
-    type Error = Ws2812Esp32RmtDriverError;
+    type Error = D::Error;
 
     fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
     where
         I: IntoIterator<Item = Pixel<Self::Color>>,
     {
         for Pixel(point, color) in pixels {
-            if let Some(pixel_index) = S::pixel_index(point) {
-                let index = pixel_index * CDev::BPP;
-                let color_device = CDev::from(color).brightness(self.brightness);
-                for (offset, v) in color_device.as_ref().iter().enumerate() {
-                    self.data[index + offset] = *v;
-                }
-                self.changed = true;
+            if let Some(pixel_index) = self.shape.shape_pixel_index(point) {
+                self.frame.set_pixel(pixel_index, color);
             }
         }
         Ok(())
     }
 
     fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
-        let c = CDev::from(color).brightness(self.brightness);
-        for (index, v) in self.data.iter_mut().enumerate() {
-            *v = c.as_ref()[index % CDev::BPP];
-        }
-        self.changed = true;
+        self.frame.fill(color);
         Ok(())
     }
 }
@@ -267,6 +706,7 @@ pub type Ws2812DrawTarget<'d, S, Data = LedPixelDrawTargetData> =
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::driver::color::LedPixelColorGrbw32;
     use crate::mock::esp_idf_hal::peripherals::Peripherals;
 
     #[test]
@@ -312,6 +752,45 @@ mod test {
         assert_eq!(LedPixelStrip::<10>::pixel_index(Point::new(9, 1)), None);
     }
 
+    #[test]
+    fn test_dyn_led_pixel_shape() {
+        let shape = DynLedPixelShape::new(10, 5);
+        assert_eq!((shape.width(), shape.height()), (10, 5));
+        assert_eq!(shape.shape_pixel_len(), 50);
+        assert_eq!(shape.shape_size(), Size::new(10, 5));
+        for point in [(0, 0), (9, 4), (-1, 0), (0, -1), (10, 4), (9, 5)] {
+            let point = Point::new(point.0, point.1);
+            assert_eq!(
+                shape.shape_pixel_index(point),
+                LedPixelMatrix::<10, 5>::pixel_index(point)
+            );
+        }
+        assert_eq!(DynLedPixelShape::strip(10).shape_size(), Size::new(10, 1));
+    }
+
+    #[test]
+    fn test_ws2812draw_target_new_with_shape() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+
+        let mut draw = Ws2812DrawTarget::<DynLedPixelShape>::new_with_shape(
+            DynLedPixelShape::new(3, 2),
+            channel,
+            led_pin,
+        )
+        .unwrap();
+        assert_eq!(draw.frame.data.len(), 18);
+        assert_eq!(draw.size(), Size::new(3, 2));
+        assert_eq!(draw.shape().width(), 3);
+        draw.draw_iter([Pixel(Point::new(2, 1), Rgb888::new(0x01, 0x02, 0x03))])
+            .unwrap();
+        draw.draw_iter([Pixel(Point::new(3, 0), Rgb888::WHITE)])
+            .unwrap();
+        assert_eq!(draw.frame.data[15..18], [0x02, 0x01, 0x03]);
+        assert_eq!(draw.frame.data[..15], [0; 15]);
+    }
+
     #[test]
     fn test_ws2812draw_target_new() {
         let peripherals = Peripherals::take().unwrap();
@@ -319,9 +798,9 @@ mod test {
         let channel = peripherals.rmt.channel0;
 
         let draw = Ws2812DrawTarget::<LedPixelMatrix<10, 5>>::new(channel, led_pin).unwrap();
-        assert_eq!(draw.changed, true);
+        assert_eq!(draw.frame.changed, true);
         assert_eq!(
-            draw.data,
+            draw.frame.data,
             core::iter::repeat(0).take(150).collect::<Vec<_>>()
         );
     }
@@ -338,9 +817,9 @@ mod test {
             channel, led_pin,
         )
         .unwrap();
-        assert_eq!(draw.changed, true);
+        assert_eq!(draw.frame.changed, true);
         assert_eq!(
-            draw.data,
+            draw.frame.data,
             core::iter::repeat(0)
                 .take(150)
                 .collect::<heapless::Vec<_, VEC_CAPACITY>>()
@@ -365,30 +844,31 @@ mod test {
             .cloned(),
         )
         .unwrap();
-        assert_eq!(draw.changed, true);
-        assert_eq!(draw.data[0..3], [0x02, 0x01, 0x03]);
-        assert_eq!(draw.data[3..147], [0x00; 144]);
-        assert_eq!(draw.data[147..150], [0x05, 0x04, 0x06]);
-        draw.changed = false;
+        assert_eq!(draw.frame.changed, true);
+        assert_eq!(draw.frame.data[0..3], [0x02, 0x01, 0x03]);
+        assert_eq!(draw.frame.data[3..147], [0x00; 144]);
+        assert_eq!(draw.frame.data[147..150], [0x05, 0x04, 0x06]);
+        draw.frame.changed = false;
 
         draw.clear(Rgb888::new(0x07, 0x08, 0x0A)).unwrap();
-        assert_eq!(draw.changed, true);
+        assert_eq!(draw.frame.changed, true);
         assert_eq!(
-            draw.data,
+            draw.frame.data,
             core::iter::repeat([0x08, 0x07, 0x0A])
                 .take(50)
                 .flatten()
                 .collect::<Vec<_>>()
         );
-        draw.changed = false;
+        draw.frame.changed = false;
 
         draw.clear_with_black().unwrap();
-        assert_eq!(draw.changed, true);
-        assert_eq!(draw.data, [0x00; 150]);
-        draw.changed = false;
+        assert_eq!(draw.frame.changed, true);
+        assert_eq!(draw.frame.data, [0x00; 150]);
+        draw.frame.changed = false;
     }
 
     #[test]
+    #[cfg(not(any(mock_chip = "esp32c3", mock_chip = "esp32c6", mock_chip = "esp32h2")))]
     fn test_ws2812draw_target_flush() {
         let peripherals = Peripherals::take().unwrap();
         let led_pin = peripherals.pins.gpio2;
@@ -396,16 +876,224 @@ mod test {
 
         let mut draw = Ws2812DrawTarget::<LedPixelMatrix<10, 5>>::new(channel, led_pin).unwrap();
 
-        draw.changed = true;
-        draw.data.fill(0x01);
-        draw.driver.pixel_data = None;
+        draw.frame.changed = true;
+        draw.frame.data.fill(0x01);
+        draw.frame.driver.pixel_data = None;
+        draw.flush().unwrap();
+        assert_eq!(draw.frame.driver.pixel_data.unwrap(), draw.frame.data);
+        assert_eq!(draw.frame.changed, false);
+
+        draw.frame.driver.pixel_data = None;
         draw.flush().unwrap();
-        assert_eq!(draw.driver.pixel_data.unwrap(), draw.data);
-        assert_eq!(draw.changed, false);
+        assert_eq!(draw.frame.driver.pixel_data, None);
+        assert_eq!(draw.frame.changed, false);
+    }
+
+    #[test]
+    fn test_ws2812draw_target_flush_nonblocking() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio2;
+        let channel = peripherals.rmt.channel1;
+
+        let mut draw = Ws2812DrawTarget::<LedPixelMatrix<2, 1>>::new(channel, led_pin).unwrap();
+        draw.draw_iter([Pixel(Point::new(1, 0), Rgb888::new(0x01, 0x02, 0x03))])
+            .unwrap();
+        draw.flush_nonblocking().unwrap();
+        draw.clear_with_black().unwrap();
+        draw.latch().unwrap();
+        assert_eq!(
+            draw.frame_buffer().driver().pixel_data,
+            Some(vec![0x00, 0x00, 0x00, 0x02, 0x01, 0x03])
+        );
+    }
+
+    #[test]
+    fn test_ws2812draw_target_dirty_range() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio2;
+        let channel = peripherals.rmt.channel1;
 
-        draw.driver.pixel_data = None;
+        let mut draw = Ws2812DrawTarget::<LedPixelMatrix<10, 5>>::new(channel, led_pin).unwrap();
         draw.flush().unwrap();
-        assert_eq!(draw.driver.pixel_data, None);
-        assert_eq!(draw.changed, false);
+        assert_eq!(draw.dirty_range(), None);
+        assert_eq!(draw.last_dirty_range(), Some(0..50));
+
+        draw.draw_iter([
+            Pixel(Point::new(3, 1), Rgb888::WHITE),
+            Pixel(Point::new(1, 2), Rgb888::WHITE),
+            Pixel(Point::new(10, 4), Rgb888::WHITE), // out of matrix shape
+        ])
+        .unwrap();
+        assert_eq!(draw.dirty_range(), Some(13..22));
+        draw.flush().unwrap();
+        assert_eq!(draw.last_dirty_range(), Some(13..22));
+
+        draw.clear(Rgb888::BLACK).unwrap();
+        assert_eq!(draw.dirty_range(), Some(0..50));
+    }
+
+    #[test]
+    fn test_ws2812draw_target_brightness_source() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio3;
+        let channel = peripherals.rmt.channel1;
+
+        let mut draw = Ws2812DrawTarget::<LedPixelStrip<2>>::new(channel, led_pin).unwrap();
+        let mut ambient = [0x7F, 0x7F, 0x3F].into_iter();
+        draw.set_brightness_source(move || ambient.next().unwrap());
+        draw.frame.data.fill(0xFF);
+
+        draw.flush().unwrap();
+        assert_eq!(draw.frame.driver.pixel_data, Some(vec![0x7F; 6]));
+        assert_eq!(draw.frame.data, [0xFF; 6]);
+
+        draw.frame.driver.pixel_data = None;
+        draw.flush().unwrap();
+        assert_eq!(draw.frame.driver.pixel_data, None);
+
+        draw.flush().unwrap();
+        assert_eq!(draw.frame.driver.pixel_data, Some(vec![0x3F; 6]));
+
+        draw.clear_brightness_source();
+        draw.flush().unwrap();
+        assert_eq!(draw.frame.driver.pixel_data, Some(vec![0xFF; 6]));
+    }
+
+    #[test]
+    fn test_ws2812draw_target_gamma() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio4;
+        let channel = peripherals.rmt.channel1;
+
+        let mut draw = Ws2812DrawTarget::<LedPixelStrip<1>>::new(channel, led_pin).unwrap();
+        draw.set_gamma(Some(GammaLut::CIE1931));
+        assert_eq!(draw.gamma(), Some(&GammaLut::CIE1931));
+        draw.frame.data.copy_from_slice(&[0x80, 0x10, 0xFF]);
+
+        draw.flush().unwrap();
+        assert_eq!(draw.frame.driver.pixel_data, Some(vec![47, 2, 0xFF]));
+        assert_eq!(draw.frame.data, [0x80, 0x10, 0xFF]);
+    }
+
+    #[test]
+    fn test_ws2812draw_target_color_temperature() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio4;
+        let channel = peripherals.rmt.channel1;
+
+        let mut draw = Ws2812DrawTarget::<LedPixelStrip<1>>::new(channel, led_pin).unwrap();
+        draw.set_color_temperature(Some(ColorTemperature::K2700));
+        assert_eq!(draw.color_temperature(), Some(ColorTemperature::K2700));
+        draw.clear(Rgb888::WHITE).unwrap();
+
+        draw.flush().unwrap();
+        assert_eq!(draw.frame.driver.pixel_data, Some(vec![167, 255, 87]));
+        assert_eq!(draw.frame.data, [0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn test_ws2812draw_target_pixel_calibration() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio4;
+        let channel = peripherals.rmt.channel1;
+
+        let table = [[0xFF, 0x7F, 0xFF], [0x3F, 0xFF, 0xFF]];
+        let mut draw = Ws2812DrawTarget::<LedPixelMatrix<1, 2>>::new(channel, led_pin).unwrap();
+        draw.set_pixel_calibration(Some(&table));
+        assert_eq!(draw.pixel_calibration(), Some(&table[..]));
+        draw.clear(Rgb888::new(0x80, 0x80, 0x80)).unwrap();
+
+        draw.flush().unwrap();
+        assert_eq!(
+            draw.frame.driver.pixel_data,
+            Some(vec![0x40, 0x80, 0x80, 0x80, 0x20, 0x80])
+        );
+    }
+
+    #[test]
+    fn test_ws2812draw_target_dead_pixels() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio4;
+        let channel = peripherals.rmt.channel1;
+
+        let mut draw = Ws2812DrawTarget::<LedPixelMatrix<3, 1>>::new(channel, led_pin).unwrap();
+        draw.set_dead_pixels(Some(DeadPixelMap::absent(&[1])));
+        assert_eq!(draw.dead_pixels(), Some(DeadPixelMap::absent(&[1])));
+        draw.draw_iter([
+            Pixel(Point::new(0, 0), Rgb888::new(0x01, 0x00, 0x00)),
+            Pixel(Point::new(1, 0), Rgb888::new(0x02, 0x00, 0x00)),
+            Pixel(Point::new(2, 0), Rgb888::new(0x03, 0x00, 0x00)),
+        ])
+        .unwrap();
+
+        draw.flush().unwrap();
+        assert_eq!(draw.frame.driver.pixel_data, Some(vec![0, 1, 0, 0, 3, 0]));
+    }
+
+    #[test]
+    fn test_ws2812draw_target_white_extraction() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio4;
+        let channel = peripherals.rmt.channel1;
+
+        let mut draw = LedPixelDrawTarget::<Rgb888, LedPixelColorGrbw32, LedPixelStrip<1>>::new(
+            channel, led_pin,
+        )
+        .unwrap();
+        draw.set_white_extraction(true);
+        assert!(draw.white_extraction());
+        draw.set_calibration(Some(ColorCalibration::IDENTITY));
+        assert_eq!(draw.calibration(), Some(&ColorCalibration::IDENTITY));
+        draw.draw_iter([Pixel(Point::new(0, 0), Rgb888::new(0x10, 0x20, 0x30))])
+            .unwrap();
+
+        draw.flush().unwrap();
+        assert_eq!(
+            draw.frame.driver.pixel_data,
+            Some(vec![0x10, 0x00, 0x20, 0x10])
+        );
+    }
+
+    #[test]
+    fn test_ws2812draw_target_max_power() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio4;
+        let channel = peripherals.rmt.channel1;
+
+        let mut draw = Ws2812DrawTarget::<LedPixelStrip<2>>::new(channel, led_pin).unwrap();
+        draw.set_max_power_ma(Some(60));
+        draw.set_current_model(CurrentModel::WS2812);
+        assert_eq!(draw.max_power_ma(), Some(60));
+        assert_eq!(draw.current_model(), CurrentModel::WS2812);
+        draw.frame.data.copy_from_slice(&[0xFF; 6]);
+
+        draw.flush().unwrap();
+        assert_eq!(draw.frame.driver.pixel_data, Some(vec![127; 6]));
+        assert_eq!(draw.estimated_current_ma(), 60);
+    }
+
+    #[test]
+    fn test_ws2812draw_target_dithering() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio5;
+        let channel = peripherals.rmt.channel1;
+
+        let mut draw = Ws2812DrawTarget::<LedPixelStrip<1>>::new(channel, led_pin).unwrap();
+        draw.set_gamma(Some(GammaLut::LINEAR));
+        draw.set_brightness_source(|| 0x0F);
+        draw.set_dithering(true);
+        assert!(draw.dithering());
+        draw.frame.data.copy_from_slice(&[0x04, 0x08, 0x0C]);
+
+        // 0.25, 0.5 and 0.75 on average
+        let mut sums = [0; 3];
+        for _ in 0..4 {
+            draw.flush().unwrap();
+            let pixel_data = draw.frame.driver.pixel_data.take().unwrap();
+            sums.iter_mut()
+                .zip(pixel_data)
+                .for_each(|(sum, v)| *sum += v);
+        }
+        assert_eq!(sums, [1, 2, 3]);
     }
 }
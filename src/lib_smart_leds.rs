@@ -1,17 +1,29 @@
 //! smart-leds driver wrapper API.
 
-use crate::driver::color::{LedPixelColor, LedPixelColorGrb24, LedPixelColorImpl};
-use crate::driver::{Ws2812Esp32RmtDriver, Ws2812Esp32RmtDriverError};
+use crate::driver::brightness::scale;
+#[cfg(feature = "alloc")]
+use crate::driver::brightness::BrightnessSource;
+use crate::driver::calibration::{ColorCalibration, ColorTemperature};
+use crate::driver::color::{
+    ColorOrder, LedPixelColor, LedPixelColor16, LedPixelColorGrb24, LedPixelColorImpl,
+};
+use crate::driver::gamma::GammaLut;
+use crate::driver::power::CurrentModel;
+#[cfg(not(target_os = "none"))]
+use crate::driver::{channel::TxRmtChannel, Ws2812Esp32RmtDriver, Ws2812Esp32RmtDriverError};
+use crate::driver::{DefaultLedDriver, LedStripDriver};
+use crate::framebuffer::{write_with, FrameBufferData, LedPixelFrameBuffer};
 #[cfg(all(not(feature = "std"), feature = "alloc"))]
-use alloc::vec::Vec;
+use alloc::{boxed::Box, vec::Vec};
 use core::marker::PhantomData;
 #[cfg(feature = "alloc")]
 use smart_leds_trait::SmartLedsWrite;
-use smart_leds_trait::{RGB8, RGBW};
+use smart_leds_trait::{RGB, RGB8, RGBW};
 
-#[cfg(not(target_vendor = "espressif"))]
+#[cfg(not(any(target_vendor = "espressif", target_os = "none")))]
 use crate::mock::esp_idf_hal;
-use esp_idf_hal::{gpio::OutputPin, peripheral::Peripheral, rmt::RmtChannel};
+#[cfg(not(target_os = "none"))]
+use esp_idf_hal::{gpio::OutputPin, peripheral::Peripheral};
 
 /// 8-bit RGBW (RGB + white)
 pub type RGBW8 = RGBW<u8, u8>;
@@ -42,6 +54,18 @@ impl<
     }
 }
 
+impl From<RGB<u16>> for LedPixelColor16 {
+    fn from(x: RGB<u16>) -> Self {
+        Self::new_with_rgb(x.r, x.g, x.b)
+    }
+}
+
+impl From<RGBW<u16, u16>> for LedPixelColor16 {
+    fn from(x: RGBW<u16, u16>) -> Self {
+        Self::new_with_rgbw(x.r, x.g, x.b, x.a.0)
+    }
+}
+
 /// ws2812-like smart led driver wrapper providing smart-leds API
 ///
 /// This is a generalization to handle variants such as SK6812-RGBW 4-color LED.
@@ -66,14 +90,27 @@ impl<
 /// let pixels = std::iter::repeat(RGBW8 {r: 0, g: 0, b: 0, a: White(30)}).take(25);
 /// ws2812.write(pixels).unwrap();
 /// ```
-pub struct LedPixelEsp32Rmt<'d, CSmart, CDev>
+pub struct LedPixelEsp32Rmt<'d, CSmart, CDev, D = DefaultLedDriver<'d>>
 where
     CDev: LedPixelColor + From<CSmart>,
+    D: LedStripDriver,
 {
-    driver: Ws2812Esp32RmtDriver<'d>,
-    phantom: PhantomData<(CSmart, CDev)>,
+    driver: D,
+    #[cfg(feature = "alloc")]
+    brightness_source: Option<Box<dyn BrightnessSource + Send>>,
+    color_order: Option<ColorOrder>,
+    gamma: Option<GammaLut>,
+    calibration: Option<ColorCalibration>,
+    color_temperature: Option<ColorTemperature>,
+    white_extraction: bool,
+    max_power_ma: Option<u32>,
+    current_model: CurrentModel,
+    /// Sum of the channel values of the last written colors
+    channel_sum: u64,
+    phantom: PhantomData<(&'d (), CSmart, CDev)>,
 }
 
+#[cfg(not(target_os = "none"))]
 impl<'d, CSmart, CDev> LedPixelEsp32Rmt<'d, CSmart, CDev>
 where
     CDev: LedPixelColor + From<CSmart>,
@@ -81,15 +118,276 @@ where
     /// Create a new driver wrapper.
     ///
     /// `channel` shall be different between different `pin`.
-    pub fn new<C: RmtChannel>(
+    pub fn new<C: TxRmtChannel>(
         channel: impl Peripheral<P = C> + 'd,
         pin: impl Peripheral<P = impl OutputPin> + 'd,
     ) -> Result<Self, Ws2812Esp32RmtDriverError> {
         let driver = Ws2812Esp32RmtDriver::<'d>::new(channel, pin)?;
-        Ok(Self {
+        Ok(Self::from_driver(driver))
+    }
+}
+
+impl<'d, CSmart, CDev, D> LedPixelEsp32Rmt<'d, CSmart, CDev, D>
+where
+    CDev: LedPixelColor + From<CSmart>,
+    D: LedStripDriver,
+{
+    /// Create a new driver wrapper written through `driver`.
+    pub fn from_driver(driver: D) -> Self {
+        Self {
             driver,
+            #[cfg(feature = "alloc")]
+            brightness_source: None,
+            color_order: None,
+            gamma: None,
+            calibration: None,
+            color_temperature: None,
+            white_extraction: false,
+            max_power_ma: None,
+            current_model: CurrentModel::WS2812,
+            channel_sum: 0,
             phantom: Default::default(),
-        })
+        }
+    }
+
+    /// Set the channel order of the LED pixels on the wire chosen at runtime, or `None` to write
+    /// the bytes in the order of `CDev`.
+    ///
+    /// The bytes of the colors are reordered on each write.
+    pub fn set_color_order(&mut self, color_order: Option<ColorOrder>) {
+        self.color_order = color_order;
+    }
+
+    /// Returns the channel order of the LED pixels on the wire set by [`Self::set_color_order()`].
+    #[inline]
+    pub fn color_order(&self) -> Option<ColorOrder> {
+        self.color_order
+    }
+
+    /// Set the gamma correction of the channel values, e.g. [`GammaLut::CIE1931`] for the even
+    /// dimming at the low end, or `None` to write them as is.
+    ///
+    /// The channel values are corrected on each write, after the brightness is applied.
+    pub fn set_gamma(&mut self, gamma: Option<GammaLut>) {
+        self.gamma = gamma;
+    }
+
+    /// Returns the gamma correction set by [`Self::set_gamma()`].
+    #[inline]
+    pub fn gamma(&self) -> Option<&GammaLut> {
+        self.gamma.as_ref()
+    }
+
+    /// Set the color calibration of the LED pixels, e.g. [`ColorCalibration::TYPICAL_LED_STRIP`]
+    /// for the stronger green of the typical strips, or `None` to write the colors as is.
+    ///
+    /// The colors are calibrated on each write, before the brightness and the gamma are applied.
+    pub fn set_calibration(&mut self, calibration: Option<ColorCalibration>) {
+        self.calibration = calibration;
+    }
+
+    /// Returns the color calibration set by [`Self::set_calibration()`].
+    #[inline]
+    pub fn calibration(&self) -> Option<&ColorCalibration> {
+        self.calibration.as_ref()
+    }
+
+    /// Set the white point of the LED pixels, e.g. [`ColorTemperature::K2700`] to match the warm
+    /// ambient lighting, or `None` to write the colors as is.
+    ///
+    /// The colors are tinted on each write, after the color calibration.
+    pub fn set_color_temperature(&mut self, color_temperature: Option<ColorTemperature>) {
+        self.color_temperature = color_temperature;
+    }
+
+    /// Returns the white point set by [`Self::set_color_temperature()`].
+    #[inline]
+    pub fn color_temperature(&self) -> Option<ColorTemperature> {
+        self.color_temperature
+    }
+
+    /// Enables or disables the white channel extraction of the RGBW LED pixels.
+    ///
+    /// While enabled, the common component of the red, green and blue channels, `min(r, g, b)`,
+    /// of the written colors is moved into the white channel, so that the RGB colors light the
+    /// white LED. It has no effect on the LED pixels without the white channel.
+    pub fn set_white_extraction(&mut self, white_extraction: bool) {
+        self.white_extraction = white_extraction;
+    }
+
+    /// Returns `true` if the white channel extraction is enabled by
+    /// [`Self::set_white_extraction()`].
+    #[inline]
+    pub fn white_extraction(&self) -> bool {
+        self.white_extraction
+    }
+
+    /// Set the power budget of the LED pixels in milliamperes, or `None` not to limit.
+    ///
+    /// The current draw of the colors is estimated with [`Self::current_model()`] on each
+    /// write, and the brightness is scaled down so that the estimation stays within `limit_ma`.
+    /// [`Self::write_nocopy()`] does not limit the power as it does not look ahead the colors.
+    pub fn set_max_power_ma(&mut self, limit_ma: Option<u32>) {
+        self.max_power_ma = limit_ma;
+    }
+
+    /// Returns the power budget set by [`Self::set_max_power_ma()`].
+    #[inline]
+    pub fn max_power_ma(&self) -> Option<u32> {
+        self.max_power_ma
+    }
+
+    /// Set the current draw model of the LED pixels, [`CurrentModel::WS2812`] by default.
+    pub fn set_current_model(&mut self, model: CurrentModel) {
+        self.current_model = model;
+    }
+
+    /// Returns the current draw model set by [`Self::set_current_model()`].
+    #[inline]
+    pub fn current_model(&self) -> CurrentModel {
+        self.current_model
+    }
+
+    /// Returns the estimated current draw of the last written colors in milliamperes, with the
+    /// brightness, the power budget and the gamma applied.
+    ///
+    /// See [`CurrentModel::estimate_ma()`] for details.
+    #[inline]
+    pub fn estimated_current_ma(&self) -> u32 {
+        self.current_model.channel_sum_ma(self.channel_sum)
+    }
+
+    /// Set the source of the brightness polled on each write.
+    ///
+    /// Each channel value of the written colors is scaled down to `(brightness + 1) / 256`.
+    #[cfg(feature = "alloc")]
+    pub fn set_brightness_source<B: BrightnessSource + Send + 'static>(&mut self, source: B) {
+        self.brightness_source = Some(Box::new(source));
+    }
+
+    /// Remove the source of the brightness.
+    #[cfg(feature = "alloc")]
+    pub fn clear_brightness_source(&mut self) {
+        self.brightness_source = None;
+    }
+
+    /// Converts into the framebuffer of `pixel_len` LED pixels, which keeps the frame to set the
+    /// LED pixels by index and [`show()`] it.
+    ///
+    /// The brightness source, the color order, the gamma, the color calibration, the white point,
+    /// the white channel extraction and the power budget are carried over to the framebuffer.
+    ///
+    /// [`show()`]: LedPixelFrameBuffer::show
+    pub fn into_frame_buffer(
+        self,
+        pixel_len: usize,
+    ) -> LedPixelFrameBuffer<'d, CDev, FrameBufferData, D> {
+        let mut frame = LedPixelFrameBuffer::from_driver(self.driver, pixel_len);
+        frame.set_color_order(self.color_order);
+        frame.set_gamma(self.gamma);
+        frame.set_calibration(self.calibration);
+        frame.set_color_temperature(self.color_temperature);
+        frame.set_white_extraction(self.white_extraction);
+        frame.set_max_power_ma(self.max_power_ma);
+        frame.set_current_model(self.current_model);
+        #[cfg(feature = "alloc")]
+        {
+            frame.brightness_source = self.brightness_source;
+        }
+        frame
+    }
+
+    /// Starts writing pixel data from a color sequence to the driver without waiting for the
+    /// transmission to complete, if the driver supports it.
+    ///
+    /// Call [`latch()`] to wait until the LED pixels show the frame. See
+    /// [`LedStripDriver::write()`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an RMT driver error occurred.
+    ///
+    /// [`latch()`]: Self::latch
+    #[cfg(feature = "alloc")]
+    pub fn write_nonblocking<T, I>(&mut self, iterator: T) -> Result<(), D::Error>
+    where
+        T: IntoIterator<Item = I>,
+        I: Into<CSmart>,
+    {
+        self.write_with(iterator, true)
+    }
+
+    /// Waits until the frame written by [`Self::write_nonblocking()`] has been latched by the LED
+    /// pixels.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transmission failed.
+    pub fn latch(&mut self) -> Result<(), D::Error> {
+        self.driver.latch()
+    }
+
+    /// Writes pixel data from a color sequence to the driver, without waiting for the
+    /// transmission if `nonblocking`.
+    #[cfg(feature = "alloc")]
+    fn write_with<T, I>(&mut self, iterator: T, nonblocking: bool) -> Result<(), D::Error>
+    where
+        T: IntoIterator<Item = I>,
+        I: Into<CSmart>,
+    {
+        let order = self.color_order;
+        let filter = self.filter();
+        let pixel_data = iterator.into_iter().fold(Vec::new(), |mut vec, color| {
+            let c = filter(CDev::from(color.into()));
+            match order {
+                Some(order) => vec.extend(order.pixel_bytes(&c)),
+                None => vec.extend_from_slice(c.as_ref()),
+            }
+            vec
+        });
+        let mut brightness = self.poll_brightness();
+        if let Some(limit_ma) = self.max_power_ma {
+            brightness = self
+                .current_model
+                .limit_brightness(&pixel_data, brightness, limit_ma);
+        }
+        let correct = correction(brightness, self.gamma.as_ref());
+        let pixel_data = pixel_data.into_iter().map(correct);
+        let mut channel_sum = 0u64;
+        let pixel_data = pixel_data.inspect(|&v| channel_sum += u64::from(v));
+        write_with(&mut self.driver, pixel_data, nonblocking)?;
+        self.channel_sum = channel_sum;
+        Ok(())
+    }
+
+    /// Returns the filter of the colors with the color calibration, the white point and the
+    /// white channel extraction.
+    fn filter<C: LedPixelColor>(&self) -> impl Fn(C) -> C + Copy + Send {
+        let calibrations = [
+            self.calibration,
+            self.color_temperature.map(ColorCalibration::from),
+        ];
+        let white_extraction = self.white_extraction;
+        move |color| {
+            let color = calibrations
+                .into_iter()
+                .flatten()
+                .fold(color, |color, calibration| calibration.apply_color(&color));
+            if white_extraction {
+                color.extract_white()
+            } else {
+                color
+            }
+        }
+    }
+
+    /// Polls the brightness of the next frame.
+    fn poll_brightness(&mut self) -> u8 {
+        #[cfg(feature = "alloc")]
+        if let Some(source) = self.brightness_source.as_mut() {
+            return source.brightness();
+        }
+        u8::MAX
     }
 }
 
@@ -101,37 +399,57 @@ impl<
         const G_ORDER: usize,
         const B_ORDER: usize,
         const W_ORDER: usize,
-    > LedPixelEsp32Rmt<'d, CSmart, LedPixelColorImpl<N, R_ORDER, G_ORDER, B_ORDER, W_ORDER>>
+        D,
+    > LedPixelEsp32Rmt<'d, CSmart, LedPixelColorImpl<N, R_ORDER, G_ORDER, B_ORDER, W_ORDER>, D>
 where
     LedPixelColorImpl<N, R_ORDER, G_ORDER, B_ORDER, W_ORDER>: From<CSmart>,
+    D: LedStripDriver,
 {
     /// Writes pixel data from a color sequence to the driver without data copy
     ///
     /// # Errors
     ///
     /// Returns an error if an RMT driver error occurred.
-    pub fn write_nocopy<T, I>(&mut self, iterator: T) -> Result<(), Ws2812Esp32RmtDriverError>
+    pub fn write_nocopy<T, I>(&mut self, iterator: T) -> Result<(), D::Error>
     where
         T: IntoIterator<Item = I>,
         I: Into<CSmart>,
         <T as IntoIterator>::IntoIter: Send,
     {
-        self.driver
-            .write_blocking(iterator.into_iter().flat_map(|color| {
-                let c =
-                    LedPixelColorImpl::<N, R_ORDER, G_ORDER, B_ORDER, W_ORDER>::from(color.into());
-                c.0
-            }))?;
+        let correct = correction(self.poll_brightness(), self.gamma.as_ref());
+        let filter = self.filter();
+        let colors = iterator.into_iter().map(move |color| {
+            filter(LedPixelColorImpl::<N, R_ORDER, G_ORDER, B_ORDER, W_ORDER>::from(color.into()))
+        });
+        let mut channel_sum = 0u64;
+        let sum = |&v: &u8| channel_sum += u64::from(v);
+        if let Some(order) = self.color_order {
+            let pixel_data = colors.flat_map(move |c| order.pixel_bytes(&c).map(correct));
+            self.driver.write_blocking(pixel_data.inspect(sum))?;
+        } else {
+            let pixel_data = colors.flat_map(move |c| c.0.map(correct));
+            self.driver.write_blocking(pixel_data.inspect(sum))?;
+        }
+        self.channel_sum = channel_sum;
         Ok(())
     }
 }
 
+/// Returns the correction of the channel values with `brightness` and `gamma`.
+fn correction(brightness: u8, gamma: Option<&GammaLut>) -> impl Fn(u8) -> u8 + Copy + Send + '_ {
+    move |v| {
+        let v = scale(v, brightness);
+        gamma.map_or(v, |gamma| gamma.apply(v))
+    }
+}
+
 #[cfg(feature = "alloc")]
-impl<'d, CSmart, CDev> SmartLedsWrite for LedPixelEsp32Rmt<'d, CSmart, CDev>
+impl<'d, CSmart, CDev, D> SmartLedsWrite for LedPixelEsp32Rmt<'d, CSmart, CDev, D>
 where
     CDev: LedPixelColor + From<CSmart>,
+    D: LedStripDriver,
 {
-    type Error = Ws2812Esp32RmtDriverError;
+    type Error = D::Error;
     type Color = CSmart;
 
     /// Writes pixel data from a color sequence to the driver
@@ -144,12 +462,7 @@ where
         T: IntoIterator<Item = I>,
         I: Into<Self::Color>,
     {
-        let pixel_data = iterator.into_iter().fold(Vec::new(), |mut vec, color| {
-            vec.extend_from_slice(CDev::from(color.into()).as_ref());
-            vec
-        });
-        self.driver.write_blocking(pixel_data.into_iter())?;
-        Ok(())
+        self.write_with(iterator, false)
     }
 }
 
@@ -179,6 +492,7 @@ pub type Ws2812Esp32Rmt<'d> = LedPixelEsp32Rmt<'d, RGB8, LedPixelColorGrb24>;
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::driver::color::LedPixelColorGrbw32;
     use crate::mock::esp_idf_hal::peripherals::Peripherals;
 
     #[test]
@@ -194,4 +508,224 @@ mod test {
         ws2812.write(sample_data.iter().cloned()).unwrap();
         assert_eq!(ws2812.driver.pixel_data.unwrap(), &expected_values);
     }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_write_nonblocking() {
+        let sample_data = [RGB8::new(0x00, 0x01, 0x02), RGB8::new(0x03, 0x04, 0x05)];
+
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+
+        let mut ws2812 = Ws2812Esp32Rmt::new(channel, led_pin).unwrap();
+        ws2812.write_nonblocking(sample_data).unwrap();
+        ws2812.latch().unwrap();
+        assert_eq!(
+            ws2812.driver.pixel_data.unwrap(),
+            [0x01, 0x00, 0x02, 0x04, 0x03, 0x05]
+        );
+        assert_eq!(ws2812.channel_sum, 0x0F);
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_brightness_source() {
+        let sample_data = [RGB8::new(0xFF, 0x80, 0x00)];
+
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+
+        let mut ws2812 = Ws2812Esp32Rmt::new(channel, led_pin).unwrap();
+        ws2812.set_brightness_source(|| 0x7F);
+        ws2812.write(sample_data.iter().cloned()).unwrap();
+        assert_eq!(
+            ws2812.driver.pixel_data.as_deref(),
+            Some(&[0x40, 0x7F, 0x00][..])
+        );
+        ws2812.write_nocopy(sample_data.iter().cloned()).unwrap();
+        assert_eq!(
+            ws2812.driver.pixel_data.as_deref(),
+            Some(&[0x40, 0x7F, 0x00][..])
+        );
+
+        ws2812.clear_brightness_source();
+        ws2812.write(sample_data.iter().cloned()).unwrap();
+        assert_eq!(
+            ws2812.driver.pixel_data.as_deref(),
+            Some(&[0x80, 0xFF, 0x00][..])
+        );
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_color_order() {
+        let sample_data = [RGB8::new(0x01, 0x02, 0x03)];
+
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+
+        let mut ws2812 = Ws2812Esp32Rmt::new(channel, led_pin).unwrap();
+        ws2812.set_color_order(Some(ColorOrder::Bgr));
+        ws2812.write(sample_data.iter().cloned()).unwrap();
+        assert_eq!(
+            ws2812.driver.pixel_data.as_deref(),
+            Some(&[0x03, 0x02, 0x01][..])
+        );
+        ws2812.write_nocopy(sample_data.iter().cloned()).unwrap();
+        assert_eq!(
+            ws2812.driver.pixel_data.as_deref(),
+            Some(&[0x03, 0x02, 0x01][..])
+        );
+
+        let mut frame = ws2812.into_frame_buffer(1);
+        assert_eq!(frame.color_order(), Some(ColorOrder::Bgr));
+        frame.set_pixel(0, RGB8::new(0x04, 0x05, 0x06));
+        frame.show().unwrap();
+        assert_eq!(
+            frame.driver.pixel_data.as_deref(),
+            Some(&[0x06, 0x05, 0x04][..])
+        );
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_gamma() {
+        let sample_data = [RGB8::new(0x80, 0xFF, 0x00)];
+
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+
+        let mut ws2812 = Ws2812Esp32Rmt::new(channel, led_pin).unwrap();
+        ws2812.set_gamma(Some(GammaLut::GAMMA_2_2));
+        assert_eq!(ws2812.gamma(), Some(&GammaLut::GAMMA_2_2));
+        ws2812.write(sample_data.iter().cloned()).unwrap();
+        assert_eq!(
+            ws2812.driver.pixel_data.as_deref(),
+            Some(&[0xFF, 56, 0][..])
+        );
+        ws2812.write_nocopy(sample_data.iter().cloned()).unwrap();
+        assert_eq!(
+            ws2812.driver.pixel_data.as_deref(),
+            Some(&[0xFF, 56, 0][..])
+        );
+
+        let frame = ws2812.into_frame_buffer(1);
+        assert_eq!(frame.gamma(), Some(&GammaLut::GAMMA_2_2));
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_calibration() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+
+        let mut ws2812 = Ws2812Esp32Rmt::new(channel, led_pin).unwrap();
+        let calibration = ColorCalibration::with_scale(0xFF, 0x7F, 0x3F);
+        ws2812.set_calibration(Some(calibration));
+        assert_eq!(ws2812.calibration(), Some(&calibration));
+        ws2812.write([RGB8::new(0x80, 0x80, 0x80)]).unwrap();
+        assert_eq!(
+            ws2812.driver.pixel_data.as_deref(),
+            Some(&[0x40, 0x80, 0x20][..])
+        );
+        ws2812.write_nocopy([RGB8::new(0xFF, 0xFF, 0xFF)]).unwrap();
+        assert_eq!(
+            ws2812.driver.pixel_data.as_deref(),
+            Some(&[0x7F, 0xFF, 0x3F][..])
+        );
+
+        ws2812.set_calibration(None);
+        ws2812.set_color_temperature(Some(ColorTemperature::K4000));
+        assert_eq!(ws2812.color_temperature(), Some(ColorTemperature::K4000));
+        ws2812.write([RGB8::new(0xFF, 0xFF, 0xFF)]).unwrap();
+        assert_eq!(
+            ws2812.driver.pixel_data.as_deref(),
+            Some(&[206, 255, 166][..])
+        );
+
+        let frame = ws2812.into_frame_buffer(1);
+        assert_eq!(frame.color_temperature(), Some(ColorTemperature::K4000));
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_white_extraction() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+
+        let mut sk6812 =
+            LedPixelEsp32Rmt::<RGB8, LedPixelColorGrbw32>::new(channel, led_pin).unwrap();
+        sk6812.set_white_extraction(true);
+        assert!(sk6812.white_extraction());
+        sk6812.write([RGB8::new(0x10, 0x20, 0x30)]).unwrap();
+        assert_eq!(
+            sk6812.driver.pixel_data.as_deref(),
+            Some(&[0x10, 0x00, 0x20, 0x10][..])
+        );
+        sk6812.write_nocopy([RGB8::new(0x30, 0x20, 0x10)]).unwrap();
+        assert_eq!(
+            sk6812.driver.pixel_data.as_deref(),
+            Some(&[0x10, 0x20, 0x00, 0x10][..])
+        );
+
+        let frame = sk6812.into_frame_buffer(1);
+        assert!(frame.white_extraction());
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_max_power() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+
+        let mut ws2812 = Ws2812Esp32Rmt::new(channel, led_pin).unwrap();
+        ws2812.set_max_power_ma(Some(300));
+        assert_eq!(ws2812.max_power_ma(), Some(300));
+        ws2812
+            .write(core::iter::repeat_n(RGB8::new(0xFF, 0xFF, 0xFF), 10))
+            .unwrap();
+        assert_eq!(ws2812.driver.pixel_data.as_deref(), Some(&[127; 30][..]));
+        assert_eq!(ws2812.estimated_current_ma(), 299);
+
+        ws2812.set_current_model(CurrentModel { channel_ma: 10 });
+        assert_eq!(ws2812.current_model(), CurrentModel { channel_ma: 10 });
+        ws2812
+            .write(core::iter::repeat_n(RGB8::new(0xFF, 0xFF, 0xFF), 10))
+            .unwrap();
+        assert_eq!(ws2812.driver.pixel_data.as_deref(), Some(&[0xFF; 30][..]));
+        assert_eq!(ws2812.estimated_current_ma(), 300);
+
+        ws2812
+            .write_nocopy(core::iter::repeat_n(RGB8::new(0xFF, 0x00, 0x00), 10))
+            .unwrap();
+        assert_eq!(ws2812.estimated_current_ma(), 100);
+
+        let frame = ws2812.into_frame_buffer(1);
+        assert_eq!(frame.max_power_ma(), Some(300));
+        assert_eq!(frame.current_model(), CurrentModel { channel_ma: 10 });
+    }
+
+    #[test]
+    fn test_ws2812_esp32_rmt_into_frame_buffer() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+
+        let mut ws2812 = Ws2812Esp32Rmt::new(channel, led_pin).unwrap();
+        ws2812.set_brightness_source(|| 0x7F);
+        let mut frame = ws2812.into_frame_buffer(2);
+        frame.set_pixel(1, RGB8::new(0xFF, 0x80, 0x00));
+        frame.show().unwrap();
+        assert_eq!(
+            frame.driver.pixel_data.as_deref(),
+            Some(&[0x00, 0x00, 0x00, 0x40, 0x7F, 0x00][..])
+        );
+
+        assert!(frame.set_pixel16(0, RGB::<u16>::new(0xFFFF, 0x8000, 0x0000)));
+        frame.show().unwrap();
+        assert_eq!(
+            frame.driver.pixel_data.as_deref(),
+            Some(&[0x40, 0x7F, 0x00, 0x40, 0x7F, 0x00][..])
+        );
+    }
 }
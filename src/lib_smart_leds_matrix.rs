@@ -0,0 +1,88 @@
+//! smart-leds-matrix backend API.
+//!
+//! The `smart-leds-matrix` crate renders embedded-graphics onto any `SmartLedsWrite` writer
+//! through its `Layout`. [`LedPixelEsp32Rmt`] is such a writer, so it serves as the backend of
+//! `SmartLedMatrix` as is; [`ShapeLayout`] adapts the [`LedPixelShape`]s of this crate, e.g. the
+//! ones of [`led_pixel_map!`](crate::led_pixel_map) and [`crate::topology`], to the `Layout`, so
+//! that the rendering layer built on `SmartLedMatrix` is kept while switching to this driver.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use embedded_graphics::pixelcolor::Rgb888;
+//! use embedded_graphics::prelude::*;
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::lib_embedded_graphics::LedPixelMatrix;
+//! use ws2812_esp32_rmt_driver::lib_smart_leds_matrix::{ShapeLayout, Ws2812SmartLedMatrix};
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32Rmt;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let writer = Ws2812Esp32Rmt::new(peripherals.rmt.channel0, peripherals.pins.gpio27).unwrap();
+//! let mut matrix: Ws2812SmartLedMatrix<_, 25> =
+//!     Ws2812SmartLedMatrix::new(writer, ShapeLayout::<LedPixelMatrix<5, 5>>::new());
+//! matrix.set_brightness(20);
+//! Pixel(Point::new(2, 2), Rgb888::WHITE).draw(&mut matrix).unwrap();
+//! matrix.flush().unwrap();
+//! ```
+
+use crate::lib_embedded_graphics::LedPixelShape;
+use crate::lib_smart_leds::Ws2812Esp32Rmt;
+use core::marker::PhantomData;
+use embedded_graphics_core::geometry::{Point, Size};
+use smart_leds_matrix::layout::Layout;
+use smart_leds_matrix::SmartLedMatrix;
+
+/// `Layout` of `smart-leds-matrix` of an [`LedPixelShape`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ShapeLayout<S: LedPixelShape> {
+    phantom: PhantomData<S>,
+}
+
+impl<S: LedPixelShape> ShapeLayout<S> {
+    /// Creates the layout of the shape `S`.
+    pub const fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<S: LedPixelShape> Default for ShapeLayout<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: LedPixelShape> Layout for ShapeLayout<S> {
+    #[inline]
+    fn map(&self, p: Point) -> Option<usize> {
+        S::pixel_index(p)
+    }
+
+    #[inline]
+    fn size(&self) -> Size {
+        S::size()
+    }
+}
+
+/// `SmartLedMatrix` of `N` LED pixels of the layout `L` written by [`Ws2812Esp32Rmt`]
+///
+/// Use [`ShapeLayout`] for the shapes of this crate, or the layouts of `smart-leds-matrix`.
+pub type Ws2812SmartLedMatrix<'d, L, const N: usize> = SmartLedMatrix<Ws2812Esp32Rmt<'d>, L, N>;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lib_embedded_graphics::LedPixelMatrix;
+
+    #[test]
+    fn test_shape_layout() {
+        let layout = ShapeLayout::<LedPixelMatrix<5, 4>>::new();
+        assert_eq!(layout.size(), Size::new(5, 4));
+        assert_eq!(layout.map(Point::new(1, 2)), Some(11));
+        assert_eq!(layout.map(Point::new(5, 0)), None);
+    }
+}
@@ -1,5 +1,47 @@
 //! Mock modules for local testing
 
+pub(crate) mod recorder;
+#[cfg(feature = "embedded-graphics-core")]
+mod render;
+pub mod replay;
+#[cfg(feature = "simulator")]
+pub mod simulator;
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+#[cfg(feature = "embedded-graphics-core")]
+pub mod tee;
+pub mod time;
+#[cfg(feature = "waveform")]
+pub mod waveform;
+
+/// Name of the chip emulated by the mock.
+///
+/// Enable one of the `mock-esp32*` features to restrict the RMT channels and GPIO pins of the mock
+/// to the TX-capable ones of the chip. Otherwise, all the channels and pins of all the chips are
+/// available. When several of them are enabled, e.g. by `--all-features`, the first one in the
+/// order of `esp32`, `esp32s2`, `esp32s3`, `esp32c3`, `esp32c6` and `esp32h2` is emulated.
+#[cfg(mock_chip = "esp32")]
+pub const CHIP: &str = "esp32";
+#[cfg(mock_chip = "esp32s2")]
+pub const CHIP: &str = "esp32s2";
+#[cfg(mock_chip = "esp32s3")]
+pub const CHIP: &str = "esp32s3";
+#[cfg(mock_chip = "esp32c3")]
+pub const CHIP: &str = "esp32c3";
+#[cfg(mock_chip = "esp32c6")]
+pub const CHIP: &str = "esp32c6";
+#[cfg(mock_chip = "esp32h2")]
+pub const CHIP: &str = "esp32h2";
+#[cfg(not(any(
+    mock_chip = "esp32",
+    mock_chip = "esp32s2",
+    mock_chip = "esp32s3",
+    mock_chip = "esp32c3",
+    mock_chip = "esp32c6",
+    mock_chip = "esp32h2"
+)))]
+pub const CHIP: &str = "generic";
+
 /// Mock module for `esp_idf_hal`
 pub mod esp_idf_hal {
     pub use super::esp_idf_sys as sys;
@@ -9,8 +51,33 @@ pub mod esp_idf_hal {
         use super::peripheral::Peripheral;
         use paste::paste;
 
+        /// Mock trait for `esp_idf_hal::gpio::Pin`.
+        pub trait Pin {
+            /// Returns the GPIO number.
+            fn pin(&self) -> i32;
+        }
+
         /// Mock trait for `esp_idf_hal::gpio::OutputPin`.
-        pub trait OutputPin {}
+        pub trait OutputPin: Pin {}
+
+        /// Mock struct for `esp_idf_hal::gpio::AnyOutputPin`
+        #[derive(Debug)]
+        pub struct AnyOutputPin {
+            pin: i32,
+        }
+
+        impl Pin for AnyOutputPin {
+            fn pin(&self) -> i32 {
+                self.pin
+            }
+        }
+        impl OutputPin for AnyOutputPin {}
+        impl Peripheral for AnyOutputPin {
+            type P = AnyOutputPin;
+            unsafe fn clone_unchecked(&mut self) -> Self::P {
+                Self { pin: self.pin }
+            }
+        }
 
         macro_rules! define_pins_struct {
             ($($num:expr),*) => {
@@ -25,12 +92,6 @@ pub mod esp_idf_hal {
                 }
             }
         }
-        define_pins_struct!(
-            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23,
-            24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45,
-            46, 47, 48
-        );
-
         impl Pins {
             pub(super) fn new() -> Self {
                 Default::default()
@@ -51,15 +112,70 @@ pub mod esp_idf_hal {
                         //    }
                         //}
 
+                        impl Pin for [<Gpio $num>] {
+                            fn pin(&self) -> i32 {
+                                $num
+                            }
+                        }
                         impl OutputPin for [<Gpio $num>] {}
                         impl Peripheral for [<Gpio $num>] {
                             type P=[<Gpio $num>];
+                            unsafe fn clone_unchecked(&mut self) -> Self::P {
+                                Self {}
+                            }
+                        }
+                        impl From<[<Gpio $num>]> for AnyOutputPin {
+                            fn from(_: [<Gpio $num>]) -> Self {
+                                Self { pin: $num }
+                            }
                         }
                     )*
                 }
             };
         }
-        define_gpio_structs!(
+
+        macro_rules! define_pins {
+            ($($num:expr),*) => {
+                define_pins_struct!($($num),*);
+                define_gpio_structs!($($num),*);
+            };
+        }
+        #[cfg(mock_chip = "esp32")]
+        define_pins!(
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 21, 22, 23, 25,
+            26, 27, 32, 33
+        );
+        #[cfg(mock_chip = "esp32s2")]
+        define_pins!(
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 26, 27,
+            28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45
+        );
+        #[cfg(mock_chip = "esp32s3")]
+        define_pins!(
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 26, 27,
+            28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48
+        );
+        #[cfg(mock_chip = "esp32c3")]
+        define_pins!(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21);
+        #[cfg(mock_chip = "esp32c6")]
+        define_pins!(
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23,
+            24, 25, 26, 27, 28, 29, 30
+        );
+        #[cfg(mock_chip = "esp32h2")]
+        define_pins!(
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23,
+            24, 25, 26, 27
+        );
+        #[cfg(not(any(
+            mock_chip = "esp32",
+            mock_chip = "esp32s2",
+            mock_chip = "esp32s3",
+            mock_chip = "esp32c3",
+            mock_chip = "esp32c6",
+            mock_chip = "esp32h2"
+        )))]
+        define_pins!(
             0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23,
             24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45,
             46, 47, 48
@@ -72,6 +188,13 @@ pub mod esp_idf_hal {
         pub trait Peripheral: Sized {
             /// Peripheral singleton type
             type P;
+
+            /// Returns the peripheral singleton.
+            ///
+            /// # Safety
+            ///
+            /// Same as the original one; there is no actual unsafety in the mock.
+            unsafe fn clone_unchecked(&mut self) -> Self::P;
         }
     }
 
@@ -79,16 +202,53 @@ pub mod esp_idf_hal {
     pub mod peripherals {
         use super::gpio;
         use super::rmt;
+        use super::spi;
+        use super::sys::EspError;
+        use std::cell::Cell;
+
+        std::thread_local! {
+            /// Whether `Peripherals` has been taken.
+            ///
+            /// This is tracked per thread so that tests running in parallel do not interfere.
+            static TAKEN: Cell<bool> = const { Cell::new(false) };
+        }
 
         /// Mock struct for `esp_idf_hal::peripherals::Peripherals`
         pub struct Peripherals {
             pub pins: gpio::Pins,
             pub rmt: rmt::RMT,
+            pub spi2: spi::SPI2,
+            #[cfg(not(any(mock_chip = "esp32c3", mock_chip = "esp32c6", mock_chip = "esp32h2")))]
+            pub spi3: spi::SPI3,
         }
 
         impl Peripherals {
-            pub fn take() -> Result<Self, super::sys::EspError> {
-                Ok(Self::new())
+            /// Takes the peripherals.
+            ///
+            /// As the original one, this succeeds only once and fails on subsequent calls
+            /// until [`Self::reset_taken()`] is called.
+            pub fn take() -> Result<Self, EspError> {
+                if TAKEN.with(|taken| taken.replace(true)) {
+                    Err(EspError())
+                } else {
+                    Ok(Self::new())
+                }
+            }
+
+            /// Creates `Peripherals` instance regardless of whether it has been taken.
+            ///
+            /// # Safety
+            ///
+            /// Same as the original one; there is no actual unsafety in the mock.
+            pub unsafe fn steal() -> Self {
+                Self::new()
+            }
+
+            /// Makes [`Self::take()`] succeed again.
+            ///
+            /// This function is only available in the mock.
+            pub fn reset_taken() {
+                TAKEN.with(|taken| taken.set(false));
             }
 
             // Create `Peripherals` instance.
@@ -100,6 +260,13 @@ pub mod esp_idf_hal {
                 Self {
                     pins: gpio::Pins::new(),
                     rmt: rmt::RMT::new(),
+                    spi2: spi::SPI2::new(),
+                    #[cfg(not(any(
+                        mock_chip = "esp32c3",
+                        mock_chip = "esp32c6",
+                        mock_chip = "esp32h2"
+                    )))]
+                    spi3: spi::SPI3::new(),
                 }
             }
         }
@@ -110,9 +277,18 @@ pub mod esp_idf_hal {
         use super::gpio::OutputPin;
         use super::peripheral::Peripheral;
         use super::sys::EspError;
+        use super::units::Hertz;
         use config::TransmitConfig;
         use core::marker::PhantomData;
         use paste::paste;
+        use std::cell::RefCell;
+
+        std::thread_local! {
+            /// RMT channels and GPIO pins used by the live `TxRmtDriver`s.
+            ///
+            /// This is tracked per thread so that tests running in parallel do not interfere.
+            static IN_USE: RefCell<Vec<(u32, i32)>> = const { RefCell::new(Vec::new()) };
+        }
 
         macro_rules! define_channel_structs {
             ($($num:expr),*) => {
@@ -130,27 +306,45 @@ pub mod esp_idf_hal {
 
                         impl Peripheral for [<CHANNEL $num>] {
                             type P=[<CHANNEL $num>];
+                            unsafe fn clone_unchecked(&mut self) -> Self::P {
+                                Self {}
+                            }
                         }
 
-                        impl RmtChannel for [<CHANNEL $num>] {}
+                        impl RmtChannel for [<CHANNEL $num>] {
+                            fn channel() -> u32 {
+                                $num
+                            }
+                        }
                     )*
+
+                    /// mock struct for `esp_idf_hal::rmt::RMT`
+                    ///
+                    /// Only the TX-capable channels of the emulated chip are available.
+                    #[derive(Debug, Default)]
+                    pub struct RMT {
+                        $(
+                            pub [<channel $num>]: [<CHANNEL $num>],
+                        )*
+                    }
                 }
             };
         }
+        #[cfg(mock_chip = "esp32")]
+        define_channel_structs!(0, 1, 2, 3, 4, 5, 6, 7);
+        #[cfg(any(mock_chip = "esp32s2", mock_chip = "esp32s3"))]
+        define_channel_structs!(0, 1, 2, 3);
+        #[cfg(any(mock_chip = "esp32c3", mock_chip = "esp32c6", mock_chip = "esp32h2"))]
+        define_channel_structs!(0, 1);
+        #[cfg(not(any(
+            mock_chip = "esp32",
+            mock_chip = "esp32s2",
+            mock_chip = "esp32s3",
+            mock_chip = "esp32c3",
+            mock_chip = "esp32c6",
+            mock_chip = "esp32h2"
+        )))]
         define_channel_structs!(0, 1, 2, 3, 4, 5, 6, 7);
-
-        /// mock struct for `esp_idf_hal::rmt::RMT`
-        #[derive(Debug, Default)]
-        pub struct RMT {
-            pub channel0: CHANNEL0,
-            pub channel1: CHANNEL1,
-            pub channel2: CHANNEL2,
-            pub channel3: CHANNEL3,
-            pub channel4: CHANNEL4,
-            pub channel5: CHANNEL5,
-            pub channel6: CHANNEL6,
-            pub channel7: CHANNEL7,
-        }
 
         impl RMT {
             pub fn new() -> Self {
@@ -159,41 +353,204 @@ pub mod esp_idf_hal {
         }
 
         /// Mock trait fo `esp_idf_hal::rmt::RmtChannel`
-        pub trait RmtChannel {}
+        pub trait RmtChannel {
+            /// Returns the RMT channel number.
+            fn channel() -> u32;
+        }
 
         //pub type RmtTransmitConfig = config::TransmitConfig;
 
+        /// Source clock frequency of the mock RMT (APB clock)
+        pub const SOURCE_CLOCK_HZ: u32 = 80_000_000;
+
         /// Mock module for `esp_idf_hal::rmt::TxRmtDriver`
         pub struct TxRmtDriver<'d> {
+            channel: u32,
+            pin: i32,
+            config: TransmitConfig,
             _p: PhantomData<&'d mut ()>,
         }
 
         impl<'d> TxRmtDriver<'d> {
             /// Initialize the mock of `TxRmtDriver`.
-            /// No argument is used in this mock.
+            /// The channel number, the GPIO number and `config` are recorded in this mock.
+            ///
+            /// # Errors
+            ///
+            /// Returns an error if the channel or the pin is used by another live driver,
+            /// as the original one fails to install the driver on the channel in use.
             pub fn new<C: RmtChannel>(
                 _channel: impl Peripheral<P = C> + 'd,
-                _pin: impl Peripheral<P = impl OutputPin> + 'd,
-                _config: &TransmitConfig,
+                mut pin: impl Peripheral<P = impl OutputPin> + 'd,
+                config: &TransmitConfig,
             ) -> Result<Self, EspError> {
-                Ok(Self { _p: PhantomData })
+                let channel = C::channel();
+                let pin = unsafe { pin.clone_unchecked() }.pin();
+                IN_USE.with(|in_use| {
+                    let mut in_use = in_use.borrow_mut();
+                    if in_use.iter().any(|&(c, p)| c == channel || p == pin) {
+                        return Err(EspError());
+                    }
+                    in_use.push((channel, pin));
+                    Ok(())
+                })?;
+                Ok(Self {
+                    channel,
+                    pin,
+                    config: config.clone(),
+                    _p: PhantomData,
+                })
+            }
+
+            /// Returns the RMT channel number.
+            pub fn channel(&self) -> u32 {
+                self.channel
+            }
+
+            /// Returns the GPIO number used to initialize this mock.
+            ///
+            /// This function is only available in the mock.
+            pub fn pin(&self) -> i32 {
+                self.pin
+            }
+
+            /// Returns the counter clock frequency derived from [`SOURCE_CLOCK_HZ`] and the clock
+            /// divider.
+            pub fn counter_clock(&self) -> Result<Hertz, EspError> {
+                Ok(Hertz(
+                    SOURCE_CLOCK_HZ / self.config.clock_divider.max(1) as u32,
+                ))
+            }
+
+            /// Mock of `rmt_set_clk_div()`: sets the clock divider of the channel.
+            pub fn set_clock_divider(&mut self, divider: u8) -> Result<(), EspError> {
+                if divider == 0 {
+                    return Err(EspError());
+                }
+                self.config.clock_divider = divider;
+                Ok(())
+            }
+
+            /// Returns the configuration used to initialize this mock.
+            ///
+            /// This function is only available in the mock.
+            pub fn config(&self) -> &TransmitConfig {
+                &self.config
+            }
+        }
+
+        impl Drop for TxRmtDriver<'_> {
+            /// Releases the channel and the pin.
+            fn drop(&mut self) {
+                let used = (self.channel, self.pin);
+                IN_USE.with(|in_use| in_use.borrow_mut().retain(|&entry| entry != used));
             }
         }
 
         /// Mock module for `esp_idf_hal::rmt::config`
         pub mod config {
             /// Mock struct for `esp_idf_hal::rmt::config::TransmitConfig`
-            #[derive(Debug, Clone)]
-            pub struct TransmitConfig {}
+            #[derive(Debug, Clone, PartialEq, Eq)]
+            pub struct TransmitConfig {
+                pub clock_divider: u8,
+                pub mem_block_num: u8,
+            }
 
             impl TransmitConfig {
                 pub fn new() -> Self {
-                    Self {}
+                    Self {
+                        clock_divider: 80,
+                        mem_block_num: 1,
+                    }
                 }
-                #[allow(unused_mut)]
-                pub fn clock_divider(mut self, _divider: u8) -> Self {
+                pub fn clock_divider(mut self, divider: u8) -> Self {
+                    self.clock_divider = divider;
                     self
                 }
+                pub fn mem_block_num(mut self, mem_block_num: u8) -> Self {
+                    self.mem_block_num = mem_block_num;
+                    self
+                }
+            }
+
+            impl Default for TransmitConfig {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+        }
+    }
+
+    /// Mock module for `esp_idf_hal::spi`
+    pub mod spi {
+        use super::peripheral::Peripheral;
+        use paste::paste;
+
+        /// Mock trait for `esp_idf_hal::spi::SpiAnyPins`
+        pub trait SpiAnyPins {
+            /// Returns the SPI host number.
+            fn device() -> u32;
+        }
+
+        macro_rules! define_spi_structs {
+            ($($num:expr),*) => {
+                paste! {
+                    $(
+                        #[doc = concat!("Mock struct for `esp_idf_hal::spi::SPI", stringify!($num) ,"`")]
+                        #[derive(Debug, Default)]
+                        pub struct [<SPI $num>] {}
+
+                        impl [<SPI $num>] {
+                            pub fn new() -> Self {
+                                Self {}
+                            }
+                        }
+
+                        impl Peripheral for [<SPI $num>] {
+                            type P=[<SPI $num>];
+                            unsafe fn clone_unchecked(&mut self) -> Self::P {
+                                Self {}
+                            }
+                        }
+
+                        impl SpiAnyPins for [<SPI $num>] {
+                            fn device() -> u32 {
+                                $num - 1
+                            }
+                        }
+                    )*
+                }
+            };
+        }
+        #[cfg(any(mock_chip = "esp32c3", mock_chip = "esp32c6", mock_chip = "esp32h2"))]
+        define_spi_structs!(2);
+        #[cfg(not(any(mock_chip = "esp32c3", mock_chip = "esp32c6", mock_chip = "esp32h2")))]
+        define_spi_structs!(2, 3);
+    }
+
+    /// Mock module for `esp_idf_hal::units`
+    pub mod units {
+        use core::fmt;
+
+        /// Mock struct for `esp_idf_hal::units::Hertz`
+        #[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+        pub struct Hertz(pub u32);
+
+        impl From<u32> for Hertz {
+            fn from(val: u32) -> Self {
+                Self(val)
+            }
+        }
+
+        impl From<Hertz> for u32 {
+            fn from(val: Hertz) -> Self {
+                val.0
+            }
+        }
+
+        impl fmt::Display for Hertz {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{} Hz", self.0)
             }
         }
     }
@@ -217,3 +574,71 @@ pub mod esp_idf_sys {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::esp_idf_hal::peripherals::Peripherals;
+    use super::esp_idf_hal::rmt::{config::TransmitConfig, TxRmtDriver};
+    use super::esp_idf_hal::units::Hertz;
+
+    #[test]
+    fn test_peripherals_take() {
+        let peripherals = Peripherals::take();
+        assert!(peripherals.is_ok());
+        assert!(Peripherals::take().is_err());
+
+        Peripherals::reset_taken();
+        assert!(Peripherals::take().is_ok());
+        assert!(Peripherals::take().is_err());
+    }
+
+    #[test]
+    fn test_tx_rmt_driver_channel_and_pin() {
+        let peripherals = Peripherals::take().unwrap();
+        let config = TransmitConfig::new();
+        let tx =
+            TxRmtDriver::new(peripherals.rmt.channel1, peripherals.pins.gpio21, &config).unwrap();
+        assert_eq!(tx.channel(), 1);
+        assert_eq!(tx.pin(), 21);
+    }
+
+    #[test]
+    fn test_tx_rmt_driver_config() {
+        let peripherals = Peripherals::take().unwrap();
+        let config = TransmitConfig::new().clock_divider(2).mem_block_num(4);
+        let tx =
+            TxRmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0, &config).unwrap();
+        assert_eq!(tx.channel(), 0);
+        assert_eq!(tx.pin(), 0);
+        assert_eq!(tx.config(), &config);
+        assert_eq!(tx.config().mem_block_num, 4);
+        assert_eq!(tx.counter_clock().unwrap(), Hertz(40_000_000));
+    }
+
+    #[test]
+    fn test_tx_rmt_driver_in_use() {
+        let config = TransmitConfig::new();
+        let new_tx = |channel: u32, pin: i32| {
+            let peripherals = Peripherals::new();
+            let (rmt, pins) = (peripherals.rmt, peripherals.pins);
+            match (channel, pin) {
+                (0, 0) => TxRmtDriver::new(rmt.channel0, pins.gpio0, &config),
+                (0, 1) => TxRmtDriver::new(rmt.channel0, pins.gpio1, &config),
+                (1, 0) => TxRmtDriver::new(rmt.channel1, pins.gpio0, &config),
+                (1, 1) => TxRmtDriver::new(rmt.channel1, pins.gpio1, &config),
+                _ => unreachable!(),
+            }
+        };
+
+        let tx = new_tx(0, 0).unwrap();
+        assert!(new_tx(0, 1).is_err());
+        assert!(new_tx(1, 0).is_err());
+        let other = new_tx(1, 1).unwrap();
+        assert!(new_tx(0, 1).is_err());
+
+        drop(tx);
+        assert!(new_tx(0, 0).is_ok());
+        drop(other);
+        assert!(new_tx(0, 1).is_ok());
+    }
+}
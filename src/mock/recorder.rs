@@ -0,0 +1,139 @@
+//! Frame recording shared by the mock drivers.
+//!
+//! Every mock driver backend embeds a [`FrameRecorder`] so that it provides the same recording
+//! API, implemented by [`impl_frame_recording!`]: error injection, strip length assertion and the
+//! frame history. The RMT mock also puts the frames on the virtual timeline.
+
+use super::esp_idf_sys::EspError;
+use super::time::VirtualTimeline;
+
+/// Recording state of a mock driver.
+#[derive(Debug, Default)]
+pub(crate) struct FrameRecorder {
+    /// Number of frames to be written successfully before the injected failure.
+    pub(crate) fail_after: Option<usize>,
+    /// Error to be returned by the next write after the byte offset.
+    pub(crate) injected_error: Option<(usize, EspError)>,
+    /// Expected strip length: the number of LED pixels and byte per pixel.
+    pub(crate) expected_strip_len: Option<(usize, usize)>,
+    /// Virtual-time state
+    pub(crate) timeline: VirtualTimeline,
+    /// Pixel data of the written frames, if the history is recorded.
+    pub(crate) history: Option<Vec<Vec<u8>>>,
+}
+
+impl FrameRecorder {
+    /// Records a frame: returns the injected error if any, or the collected pixel data.
+    ///
+    /// The frame is not put on the virtual timeline; use [`Self::timeline`] for it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the byte count differs from the expected strip length.
+    pub(crate) fn record<T>(&mut self, mut pixel_sequence: T) -> Result<Vec<u8>, EspError>
+    where
+        T: Iterator<Item = u8>,
+    {
+        if let Some((byte_offset, error)) = self.take_injected_error() {
+            pixel_sequence.by_ref().take(byte_offset).for_each(drop);
+            return Err(error);
+        }
+        let pixel_data = pixel_sequence.collect::<Vec<_>>();
+        if let Some((pixel_len, bpp)) = self.expected_strip_len {
+            assert_eq!(
+                pixel_data.len(),
+                pixel_len * bpp,
+                "written frame shall be {} pixels x {} bytes",
+                pixel_len,
+                bpp
+            );
+        }
+        if let Some(history) = self.history.as_mut() {
+            history.push(pixel_data.clone());
+        }
+        Ok(pixel_data)
+    }
+
+    /// Returns the error to be returned by the current write and its byte offset, if any.
+    fn take_injected_error(&mut self) -> Option<(usize, EspError)> {
+        if let Some(injected) = self.injected_error.take() {
+            return Some(injected);
+        }
+        match self.fail_after {
+            Some(0) => {
+                self.fail_after = None;
+                Some((0, EspError()))
+            }
+            Some(n) => {
+                self.fail_after = Some(n - 1);
+                None
+            }
+            None => None,
+        }
+    }
+}
+
+/// Implements the recording API on a mock driver embedding a [`FrameRecorder`] as `recorder`.
+///
+/// The generic parameters of the `impl` are given in braces, followed by the driver type.
+macro_rules! impl_frame_recording {
+    ({ $($generics:tt)* } $driver:ty) => {
+        impl<$($generics)*> $driver {
+            /// Makes the write after `n_frames` successful writes fail with an error.
+            ///
+            /// The failure happens once and then the driver works normally again.
+            ///
+            /// This function is only available in the mock.
+            pub fn set_fail_after(&mut self, n_frames: usize) {
+                self.recorder.fail_after = Some(n_frames);
+            }
+
+            /// Makes the next write fail with `error`.
+            ///
+            /// This function is only available in the mock.
+            pub fn inject_error(&mut self, error: $crate::mock::esp_idf_sys::EspError) {
+                self.inject_error_at(0, error);
+            }
+
+            /// Makes the next write fail with `error` after `byte_offset` bytes of the pixel data
+            /// are consumed.
+            ///
+            /// This function is only available in the mock.
+            pub fn inject_error_at(
+                &mut self,
+                byte_offset: usize,
+                error: $crate::mock::esp_idf_sys::EspError,
+            ) {
+                self.recorder.injected_error = Some((byte_offset, error));
+            }
+
+            /// Sets the expected strip length, `pixel_len` LED pixels of `bpp` bytes.
+            ///
+            /// Writes of a frame with the different byte count panic afterwards,
+            /// so that layout and length bugs are caught in the tests.
+            ///
+            /// This function is only available in the mock.
+            pub fn expect_strip_len(&mut self, pixel_len: usize, bpp: usize) {
+                self.recorder.expected_strip_len = Some((pixel_len, bpp));
+            }
+
+            /// Starts recording the pixel data of the written frames, discarding the recorded
+            /// ones.
+            ///
+            /// The recorded frames can be replayed with [`crate::mock::replay::Recording`].
+            ///
+            /// This function is only available in the mock.
+            pub fn record_history(&mut self) {
+                self.recorder.history = Some(Vec::new());
+            }
+
+            /// Returns the pixel data of the frames written since `record_history()`.
+            ///
+            /// This function is only available in the mock.
+            pub fn frame_history(&self) -> &[Vec<u8>] {
+                self.recorder.history.as_deref().unwrap_or_default()
+            }
+        }
+    };
+}
+pub(crate) use impl_frame_recording;
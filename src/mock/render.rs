@@ -0,0 +1,32 @@
+//! Rendering of mock frames laid out with a LED pixel shape.
+
+use crate::driver::color::LedPixelColor;
+#[cfg(any(feature = "snapshot", feature = "simulator"))]
+use crate::lib_embedded_graphics::LedPixelShape;
+#[cfg(any(feature = "snapshot", feature = "simulator"))]
+use embedded_graphics_core::geometry::Point;
+
+/// Decodes the color of the `index`-th LED pixel from `pixel_data`.
+///
+/// Returns black if the pixel is out of the pixel data.
+pub(crate) fn pixel_rgb<CDev: LedPixelColor>(pixel_data: &[u8], index: usize) -> (u8, u8, u8) {
+    let mut color = CDev::new_with_rgb(0, 0, 0);
+    if let Some(chunk) = pixel_data.get(index * CDev::BPP..(index + 1) * CDev::BPP) {
+        color.as_mut().copy_from_slice(chunk);
+    }
+    (color.r(), color.g(), color.b())
+}
+
+/// Returns the color at `point` of the shape `S`.
+///
+/// Returns black if there is no LED pixel at `point`.
+#[cfg(any(feature = "snapshot", feature = "simulator"))]
+pub(crate) fn render_point<CDev, S>(pixel_data: &[u8], point: Point) -> (u8, u8, u8)
+where
+    CDev: LedPixelColor,
+    S: LedPixelShape,
+{
+    S::pixel_index(point)
+        .map(|index| pixel_rgb::<CDev>(pixel_data, index))
+        .unwrap_or_default()
+}
@@ -0,0 +1,277 @@
+//! Replay of recorded frames.
+//!
+//! A [`Recording`] is a sequence of frames taken from the history of the mock driver
+//! ([`Ws2812Esp32RmtDriver::frame_history()`]) or loaded from a file, e.g. captured earlier from
+//! a real session on the hardware. Replaying it through a driver or a draw target enables
+//! regression tests of the whole pipeline.
+//!
+//! [`Ws2812Esp32RmtDriver::frame_history()`]: crate::driver::Ws2812Esp32RmtDriver::frame_history
+//!
+//! The file has one frame per line, each written as the hexadecimal pixel data.
+//!
+//! # Examples
+//!
+//! ```
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::mock::replay::Recording;
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//!
+//! let recording = Recording::read("00ff00\n0000ff\n".as_bytes()).unwrap();
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+//! driver.record_history();
+//! recording.replay(&mut driver).unwrap();
+//! assert_eq!(driver.frame_history(), recording.frames);
+//! ```
+
+use crate::driver::LedStripWrite;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+#[cfg(feature = "embedded-graphics-core")]
+use super::render::pixel_rgb;
+#[cfg(feature = "embedded-graphics-core")]
+use crate::driver::color::LedPixelColor;
+#[cfg(feature = "embedded-graphics-core")]
+use crate::lib_embedded_graphics::LedPixelShape;
+#[cfg(feature = "embedded-graphics-core")]
+use embedded_graphics_core::{draw_target::DrawTarget, geometry::Point, pixelcolor::Rgb888, Pixel};
+
+/// Sequence of recorded frames
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recording {
+    /// Pixel data of the frames in the order of writing
+    pub frames: Vec<Vec<u8>>,
+}
+
+impl Recording {
+    /// Creates a recording of the frames.
+    pub fn new(frames: Vec<Vec<u8>>) -> Self {
+        Self { frames }
+    }
+
+    /// Reads the recording of one hexadecimal frame per line.
+    ///
+    /// An empty line is an empty frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading from `reader` failed or a line is not a hexadecimal byte sequence.
+    pub fn read<R: BufRead>(reader: R) -> io::Result<Self> {
+        let frames = reader
+            .lines()
+            .map(|line| parse_hex(line?.trim_end()))
+            .collect::<io::Result<_>>()?;
+        Ok(Self { frames })
+    }
+
+    /// Writes the recording as one hexadecimal frame per line.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `writer` failed.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for frame in &self.frames {
+            for v in frame {
+                write!(writer, "{:02x}", v)?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    /// Loads the recording from a file.
+    ///
+    /// See [`Self::read()`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file could not be opened or read.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Saves the recording to a file.
+    ///
+    /// See [`Self::write()`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file could not be created or written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Writes the frames to `writer` in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error returned by `writer`.
+    pub fn replay<W: LedStripWrite>(&self, writer: &mut W) -> Result<(), W::Error> {
+        for frame in &self.frames {
+            writer.write_blocking(frame.iter().copied())?;
+        }
+        Ok(())
+    }
+
+    /// Draws the frames to `target` in order, calling `on_frame` after each frame is drawn.
+    ///
+    /// * `CDev` - the LED pixel color type used to decode the pixel data
+    /// * `S` - the LED pixel shape used to locate the LED pixels
+    ///
+    /// Typically `on_frame` flushes the target, e.g. [`LedPixelDrawTarget::flush()`].
+    /// Note that the brightness of the target is applied to the drawn colors.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error returned by `target` or `on_frame`.
+    ///
+    /// [`LedPixelDrawTarget::flush()`]: crate::lib_embedded_graphics::LedPixelDrawTarget::flush
+    #[cfg(feature = "embedded-graphics-core")]
+    pub fn replay_draw<CDev, S, D, F>(
+        &self,
+        target: &mut D,
+        mut on_frame: F,
+    ) -> Result<(), D::Error>
+    where
+        CDev: LedPixelColor,
+        S: LedPixelShape,
+        D: DrawTarget<Color = Rgb888>,
+        F: FnMut(&mut D) -> Result<(), D::Error>,
+    {
+        let size = S::size();
+        let points = (0..size.height as i32)
+            .flat_map(|y| (0..size.width as i32).map(move |x| Point::new(x, y)))
+            .filter_map(|point| S::pixel_index(point).map(|index| (index, point)))
+            .collect::<Vec<_>>();
+        for frame in &self.frames {
+            target.draw_iter(points.iter().map(|&(index, point)| {
+                let (r, g, b) = pixel_rgb::<CDev>(frame, index);
+                Pixel(point, Rgb888::new(r, g, b))
+            }))?;
+            on_frame(target)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a hexadecimal byte sequence.
+fn parse_hex(line: &str) -> io::Result<Vec<u8>> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("invalid frame: {line}"));
+    if line.len() % 2 != 0 || !line.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    (0..line.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&line[i..i + 2], 16).map_err(|_| invalid()))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::Ws2812Esp32RmtDriver;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+
+    #[test]
+    fn test_recording_read_write() {
+        let recording = Recording::read("00Ff10\n\n0a\r\n".as_bytes()).unwrap();
+        assert_eq!(
+            recording.frames,
+            [vec![0x00, 0xFF, 0x10], vec![], vec![0x0A]]
+        );
+
+        let mut text = Vec::new();
+        recording.write(&mut text).unwrap();
+        assert_eq!(text, b"00ff10\n\n0a\n");
+
+        assert!(Recording::read("0".as_bytes()).is_err());
+        assert!(Recording::read("0g".as_bytes()).is_err());
+        assert!(Recording::read("+f".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_recording_replay() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        driver.record_history();
+        driver.write_blocking([0x01, 0x02].into_iter()).unwrap();
+        driver.write_blocking([0x03, 0x04].into_iter()).unwrap();
+        let recording = Recording::new(driver.frame_history().to_vec());
+
+        driver.record_history();
+        driver.set_fail_after(1);
+        assert!(recording.replay(&mut driver).is_err());
+        assert_eq!(driver.frame_history(), [vec![0x01, 0x02]]);
+
+        driver.record_history();
+        recording.replay(&mut driver).unwrap();
+        assert_eq!(driver.frame_history(), recording.frames);
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-graphics-core")]
+    fn test_recording_replay_draw() {
+        use crate::driver::color::LedPixelColorGrb24;
+        use crate::lib_embedded_graphics::LedPixelMatrix;
+        use core::convert::Infallible;
+        use embedded_graphics_core::geometry::{OriginDimensions, Size};
+
+        /// Draw target recording the drawn pixels.
+        struct PixelLog(Vec<Pixel<Rgb888>>);
+
+        impl OriginDimensions for PixelLog {
+            fn size(&self) -> Size {
+                Size::new(2, 2)
+            }
+        }
+
+        impl DrawTarget for PixelLog {
+            type Color = Rgb888;
+            type Error = Infallible;
+
+            fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+            where
+                I: IntoIterator<Item = Pixel<Self::Color>>,
+            {
+                self.0.extend(pixels);
+                Ok(())
+            }
+        }
+
+        let recording = Recording::new(vec![
+            vec![0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09],
+            vec![0x10, 0x20, 0x30],
+        ]);
+        let mut log = PixelLog(Vec::new());
+        let mut drawn = Vec::new();
+        recording
+            .replay_draw::<LedPixelColorGrb24, LedPixelMatrix<2, 2>, _, _>(&mut log, |log| {
+                drawn.push(log.0.len());
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(drawn, [4, 8]);
+        assert_eq!(
+            log.0[0],
+            Pixel(Point::new(0, 0), Rgb888::new(0x02, 0x01, 0x03))
+        );
+        assert_eq!(
+            log.0[2],
+            Pixel(Point::new(0, 1), Rgb888::new(0x08, 0x07, 0x09))
+        );
+        assert_eq!(log.0[3], Pixel(Point::new(1, 1), Rgb888::new(0, 0, 0)));
+        assert_eq!(
+            log.0[4],
+            Pixel(Point::new(0, 0), Rgb888::new(0x20, 0x10, 0x30))
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Desktop simulator window of mock frames.
+//!
+//! Renders pixel data written to the mock driver into a window laid out with a
+//! [`LedPixelShape`], so LED animations can be developed on the host before flashing.
+//!
+//! Note that some platforms (e.g. macOS) require the window to be created and updated
+//! on the main thread.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use ws2812_esp32_rmt_driver::driver::color::LedPixelColorGrb24;
+//! use ws2812_esp32_rmt_driver::lib_embedded_graphics::LedPixelMatrix;
+//! use ws2812_esp32_rmt_driver::mock::simulator::SimulatorWindow;
+//!
+//! let mut window =
+//!     SimulatorWindow::<LedPixelColorGrb24, LedPixelMatrix<5, 5>>::new("LED", 16).unwrap();
+//! while window.is_open() {
+//!     // draw and flush to the mock driver, and then update the window with its pixel data
+//!     window.update(&[0x00, 0x1E, 0x00]).unwrap();
+//! }
+//! ```
+
+use super::render::render_point;
+use crate::driver::color::LedPixelColor;
+use crate::driver::Ws2812Esp32RmtDriver;
+use crate::lib_embedded_graphics::LedPixelShape;
+use core::marker::PhantomData;
+use embedded_graphics_core::geometry::Point;
+use minifb::{Window, WindowOptions};
+
+pub use minifb::Error;
+
+/// Simulator window displaying the LED pixels.
+///
+/// * `CDev` - the LED pixel color type used to decode the pixel data
+/// * `S` - the LED pixel shape
+pub struct SimulatorWindow<CDev, S>
+where
+    CDev: LedPixelColor,
+    S: LedPixelShape,
+{
+    window: Window,
+    scale: usize,
+    buffer: Vec<u32>,
+    _phantom: PhantomData<(CDev, S)>,
+}
+
+impl<CDev, S> SimulatorWindow<CDev, S>
+where
+    CDev: LedPixelColor,
+    S: LedPixelShape,
+{
+    /// Opens a simulator window.
+    /// Each LED pixel is rendered as a `scale`x`scale` square.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the window could not be opened.
+    pub fn new(title: &str, scale: usize) -> Result<Self, Error> {
+        let (width, height) = Self::window_size(scale);
+        let window = Window::new(title, width, height, WindowOptions::default())?;
+        Ok(Self {
+            window,
+            scale,
+            buffer: vec![0; width * height],
+            _phantom: Default::default(),
+        })
+    }
+
+    /// Returns the window size in pixels.
+    fn window_size(scale: usize) -> (usize, usize) {
+        let size = S::size();
+        (size.width as usize * scale, size.height as usize * scale)
+    }
+
+    /// Returns `true` if the window is still open.
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.window.is_open()
+    }
+
+    /// Renders `pixel_data` to the window.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the window could not be updated.
+    pub fn update(&mut self, pixel_data: &[u8]) -> Result<(), Error> {
+        let (width, height) = Self::window_size(self.scale);
+        for (i, v) in self.buffer.iter_mut().enumerate() {
+            let point = Point::new(
+                (i % width / self.scale) as i32,
+                (i / width / self.scale) as i32,
+            );
+            let (r, g, b) = render_point::<CDev, S>(pixel_data, point);
+            *v = u32::from_be_bytes([0, r, g, b]);
+        }
+        self.window.update_with_buffer(&self.buffer, width, height)
+    }
+
+    /// Renders the pixel data last written to the mock `driver` to the window.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the window could not be updated.
+    pub fn update_from_driver(&mut self, driver: &Ws2812Esp32RmtDriver<'_>) -> Result<(), Error> {
+        self.update(driver.pixel_data.as_deref().unwrap_or_default())
+    }
+}
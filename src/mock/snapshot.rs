@@ -0,0 +1,325 @@
+//! Image snapshot export of mock frames.
+//!
+//! Renders pixel data written to the mock driver into a BMP image laid out with a
+//! [`LedPixelShape`], for golden-image regression tests and documentation screenshots.
+//!
+//! [`diff_frames()`] and [`assert_frame_eq()`] compare two frames and report the differing pixels
+//! with their indices, coordinates and colors.
+//!
+//! # Examples
+//!
+//! ```should_panic
+//! use ws2812_esp32_rmt_driver::driver::color::LedPixelColorGrb24;
+//! use ws2812_esp32_rmt_driver::lib_embedded_graphics::LedPixelMatrix;
+//! use ws2812_esp32_rmt_driver::mock::snapshot::assert_frame_eq;
+//!
+//! let expected = [0x00, 0xFF, 0x00, 0x00, 0x00, 0x00];
+//! let actual = [0x00, 0xFF, 0x00, 0x00, 0x00, 0xFF];
+//! // panics with "pixel #1 at (1, 0): expected #000000 but got #0000FF"
+//! assert_frame_eq::<LedPixelColorGrb24, LedPixelMatrix<2, 1>>(&expected, &actual);
+//! ```
+
+use super::render::render_point;
+use crate::driver::color::LedPixelColor;
+use crate::lib_embedded_graphics::LedPixelShape;
+use core::fmt;
+use core::marker::PhantomData;
+use embedded_graphics_core::geometry::Point;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Size of the BMP file header and the `BITMAPINFOHEADER`.
+const BMP_HEADER_LEN: u32 = 14 + 40;
+
+/// Writes the pixel data as a 24-bit BMP image.
+///
+/// * `CDev` - the LED pixel color type used to decode `pixel_data`
+/// * `S` - the LED pixel shape
+///
+/// Each LED pixel is rendered as a `scale`x`scale` square.
+/// Positions of the shape that have no LED pixel are rendered in black.
+///
+/// # Errors
+///
+/// Returns an error if writing to `writer` failed.
+pub fn write_bmp<CDev, S, W>(pixel_data: &[u8], scale: u32, mut writer: W) -> io::Result<()>
+where
+    CDev: LedPixelColor,
+    S: LedPixelShape,
+    W: Write,
+{
+    let size = S::size();
+    let (width, height) = (size.width * scale, size.height * scale);
+    let row_len = (width * 3).div_ceil(4) * 4;
+    let image_len = row_len * height;
+
+    // BITMAPFILEHEADER
+    writer.write_all(b"BM")?;
+    writer.write_all(&(BMP_HEADER_LEN + image_len).to_le_bytes())?;
+    writer.write_all(&[0; 4])?;
+    writer.write_all(&BMP_HEADER_LEN.to_le_bytes())?;
+    // BITMAPINFOHEADER
+    writer.write_all(&40u32.to_le_bytes())?;
+    writer.write_all(&(width as i32).to_le_bytes())?;
+    writer.write_all(&(height as i32).to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&24u16.to_le_bytes())?;
+    writer.write_all(&0u32.to_le_bytes())?; // BI_RGB
+    writer.write_all(&image_len.to_le_bytes())?;
+    writer.write_all(&2835i32.to_le_bytes())?; // 72 DPI
+    writer.write_all(&2835i32.to_le_bytes())?;
+    writer.write_all(&0u32.to_le_bytes())?;
+    writer.write_all(&0u32.to_le_bytes())?;
+
+    // Pixel array: rows are stored bottom-up in BGR order.
+    let mut row = Vec::with_capacity(row_len as usize);
+    for y in (0..height).rev() {
+        row.clear();
+        for x in 0..width {
+            let point = Point::new((x / scale) as i32, (y / scale) as i32);
+            let (r, g, b) = render_point::<CDev, S>(pixel_data, point);
+            row.extend_from_slice(&[b, g, r]);
+        }
+        row.resize(row_len as usize, 0);
+        writer.write_all(&row)?;
+    }
+    Ok(())
+}
+
+/// Saves the pixel data as a 24-bit BMP image file.
+///
+/// See [`write_bmp()`] for details.
+///
+/// # Errors
+///
+/// Returns an error if the file could not be created or written.
+pub fn save_bmp<CDev, S, P>(pixel_data: &[u8], scale: u32, path: P) -> io::Result<()>
+where
+    CDev: LedPixelColor,
+    S: LedPixelShape,
+    P: AsRef<Path>,
+{
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_bmp::<CDev, S, _>(pixel_data, scale, &mut writer)?;
+    writer.flush()
+}
+
+/// Pixel differing between two frames
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PixelDiff<CDev> {
+    /// Index of the LED pixel
+    pub index: usize,
+    /// Position of the LED pixel in the shape, or `None` if the shape has no such pixel
+    pub point: Option<Point>,
+    /// Expected color, or `None` if the expected frame ends before the end of the pixel
+    pub expected: Option<CDev>,
+    /// Actual color, or `None` if the actual frame ends before the end of the pixel
+    pub actual: Option<CDev>,
+}
+
+/// Differences between two frames returned by [`diff_frames()`]
+///
+/// It is formatted with one differing pixel per line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameDiff<CDev, S> {
+    /// Differing pixels in the order of the index
+    pub pixels: Vec<PixelDiff<CDev>>,
+    _shape: PhantomData<fn() -> S>,
+}
+
+impl<CDev, S> FrameDiff<CDev, S> {
+    /// Returns `true` if the frames are identical.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pixels.is_empty()
+    }
+}
+
+/// Writes the color as `#RRGGBB`, or `#RRGGBBWW` for RGBW colors.
+fn fmt_color<CDev: LedPixelColor>(f: &mut fmt::Formatter<'_>, color: &Option<CDev>) -> fmt::Result {
+    match color {
+        Some(c) if CDev::BPP >= 4 => {
+            write!(f, "#{:02X}{:02X}{:02X}{:02X}", c.r(), c.g(), c.b(), c.w())
+        }
+        Some(c) => write!(f, "#{:02X}{:02X}{:02X}", c.r(), c.g(), c.b()),
+        None => write!(f, "none"),
+    }
+}
+
+impl<CDev: LedPixelColor> fmt::Display for PixelDiff<CDev> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pixel #{}", self.index)?;
+        if let Some(point) = self.point {
+            write!(f, " at ({}, {})", point.x, point.y)?;
+        }
+        if self.expected.is_none() && self.actual.is_none() {
+            return write!(f, ": partial pixels differ");
+        }
+        write!(f, ": expected ")?;
+        fmt_color(f, &self.expected)?;
+        write!(f, " but got ")?;
+        fmt_color(f, &self.actual)
+    }
+}
+
+impl<CDev: LedPixelColor, S> fmt::Display for FrameDiff<CDev, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} pixel(s) differ", self.pixels.len())?;
+        for pixel in &self.pixels {
+            write!(f, "\n  {}", pixel)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the bytes of the `index`-th LED pixel, fewer than `BPP` if the pixel data ends in it.
+fn pixel_bytes<CDev: LedPixelColor>(pixel_data: &[u8], index: usize) -> &[u8] {
+    let start = (index * CDev::BPP).min(pixel_data.len());
+    let end = ((index + 1) * CDev::BPP).min(pixel_data.len());
+    &pixel_data[start..end]
+}
+
+/// Decodes the LED pixel of the bytes, or `None` if they are short of a pixel.
+fn decode_pixel<CDev: LedPixelColor>(bytes: &[u8]) -> Option<CDev> {
+    (bytes.len() == CDev::BPP).then(|| {
+        let mut color = CDev::new_with_rgb(0, 0, 0);
+        color.as_mut().copy_from_slice(bytes);
+        color
+    })
+}
+
+/// Compares the expected and actual pixel data.
+///
+/// * `CDev` - the LED pixel color type used to decode the pixel data
+/// * `S` - the LED pixel shape used to locate the differing pixels
+pub fn diff_frames<CDev, S>(expected: &[u8], actual: &[u8]) -> FrameDiff<CDev, S>
+where
+    CDev: LedPixelColor,
+    S: LedPixelShape,
+{
+    let size = S::size();
+    let mut points = vec![None; S::pixel_len()];
+    for y in 0..size.height as i32 {
+        for x in 0..size.width as i32 {
+            let point = Point::new(x, y);
+            if let Some(slot) = S::pixel_index(point).and_then(|index| points.get_mut(index)) {
+                *slot = Some(point);
+            }
+        }
+    }
+
+    let len = expected.len().max(actual.len()).div_ceil(CDev::BPP);
+    let pixels = (0..len)
+        .filter_map(|index| {
+            // The raw bytes are compared, as a partial trailing pixel is not decoded.
+            let expected = pixel_bytes::<CDev>(expected, index);
+            let actual = pixel_bytes::<CDev>(actual, index);
+            (expected != actual).then(|| PixelDiff {
+                index,
+                point: points.get(index).copied().flatten(),
+                expected: decode_pixel(expected),
+                actual: decode_pixel(actual),
+            })
+        })
+        .collect();
+    FrameDiff {
+        pixels,
+        _shape: PhantomData,
+    }
+}
+
+/// Asserts that the pixel data are identical.
+///
+/// # Panics
+///
+/// Panics with the differing pixels if the frames differ.
+#[track_caller]
+pub fn assert_frame_eq<CDev, S>(expected: &[u8], actual: &[u8])
+where
+    CDev: LedPixelColor,
+    S: LedPixelShape,
+{
+    let diff = diff_frames::<CDev, S>(expected, actual);
+    if !diff.is_empty() {
+        panic!("frames differ: {}", diff);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::LedPixelColorGrb24;
+    use crate::lib_embedded_graphics::{LedPixelMatrix, LedPixelStrip};
+
+    #[test]
+    fn test_write_bmp() {
+        let pixel_data = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06];
+        let mut bmp = Vec::new();
+        write_bmp::<LedPixelColorGrb24, LedPixelStrip<2>, _>(&pixel_data, 1, &mut bmp).unwrap();
+
+        assert_eq!(&bmp[0..2], b"BM");
+        assert_eq!(bmp.len(), 54 + 8);
+        assert_eq!(&bmp[2..6], &62u32.to_le_bytes());
+        assert_eq!(&bmp[18..22], &2i32.to_le_bytes());
+        assert_eq!(&bmp[22..26], &1i32.to_le_bytes());
+        assert_eq!(&bmp[54..], [0x03, 0x01, 0x02, 0x06, 0x04, 0x05, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_write_bmp_scaled() {
+        let pixel_data = [0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF];
+        let mut bmp = Vec::new();
+        write_bmp::<LedPixelColorGrb24, LedPixelMatrix<1, 2>, _>(&pixel_data, 2, &mut bmp).unwrap();
+
+        assert_eq!(&bmp[18..22], &2i32.to_le_bytes());
+        assert_eq!(&bmp[22..26], &4i32.to_le_bytes());
+        // bottom-up: (0, 1) is blue, (0, 0) is green
+        assert_eq!(&bmp[54..62], [0xFF, 0, 0, 0xFF, 0, 0, 0, 0]);
+        assert_eq!(&bmp[78..86], [0, 0xFF, 0, 0, 0xFF, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_diff_frames() {
+        let expected = [0x00, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x10, 0x20, 0x30];
+        let actual = [0x00, 0xFF, 0x00, 0x00, 0x00, 0xFF];
+        let diff = diff_frames::<LedPixelColorGrb24, LedPixelMatrix<2, 2>>(&expected, &actual);
+
+        assert_eq!(diff.pixels.len(), 2);
+        assert_eq!(diff.pixels[0].index, 1);
+        assert_eq!(diff.pixels[0].point, Some(Point::new(1, 0)));
+        assert_eq!(diff.pixels[1].point, Some(Point::new(0, 1)));
+        assert_eq!(diff.pixels[1].actual, None);
+        assert_eq!(
+            diff.to_string(),
+            "2 pixel(s) differ\n  pixel #1 at (1, 0): expected #000000 but got #0000FF\n  pixel #2 at (0, 1): expected #201030 but got none"
+        );
+
+        assert!(
+            diff_frames::<LedPixelColorGrb24, LedPixelStrip<3>>(&expected, &expected).is_empty()
+        );
+
+        // Partial trailing pixels
+        let diff = diff_frames::<LedPixelColorGrb24, LedPixelStrip<2>>(
+            &[0x00, 0x00, 0x00, 0x10, 0x20],
+            &[0x00, 0x00, 0x00, 0x10, 0x21],
+        );
+        assert_eq!(diff.pixels.len(), 1);
+        assert_eq!(
+            diff.to_string(),
+            "1 pixel(s) differ\n  pixel #1 at (1, 0): partial pixels differ"
+        );
+        let diff = diff_frames::<LedPixelColorGrb24, LedPixelStrip<2>>(&[0x10], &[]);
+        assert_eq!(diff.pixels.len(), 1);
+        assert!(
+            diff_frames::<LedPixelColorGrb24, LedPixelStrip<2>>(&[0x10, 0x20], &[0x10, 0x20])
+                .is_empty()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "pixel #0 at (0, 0)")]
+    fn test_assert_frame_eq() {
+        assert_frame_eq::<LedPixelColorGrb24, LedPixelStrip<1>>(&[0, 0, 0], &[0, 0, 1]);
+    }
+}
@@ -0,0 +1,205 @@
+//! Draw target mirroring the drawing to another draw target.
+//!
+//! [`TeeDrawTarget`] wraps an [`LedPixelDrawTarget`] and mirrors every drawing to a second draw
+//! target, e.g. the `SimulatorDisplay` of `embedded-graphics-simulator`, and calls a hook on
+//! every flush, e.g. to update the simulator window. Thus the application code drawing onto the
+//! LED pixels renders on the desktop side by side with the mock frames.
+//!
+//! # Examples
+//!
+//! ```
+//! use embedded_graphics::mock_display::MockDisplay;
+//! use embedded_graphics::pixelcolor::Rgb888;
+//! use embedded_graphics::prelude::*;
+//! use ws2812_esp32_rmt_driver::lib_embedded_graphics::{LedPixelMatrix, Ws2812DrawTarget};
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::mock::tee::TeeDrawTarget;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let draw =
+//!     Ws2812DrawTarget::<LedPixelMatrix<5, 5>>::new(peripherals.rmt.channel0, peripherals.pins.gpio0)
+//!         .unwrap();
+//! // e.g. `SimulatorDisplay::<Rgb888>::new(Size::new(5, 5))` updating the `Window` on flush
+//! let mut flushes = 0;
+//! let mut tee = TeeDrawTarget::new(draw, MockDisplay::<Rgb888>::new()).on_flush(|_| flushes += 1);
+//!
+//! Pixel(Point::new(1, 1), Rgb888::RED).draw(&mut tee).unwrap();
+//! tee.flush().unwrap();
+//! assert_eq!(tee.mirror().get_pixel(Point::new(1, 1)), Some(Rgb888::RED));
+//! drop(tee);
+//! assert_eq!(flushes, 1);
+//! ```
+
+use crate::driver::color::LedPixelColor;
+use crate::driver::LedStripDriver;
+use crate::lib_embedded_graphics::{LedPixelDrawTarget, LedPixelShapeInstance};
+use core::convert::Infallible;
+use core::ops::DerefMut;
+use embedded_graphics_core::draw_target::DrawTarget;
+use embedded_graphics_core::geometry::Dimensions;
+use embedded_graphics_core::pixelcolor::RgbColor;
+use embedded_graphics_core::primitives::Rectangle;
+use embedded_graphics_core::Pixel;
+
+/// Draw target drawing onto `T` and mirroring to `M`, calling `F` with `M` on every flush.
+pub struct TeeDrawTarget<T, M, F = fn(&M)> {
+    target: T,
+    mirror: M,
+    on_flush: F,
+}
+
+impl<T, M> TeeDrawTarget<T, M>
+where
+    T: DrawTarget,
+    M: DrawTarget<Color = T::Color, Error = Infallible>,
+{
+    /// Creates a draw target drawing onto `target` and mirroring to `mirror`.
+    pub fn new(target: T, mirror: M) -> Self {
+        Self {
+            target,
+            mirror,
+            on_flush: |_| {},
+        }
+    }
+}
+
+impl<T, M, F> TeeDrawTarget<T, M, F> {
+    /// Returns the draw target calling `on_flush` with the mirror on every flush, e.g. to
+    /// update the simulator window.
+    pub fn on_flush<G: FnMut(&M)>(self, on_flush: G) -> TeeDrawTarget<T, M, G> {
+        TeeDrawTarget {
+            target: self.target,
+            mirror: self.mirror,
+            on_flush,
+        }
+    }
+
+    /// Returns the wrapped draw target.
+    #[inline]
+    pub fn target(&self) -> &T {
+        &self.target
+    }
+
+    /// Returns the wrapped draw target mutably.
+    ///
+    /// The drawing onto it is not mirrored.
+    #[inline]
+    pub fn target_mut(&mut self) -> &mut T {
+        &mut self.target
+    }
+
+    /// Returns the mirror.
+    #[inline]
+    pub fn mirror(&self) -> &M {
+        &self.mirror
+    }
+
+    /// Returns the wrapped draw target and the mirror.
+    pub fn into_inner(self) -> (T, M) {
+        (self.target, self.mirror)
+    }
+}
+
+impl<'d, CDraw, CDev, S, Data, D, M, F>
+    TeeDrawTarget<LedPixelDrawTarget<'d, CDraw, CDev, S, Data, D>, M, F>
+where
+    CDraw: RgbColor,
+    CDev: LedPixelColor + From<CDraw>,
+    S: LedPixelShapeInstance,
+    Data: DerefMut<Target = [u8]> + FromIterator<u8> + IntoIterator<Item = u8>,
+    D: LedStripDriver,
+    F: FnMut(&M),
+{
+    /// Writes changes from a framebuffer to the LED pixels, and then calls the flush hook with
+    /// the mirror.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the driver. The hook is not called then.
+    pub fn flush(&mut self) -> Result<(), D::Error> {
+        self.target.flush()?;
+        (self.on_flush)(&self.mirror);
+        Ok(())
+    }
+}
+
+impl<T, M, F> Dimensions for TeeDrawTarget<T, M, F>
+where
+    T: Dimensions,
+{
+    #[inline]
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+impl<T, M, F> DrawTarget for TeeDrawTarget<T, M, F>
+where
+    T: DrawTarget,
+    T::Color: Copy,
+    M: DrawTarget<Color = T::Color, Error = Infallible>,
+{
+    type Color = T::Color;
+    type Error = T::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bounding_box = self.target.bounding_box();
+        for pixel in pixels {
+            self.target.draw_iter([pixel])?;
+            if bounding_box.contains(pixel.0) {
+                let Ok(()) = self.mirror.draw_iter([pixel]);
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.target.clear(color)?;
+        let Ok(()) = self.mirror.fill_solid(&self.target.bounding_box(), color);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lib_embedded_graphics::{LedPixelMatrix, Ws2812DrawTarget};
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+    use embedded_graphics::mock_display::MockDisplay;
+    use embedded_graphics::pixelcolor::Rgb888;
+    use embedded_graphics::prelude::*;
+
+    #[test]
+    fn test_tee_draw_target() {
+        let peripherals = Peripherals::take().unwrap();
+        let draw = Ws2812DrawTarget::<LedPixelMatrix<3, 2>>::new(
+            peripherals.rmt.channel0,
+            peripherals.pins.gpio0,
+        )
+        .unwrap();
+        let mut mirror = MockDisplay::<Rgb888>::new();
+        mirror.set_allow_overdraw(true);
+        let mut tee = TeeDrawTarget::new(draw, mirror);
+        assert_eq!(tee.bounding_box().size, Size::new(3, 2));
+
+        tee.clear(Rgb888::BLUE).unwrap();
+        // Out of the LED pixels: mirrored to neither.
+        Pixel(Point::new(5, 5), Rgb888::GREEN)
+            .draw(&mut tee)
+            .unwrap();
+        Pixel(Point::new(2, 1), Rgb888::RED).draw(&mut tee).unwrap();
+        tee.flush().unwrap();
+
+        let (draw, mirror) = tee.into_inner();
+        assert_eq!(mirror.get_pixel(Point::new(0, 0)), Some(Rgb888::BLUE));
+        assert_eq!(mirror.get_pixel(Point::new(2, 1)), Some(Rgb888::RED));
+        assert_eq!(mirror.get_pixel(Point::new(5, 5)), None);
+        assert_eq!(
+            draw.frame_buffer().as_bytes()[15..18],
+            [0, 255, 0] // GRB of red
+        );
+    }
+}
@@ -0,0 +1,389 @@
+//! Virtual time for deterministic tests of the timing-dependent logic.
+//!
+//! Attach a [`VirtualClock`] to the mock driver with
+//! [`Ws2812Esp32RmtDriver::set_virtual_clock()`].
+//! Each write then "transmits" the frame on the virtual timeline: it waits for the reset gap of the
+//! previous frame, takes the wire time of the frame, and advances the clock accordingly
+//! as the genuine blocking write does.
+//! The timeline of the written frames is available with [`Ws2812Esp32RmtDriver::frame_records()`].
+//!
+//! [`Ws2812Esp32RmtDriver::set_transmit_delay()`] delays the completion of the transmissions with
+//! a latency and a jitter, to validate the frame pacing under adverse conditions.
+//!
+//! # Examples
+//!
+//! ```
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::mock::time::VirtualClock;
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//! use core::time::Duration;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+//! let clock = VirtualClock::new();
+//! driver.set_virtual_clock(clock.clone());
+//!
+//! driver.write_blocking([0u8; 3].into_iter()).unwrap();
+//! assert_eq!(clock.now(), Duration::from_micros(30));
+//! ```
+
+use crate::driver::timing::LedTiming;
+use crate::driver::Ws2812Esp32RmtDriver;
+use crate::rng::Rng;
+use crate::time::TimeSource;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Duration to transmit a bit (T0H + T0L, T1H + T1L)
+pub const BIT_DURATION: Duration = LedTiming::WS2812.bit_duration();
+
+/// Low duration required between frames to latch the data
+pub const RESET_GAP: Duration = LedTiming::WS2812.reset;
+
+/// Virtual clock shared between the mock driver and the test code.
+///
+/// Cloned clocks share the same virtual time.
+#[derive(Debug, Clone, Default)]
+pub struct VirtualClock(Arc<AtomicU64>);
+
+impl VirtualClock {
+    /// Creates a virtual clock starting at zero.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the current virtual time.
+    pub fn now(&self) -> Duration {
+        Duration::from_nanos(self.0.load(Ordering::SeqCst))
+    }
+
+    /// Sets the current virtual time.
+    pub fn set(&self, now: Duration) {
+        self.0.store(now.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    /// Advances the virtual time by `duration`, like sleeping on the genuine device.
+    pub fn advance(&self, duration: Duration) {
+        self.0
+            .fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+impl TimeSource for VirtualClock {
+    #[inline]
+    fn now(&self) -> Duration {
+        VirtualClock::now(self)
+    }
+}
+
+/// Timeline of a frame written to the mock driver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameRecord {
+    /// Virtual time when the transmission started.
+    pub start: Duration,
+    /// Virtual time when the transmission completed.
+    pub end: Duration,
+    /// The number of bytes of the frame.
+    pub len: usize,
+}
+
+/// Delay added to the completion of each transmission on the virtual timeline
+///
+/// The delay of a frame is `latency` plus a pseudo-random jitter in `0..=jitter`.
+/// The jitter sequence is determined by `seed` so that the tests are reproducible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TransmitDelay {
+    /// Fixed delay of every frame
+    pub latency: Duration,
+    /// Maximum random delay added to `latency`
+    pub jitter: Duration,
+    /// Seed of the jitter sequence
+    pub seed: u64,
+}
+
+/// Pseudo-random generator of the transmission delays.
+#[derive(Debug, Default)]
+struct DelayGenerator {
+    delay: TransmitDelay,
+    rng: Rng,
+}
+
+impl DelayGenerator {
+    /// Creates a generator of `delay`.
+    fn new(delay: TransmitDelay) -> Self {
+        Self {
+            delay,
+            rng: Rng::new(delay.seed),
+        }
+    }
+
+    /// Returns the delay of the next frame.
+    fn next_delay(&mut self) -> Duration {
+        let jitter = self.delay.jitter.as_nanos() as u64;
+        if jitter == 0 {
+            return self.delay.latency;
+        }
+        self.delay.latency + Duration::from_nanos(self.rng.next_u64() % (jitter + 1))
+    }
+}
+
+/// Returns the wire time to transmit `len` bytes.
+#[inline]
+pub fn frame_duration(len: usize) -> Duration {
+    LedTiming::WS2812.data_duration(len)
+}
+
+/// Virtual-time state of the mock driver.
+#[derive(Debug, Default)]
+pub(crate) struct VirtualTimeline {
+    pub(crate) clock: Option<VirtualClock>,
+    pub(crate) records: Vec<FrameRecord>,
+    delay: DelayGenerator,
+}
+
+impl VirtualTimeline {
+    /// Schedules a frame of `len` bytes on the virtual timeline if the clock is attached.
+    ///
+    /// Returns the clock and the virtual time when the transmission completes.
+    pub(crate) fn schedule(&mut self, len: usize) -> Option<(VirtualClock, Duration)> {
+        let clock = self.clock.as_ref()?;
+        let ready = self
+            .records
+            .last()
+            .map(|record| record.end + RESET_GAP)
+            .unwrap_or_default();
+        let start = clock.now().max(ready);
+        let end = start + frame_duration(len) + self.delay.next_delay();
+        self.records.push(FrameRecord { start, end, len });
+        Some((clock.clone(), end))
+    }
+
+    /// Transmits a frame of `len` bytes on the virtual timeline if the clock is attached.
+    ///
+    /// The clock is advanced to the end of the transmission.
+    pub(crate) fn transmit(&mut self, len: usize) {
+        if let Some((clock, end)) = self.schedule(len) {
+            clock.set(end);
+        }
+    }
+}
+
+/// Future that completes when the virtual clock reaches the deadline.
+///
+/// The virtual clock is never advanced by this future; advance it from the test code.
+#[derive(Debug)]
+pub struct VirtualTimer {
+    clock: VirtualClock,
+    deadline: Duration,
+}
+
+impl VirtualTimer {
+    /// Creates a timer that completes when `clock` reaches `deadline`.
+    pub fn new(clock: VirtualClock, deadline: Duration) -> Self {
+        Self { clock, deadline }
+    }
+}
+
+impl Future for VirtualTimer {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.clock.now() >= self.deadline {
+            Poll::Ready(())
+        } else {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+impl Ws2812Esp32RmtDriver<'_> {
+    /// Attaches the virtual clock to the mock driver and clears the frame records.
+    ///
+    /// This function is only available in the mock.
+    pub fn set_virtual_clock(&mut self, clock: VirtualClock) {
+        let timeline = &mut self.recorder.timeline;
+        timeline.clock = Some(clock);
+        timeline.records.clear();
+    }
+
+    /// Delays the completion of each transmission on the virtual timeline by `delay`.
+    ///
+    /// The jitter sequence restarts from the seed.
+    /// It has no effect unless the virtual clock is attached.
+    ///
+    /// This function is only available in the mock.
+    pub fn set_transmit_delay(&mut self, delay: TransmitDelay) {
+        self.recorder.timeline.delay = DelayGenerator::new(delay);
+    }
+
+    /// Returns the timeline of the frames written since the virtual clock was attached.
+    ///
+    /// This function is only available in the mock.
+    pub fn frame_records(&self) -> &[FrameRecord] {
+        &self.recorder.timeline.records
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+    use core::task::{RawWaker, RawWakerVTable, Waker};
+
+    /// Returns a waker doing nothing.
+    fn noop_waker() -> Waker {
+        const VTABLE: RawWakerVTable = RawWakerVTable::new(
+            |_| RawWaker::new(core::ptr::null(), &VTABLE),
+            |_| {},
+            |_| {},
+            |_| {},
+        );
+        unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &VTABLE)) }
+    }
+
+    #[test]
+    fn test_virtual_clock() {
+        let clock = VirtualClock::new();
+        let clock2 = clock.clone();
+        assert_eq!(clock.now(), Duration::ZERO);
+        clock.advance(Duration::from_millis(5));
+        assert_eq!(clock2.now(), Duration::from_millis(5));
+        clock2.set(Duration::from_secs(1));
+        assert_eq!(clock.now(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_driver_frame_records() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        driver.write_blocking([0x00; 3].into_iter()).unwrap();
+        assert_eq!(driver.frame_records(), []);
+
+        let clock = VirtualClock::new();
+        driver.set_virtual_clock(clock.clone());
+        driver.write_blocking([0x00; 3].into_iter()).unwrap();
+        driver.write_blocking([0x00; 3].into_iter()).unwrap();
+        clock.advance(Duration::from_millis(1));
+        driver.write_blocking([0x00; 6].into_iter()).unwrap();
+
+        let us = Duration::from_micros;
+        assert_eq!(
+            driver.frame_records(),
+            [
+                FrameRecord {
+                    start: us(0),
+                    end: us(30),
+                    len: 3
+                },
+                FrameRecord {
+                    start: us(80),
+                    end: us(110),
+                    len: 3
+                },
+                FrameRecord {
+                    start: us(1110),
+                    end: us(1170),
+                    len: 6
+                },
+            ]
+        );
+        assert_eq!(clock.now(), us(1170));
+    }
+
+    #[test]
+    fn test_driver_transmit_delay() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        let clock = VirtualClock::new();
+        driver.set_virtual_clock(clock.clone());
+        driver.set_transmit_delay(TransmitDelay {
+            latency: Duration::from_micros(100),
+            ..Default::default()
+        });
+        driver.write_blocking([0x00; 3].into_iter()).unwrap();
+        driver.write_blocking([0x00; 3].into_iter()).unwrap();
+
+        let us = Duration::from_micros;
+        assert_eq!(
+            driver.frame_records(),
+            [
+                FrameRecord {
+                    start: us(0),
+                    end: us(130),
+                    len: 3
+                },
+                FrameRecord {
+                    start: us(180),
+                    end: us(310),
+                    len: 3
+                },
+            ]
+        );
+
+        let delay = TransmitDelay {
+            latency: us(100),
+            jitter: us(20),
+            seed: 42,
+        };
+        let mut durations = Vec::new();
+        for _ in 0..2 {
+            driver.set_virtual_clock(clock.clone());
+            driver.set_transmit_delay(delay);
+            for _ in 0..16 {
+                driver.write_blocking([0x00; 3].into_iter()).unwrap();
+            }
+            durations.push(
+                driver
+                    .frame_records()
+                    .iter()
+                    .map(|record| record.end - record.start)
+                    .collect::<Vec<_>>(),
+            );
+        }
+        assert_eq!(durations[0], durations[1]);
+        assert!(durations[0]
+            .iter()
+            .all(|duration| (us(130)..=us(150)).contains(duration)));
+        assert!(durations[0]
+            .iter()
+            .any(|duration| *duration != durations[0][0]));
+    }
+
+    #[test]
+    fn test_driver_write_async() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        {
+            let mut future = core::pin::pin!(driver.write_async([0x01; 3].into_iter()));
+            assert!(future.as_mut().poll(&mut cx).is_ready());
+        }
+
+        let clock = VirtualClock::new();
+        driver.set_virtual_clock(clock.clone());
+        {
+            let mut future = core::pin::pin!(driver.write_async([0x02; 3].into_iter()));
+            assert!(future.as_mut().poll(&mut cx).is_pending());
+            clock.advance(Duration::from_micros(29));
+            assert!(future.as_mut().poll(&mut cx).is_pending());
+            clock.advance(Duration::from_micros(1));
+            assert!(future.as_mut().poll(&mut cx).is_ready());
+        }
+        assert_eq!(driver.pixel_data, Some(vec![0x02; 3]));
+    }
+}
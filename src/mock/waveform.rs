@@ -0,0 +1,175 @@
+//! Waveform export of mock frames.
+//!
+//! Encodes pixel data into the signal waveform the same way as the RMT item encoder of the driver
+//! with [`crate::codec`], and writes it as a VCD (Value Change Dump) file or a CSV file of the edges,
+//! so that the output of this crate can be compared against logic-analyzer captures.
+//!
+//! # Examples
+//!
+//! ```
+//! use ws2812_esp32_rmt_driver::driver::timing::LedTiming;
+//! use ws2812_esp32_rmt_driver::mock::waveform::Waveform;
+//!
+//! let waveform = Waveform::encode(&[0x80], &LedTiming::WS2812);
+//! let mut csv = Vec::new();
+//! waveform.write_csv(&mut csv).unwrap();
+//! assert!(String::from_utf8(csv).unwrap().starts_with("time_ns,level\n0,1\n800,0\n1250,1\n"));
+//! ```
+
+use crate::codec::edges;
+use crate::driver::timing::LedTiming;
+use core::time::Duration;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+pub use crate::codec::Edge;
+
+/// Signal waveform of a frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Waveform {
+    /// Edges in the order of the time, ending with the low level at the end of the frame
+    pub edges: Vec<Edge>,
+    /// Duration of the frame, including the reset code
+    pub duration: Duration,
+}
+
+impl Waveform {
+    /// Encodes the pixel data with the given timing.
+    ///
+    /// Each bit is sent MSB first as a high pulse followed by a low pulse,
+    /// and the frame ends with the low voltage of the reset code.
+    pub fn encode(pixel_data: &[u8], timing: &LedTiming) -> Self {
+        let edges = edges(pixel_data.iter().copied(), timing).collect::<Vec<_>>();
+        let duration = edges.last().map_or(Duration::ZERO, |edge| edge.time);
+        Self { edges, duration }
+    }
+
+    /// Writes the waveform as a VCD file with 1 ns time scale.
+    ///
+    /// The signal is named `dout` in the `ws2812` scope.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `writer` failed.
+    pub fn write_vcd<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "$timescale 1ns $end")?;
+        writeln!(writer, "$scope module ws2812 $end")?;
+        writeln!(writer, "$var wire 1 ! dout $end")?;
+        writeln!(writer, "$upscope $end")?;
+        writeln!(writer, "$enddefinitions $end")?;
+        writeln!(writer, "#0")?;
+        writeln!(writer, "$dumpvars")?;
+        writeln!(writer, "0!")?;
+        writeln!(writer, "$end")?;
+        for edge in &self.edges {
+            writeln!(writer, "#{}", edge.time.as_nanos())?;
+            writeln!(writer, "{}!", u8::from(edge.level))?;
+        }
+        Ok(())
+    }
+
+    /// Writes the edges of the waveform as a CSV file of `time_ns,level` rows.
+    ///
+    /// The last row is the low level at the end of the frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `writer` failed.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "time_ns,level")?;
+        for edge in &self.edges {
+            writeln!(writer, "{},{}", edge.time.as_nanos(), u8::from(edge.level))?;
+        }
+        Ok(())
+    }
+
+    /// Saves the waveform as a VCD file.
+    ///
+    /// See [`Self::write_vcd()`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file could not be created or written.
+    pub fn save_vcd<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_vcd(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Saves the edges of the waveform as a CSV file.
+    ///
+    /// See [`Self::write_csv()`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file could not be created or written.
+    pub fn save_csv<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_csv(&mut writer)?;
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_waveform_encode() {
+        let waveform = Waveform::encode(&[0xA0], &LedTiming::WS2812);
+
+        assert_eq!(waveform.edges.len(), 16 + 1);
+        assert_eq!(
+            waveform.edges[..4],
+            [
+                Edge {
+                    time: Duration::ZERO,
+                    level: true
+                },
+                Edge {
+                    time: Duration::from_nanos(800),
+                    level: false
+                },
+                Edge {
+                    time: Duration::from_nanos(1250),
+                    level: true
+                },
+                Edge {
+                    time: Duration::from_nanos(1650),
+                    level: false
+                },
+            ]
+        );
+        assert_eq!(waveform.duration, LedTiming::WS2812.frame_duration(1, 1));
+
+        let empty = Waveform::encode(&[], &LedTiming::WS2812);
+        assert_eq!(empty.edges.len(), 1);
+        assert_eq!(empty.duration, LedTiming::WS2812.reset);
+    }
+
+    #[test]
+    fn test_waveform_write_vcd() {
+        let mut vcd = Vec::new();
+        Waveform::encode(&[0x00], &LedTiming::WS2812)
+            .write_vcd(&mut vcd)
+            .unwrap();
+        let vcd = String::from_utf8(vcd).unwrap();
+
+        assert!(vcd.starts_with("$timescale 1ns $end\n"));
+        assert!(vcd.contains("$enddefinitions $end\n#0\n$dumpvars\n0!\n$end\n#0\n1!\n#400\n0!\n"));
+        assert!(vcd.ends_with("#8750\n1!\n#9150\n0!\n#60000\n0!\n"));
+    }
+
+    #[test]
+    fn test_waveform_write_csv() {
+        let mut csv = Vec::new();
+        Waveform::encode(&[0x01], &LedTiming::WS2812)
+            .write_csv(&mut csv)
+            .unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+
+        assert_eq!(csv.lines().count(), 1 + 16 + 1);
+        assert!(csv.ends_with("8750,1\n9550,0\n60000,0\n"));
+    }
+}
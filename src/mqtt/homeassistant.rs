@@ -0,0 +1,419 @@
+//! Home Assistant MQTT JSON light schema adapter.
+//!
+//! [`HomeAssistantLight`] controls a [`MqttLight`] as a Home Assistant light of the JSON schema:
+//! the commands are received on `<prefix>/set`, the state is published to `<prefix>/state`, and
+//! the discovery payload lets Home Assistant add the light without any configuration. The effect
+//! list is the effects registered to the [`MqttLight`].
+//!
+//! The commands are applied to all the segments of the light. The color mode is `rgbw` for the
+//! LED pixel color types with W channel, otherwise `rgb`.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::driver::color::LedPixelColorGrb24;
+//! use ws2812_esp32_rmt_driver::effects::segment::Segments;
+//! use ws2812_esp32_rmt_driver::effects::Solid;
+//! use ws2812_esp32_rmt_driver::mqtt::homeassistant::HomeAssistantLight;
+//! use ws2812_esp32_rmt_driver::mqtt::MqttLight;
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio27).unwrap();
+//!
+//! let mut segments = Segments::<LedPixelColorGrb24>::new(60);
+//! segments.add("strip", 0..60, Solid::new(Default::default())).unwrap();
+//! let mut light = MqttLight::new("home/shelf", segments);
+//! light.add_default_effects();
+//! let mut light = HomeAssistantLight::new(light, "Shelf", "shelf_0001");
+//!
+//! // Published by the MQTT client on connection, with the retain flag
+//! let discovery_topic = light.discovery_topic("homeassistant");
+//! let discovery_payload = light.discovery_payload();
+//!
+//! // Messages received by the MQTT client
+//! light
+//!     .handle_message("home/shelf/set", br#"{"state":"ON","effect":"rainbow"}"#)
+//!     .unwrap();
+//! // Published to `light.state_topic()`
+//! let state_payload = light.state_payload();
+//!
+//! for tick in 0..10 {
+//!     light.update(tick, &mut driver).unwrap();
+//! }
+//! ```
+
+use super::{LightCommand, MqttError, MqttLight};
+use crate::driver::color::LedPixelColor;
+use crate::driver::LedStripWrite;
+use serde::{Deserialize, Serialize};
+
+#[cfg(target_vendor = "espressif")]
+use esp_idf_svc::mqtt::client::{Details, EspMqttClient, EspMqttEvent, EventPayload, QoS};
+#[cfg(target_vendor = "espressif")]
+use esp_idf_svc::sys::EspError;
+
+const STATE_ON: &str = "ON";
+const STATE_OFF: &str = "OFF";
+
+/// Color of the JSON schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct JsonColor {
+    r: u8,
+    g: u8,
+    b: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    w: Option<u8>,
+}
+
+/// Command payload of the JSON schema
+#[derive(Debug, Deserialize)]
+struct JsonCommand {
+    state: Option<String>,
+    brightness: Option<u8>,
+    color: Option<JsonColor>,
+    effect: Option<String>,
+}
+
+/// State payload of the JSON schema
+#[derive(Debug, Serialize)]
+struct JsonState<'a> {
+    state: &'static str,
+    brightness: u8,
+    color_mode: &'static str,
+    color: JsonColor,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    effect: Option<&'a str>,
+}
+
+/// Discovery payload of the JSON schema
+#[derive(Debug, Serialize)]
+struct JsonDiscovery<'a> {
+    name: &'a str,
+    unique_id: &'a str,
+    schema: &'static str,
+    command_topic: String,
+    state_topic: String,
+    brightness: bool,
+    supported_color_modes: [&'static str; 1],
+    effect: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    effect_list: Vec<&'a str>,
+}
+
+/// Home Assistant light of the JSON schema
+///
+/// * `C` - the LED pixel color type
+#[derive(Debug)]
+pub struct HomeAssistantLight<C: LedPixelColor> {
+    light: MqttLight<C>,
+    name: String,
+    unique_id: String,
+    on: bool,
+    brightness: u8,
+    color: JsonColor,
+    effect: Option<String>,
+}
+
+impl<C: LedPixelColor + Send + 'static> HomeAssistantLight<C> {
+    /// Creates a Home Assistant light of `light` named `name`.
+    ///
+    /// `unique_id` identifies the light in Home Assistant, which shall be unique and stable,
+    /// e.g. derived from the MAC address. The light is on at the full brightness.
+    pub fn new(light: MqttLight<C>, name: &str, unique_id: &str) -> Self {
+        Self {
+            light,
+            name: name.to_owned(),
+            unique_id: unique_id.to_owned(),
+            on: true,
+            brightness: u8::MAX,
+            color: JsonColor {
+                r: u8::MAX,
+                g: u8::MAX,
+                b: u8::MAX,
+                w: (C::BPP == 4).then_some(0),
+            },
+            effect: None,
+        }
+    }
+
+    /// Returns the light.
+    #[inline]
+    pub fn light(&self) -> &MqttLight<C> {
+        &self.light
+    }
+
+    /// Returns the light mutably, e.g. to register an effect.
+    #[inline]
+    pub fn light_mut(&mut self) -> &mut MqttLight<C> {
+        &mut self.light
+    }
+
+    /// Returns `true` if the light is on.
+    #[inline]
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    /// Returns the command topic, i.e. `<prefix>/set`.
+    pub fn command_topic(&self) -> String {
+        format!("{}/set", self.light.prefix())
+    }
+
+    /// Returns the state topic, i.e. `<prefix>/state`.
+    pub fn state_topic(&self) -> String {
+        format!("{}/state", self.light.prefix())
+    }
+
+    /// Returns the discovery topic below `discovery_prefix`, typically `homeassistant`.
+    pub fn discovery_topic(&self, discovery_prefix: &str) -> String {
+        format!(
+            "{}/light/{}/config",
+            discovery_prefix.trim_end_matches('/'),
+            self.unique_id
+        )
+    }
+
+    /// Returns the discovery payload, which shall be published with the retain flag.
+    pub fn discovery_payload(&self) -> String {
+        let effect_list = self.light.effect_names().collect::<Vec<_>>();
+        serde_json::to_string(&JsonDiscovery {
+            name: &self.name,
+            unique_id: &self.unique_id,
+            schema: "json",
+            command_topic: self.command_topic(),
+            state_topic: self.state_topic(),
+            brightness: true,
+            supported_color_modes: [Self::color_mode()],
+            effect: !effect_list.is_empty(),
+            effect_list,
+        })
+        .unwrap()
+    }
+
+    /// Returns the state payload, which shall be published after the commands.
+    pub fn state_payload(&self) -> String {
+        serde_json::to_string(&JsonState {
+            state: if self.on { STATE_ON } else { STATE_OFF },
+            brightness: self.brightness,
+            color_mode: Self::color_mode(),
+            color: self.color,
+            effect: self.effect.as_deref(),
+        })
+        .unwrap()
+    }
+
+    /// Returns the color mode of the LED pixel color type.
+    fn color_mode() -> &'static str {
+        if C::BPP == 4 {
+            "rgbw"
+        } else {
+            "rgb"
+        }
+    }
+
+    /// Handles the message received on the topic.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the topic is not the command topic, or the command is invalid.
+    pub fn handle_message(&mut self, topic: &str, payload: &[u8]) -> Result<(), MqttError> {
+        if topic != self.command_topic() {
+            return Err(MqttError::UnknownTopic);
+        }
+        let command = serde_json::from_slice::<JsonCommand>(payload)
+            .map_err(|_| MqttError::InvalidPayload)?;
+        let on = match command.state.as_deref() {
+            Some(STATE_ON) => true,
+            Some(STATE_OFF) => false,
+            Some(_) => return Err(MqttError::InvalidPayload),
+            None => self.on,
+        };
+
+        if let Some(effect) = command.effect {
+            self.light
+                .apply(None, LightCommand::Effect(effect.as_str()))?;
+            self.effect = Some(effect);
+        }
+        if let Some(color) = command.color {
+            let color = JsonColor {
+                w: (C::BPP == 4).then_some(color.w.unwrap_or(0)),
+                ..color
+            };
+            let command = match color.w {
+                Some(w) => LightCommand::ColorRgbw(color.r, color.g, color.b, w),
+                None => LightCommand::Color(color.r, color.g, color.b),
+            };
+            self.light.apply(None, command)?;
+            self.color = color;
+        }
+        if let Some(brightness) = command.brightness {
+            self.brightness = brightness;
+        }
+        self.on = on;
+        let brightness = if self.on { self.brightness } else { 0 };
+        self.light.apply(None, LightCommand::Brightness(brightness))
+    }
+
+    /// Renders the light of `tick` and writes the frame through the driver.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by `writer`.
+    pub fn update<W: LedStripWrite>(&mut self, tick: u32, writer: &mut W) -> Result<(), W::Error> {
+        self.light.update(tick, writer)
+    }
+}
+
+#[cfg(target_vendor = "espressif")]
+impl<C: LedPixelColor + Send + 'static> HomeAssistantLight<C> {
+    /// Publishes the discovery payload below `discovery_prefix` and the state, and subscribes the
+    /// client to the command topic.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by the client.
+    pub fn announce(
+        &self,
+        client: &mut EspMqttClient<'_>,
+        discovery_prefix: &str,
+    ) -> Result<(), EspError> {
+        client.publish(
+            &self.discovery_topic(discovery_prefix),
+            QoS::AtLeastOnce,
+            true,
+            self.discovery_payload().as_bytes(),
+        )?;
+        client.subscribe(&self.command_topic(), QoS::AtLeastOnce)?;
+        self.publish_state(client)
+    }
+
+    /// Publishes the state with the retain flag.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by the client.
+    pub fn publish_state(&self, client: &mut EspMqttClient<'_>) -> Result<(), EspError> {
+        client.publish(
+            &self.state_topic(),
+            QoS::AtLeastOnce,
+            true,
+            self.state_payload().as_bytes(),
+        )?;
+        Ok(())
+    }
+
+    /// Handles the event of the MQTT connection.
+    ///
+    /// Returns `true` if the event is a command message to the light, after which the state shall
+    /// be published. The messages fragmented into the chunks are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command is invalid.
+    pub fn handle_event(&mut self, event: &EspMqttEvent<'_>) -> Result<bool, MqttError> {
+        match event.payload() {
+            EventPayload::Received {
+                topic: Some(topic),
+                data,
+                details: Details::Complete,
+                ..
+            } if topic == self.command_topic() => {
+                self.handle_message(topic, data)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::{LedPixelColorGrb24, LedPixelColorGrbw32};
+    use crate::driver::Ws2812Esp32RmtDriver;
+    use crate::effects::segment::Segments;
+    use crate::effects::Solid;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+
+    fn light<C: LedPixelColor + Send + 'static>(len: usize) -> HomeAssistantLight<C> {
+        let mut segments = Segments::<C>::new(len);
+        segments
+            .add("strip", 0..len, Solid::new(C::new_with_rgb(0, 0, 0)))
+            .unwrap();
+        let mut light = MqttLight::new("home/shelf", segments);
+        light.add_effect("solid", |color| Box::new(Solid::new(color)));
+        HomeAssistantLight::new(light, "Shelf", "shelf_0001")
+    }
+
+    #[test]
+    fn test_home_assistant_discovery() {
+        let light = light::<LedPixelColorGrb24>(1);
+        assert_eq!(
+            light.discovery_topic("homeassistant/"),
+            "homeassistant/light/shelf_0001/config"
+        );
+        assert_eq!(
+            light.discovery_payload(),
+            concat!(
+                r#"{"name":"Shelf","unique_id":"shelf_0001","schema":"json","#,
+                r#""command_topic":"home/shelf/set","state_topic":"home/shelf/state","#,
+                r#""brightness":true,"supported_color_modes":["rgb"],"#,
+                r#""effect":true,"effect_list":["solid"]}"#
+            )
+        );
+        assert_eq!(
+            light.state_payload(),
+            r#"{"state":"ON","brightness":255,"color_mode":"rgb","color":{"r":255,"g":255,"b":255}}"#
+        );
+    }
+
+    #[test]
+    fn test_home_assistant_command() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        let mut light = light::<LedPixelColorGrbw32>(1);
+        light
+            .handle_message(
+                "home/shelf/set",
+                br#"{"state":"ON","color":{"r":1,"g":2,"b":3,"w":4},"effect":"solid"}"#,
+            )
+            .unwrap();
+        light.update(0, &mut driver).unwrap();
+        assert_eq!(driver.pixel_data, Some(vec![0x02, 0x01, 0x03, 0x04]));
+        assert_eq!(
+            light.state_payload(),
+            concat!(
+                r#"{"state":"ON","brightness":255,"color_mode":"rgbw","#,
+                r#""color":{"r":1,"g":2,"b":3,"w":4},"effect":"solid"}"#
+            )
+        );
+
+        light
+            .handle_message("home/shelf/set", br#"{"state":"OFF"}"#)
+            .unwrap();
+        assert!(!light.is_on());
+        light.update(0, &mut driver).unwrap();
+        assert_eq!(driver.pixel_data, Some(vec![0x00; 4]));
+
+        assert_eq!(
+            light.handle_message("home/shelf/set", br#"{"effect":"strobe"}"#),
+            Err(MqttError::UnknownEffect)
+        );
+        assert_eq!(
+            light.handle_message("home/shelf/set", b"ON"),
+            Err(MqttError::InvalidPayload)
+        );
+        assert_eq!(
+            light.handle_message("home/shelf/state", b"{}"),
+            Err(MqttError::UnknownTopic)
+        );
+    }
+}
@@ -0,0 +1,541 @@
+//! MQTT light control.
+//!
+//! [`MqttLight`] applies the brightness, color and effect commands received on the MQTT topics to
+//! the [`Segments`] of a strip, so that a simple smart-light firmware only connects the MQTT
+//! client and calls [`MqttLight::update()`] periodically. A whole strip is controlled as a set of
+//! one segment covering it.
+//!
+//! The commands are published to the topics below the prefix, e.g. `home/shelf`:
+//!
+//! |Topic                            |Payload                                          |Applied to          |
+//! |---------------------------------|-------------------------------------------------|--------------------|
+//! |`<prefix>/brightness`            |`0` to `255`                                     |all the segments    |
+//! |`<prefix>/color`                 |`r,g,b[,w]` (decimal) or `#rrggbb[ww]` (hex)     |all the segments    |
+//! |`<prefix>/effect`                |effect name, e.g. `rainbow`                      |all the segments    |
+//! |`<prefix>/<segment>/brightness`  |same as above                                    |the segment         |
+//! |`<prefix>/<segment>/color`       |same as above                                    |the segment         |
+//! |`<prefix>/<segment>/effect`      |same as above                                    |the segment         |
+//!
+//! The effects are created by the factories registered by name, see
+//! [`MqttLight::add_effect()`] and [`MqttLight::add_default_effects()`].
+//!
+//! On ESP-IDF, [`MqttLight::subscribe()`] and [`MqttLight::handle_event()`] connect it with the
+//! `esp-idf-svc` MQTT client. On the other targets, the messages received by any MQTT client are
+//! passed to [`MqttLight::handle_message()`].
+//!
+//! [`homeassistant`] speaks the Home Assistant MQTT JSON light schema instead of the topics above
+//! (requires `homeassistant` feature).
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::driver::color::LedPixelColorGrb24;
+//! use ws2812_esp32_rmt_driver::effects::segment::Segments;
+//! use ws2812_esp32_rmt_driver::effects::RainbowCycle;
+//! use ws2812_esp32_rmt_driver::mqtt::MqttLight;
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio27).unwrap();
+//!
+//! let mut segments = Segments::<LedPixelColorGrb24>::new(60);
+//! segments.add("shelf", 0..40, RainbowCycle::new(4)).unwrap();
+//! segments.add("accent", 40..60, RainbowCycle::new(4)).unwrap();
+//! let mut light = MqttLight::new("home/light", segments);
+//! light.add_default_effects();
+//!
+//! // Messages received by the MQTT client
+//! light.handle_message("home/light/brightness", b"128").unwrap();
+//! light.handle_message("home/light/accent/color", b"#ff8000").unwrap();
+//! light.handle_message("home/light/accent/effect", b"breathe").unwrap();
+//!
+//! for tick in 0..10 {
+//!     light.update(tick, &mut driver).unwrap();
+//! }
+//! ```
+
+#[cfg(feature = "homeassistant")]
+pub mod homeassistant;
+
+use crate::driver::color::LedPixelColor;
+use crate::driver::LedStripWrite;
+use crate::effects::segment::Segments;
+use crate::effects::{Breathe, ColorWipe, Effect, RainbowCycle, Solid, Sparkle, TheaterChase};
+use std::error::Error;
+use std::fmt;
+
+#[cfg(target_vendor = "espressif")]
+use esp_idf_svc::mqtt::client::{Details, EspMqttClient, EspMqttEvent, EventPayload, QoS};
+#[cfg(target_vendor = "espressif")]
+use esp_idf_svc::sys::EspError;
+
+/// Factory of an effect of the color
+type EffectFactory<C> = Box<dyn Fn(C) -> Box<dyn Effect<C> + Send> + Send>;
+
+/// Light command received on a topic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightCommand<'a> {
+    /// Sets the brightness, 255 for as is.
+    Brightness(u8),
+    /// Sets the color in R, G and B.
+    Color(u8, u8, u8),
+    /// Sets the color in R, G, B and W.
+    ColorRgbw(u8, u8, u8, u8),
+    /// Sets the effect of the name.
+    Effect(&'a str),
+}
+
+impl<'a> LightCommand<'a> {
+    /// Parses the payload of the topic of the `property`, i.e. `brightness`, `color` or `effect`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the property is unknown or the payload is invalid.
+    pub fn parse(property: &str, payload: &'a [u8]) -> Result<Self, MqttError> {
+        let payload = core::str::from_utf8(payload)
+            .map_err(|_| MqttError::InvalidPayload)?
+            .trim();
+        match property {
+            "brightness" => payload
+                .parse()
+                .map(Self::Brightness)
+                .map_err(|_| MqttError::InvalidPayload),
+            "color" => match parse_color(payload) {
+                Some((r, g, b, None)) => Ok(Self::Color(r, g, b)),
+                Some((r, g, b, Some(w))) => Ok(Self::ColorRgbw(r, g, b, w)),
+                None => Err(MqttError::InvalidPayload),
+            },
+            "effect" if !payload.is_empty() => Ok(Self::Effect(payload)),
+            "effect" => Err(MqttError::InvalidPayload),
+            _ => Err(MqttError::UnknownTopic),
+        }
+    }
+}
+
+/// Parses the color in `r,g,b[,w]` decimal or `#rrggbb[ww]` hexadecimal.
+fn parse_color(payload: &str) -> Option<(u8, u8, u8, Option<u8>)> {
+    if let Some(hex) = payload.strip_prefix('#') {
+        if (hex.len() != 6 && hex.len() != 8) || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        let w = if hex.len() == 8 {
+            Some(channel(6)?)
+        } else {
+            None
+        };
+        return Some((channel(0)?, channel(2)?, channel(4)?, w));
+    }
+    let mut channels = payload.split(',').map(|v| v.trim().parse::<u8>().ok());
+    let (r, g, b) = (channels.next()??, channels.next()??, channels.next()??);
+    match (channels.next(), channels.next()) {
+        (None, _) => Some((r, g, b, None)),
+        (Some(w), None) => Some((r, g, b, Some(w?))),
+        _ => None,
+    }
+}
+
+/// MQTT light control error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MqttError {
+    /// The topic is not a command topic of the light.
+    UnknownTopic,
+    /// The payload is invalid for the topic.
+    InvalidPayload,
+    /// No segment of the name exists.
+    UnknownSegment,
+    /// No effect of the name is registered.
+    UnknownEffect,
+}
+
+impl Error for MqttError {}
+
+impl fmt::Display for MqttError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownTopic => write!(f, "unknown topic"),
+            Self::InvalidPayload => write!(f, "invalid payload"),
+            Self::UnknownSegment => write!(f, "unknown segment"),
+            Self::UnknownEffect => write!(f, "unknown effect"),
+        }
+    }
+}
+
+/// Color and effect last commanded to a segment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LightState<C: LedPixelColor> {
+    /// Color passed to the effect
+    pub color: C,
+    /// Name of the effect, or `None` for the effect set to the segment directly
+    pub effect: Option<String>,
+}
+
+impl<C: LedPixelColor> Default for LightState<C> {
+    fn default() -> Self {
+        Self {
+            color: C::new_with_rgb(255, 255, 255),
+            effect: None,
+        }
+    }
+}
+
+/// Light controlled by the MQTT commands
+///
+/// * `C` - the LED pixel color type
+pub struct MqttLight<C: LedPixelColor> {
+    prefix: String,
+    segments: Segments<C>,
+    states: Vec<(String, LightState<C>)>,
+    effects: Vec<(String, EffectFactory<C>)>,
+}
+
+impl<C: LedPixelColor + Send + 'static> MqttLight<C> {
+    /// Creates a light of the segments controlled by the commands below the topic `prefix`.
+    ///
+    /// No effect is registered.
+    pub fn new(prefix: &str, segments: Segments<C>) -> Self {
+        Self {
+            prefix: prefix.trim_end_matches('/').to_owned(),
+            segments,
+            states: Vec::new(),
+            effects: Vec::new(),
+        }
+    }
+
+    /// Registers the effect factory by name.
+    ///
+    /// The factory creates the effect of the color commanded. The factory of the same name is
+    /// replaced.
+    pub fn add_effect<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(C) -> Box<dyn Effect<C> + Send> + Send + 'static,
+    {
+        self.effects.retain(|(n, _)| n != name);
+        self.effects.push((name.to_owned(), Box::new(factory)));
+    }
+
+    /// Registers the effects of the [`effects`](crate::effects) module:
+    /// `solid`, `rainbow`, `theater_chase`, `breathe`, `sparkle` and `color_wipe`.
+    pub fn add_default_effects(&mut self) {
+        self.add_effect("solid", |color| Box::new(Solid::new(color)));
+        self.add_effect("rainbow", |_| Box::new(RainbowCycle::new(4)));
+        self.add_effect("theater_chase", |color| {
+            Box::new(TheaterChase::new(color, 3))
+        });
+        self.add_effect("breathe", |color| Box::new(Breathe::new(color, 128)));
+        self.add_effect("sparkle", |color| Box::new(Sparkle::new(color, 16, 1)));
+        self.add_effect("color_wipe", |color| Box::new(ColorWipe::new(color)));
+    }
+
+    /// Returns the names of the effects registered.
+    pub fn effect_names(&self) -> impl Iterator<Item = &str> {
+        self.effects.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Returns the topic prefix.
+    #[inline]
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Returns the topic filter matching all the command topics, i.e. `<prefix>/#`.
+    pub fn topic_filter(&self) -> String {
+        format!("{}/#", self.prefix)
+    }
+
+    /// Returns the segments.
+    #[inline]
+    pub fn segments(&self) -> &Segments<C> {
+        &self.segments
+    }
+
+    /// Returns the segments mutably, e.g. to add a segment.
+    #[inline]
+    pub fn segments_mut(&mut self) -> &mut Segments<C> {
+        &mut self.segments
+    }
+
+    /// Returns the state last commanded to the segment.
+    ///
+    /// Returns `None` if no segment of the name exists.
+    pub fn state(&self, segment: &str) -> Option<LightState<C>> {
+        self.segments.get(segment)?;
+        Some(
+            self.states
+                .iter()
+                .find(|(name, _)| name == segment)
+                .map(|(_, state)| state.clone())
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Handles the message received on the topic.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the topic is not a command topic of the light, or the command could
+    /// not be applied.
+    pub fn handle_message(&mut self, topic: &str, payload: &[u8]) -> Result<(), MqttError> {
+        let path = topic
+            .strip_prefix(self.prefix.as_str())
+            .and_then(|path| path.strip_prefix('/'))
+            .ok_or(MqttError::UnknownTopic)?;
+        match path.rsplit_once('/') {
+            Some((segment, property)) => {
+                self.apply(Some(segment), LightCommand::parse(property, payload)?)
+            }
+            None => self.apply(None, LightCommand::parse(path, payload)?),
+        }
+    }
+
+    /// Applies the command to the segment, or all the segments if `segment` is `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the segment or the effect is unknown.
+    pub fn apply(&mut self, segment: Option<&str>, command: LightCommand) -> Result<(), MqttError> {
+        if let LightCommand::Effect(name) = command {
+            if !self.effects.iter().any(|(n, _)| n == name) {
+                return Err(MqttError::UnknownEffect);
+            }
+        }
+        match segment {
+            Some(segment) => {
+                if self.segments.get(segment).is_none() {
+                    return Err(MqttError::UnknownSegment);
+                }
+                self.apply_to(segment, command);
+            }
+            None => {
+                let names = self
+                    .segments
+                    .iter()
+                    .map(|segment| segment.name().to_owned())
+                    .collect::<Vec<_>>();
+                for name in names {
+                    self.apply_to(&name, command);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the command to the existing segment.
+    fn apply_to(&mut self, segment: &str, command: LightCommand) {
+        let index = match self.states.iter().position(|(name, _)| name == segment) {
+            Some(index) => index,
+            None => {
+                self.states
+                    .push((segment.to_owned(), LightState::default()));
+                self.states.len() - 1
+            }
+        };
+        let state = &mut self.states[index].1;
+        let Some(target) = self.segments.get_mut(segment) else {
+            return;
+        };
+        match command {
+            LightCommand::Brightness(brightness) => {
+                target.brightness = brightness;
+                return;
+            }
+            LightCommand::Color(r, g, b) => state.color = C::new_with_rgb(r, g, b),
+            LightCommand::ColorRgbw(r, g, b, w) => state.color = C::new_with_rgbw(r, g, b, w),
+            LightCommand::Effect(name) => state.effect = Some(name.to_owned()),
+        }
+        let factory = state
+            .effect
+            .as_deref()
+            .and_then(|name| self.effects.iter().find(|(n, _)| n == name));
+        match factory {
+            Some((_, factory)) => target.set_boxed_effect(factory(state.color.clone())),
+            None => target.set_effect(Solid::new(state.color.clone())),
+        }
+    }
+
+    /// Renders the segments of `tick` and writes the frame through the driver.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by `writer`.
+    pub fn update<W: LedStripWrite>(&mut self, tick: u32, writer: &mut W) -> Result<(), W::Error> {
+        self.segments.update(tick, writer)
+    }
+}
+
+#[cfg(target_vendor = "espressif")]
+impl<C: LedPixelColor + Send + 'static> MqttLight<C> {
+    /// Subscribes the client to all the command topics.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by the client.
+    pub fn subscribe(&self, client: &mut EspMqttClient<'_>) -> Result<(), EspError> {
+        client.subscribe(&self.topic_filter(), QoS::AtLeastOnce)?;
+        Ok(())
+    }
+
+    /// Handles the event of the MQTT connection.
+    ///
+    /// Returns `true` if the event is a command message to the light. The messages fragmented
+    /// into the chunks are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command could not be applied.
+    pub fn handle_event(&mut self, event: &EspMqttEvent<'_>) -> Result<bool, MqttError> {
+        match event.payload() {
+            EventPayload::Received {
+                topic: Some(topic),
+                data,
+                details: Details::Complete,
+                ..
+            } if topic.starts_with(self.prefix.as_str()) => {
+                self.handle_message(topic, data)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+impl<C: LedPixelColor> fmt::Debug for MqttLight<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MqttLight")
+            .field("prefix", &self.prefix)
+            .field("segments", &self.segments.iter().collect::<Vec<_>>())
+            .field(
+                "effects",
+                &self
+                    .effects
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::LedPixelColorGrb24;
+    use crate::driver::Ws2812Esp32RmtDriver;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+
+    type Color = LedPixelColorGrb24;
+
+    #[test]
+    fn test_light_command_parse() {
+        assert_eq!(
+            LightCommand::parse("brightness", b" 128\n"),
+            Ok(LightCommand::Brightness(128))
+        );
+        assert_eq!(
+            LightCommand::parse("brightness", b"256"),
+            Err(MqttError::InvalidPayload)
+        );
+        assert_eq!(
+            LightCommand::parse("color", b"255, 128,0"),
+            Ok(LightCommand::Color(255, 128, 0))
+        );
+        assert_eq!(
+            LightCommand::parse("color", b"#FF8001"),
+            Ok(LightCommand::Color(255, 128, 1))
+        );
+        assert_eq!(
+            LightCommand::parse("color", b"1,2,3,4"),
+            Ok(LightCommand::ColorRgbw(1, 2, 3, 4))
+        );
+        assert_eq!(
+            LightCommand::parse("color", b"#01020304"),
+            Ok(LightCommand::ColorRgbw(1, 2, 3, 4))
+        );
+        assert_eq!(
+            LightCommand::parse("color", b"1,2,3,4,5"),
+            Err(MqttError::InvalidPayload)
+        );
+        assert_eq!(
+            LightCommand::parse("color", b"1,2,3,x"),
+            Err(MqttError::InvalidPayload)
+        );
+        assert_eq!(
+            LightCommand::parse("color", b"#FF80"),
+            Err(MqttError::InvalidPayload)
+        );
+        assert_eq!(
+            LightCommand::parse("effect", b"rainbow"),
+            Ok(LightCommand::Effect("rainbow"))
+        );
+        assert_eq!(
+            LightCommand::parse("effect", b""),
+            Err(MqttError::InvalidPayload)
+        );
+        assert_eq!(
+            LightCommand::parse("speed", b"1"),
+            Err(MqttError::UnknownTopic)
+        );
+    }
+
+    #[test]
+    fn test_mqtt_light() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        let mut segments = Segments::<Color>::new(3);
+        segments.add("a", 0..1, RainbowCycle::new(0)).unwrap();
+        segments.add("b", 1..3, RainbowCycle::new(0)).unwrap();
+        let mut light = MqttLight::new("home/light/", segments);
+        light.add_default_effects();
+        assert_eq!(light.topic_filter(), "home/light/#");
+        assert!(light.effect_names().any(|name| name == "breathe"));
+
+        light
+            .handle_message("home/light/color", b"0,0,255")
+            .unwrap();
+        light
+            .handle_message("home/light/b/color", b"#ff0000")
+            .unwrap();
+        light
+            .handle_message("home/light/b/brightness", b"128")
+            .unwrap();
+        light.update(0, &mut driver).unwrap();
+        assert_eq!(
+            driver.pixel_data,
+            Some(vec![0x00, 0x00, 0xFF, 0x00, 0x80, 0x00, 0x00, 0x80, 0x00])
+        );
+
+        light
+            .handle_message("home/light/a/effect", b"color_wipe")
+            .unwrap();
+        light.update(0, &mut driver).unwrap();
+        assert_eq!(driver.pixel_data.as_ref().unwrap()[..3], [0x00; 3]);
+        assert_eq!(
+            light.state("a"),
+            Some(LightState {
+                color: Color::new_with_rgb(0, 0, 255),
+                effect: Some("color_wipe".to_owned()),
+            })
+        );
+        assert_eq!(light.state("b").unwrap().effect, None);
+
+        assert_eq!(
+            light.handle_message("home/light/a/effect", b"strobe"),
+            Err(MqttError::UnknownEffect)
+        );
+        assert_eq!(
+            light.handle_message("home/light/c/color", b"#ffffff"),
+            Err(MqttError::UnknownSegment)
+        );
+        assert_eq!(
+            light.handle_message("home/other/color", b"#ffffff"),
+            Err(MqttError::UnknownTopic)
+        );
+    }
+}
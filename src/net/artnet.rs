@@ -0,0 +1,397 @@
+//! Art-Net receiver.
+//!
+//! [`ArtNetReceiver`] receives the ArtDmx packets of the universes of a [`DmxFrame`] over UDP
+//! and writes the frame through the driver once all of its universes are received.
+//! Once an ArtSync packet is received, the frame is written on each ArtSync packet instead, until
+//! no ArtSync packet is received for [`SYNC_TIMEOUT`].
+//!
+//! The universes of the frame are the 15-bit port-addresses of Art-Net, i.e. net, sub-net and
+//! universe.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use std::net::Ipv4Addr;
+//! use ws2812_esp32_rmt_driver::driver::color::LedPixelColorGrb24;
+//! use ws2812_esp32_rmt_driver::net::artnet::{ArtNetReceiver, ARTNET_PORT};
+//! use ws2812_esp32_rmt_driver::net::DmxFrame;
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio27).unwrap();
+//!
+//! // 300 RGB LED pixels on the port-addresses 0 and 1
+//! let frame = DmxFrame::<LedPixelColorGrb24>::new(300, 0);
+//! let mut receiver = ArtNetReceiver::bind((Ipv4Addr::UNSPECIFIED, ARTNET_PORT), frame).unwrap();
+//! receiver.run(&mut driver).unwrap();
+//! ```
+
+use super::{is_out_of_order, DmxFrame, ReceiveError, UdpReceiver, DMX_CHANNELS};
+use crate::driver::color::LedPixelColor;
+use crate::driver::LedStripWrite;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// Default UDP port of Art-Net
+pub const ARTNET_PORT: u16 = 6454;
+/// Duration without ArtSync packet to go back to writing the frame on the ArtDmx packets
+pub const SYNC_TIMEOUT: Duration = Duration::from_secs(4);
+
+/// Packet ID at the beginning of an Art-Net packet
+const ARTNET_ID: [u8; 8] = *b"Art-Net\0";
+/// Minimum protocol version
+const PROTOCOL_VERSION: u16 = 14;
+const OP_DMX: u16 = 0x5000;
+const OP_SYNC: u16 = 0x5200;
+
+/// Byte offset of the DMX data in an ArtDmx packet
+const DATA_OFFSET: usize = 18;
+/// Byte count of an ArtSync packet
+const SYNC_PACKET_LEN: usize = 14;
+
+/// Art-Net packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtNetPacket<'a> {
+    /// ArtDmx packet carrying the DMX data of a universe
+    Dmx {
+        /// 15-bit port-address of the universe
+        universe: u16,
+        /// Sequence number, 0 if disabled
+        sequence: u8,
+        /// DMX data from the first channel
+        data: &'a [u8],
+    },
+    /// ArtSync packet to write the received data
+    Sync,
+}
+
+impl<'a> ArtNetPacket<'a> {
+    /// Parses the packet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the packet is not an Art-Net packet, or if it is neither ArtDmx nor
+    /// ArtSync.
+    pub fn parse(packet: &'a [u8]) -> Result<Self, ArtNetError> {
+        if packet.len() < SYNC_PACKET_LEN
+            || packet[0..8] != ARTNET_ID
+            || u16::from_be_bytes([packet[10], packet[11]]) < PROTOCOL_VERSION
+        {
+            return Err(ArtNetError::InvalidPacket);
+        }
+        match u16::from_le_bytes([packet[8], packet[9]]) {
+            OP_DMX => {
+                if packet.len() < DATA_OFFSET {
+                    return Err(ArtNetError::InvalidPacket);
+                }
+                let len = u16::from_be_bytes([packet[16], packet[17]]) as usize;
+                if len > DMX_CHANNELS || DATA_OFFSET + len > packet.len() {
+                    return Err(ArtNetError::InvalidPacket);
+                }
+                Ok(Self::Dmx {
+                    universe: u16::from_le_bytes([packet[14], packet[15] & 0x7F]),
+                    sequence: packet[12],
+                    data: &packet[DATA_OFFSET..DATA_OFFSET + len],
+                })
+            }
+            OP_SYNC => Ok(Self::Sync),
+            op => Err(ArtNetError::UnsupportedOpCode(op)),
+        }
+    }
+}
+
+/// Art-Net packet parse error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArtNetError {
+    /// The packet is not a valid Art-Net packet.
+    InvalidPacket,
+    /// The OpCode is not supported.
+    UnsupportedOpCode(u16),
+}
+
+impl Error for ArtNetError {}
+
+impl fmt::Display for ArtNetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPacket => write!(f, "invalid Art-Net packet"),
+            Self::UnsupportedOpCode(op) => write!(f, "unsupported Art-Net OpCode {:#06x}", op),
+        }
+    }
+}
+
+/// Art-Net receiver writing the frame through the driver
+#[derive(Debug)]
+pub struct ArtNetReceiver<C: LedPixelColor> {
+    socket: UdpSocket,
+    frame: DmxFrame<C>,
+    buffer: Vec<u8>,
+    /// Last sequence number of each universe
+    sequences: Vec<Option<u8>>,
+    last_sync: Option<Instant>,
+}
+
+impl<C: LedPixelColor> ArtNetReceiver<C> {
+    /// Creates a receiver bound to the address, typically port [`ARTNET_PORT`] of all the
+    /// interfaces.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket could not be bound.
+    pub fn bind<A: ToSocketAddrs>(addr: A, frame: DmxFrame<C>) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        let universe_count = frame.universe_count();
+        Ok(Self {
+            socket,
+            frame,
+            buffer: vec![0; DATA_OFFSET + DMX_CHANNELS],
+            sequences: vec![None; universe_count],
+            last_sync: None,
+        })
+    }
+
+    /// Returns the socket.
+    #[inline]
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Returns the frame.
+    #[inline]
+    pub fn frame(&self) -> &DmxFrame<C> {
+        &self.frame
+    }
+
+    /// Returns `true` if the frame is written on the ArtSync packets.
+    pub fn is_synchronous(&self) -> bool {
+        self.last_sync
+            .is_some_and(|last_sync| last_sync.elapsed() < SYNC_TIMEOUT)
+    }
+
+    /// Handles the packet, writing the frame through the driver if it is ready to be shown.
+    ///
+    /// Returns `true` if the frame is written. Invalid packets, unsupported packets, the packets
+    /// of the other universes and the ArtDmx packets out of sequence are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by `writer`.
+    pub fn handle_packet<W: LedStripWrite>(
+        &mut self,
+        packet: &[u8],
+        writer: &mut W,
+    ) -> Result<bool, W::Error> {
+        match ArtNetPacket::parse(packet) {
+            Ok(ArtNetPacket::Dmx {
+                universe,
+                sequence,
+                data,
+            }) => {
+                if !self.frame.contains_universe(universe) {
+                    return Ok(false);
+                }
+                let last = &mut self.sequences[(universe - self.frame.start_universe()) as usize];
+                if sequence == 0 {
+                    *last = None;
+                } else if is_out_of_order(*last, sequence) {
+                    return Ok(false);
+                } else {
+                    *last = Some(sequence);
+                }
+                self.frame.set_universe(universe, data);
+                if !self.is_synchronous() && self.frame.is_complete() {
+                    self.frame.flush(writer)?;
+                    return Ok(true);
+                }
+                Ok(false)
+            }
+            Ok(ArtNetPacket::Sync) => {
+                self.last_sync = Some(Instant::now());
+                self.frame.flush(writer)?;
+                Ok(true)
+            }
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// Receives a packet and handles it by [`Self::handle_packet()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if receiving failed or `writer` returned an error.
+    pub fn receive<W: LedStripWrite>(
+        &mut self,
+        writer: &mut W,
+    ) -> Result<bool, ReceiveError<W::Error>> {
+        self.receive_packet(writer)
+    }
+
+    /// Receives and handles the packets until an error occurs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if receiving failed or `writer` returned an error.
+    pub fn run<W: LedStripWrite>(&mut self, writer: &mut W) -> Result<(), ReceiveError<W::Error>> {
+        self.run_loop(writer)
+    }
+}
+
+impl<C: LedPixelColor> UdpReceiver for ArtNetReceiver<C> {
+    fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    fn buffer_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buffer
+    }
+
+    fn handle<W: LedStripWrite>(
+        &mut self,
+        packet: &[u8],
+        writer: &mut W,
+    ) -> Result<bool, W::Error> {
+        self.handle_packet(packet, writer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::LedPixelColorGrb24;
+    use crate::driver::Ws2812Esp32RmtDriver;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+    use std::net::Ipv4Addr;
+
+    fn header(op: u16) -> Vec<u8> {
+        let mut packet = ARTNET_ID.to_vec();
+        packet.extend_from_slice(&op.to_le_bytes());
+        packet.extend_from_slice(&PROTOCOL_VERSION.to_be_bytes());
+        packet
+    }
+
+    fn dmx_packet(universe: u16, data: &[u8]) -> Vec<u8> {
+        sequenced_dmx_packet(universe, 0, data)
+    }
+
+    fn sequenced_dmx_packet(universe: u16, sequence: u8, data: &[u8]) -> Vec<u8> {
+        let mut packet = header(OP_DMX);
+        packet.extend_from_slice(&[sequence, 0]);
+        packet.extend_from_slice(&universe.to_le_bytes());
+        packet.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        packet.extend_from_slice(data);
+        packet
+    }
+
+    fn sync_packet() -> Vec<u8> {
+        let mut packet = header(OP_SYNC);
+        packet.extend_from_slice(&[0, 0]);
+        packet
+    }
+
+    #[test]
+    fn test_artnet_packet_parse() {
+        let packet = dmx_packet(0x0123, &[0x01, 0x02]);
+        assert_eq!(
+            ArtNetPacket::parse(&packet),
+            Ok(ArtNetPacket::Dmx {
+                universe: 0x0123,
+                sequence: 0,
+                data: &[0x01, 0x02],
+            })
+        );
+        assert_eq!(ArtNetPacket::parse(&sync_packet()), Ok(ArtNetPacket::Sync));
+
+        assert_eq!(
+            ArtNetPacket::parse(&packet[..packet.len() - 1]),
+            Err(ArtNetError::InvalidPacket)
+        );
+        let mut bad = packet.clone();
+        bad[0] = b'X';
+        assert_eq!(ArtNetPacket::parse(&bad), Err(ArtNetError::InvalidPacket));
+        let mut bad = packet.clone();
+        bad[11] = 13;
+        assert_eq!(ArtNetPacket::parse(&bad), Err(ArtNetError::InvalidPacket));
+        let mut poll = header(0x2000);
+        poll.extend_from_slice(&[0, 0]);
+        assert_eq!(
+            ArtNetPacket::parse(&poll),
+            Err(ArtNetError::UnsupportedOpCode(0x2000))
+        );
+    }
+
+    #[test]
+    fn test_artnet_receiver() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        let mut frame = DmxFrame::<LedPixelColorGrb24>::new(2, 0x10);
+        frame.set_channels_per_universe(3);
+        let mut receiver = ArtNetReceiver::bind((Ipv4Addr::LOCALHOST, 0), frame).unwrap();
+        let sender = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        sender
+            .connect(receiver.socket().local_addr().unwrap())
+            .unwrap();
+
+        sender.send(&dmx_packet(0x10, &[0x01, 0x02, 0x03])).unwrap();
+        assert!(!receiver.receive(&mut driver).unwrap());
+        sender.send(&dmx_packet(0x11, &[0x04, 0x05, 0x06])).unwrap();
+        assert!(receiver.receive(&mut driver).unwrap());
+        assert_eq!(
+            driver.pixel_data,
+            Some(vec![0x02, 0x01, 0x03, 0x05, 0x04, 0x06])
+        );
+
+        assert!(!receiver.is_synchronous());
+        assert!(receiver.handle_packet(&sync_packet(), &mut driver).unwrap());
+        assert!(receiver.is_synchronous());
+
+        driver.pixel_data = None;
+        for universe in [0x10, 0x11] {
+            let packet = dmx_packet(universe, &[0x10; 3]);
+            assert!(!receiver.handle_packet(&packet, &mut driver).unwrap());
+        }
+        assert_eq!(driver.pixel_data, None);
+        assert!(receiver.handle_packet(&sync_packet(), &mut driver).unwrap());
+        assert_eq!(driver.pixel_data, Some(vec![0x10; 6]));
+    }
+
+    #[test]
+    fn test_artnet_receiver_sequence() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        let frame = DmxFrame::<LedPixelColorGrb24>::new(1, 0);
+        let mut receiver = ArtNetReceiver::bind((Ipv4Addr::LOCALHOST, 0), frame).unwrap();
+
+        let packet = sequenced_dmx_packet(0, 10, &[0x01; 3]);
+        assert!(receiver.handle_packet(&packet, &mut driver).unwrap());
+        // Stale sequence
+        let packet = sequenced_dmx_packet(0, 9, &[0x02; 3]);
+        assert!(!receiver.handle_packet(&packet, &mut driver).unwrap());
+        assert_eq!(driver.pixel_data, Some(vec![0x01; 3]));
+        // Wrapped around
+        let packet = sequenced_dmx_packet(0, 11, &[0x03; 3]);
+        assert!(receiver.handle_packet(&packet, &mut driver).unwrap());
+        let packet = sequenced_dmx_packet(0, 240, &[0x04; 3]);
+        assert!(receiver.handle_packet(&packet, &mut driver).unwrap());
+        let packet = sequenced_dmx_packet(0, 1, &[0x05; 3]);
+        assert!(receiver.handle_packet(&packet, &mut driver).unwrap());
+        // Sequence 0 disables the check.
+        let packet = sequenced_dmx_packet(0, 0, &[0x06; 3]);
+        assert!(receiver.handle_packet(&packet, &mut driver).unwrap());
+        let packet = sequenced_dmx_packet(0, 1, &[0x07; 3]);
+        assert!(receiver.handle_packet(&packet, &mut driver).unwrap());
+        assert_eq!(driver.pixel_data, Some(vec![0x07; 3]));
+    }
+}
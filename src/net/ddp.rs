@@ -0,0 +1,329 @@
+//! Distributed Display Protocol (DDP) receiver.
+//!
+//! [`DdpReceiver`] receives the DDP packets over UDP into a [`DmxFrame`], each of which carries
+//! the channel values from a byte offset of the frame. The frame is written through the driver
+//! on the packet with the push flag, which is the last packet of a frame split into the packets.
+//!
+//! Only the data packets to the default output device are handled. Queries are not answered.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use std::net::Ipv4Addr;
+//! use ws2812_esp32_rmt_driver::driver::color::LedPixelColorGrb24;
+//! use ws2812_esp32_rmt_driver::net::ddp::{DdpReceiver, DDP_PORT};
+//! use ws2812_esp32_rmt_driver::net::DmxFrame;
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio27).unwrap();
+//!
+//! let frame = DmxFrame::<LedPixelColorGrb24>::new(300, 0);
+//! let mut receiver = DdpReceiver::bind((Ipv4Addr::UNSPECIFIED, DDP_PORT), frame).unwrap();
+//! receiver.run(&mut driver).unwrap();
+//! ```
+
+use super::{DmxFrame, ReceiveError, UdpReceiver};
+use crate::driver::color::LedPixelColor;
+use crate::driver::LedStripWrite;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::net::{ToSocketAddrs, UdpSocket};
+
+/// Default UDP port of DDP
+pub const DDP_PORT: u16 = 4048;
+/// Destination ID of the default output device
+pub const DESTINATION_DISPLAY: u8 = 1;
+/// Destination ID of all the devices
+pub const DESTINATION_ALL: u8 = 255;
+
+const FLAG_VERSION_MASK: u8 = 0xC0;
+const FLAG_VERSION_1: u8 = 0x40;
+const FLAG_TIMECODE: u8 = 0x10;
+const FLAG_STORAGE: u8 = 0x08;
+const FLAG_REPLY: u8 = 0x04;
+const FLAG_QUERY: u8 = 0x02;
+const FLAG_PUSH: u8 = 0x01;
+
+/// Byte count of the header without timecode
+const HEADER_LEN: usize = 10;
+/// Byte count of the timecode following the header
+const TIMECODE_LEN: usize = 4;
+/// Maximum byte count of a packet received
+const MAX_PACKET_LEN: usize = 1500;
+
+/// DDP packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DdpPacket<'a> {
+    /// `true` if the frame shall be shown after this packet
+    pub push: bool,
+    /// `true` if the packet is a query, and `data` is empty
+    pub query: bool,
+    /// `true` if the packet is a reply from a display
+    pub reply: bool,
+    /// `true` if the data is to be stored, not to be shown
+    pub storage: bool,
+    /// Sequence number, 1 to 15, or 0 if not used
+    pub sequence: u8,
+    /// Data type
+    pub data_type: u8,
+    /// Destination ID
+    pub destination: u8,
+    /// Byte offset of the data in the frame
+    pub offset: u32,
+    /// Data
+    pub data: &'a [u8],
+}
+
+impl<'a> DdpPacket<'a> {
+    /// Parses the packet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the packet is not a DDP version 1 packet.
+    pub fn parse(packet: &'a [u8]) -> Result<Self, DdpError> {
+        let Some(header) = packet.first_chunk::<HEADER_LEN>() else {
+            return Err(DdpError::InvalidPacket);
+        };
+        let flags = header[0];
+        if flags & FLAG_VERSION_MASK != FLAG_VERSION_1 {
+            return Err(DdpError::UnsupportedVersion(flags >> 6));
+        }
+        let data_offset = if flags & FLAG_TIMECODE != 0 {
+            HEADER_LEN + TIMECODE_LEN
+        } else {
+            HEADER_LEN
+        };
+        let len = u16::from_be_bytes([header[8], header[9]]) as usize;
+        let data = packet
+            .get(data_offset..data_offset + len)
+            .ok_or(DdpError::InvalidPacket)?;
+        Ok(Self {
+            push: flags & FLAG_PUSH != 0,
+            query: flags & FLAG_QUERY != 0,
+            reply: flags & FLAG_REPLY != 0,
+            storage: flags & FLAG_STORAGE != 0,
+            sequence: header[1] & 0x0F,
+            data_type: header[2],
+            destination: header[3],
+            offset: u32::from_be_bytes([header[4], header[5], header[6], header[7]]),
+            data,
+        })
+    }
+}
+
+/// DDP packet parse error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DdpError {
+    /// The packet is truncated.
+    InvalidPacket,
+    /// The version of the protocol is not supported.
+    UnsupportedVersion(u8),
+}
+
+impl Error for DdpError {}
+
+impl fmt::Display for DdpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPacket => write!(f, "invalid DDP packet"),
+            Self::UnsupportedVersion(v) => write!(f, "DDP version {} is not supported", v),
+        }
+    }
+}
+
+/// DDP receiver writing the frame through the driver
+#[derive(Debug)]
+pub struct DdpReceiver<C: LedPixelColor> {
+    socket: UdpSocket,
+    frame: DmxFrame<C>,
+    buffer: Vec<u8>,
+}
+
+impl<C: LedPixelColor> DdpReceiver<C> {
+    /// Creates a receiver bound to the address, typically port [`DDP_PORT`] of all the
+    /// interfaces.
+    ///
+    /// The universes of `frame` are not used.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket could not be bound.
+    pub fn bind<A: ToSocketAddrs>(addr: A, frame: DmxFrame<C>) -> io::Result<Self> {
+        Ok(Self {
+            socket: UdpSocket::bind(addr)?,
+            frame,
+            buffer: vec![0; MAX_PACKET_LEN],
+        })
+    }
+
+    /// Returns the socket.
+    #[inline]
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Returns the frame.
+    #[inline]
+    pub fn frame(&self) -> &DmxFrame<C> {
+        &self.frame
+    }
+
+    /// Handles the packet, writing the frame through the driver if the push flag is set.
+    ///
+    /// Returns `true` if the frame is written. Invalid packets, queries, replies, storage data and
+    /// the packets to the other devices are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by `writer`.
+    pub fn handle_packet<W: LedStripWrite>(
+        &mut self,
+        packet: &[u8],
+        writer: &mut W,
+    ) -> Result<bool, W::Error> {
+        let Ok(packet) = DdpPacket::parse(packet) else {
+            return Ok(false);
+        };
+        if packet.query
+            || packet.reply
+            || packet.storage
+            || !matches!(packet.destination, DESTINATION_DISPLAY | DESTINATION_ALL)
+        {
+            return Ok(false);
+        }
+        self.frame.set_channels(packet.offset as usize, packet.data);
+        if packet.push {
+            self.frame.flush(writer)?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Receives a packet and handles it by [`Self::handle_packet()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if receiving failed or `writer` returned an error.
+    pub fn receive<W: LedStripWrite>(
+        &mut self,
+        writer: &mut W,
+    ) -> Result<bool, ReceiveError<W::Error>> {
+        self.receive_packet(writer)
+    }
+
+    /// Receives and handles the packets until an error occurs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if receiving failed or `writer` returned an error.
+    pub fn run<W: LedStripWrite>(&mut self, writer: &mut W) -> Result<(), ReceiveError<W::Error>> {
+        self.run_loop(writer)
+    }
+}
+
+impl<C: LedPixelColor> UdpReceiver for DdpReceiver<C> {
+    fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    fn buffer_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buffer
+    }
+
+    fn handle<W: LedStripWrite>(
+        &mut self,
+        packet: &[u8],
+        writer: &mut W,
+    ) -> Result<bool, W::Error> {
+        self.handle_packet(packet, writer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::LedPixelColorGrb24;
+    use crate::driver::Ws2812Esp32RmtDriver;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+    use std::net::Ipv4Addr;
+
+    fn packet(flags: u8, offset: u32, data: &[u8]) -> Vec<u8> {
+        let mut packet = vec![FLAG_VERSION_1 | flags, 0x01, 0x0B, DESTINATION_DISPLAY];
+        packet.extend_from_slice(&offset.to_be_bytes());
+        packet.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        packet.extend_from_slice(data);
+        packet
+    }
+
+    #[test]
+    fn test_ddp_packet_parse() {
+        let data = packet(FLAG_PUSH, 3, &[0x01, 0x02]);
+        assert_eq!(
+            DdpPacket::parse(&data),
+            Ok(DdpPacket {
+                push: true,
+                query: false,
+                reply: false,
+                storage: false,
+                sequence: 1,
+                data_type: 0x0B,
+                destination: DESTINATION_DISPLAY,
+                offset: 3,
+                data: &[0x01, 0x02],
+            })
+        );
+
+        let mut timecode = packet(FLAG_TIMECODE, 0, &[0x01]);
+        timecode.splice(HEADER_LEN..HEADER_LEN, [0xFF; TIMECODE_LEN]);
+        assert_eq!(DdpPacket::parse(&timecode).unwrap().data, [0x01]);
+
+        assert_eq!(
+            DdpPacket::parse(&data[..data.len() - 1]),
+            Err(DdpError::InvalidPacket)
+        );
+        let mut bad = data.clone();
+        bad[0] = 0x80;
+        assert_eq!(DdpPacket::parse(&bad), Err(DdpError::UnsupportedVersion(2)));
+    }
+
+    #[test]
+    fn test_ddp_receiver() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        let frame = DmxFrame::<LedPixelColorGrb24>::new(2, 0);
+        let mut receiver = DdpReceiver::bind((Ipv4Addr::LOCALHOST, 0), frame).unwrap();
+        let sender = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        sender
+            .connect(receiver.socket().local_addr().unwrap())
+            .unwrap();
+
+        sender.send(&packet(0, 0, &[0x01, 0x02, 0x03])).unwrap();
+        assert!(!receiver.receive(&mut driver).unwrap());
+        assert_eq!(driver.pixel_data, None);
+        sender
+            .send(&packet(FLAG_PUSH, 3, &[0x04, 0x05, 0x06]))
+            .unwrap();
+        assert!(receiver.receive(&mut driver).unwrap());
+        assert_eq!(
+            driver.pixel_data,
+            Some(vec![0x02, 0x01, 0x03, 0x05, 0x04, 0x06])
+        );
+
+        driver.pixel_data = None;
+        let query = packet(FLAG_QUERY | FLAG_PUSH, 0, &[]);
+        assert!(!receiver.handle_packet(&query, &mut driver).unwrap());
+        let mut other = packet(FLAG_PUSH, 0, &[0xFF; 3]);
+        other[3] = 2;
+        assert!(!receiver.handle_packet(&other, &mut driver).unwrap());
+        assert_eq!(driver.pixel_data, None);
+    }
+}
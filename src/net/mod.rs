@@ -0,0 +1,307 @@
+//! Network pixel protocol receivers.
+//!
+//! The receivers listen to the pixel data streamed by the lighting consoles and the pixel mapping
+//! software over the network, assemble it into a [`DmxFrame`] and write the complete frames
+//! through the driver.
+//!
+//! * [`sacn`] - E1.31 (Streaming ACN)
+//! * [`artnet`] - Art-Net
+//! * [`ddp`] - Distributed Display Protocol (DDP)
+//! * [`wled`] - WLED UDP realtime (WARLS, DRGB, DRGBW and DNRGB)
+//! * [`opc`] - Open Pixel Control (OPC)
+
+pub mod artnet;
+pub mod ddp;
+pub mod opc;
+pub mod sacn;
+pub mod wled;
+
+use crate::driver::color::LedPixelColor;
+use crate::driver::LedStripWrite;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::net::UdpSocket;
+
+/// DMX channel count of a universe
+pub const DMX_CHANNELS: usize = 512;
+
+/// Frame of the LED pixels assembled from DMX universes.
+///
+/// The DMX channels are mapped onto the LED pixels in R, G, B (and W) order from the first
+/// channel of the first universe, and each universe carries the channels of the whole LED pixels
+/// fitting in it, e.g. 170 RGB LED pixels (510 channels) or 128 RGBW LED pixels.
+///
+/// * `C` - the LED pixel color type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DmxFrame<C: LedPixelColor> {
+    pixels: Vec<C>,
+    start_universe: u16,
+    channels_per_universe: usize,
+    received: Vec<bool>,
+}
+
+impl<C: LedPixelColor> DmxFrame<C> {
+    /// Creates a black frame of `pixel_len` LED pixels mapped from `start_universe`.
+    pub fn new(pixel_len: usize, start_universe: u16) -> Self {
+        let mut frame = Self {
+            pixels: core::iter::repeat_with(|| C::new_with_rgbw(0, 0, 0, 0))
+                .take(pixel_len)
+                .collect(),
+            start_universe,
+            channels_per_universe: 0,
+            received: Vec::new(),
+        };
+        frame.set_channels_per_universe(DMX_CHANNELS / C::BPP * C::BPP);
+        frame
+    }
+
+    /// Sets the number of the channels mapped from a universe.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channels` is zero or more than [`DMX_CHANNELS`].
+    pub fn set_channels_per_universe(&mut self, channels: usize) {
+        assert!(
+            (1..=DMX_CHANNELS).contains(&channels),
+            "channels per universe shall be 1 to {}",
+            DMX_CHANNELS
+        );
+        self.channels_per_universe = channels;
+        let channel_len = self.pixels.len() * C::BPP;
+        self.received = vec![false; channel_len.div_ceil(channels)];
+    }
+
+    /// Returns the first universe.
+    #[inline]
+    pub fn start_universe(&self) -> u16 {
+        self.start_universe
+    }
+
+    /// Returns the number of the universes of the frame.
+    #[inline]
+    pub fn universe_count(&self) -> usize {
+        self.received.len()
+    }
+
+    /// Returns `true` if the universe is mapped onto the frame.
+    pub fn contains_universe(&self, universe: u16) -> bool {
+        universe
+            .checked_sub(self.start_universe)
+            .is_some_and(|index| (index as usize) < self.received.len())
+    }
+
+    /// Returns the LED pixels.
+    #[inline]
+    pub fn pixels(&self) -> &[C] {
+        &self.pixels
+    }
+
+    /// Sets the DMX data of the universe, starting from the first channel without START code.
+    ///
+    /// Returns `false` if the universe is not mapped onto the frame.
+    pub fn set_universe(&mut self, universe: u16, data: &[u8]) -> bool {
+        if !self.contains_universe(universe) {
+            return false;
+        }
+        let index = (universe - self.start_universe) as usize;
+        let len = data.len().min(self.channels_per_universe);
+        self.set_channels(index * self.channels_per_universe, &data[..len]);
+        self.received[index] = true;
+        true
+    }
+
+    /// Sets the channel values from the `offset`-th channel of the frame.
+    ///
+    /// The channels beyond the frame are ignored.
+    pub fn set_channels(&mut self, offset: usize, data: &[u8]) {
+        for (i, &v) in data.iter().enumerate() {
+            let Some(channel) = offset.checked_add(i) else {
+                break;
+            };
+            let Some(pixel) = self.pixels.get_mut(channel / C::BPP) else {
+                break;
+            };
+            let mut rgbw = [pixel.r(), pixel.g(), pixel.b(), pixel.w()];
+            rgbw[channel % C::BPP] = v;
+            *pixel = C::new_with_rgbw(rgbw[0], rgbw[1], rgbw[2], rgbw[3]);
+        }
+    }
+
+    /// Sets the color of the `index`-th LED pixel.
+    ///
+    /// Returns `false` if the LED pixel is beyond the frame.
+    pub fn set_pixel(&mut self, index: usize, color: C) -> bool {
+        let Some(pixel) = self.pixels.get_mut(index) else {
+            return false;
+        };
+        *pixel = color;
+        true
+    }
+
+    /// Returns `true` if all the universes have been received since the last flush.
+    pub fn is_complete(&self) -> bool {
+        self.received.iter().all(|&received| received)
+    }
+
+    /// Writes the frame through the driver and restarts the reception of the universes.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by `writer`.
+    pub fn flush<W: LedStripWrite>(&mut self, writer: &mut W) -> Result<(), W::Error> {
+        self.received.fill(false);
+        writer.write_blocking(
+            self.pixels
+                .iter()
+                .flat_map(|color| color.as_ref().iter().copied()),
+        )
+    }
+}
+
+/// Error of a receiver
+#[derive(Debug)]
+pub enum ReceiveError<E> {
+    /// Network I/O error
+    Io(io::Error),
+    /// Error returned by the driver
+    Write(E),
+}
+
+impl<E: Error + 'static> Error for ReceiveError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Write(e) => Some(e),
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for ReceiveError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "network error: {}", e),
+            Self::Write(e) => write!(f, "write error: {}", e),
+        }
+    }
+}
+
+impl<E> From<io::Error> for ReceiveError<E> {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Calls `f` with the buffer moved out of `owner`, and moves it back.
+///
+/// `f` can then borrow `owner` mutably together with the buffer.
+pub(crate) fn with_buffer<T, R>(
+    owner: &mut T,
+    buffer: fn(&mut T) -> &mut Vec<u8>,
+    f: impl FnOnce(&mut T, &mut Vec<u8>) -> R,
+) -> R {
+    let mut taken = core::mem::take(buffer(owner));
+    let result = f(owner, &mut taken);
+    *buffer(owner) = taken;
+    result
+}
+
+/// Receiver of the packets over a UDP socket
+pub(crate) trait UdpReceiver {
+    /// Returns the socket.
+    fn socket(&self) -> &UdpSocket;
+
+    /// Returns the buffer to receive the packets into.
+    fn buffer_mut(&mut self) -> &mut Vec<u8>;
+
+    /// Handles the packet, returning `true` if the frame is written.
+    fn handle<W: LedStripWrite>(&mut self, packet: &[u8], writer: &mut W)
+        -> Result<bool, W::Error>;
+
+    /// Receives a packet and handles it.
+    fn receive_packet<W: LedStripWrite>(
+        &mut self,
+        writer: &mut W,
+    ) -> Result<bool, ReceiveError<W::Error>>
+    where
+        Self: Sized,
+    {
+        with_buffer(self, Self::buffer_mut, |receiver, buffer| {
+            let len = receiver.socket().recv(buffer)?;
+            receiver
+                .handle(&buffer[..len], writer)
+                .map_err(ReceiveError::Write)
+        })
+    }
+
+    /// Receives and handles the packets until an error occurs.
+    fn run_loop<W: LedStripWrite>(&mut self, writer: &mut W) -> Result<(), ReceiveError<W::Error>>
+    where
+        Self: Sized,
+    {
+        loop {
+            self.receive_packet(writer)?;
+        }
+    }
+}
+
+/// Returns `true` if the sequence number is older than the last one.
+///
+/// The packets whose sequence numbers are behind the last one by 0 to 19 are discarded, as
+/// specified by E1.31 and applied to Art-Net as well.
+pub(crate) fn is_out_of_order(last: Option<u8>, sequence: u8) -> bool {
+    last.is_some_and(|last| (-19..=0).contains(&(sequence.wrapping_sub(last) as i8)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::{LedPixelColorGrb24, LedPixelColorGrbw32};
+
+    #[test]
+    fn test_dmx_frame_universes() {
+        let mut frame = DmxFrame::<LedPixelColorGrb24>::new(200, 1);
+        assert_eq!(frame.universe_count(), 2);
+        assert!(!frame.contains_universe(0));
+        assert!(frame.contains_universe(2));
+        assert!(!frame.contains_universe(3));
+
+        assert!(frame.set_universe(2, &[0x01, 0x02, 0x03, 0x04]));
+        assert!(!frame.is_complete());
+        assert!(frame.set_universe(1, &[0x10; 512]));
+        assert!(frame.is_complete());
+        assert!(!frame.set_universe(3, &[0xFF; 3]));
+
+        assert_eq!(frame.pixels()[169].as_ref(), [0x10; 3]);
+        assert_eq!(frame.pixels()[170].as_ref(), [0x02, 0x01, 0x03]);
+        assert_eq!(frame.pixels()[171].as_ref(), [0x00, 0x04, 0x00]);
+
+        let frame = DmxFrame::<LedPixelColorGrbw32>::new(200, 1);
+        assert_eq!(frame.universe_count(), 2);
+        let mut frame = DmxFrame::<LedPixelColorGrb24>::new(200, 1);
+        frame.set_channels_per_universe(3);
+        assert_eq!(frame.universe_count(), 200);
+    }
+
+    #[test]
+    fn test_dmx_frame_set_channels() {
+        let mut frame = DmxFrame::<LedPixelColorGrbw32>::new(2, 1);
+        frame.set_channels(2, &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]);
+        assert_eq!(frame.pixels()[0].as_ref(), [0x00, 0x00, 0x01, 0x02]);
+        assert_eq!(frame.pixels()[1].as_ref(), [0x04, 0x03, 0x05, 0x06]);
+
+        // Beyond the frame, near the end of the address space
+        frame.set_channels(usize::MAX - 1, &[0xFF; 4]);
+        assert_eq!(frame.pixels()[1].as_ref(), [0x04, 0x03, 0x05, 0x06]);
+    }
+
+    #[test]
+    fn test_is_out_of_order() {
+        assert!(!is_out_of_order(None, 0));
+        assert!(!is_out_of_order(Some(10), 11));
+        assert!(is_out_of_order(Some(10), 10));
+        assert!(is_out_of_order(Some(10), 247));
+        assert!(!is_out_of_order(Some(10), 246));
+        assert!(!is_out_of_order(Some(255), 0));
+    }
+}
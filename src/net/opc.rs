@@ -0,0 +1,294 @@
+//! Open Pixel Control (OPC) server.
+//!
+//! [`OpcServer`] accepts the OPC clients over TCP, e.g. Processing and openFrameworks sketches,
+//! and writes the frame of a strip through its driver on each "set pixel colors" message to the
+//! OPC channel of the strip. Channel 0 is the broadcast to all the strips.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use std::net::Ipv4Addr;
+//! use ws2812_esp32_rmt_driver::driver::color::LedPixelColorGrb24;
+//! use ws2812_esp32_rmt_driver::net::opc::{OpcServer, OPC_PORT};
+//! use ws2812_esp32_rmt_driver::net::DmxFrame;
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let driver1 = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio26).unwrap();
+//! let driver2 = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel1, peripherals.pins.gpio27).unwrap();
+//!
+//! let mut server = OpcServer::bind((Ipv4Addr::UNSPECIFIED, OPC_PORT)).unwrap();
+//! server.add_strip(1, DmxFrame::<LedPixelColorGrb24>::new(60, 0), driver1);
+//! server.add_strip(2, DmxFrame::<LedPixelColorGrb24>::new(60, 0), driver2);
+//! server.run().unwrap();
+//! ```
+
+use super::{with_buffer, DmxFrame, ReceiveError};
+use crate::driver::color::LedPixelColor;
+use crate::driver::LedStripWrite;
+use std::io::{self, ErrorKind, Read};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+/// Default TCP port of OPC
+pub const OPC_PORT: u16 = 7890;
+/// Channel of the broadcast to all the strips
+pub const CHANNEL_BROADCAST: u8 = 0;
+/// Command to set the 8-bit RGB colors of the LED pixels
+pub const COMMAND_SET_PIXEL_COLORS: u8 = 0;
+
+/// Byte count of the message header
+const HEADER_LEN: usize = 4;
+
+/// OPC message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpcMessage<'a> {
+    /// Channel, [`CHANNEL_BROADCAST`] for all the strips
+    pub channel: u8,
+    /// Command
+    pub command: u8,
+    /// Data
+    pub data: &'a [u8],
+}
+
+impl<'a> OpcMessage<'a> {
+    /// Reads a message from the stream into `buffer`.
+    ///
+    /// Returns `None` if the stream is closed before a message.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading failed or the stream is closed in a message.
+    pub fn read<R: Read>(reader: &mut R, buffer: &'a mut Vec<u8>) -> io::Result<Option<Self>> {
+        let mut header = [0; HEADER_LEN];
+        let mut filled = 0;
+        while filled < HEADER_LEN {
+            match reader.read(&mut header[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        let len = u16::from_be_bytes([header[2], header[3]]) as usize;
+        buffer.resize(len, 0);
+        reader.read_exact(buffer)?;
+        Ok(Some(Self {
+            channel: header[0],
+            command: header[1],
+            data: buffer,
+        }))
+    }
+}
+
+/// Strip of an OPC channel
+#[derive(Debug)]
+struct OpcStrip<C: LedPixelColor, W: LedStripWrite> {
+    channel: u8,
+    frame: DmxFrame<C>,
+    writer: W,
+}
+
+/// OPC server writing the frames of the strips through the drivers
+///
+/// The clients are served one by one.
+#[derive(Debug)]
+pub struct OpcServer<C: LedPixelColor, W: LedStripWrite> {
+    listener: TcpListener,
+    strips: Vec<OpcStrip<C, W>>,
+    buffer: Vec<u8>,
+}
+
+impl<C: LedPixelColor, W: LedStripWrite> OpcServer<C, W> {
+    /// Creates a server bound to the address, typically port [`OPC_PORT`] of all the interfaces.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the listener could not be bound.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            strips: Vec::new(),
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Adds the strip of the OPC channel, written through `writer`.
+    ///
+    /// The universes of `frame` are not used.
+    pub fn add_strip(&mut self, channel: u8, frame: DmxFrame<C>, writer: W) {
+        self.strips.push(OpcStrip {
+            channel,
+            frame,
+            writer,
+        });
+    }
+
+    /// Returns the listener.
+    #[inline]
+    pub fn listener(&self) -> &TcpListener {
+        &self.listener
+    }
+
+    /// Returns the frame of the first strip of the OPC channel.
+    pub fn frame(&self, channel: u8) -> Option<&DmxFrame<C>> {
+        self.strips
+            .iter()
+            .find(|strip| strip.channel == channel)
+            .map(|strip| &strip.frame)
+    }
+
+    /// Handles the message, writing the frames of the strips of the channel through the drivers.
+    ///
+    /// Returns the number of the strips written. The commands other than
+    /// [`COMMAND_SET_PIXEL_COLORS`] are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error returned by the drivers.
+    pub fn handle_message(&mut self, message: &OpcMessage) -> Result<usize, W::Error> {
+        if message.command != COMMAND_SET_PIXEL_COLORS {
+            return Ok(0);
+        }
+        let mut written = 0;
+        for strip in &mut self.strips {
+            if message.channel != CHANNEL_BROADCAST && message.channel != strip.channel {
+                continue;
+            }
+            for (index, rgb) in message.data.chunks_exact(3).enumerate() {
+                if !strip
+                    .frame
+                    .set_pixel(index, C::new_with_rgb(rgb[0], rgb[1], rgb[2]))
+                {
+                    break;
+                }
+            }
+            strip.frame.flush(&mut strip.writer)?;
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    /// Serves the client until it closes the connection.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading failed or a driver returned an error.
+    pub fn serve(&mut self, mut stream: TcpStream) -> Result<(), ReceiveError<W::Error>> {
+        with_buffer(
+            self,
+            |server| &mut server.buffer,
+            |server, buffer| loop {
+                match OpcMessage::read(&mut stream, buffer) {
+                    Ok(Some(message)) => {
+                        if let Err(e) = server.handle_message(&message) {
+                            break Err(ReceiveError::Write(e));
+                        }
+                    }
+                    Ok(None) => break Ok(()),
+                    Err(e) => break Err(ReceiveError::Io(e)),
+                }
+            },
+        )
+    }
+
+    /// Accepts and serves the clients until an error occurs.
+    ///
+    /// Reading errors of a client only close its connection.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if accepting failed or a driver returned an error.
+    pub fn run(&mut self) -> Result<(), ReceiveError<W::Error>> {
+        loop {
+            let (stream, _) = self.listener.accept()?;
+            match self.serve(stream) {
+                Ok(()) | Err(ReceiveError::Io(_)) => (),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::LedPixelColorGrb24;
+    use crate::driver::Ws2812Esp32RmtDriver;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+    use std::io::Write;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_opc_message_read() {
+        let mut buffer = Vec::new();
+        let mut reader = &[1, 0, 0, 3, 0x01, 0x02, 0x03, 2, 255, 0, 0][..];
+        assert_eq!(
+            OpcMessage::read(&mut reader, &mut buffer).unwrap(),
+            Some(OpcMessage {
+                channel: 1,
+                command: 0,
+                data: &[0x01, 0x02, 0x03],
+            })
+        );
+        assert_eq!(
+            OpcMessage::read(&mut reader, &mut buffer).unwrap(),
+            Some(OpcMessage {
+                channel: 2,
+                command: 255,
+                data: &[],
+            })
+        );
+        assert_eq!(OpcMessage::read(&mut reader, &mut buffer).unwrap(), None);
+
+        let mut reader = &[1, 0, 0, 3, 0x01][..];
+        assert!(OpcMessage::read(&mut reader, &mut buffer).is_err());
+        // Closed in the header
+        let mut reader = &[1, 0][..];
+        assert_eq!(
+            OpcMessage::read(&mut reader, &mut buffer)
+                .unwrap_err()
+                .kind(),
+            ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn test_opc_server() {
+        let peripherals = Peripherals::take().unwrap();
+        let mut driver1 =
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+        let mut driver2 =
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel1, peripherals.pins.gpio1).unwrap();
+
+        {
+            let mut server = OpcServer::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+            server.add_strip(1, DmxFrame::<LedPixelColorGrb24>::new(1, 0), &mut driver1);
+            server.add_strip(2, DmxFrame::<LedPixelColorGrb24>::new(2, 0), &mut driver2);
+
+            let mut client = TcpStream::connect(server.listener().local_addr().unwrap()).unwrap();
+            client
+                .write_all(&[2, 0, 0, 6, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06])
+                .unwrap();
+            client.write_all(&[0, 0, 0, 3, 0x07, 0x08, 0x09]).unwrap();
+            drop(client);
+
+            let (stream, _) = server.listener().accept().unwrap();
+            server.serve(stream).unwrap();
+            assert_eq!(
+                server.frame(1).unwrap().pixels()[0].as_ref(),
+                [0x08, 0x07, 0x09]
+            );
+        }
+
+        assert_eq!(driver1.pixel_data, Some(vec![0x08, 0x07, 0x09]));
+        assert_eq!(
+            driver2.pixel_data,
+            Some(vec![0x08, 0x07, 0x09, 0x05, 0x04, 0x06])
+        );
+    }
+}
@@ -0,0 +1,583 @@
+//! E1.31 (Streaming ACN) receiver.
+//!
+//! [`SacnReceiver`] receives the E1.31 data packets of the universes of a [`DmxFrame`] over UDP
+//! and writes the frame through the driver once all of its universes are received, or on the
+//! synchronization packet if the source uses universe synchronization.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use std::net::Ipv4Addr;
+//! use ws2812_esp32_rmt_driver::driver::color::LedPixelColorGrb24;
+//! use ws2812_esp32_rmt_driver::net::sacn::{SacnReceiver, SACN_PORT};
+//! use ws2812_esp32_rmt_driver::net::DmxFrame;
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio27).unwrap();
+//!
+//! // 300 RGB LED pixels on the universes 1 and 2
+//! let frame = DmxFrame::<LedPixelColorGrb24>::new(300, 1);
+//! let mut receiver = SacnReceiver::bind((Ipv4Addr::UNSPECIFIED, SACN_PORT), frame).unwrap();
+//! receiver.join_multicast(Ipv4Addr::UNSPECIFIED).unwrap();
+//! receiver.run(&mut driver).unwrap();
+//! ```
+
+use super::{is_out_of_order, DmxFrame, ReceiveError, UdpReceiver, DMX_CHANNELS};
+use crate::driver::color::LedPixelColor;
+use crate::driver::LedStripWrite;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::net::{Ipv4Addr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// Default UDP port of E1.31
+pub const SACN_PORT: u16 = 5568;
+
+/// Duration without data packets of a universe to consider its source lost (E1.31 network data
+/// loss timeout)
+pub const NETWORK_DATA_LOSS_TIMEOUT: Duration = Duration::from_millis(2500);
+
+/// ACN packet identifier of the root layer
+const ACN_PACKET_IDENTIFIER: [u8; 12] = *b"ASC-E1.17\0\0\0";
+const VECTOR_ROOT_E131_DATA: u32 = 0x0000_0004;
+const VECTOR_ROOT_E131_EXTENDED: u32 = 0x0000_0008;
+const VECTOR_E131_DATA_PACKET: u32 = 0x0000_0002;
+const VECTOR_E131_EXTENDED_SYNCHRONIZATION: u32 = 0x0000_0001;
+const VECTOR_DMP_SET_PROPERTY: u8 = 0x02;
+/// Options bit of the preview data, not to be shown on the LED pixels
+const OPTION_PREVIEW_DATA: u8 = 0x80;
+/// Options bit of the stream termination
+const OPTION_STREAM_TERMINATED: u8 = 0x40;
+
+/// Byte offset of the DMX data (after the START code) in a data packet
+const DATA_OFFSET: usize = 126;
+/// Byte count of a synchronization packet
+const SYNC_PACKET_LEN: usize = 49;
+
+/// Returns the multicast address of the universe.
+pub fn multicast_addr(universe: u16) -> Ipv4Addr {
+    let [hi, lo] = universe.to_be_bytes();
+    Ipv4Addr::new(239, 255, hi, lo)
+}
+
+/// E1.31 packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SacnPacket<'a> {
+    /// Data packet carrying the DMX data of a universe
+    Data {
+        /// Universe of the data
+        universe: u16,
+        /// Priority of the source, 0 to 200
+        priority: u8,
+        /// Universe of the synchronization packets to wait for, 0 if not synchronized
+        sync_address: u16,
+        /// Sequence number
+        sequence: u8,
+        /// `true` if the data is for the visualization, not to be shown on the LED pixels
+        preview: bool,
+        /// `true` if the source stops sending to the universe
+        terminated: bool,
+        /// DMX data from the first channel, without START code
+        data: &'a [u8],
+    },
+    /// Synchronization packet to show the waiting data
+    Sync {
+        /// Universe of the synchronization
+        sync_address: u16,
+        /// Sequence number
+        sequence: u8,
+    },
+}
+
+impl<'a> SacnPacket<'a> {
+    /// Parses the packet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the packet is not an E1.31 data or synchronization packet, or if the
+    /// DMX data does not start with the null START code.
+    pub fn parse(packet: &'a [u8]) -> Result<Self, SacnError> {
+        let u16_at = |i: usize| u16::from_be_bytes([packet[i], packet[i + 1]]);
+        let u32_at =
+            |i: usize| u32::from_be_bytes([packet[i], packet[i + 1], packet[i + 2], packet[i + 3]]);
+
+        if packet.len() < SYNC_PACKET_LEN
+            || u16_at(0) != 0x0010
+            || packet[4..16] != ACN_PACKET_IDENTIFIER
+        {
+            return Err(SacnError::InvalidPacket);
+        }
+        match (u32_at(18), u32_at(40)) {
+            (VECTOR_ROOT_E131_DATA, VECTOR_E131_DATA_PACKET) => {
+                if packet.len() < DATA_OFFSET
+                    || packet[117] != VECTOR_DMP_SET_PROPERTY
+                    || packet[118] != 0xA1
+                {
+                    return Err(SacnError::InvalidPacket);
+                }
+                let count = u16_at(123) as usize;
+                let end = DATA_OFFSET - 1 + count;
+                if count == 0 || count > DMX_CHANNELS + 1 || end > packet.len() {
+                    return Err(SacnError::InvalidPacket);
+                }
+                if packet[125] != 0x00 {
+                    return Err(SacnError::UnsupportedStartCode(packet[125]));
+                }
+                Ok(Self::Data {
+                    universe: u16_at(113),
+                    priority: packet[108],
+                    sync_address: u16_at(109),
+                    sequence: packet[111],
+                    preview: packet[112] & OPTION_PREVIEW_DATA != 0,
+                    terminated: packet[112] & OPTION_STREAM_TERMINATED != 0,
+                    data: &packet[DATA_OFFSET..end],
+                })
+            }
+            (VECTOR_ROOT_E131_EXTENDED, VECTOR_E131_EXTENDED_SYNCHRONIZATION) => Ok(Self::Sync {
+                sync_address: u16_at(45),
+                sequence: packet[44],
+            }),
+            _ => Err(SacnError::InvalidPacket),
+        }
+    }
+}
+
+/// E1.31 packet parse error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SacnError {
+    /// The packet is not an E1.31 data or synchronization packet.
+    InvalidPacket,
+    /// The DMX data has a START code other than the null START code.
+    UnsupportedStartCode(u8),
+}
+
+impl Error for SacnError {}
+
+impl fmt::Display for SacnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPacket => write!(f, "invalid E1.31 packet"),
+            Self::UnsupportedStartCode(code) => write!(f, "unsupported START code {:#04x}", code),
+        }
+    }
+}
+
+/// Source shown on a universe
+#[derive(Debug, Clone, Copy, Default)]
+struct UniverseSource {
+    priority: u8,
+    sequence: Option<u8>,
+    last_seen: Option<Instant>,
+    /// Universe of the synchronization packets the data waits for, 0 if not synchronized
+    sync_address: u16,
+}
+
+impl UniverseSource {
+    /// Returns `true` if no data packet has been received for [`NETWORK_DATA_LOSS_TIMEOUT`].
+    fn is_lost(&self, now: Instant) -> bool {
+        self.last_seen.is_none_or(|last_seen| {
+            now.saturating_duration_since(last_seen) >= NETWORK_DATA_LOSS_TIMEOUT
+        })
+    }
+}
+
+/// E1.31 receiver writing the frame through the driver
+///
+/// Only the sources of the highest priority are shown, per universe. When the source of a
+/// universe stops sending for [`NETWORK_DATA_LOSS_TIMEOUT`] without terminating the stream,
+/// e.g. on a power loss, the sources of the lower priorities take over. The receiver does not
+/// track multiple sources of the same priority.
+///
+/// The universes synchronized by a source are shown on its synchronization packet. The other
+/// universes are shown as soon as the frame is complete, unless a universe waits for a
+/// synchronization packet.
+#[derive(Debug)]
+pub struct SacnReceiver<C: LedPixelColor> {
+    socket: UdpSocket,
+    frame: DmxFrame<C>,
+    buffer: Vec<u8>,
+    sources: Vec<UniverseSource>,
+    sync_sequence: Option<u8>,
+}
+
+impl<C: LedPixelColor> SacnReceiver<C> {
+    /// Creates a receiver bound to the address, typically port [`SACN_PORT`] of all the interfaces.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket could not be bound.
+    pub fn bind<A: ToSocketAddrs>(addr: A, frame: DmxFrame<C>) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        let universe_count = frame.universe_count();
+        Ok(Self {
+            socket,
+            frame,
+            buffer: vec![0; DATA_OFFSET + DMX_CHANNELS],
+            sources: vec![UniverseSource::default(); universe_count],
+            sync_sequence: None,
+        })
+    }
+
+    /// Joins the multicast groups of the universes of the frame on the interface.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if joining a group failed.
+    pub fn join_multicast(&self, interface: Ipv4Addr) -> io::Result<()> {
+        let start = self.frame.start_universe();
+        for universe in (start..).take(self.frame.universe_count()) {
+            self.socket
+                .join_multicast_v4(&multicast_addr(universe), &interface)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the socket.
+    #[inline]
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Returns the frame.
+    #[inline]
+    pub fn frame(&self) -> &DmxFrame<C> {
+        &self.frame
+    }
+
+    /// Handles the packet, writing the frame through the driver if it is ready to be shown.
+    ///
+    /// Returns `true` if the frame is written. Invalid packets and the packets of the other
+    /// universes are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by `writer`.
+    #[inline]
+    pub fn handle_packet<W: LedStripWrite>(
+        &mut self,
+        packet: &[u8],
+        writer: &mut W,
+    ) -> Result<bool, W::Error> {
+        self.handle_packet_at(packet, Instant::now(), writer)
+    }
+
+    /// Handles the packet received at `now`, as [`Self::handle_packet()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by `writer`.
+    pub fn handle_packet_at<W: LedStripWrite>(
+        &mut self,
+        packet: &[u8],
+        now: Instant,
+        writer: &mut W,
+    ) -> Result<bool, W::Error> {
+        match SacnPacket::parse(packet) {
+            Ok(SacnPacket::Data {
+                universe,
+                priority,
+                sync_address,
+                sequence,
+                preview,
+                terminated,
+                data,
+            }) => {
+                if preview || !self.frame.contains_universe(universe) {
+                    return Ok(false);
+                }
+                let source = &mut self.sources[(universe - self.frame.start_universe()) as usize];
+                if source.is_lost(now) {
+                    *source = UniverseSource::default();
+                }
+                if priority < source.priority {
+                    return Ok(false);
+                }
+                if terminated {
+                    *source = UniverseSource::default();
+                    return Ok(false);
+                }
+                if priority > source.priority {
+                    // The sequence numbers of the other source do not apply.
+                    source.sequence = None;
+                }
+                if is_out_of_order(source.sequence, sequence) {
+                    return Ok(false);
+                }
+                *source = UniverseSource {
+                    priority,
+                    sequence: Some(sequence),
+                    last_seen: Some(now),
+                    sync_address,
+                };
+                self.frame.set_universe(universe, data);
+                if self.sources.iter().all(|source| source.sync_address == 0)
+                    && self.frame.is_complete()
+                {
+                    self.frame.flush(writer)?;
+                    return Ok(true);
+                }
+                Ok(false)
+            }
+            Ok(SacnPacket::Sync {
+                sync_address,
+                sequence,
+            }) => {
+                if sync_address == 0
+                    || !self
+                        .sources
+                        .iter()
+                        .any(|source| source.sync_address == sync_address)
+                    || is_out_of_order(self.sync_sequence, sequence)
+                {
+                    return Ok(false);
+                }
+                self.sync_sequence = Some(sequence);
+                self.frame.flush(writer)?;
+                Ok(true)
+            }
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// Receives a packet and handles it by [`Self::handle_packet()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if receiving failed or `writer` returned an error.
+    pub fn receive<W: LedStripWrite>(
+        &mut self,
+        writer: &mut W,
+    ) -> Result<bool, ReceiveError<W::Error>> {
+        self.receive_packet(writer)
+    }
+
+    /// Receives and handles the packets until an error occurs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if receiving failed or `writer` returned an error.
+    pub fn run<W: LedStripWrite>(&mut self, writer: &mut W) -> Result<(), ReceiveError<W::Error>> {
+        self.run_loop(writer)
+    }
+}
+
+impl<C: LedPixelColor> UdpReceiver for SacnReceiver<C> {
+    fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    fn buffer_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buffer
+    }
+
+    fn handle<W: LedStripWrite>(
+        &mut self,
+        packet: &[u8],
+        writer: &mut W,
+    ) -> Result<bool, W::Error> {
+        self.handle_packet(packet, writer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::LedPixelColorGrb24;
+    use crate::driver::Ws2812Esp32RmtDriver;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+
+    fn data_packet(universe: u16, sequence: u8, sync_address: u16, data: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0; DATA_OFFSET];
+        packet[0..2].copy_from_slice(&0x0010u16.to_be_bytes());
+        packet[4..16].copy_from_slice(&ACN_PACKET_IDENTIFIER);
+        packet[18..22].copy_from_slice(&VECTOR_ROOT_E131_DATA.to_be_bytes());
+        packet[40..44].copy_from_slice(&VECTOR_E131_DATA_PACKET.to_be_bytes());
+        packet[108] = 100;
+        packet[109..111].copy_from_slice(&sync_address.to_be_bytes());
+        packet[111] = sequence;
+        packet[113..115].copy_from_slice(&universe.to_be_bytes());
+        packet[117] = VECTOR_DMP_SET_PROPERTY;
+        packet[118] = 0xA1;
+        packet[121..123].copy_from_slice(&1u16.to_be_bytes());
+        packet[123..125].copy_from_slice(&(data.len() as u16 + 1).to_be_bytes());
+        packet.extend_from_slice(data);
+        packet
+    }
+
+    fn sync_packet(sync_address: u16, sequence: u8) -> Vec<u8> {
+        let mut packet = vec![0; SYNC_PACKET_LEN];
+        packet[0..2].copy_from_slice(&0x0010u16.to_be_bytes());
+        packet[4..16].copy_from_slice(&ACN_PACKET_IDENTIFIER);
+        packet[18..22].copy_from_slice(&VECTOR_ROOT_E131_EXTENDED.to_be_bytes());
+        packet[40..44].copy_from_slice(&VECTOR_E131_EXTENDED_SYNCHRONIZATION.to_be_bytes());
+        packet[44] = sequence;
+        packet[45..47].copy_from_slice(&sync_address.to_be_bytes());
+        packet
+    }
+
+    #[test]
+    fn test_sacn_packet_parse() {
+        assert_eq!(multicast_addr(0x0102), Ipv4Addr::new(239, 255, 1, 2));
+
+        let packet = data_packet(7, 3, 0, &[0x01, 0x02]);
+        assert_eq!(
+            SacnPacket::parse(&packet),
+            Ok(SacnPacket::Data {
+                universe: 7,
+                priority: 100,
+                sync_address: 0,
+                sequence: 3,
+                preview: false,
+                terminated: false,
+                data: &[0x01, 0x02],
+            })
+        );
+        assert_eq!(
+            SacnPacket::parse(&sync_packet(9, 4)),
+            Ok(SacnPacket::Sync {
+                sync_address: 9,
+                sequence: 4
+            })
+        );
+
+        assert_eq!(
+            SacnPacket::parse(&packet[..packet.len() - 1]),
+            Err(SacnError::InvalidPacket)
+        );
+        let mut bad = packet.clone();
+        bad[4] = b'X';
+        assert_eq!(SacnPacket::parse(&bad), Err(SacnError::InvalidPacket));
+        let mut bad = packet.clone();
+        bad[125] = 0xCC;
+        assert_eq!(
+            SacnPacket::parse(&bad),
+            Err(SacnError::UnsupportedStartCode(0xCC))
+        );
+    }
+
+    #[test]
+    fn test_sacn_receiver() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        let frame = DmxFrame::<LedPixelColorGrb24>::new(2, 1);
+        let mut receiver = SacnReceiver::bind((Ipv4Addr::LOCALHOST, 0), frame).unwrap();
+        let sender = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        sender
+            .connect(receiver.socket().local_addr().unwrap())
+            .unwrap();
+
+        sender
+            .send(&data_packet(1, 0, 0, &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06]))
+            .unwrap();
+        assert!(receiver.receive(&mut driver).unwrap());
+        assert_eq!(
+            driver.pixel_data,
+            Some(vec![0x02, 0x01, 0x03, 0x05, 0x04, 0x06])
+        );
+
+        // out of order
+        driver.pixel_data = None;
+        let packet = data_packet(1, 0, 0, &[0xFF; 6]);
+        assert!(!receiver.handle_packet(&packet, &mut driver).unwrap());
+        // synchronized
+        let packet = data_packet(1, 1, 100, &[0x10; 6]);
+        assert!(!receiver.handle_packet(&packet, &mut driver).unwrap());
+        assert!(!receiver
+            .handle_packet(&sync_packet(101, 0), &mut driver)
+            .unwrap());
+        assert_eq!(driver.pixel_data, None);
+        assert!(receiver
+            .handle_packet(&sync_packet(100, 0), &mut driver)
+            .unwrap());
+        assert_eq!(driver.pixel_data, Some(vec![0x10; 6]));
+    }
+
+    #[test]
+    fn test_sacn_receiver_sync_per_universe() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        let frame = DmxFrame::<LedPixelColorGrb24>::new(2 * 170, 1);
+        let mut receiver = SacnReceiver::bind((Ipv4Addr::LOCALHOST, 0), frame).unwrap();
+
+        // The universe 2 is not synchronized, but the universe 1 waits for the sync packet.
+        let packet = data_packet(1, 0, 100, &[0x10; 510]);
+        assert!(!receiver.handle_packet(&packet, &mut driver).unwrap());
+        let packet = data_packet(2, 0, 0, &[0x20; 510]);
+        assert!(!receiver.handle_packet(&packet, &mut driver).unwrap());
+        assert_eq!(driver.pixel_data, None);
+        assert!(receiver
+            .handle_packet(&sync_packet(100, 0), &mut driver)
+            .unwrap());
+        assert_eq!(
+            driver
+                .pixel_data
+                .as_deref()
+                .map(|data| (data[0], data[510])),
+            Some((0x10, 0x20))
+        );
+
+        // The universe 1 is not synchronized anymore.
+        let packet = data_packet(1, 1, 0, &[0x11; 510]);
+        assert!(!receiver.handle_packet(&packet, &mut driver).unwrap());
+        let packet = data_packet(2, 1, 0, &[0x21; 510]);
+        assert!(receiver.handle_packet(&packet, &mut driver).unwrap());
+        assert!(!receiver
+            .handle_packet(&sync_packet(100, 1), &mut driver)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_sacn_receiver_source_loss() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio1;
+        let channel = peripherals.rmt.channel1;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        let frame = DmxFrame::<LedPixelColorGrb24>::new(1, 1);
+        let mut receiver = SacnReceiver::bind((Ipv4Addr::LOCALHOST, 0), frame).unwrap();
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+
+        let mut primary = data_packet(1, 0, 0, &[0x01, 0x02, 0x03]);
+        primary[108] = 200;
+        assert!(receiver
+            .handle_packet_at(&primary, at(0), &mut driver)
+            .unwrap());
+        assert_eq!(driver.pixel_data, Some(vec![0x02, 0x01, 0x03]));
+
+        // The backup is ignored while the primary is alive.
+        let backup = data_packet(1, 50, 0, &[0x10; 3]);
+        assert!(!receiver
+            .handle_packet_at(&backup, at(1000), &mut driver)
+            .unwrap());
+        assert!(!receiver
+            .handle_packet_at(&backup, at(2499), &mut driver)
+            .unwrap());
+
+        // The primary stopped without terminating, and the backup takes over.
+        let backup = data_packet(1, 51, 0, &[0x10; 3]);
+        assert!(receiver
+            .handle_packet_at(&backup, at(2500), &mut driver)
+            .unwrap());
+        assert_eq!(driver.pixel_data, Some(vec![0x10; 3]));
+
+        // The primary is back.
+        primary[111] = 1;
+        assert!(receiver
+            .handle_packet_at(&primary, at(3000), &mut driver)
+            .unwrap());
+        assert_eq!(driver.pixel_data, Some(vec![0x02, 0x01, 0x03]));
+    }
+}
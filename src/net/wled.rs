@@ -0,0 +1,369 @@
+//! WLED UDP realtime receiver.
+//!
+//! [`WledReceiver`] receives the WLED UDP realtime packets (WARLS, DRGB, DRGBW and DNRGB) sent by
+//! the tools such as LedFx and Hyperion, and writes the frame through the driver on each packet.
+//!
+//! Each packet carries the timeout to return from the realtime mode. [`WledReceiver::is_realtime()`]
+//! tells whether the source is still streaming, so that the application can resume its own
+//! effects after the timeout.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use std::net::Ipv4Addr;
+//! use ws2812_esp32_rmt_driver::driver::color::LedPixelColorGrb24;
+//! use ws2812_esp32_rmt_driver::net::wled::{WledReceiver, WLED_PORT};
+//! use ws2812_esp32_rmt_driver::net::DmxFrame;
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio27).unwrap();
+//!
+//! let frame = DmxFrame::<LedPixelColorGrb24>::new(300, 0);
+//! let mut receiver = WledReceiver::bind((Ipv4Addr::UNSPECIFIED, WLED_PORT), frame).unwrap();
+//! receiver.run(&mut driver).unwrap();
+//! ```
+
+use super::{DmxFrame, ReceiveError, UdpReceiver};
+use crate::driver::color::LedPixelColor;
+use crate::driver::LedStripWrite;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// Default UDP port of the WLED realtime protocols
+pub const WLED_PORT: u16 = 21324;
+/// Timeout value never returning from the realtime mode
+pub const TIMEOUT_INFINITE: u8 = u8::MAX;
+
+const PROTOCOL_WARLS: u8 = 1;
+const PROTOCOL_DRGB: u8 = 2;
+const PROTOCOL_DRGBW: u8 = 3;
+const PROTOCOL_DNRGB: u8 = 4;
+
+/// Maximum byte count of a packet received
+const MAX_PACKET_LEN: usize = 1500;
+
+/// WLED UDP realtime packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WledPacket<'a> {
+    /// WARLS: LED pixel index and RGB per LED pixel, up to 255 LED pixels
+    Warls {
+        /// Timeout in seconds, [`TIMEOUT_INFINITE`] for infinite
+        timeout: u8,
+        /// Index, R, G and B of the LED pixels
+        data: &'a [u8],
+    },
+    /// DRGB: RGB of the LED pixels from the first LED pixel
+    Drgb {
+        /// Timeout in seconds, [`TIMEOUT_INFINITE`] for infinite
+        timeout: u8,
+        /// R, G and B of the LED pixels
+        data: &'a [u8],
+    },
+    /// DRGBW: RGBW of the LED pixels from the first LED pixel
+    Drgbw {
+        /// Timeout in seconds, [`TIMEOUT_INFINITE`] for infinite
+        timeout: u8,
+        /// R, G, B and W of the LED pixels
+        data: &'a [u8],
+    },
+    /// DNRGB: RGB of the LED pixels from a start index
+    Dnrgb {
+        /// Timeout in seconds, [`TIMEOUT_INFINITE`] for infinite
+        timeout: u8,
+        /// Index of the first LED pixel
+        start: u16,
+        /// R, G and B of the LED pixels
+        data: &'a [u8],
+    },
+}
+
+impl<'a> WledPacket<'a> {
+    /// Parses the packet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the packet is truncated or not a realtime packet.
+    pub fn parse(packet: &'a [u8]) -> Result<Self, WledError> {
+        let [protocol, timeout, data @ ..] = packet else {
+            return Err(WledError::InvalidPacket);
+        };
+        let timeout = *timeout;
+        match *protocol {
+            PROTOCOL_WARLS => Ok(Self::Warls { timeout, data }),
+            PROTOCOL_DRGB => Ok(Self::Drgb { timeout, data }),
+            PROTOCOL_DRGBW => Ok(Self::Drgbw { timeout, data }),
+            PROTOCOL_DNRGB => {
+                let [hi, lo, data @ ..] = data else {
+                    return Err(WledError::InvalidPacket);
+                };
+                Ok(Self::Dnrgb {
+                    timeout,
+                    start: u16::from_be_bytes([*hi, *lo]),
+                    data,
+                })
+            }
+            protocol => Err(WledError::UnsupportedProtocol(protocol)),
+        }
+    }
+
+    /// Returns the timeout in seconds, [`TIMEOUT_INFINITE`] for infinite.
+    pub fn timeout(&self) -> u8 {
+        match *self {
+            Self::Warls { timeout, .. }
+            | Self::Drgb { timeout, .. }
+            | Self::Drgbw { timeout, .. }
+            | Self::Dnrgb { timeout, .. } => timeout,
+        }
+    }
+
+    /// Returns the index and R, G, B and W of the LED pixels of the packet.
+    ///
+    /// W is zero for the RGB protocols. A trailing incomplete LED pixel is ignored.
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, [u8; 4])> + 'a {
+        let (data, stride, start, indexed): (&[u8], usize, usize, bool) = match *self {
+            Self::Warls { data, .. } => (data, 4, 0, true),
+            Self::Drgb { data, .. } => (data, 3, 0, false),
+            Self::Drgbw { data, .. } => (data, 4, 0, false),
+            Self::Dnrgb { data, start, .. } => (data, 3, start as usize, false),
+        };
+        data.chunks_exact(stride)
+            .enumerate()
+            .map(move |(i, chunk)| match (indexed, chunk) {
+                (true, &[index, r, g, b]) => (index as usize, [r, g, b, 0]),
+                (_, &[r, g, b]) => (start + i, [r, g, b, 0]),
+                (_, &[r, g, b, w]) => (start + i, [r, g, b, w]),
+                _ => unreachable!(),
+            })
+    }
+}
+
+/// WLED UDP realtime packet parse error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WledError {
+    /// The packet is truncated.
+    InvalidPacket,
+    /// The protocol is not a supported realtime protocol, e.g. WLED sync notifier.
+    UnsupportedProtocol(u8),
+}
+
+impl Error for WledError {}
+
+impl fmt::Display for WledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPacket => write!(f, "invalid WLED realtime packet"),
+            Self::UnsupportedProtocol(v) => {
+                write!(f, "WLED realtime protocol {} is not supported", v)
+            }
+        }
+    }
+}
+
+/// WLED UDP realtime receiver writing the frame through the driver
+#[derive(Debug)]
+pub struct WledReceiver<C: LedPixelColor> {
+    socket: UdpSocket,
+    frame: DmxFrame<C>,
+    buffer: Vec<u8>,
+    /// End of the realtime mode: `None` before the first packet and `Some(None)` for infinite
+    realtime_until: Option<Option<Instant>>,
+}
+
+impl<C: LedPixelColor> WledReceiver<C> {
+    /// Creates a receiver bound to the address, typically port [`WLED_PORT`] of all the
+    /// interfaces.
+    ///
+    /// The universes of `frame` are not used.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket could not be bound.
+    pub fn bind<A: ToSocketAddrs>(addr: A, frame: DmxFrame<C>) -> io::Result<Self> {
+        Ok(Self {
+            socket: UdpSocket::bind(addr)?,
+            frame,
+            buffer: vec![0; MAX_PACKET_LEN],
+            realtime_until: None,
+        })
+    }
+
+    /// Returns the socket.
+    #[inline]
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Returns the frame.
+    #[inline]
+    pub fn frame(&self) -> &DmxFrame<C> {
+        &self.frame
+    }
+
+    /// Returns `true` until the timeout of the last packet elapses.
+    pub fn is_realtime(&self) -> bool {
+        match self.realtime_until {
+            None => false,
+            Some(None) => true,
+            Some(Some(until)) => Instant::now() < until,
+        }
+    }
+
+    /// Handles the packet, writing the frame through the driver.
+    ///
+    /// Returns `true` if the frame is written. Invalid packets and unsupported packets are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by `writer`.
+    pub fn handle_packet<W: LedStripWrite>(
+        &mut self,
+        packet: &[u8],
+        writer: &mut W,
+    ) -> Result<bool, W::Error> {
+        let Ok(packet) = WledPacket::parse(packet) else {
+            return Ok(false);
+        };
+        self.realtime_until = Some(match packet.timeout() {
+            TIMEOUT_INFINITE => None,
+            timeout => Some(Instant::now() + Duration::from_secs(timeout.into())),
+        });
+        for (index, [r, g, b, w]) in packet.pixels() {
+            self.frame.set_pixel(index, C::new_with_rgbw(r, g, b, w));
+        }
+        self.frame.flush(writer)?;
+        Ok(true)
+    }
+
+    /// Receives a packet and handles it by [`Self::handle_packet()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if receiving failed or `writer` returned an error.
+    pub fn receive<W: LedStripWrite>(
+        &mut self,
+        writer: &mut W,
+    ) -> Result<bool, ReceiveError<W::Error>> {
+        self.receive_packet(writer)
+    }
+
+    /// Receives and handles the packets until an error occurs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if receiving failed or `writer` returned an error.
+    pub fn run<W: LedStripWrite>(&mut self, writer: &mut W) -> Result<(), ReceiveError<W::Error>> {
+        self.run_loop(writer)
+    }
+}
+
+impl<C: LedPixelColor> UdpReceiver for WledReceiver<C> {
+    fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    fn buffer_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buffer
+    }
+
+    fn handle<W: LedStripWrite>(
+        &mut self,
+        packet: &[u8],
+        writer: &mut W,
+    ) -> Result<bool, W::Error> {
+        self.handle_packet(packet, writer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::{LedPixelColorGrb24, LedPixelColorGrbw32};
+    use crate::driver::Ws2812Esp32RmtDriver;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_wled_packet_parse() {
+        let packet = WledPacket::parse(&[PROTOCOL_WARLS, 2, 5, 0x01, 0x02, 0x03, 9]).unwrap();
+        assert_eq!(packet.timeout(), 2);
+        assert_eq!(
+            packet.pixels().collect::<Vec<_>>(),
+            [(5, [0x01, 0x02, 0x03, 0])]
+        );
+
+        let packet = WledPacket::parse(&[PROTOCOL_DRGB, 1, 1, 2, 3, 4, 5, 6]).unwrap();
+        assert_eq!(
+            packet.pixels().collect::<Vec<_>>(),
+            [(0, [1, 2, 3, 0]), (1, [4, 5, 6, 0])]
+        );
+        let packet = WledPacket::parse(&[PROTOCOL_DRGBW, 1, 1, 2, 3, 4]).unwrap();
+        assert_eq!(packet.pixels().collect::<Vec<_>>(), [(0, [1, 2, 3, 4])]);
+        let packet = WledPacket::parse(&[PROTOCOL_DNRGB, 1, 0x01, 0x00, 1, 2, 3]).unwrap();
+        assert_eq!(packet.pixels().collect::<Vec<_>>(), [(256, [1, 2, 3, 0])]);
+
+        assert_eq!(WledPacket::parse(&[1]), Err(WledError::InvalidPacket));
+        assert_eq!(
+            WledPacket::parse(&[PROTOCOL_DNRGB, 1, 0]),
+            Err(WledError::InvalidPacket)
+        );
+        assert_eq!(
+            WledPacket::parse(&[0, 1, 2, 3]),
+            Err(WledError::UnsupportedProtocol(0))
+        );
+    }
+
+    #[test]
+    fn test_wled_receiver() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        let frame = DmxFrame::<LedPixelColorGrbw32>::new(2, 0);
+        let mut receiver = WledReceiver::bind((Ipv4Addr::LOCALHOST, 0), frame).unwrap();
+        let sender = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        sender
+            .connect(receiver.socket().local_addr().unwrap())
+            .unwrap();
+        assert!(!receiver.is_realtime());
+
+        sender.send(&[PROTOCOL_DRGB, 0, 1, 2, 3]).unwrap();
+        assert!(receiver.receive(&mut driver).unwrap());
+        assert_eq!(driver.pixel_data, Some(vec![2, 1, 3, 0, 0, 0, 0, 0]));
+        assert!(!receiver.is_realtime());
+
+        sender
+            .send(&[PROTOCOL_WARLS, TIMEOUT_INFINITE, 1, 4, 5, 6])
+            .unwrap();
+        assert!(receiver.receive(&mut driver).unwrap());
+        assert_eq!(driver.pixel_data, Some(vec![2, 1, 3, 0, 5, 4, 6, 0]));
+        assert!(receiver.is_realtime());
+
+        driver.pixel_data = None;
+        assert!(!receiver.handle_packet(&[0, 1, 2], &mut driver).unwrap());
+        assert_eq!(driver.pixel_data, None);
+    }
+
+    #[test]
+    fn test_wled_receiver_rgb() {
+        let peripherals = Peripherals::take().unwrap();
+        let led_pin = peripherals.pins.gpio0;
+        let channel = peripherals.rmt.channel0;
+        let mut driver = Ws2812Esp32RmtDriver::new(channel, led_pin).unwrap();
+
+        let frame = DmxFrame::<LedPixelColorGrb24>::new(1, 0);
+        let mut receiver = WledReceiver::bind((Ipv4Addr::LOCALHOST, 0), frame).unwrap();
+        let packet = [PROTOCOL_DRGBW, 1, 1, 2, 3, 4, 5, 6, 7, 8];
+        assert!(receiver.handle_packet(&packet, &mut driver).unwrap());
+        assert_eq!(driver.pixel_data, Some(vec![2, 1, 3]));
+        assert!(receiver.is_realtime());
+    }
+}
@@ -0,0 +1,314 @@
+//! Test patterns for the installation bring-up.
+//!
+//! [`TestPattern`] generates the standard test patterns, e.g. the color bars to check the color
+//! order, the gradients to check the channels, the index blink to count the LED pixels, and the
+//! checkerboard to check the wiring of a matrix. A pattern is rendered at a `step`, so that the
+//! animated ones are played by incrementing it, e.g. every 250 ms, up to
+//! [`TestPattern::cycle_len()`].
+//!
+//! The patterns are written through any wrapper: as the pixel-byte sequence of
+//! [`TestPattern::frame()`] through an [`LedStripWrite`], as the colors of
+//! [`TestPattern::colors()`] onto a framebuffer, or as the RGB tuples of [`TestPattern::rgb()`]
+//! through the smart-leds wrapper. [`byte_probe()`] lights a byte of the LED pixels regardless of
+//! the colors, e.g. for the color-order detection.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::driver::color::LedPixelColorGrb24;
+//! use ws2812_esp32_rmt_driver::patterns::TestPattern;
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+//!
+//! // The 3rd LED pixel flashes 3 times per cycle.
+//! let pattern = TestPattern::IndexBlink;
+//! for step in 0..pattern.cycle_len(3) {
+//!     pattern.write::<LedPixelColorGrb24, _>(&mut driver, 3, step, 64).unwrap();
+//!     // e.g. `FreeRtos::delay_ms(250)`
+//! }
+//! assert_eq!(driver.pixel_data, Some(vec![0; 9]));
+//! ```
+
+use crate::driver::color::LedPixelColor;
+use crate::driver::LedStripWrite;
+
+/// Color channel of the LED pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Channel {
+    /// Red
+    Red,
+    /// Green
+    Green,
+    /// Blue
+    Blue,
+    /// White of the white channel
+    White,
+}
+
+impl Channel {
+    /// Returns the RGBW of the channel at `level`.
+    const fn rgbw(&self, level: u8) -> [u8; 4] {
+        match self {
+            Self::Red => [level, 0, 0, 0],
+            Self::Green => [0, level, 0, 0],
+            Self::Blue => [0, 0, level, 0],
+            Self::White => [0, 0, 0, level],
+        }
+    }
+}
+
+/// Colors of the color bars: white, yellow, cyan, green, magenta, red, blue and black
+const BARS: [[bool; 3]; 8] = [
+    [true, true, true],
+    [true, true, false],
+    [false, true, true],
+    [false, true, false],
+    [true, false, true],
+    [true, false, false],
+    [false, false, true],
+    [false, false, false],
+];
+
+/// Steps of the pause at the end of the cycle of [`TestPattern::IndexBlink`]
+const INDEX_BLINK_PAUSE: usize = 4;
+
+/// Test pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TestPattern {
+    /// All the LED pixels in the channel
+    Solid(Channel),
+    /// The 8 color bars of `width` LED pixels each, scrolling by a bar per step
+    ColorBars {
+        /// The number of the LED pixels of a bar
+        width: usize,
+    },
+    /// Gradient of the channel from 0 at the first LED pixel to the level at the last one
+    Gradient(Channel),
+    /// White blink where the `n`-th LED pixel flashes `n` times per cycle, counting from 1
+    IndexBlink,
+    /// White checkerboard of the matrix of `width` LED pixels per row, inverted per step
+    ///
+    /// The LED pixels are laid out row by row; see the shapes of `lib_embedded_graphics` for
+    /// the other layouts.
+    Checkerboard {
+        /// The number of the LED pixels of a row
+        width: usize,
+        /// The number of the LED pixels of a side of a cell
+        cell: usize,
+    },
+}
+
+impl TestPattern {
+    /// Returns the number of the steps of a cycle of the pattern on `pixel_len` LED pixels.
+    pub const fn cycle_len(&self, pixel_len: usize) -> usize {
+        match self {
+            Self::Solid(_) | Self::Gradient(_) => 1,
+            Self::ColorBars { .. } => BARS.len(),
+            Self::IndexBlink => 2 * pixel_len + INDEX_BLINK_PAUSE,
+            Self::Checkerboard { .. } => 2,
+        }
+    }
+
+    /// Returns the RGBW of the `index`-th of `pixel_len` LED pixels at `step`, whose channels
+    /// are up to `level`.
+    pub fn rgbw(&self, index: usize, pixel_len: usize, step: usize, level: u8) -> [u8; 4] {
+        let white = |on: bool| if on { [level, level, level, 0] } else { [0; 4] };
+        match *self {
+            Self::Solid(channel) => channel.rgbw(level),
+            Self::ColorBars { width } => {
+                let bar = BARS[(index / width.max(1) + step) % BARS.len()];
+                let [r, g, b] = bar.map(|on| if on { level } else { 0 });
+                [r, g, b, 0]
+            }
+            Self::Gradient(channel) => {
+                let value = match pixel_len {
+                    0 | 1 => level,
+                    _ => (index.min(pixel_len - 1) * level as usize / (pixel_len - 1)) as u8,
+                };
+                channel.rgbw(value)
+            }
+            Self::IndexBlink => {
+                let phase = step % self.cycle_len(pixel_len);
+                white(phase % 2 == 0 && phase / 2 <= index)
+            }
+            Self::Checkerboard { width, cell } => {
+                let (x, y) = (index % width.max(1), index / width.max(1));
+                let cell = cell.max(1);
+                white((x / cell + y / cell + step) % 2 == 0)
+            }
+        }
+    }
+
+    /// Returns the colors of the `pixel_len` LED pixels at `step`, whose channels are up to
+    /// `level`.
+    ///
+    /// The white channel is mixed into the red, green and blue on the LED pixels without it.
+    pub fn colors<CDev: LedPixelColor>(
+        &self,
+        pixel_len: usize,
+        step: usize,
+        level: u8,
+    ) -> impl Iterator<Item = CDev> + Send + Clone {
+        let pattern = *self;
+        (0..pixel_len).map(move |index| {
+            let [r, g, b, w] = pattern.rgbw(index, pixel_len, step, level);
+            if CDev::BPP < 4 {
+                CDev::new_with_rgb(r.max(w), g.max(w), b.max(w))
+            } else {
+                CDev::new_with_rgbw(r, g, b, w)
+            }
+        })
+    }
+
+    /// Returns the RGB of the `pixel_len` LED pixels at `step`, e.g. for `RGB8::from()` of
+    /// smart-leds.
+    ///
+    /// The white channel is mixed into the red, green and blue.
+    pub fn rgb(
+        &self,
+        pixel_len: usize,
+        step: usize,
+        level: u8,
+    ) -> impl Iterator<Item = (u8, u8, u8)> + Send + Clone {
+        let pattern = *self;
+        (0..pixel_len).map(move |index| {
+            let [r, g, b, w] = pattern.rgbw(index, pixel_len, step, level);
+            (r.max(w), g.max(w), b.max(w))
+        })
+    }
+
+    /// Returns the pixel-byte sequence of the `pixel_len` LED pixels of `CDev` at `step`.
+    pub fn frame<CDev: LedPixelColor>(
+        &self,
+        pixel_len: usize,
+        step: usize,
+        level: u8,
+    ) -> impl Iterator<Item = u8> + Send + Clone {
+        self.colors::<CDev>(pixel_len, step, level)
+            .flat_map(|color| pixel_bytes(&color))
+    }
+
+    /// Writes the pattern of the `pixel_len` LED pixels of `CDev` at `step` through `writer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by `writer`.
+    pub fn write<CDev, W>(
+        &self,
+        writer: &mut W,
+        pixel_len: usize,
+        step: usize,
+        level: u8,
+    ) -> Result<(), W::Error>
+    where
+        CDev: LedPixelColor,
+        W: LedStripWrite,
+    {
+        writer.write_blocking(self.frame::<CDev>(pixel_len, step, level))
+    }
+}
+
+/// Returns the pixel-byte sequence of the `pixel_len` LED pixels of `CDev` whose `byte`-th byte
+/// is `level` and the others are 0.
+///
+/// Unlike [`TestPattern`], the byte is lit regardless of the channel order of `CDev`.
+pub fn byte_probe<CDev: LedPixelColor>(
+    byte: usize,
+    pixel_len: usize,
+    level: u8,
+) -> impl Iterator<Item = u8> + Send + Clone {
+    let mut color = CDev::new_with_rgb(0, 0, 0);
+    if let Some(value) = color.as_mut().get_mut(byte) {
+        *value = level;
+    }
+    let bytes = pixel_bytes(&color);
+    (0..pixel_len).flat_map(move |_| bytes.clone())
+}
+
+/// Returns the bytes of the LED pixel.
+fn pixel_bytes<CDev: LedPixelColor>(color: &CDev) -> impl Iterator<Item = u8> + Send + Clone {
+    let mut bytes = [0; 4];
+    let len = CDev::BPP.min(bytes.len());
+    bytes[..len].copy_from_slice(&color.as_ref()[..len]);
+    bytes.into_iter().take(len)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::{LedPixelColorGrb24, LedPixelColorGrbw32};
+
+    #[test]
+    fn test_color_bars() {
+        let pattern = TestPattern::ColorBars { width: 2 };
+        let frame = pattern
+            .frame::<LedPixelColorGrb24>(4, 0, 10)
+            .collect::<Vec<_>>();
+        // white, white, yellow, yellow in GRB
+        assert_eq!(frame, [10, 10, 10, 10, 10, 10, 10, 10, 0, 10, 10, 0]);
+        let scrolled = pattern.rgb(1, 7, 10).collect::<Vec<_>>();
+        assert_eq!(scrolled, [(0, 0, 0)]);
+    }
+
+    #[test]
+    fn test_gradient() {
+        let pattern = TestPattern::Gradient(Channel::White);
+        let rgbw = pattern
+            .colors::<LedPixelColorGrbw32>(3, 0, 200)
+            .map(|color| color.w())
+            .collect::<Vec<_>>();
+        assert_eq!(rgbw, [0, 100, 200]);
+        // White mixed into RGB of the LED pixels without the white channel
+        let rgb = pattern
+            .colors::<LedPixelColorGrb24>(3, 0, 200)
+            .map(|color| (color.r(), color.g(), color.b()))
+            .collect::<Vec<_>>();
+        assert_eq!(rgb, [(0, 0, 0), (100, 100, 100), (200, 200, 200)]);
+    }
+
+    #[test]
+    fn test_index_blink() {
+        let pattern = TestPattern::IndexBlink;
+        let pixel_len = 3;
+        let mut flashes = [0; 3];
+        let mut last = [false; 3];
+        for step in 0..pattern.cycle_len(pixel_len) {
+            for (index, rgb) in pattern.rgb(pixel_len, step, 1).enumerate() {
+                let on = rgb != (0, 0, 0);
+                if on && !last[index] {
+                    flashes[index] += 1;
+                }
+                last[index] = on;
+            }
+        }
+        assert_eq!(flashes, [1, 2, 3]);
+        assert_eq!(last, [false; 3]);
+    }
+
+    #[test]
+    fn test_checkerboard() {
+        let pattern = TestPattern::Checkerboard { width: 4, cell: 2 };
+        let lit = |step| {
+            pattern
+                .rgb(8, step, 1)
+                .map(|rgb| rgb != (0, 0, 0))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(lit(0), [true, true, false, false, true, true, false, false]);
+        assert!(lit(1).iter().zip(lit(0)).all(|(&a, b)| a != b));
+    }
+
+    #[test]
+    fn test_byte_probe() {
+        let frame = byte_probe::<LedPixelColorGrbw32>(3, 2, 5).collect::<Vec<_>>();
+        assert_eq!(frame, [0, 0, 0, 5, 0, 0, 0, 5]);
+        let frame = byte_probe::<LedPixelColorGrb24>(3, 1, 5).collect::<Vec<_>>();
+        assert_eq!(frame, [0, 0, 0]);
+    }
+}
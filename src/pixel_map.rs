@@ -0,0 +1,365 @@
+//! LED pixel shapes of the irregular fixtures.
+//!
+//! [`led_pixel_map!`] defines an [`LedPixelShape`] from the list of the physical coordinates
+//! of the LED pixels in the chain order, e.g. of the LED letters or the wire-frame sculptures,
+//! so that they are drawn with the embedded-graphics coordinates. The lookup table
+//! ([`PixelMap`]) from the coordinates to the LED pixels is built at compile time, and the
+//! duplicated coordinates are compile errors.
+//!
+//! [`PixelTable`] is the other way around: the shape of a lookup table given by the user, each
+//! cell holding the LED pixel index at the coordinates or [`NO_PIXEL`], e.g. drawn by hand
+//! from the wiring of a logo, or loaded at runtime. It is a shape of the instance, used with
+//! `LedPixelDrawTarget::new_with_shape()`.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use embedded_graphics::pixelcolor::Rgb888;
+//! use embedded_graphics::prelude::*;
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::led_pixel_map;
+//! use ws2812_esp32_rmt_driver::lib_embedded_graphics::{LedPixelShape, Ws2812DrawTarget};
+//!
+//! led_pixel_map! {
+//!     /// LED letter "L" wired from the top
+//!     pub LetterL = [(0, 0), (0, 1), (0, 2), (1, 2), (2, 2)];
+//! }
+//! assert_eq!(LetterL::size(), Size::new(3, 3));
+//! assert_eq!(LetterL::pixel_len(), 5);
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut draw =
+//!     Ws2812DrawTarget::<LetterL>::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+//! Pixel(Point::new(1, 2), Rgb888::RED).draw(&mut draw).unwrap();
+//! // (1, 1) has no LED pixel.
+//! Pixel(Point::new(1, 1), Rgb888::RED).draw(&mut draw).unwrap();
+//! draw.flush().unwrap();
+//! assert_eq!(draw.frame_buffer().as_bytes()[9..12], [0, 255, 0]);
+//! ```
+//!
+//! The same letter wired from the bottom, by the lookup table:
+//!
+//! ```
+//! # #[cfg(not(target_vendor = "espressif"))]
+//! # use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//! # use embedded_graphics::pixelcolor::Rgb888;
+//! # use embedded_graphics::prelude::*;
+//! # use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::lib_embedded_graphics::Ws2812DrawTarget;
+//! use ws2812_esp32_rmt_driver::pixel_map::{PixelTable, NO_PIXEL as X};
+//!
+//! #[rustfmt::skip]
+//! const LETTER_L: PixelTable = PixelTable::new(3, &[
+//!     2, X, X,
+//!     1, X, X,
+//!     0, 3, 4,
+//! ]);
+//! assert_eq!(LETTER_L.pixel_len(), 5);
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut draw = Ws2812DrawTarget::<PixelTable>::new_with_shape(
+//!     LETTER_L,
+//!     peripherals.rmt.channel0,
+//!     peripherals.pins.gpio0,
+//! )
+//! .unwrap();
+//! Pixel(Point::new(0, 0), Rgb888::RED).draw(&mut draw).unwrap();
+//! draw.flush().unwrap();
+//! assert_eq!(draw.frame_buffer().as_bytes()[6..9], [0, 255, 0]);
+//! ```
+//!
+//! [`LedPixelShape`]: crate::lib_embedded_graphics::LedPixelShape
+
+use crate::lib_embedded_graphics::LedPixelShapeInstance;
+use embedded_graphics_core::geometry::{Point, Size};
+
+#[doc(hidden)]
+pub use embedded_graphics_core::geometry as __geometry;
+
+/// Cell of the lookup table without LED pixel
+pub const NO_PIXEL: u16 = u16::MAX;
+
+/// Returns the size of the bounding box of the coordinates.
+pub const fn coordinates_size(coordinates: &[(u32, u32)]) -> Size {
+    if coordinates.is_empty() {
+        return Size::zero();
+    }
+    let (mut width, mut height) = (0, 0);
+    let mut i = 0;
+    while i < coordinates.len() {
+        let (x, y) = coordinates[i];
+        if x >= width {
+            width = x + 1;
+        }
+        if y >= height {
+            height = y + 1;
+        }
+        i += 1;
+    }
+    Size::new(width, height)
+}
+
+/// Returns the number of the cells of the lookup table of the coordinates.
+pub const fn coordinates_cells(coordinates: &[(u32, u32)]) -> usize {
+    let size = coordinates_size(coordinates);
+    (size.width * size.height) as usize
+}
+
+/// Lookup table from the coordinates to the LED pixels.
+///
+/// * `CELLS` - the number of the cells of the bounding box, see [`coordinates_cells()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PixelMap<const CELLS: usize> {
+    size: Size,
+    pixel_len: usize,
+    cells: [u16; CELLS],
+}
+
+impl<const CELLS: usize> PixelMap<CELLS> {
+    /// Builds the lookup table of the coordinates of the LED pixels in the chain order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a coordinate is duplicated, there are 65535 LED pixels or more, or `CELLS`
+    /// does not equal to [`coordinates_cells()`]. In const context, they are compile errors.
+    pub const fn from_coordinates(coordinates: &[(u32, u32)]) -> Self {
+        let size = coordinates_size(coordinates);
+        assert!(
+            coordinates_cells(coordinates) == CELLS,
+            "CELLS shall equal to the cells of the coordinates"
+        );
+        assert!(coordinates.len() < NO_PIXEL as usize, "too many LED pixels");
+        let mut cells = [NO_PIXEL; CELLS];
+        let mut i = 0;
+        while i < coordinates.len() {
+            let (x, y) = coordinates[i];
+            let cell = (x + y * size.width) as usize;
+            assert!(cells[cell] == NO_PIXEL, "duplicated coordinate");
+            cells[cell] = i as u16;
+            i += 1;
+        }
+        Self {
+            size,
+            pixel_len: coordinates.len(),
+            cells,
+        }
+    }
+
+    /// Returns the size of the bounding box.
+    #[inline]
+    pub const fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Returns the number of the LED pixels.
+    #[inline]
+    pub const fn pixel_len(&self) -> usize {
+        self.pixel_len
+    }
+
+    /// Convert from `point` to the index.
+    /// Returns `None` if no LED pixel is there.
+    pub fn pixel_index(&self, point: Point) -> Option<usize> {
+        if !((0..self.size.width as i32).contains(&point.x)
+            && (0..self.size.height as i32).contains(&point.y))
+        {
+            return None;
+        }
+        let cell = self.cells[(point.x + point.y * self.size.width as i32) as usize];
+        (cell != NO_PIXEL).then_some(cell as usize)
+    }
+}
+
+/// Lookup table of the user from the coordinates to the LED pixels
+///
+/// The cells are in the row-major order, each holding the index of the LED pixel at the
+/// coordinates, or [`NO_PIXEL`]. The number of the LED pixels is the largest index plus one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PixelTable<'a> {
+    width: usize,
+    pixel_len: usize,
+    cells: &'a [u16],
+}
+
+impl<'a> PixelTable<'a> {
+    /// Creates a lookup table of the rows of `width` cells.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of the cells is not a multiple of `width`. In const context, it is
+    /// a compile error.
+    pub const fn new(width: usize, cells: &'a [u16]) -> Self {
+        assert!(
+            width == 0 && cells.is_empty() || width != 0 && cells.len() % width == 0,
+            "cells shall be the rows of the width"
+        );
+        let mut pixel_len = 0;
+        let mut i = 0;
+        while i < cells.len() {
+            if cells[i] != NO_PIXEL && cells[i] as usize >= pixel_len {
+                pixel_len = cells[i] as usize + 1;
+            }
+            i += 1;
+        }
+        Self {
+            width,
+            pixel_len,
+            cells,
+        }
+    }
+
+    /// Returns the size of the table.
+    #[inline]
+    pub const fn size(&self) -> Size {
+        if self.width == 0 {
+            return Size::zero();
+        }
+        Size::new(self.width as u32, (self.cells.len() / self.width) as u32)
+    }
+
+    /// Returns the number of the LED pixels.
+    #[inline]
+    pub const fn pixel_len(&self) -> usize {
+        self.pixel_len
+    }
+
+    /// Returns the cells of the table.
+    #[inline]
+    pub const fn cells(&self) -> &'a [u16] {
+        self.cells
+    }
+
+    /// Convert from `point` to the index.
+    /// Returns `None` if no LED pixel is there.
+    pub fn pixel_index(&self, point: Point) -> Option<usize> {
+        let x = usize::try_from(point.x).ok().filter(|x| *x < self.width)?;
+        let y = usize::try_from(point.y).ok()?;
+        let cell = *self.cells.get(x + y * self.width)?;
+        (cell != NO_PIXEL).then_some(cell as usize)
+    }
+}
+
+impl LedPixelShapeInstance for PixelTable<'_> {
+    #[inline]
+    fn shape_pixel_len(&self) -> usize {
+        self.pixel_len()
+    }
+    #[inline]
+    fn shape_size(&self) -> Size {
+        self.size()
+    }
+    #[inline]
+    fn shape_pixel_index(&self, point: Point) -> Option<usize> {
+        self.pixel_index(point)
+    }
+}
+
+/// Defines an [`LedPixelShape`] of the physical coordinates `(x, y)` of the LED pixels in the
+/// chain order.
+///
+/// The defined type has the associated consts `COORDINATES` of the coordinates and `MAP` of
+/// the [`PixelMap`]. See [the module documentation](crate::pixel_map) for the example.
+///
+/// [`LedPixelShape`]: crate::lib_embedded_graphics::LedPixelShape
+#[macro_export]
+macro_rules! led_pixel_map {
+    ($(#[$meta:meta])* $vis:vis $name:ident = [$(($x:expr, $y:expr)),* $(,)?];) => {
+        $(#[$meta])*
+        #[derive(Default)]
+        $vis struct $name;
+
+        impl $name {
+            /// Physical coordinates of the LED pixels in the chain order
+            pub const COORDINATES: &'static [(u32, u32)] = &[$(($x, $y)),*];
+            /// Lookup table from the coordinates to the LED pixels
+            pub const MAP: $crate::pixel_map::PixelMap<
+                { $crate::pixel_map::coordinates_cells($name::COORDINATES) },
+            > = $crate::pixel_map::PixelMap::from_coordinates($name::COORDINATES);
+        }
+
+        impl $crate::lib_embedded_graphics::LedPixelShape for $name {
+            #[inline]
+            fn pixel_len() -> usize {
+                Self::MAP.pixel_len()
+            }
+            #[inline]
+            fn size() -> $crate::pixel_map::__geometry::Size {
+                Self::MAP.size()
+            }
+            #[inline]
+            fn pixel_index(point: $crate::pixel_map::__geometry::Point) -> Option<usize> {
+                Self::MAP.pixel_index(point)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lib_embedded_graphics::LedPixelShape;
+
+    led_pixel_map! {
+        Ring = [(1, 0), (2, 1), (1, 2), (0, 1)];
+    }
+
+    #[test]
+    fn test_pixel_map() {
+        const MAP: PixelMap<6> = PixelMap::from_coordinates(&[(0, 0), (2, 1), (1, 0)]);
+        assert_eq!(MAP.size(), Size::new(3, 2));
+        assert_eq!(MAP.pixel_len(), 3);
+        assert_eq!(MAP.pixel_index(Point::new(0, 0)), Some(0));
+        assert_eq!(MAP.pixel_index(Point::new(1, 0)), Some(2));
+        assert_eq!(MAP.pixel_index(Point::new(2, 1)), Some(1));
+        assert_eq!(MAP.pixel_index(Point::new(0, 1)), None);
+        assert_eq!(MAP.pixel_index(Point::new(3, 0)), None);
+        assert_eq!(MAP.pixel_index(Point::new(-1, 0)), None);
+
+        assert_eq!(coordinates_size(&[]), Size::zero());
+        assert_eq!(PixelMap::<0>::from_coordinates(&[]).pixel_len(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicated coordinate")]
+    fn test_pixel_map_duplicated() {
+        PixelMap::<1>::from_coordinates(&[(0, 0), (0, 0)]);
+    }
+
+    #[test]
+    fn test_pixel_table() {
+        const X: u16 = NO_PIXEL;
+        let table = PixelTable::new(3, &[X, 0, X, 3, X, 1, X, 5, X]);
+        assert_eq!(table.size(), Size::new(3, 3));
+        assert_eq!(table.pixel_len(), 6);
+        assert_eq!(table.shape_pixel_len(), 6);
+        assert_eq!(table.pixel_index(Point::new(1, 0)), Some(0));
+        assert_eq!(table.pixel_index(Point::new(0, 1)), Some(3));
+        assert_eq!(table.shape_pixel_index(Point::new(1, 2)), Some(5));
+        assert_eq!(table.pixel_index(Point::new(1, 1)), None);
+        assert_eq!(table.pixel_index(Point::new(3, 0)), None);
+        assert_eq!(table.pixel_index(Point::new(1, 3)), None);
+        assert_eq!(table.pixel_index(Point::new(-1, 0)), None);
+
+        let table = PixelTable::new(0, &[]);
+        assert_eq!((table.size(), table.pixel_len()), (Size::zero(), 0));
+        assert_eq!(table.pixel_index(Point::zero()), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "cells shall be the rows of the width")]
+    fn test_pixel_table_width() {
+        PixelTable::new(2, &[0, 1, 2]);
+    }
+
+    #[test]
+    fn test_led_pixel_map() {
+        assert_eq!(Ring::size(), Size::new(3, 3));
+        assert_eq!(Ring::pixel_len(), 4);
+        assert_eq!(Ring::COORDINATES.len(), 4);
+        assert_eq!(Ring::pixel_index(Point::new(0, 1)), Some(3));
+        assert_eq!(Ring::pixel_index(Point::new(1, 1)), None);
+    }
+}
@@ -0,0 +1,199 @@
+//! Blanking on the power events.
+//!
+//! [`PowerGuard`] limits the brightness of the LED pixels while the power rail is in trouble:
+//! it blanks the strip on a brown-out warning, and drops the brightness to a safe level on a
+//! low battery, until the power is restored. It is a [`BrightnessSource`] of the framebuffer, so
+//! that the next `show()` writes the frame with the limit applied.
+//!
+//! On ESP-IDF, [`PowerEvent`] is an event of the ESP-IDF event loop, posted by the brown-out or
+//! battery monitor of the application and received by [`PowerGuard::subscribe()`]. On the
+//! other targets, the events are signaled by [`PowerGuard::signal()`] directly.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::driver::color::{LedPixelColor, LedPixelColorGrb24};
+//! use ws2812_esp32_rmt_driver::framebuffer::Ws2812FrameBuffer;
+//! use ws2812_esp32_rmt_driver::power_guard::{PowerEvent, PowerGuard};
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut frame: Ws2812FrameBuffer =
+//!     Ws2812FrameBuffer::new(peripherals.rmt.channel0, peripherals.pins.gpio0, 1).unwrap();
+//! let guard = PowerGuard::new(63);
+//! // On ESP-IDF: `let _subscription = guard.subscribe(&EspSystemEventLoop::take()?)?;`
+//! frame.set_brightness_source(guard.clone());
+//!
+//! frame.fill(LedPixelColorGrb24::new_with_rgb(255, 255, 255));
+//! frame.show().unwrap();
+//!
+//! // On ESP-IDF: `sysloop.post::<PowerEvent>(&PowerEvent::LowBattery, delay::NON_BLOCK)?;`
+//! guard.signal(PowerEvent::LowBattery);
+//! frame.show().unwrap();
+//! assert_eq!(frame.driver().pixel_data, Some(vec![63, 63, 63]));
+//!
+//! guard.signal(PowerEvent::BrownOut);
+//! frame.show().unwrap();
+//! assert_eq!(frame.driver().pixel_data, Some(vec![0, 0, 0]));
+//! ```
+
+use crate::driver::brightness::BrightnessSource;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+#[cfg(target_vendor = "espressif")]
+use esp_idf_svc::eventloop::{
+    EspEvent, EspEventDeserializer, EspEventLoop, EspEventLoopType, EspEventPostData,
+    EspEventSerializer, EspEventSource, EspSubscription,
+};
+#[cfg(target_vendor = "espressif")]
+use esp_idf_svc::sys::EspError;
+
+/// Event of the power rail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PowerEvent {
+    /// The supply voltage is dipping: blanks the strip.
+    BrownOut,
+    /// The battery is low: drops the brightness to the safe level.
+    LowBattery,
+    /// The power is restored: lifts the limit.
+    Restored,
+}
+
+#[cfg(target_vendor = "espressif")]
+unsafe impl EspEventSource for PowerEvent {
+    fn source() -> Option<&'static core::ffi::CStr> {
+        Some(c"WS2812_POWER")
+    }
+}
+
+#[cfg(target_vendor = "espressif")]
+impl EspEventSerializer for PowerEvent {
+    type Data<'a> = PowerEvent;
+
+    fn serialize<F, R>(event: &Self::Data<'_>, f: F) -> R
+    where
+        F: FnOnce(&EspEventPostData) -> R,
+    {
+        f(&unsafe { EspEventPostData::new(Self::source().unwrap(), Self::event_id(), event) })
+    }
+}
+
+#[cfg(target_vendor = "espressif")]
+impl EspEventDeserializer for PowerEvent {
+    type Data<'a> = PowerEvent;
+
+    fn deserialize<'a>(data: &EspEvent<'a>) -> Self::Data<'a> {
+        *unsafe { data.as_payload::<PowerEvent>() }
+    }
+}
+
+/// Brightness limit following the power events.
+///
+/// The clones share the limit, so that a clone is signaled from the event handler and another
+/// is polled as the brightness source.
+#[derive(Debug, Clone)]
+pub struct PowerGuard {
+    limit: Arc<AtomicU8>,
+    safe_brightness: u8,
+}
+
+impl PowerGuard {
+    /// Creates a power guard dropping the brightness to `safe_brightness` on a low battery.
+    pub fn new(safe_brightness: u8) -> Self {
+        Self {
+            limit: Arc::new(AtomicU8::new(u8::MAX)),
+            safe_brightness,
+        }
+    }
+
+    /// Applies a power event to the limit.
+    pub fn signal(&self, event: PowerEvent) {
+        let limit = match event {
+            PowerEvent::BrownOut => 0,
+            PowerEvent::LowBattery => self.safe_brightness,
+            PowerEvent::Restored => u8::MAX,
+        };
+        self.limit.store(limit, Ordering::Relaxed);
+    }
+
+    /// Returns the current brightness limit.
+    pub fn limit(&self) -> u8 {
+        self.limit.load(Ordering::Relaxed)
+    }
+
+    /// Returns a brightness source of `source` limited by this guard.
+    pub fn guard<S: BrightnessSource>(&self, source: S) -> GuardedBrightness<S> {
+        GuardedBrightness {
+            guard: self.clone(),
+            source,
+        }
+    }
+
+    /// Subscribes this guard to the [`PowerEvent`]s posted to `event_loop`.
+    ///
+    /// The guard follows the events while the returned subscription is alive.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the subscription failed.
+    #[cfg(target_vendor = "espressif")]
+    pub fn subscribe<T: EspEventLoopType>(
+        &self,
+        event_loop: &EspEventLoop<T>,
+    ) -> Result<EspSubscription<'static, T>, EspError> {
+        let guard = self.clone();
+        event_loop.subscribe::<PowerEvent, _>(move |event| guard.signal(event))
+    }
+}
+
+impl BrightnessSource for PowerGuard {
+    #[inline]
+    fn brightness(&mut self) -> u8 {
+        self.limit()
+    }
+}
+
+/// Brightness source limited by a [`PowerGuard`].
+#[derive(Debug, Clone)]
+pub struct GuardedBrightness<S: BrightnessSource> {
+    guard: PowerGuard,
+    source: S,
+}
+
+impl<S: BrightnessSource> BrightnessSource for GuardedBrightness<S> {
+    fn brightness(&mut self) -> u8 {
+        self.source.brightness().min(self.guard.limit())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_power_guard() {
+        let guard = PowerGuard::new(63);
+        let mut source = guard.clone();
+        assert_eq!(source.brightness(), 255);
+
+        guard.signal(PowerEvent::LowBattery);
+        assert_eq!(source.brightness(), 63);
+        guard.signal(PowerEvent::BrownOut);
+        assert_eq!(source.brightness(), 0);
+        guard.signal(PowerEvent::Restored);
+        assert_eq!(source.brightness(), 255);
+    }
+
+    #[test]
+    fn test_guarded_brightness() {
+        let guard = PowerGuard::new(63);
+        let mut source = guard.guard(|| 100);
+        assert_eq!(source.brightness(), 100);
+        guard.signal(PowerEvent::LowBattery);
+        assert_eq!(source.brightness(), 63);
+    }
+}
@@ -0,0 +1,380 @@
+//! LED pixel shapes of the LED rings.
+//!
+//! [`LedPixelRing`] maps the points of a square canvas to the LED pixels of a ring by the angle
+//! around the center, and [`LedPixelRings`] to the concentric rings by the distance from the
+//! center as well. The clock faces and the circular gauges are drawn with the embedded-graphics
+//! primitives without trigonometry: a line from the center lights the LED pixel of its angle as
+//! a clock hand, and a sector lights the LED pixels of its angles as a gauge.
+//!
+//! The angles are in degrees clockwise from the 3 o'clock, as the embedded-graphics arcs. The
+//! diameter of the canvas is the resolution of the angles: the larger, the finer the sectors
+//! are drawn.
+//!
+//! They are shapes of the instance, used with `LedPixelDrawTarget::new_with_shape()`.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use embedded_graphics::pixelcolor::Rgb888;
+//! use embedded_graphics::prelude::*;
+//! use embedded_graphics::primitives::{Line, PrimitiveStyle};
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::lib_embedded_graphics::Ws2812DrawTarget;
+//! use ws2812_esp32_rmt_driver::ring::LedPixelRing;
+//!
+//! // 12-LED clock face, chained clockwise from the 12 o'clock
+//! let ring = LedPixelRing::new(12, 9).with_start_angle(-90.0);
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let mut draw = Ws2812DrawTarget::<LedPixelRing>::new_with_shape(
+//!     ring,
+//!     peripherals.rmt.channel0,
+//!     peripherals.pins.gpio0,
+//! )
+//! .unwrap();
+//! // Hand of 3 o'clock
+//! Line::new(ring.center(), Point::new(8, 4))
+//!     .into_styled(PrimitiveStyle::with_stroke(Rgb888::RED, 1))
+//!     .draw(&mut draw)
+//!     .unwrap();
+//! draw.flush().unwrap();
+//! assert_eq!(ring.angle_pixel_index(0.0), Some(3));
+//! assert_eq!(draw.frame_buffer().as_bytes()[9..12], [0, 255, 0]);
+//! ```
+
+use crate::lib_embedded_graphics::LedPixelShapeInstance;
+use embedded_graphics_core::geometry::{Point, Size};
+
+/// Returns the absolute value, `f32::abs()` of `no_std`.
+#[inline]
+fn abs(v: f32) -> f32 {
+    if v < 0.0 {
+        -v
+    } else {
+        v
+    }
+}
+
+/// Returns the angle of `(dx, dy)` in degrees clockwise from the x axis, in `0.0..360.0`.
+///
+/// The arctangent is approximated within 0.3 degrees.
+fn angle_of(dx: f32, dy: f32) -> f32 {
+    let (ax, ay) = (abs(dx), abs(dy));
+    if ax == 0.0 && ay == 0.0 {
+        return 0.0;
+    }
+    let z = ax.min(ay) / ax.max(ay);
+    let mut angle = z * (45.0 + 15.64 * (1.0 - z));
+    if ay > ax {
+        angle = 90.0 - angle;
+    }
+    if dx < 0.0 {
+        angle = 180.0 - angle;
+    }
+    if dy < 0.0 {
+        angle = 360.0 - angle;
+    }
+    angle % 360.0
+}
+
+/// Geometry of the canvas and the chain of the rings
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Polar {
+    diameter: u32,
+    start_angle: f32,
+    clockwise: bool,
+}
+
+impl Polar {
+    /// Returns the index of the LED pixel at `degrees` in the ring of `pixel_len` LED pixels.
+    fn ring_index(&self, pixel_len: usize, degrees: f32) -> Option<usize> {
+        if pixel_len == 0 {
+            return None;
+        }
+        let mut angle = (degrees - self.start_angle) % 360.0;
+        if !self.clockwise {
+            angle = -angle;
+        }
+        if angle < 0.0 {
+            angle += 360.0;
+        }
+        Some((angle * pixel_len as f32 / 360.0 + 0.5) as usize % pixel_len)
+    }
+
+    /// Returns the index of the LED pixel at `point` in the rings of the LED pixels, outermost
+    /// first.
+    fn pixel_index(&self, rings: &[usize], point: Point) -> Option<usize> {
+        let radius = self.diameter as f32 / 2.0;
+        let center = (self.diameter as f32 - 1.0) / 2.0;
+        let (dx, dy) = (point.x as f32 - center, point.y as f32 - center);
+        let distance2 = dx * dx + dy * dy;
+        if rings.is_empty() || distance2 > radius * radius {
+            return None;
+        }
+        let mut offset = 0;
+        for (i, &pixel_len) in rings.iter().enumerate() {
+            let inner = radius * (rings.len() - 1 - i) as f32 / rings.len() as f32;
+            if distance2 > inner * inner || i == rings.len() - 1 {
+                if distance2 == 0.0 && pixel_len > 1 {
+                    return None;
+                }
+                return Some(offset + self.ring_index(pixel_len, angle_of(dx, dy))?);
+            }
+            offset += pixel_len;
+        }
+        None
+    }
+
+    /// Returns the geometry with the LED pixel 0 at `degrees`.
+    const fn with_start_angle(self, degrees: f32) -> Self {
+        Self {
+            start_angle: degrees,
+            ..self
+        }
+    }
+
+    /// Returns the geometry chained counterclockwise.
+    const fn counterclockwise(self) -> Self {
+        Self {
+            clockwise: false,
+            ..self
+        }
+    }
+
+    /// Returns the center of the canvas.
+    const fn center(&self) -> Point {
+        let center = ((self.diameter.saturating_sub(1)) / 2) as i32;
+        Point::new(center, center)
+    }
+
+    /// Returns the size of the canvas.
+    const fn size(&self) -> Size {
+        Size::new(self.diameter, self.diameter)
+    }
+}
+
+/// LED pixel shape of a ring of the LED pixels
+///
+/// The points within the circle of the canvas are mapped to the LED pixel of the angle, except
+/// the center.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LedPixelRing {
+    pixel_len: usize,
+    polar: Polar,
+}
+
+impl LedPixelRing {
+    /// Creates a ring of `pixel_len` LED pixels on the canvas of `diameter`x`diameter`.
+    ///
+    /// The LED pixel 0 is at the 3 o'clock, and chained clockwise.
+    pub const fn new(pixel_len: usize, diameter: u32) -> Self {
+        Self {
+            pixel_len,
+            polar: Polar {
+                diameter,
+                start_angle: 0.0,
+                clockwise: true,
+            },
+        }
+    }
+
+    /// Returns the ring with the LED pixel 0 at `degrees`, e.g. `-90.0` for the 12 o'clock.
+    pub const fn with_start_angle(self, degrees: f32) -> Self {
+        Self {
+            polar: self.polar.with_start_angle(degrees),
+            ..self
+        }
+    }
+
+    /// Returns the ring chained counterclockwise.
+    pub const fn counterclockwise(self) -> Self {
+        Self {
+            polar: self.polar.counterclockwise(),
+            ..self
+        }
+    }
+
+    /// Returns the number of the LED pixels.
+    #[inline]
+    pub const fn pixel_len(&self) -> usize {
+        self.pixel_len
+    }
+
+    /// Returns the center of the canvas.
+    #[inline]
+    pub const fn center(&self) -> Point {
+        self.polar.center()
+    }
+
+    /// Returns the index of the LED pixel nearest to `degrees`.
+    /// Returns `None` if the ring has no LED pixel.
+    pub fn angle_pixel_index(&self, degrees: f32) -> Option<usize> {
+        self.polar.ring_index(self.pixel_len, degrees)
+    }
+}
+
+impl LedPixelShapeInstance for LedPixelRing {
+    #[inline]
+    fn shape_pixel_len(&self) -> usize {
+        self.pixel_len
+    }
+    #[inline]
+    fn shape_size(&self) -> Size {
+        self.polar.size()
+    }
+
+    fn shape_pixel_index(&self, point: Point) -> Option<usize> {
+        self.polar.pixel_index(&[self.pixel_len], point)
+    }
+}
+
+/// LED pixel shape of the concentric rings of the LED pixels, e.g. the LED discs
+///
+/// The rings are chained from the outermost, and the circle of the canvas is divided into the
+/// bands of the same width, one per ring. A ring of a LED pixel at the center is lit by any
+/// point of the innermost band.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LedPixelRings<'a> {
+    rings: &'a [usize],
+    polar: Polar,
+}
+
+impl<'a> LedPixelRings<'a> {
+    /// Creates the rings of the numbers of the LED pixels, outermost first, on the canvas of
+    /// `diameter`x`diameter`.
+    ///
+    /// The LED pixel 0 of each ring is at the 3 o'clock, and chained clockwise.
+    pub const fn new(rings: &'a [usize], diameter: u32) -> Self {
+        Self {
+            rings,
+            polar: Polar {
+                diameter,
+                start_angle: 0.0,
+                clockwise: true,
+            },
+        }
+    }
+
+    /// Returns the rings with the LED pixel 0 of each ring at `degrees`.
+    pub const fn with_start_angle(self, degrees: f32) -> Self {
+        Self {
+            polar: self.polar.with_start_angle(degrees),
+            ..self
+        }
+    }
+
+    /// Returns the rings chained counterclockwise.
+    pub const fn counterclockwise(self) -> Self {
+        Self {
+            polar: self.polar.counterclockwise(),
+            ..self
+        }
+    }
+
+    /// Returns the numbers of the LED pixels of the rings, outermost first.
+    #[inline]
+    pub const fn rings(&self) -> &'a [usize] {
+        self.rings
+    }
+
+    /// Returns the number of the LED pixels.
+    pub fn pixel_len(&self) -> usize {
+        self.rings.iter().sum()
+    }
+
+    /// Returns the center of the canvas.
+    #[inline]
+    pub const fn center(&self) -> Point {
+        self.polar.center()
+    }
+
+    /// Returns the index of the LED pixel of the `ring`, outermost 0, nearest to `degrees`.
+    /// Returns `None` if there is no such ring or it has no LED pixel.
+    pub fn angle_pixel_index(&self, ring: usize, degrees: f32) -> Option<usize> {
+        let offset = self.rings.get(..ring)?.iter().sum::<usize>();
+        let index = self.polar.ring_index(*self.rings.get(ring)?, degrees)?;
+        Some(offset + index)
+    }
+}
+
+impl LedPixelShapeInstance for LedPixelRings<'_> {
+    #[inline]
+    fn shape_pixel_len(&self) -> usize {
+        self.pixel_len()
+    }
+    #[inline]
+    fn shape_size(&self) -> Size {
+        self.polar.size()
+    }
+
+    fn shape_pixel_index(&self, point: Point) -> Option<usize> {
+        self.polar.pixel_index(self.rings, point)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_angle_of() {
+        for degrees in (0..360).step_by(5) {
+            let radians = degrees as f32 * core::f32::consts::PI / 180.0;
+            let angle = angle_of(10.0 * radians.cos(), 10.0 * radians.sin());
+            let error = (angle - degrees as f32 + 180.0).rem_euclid(360.0) - 180.0;
+            assert!(error.abs() < 0.3, "{degrees}: {angle}");
+        }
+    }
+
+    #[test]
+    fn test_led_pixel_ring() {
+        let ring = LedPixelRing::new(12, 9).with_start_angle(-90.0);
+        assert_eq!(ring.shape_size(), Size::new(9, 9));
+        assert_eq!(ring.shape_pixel_len(), 12);
+        assert_eq!(ring.center(), Point::new(4, 4));
+        assert_eq!(ring.shape_pixel_index(Point::new(4, 0)), Some(0));
+        assert_eq!(ring.shape_pixel_index(Point::new(4, 3)), Some(0));
+        assert_eq!(ring.shape_pixel_index(Point::new(8, 4)), Some(3));
+        assert_eq!(ring.shape_pixel_index(Point::new(4, 8)), Some(6));
+        assert_eq!(ring.shape_pixel_index(Point::new(0, 4)), Some(9));
+        assert_eq!(ring.shape_pixel_index(Point::new(4, 4)), None);
+        assert_eq!(ring.shape_pixel_index(Point::new(0, 0)), None);
+        assert_eq!(ring.shape_pixel_index(Point::new(9, 4)), None);
+        assert_eq!(ring.angle_pixel_index(-90.0), Some(0));
+        assert_eq!(ring.angle_pixel_index(-76.0), Some(0));
+        assert_eq!(ring.angle_pixel_index(-74.0), Some(1));
+        assert_eq!(ring.angle_pixel_index(240.0), Some(11));
+        assert_eq!(ring.angle_pixel_index(630.0), Some(0));
+
+        let ring = ring.counterclockwise();
+        assert_eq!(ring.shape_pixel_index(Point::new(8, 4)), Some(9));
+        assert_eq!(ring.angle_pixel_index(240.0), Some(1));
+        assert_eq!(LedPixelRing::new(0, 9).angle_pixel_index(0.0), None);
+    }
+
+    #[test]
+    fn test_led_pixel_rings() {
+        const RINGS: &[usize] = &[8, 4, 1];
+        let rings = LedPixelRings::new(RINGS, 12);
+        assert_eq!(rings.rings(), RINGS);
+        assert_eq!(rings.shape_pixel_len(), 13);
+        assert_eq!(rings.shape_size(), Size::new(12, 12));
+        assert_eq!(rings.shape_pixel_index(Point::new(11, 6)), Some(0));
+        assert_eq!(rings.shape_pixel_index(Point::new(6, 11)), Some(2));
+        assert_eq!(rings.shape_pixel_index(Point::new(8, 6)), Some(8));
+        assert_eq!(rings.shape_pixel_index(Point::new(5, 8)), Some(9));
+        assert_eq!(rings.shape_pixel_index(Point::new(6, 6)), Some(12));
+        assert_eq!(rings.shape_pixel_index(Point::new(5, 5)), Some(12));
+        assert_eq!(rings.shape_pixel_index(Point::new(0, 0)), None);
+        assert_eq!(rings.angle_pixel_index(1, 180.0), Some(10));
+        assert_eq!(rings.angle_pixel_index(2, 45.0), Some(12));
+        assert_eq!(rings.angle_pixel_index(3, 0.0), None);
+        assert_eq!(
+            LedPixelRings::new(&[], 4).shape_pixel_index(Point::zero()),
+            None
+        );
+    }
+}
@@ -0,0 +1,67 @@
+//! Pseudo random generator shared by the effects, the mock and the test support.
+
+/// Seeded pseudo random generator (xorshift64*).
+///
+/// The same seed generates the same sequence on every platform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Mixed into the seed so that the small seeds do not start with the sparse states.
+    const SEED_MIX: u64 = 0x9E37_79B9_7F4A_7C15;
+
+    /// Creates a generator with the `seed`.
+    pub(crate) const fn new(seed: u64) -> Self {
+        let state = seed ^ Self::SEED_MIX;
+        // xorshift gets stuck at the zero state.
+        Self {
+            state: if state == 0 { Self::SEED_MIX } else { state },
+        }
+    }
+
+    /// Returns the next pseudo random value.
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns the next pseudo random value of 8 bits.
+    #[cfg_attr(not(feature = "effects"), allow(dead_code))]
+    pub(crate) fn next_u8(&mut self) -> u8 {
+        (self.next_u64() >> 56) as u8
+    }
+
+    /// Returns the next pseudo random value in `0..n`, or 0 if `n` is 0.
+    #[cfg_attr(not(feature = "effects"), allow(dead_code))]
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        ((u128::from(self.next_u64()) * n as u128) >> 64) as usize
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rng() {
+        // The seed cancelling the mix does not result in the zero state.
+        let mut rng = Rng::new(Rng::SEED_MIX);
+        assert_ne!(rng.next_u64(), rng.next_u64());
+        assert_eq!(Rng::new(42).next_u64(), Rng::new(42).next_u64());
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+
+        let mut rng = Rng::new(7);
+        assert!((0..1000).all(|_| rng.below(10) < 10));
+        assert_eq!(rng.below(0), 0);
+    }
+}
@@ -0,0 +1,514 @@
+//! Strip settings persisted to NVS.
+//!
+//! [`StripSettings`] holds the settings of a strip configured at runtime, e.g. from a web UI, and
+//! is saved to a [`SettingsStore`] as a small blob and restored at boot. On ESP-IDF, the store is
+//! the `esp-idf-svc` NVS namespace `EspNvs`. On the other targets, a
+//! [`HashMap`] works as the store.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::collections::HashMap;
+//! use ws2812_esp32_rmt_driver::settings::{Chipset, ColorOrder, StripSettings};
+//!
+//! // `EspNvs::new(partition, "ws2812", true)` on ESP-IDF
+//! let mut nvs = HashMap::new();
+//!
+//! let settings = StripSettings::load(&nvs, "strip0")
+//!     .unwrap()
+//!     .unwrap_or_else(|| StripSettings::new(60));
+//! assert_eq!(settings.color_order, ColorOrder::Grb);
+//!
+//! let settings = StripSettings {
+//!     chipset: Chipset::Sk6812,
+//!     color_order: ColorOrder::Grbw,
+//!     max_brightness: 128,
+//!     ..settings
+//! };
+//! settings.save(&mut nvs, "strip0").unwrap();
+//!
+//! let settings = StripSettings::load(&nvs, "strip0").unwrap().unwrap();
+//! assert_eq!(settings.chipset.timing().reset.as_micros(), 80);
+//! assert_eq!(settings.color_order.bpp(), 4);
+//! ```
+//!
+//! The color order and the chipset of a no-name strip are found by [`detect_color_order()`],
+//! which lights the test patterns and asks the user to confirm the colors.
+
+use crate::driver::color::{LedPixelColorGrb24, LedPixelColorGrbw32};
+use crate::driver::timing::LedTiming;
+use crate::driver::LedStripWrite;
+use crate::patterns::byte_probe;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt;
+
+pub use crate::driver::color::ColorOrder;
+
+#[cfg(target_vendor = "espressif")]
+use esp_idf_svc::nvs::{EspNvs, NvsPartitionId};
+#[cfg(target_vendor = "espressif")]
+use esp_idf_svc::sys::EspError;
+
+/// Format version of the blob
+const VERSION: u8 = 1;
+/// Byte count of the blob
+const BLOB_LEN: usize = 12;
+
+impl ColorOrder {
+    /// Returns the colors of the bytes of an LED pixel.
+    ///
+    /// The fourth is [`TestColor::White`] for the 3-byte orders, which have no white channel.
+    pub const fn channels(&self) -> [TestColor; 4] {
+        use TestColor::{Blue as B, Green as G, Red as R, White as W};
+        match self {
+            Self::Rgb | Self::Rgbw => [R, G, B, W],
+            Self::Rbg => [R, B, G, W],
+            Self::Grb | Self::Grbw => [G, R, B, W],
+            Self::Gbr => [G, B, R, W],
+            Self::Brg => [B, R, G, W],
+            Self::Bgr => [B, G, R, W],
+        }
+    }
+
+    /// Returns the color of the `byte`-th byte of an LED pixel.
+    /// Returns `None` if it is beyond the LED pixel.
+    pub const fn channel(&self, byte: usize) -> Option<TestColor> {
+        if byte < self.bpp() {
+            Some(self.channels()[byte])
+        } else {
+            None
+        }
+    }
+
+    /// Returns the color order of the value stored.
+    const fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => Self::Rgb,
+            1 => Self::Rbg,
+            2 => Self::Grb,
+            3 => Self::Gbr,
+            4 => Self::Brg,
+            5 => Self::Bgr,
+            6 => Self::Rgbw,
+            7 => Self::Grbw,
+            _ => return None,
+        })
+    }
+}
+
+/// LED pixel chipset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Chipset {
+    /// WS2812 and its compatibles
+    Ws2812 = 0,
+    /// SK6812
+    Sk6812 = 1,
+}
+
+impl Chipset {
+    /// Returns the signal timing of the chipset.
+    pub const fn timing(&self) -> LedTiming {
+        match self {
+            Self::Ws2812 => LedTiming::WS2812,
+            Self::Sk6812 => LedTiming::SK6812,
+        }
+    }
+
+    /// Returns the chipset of the value stored.
+    const fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => Self::Ws2812,
+            1 => Self::Sk6812,
+            _ => return None,
+        })
+    }
+}
+
+/// Color of a test pattern of [`detect_color_order()`]
+pub use crate::patterns::Channel as TestColor;
+
+/// Step of [`detect_color_order()`]: the test pattern lit and the color to be confirmed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DetectionStep {
+    /// Byte of the first LED pixel lit by the test pattern, the others are off
+    pub byte: usize,
+    /// Color the user is asked to confirm on the first LED pixel
+    pub expected: TestColor,
+}
+
+/// Result of [`detect_color_order()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Detection {
+    /// Channel order of the LED pixels
+    pub color_order: ColorOrder,
+    /// Chipset of the LED pixels, SK6812 for the RGBW strips
+    pub chipset: Chipset,
+}
+
+/// Detects the color order and the chipset of the strip with the user confirmations.
+///
+/// It lights a byte of the first LED pixel at `level` at a time, and calls `confirm` with the
+/// step asking whether the first LED pixel shows the expected color: pure red, then green, then
+/// blue for each of the first three bytes, and then white for the fourth byte. `confirm`
+/// returns the answer of the user, e.g. blocking until one of the yes/no buttons is pressed or
+/// a line is read from the serial. The LED pixels are turned off at the end.
+///
+/// Returns `None` if the answers are inconsistent, or the order is not one of [`ColorOrder`].
+///
+/// # Errors
+///
+/// Returns the error returned by `writer`.
+///
+/// # Examples
+///
+/// ```
+/// #[cfg(not(target_vendor = "espressif"))]
+/// use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+///
+/// use esp_idf_hal::peripherals::Peripherals;
+/// use ws2812_esp32_rmt_driver::settings::{detect_color_order, ColorOrder, StripSettings};
+/// use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+///
+/// let peripherals = Peripherals::take().unwrap();
+/// let mut driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+///
+/// // The user answers as seen on a GRB strip.
+/// let detection = detect_color_order(&mut driver, 64, |step| {
+///     println!("Is the first LED {:?}?", step.expected);
+///     step.byte < 3 && ColorOrder::Grb.channel(step.byte) == Some(step.expected)
+/// })
+/// .unwrap();
+/// assert_eq!(detection.unwrap().color_order, ColorOrder::Grb);
+///
+/// let settings = StripSettings {
+///     color_order: detection.unwrap().color_order,
+///     chipset: detection.unwrap().chipset,
+///     ..StripSettings::new(60)
+/// };
+/// ```
+pub fn detect_color_order<W, F>(
+    writer: &mut W,
+    level: u8,
+    mut confirm: F,
+) -> Result<Option<Detection>, W::Error>
+where
+    W: LedStripWrite,
+    F: FnMut(&DetectionStep) -> bool,
+{
+    const COLORS: [TestColor; 3] = [TestColor::Red, TestColor::Green, TestColor::Blue];
+
+    let mut ask = |writer: &mut W, byte: usize, expected: TestColor| {
+        if byte < 3 {
+            writer.write_blocking(byte_probe::<LedPixelColorGrb24>(byte, 1, level))?;
+        } else {
+            writer.write_blocking(byte_probe::<LedPixelColorGrbw32>(byte, 1, level))?;
+        }
+        Ok(confirm(&DetectionStep { byte, expected }))
+    };
+
+    let mut channels = [TestColor::White; 3];
+    for byte in 0..3 {
+        let mut found = None;
+        for color in COLORS {
+            if channels[..byte].contains(&color) {
+                continue;
+            }
+            if ask(writer, byte, color)? {
+                found = Some(color);
+                break;
+            }
+        }
+        match found {
+            Some(color) => channels[byte] = color,
+            None => {
+                writer.write_blocking([0; 4].into_iter())?;
+                return Ok(None);
+            }
+        }
+    }
+    let white = ask(writer, 3, TestColor::White)?;
+    writer.write_blocking([0; 8].into_iter())?;
+
+    let color_order = ColorOrder::ALL
+        .into_iter()
+        .find(|order| order.bpp() == 3 + white as usize && order.channels()[..3] == channels);
+    Ok(color_order.map(|color_order| Detection {
+        color_order,
+        chipset: if white {
+            Chipset::Sk6812
+        } else {
+            Chipset::Ws2812
+        },
+    }))
+}
+
+/// Settings of a strip
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StripSettings {
+    /// Number of the LED pixels
+    pub len: u32,
+    /// Channel order of the LED pixels
+    pub color_order: ColorOrder,
+    /// Chipset of the LED pixels
+    pub chipset: Chipset,
+    /// Maximum brightness, 255 for as is
+    pub max_brightness: u8,
+    /// Gamma of the color correction, 1.0 for none
+    pub gamma: f32,
+}
+
+impl StripSettings {
+    /// Creates the settings of `len` WS2812 LED pixels without brightness limit and color
+    /// correction.
+    pub const fn new(len: u32) -> Self {
+        Self {
+            len,
+            color_order: ColorOrder::Grb,
+            chipset: Chipset::Ws2812,
+            max_brightness: u8::MAX,
+            gamma: 1.0,
+        }
+    }
+
+    /// Returns the blob of the settings.
+    pub fn to_bytes(&self) -> [u8; BLOB_LEN] {
+        let mut bytes = [0; BLOB_LEN];
+        bytes[0] = VERSION;
+        bytes[1..5].copy_from_slice(&self.len.to_le_bytes());
+        bytes[5] = self.color_order as u8;
+        bytes[6] = self.chipset as u8;
+        bytes[7] = self.max_brightness;
+        bytes[8..12].copy_from_slice(&self.gamma.to_le_bytes());
+        bytes
+    }
+
+    /// Parses the blob of the settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the blob is not of the settings of this version.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SettingsError> {
+        let bytes: &[u8; BLOB_LEN] = bytes.try_into().map_err(|_| SettingsError::InvalidLength)?;
+        if bytes[0] != VERSION {
+            return Err(SettingsError::UnsupportedVersion(bytes[0]));
+        }
+        let gamma = f32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+        if !(gamma.is_finite() && gamma > 0.0) {
+            return Err(SettingsError::InvalidValue);
+        }
+        Ok(Self {
+            len: u32::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]),
+            color_order: ColorOrder::from_u8(bytes[5]).ok_or(SettingsError::InvalidValue)?,
+            chipset: Chipset::from_u8(bytes[6]).ok_or(SettingsError::InvalidValue)?,
+            max_brightness: bytes[7],
+            gamma,
+        })
+    }
+
+    /// Loads the settings of the key from the store.
+    ///
+    /// Returns `None` if the settings are not stored or the stored blob is invalid,
+    /// e.g. written by an incompatible version, so that the defaults are used instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by the store.
+    pub fn load<S: SettingsStore + ?Sized>(store: &S, key: &str) -> Result<Option<Self>, S::Error> {
+        let mut buffer = [0; BLOB_LEN + 1];
+        Ok(store
+            .load_blob(key, &mut buffer)?
+            .and_then(|len| Self::from_bytes(&buffer[..len]).ok()))
+    }
+
+    /// Saves the settings of the key to the store.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by the store.
+    pub fn save<S: SettingsStore + ?Sized>(
+        &self,
+        store: &mut S,
+        key: &str,
+    ) -> Result<(), S::Error> {
+        store.store_blob(key, &self.to_bytes())
+    }
+}
+
+/// Settings blob parse error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SettingsError {
+    /// The length of the blob is not of the settings.
+    InvalidLength,
+    /// The version of the blob is not supported.
+    UnsupportedVersion(u8),
+    /// A value is out of range.
+    InvalidValue,
+}
+
+impl Error for SettingsError {}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength => write!(f, "invalid settings length"),
+            Self::UnsupportedVersion(v) => write!(f, "settings version {} is not supported", v),
+            Self::InvalidValue => write!(f, "invalid settings value"),
+        }
+    }
+}
+
+/// Key-value store of the blobs
+pub trait SettingsStore {
+    /// Error type returned by the store
+    type Error;
+
+    /// Loads the blob of the key into `buffer`.
+    ///
+    /// Returns the byte count of the blob, or `None` if the key is not stored. A blob larger
+    /// than `buffer` is truncated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store could not be read.
+    fn load_blob(&self, key: &str, buffer: &mut [u8]) -> Result<Option<usize>, Self::Error>;
+
+    /// Stores the blob of the key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store could not be written.
+    fn store_blob(&mut self, key: &str, blob: &[u8]) -> Result<(), Self::Error>;
+}
+
+impl SettingsStore for HashMap<String, Vec<u8>> {
+    type Error = Infallible;
+
+    fn load_blob(&self, key: &str, buffer: &mut [u8]) -> Result<Option<usize>, Self::Error> {
+        Ok(self.get(key).map(|blob| {
+            let len = blob.len().min(buffer.len());
+            buffer[..len].copy_from_slice(&blob[..len]);
+            len
+        }))
+    }
+
+    fn store_blob(&mut self, key: &str, blob: &[u8]) -> Result<(), Self::Error> {
+        self.insert(key.to_owned(), blob.to_vec());
+        Ok(())
+    }
+}
+
+#[cfg(target_vendor = "espressif")]
+impl<T: NvsPartitionId> SettingsStore for EspNvs<T> {
+    type Error = EspError;
+
+    fn load_blob(&self, key: &str, buffer: &mut [u8]) -> Result<Option<usize>, Self::Error> {
+        // A blob larger than the buffer is an error of ESP-IDF, and treated as an invalid blob.
+        match self.get_blob(key, buffer) {
+            Ok(blob) => Ok(blob.map(|blob| blob.len())),
+            Err(e) if e.code() == esp_idf_svc::sys::ESP_ERR_NVS_INVALID_LENGTH as i32 => {
+                Ok(Some(buffer.len()))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn store_blob(&mut self, key: &str, blob: &[u8]) -> Result<(), Self::Error> {
+        self.set_blob(key, blob)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+    use crate::Ws2812Esp32RmtDriver;
+
+    #[test]
+    fn test_detect_color_order() {
+        let peripherals = Peripherals::take().unwrap();
+        let mut driver =
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+        driver.record_history();
+
+        for order in ColorOrder::ALL {
+            let detection = detect_color_order(&mut driver, 32, |step| {
+                order.channel(step.byte) == Some(step.expected)
+            })
+            .unwrap()
+            .unwrap();
+            assert_eq!(detection.color_order, order);
+            let white = order.bpp() == 4;
+            assert_eq!(detection.chipset == Chipset::Sk6812, white);
+        }
+        // RGB: a byte at a time, and then the white channel
+        assert_eq!(
+            driver.frame_history()[..5],
+            [
+                vec![32, 0, 0],
+                vec![0, 32, 0],
+                vec![0, 0, 32],
+                vec![0, 0, 0, 32],
+                vec![0; 8]
+            ]
+        );
+        assert_eq!(driver.pixel_data, Some(vec![0; 8]));
+
+        // Inconsistent answers
+        let detection = detect_color_order(&mut driver, 32, |_| false).unwrap();
+        assert_eq!(detection, None);
+        assert_eq!(driver.pixel_data, Some(vec![0; 4]));
+    }
+
+    #[test]
+    fn test_strip_settings_bytes() {
+        let settings = StripSettings {
+            len: 300,
+            color_order: ColorOrder::Rgbw,
+            chipset: Chipset::Sk6812,
+            max_brightness: 64,
+            gamma: 2.2,
+        };
+        let bytes = settings.to_bytes();
+        assert_eq!(bytes[..8], [1, 0x2C, 0x01, 0x00, 0x00, 6, 1, 64]);
+        assert_eq!(StripSettings::from_bytes(&bytes), Ok(settings));
+
+        assert_eq!(
+            StripSettings::from_bytes(&bytes[..11]),
+            Err(SettingsError::InvalidLength)
+        );
+        let mut bad = bytes;
+        bad[0] = 2;
+        assert_eq!(
+            StripSettings::from_bytes(&bad),
+            Err(SettingsError::UnsupportedVersion(2))
+        );
+        let mut bad = bytes;
+        bad[5] = 8;
+        assert_eq!(
+            StripSettings::from_bytes(&bad),
+            Err(SettingsError::InvalidValue)
+        );
+        let mut bad = bytes;
+        bad[8..].copy_from_slice(&f32::NAN.to_le_bytes());
+        assert_eq!(
+            StripSettings::from_bytes(&bad),
+            Err(SettingsError::InvalidValue)
+        );
+    }
+
+    #[test]
+    fn test_strip_settings_store() {
+        let mut store = HashMap::new();
+        assert_eq!(StripSettings::load(&store, "strip"), Ok(None));
+
+        let settings = StripSettings::new(60);
+        settings.save(&mut store, "strip").unwrap();
+        assert_eq!(StripSettings::load(&store, "strip"), Ok(Some(settings)));
+
+        store.insert("strip".to_owned(), vec![0xFF; 32]);
+        assert_eq!(StripSettings::load(&store, "strip"), Ok(None));
+    }
+}
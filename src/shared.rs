@@ -0,0 +1,273 @@
+//! Interrupt-safe sharing based on `critical-section` and `portable-atomic`.
+//!
+//! On no_std targets without `std::sync`, [`SharedDriver`] shares the LED driver between the
+//! main code and the interrupt handlers, and [`ChangedFlag`] lets an interrupt handler or
+//! another task mark a framebuffer (or a draw target) as changed, so that it is written again on
+//! the next `show()` (or `flush()`) by the task owning it. Both of them can be placed in a
+//! `static`.
+//!
+//! The access to [`SharedDriver`] is serialized by `critical_section::with()`; note that
+//! writing a frame through it keeps the interrupts masked during the transmission.
+//! [`ChangedFlag`] is a single atomic word of `portable-atomic`, which is lock-free on the
+//! chips with the atomic CAS; on the others, e.g. ESP32-C2/C3, it falls back to the critical
+//! sections by `critical-section` feature (or to the `portable_atomic_unsafe_assume_single_core`
+//! cfg of `portable-atomic`). [`SharedDriver`] requires `critical-section` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::driver::color::{LedPixelColor, LedPixelColorGrb24};
+//! use ws2812_esp32_rmt_driver::framebuffer::LedPixelFrameBuffer;
+//! use ws2812_esp32_rmt_driver::shared::{ChangedFlag, SharedDriver};
+//! use ws2812_esp32_rmt_driver::{LedStripWrite, Ws2812Esp32RmtDriver};
+//!
+//! static CHANGED: ChangedFlag = ChangedFlag::new();
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let driver = Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+//! let driver: &'static SharedDriver<_> = Box::leak(Box::new(SharedDriver::new(driver)));
+//!
+//! let mut frame = LedPixelFrameBuffer::<LedPixelColorGrb24, Vec<u8>, _>::from_driver(driver, 1);
+//! frame.set_changed_flag(&CHANGED);
+//! frame.set_pixel(0, LedPixelColorGrb24::new_with_rgb(30, 0, 0));
+//! frame.show().unwrap();
+//!
+//! // In an interrupt handler
+//! let mut isr_driver = driver;
+//! isr_driver.write_blocking([0, 0, 0].into_iter()).unwrap();
+//! CHANGED.set();
+//!
+//! // The framebuffer is written again to restore the LED pixels.
+//! frame.show().unwrap();
+//! assert_eq!(driver.lock(|driver| driver.pixel_data.clone()), Some(vec![0, 30, 0]));
+//! ```
+
+#[cfg(feature = "critical-section")]
+use crate::driver::{LedStripDriver, LedStripWrite};
+#[cfg(feature = "critical-section")]
+use core::cell::RefCell;
+use core::ops::Range;
+#[cfg(feature = "critical-section")]
+use critical_section::Mutex;
+use portable_atomic::{AtomicU32, Ordering};
+
+/// LED driver shared between the main code and the interrupt handlers.
+///
+/// `&SharedDriver<D>` is itself an LED driver, so the wrappers can be built on it by
+/// `from_driver()`.
+#[cfg(feature = "critical-section")]
+pub struct SharedDriver<D> {
+    driver: Mutex<RefCell<D>>,
+}
+
+#[cfg(feature = "critical-section")]
+impl<D> SharedDriver<D> {
+    /// Creates a shared driver of `driver`.
+    pub const fn new(driver: D) -> Self {
+        Self {
+            driver: Mutex::new(RefCell::new(driver)),
+        }
+    }
+
+    /// Calls `f` with the driver in a critical section.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the driver is locked again inside `f`.
+    pub fn lock<R>(&self, f: impl FnOnce(&mut D) -> R) -> R {
+        critical_section::with(|cs| f(&mut self.driver.borrow_ref_mut(cs)))
+    }
+
+    /// Returns the driver.
+    pub fn into_inner(self) -> D {
+        self.driver.into_inner().into_inner()
+    }
+}
+
+#[cfg(feature = "critical-section")]
+impl<D: LedStripWrite> LedStripWrite for &SharedDriver<D> {
+    type Error = D::Error;
+
+    #[inline]
+    fn write_blocking<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        self.lock(|driver| driver.write_blocking(pixel_sequence))
+    }
+}
+
+#[cfg(feature = "critical-section")]
+impl<D: LedStripDriver> LedStripDriver for &SharedDriver<D> {
+    #[inline]
+    fn write<T>(&mut self, pixel_sequence: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = u8> + Send,
+    {
+        self.lock(|driver| driver.write(pixel_sequence))
+    }
+
+    #[inline]
+    fn latch(&mut self) -> Result<(), Self::Error> {
+        self.lock(|driver| driver.latch())
+    }
+}
+
+/// Flag to mark a framebuffer as changed from the interrupt handlers or the other tasks.
+///
+/// Along with the flag, it keeps the range of the LED pixels changed since the flag was taken,
+/// so that the partial writes of the dirty range are possible. [`Self::set()`] marks all the LED
+/// pixels. The indices are tracked up to 65534; the ranges beyond it extend to the end.
+///
+/// See [`LedPixelFrameBuffer::set_changed_flag()`].
+///
+/// [`LedPixelFrameBuffer::set_changed_flag()`]: crate::framebuffer::LedPixelFrameBuffer::set_changed_flag
+#[derive(Debug)]
+pub struct ChangedFlag {
+    /// Dirty range packed as `start << 16 | end`, or 0 if cleared
+    range: AtomicU32,
+}
+
+/// Packed dirty range of all the LED pixels
+const ALL: u32 = END_OF_STRIP;
+/// Packed end of the dirty range extending to the end of the strip
+const END_OF_STRIP: u32 = u16::MAX as u32;
+
+impl ChangedFlag {
+    /// Creates a cleared flag.
+    pub const fn new() -> Self {
+        Self {
+            range: AtomicU32::new(0),
+        }
+    }
+
+    /// Sets the flag, marking all the LED pixels.
+    pub fn set(&self) {
+        self.range.store(ALL, Ordering::Release);
+    }
+
+    /// Sets the flag, marking the LED pixels of `range` in addition to the marked ones.
+    ///
+    /// An empty `range` is ignored.
+    pub fn set_range(&self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        let start = range.start.min(END_OF_STRIP as usize - 1) as u32;
+        let end = range.end.min(END_OF_STRIP as usize) as u32;
+        let _ = self
+            .range
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |packed| {
+                Some(match packed {
+                    0 => start << 16 | end,
+                    _ => (packed >> 16).min(start) << 16 | (packed & END_OF_STRIP).max(end),
+                })
+            });
+    }
+
+    /// Returns whether the flag is set.
+    pub fn is_set(&self) -> bool {
+        self.range.load(Ordering::Acquire) != 0
+    }
+
+    /// Clears the flag and returns whether it was set.
+    pub fn take(&self) -> bool {
+        self.take_range().is_some()
+    }
+
+    /// Clears the flag and returns the range of the marked LED pixels if it was set.
+    ///
+    /// The end is `usize::MAX` if the range extends to the end of the strip.
+    pub fn take_range(&self) -> Option<Range<usize>> {
+        match self.range.swap(0, Ordering::AcqRel) {
+            0 => None,
+            packed => {
+                let start = (packed >> 16) as usize;
+                let end = match packed & END_OF_STRIP {
+                    END_OF_STRIP => usize::MAX,
+                    end => end as usize,
+                };
+                Some(start..end)
+            }
+        }
+    }
+}
+
+impl Default for ChangedFlag {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(feature = "critical-section")]
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+    #[cfg(feature = "critical-section")]
+    use crate::Ws2812Esp32RmtDriver;
+
+    #[test]
+    fn test_changed_flag() {
+        let flag = ChangedFlag::new();
+        assert!(!flag.is_set());
+        flag.set();
+        assert!(flag.is_set());
+        assert!(flag.take());
+        assert!(!flag.take());
+    }
+
+    #[test]
+    fn test_changed_flag_range() {
+        let flag = ChangedFlag::new();
+        flag.set_range(4..6);
+        flag.set_range(1..2);
+        flag.set_range(3..3);
+        assert!(flag.is_set());
+        assert_eq!(flag.take_range(), Some(1..6));
+        assert_eq!(flag.take_range(), None);
+
+        flag.set_range(2..100_000);
+        assert_eq!(flag.take_range(), Some(2..usize::MAX));
+        flag.set_range(2..3);
+        flag.set();
+        assert_eq!(flag.take_range(), Some(0..usize::MAX));
+    }
+
+    #[test]
+    fn test_changed_flag_concurrent() {
+        let flag = ChangedFlag::new();
+        std::thread::scope(|scope| {
+            for i in 0..4 {
+                let flag = &flag;
+                scope.spawn(move || {
+                    for j in 0..100 {
+                        flag.set_range(i * 100 + j..i * 100 + j + 1);
+                    }
+                });
+            }
+        });
+        assert_eq!(flag.take_range(), Some(0..400));
+    }
+
+    #[cfg(feature = "critical-section")]
+    #[test]
+    fn test_shared_driver() {
+        let peripherals = Peripherals::take().unwrap();
+        let driver =
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap();
+        let shared = SharedDriver::new(driver);
+
+        let mut handle = &shared;
+        handle.write_blocking([1, 2, 3].into_iter()).unwrap();
+        assert_eq!(
+            shared.lock(|driver| driver.pixel_data.clone()),
+            Some(vec![1, 2, 3])
+        );
+        assert_eq!(shared.into_inner().pixel_data, Some(vec![1, 2, 3]));
+    }
+}
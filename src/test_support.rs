@@ -0,0 +1,248 @@
+//! Deterministic property-testing support.
+//!
+//! Provides a seeded pixel frame generator and invariant checkers of [`LedPixelColor`]
+//! implementations, so that downstream crates can fuzz their conversions consistently.
+//! With `arbitrary` feature, `arbitrary::Arbitrary` is implemented for
+//! [`LedPixelColorImpl`](crate::driver::color::LedPixelColorImpl).
+//! The checkers return the violated invariant instead of panicking,
+//! so they can be used from `proptest`, `quickcheck`, `cargo-fuzz` or plain tests.
+//!
+//! # Examples
+//!
+//! ```
+//! use ws2812_esp32_rmt_driver::driver::color::LedPixelColorGrbw32;
+//! use ws2812_esp32_rmt_driver::test_support::{
+//!     check_brightness_monotonic, check_layout_round_trip, PixelFrameGenerator,
+//! };
+//!
+//! let mut generator = PixelFrameGenerator::new(42);
+//! for color in generator.frame::<LedPixelColorGrbw32>(100) {
+//!     check_brightness_monotonic(&color).unwrap();
+//! }
+//! for _ in 0..100 {
+//!     let [r, g, b, w] = generator.next_rgbw();
+//!     check_layout_round_trip::<LedPixelColorGrbw32>(r, g, b, w).unwrap();
+//! }
+//! ```
+
+use crate::driver::color::LedPixelColor;
+#[cfg(feature = "arbitrary")]
+use crate::driver::color::LedPixelColorImpl;
+use crate::rng::Rng;
+use core::fmt;
+
+/// Seeded pseudo random pixel generator (xorshift64*).
+///
+/// The same seed generates the same sequence on every platform.
+#[derive(Debug, Clone)]
+pub struct PixelFrameGenerator {
+    rng: Rng,
+}
+
+impl PixelFrameGenerator {
+    /// Creates a generator with the `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Returns the next pseudo random value.
+    pub fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    /// Returns the next pseudo random RGBW channel values.
+    pub fn next_rgbw(&mut self) -> [u8; 4] {
+        let [r, g, b, w, ..] = self.next_u64().to_le_bytes();
+        [r, g, b, w]
+    }
+
+    /// Returns the next pseudo random color.
+    pub fn next_color<CDev: LedPixelColor>(&mut self) -> CDev {
+        let [r, g, b, w] = self.next_rgbw();
+        CDev::new_with_rgbw(r, g, b, w)
+    }
+
+    /// Returns an iterator of `len` pseudo random colors.
+    pub fn frame<CDev: LedPixelColor>(&mut self, len: usize) -> impl Iterator<Item = CDev> + '_ {
+        (0..len).map(move |_| self.next_color())
+    }
+}
+
+/// Violated invariant reported by the checkers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvariantViolation {
+    /// The byte length of the color differs from `BPP`.
+    ByteLength {
+        /// `BPP` of the color type
+        expected: usize,
+        /// the length of `as_ref()`
+        actual: usize,
+    },
+    /// The channel value read differs from the written one.
+    RoundTrip {
+        /// channel name: `'r'`, `'g'`, `'b'` or `'w'`
+        channel: char,
+        /// the written value
+        written: u8,
+        /// the read value
+        read: u8,
+    },
+    /// A channel value decreased while the brightness increased.
+    BrightnessNotMonotonic {
+        /// the brightness where the channel value decreased
+        brightness: u8,
+    },
+    /// The maximum brightness changed the color.
+    BrightnessMaxNotIdentity,
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ByteLength { expected, actual } => {
+                write!(f, "byte length is {} but BPP is {}", actual, expected)
+            }
+            Self::RoundTrip {
+                channel,
+                written,
+                read,
+            } => write!(
+                f,
+                "channel {} written as {} but read as {}",
+                channel, written, read
+            ),
+            Self::BrightnessNotMonotonic { brightness } => {
+                write!(f, "channel value decreased at brightness {}", brightness)
+            }
+            Self::BrightnessMaxNotIdentity => write!(f, "maximum brightness changed the color"),
+        }
+    }
+}
+
+impl core::error::Error for InvariantViolation {}
+
+/// Returns channel values of the color in the order of `r`, `g`, `b` and `w`.
+#[inline]
+fn channels<CDev: LedPixelColor>(color: &CDev) -> [u8; 4] {
+    [color.r(), color.g(), color.b(), color.w()]
+}
+
+/// Checks the layout round-trip: the byte length equals to `BPP`,
+/// and the channel values written by `new_with_rgbw()` are read back as they are,
+/// or as zero for the channel the color type does not have.
+///
+/// # Errors
+///
+/// Returns the violated invariant.
+pub fn check_layout_round_trip<CDev: LedPixelColor>(
+    r: u8,
+    g: u8,
+    b: u8,
+    w: u8,
+) -> Result<(), InvariantViolation> {
+    let color = CDev::new_with_rgbw(r, g, b, w);
+    if color.as_ref().len() != CDev::BPP {
+        return Err(InvariantViolation::ByteLength {
+            expected: CDev::BPP,
+            actual: color.as_ref().len(),
+        });
+    }
+
+    let present = channels(&CDev::new_with_rgbw(u8::MAX, u8::MAX, u8::MAX, u8::MAX));
+    let written = [r, g, b, w];
+    let read = channels(&color);
+    for (i, channel) in ['r', 'g', 'b', 'w'].into_iter().enumerate() {
+        let expected = if present[i] == u8::MAX { written[i] } else { 0 };
+        if read[i] != expected {
+            return Err(InvariantViolation::RoundTrip {
+                channel,
+                written: written[i],
+                read: read[i],
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Checks the brightness monotonicity: no channel value decreases as the brightness increases,
+/// and the maximum brightness does not change the color.
+///
+/// # Errors
+///
+/// Returns the violated invariant.
+pub fn check_brightness_monotonic<CDev: LedPixelColor>(
+    color: &CDev,
+) -> Result<(), InvariantViolation> {
+    let mut prev = channels(&color.brightness(0));
+    for brightness in 1..=u8::MAX {
+        let current = channels(&color.brightness(brightness));
+        if current.iter().zip(prev.iter()).any(|(c, p)| c < p) {
+            return Err(InvariantViolation::BrightnessNotMonotonic { brightness });
+        }
+        prev = current;
+    }
+    if prev != channels(color) {
+        return Err(InvariantViolation::BrightnessMaxNotIdentity);
+    }
+    Ok(())
+}
+
+/// Arbitrary color whose absent channels are zero.
+#[cfg(feature = "arbitrary")]
+impl<
+        'a,
+        const N: usize,
+        const R_ORDER: usize,
+        const G_ORDER: usize,
+        const B_ORDER: usize,
+        const W_ORDER: usize,
+    > arbitrary::Arbitrary<'a> for LedPixelColorImpl<N, R_ORDER, G_ORDER, B_ORDER, W_ORDER>
+{
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new_with_rgbw(
+            u.arbitrary()?,
+            u.arbitrary()?,
+            u.arbitrary()?,
+            u.arbitrary()?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::driver::color::{LedPixelColorGrb24, LedPixelColorGrbw32, LedPixelColorRgbw32};
+
+    #[test]
+    fn test_pixel_frame_generator() {
+        let mut generator1 = PixelFrameGenerator::new(0);
+        let mut generator2 = PixelFrameGenerator::new(0);
+        let frame1 = generator1
+            .frame::<LedPixelColorGrb24>(10)
+            .collect::<Vec<_>>();
+        let frame2 = generator2
+            .frame::<LedPixelColorGrb24>(10)
+            .collect::<Vec<_>>();
+        assert_eq!(frame1, frame2);
+        assert_ne!(frame1[0], frame1[1]);
+
+        let mut generator3 = PixelFrameGenerator::new(1);
+        assert_ne!(generator3.next_color::<LedPixelColorGrb24>(), frame1[0]);
+    }
+
+    #[test]
+    fn test_check_invariants() {
+        let mut generator = PixelFrameGenerator::new(0x5EED);
+        for _ in 0..64 {
+            let [r, g, b, w] = generator.next_rgbw();
+            check_layout_round_trip::<LedPixelColorGrb24>(r, g, b, w).unwrap();
+            check_layout_round_trip::<LedPixelColorRgbw32>(r, g, b, w).unwrap();
+            check_layout_round_trip::<LedPixelColorGrbw32>(r, g, b, w).unwrap();
+        }
+        for color in generator.frame::<LedPixelColorGrbw32>(64) {
+            check_brightness_monotonic(&color).unwrap();
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! Time sources of the time-dependent features.
+//!
+//! A [`TimeSource`] returns the monotonic time since an arbitrary epoch, so that the frame pacing
+//! ([`FramePacer`], `governor`), the transitions and the effects engine run on any of std
+//! ([`StdTimeSource`]), ESP-IDF ([`EspTimerTimeSource`], [`FreeRtosTimeSource`]), embassy
+//! ([`EmbassyTimeSource`]) and the mock (`VirtualClock`). Any `Fn() -> Duration` closure is a
+//! time source as well.
+//!
+//! # Examples
+//!
+//! ```
+//! use core::time::Duration;
+//! use ws2812_esp32_rmt_driver::time::{FramePacer, TimeSource};
+//!
+//! # #[cfg(not(target_vendor = "espressif"))]
+//! # {
+//! // `EspTimerTimeSource` on ESP-IDF
+//! let clock = ws2812_esp32_rmt_driver::mock::time::VirtualClock::new();
+//! let mut pacer = FramePacer::new(clock.clone(), 50);
+//!
+//! let mut frames = 0;
+//! while frames < 3 {
+//!     if pacer.poll() {
+//!         // render the frame of `pacer.frame()` and write it
+//!         frames += 1;
+//!     }
+//!     // e.g. `FreeRtos::delay_ms(pacer.remaining().as_millis() as u32)`
+//!     clock.advance(pacer.remaining());
+//! }
+//! assert_eq!(clock.now(), Duration::from_millis(60));
+//! # }
+//! ```
+
+use core::time::Duration;
+
+/// Monotonic time source
+pub trait TimeSource {
+    /// Returns the current time since an arbitrary epoch.
+    fn now(&self) -> Duration;
+
+    /// Returns the time elapsed since `start`, a time returned by [`Self::now()`].
+    #[inline]
+    fn elapsed_since(&self, start: Duration) -> Duration {
+        self.now().saturating_sub(start)
+    }
+}
+
+impl<F: Fn() -> Duration> TimeSource for F {
+    #[inline]
+    fn now(&self) -> Duration {
+        self()
+    }
+}
+
+/// Time source of [`std::time::Instant`], since the creation
+#[cfg(all(feature = "std", not(target_os = "none")))]
+#[derive(Debug, Clone, Copy)]
+pub struct StdTimeSource {
+    epoch: std::time::Instant,
+}
+
+#[cfg(all(feature = "std", not(target_os = "none")))]
+impl StdTimeSource {
+    /// Creates a time source starting at zero.
+    pub fn new() -> Self {
+        Self {
+            epoch: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(all(feature = "std", not(target_os = "none")))]
+impl Default for StdTimeSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(feature = "std", not(target_os = "none")))]
+impl TimeSource for StdTimeSource {
+    #[inline]
+    fn now(&self) -> Duration {
+        self.epoch.elapsed()
+    }
+}
+
+/// Time source of `esp_timer`, since the boot, in microseconds
+#[cfg(target_vendor = "espressif")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EspTimerTimeSource;
+
+#[cfg(target_vendor = "espressif")]
+impl TimeSource for EspTimerTimeSource {
+    #[inline]
+    fn now(&self) -> Duration {
+        let now_us = unsafe { esp_idf_sys::esp_timer_get_time() };
+        Duration::from_micros(now_us as u64)
+    }
+}
+
+/// Time source of the FreeRTOS ticks, since the scheduler started
+///
+/// The resolution is the tick period, typically 10 ms (`CONFIG_FREERTOS_HZ` of 100), and the
+/// time wraps around with the tick count.
+#[cfg(target_vendor = "espressif")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FreeRtosTimeSource;
+
+#[cfg(target_vendor = "espressif")]
+impl TimeSource for FreeRtosTimeSource {
+    #[inline]
+    fn now(&self) -> Duration {
+        let ticks = unsafe { esp_idf_sys::xTaskGetTickCount() };
+        Duration::from_micros(
+            u64::from(ticks) * 1_000_000 / u64::from(esp_idf_sys::configTICK_RATE_HZ),
+        )
+    }
+}
+
+/// Time source of `embassy_time::Instant`, since the time driver started
+#[cfg(feature = "embassy")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EmbassyTimeSource;
+
+#[cfg(feature = "embassy")]
+impl TimeSource for EmbassyTimeSource {
+    #[inline]
+    fn now(&self) -> Duration {
+        Duration::from_micros(embassy_time::Instant::now().as_micros())
+    }
+}
+
+/// Frame pacing at a frame rate on a [`TimeSource`]
+///
+/// The frames are scheduled at the fixed interval from the first [`Self::poll()`]. The late
+/// frames are skipped rather than written in a burst, so [`Self::frame()`] tracks the time, e.g.
+/// as the `tick` of the effects.
+#[derive(Debug, Clone)]
+pub struct FramePacer<T> {
+    source: T,
+    interval: Duration,
+    start: Option<Duration>,
+    frame: u32,
+    next: u32,
+}
+
+impl<T: TimeSource> FramePacer<T> {
+    /// Creates a pacer of `fps` frames per second on `source`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fps` is zero.
+    pub fn new(source: T, fps: u32) -> Self {
+        assert!(fps > 0, "fps shall not be zero");
+        Self::with_interval(source, Duration::from_secs(1) / fps)
+    }
+
+    /// Creates a pacer of the frame `interval` on `source`.
+    pub fn with_interval(source: T, interval: Duration) -> Self {
+        Self {
+            source,
+            interval,
+            start: None,
+            frame: 0,
+            next: 0,
+        }
+    }
+
+    /// Returns the time source.
+    #[inline]
+    pub fn source(&self) -> &T {
+        &self.source
+    }
+
+    /// Returns the interval between the frames.
+    #[inline]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns the index of the last frame due, counted from the first [`Self::poll()`].
+    #[inline]
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    /// Returns the time elapsed since the first [`Self::poll()`].
+    pub fn elapsed(&self) -> Duration {
+        self.start
+            .map_or(Duration::ZERO, |start| self.source.elapsed_since(start))
+    }
+
+    /// Returns `true` if a frame is due, and then schedules the next frame.
+    pub fn poll(&mut self) -> bool {
+        let start = *self.start.get_or_insert_with(|| self.source.now());
+        let elapsed = self.source.elapsed_since(start);
+        let due = match self.interval.as_nanos() {
+            0 => self.next,
+            interval => (elapsed.as_nanos() / interval).min(u32::MAX as u128) as u32,
+        };
+        if due < self.next {
+            return false;
+        }
+        self.frame = due;
+        self.next = due.saturating_add(1);
+        true
+    }
+
+    /// Returns the time until the next frame is due, zero if due already.
+    pub fn remaining(&self) -> Duration {
+        match self.start {
+            Some(start) => {
+                let next = start + self.interval * self.next;
+                next.saturating_sub(self.source.now())
+            }
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Restarts the pacing at the next [`Self::poll()`].
+    pub fn reset(&mut self) {
+        self.start = None;
+        self.frame = 0;
+        self.next = 0;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::time::VirtualClock;
+
+    #[test]
+    fn test_frame_pacer() {
+        let clock = VirtualClock::new();
+        clock.set(Duration::from_secs(1));
+        let mut pacer = FramePacer::new(clock.clone(), 100);
+        assert_eq!(pacer.interval(), Duration::from_millis(10));
+        assert_eq!(pacer.remaining(), Duration::ZERO);
+
+        assert!(pacer.poll());
+        assert_eq!(pacer.frame(), 0);
+        assert!(!pacer.poll());
+        assert_eq!(pacer.remaining(), Duration::from_millis(10));
+
+        clock.advance(Duration::from_millis(10));
+        assert!(pacer.poll());
+        assert_eq!(pacer.frame(), 1);
+
+        // Late: the frames 2 and 3 are skipped.
+        clock.advance(Duration::from_millis(25));
+        assert!(pacer.poll());
+        assert_eq!(pacer.frame(), 3);
+        assert_eq!(pacer.remaining(), Duration::from_millis(5));
+        assert_eq!(pacer.elapsed(), Duration::from_millis(35));
+
+        pacer.reset();
+        assert!(pacer.poll());
+        assert_eq!(pacer.frame(), 0);
+    }
+
+    #[test]
+    fn test_time_source_closure() {
+        let source = || Duration::from_millis(5);
+        assert_eq!(source.now(), Duration::from_millis(5));
+        assert_eq!(
+            source.elapsed_since(Duration::from_millis(2)),
+            Duration::from_millis(3)
+        );
+        assert_eq!(
+            source.elapsed_since(Duration::from_millis(9)),
+            Duration::ZERO
+        );
+
+        let std_source = StdTimeSource::new();
+        assert!(std_source.now() <= std_source.now());
+    }
+}
@@ -0,0 +1,659 @@
+//! Declarative topology of the LED panel installations.
+//!
+//! [`PanelTopology`] describes an installation, e.g. a video wall, as the tiles of the LED
+//! panels: the size, the position in the wall, the rotation, the wiring of each panel, and the
+//! output (the driver) it is chained to. The tiles of an output are chained in the order of the
+//! description. The topology is built by `const fn`s, so that it can be a `const`.
+//!
+//! [`PanelDrawTarget`] composes the draw target of the whole installation from the topology
+//! and the drivers of the outputs, and maps each point to the LED pixel of the right panel.
+//! When all the panels are daisy-chained to one output, [`LedPixelTiledMatrix`] composes them
+//! into an [`LedPixelShape`] of the plain `LedPixelDrawTarget` instead.
+//!
+//! # Examples
+//!
+//! ```
+//! #[cfg(not(target_vendor = "espressif"))]
+//! use ws2812_esp32_rmt_driver::mock::esp_idf_hal;
+//!
+//! use embedded_graphics::pixelcolor::Rgb888;
+//! use embedded_graphics::prelude::*;
+//! use esp_idf_hal::peripherals::Peripherals;
+//! use ws2812_esp32_rmt_driver::topology::{PanelTopology, Rotation, Tile, Ws2812PanelDrawTarget};
+//! use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriver;
+//!
+//! // 2x2 wall of 8x8 serpentine panels on 2 outputs; the lower panels are mounted upside down.
+//! const WALL: PanelTopology<4> = PanelTopology::new([
+//!     Tile::new(0, Point::new(0, 0), Size::new(8, 8)).serpentine(),
+//!     Tile::new(0, Point::new(8, 0), Size::new(8, 8)).serpentine(),
+//!     Tile::new(1, Point::new(0, 8), Size::new(8, 8)).serpentine().rotated(Rotation::Deg180),
+//!     Tile::new(1, Point::new(8, 8), Size::new(8, 8)).serpentine().rotated(Rotation::Deg180),
+//! ]);
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let drivers = [
+//!     Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap(),
+//!     Ws2812Esp32RmtDriver::new(peripherals.rmt.channel1, peripherals.pins.gpio1).unwrap(),
+//! ];
+//! let mut draw: Ws2812PanelDrawTarget<4, 2> =
+//!     Ws2812PanelDrawTarget::from_drivers(WALL, drivers).unwrap();
+//! assert_eq!(draw.size(), Size::new(16, 16));
+//!
+//! // The lower right corner of the lower left panel is the first LED pixel of the second output.
+//! Pixel(Point::new(7, 15), Rgb888::RED).draw(&mut draw).unwrap();
+//! draw.flush().unwrap();
+//! assert_eq!(draw.frame_buffer(1).as_bytes()[0..3], [0, 255, 0]);
+//! ```
+//!
+//! [`LedPixelShape`]: crate::lib_embedded_graphics::LedPixelShape
+
+use crate::driver::color::{LedPixelColor, LedPixelColorGrb24};
+use crate::driver::{DefaultLedDriver, LedStripDriver};
+use crate::framebuffer::{FrameBufferData, LedPixelFrameBuffer};
+use crate::lib_embedded_graphics::LedPixelShape;
+use core::error::Error;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::DerefMut;
+use embedded_graphics_core::draw_target::DrawTarget;
+use embedded_graphics_core::geometry::{OriginDimensions, Point, Size};
+use embedded_graphics_core::pixelcolor::{Rgb888, RgbColor};
+use embedded_graphics_core::Pixel;
+
+/// Clockwise rotation of a panel mounted in the installation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Rotation {
+    /// As is
+    #[default]
+    Deg0,
+    /// 90 degrees clockwise
+    Deg90,
+    /// 180 degrees
+    Deg180,
+    /// 270 degrees clockwise
+    Deg270,
+}
+
+/// LED panel in the installation.
+///
+/// The LED pixels of the panel are wired row by row from the top left corner of the panel as
+/// is, or in zigzag with [`serpentine()`].
+///
+/// [`serpentine()`]: #method.serpentine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tile {
+    /// Index of the output the panel is chained to
+    pub output: usize,
+    /// Position of the top left corner of the panel in the installation, after rotation
+    pub origin: Point,
+    /// Size of the panel before rotation
+    pub size: Size,
+    /// Rotation of the panel
+    pub rotation: Rotation,
+    /// Whether the odd rows are wired from right to left
+    pub serpentine: bool,
+}
+
+impl Tile {
+    /// Creates a tile of the panel of `size` at `origin` chained to the `output`.
+    pub const fn new(output: usize, origin: Point, size: Size) -> Self {
+        Self {
+            output,
+            origin,
+            size,
+            rotation: Rotation::Deg0,
+            serpentine: false,
+        }
+    }
+
+    /// Returns the tile rotated by `rotation`.
+    pub const fn rotated(self, rotation: Rotation) -> Self {
+        Self { rotation, ..self }
+    }
+
+    /// Returns the tile wired in zigzag.
+    pub const fn serpentine(self) -> Self {
+        Self {
+            serpentine: true,
+            ..self
+        }
+    }
+
+    /// Returns the number of the LED pixels of the panel.
+    pub const fn pixel_len(&self) -> usize {
+        (self.size.width * self.size.height) as usize
+    }
+
+    /// Returns the size of the area the panel covers in the installation.
+    pub const fn footprint(&self) -> Size {
+        match self.rotation {
+            Rotation::Deg0 | Rotation::Deg180 => self.size,
+            Rotation::Deg90 | Rotation::Deg270 => Size::new(self.size.height, self.size.width),
+        }
+    }
+
+    /// Converts `point` in the installation to the index of the LED pixel of the panel.
+    /// Returns `None` if it is out of the panel.
+    pub fn pixel_index(&self, point: Point) -> Option<usize> {
+        let footprint = self.footprint();
+        let local = point - self.origin;
+        if !((0..footprint.width as i32).contains(&local.x)
+            && (0..footprint.height as i32).contains(&local.y))
+        {
+            return None;
+        }
+        let (w, h) = (self.size.width as i32, self.size.height as i32);
+        let (x, y) = match self.rotation {
+            Rotation::Deg0 => (local.x, local.y),
+            Rotation::Deg90 => (local.y, h - 1 - local.x),
+            Rotation::Deg180 => (w - 1 - local.x, h - 1 - local.y),
+            Rotation::Deg270 => (w - 1 - local.y, local.x),
+        };
+        let x = if self.serpentine && y % 2 == 1 {
+            w - 1 - x
+        } else {
+            x
+        };
+        Some((x + y * w) as usize)
+    }
+
+    /// Returns whether the areas of the panels overlap.
+    fn overlaps(&self, other: &Self) -> bool {
+        let (a, b) = (self.footprint(), other.footprint());
+        self.origin.x < other.origin.x + b.width as i32
+            && other.origin.x < self.origin.x + a.width as i32
+            && self.origin.y < other.origin.y + b.height as i32
+            && other.origin.y < self.origin.y + a.height as i32
+    }
+}
+
+/// Error of an invalid topology
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TopologyError {
+    /// The tile is placed at a negative position.
+    NegativeOrigin {
+        /// Index of the tile
+        tile: usize,
+    },
+    /// The tiles overlap.
+    Overlap {
+        /// Index of the tile
+        tile: usize,
+        /// Index of the other tile
+        other: usize,
+    },
+    /// The tile is chained to an output without driver.
+    NoOutput {
+        /// Index of the tile
+        tile: usize,
+    },
+}
+
+impl Error for TopologyError {}
+
+impl fmt::Display for TopologyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NegativeOrigin { tile } => write!(f, "tile {tile} is at a negative position"),
+            Self::Overlap { tile, other } => write!(f, "tile {tile} overlaps tile {other}"),
+            Self::NoOutput { tile } => write!(f, "tile {tile} is chained to no output"),
+        }
+    }
+}
+
+/// Topology of `TILES` LED panels of the installation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PanelTopology<const TILES: usize> {
+    tiles: [Tile; TILES],
+}
+
+impl<const TILES: usize> PanelTopology<TILES> {
+    /// Creates a topology of the tiles.
+    ///
+    /// The tiles of an output are chained in the order of `tiles`.
+    pub const fn new(tiles: [Tile; TILES]) -> Self {
+        Self { tiles }
+    }
+
+    /// Returns the tiles.
+    #[inline]
+    pub fn tiles(&self) -> &[Tile; TILES] {
+        &self.tiles
+    }
+
+    /// Returns the size of the installation.
+    pub fn size(&self) -> Size {
+        tiles_size(&self.tiles)
+    }
+
+    /// Returns the number of the LED pixels chained to the `output`.
+    pub fn output_len(&self, output: usize) -> usize {
+        self.tiles
+            .iter()
+            .filter(|tile| tile.output == output)
+            .map(Tile::pixel_len)
+            .sum()
+    }
+
+    /// Converts `point` to the output and the index of the LED pixel chained to the output.
+    /// Returns `None` if no panel covers it.
+    pub fn pixel_index(&self, point: Point) -> Option<(usize, usize)> {
+        for (i, tile) in self.tiles.iter().enumerate() {
+            if let Some(index) = tile.pixel_index(point) {
+                let offset = self.tiles[..i]
+                    .iter()
+                    .filter(|t| t.output == tile.output)
+                    .map(Tile::pixel_len)
+                    .sum::<usize>();
+                return Some((tile.output, offset + index));
+            }
+        }
+        None
+    }
+
+    /// Validates the topology for `outputs` outputs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a tile is placed at a negative position, overlaps another, or is
+    /// chained to the output not less than `outputs`.
+    pub fn validate(&self, outputs: usize) -> Result<(), TopologyError> {
+        for (tile, t) in self.tiles.iter().enumerate() {
+            if t.origin.x < 0 || t.origin.y < 0 {
+                return Err(TopologyError::NegativeOrigin { tile });
+            }
+            if t.output >= outputs {
+                return Err(TopologyError::NoOutput { tile });
+            }
+            if let Some(other) = self.tiles[..tile].iter().position(|o| o.overlaps(t)) {
+                return Err(TopologyError::Overlap {
+                    tile: other,
+                    other: tile,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the size of the area the tiles cover.
+fn tiles_size(tiles: &[Tile]) -> Size {
+    tiles.iter().fold(Size::zero(), |size, tile| {
+        let footprint = tile.footprint();
+        Size::new(
+            size.width
+                .max((tile.origin.x + footprint.width as i32).max(0) as u32),
+            size.height
+                .max((tile.origin.y + footprint.height as i32).max(0) as u32),
+        )
+    })
+}
+
+/// Layout of the LED panels of [`LedPixelTiledMatrix`]
+pub trait TileLayout {
+    /// Tiles of the panels in the chaining order; the outputs of the tiles are ignored.
+    const TILES: &'static [Tile];
+}
+
+/// LED pixel shape of the LED panels daisy-chained to one output, e.g. a wall of 8x32 panels
+///
+/// The panels are placed, rotated and wired by the tiles of the layout `L`, and chained in
+/// the order of [`TileLayout::TILES`].
+///
+/// # Examples
+///
+/// ```
+/// use embedded_graphics::prelude::*;
+/// use ws2812_esp32_rmt_driver::lib_embedded_graphics::{LedPixelMatrix, LedPixelShape};
+/// use ws2812_esp32_rmt_driver::topology::{LedPixelTiledMatrix, Rotation, Tile, TileLayout};
+///
+/// // 64x8 wall of two 32x8 panels; the second panel is mounted upside down.
+/// struct Wall;
+/// impl TileLayout for Wall {
+///     const TILES: &'static [Tile] = &[
+///         LedPixelMatrix::<32, 8>::tile(Point::new(0, 0)).serpentine(),
+///         LedPixelMatrix::<32, 8>::tile(Point::new(32, 0))
+///             .serpentine()
+///             .rotated(Rotation::Deg180),
+///     ];
+/// }
+/// type WallShape = LedPixelTiledMatrix<Wall>;
+///
+/// assert_eq!(WallShape::size(), Size::new(64, 8));
+/// assert_eq!(WallShape::pixel_len(), 512);
+/// assert_eq!(WallShape::pixel_index(Point::new(63, 7)), Some(256));
+/// ```
+pub struct LedPixelTiledMatrix<L: TileLayout> {
+    _phantom: PhantomData<L>,
+}
+
+impl<L: TileLayout> Default for LedPixelTiledMatrix<L> {
+    fn default() -> Self {
+        Self {
+            _phantom: Default::default(),
+        }
+    }
+}
+
+impl<L: TileLayout> LedPixelShape for LedPixelTiledMatrix<L> {
+    fn pixel_len() -> usize {
+        L::TILES.iter().map(Tile::pixel_len).sum()
+    }
+
+    fn size() -> Size {
+        tiles_size(L::TILES)
+    }
+
+    fn pixel_index(point: Point) -> Option<usize> {
+        let mut offset = 0;
+        for tile in L::TILES {
+            if let Some(index) = tile.pixel_index(point) {
+                return Some(offset + index);
+            }
+            offset += tile.pixel_len();
+        }
+        None
+    }
+}
+
+/// Target for embedded-graphics drawing operations of an installation of the LED panels.
+///
+/// * `CDraw` - color type for embedded-graphics drawing operations
+/// * `CDev` - the LED pixel color type (device dependant). It shall be convertible from `CDraw`.
+/// * `TILES` - the number of the tiles of the topology
+/// * `OUTPUTS` - the number of the outputs
+/// * `Data` - (optional) data storage type of each output. It shall be `Vec`-like struct.
+/// * `D` - (optional) the LED driver backend
+///
+/// [`flush()`] operation shall be required to write changes from the framebuffers to the
+/// display.
+///
+/// [`flush()`]: #method.flush
+pub struct PanelDrawTarget<
+    'd,
+    CDraw,
+    CDev,
+    const TILES: usize,
+    const OUTPUTS: usize,
+    Data = FrameBufferData,
+    D = DefaultLedDriver<'d>,
+> where
+    CDraw: RgbColor,
+    CDev: LedPixelColor + From<CDraw>,
+    Data: DerefMut<Target = [u8]> + FromIterator<u8> + IntoIterator<Item = u8>,
+    D: LedStripDriver,
+{
+    topology: PanelTopology<TILES>,
+    size: Size,
+    frames: [LedPixelFrameBuffer<'d, CDev, Data, D>; OUTPUTS],
+    _phantom: core::marker::PhantomData<CDraw>,
+}
+
+impl<'d, CDraw, CDev, const TILES: usize, const OUTPUTS: usize, Data, D>
+    PanelDrawTarget<'d, CDraw, CDev, TILES, OUTPUTS, Data, D>
+where
+    CDraw: RgbColor,
+    CDev: LedPixelColor + From<CDraw>,
+    Data: DerefMut<Target = [u8]> + FromIterator<u8> + IntoIterator<Item = u8>,
+    D: LedStripDriver,
+{
+    /// Create a new draw target of `topology` written through `drivers` of the outputs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the topology is invalid.
+    pub fn from_drivers(
+        topology: PanelTopology<TILES>,
+        drivers: [D; OUTPUTS],
+    ) -> Result<Self, TopologyError> {
+        topology.validate(OUTPUTS)?;
+        let mut output = 0;
+        let frames = drivers.map(|driver| {
+            let frame = LedPixelFrameBuffer::from_driver(driver, topology.output_len(output));
+            output += 1;
+            frame
+        });
+        Ok(Self {
+            topology,
+            size: topology.size(),
+            frames,
+            _phantom: Default::default(),
+        })
+    }
+
+    /// Returns the topology.
+    #[inline]
+    pub fn topology(&self) -> &PanelTopology<TILES> {
+        &self.topology
+    }
+
+    /// Returns the framebuffer of the `output`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `output` is not less than `OUTPUTS`.
+    #[inline]
+    pub fn frame_buffer(&self, output: usize) -> &LedPixelFrameBuffer<'d, CDev, Data, D> {
+        &self.frames[output]
+    }
+
+    /// Returns the framebuffer of the `output` mutably.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `output` is not less than `OUTPUTS`.
+    #[inline]
+    pub fn frame_buffer_mut(
+        &mut self,
+        output: usize,
+    ) -> &mut LedPixelFrameBuffer<'d, CDev, Data, D> {
+        &mut self.frames[output]
+    }
+
+    /// Set maximum brightness of all the outputs.
+    /// Each channel values of the returned shall be scaled down to `(brightness + 1) / 256`.
+    pub fn set_brightness(&mut self, brightness: u8) {
+        for frame in &mut self.frames {
+            frame.set_brightness(brightness);
+        }
+    }
+
+    /// Clear with black.
+    /// Same operation as `clear(black_color)`.
+    pub fn clear_with_black(&mut self) -> Result<(), D::Error> {
+        for frame in &mut self.frames {
+            frame.clear_with_black();
+        }
+        Ok(())
+    }
+
+    /// Write changes from the framebuffers to the LED pixels of all the outputs
+    pub fn flush(&mut self) -> Result<(), D::Error> {
+        for frame in &mut self.frames {
+            frame.show()?;
+        }
+        Ok(())
+    }
+}
+
+impl<'d, CDraw, CDev, const TILES: usize, const OUTPUTS: usize, Data, D> OriginDimensions
+    for PanelDrawTarget<'d, CDraw, CDev, TILES, OUTPUTS, Data, D>
+where
+    CDraw: RgbColor,
+    CDev: LedPixelColor + From<CDraw>,
+    Data: DerefMut<Target = [u8]> + FromIterator<u8> + IntoIterator<Item = u8>,
+    D: LedStripDriver,
+{
+    #[inline]
+    fn size(&self) -> Size {
+        self.size
+    }
+}
+
+impl<'d, CDraw, CDev, const TILES: usize, const OUTPUTS: usize, Data, D> DrawTarget
+    for PanelDrawTarget<'d, CDraw, CDev, TILES, OUTPUTS, Data, D>
+where
+    CDraw: RgbColor,
+    CDev: LedPixelColor + From<CDraw>,
+    Data: DerefMut<Target = [u8]> + FromIterator<u8> + IntoIterator<Item = u8>,
+    D: LedStripDriver,
+{
+    type Color = CDraw;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if let Some((output, pixel_index)) = self.topology.pixel_index(point) {
+                self.frames[output].set_pixel(pixel_index, color);
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        for frame in &mut self.frames {
+            frame.fill(color);
+        }
+        Ok(())
+    }
+}
+
+/// 8-bit GRB (total 24-bit pixel) LED draw target of an installation of the LED panels
+pub type Ws2812PanelDrawTarget<
+    'd,
+    const TILES: usize,
+    const OUTPUTS: usize,
+    Data = FrameBufferData,
+> = PanelDrawTarget<'d, Rgb888, LedPixelColorGrb24, TILES, OUTPUTS, Data>;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::esp_idf_hal::peripherals::Peripherals;
+    use crate::Ws2812Esp32RmtDriver;
+
+    #[test]
+    fn test_tile_pixel_index() {
+        let size = Size::new(3, 2);
+        let tile = Tile::new(0, Point::new(1, 1), size);
+        assert_eq!(tile.pixel_index(Point::new(1, 1)), Some(0));
+        assert_eq!(tile.pixel_index(Point::new(3, 2)), Some(5));
+        assert_eq!(tile.pixel_index(Point::new(0, 1)), None);
+        assert_eq!(tile.pixel_index(Point::new(4, 1)), None);
+
+        let tile = Tile::new(0, Point::zero(), size).serpentine();
+        assert_eq!(tile.pixel_index(Point::new(0, 1)), Some(5));
+        assert_eq!(tile.pixel_index(Point::new(2, 1)), Some(3));
+
+        // Upper left corner of the footprint for each rotation
+        let indices = [
+            (Rotation::Deg0, 0),
+            (Rotation::Deg90, 3),
+            (Rotation::Deg180, 5),
+            (Rotation::Deg270, 2),
+        ];
+        for (rotation, index) in indices {
+            let tile = Tile::new(0, Point::zero(), size).rotated(rotation);
+            assert_eq!(tile.pixel_index(Point::zero()), Some(index), "{rotation:?}");
+        }
+        let tile = Tile::new(0, Point::zero(), size).rotated(Rotation::Deg90);
+        assert_eq!(tile.footprint(), Size::new(2, 3));
+        assert_eq!(tile.pixel_index(Point::new(1, 0)), Some(0));
+        assert_eq!(tile.pixel_index(Point::new(2, 0)), None);
+    }
+
+    #[test]
+    fn test_panel_topology() {
+        let topology = PanelTopology::new([
+            Tile::new(0, Point::new(0, 0), Size::new(2, 2)),
+            Tile::new(1, Point::new(2, 0), Size::new(2, 2)),
+            Tile::new(0, Point::new(0, 2), Size::new(4, 1)),
+        ]);
+        assert_eq!(topology.size(), Size::new(4, 3));
+        assert_eq!(topology.output_len(0), 8);
+        assert_eq!(topology.output_len(1), 4);
+        assert_eq!(topology.pixel_index(Point::new(1, 1)), Some((0, 3)));
+        assert_eq!(topology.pixel_index(Point::new(2, 0)), Some((1, 0)));
+        assert_eq!(topology.pixel_index(Point::new(3, 2)), Some((0, 7)));
+        assert_eq!(topology.pixel_index(Point::new(4, 0)), None);
+
+        assert_eq!(topology.validate(2), Ok(()));
+        assert_eq!(
+            topology.validate(1),
+            Err(TopologyError::NoOutput { tile: 1 })
+        );
+        let topology = PanelTopology::new([
+            Tile::new(0, Point::new(0, 0), Size::new(2, 2)),
+            Tile::new(0, Point::new(1, 1), Size::new(2, 2)),
+        ]);
+        assert_eq!(
+            topology.validate(1),
+            Err(TopologyError::Overlap { tile: 0, other: 1 })
+        );
+        let topology = PanelTopology::new([Tile::new(0, Point::new(-1, 0), Size::new(2, 2))]);
+        assert_eq!(
+            topology.validate(1),
+            Err(TopologyError::NegativeOrigin { tile: 0 })
+        );
+    }
+
+    #[test]
+    fn test_led_pixel_tiled_matrix() {
+        struct Layout;
+        impl TileLayout for Layout {
+            const TILES: &'static [Tile] = &[
+                Tile::new(0, Point::new(0, 0), Size::new(2, 2)),
+                Tile::new(1, Point::new(2, 0), Size::new(1, 2)).rotated(Rotation::Deg90),
+                Tile::new(0, Point::new(0, 2), Size::new(4, 1)).serpentine(),
+            ];
+        }
+        type Shape = LedPixelTiledMatrix<Layout>;
+        assert_eq!(Shape::size(), Size::new(4, 3));
+        assert_eq!(Shape::pixel_len(), 10);
+        assert_eq!(Shape::pixel_index(Point::new(1, 1)), Some(3));
+        assert_eq!(Shape::pixel_index(Point::new(2, 0)), Some(5));
+        assert_eq!(Shape::pixel_index(Point::new(3, 0)), Some(4));
+        assert_eq!(Shape::pixel_index(Point::new(3, 2)), Some(9));
+        assert_eq!(Shape::pixel_index(Point::new(2, 1)), None);
+        assert_eq!(Shape::pixel_index(Point::new(-1, 0)), None);
+    }
+
+    #[test]
+    fn test_panel_draw_target() {
+        let peripherals = Peripherals::take().unwrap();
+        let topology = PanelTopology::new([
+            Tile::new(0, Point::new(0, 0), Size::new(2, 1)),
+            Tile::new(1, Point::new(2, 0), Size::new(1, 2)).rotated(Rotation::Deg270),
+        ]);
+        let drivers = [
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel0, peripherals.pins.gpio0).unwrap(),
+            Ws2812Esp32RmtDriver::new(peripherals.rmt.channel1, peripherals.pins.gpio1).unwrap(),
+        ];
+        let mut draw: Ws2812PanelDrawTarget<2, 2> =
+            Ws2812PanelDrawTarget::from_drivers(topology, drivers).unwrap();
+        assert_eq!(draw.size(), Size::new(4, 1));
+
+        draw.draw_iter([
+            Pixel(Point::new(1, 0), Rgb888::new(1, 2, 3)),
+            Pixel(Point::new(2, 0), Rgb888::new(4, 5, 6)),
+            Pixel(Point::new(4, 0), Rgb888::new(7, 8, 9)),
+        ])
+        .unwrap();
+        draw.flush().unwrap();
+        assert_eq!(
+            draw.frame_buffer(0).driver().pixel_data,
+            Some(vec![0, 0, 0, 2, 1, 3])
+        );
+        assert_eq!(
+            draw.frame_buffer(1).driver().pixel_data,
+            Some(vec![5, 4, 6, 0, 0, 0])
+        );
+
+        draw.clear(Rgb888::new(1, 1, 1)).unwrap();
+        draw.flush().unwrap();
+        assert_eq!(draw.frame_buffer(1).driver().pixel_data, Some(vec![1; 6]));
+    }
+}